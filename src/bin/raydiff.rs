@@ -0,0 +1,270 @@
+//! renders two scene files the same way and reports how they differ, both in what they
+//! contain and in the images they give.
+
+use std::{
+    env,
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+use ray_tracer_challenge::{
+    scene::Scene,
+    world::{Camera, Canvas},
+};
+
+const USAGE: &str = "usage: raydiff [options] <a.yaml or .json> <b.yaml or .json>
+       raydiff --help";
+
+const HELP: &str = "renders both scenes and compares them, listing the objects that were added,
+removed or changed, and how far apart the images are. objects are matched up by
+their place in the scene. exits with 0 when no pixel differs by more than the
+threshold, 1 when one does, and 2 when the scenes couldn't be compared.
+
+options:
+  -o, --out <file>        saves an image of how much each pixel differs, along with
+                          both renders beside it, named with -a and -b
+      --width <pixels>    how wide both images are (default: the scenes' own)
+      --height <pixels>   how tall both images are (default: the scenes' own)
+      --threshold <n>     how far apart a pixel's colors may be before the scenes
+                          count as different (default 0)
+  -h, --help              shows this message";
+
+/// the options given on the command line.
+#[derive(Debug, PartialEq)]
+struct Args {
+    before: PathBuf,
+    after: PathBuf,
+    /// when set, where to save the image of the differences.
+    out: Option<PathBuf>,
+    /// when set, the size of both images, replacing the scenes' own.
+    width: Option<usize>,
+    height: Option<usize>,
+    /// the largest difference between two pixels that still counts as the same.
+    threshold: f64,
+}
+
+/// what the command line asks for.
+#[derive(Debug, PartialEq)]
+enum Command {
+    Compare(Args),
+    Help,
+}
+
+/// reads the arguments that follow the name of the program.
+fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<Command, String> {
+    let (mut out, mut width, mut height, mut threshold) = (None, None, None, 0.0);
+    let mut scenes = vec![];
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut value = |what: &str| args.next().ok_or_else(|| format!("{} needs {}", arg, what));
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-o" | "--out" => out = Some(value("a file")?.into()),
+            "--width" => width = Some(positive(&arg, &value("a number of pixels")?)?),
+            "--height" => height = Some(positive(&arg, &value("a number of pixels")?)?),
+            "--threshold" => {
+                let value = value("a number")?;
+                threshold = match value.parse::<f64>() {
+                    Ok(n) if n >= 0.0 => n,
+                    _ => {
+                        return Err(format!(
+                            "--threshold needs a number no less than 0, not {}",
+                            value
+                        ))
+                    }
+                };
+            }
+            _ if scenes.len() < 2 && !arg.starts_with('-') => scenes.push(PathBuf::from(arg)),
+            _ => return Err(format!("unexpected argument {}", arg)),
+        }
+    }
+
+    let mut scenes = scenes.into_iter();
+    match (scenes.next(), scenes.next()) {
+        (Some(before), Some(after)) => Ok(Command::Compare(Args {
+            before,
+            after,
+            out,
+            width,
+            height,
+            threshold,
+        })),
+        _ => Err("two scenes are needed to compare".to_string()),
+    }
+}
+
+/// reads the value of `flag` as a whole number above zero.
+fn positive(flag: &str, value: &str) -> Result<usize, String> {
+    match value.parse() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(format!(
+            "{} needs a whole number above 0, not {}",
+            flag, value
+        )),
+    }
+}
+
+/// reads the scene in `path`, telling its format by its extension.
+fn load_scene(path: &Path) -> Result<Scene, String> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase);
+    let failed = |error: &dyn Display| format!("could not load {}: {}", path.display(), error);
+    let text = fs::read_to_string(path).map_err(|error| failed(&error))?;
+    match extension.as_deref() {
+        Some("yaml") | Some("yml") => serde_yaml::from_str(&text).map_err(|error| failed(&error)),
+        Some("json") => serde_json::from_str(&text).map_err(|error| failed(&error)),
+        _ => Err(format!(
+            "don't know how to load {}, which isn't a .yaml or .json file",
+            path.display()
+        )),
+    }
+}
+
+/// the same camera, looking the same way, but making an image of the given size. like
+/// the main binary's --width and --height, this always gives a perspective camera.
+fn resized(camera: &Camera, width: Option<usize>, height: Option<usize>) -> Camera {
+    if width.is_none() && height.is_none() {
+        return *camera;
+    }
+    let mut resized = Camera::new(
+        width.unwrap_or(camera.image_width),
+        height.unwrap_or(camera.image_height),
+        camera.field_of_view,
+    );
+    resized.set_view(*camera.view());
+    resized
+}
+
+/// `out` with `suffix` added to the end of its name, before the extension.
+fn beside(out: &Path, suffix: &str) -> PathBuf {
+    let stem = out.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match out.extension() {
+        Some(extension) => format!("{}-{}.{}", stem, suffix, extension.to_string_lossy()),
+        None => format!("{}-{}", stem, suffix),
+    };
+    out.with_file_name(name)
+}
+
+/// compares the scenes, printing what differs. returns whether the images are further
+/// apart than the threshold.
+fn compare(args: &Args) -> Result<bool, String> {
+    let before = load_scene(&args.before)?;
+    let after = load_scene(&args.after)?;
+
+    for change in before.changes(&after) {
+        println!("{}", change);
+    }
+
+    let render =
+        |scene: &Scene| resized(&scene.camera, args.width, args.height).render(&scene.world);
+    let (a, b) = (render(&before), render(&after));
+    let diff = a.diff(&b).map_err(|error| {
+        format!(
+            "the scenes render at different sizes ({}); use --width and --height",
+            error
+        )
+    })?;
+
+    let differing = diff.pixels_over(args.threshold);
+    println!(
+        "{} of {} pixels differ by more than {}, by at most {:.5}",
+        differing,
+        a.width * a.height,
+        args.threshold,
+        diff.max_error
+    );
+
+    if let Some(out) = &args.out {
+        let save = |canvas: &Canvas, path: &Path| {
+            canvas
+                .save(path)
+                .map_err(|error| format!("could not save {}: {}", path.display(), error))
+        };
+        save(&a.difference(&b), out)?;
+        save(&a, &beside(out, "a"))?;
+        save(&b, &beside(out, "b"))?;
+    }
+
+    Ok(differing > 0)
+}
+
+fn main() {
+    let args = match parse_args(env::args().skip(1)) {
+        Ok(Command::Compare(args)) => args,
+        Ok(Command::Help) => {
+            println!("{}\n\n{}", USAGE, HELP);
+            return;
+        }
+        Err(error) => {
+            eprintln!("{}\n{}", error, USAGE);
+            process::exit(2);
+        }
+    };
+
+    match compare(&args) {
+        Ok(false) => {}
+        Ok(true) => process::exit(1),
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(2);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Command, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn reading_every_option() {
+        assert_eq!(
+            parse(&[
+                "a.yaml",
+                "--out",
+                "diff.ppm",
+                "--width",
+                "40",
+                "--height",
+                "30",
+                "--threshold",
+                "0.01",
+                "b.json"
+            ]),
+            Ok(Command::Compare(Args {
+                before: "a.yaml".into(),
+                after: "b.json".into(),
+                out: Some("diff.ppm".into()),
+                width: Some(40),
+                height: Some(30),
+                threshold: 0.01,
+            }))
+        );
+        assert_eq!(parse(&["a.yaml", "--help"]), Ok(Command::Help));
+    }
+
+    #[test]
+    fn invalid_arguments_are_explained() {
+        assert!(parse(&["a.yaml"]).is_err());
+        assert!(parse(&["a.yaml", "b.yaml", "c.yaml"]).is_err());
+        assert!(parse(&["a.yaml", "b.yaml", "--threshold", "-1"]).is_err());
+        assert!(parse(&["a.yaml", "b.yaml", "--width", "0"]).is_err());
+        assert!(parse(&["a.yaml", "b.yaml", "--out"]).is_err());
+    }
+
+    #[test]
+    fn the_renders_are_saved_beside_the_difference() {
+        assert_eq!(
+            beside(Path::new("out/diff.ppm"), "a"),
+            PathBuf::from("out/diff-a.ppm")
+        );
+        assert_eq!(beside(Path::new("diff"), "b"), PathBuf::from("diff-b"));
+    }
+}
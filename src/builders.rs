@@ -0,0 +1,139 @@
+use std::f64::consts;
+
+use crate::math::{geometry::Cylinder, Form, Geometry, Matrix, Transformable};
+
+/// a small sphere sitting at one corner of the hexagon, at `(0, 0, -1)`.
+pub fn hexagon_corner() -> Geometry {
+    Geometry::default().with_form(Form::Sphere).transformed(
+        *Matrix::identity()
+            .scale(0.25, 0.25, 0.25)
+            .translate(0.0, 0.0, -1.0),
+    )
+}
+
+/// a thin cylinder running from the corner at `(0, 0, -1)` to the next corner around
+/// the hexagon.
+pub fn hexagon_edge() -> Geometry {
+    Geometry::default()
+        .with_form(Form::Cylinder(Cylinder::new(0.0, 1.0, false)))
+        .transformed(
+            *Matrix::identity()
+                .scale(0.25, 1.0, 0.25)
+                .rotate_z(-consts::PI / 2.0)
+                .rotate_y(-consts::PI / 6.0)
+                .translate(0.0, 0.0, -1.0),
+        )
+}
+
+/// one corner of the hexagon along with the edge leading away from it.
+pub fn hexagon_side() -> Geometry {
+    Geometry::default().with_form(Form::Group(vec![hexagon_corner(), hexagon_edge()]))
+}
+
+/// a hexagon of radius 1 lying in the xz plane, built from six corner spheres joined
+/// by six edge cylinders.
+pub fn hexagon() -> Geometry {
+    ring_of(6, hexagon_side())
+}
+
+/// a group of `n` copies of `child`, each rotated around the y axis so that they are
+/// evenly spaced in a circle. the first copy is left exactly where `child` already was.
+pub fn ring_of(n: usize, child: Geometry) -> Geometry {
+    Geometry::default().with_form(Form::Group(
+        (0..n)
+            .map(|i| {
+                let angle = (i as f64) * 2.0 * consts::PI / (n as f64);
+                let transform = Matrix::rotation_y(angle) * child.transform;
+                child.clone().transformed(transform)
+            })
+            .collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        math::{Hittable, Point, Vector},
+        world::Ray,
+    };
+
+    #[test]
+    fn hexagon_has_six_sides() {
+        if let Form::Group(sides) = hexagon().form {
+            assert_eq!(sides.len(), 6);
+            for (i, side) in sides.iter().enumerate() {
+                assert_eq!(
+                    side.transform,
+                    Matrix::rotation_y((i as f64) * consts::PI / 3.0)
+                );
+                if let Form::Group(parts) = &side.form {
+                    assert_eq!(parts, &vec![hexagon_corner(), hexagon_edge()]);
+                } else {
+                    panic!();
+                }
+            }
+        } else {
+            panic!();
+        }
+    }
+
+    #[test]
+    fn ring_of_spaces_children_evenly() {
+        let child = Geometry::default()
+            .with_form(Form::Sphere)
+            .transformed(Matrix::translation(0.0, 0.0, 2.0));
+        if let Form::Group(children) = ring_of(4, child.clone()).form {
+            assert_eq!(children.len(), 4);
+            let centers: Vec<Point> = children
+                .iter()
+                .map(|c| c.transform * Point::zero())
+                .collect();
+            assert_eq!(
+                centers,
+                vec![
+                    Point::new(0.0, 0.0, 2.0),
+                    Point::new(2.0, 0.0, 0.0),
+                    Point::new(0.0, 0.0, -2.0),
+                    Point::new(-2.0, 0.0, 0.0),
+                ]
+            );
+            assert_eq!(children[0], child);
+        } else {
+            panic!();
+        }
+    }
+
+    #[test]
+    fn ray_from_above_hits_corner_sphere() {
+        let r = Ray::new(Point::new(0.0, 5.0, -1.0), Vector::new(0.0, -1.0, 0.0));
+        let hit = hexagon().hit(r).unwrap().closest().unwrap();
+        assert_eq!(hit.time, 4.75);
+        assert_eq!(hit.object.form, Form::Sphere);
+        assert_eq!(hit.object.transform, hexagon_corner().transform);
+    }
+
+    #[test]
+    fn ray_hits_rotated_corner_sphere() {
+        // the fourth corner sits opposite the first one, at (0, 0, 1).
+        let r = Ray::new(Point::new(0.0, 5.0, 1.0), Vector::new(0.0, -1.0, 0.0));
+        let hit = hexagon().hit(r).unwrap().closest().unwrap();
+        assert_eq!(hit.time, 4.75);
+        assert_eq!(
+            hit.object.transform,
+            Matrix::rotation_y(consts::PI) * hexagon_corner().transform
+        );
+    }
+
+    #[test]
+    fn transformed_hexagon_moves_its_corners() {
+        let hexagon = hexagon().transformed(Matrix::translation(0.0, 1.0, 0.0));
+        let r = Ray::new(Point::new(0.0, 5.0, -1.0), Vector::new(0.0, -1.0, 0.0));
+        let hit = hexagon.hit(r).unwrap().closest().unwrap();
+        assert_eq!(hit.time, 3.75);
+        assert_eq!(
+            hit.object.normal_at(r.at(hit.time)).unwrap(),
+            Vector::new(0.0, 1.0, 0.0)
+        );
+    }
+}
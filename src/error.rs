@@ -0,0 +1,159 @@
+//! the errors the ray tracer reports, rather than panicking or quietly producing
+//! nonsense.
+
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+use crate::{
+    math::{Matrix, Matrix4, ParseMathError, Point},
+    obj::ObjError,
+    stl::StlError,
+    world::PpmError,
+};
+
+/// something that was asked of the ray tracer but can't be done.
+#[derive(Debug)]
+pub enum RayTracerError {
+    /// the matrix has no inverse, since its determinant is (close to) zero.
+    NonInvertibleMatrix(Matrix),
+    /// a 4-by-4 matrix has a fourth row other than `{ 0, 0, 0, 1 }`, so it can't be
+    /// made into an affine `Matrix`.
+    NotAffine(Box<Matrix4>),
+    /// a camera was asked to look at the point it stands on, so it faces no direction.
+    DegenerateView { from: Point },
+    /// the pixel at (x, y) is outside a canvas of the given size.
+    InvalidCanvasIndex {
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    },
+    /// a heightfield was asked to march rays in steps that aren't positive and finite,
+    /// so it would never get anywhere.
+    InvalidStep(f64),
+    /// a grid of heights has fewer than 2 by 2 samples, or a number of `samples` other
+    /// than `columns * rows`.
+    InvalidGrid {
+        columns: usize,
+        rows: usize,
+        samples: usize,
+    },
+    /// a depth pass was asked to shade from `near` to `far`, which isn't a range that
+    /// runs forwards.
+    InvalidDepthRange { near: f64, far: f64 },
+    /// a slice of numbers didn't have the `expected` length.
+    WrongLength { expected: usize, found: usize },
+    /// a model or image couldn't be read.
+    ParseError(Box<dyn Error + Send + Sync>),
+}
+
+impl Display for RayTracerError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            RayTracerError::NonInvertibleMatrix(matrix) => {
+                write!(f, "the matrix {:?} has no inverse", matrix)
+            }
+            RayTracerError::NotAffine(matrix) => write!(
+                f,
+                "the matrix {:?} has a projective fourth row, so it isn't affine",
+                matrix
+            ),
+            RayTracerError::DegenerateView { from } => write!(
+                f,
+                "the camera at {:?} is looking at the point it stands on",
+                from
+            ),
+            RayTracerError::InvalidCanvasIndex {
+                x,
+                y,
+                width,
+                height,
+            } => write!(
+                f,
+                "pixel ({}, {}) is outside the {}x{} image",
+                x, y, width, height
+            ),
+            RayTracerError::InvalidStep(step) => write!(
+                f,
+                "a heightfield can't be marched in steps of {}; they must be positive",
+                step
+            ),
+            RayTracerError::InvalidGrid {
+                columns,
+                rows,
+                samples,
+            } => write!(
+                f,
+                "a {} by {} grid of heights can't be made from {} samples; it needs at \
+                 least 2 by 2, and a sample for each point",
+                columns, rows, samples
+            ),
+            RayTracerError::InvalidDepthRange { near, far } => write!(
+                f,
+                "depths can't be shaded from {} to {}; the near depth must be less than \
+                 the far one",
+                near, far
+            ),
+            RayTracerError::WrongLength { expected, found } => {
+                write!(f, "expected {} numbers, found {}", expected, found)
+            }
+            RayTracerError::ParseError(error) => error.fmt(f),
+        }
+    }
+}
+
+impl Error for RayTracerError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RayTracerError::ParseError(error) => Some(error.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<ParseMathError> for RayTracerError {
+    fn from(error: ParseMathError) -> RayTracerError {
+        RayTracerError::ParseError(Box::new(error))
+    }
+}
+
+impl From<ObjError> for RayTracerError {
+    fn from(error: ObjError) -> RayTracerError {
+        RayTracerError::ParseError(Box::new(error))
+    }
+}
+
+impl From<StlError> for RayTracerError {
+    fn from(error: StlError) -> RayTracerError {
+        RayTracerError::ParseError(Box::new(error))
+    }
+}
+
+impl From<PpmError> for RayTracerError {
+    fn from(error: PpmError) -> RayTracerError {
+        RayTracerError::ParseError(Box::new(error))
+    }
+}
+
+#[cfg(feature = "gltf")]
+impl From<crate::gltf::GltfError> for RayTracerError {
+    fn from(error: crate::gltf::GltfError) -> RayTracerError {
+        RayTracerError::ParseError(Box::new(error))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stl;
+
+    #[test]
+    fn models_that_cant_be_read_are_parse_errors() {
+        let error = RayTracerError::from(stl::parse(&b"solid cube\n  facet"[..]).unwrap_err());
+        assert!(matches!(error, RayTracerError::ParseError(_)));
+        assert!(error.source().unwrap().is::<StlError>());
+        assert_eq!(error.to_string(), error.source().unwrap().to_string());
+    }
+}
@@ -0,0 +1,893 @@
+//! importing static meshes from glTF 2.0 files, in either their JSON (`.gltf`) or
+//! binary (`.glb`) form.
+//!
+//! the nodes of the scene become nested groups with each node's transform, and
+//! triangle primitives become triangles, smooth where the primitive gives normals.
+//! materials only keep their base color. skins, animations, textures and cameras
+//! aren't supported, so they are skipped with a warning.
+
+mod json;
+pub use json::JsonError;
+
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::{self, Display, Formatter},
+    fs, io,
+    path::Path,
+    sync::Arc,
+};
+
+use crate::{
+    math::{
+        geometry::{SmoothTriangle, Triangle},
+        Form, Geometry, Matrix, Point, Transformable, Vector,
+    },
+    world::{Color, Material, Pattern, Texture},
+};
+use json::Json;
+
+/// the scene read from a glTF file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GltfModel {
+    /// a group holding the root nodes of the scene, each a group itself.
+    pub scene: Geometry,
+    /// the parts of the file that were skipped.
+    pub warnings: Vec<GltfWarning>,
+}
+
+/// something in a glTF file that was skipped.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GltfWarning {
+    /// the file uses a feature that can't be imported, such as animations.
+    Unsupported { feature: String },
+}
+
+impl Display for GltfWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            GltfWarning::Unsupported { feature } => {
+                write!(f, "{} are not supported, so they were skipped", feature)
+            }
+        }
+    }
+}
+
+/// why a glTF file couldn't be read.
+#[derive(Debug)]
+pub enum GltfError {
+    /// the file, or one of its buffers, couldn't be read.
+    Io(io::Error),
+    /// the document describing the file isn't valid JSON.
+    Json(JsonError),
+    /// a binary file isn't laid out the way a `.glb` should be.
+    Container(&'static str),
+    /// the document is missing something it needs, or has something it shouldn't.
+    Invalid(String),
+}
+
+impl Display for GltfError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            GltfError::Io(error) => write!(f, "could not read the model: {}", error),
+            GltfError::Json(error) => error.fmt(f),
+            GltfError::Container(problem) => write!(f, "invalid binary glTF: {}", problem),
+            GltfError::Invalid(problem) => write!(f, "invalid glTF: {}", problem),
+        }
+    }
+}
+
+impl Error for GltfError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            GltfError::Io(error) => Some(error),
+            GltfError::Json(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for GltfError {
+    fn from(error: io::Error) -> GltfError {
+        GltfError::Io(error)
+    }
+}
+
+impl From<JsonError> for GltfError {
+    fn from(error: JsonError) -> GltfError {
+        GltfError::Json(error)
+    }
+}
+
+/// reads the scene of a glTF file, telling the binary form from the JSON form by its
+/// first bytes. buffers must be inside the file, either as the binary chunk of a
+/// `.glb` or as `data:` URIs.
+pub fn parse_gltf(bytes: &[u8]) -> Result<GltfModel, GltfError> {
+    parse_gltf_with(bytes, |uri| {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("{} can't be opened while parsing on its own", uri),
+        ))
+    })
+}
+
+/// reads the scene of the glTF file at `path`, along with any buffers it keeps in
+/// other files, which are looked for relative to it.
+pub fn load_gltf<P: AsRef<Path>>(path: P) -> Result<GltfModel, GltfError> {
+    let path = path.as_ref();
+    let directory = path.parent().unwrap_or_else(|| Path::new(""));
+    parse_gltf_with(&fs::read(path)?, |uri| fs::read(directory.join(uri)))
+}
+
+/// like `parse_gltf`, but reads buffers kept in other files from what `open_buffer`
+/// gives for their URIs.
+pub fn parse_gltf_with<F>(bytes: &[u8], mut open_buffer: F) -> Result<GltfModel, GltfError>
+where
+    F: FnMut(&str) -> io::Result<Vec<u8>>,
+{
+    let (document, binary_chunk) = if bytes.starts_with(b"glTF") {
+        let (document, binary_chunk) = glb_chunks(bytes)?;
+        (document, Some(binary_chunk))
+    } else {
+        (bytes, None)
+    };
+    let document = std::str::from_utf8(document)
+        .map_err(|_| GltfError::Invalid("the document is not UTF-8".to_string()))?;
+    let document = json::parse(document)?;
+
+    let version = document
+        .get("asset")
+        .and_then(|asset| asset.get("version"))
+        .and_then(Json::as_str);
+    if !version.is_some_and(|version| version.starts_with("2.")) {
+        return Err(GltfError::Invalid(format!(
+            "only version 2 is supported, not {:?}",
+            version
+        )));
+    }
+    if let Some(required) = document.get("extensionsRequired").and_then(Json::as_array) {
+        if let Some(extension) = required.first() {
+            return Err(GltfError::Invalid(format!(
+                "the file needs the extension {:?}",
+                extension.as_str().unwrap_or_default()
+            )));
+        }
+    }
+
+    let mut buffers = vec![];
+    for (i, buffer) in array(&document, "buffers").iter().enumerate() {
+        let length = usize_member(buffer, "byteLength", &format!("buffers[{}]", i))?;
+        let data = match buffer.get("uri").and_then(Json::as_str) {
+            Some(uri) if uri.starts_with("data:") => decode_data_uri(uri)
+                .ok_or_else(|| GltfError::Invalid(format!("buffers[{}] has a bad data URI", i)))?,
+            Some(uri) => open_buffer(uri)?,
+            None if i == 0 => binary_chunk.map(<[u8]>::to_vec).ok_or_else(|| {
+                GltfError::Invalid("buffers[0] has no URI or binary chunk".to_string())
+            })?,
+            None => return Err(GltfError::Invalid(format!("buffers[{}] has no URI", i))),
+        };
+        if data.len() < length {
+            return Err(GltfError::Invalid(format!(
+                "buffers[{}] should be {} bytes long, but is only {}",
+                i,
+                length,
+                data.len()
+            )));
+        }
+        buffers.push(data);
+    }
+
+    let mut importer = Importer {
+        document: &document,
+        buffers,
+        materials: HashMap::new(),
+        default_material: Arc::new(Material::default()),
+        warnings: vec![],
+    };
+    for &feature in ["animations", "cameras", "skins", "textures"].iter() {
+        if !array(&document, feature).is_empty() {
+            importer.warn(feature);
+        }
+    }
+
+    let roots = importer.root_nodes()?;
+    let mut children = vec![];
+    for root in roots {
+        children.push(importer.node(root, &mut vec![])?);
+    }
+    Ok(GltfModel {
+        scene: Geometry::default().with_form(Form::Group(children)),
+        warnings: importer.warnings,
+    })
+}
+
+/// the JSON and binary chunks of a `.glb` file.
+fn glb_chunks(bytes: &[u8]) -> Result<(&[u8], &[u8]), GltfError> {
+    let word = |at: usize| {
+        bytes
+            .get(at..at + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+            .ok_or(GltfError::Container("the file ends too soon"))
+    };
+    if word(4)? != 2 {
+        return Err(GltfError::Container("only version 2 is supported"));
+    }
+    let length = word(8)?;
+    if bytes.len() < length {
+        return Err(GltfError::Container("the file ends too soon"));
+    }
+
+    let mut chunks = vec![];
+    let mut at = 12;
+    while at < length {
+        let chunk_length = word(at)?;
+        let kind = word(at + 4)?;
+        let data = at
+            .checked_add(8 + chunk_length)
+            .filter(|&end| end <= length)
+            .map(|end| &bytes[at + 8..end])
+            .ok_or(GltfError::Container(
+                "a chunk runs past the end of the file",
+            ))?;
+        chunks.push((kind, data));
+        at += 8 + chunk_length;
+    }
+
+    const JSON_CHUNK: usize = 0x4e4f_534a;
+    const BINARY_CHUNK: usize = 0x004e_4942;
+    match chunks.as_slice() {
+        [(JSON_CHUNK, document), rest @ ..] => {
+            let binary = rest
+                .iter()
+                .find(|(kind, _)| *kind == BINARY_CHUNK)
+                .map_or(&[][..], |(_, data)| data);
+            Ok((document, binary))
+        }
+        _ => Err(GltfError::Container("the first chunk must be JSON")),
+    }
+}
+
+/// the bytes of a base64 `data:` URI.
+fn decode_data_uri(uri: &str) -> Option<Vec<u8>> {
+    let (header, data) = uri.split_at(uri.find(',')?);
+    if !header.ends_with(";base64") {
+        return None;
+    }
+
+    let value = |c: u8| match c {
+        b'A'..=b'Z' => Some(c - b'A'),
+        b'a'..=b'z' => Some(c - b'a' + 26),
+        b'0'..=b'9' => Some(c - b'0' + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    };
+    let digits = data[1..].trim_end_matches('=').as_bytes();
+    let mut bytes = Vec::with_capacity(digits.len() * 3 / 4);
+    for group in digits.chunks(4) {
+        let mut bits = 0u32;
+        for (i, &digit) in group.iter().enumerate() {
+            bits |= (value(digit)? as u32) << (18 - 6 * i);
+        }
+        // each digit after the first carries another byte.
+        for i in 0..group.len() - 1 {
+            bytes.push((bits >> (16 - 8 * i)) as u8);
+        }
+    }
+    Some(bytes)
+}
+
+/// the items of the array member `key` of `json`, or none if it has no such member.
+fn array<'a>(json: &'a Json, key: &str) -> &'a [Json] {
+    json.get(key).and_then(Json::as_array).unwrap_or(&[])
+}
+
+/// the member `key` of `json` as an index or count. `path` says where `json` is, for
+/// the error if it's missing.
+fn usize_member(json: &Json, key: &str, path: &str) -> Result<usize, GltfError> {
+    json.get(key)
+        .and_then(Json::as_usize)
+        .ok_or_else(|| GltfError::Invalid(format!("{}.{} is missing or invalid", path, key)))
+}
+
+/// the numbers in the array member `key` of `json`, if there are exactly `N`.
+fn numbers<const N: usize>(
+    json: &Json,
+    key: &str,
+    path: &str,
+) -> Result<Option<[f64; N]>, GltfError> {
+    let items = match json.get(key) {
+        Some(items) => items,
+        None => return Ok(None),
+    };
+    let invalid = || GltfError::Invalid(format!("{}.{} should be {} numbers", path, key, N));
+    let items = items
+        .as_array()
+        .filter(|items| items.len() == N)
+        .ok_or_else(invalid)?;
+    let mut numbers = [0.0; N];
+    for (number, item) in numbers.iter_mut().zip(items) {
+        *number = item.as_f64().ok_or_else(invalid)?;
+    }
+    Ok(Some(numbers))
+}
+
+/// what is needed while turning a document into geometry.
+struct Importer<'a> {
+    document: &'a Json,
+    buffers: Vec<Vec<u8>>,
+    /// the material made for each of the document's materials, so that the triangles
+    /// that use one share it.
+    materials: HashMap<usize, Arc<Material>>,
+    default_material: Arc<Material>,
+    warnings: Vec<GltfWarning>,
+}
+
+impl<'a> Importer<'a> {
+    fn warn(&mut self, feature: &str) {
+        let warning = GltfWarning::Unsupported {
+            feature: feature.to_string(),
+        };
+        if !self.warnings.contains(&warning) {
+            self.warnings.push(warning);
+        }
+    }
+
+    /// the nodes at the top of the scene to show: the one the document picks, or its
+    /// first. a document without scenes shows every node that isn't a child.
+    fn root_nodes(&self) -> Result<Vec<usize>, GltfError> {
+        let scenes = array(self.document, "scenes");
+        if scenes.is_empty() {
+            let nodes = array(self.document, "nodes");
+            let children: Vec<usize> = nodes
+                .iter()
+                .flat_map(|node| array(node, "children"))
+                .filter_map(Json::as_usize)
+                .collect();
+            return Ok((0..nodes.len()).filter(|i| !children.contains(i)).collect());
+        }
+
+        let index = match self.document.get("scene") {
+            Some(scene) => scene
+                .as_usize()
+                .ok_or_else(|| GltfError::Invalid("scene is invalid".to_string()))?,
+            None => 0,
+        };
+        let scene = scenes
+            .get(index)
+            .ok_or_else(|| GltfError::Invalid(format!("there is no scene {}", index)))?;
+        array(scene, "nodes")
+            .iter()
+            .map(|node| {
+                node.as_usize().ok_or_else(|| {
+                    GltfError::Invalid(format!("scenes[{}].nodes is invalid", index))
+                })
+            })
+            .collect()
+    }
+
+    /// the node at `index` as a group of its mesh's triangles and its children.
+    /// `ancestors` are the nodes above it, so that a node can't contain itself.
+    fn node(&mut self, index: usize, ancestors: &mut Vec<usize>) -> Result<Geometry, GltfError> {
+        let path = format!("nodes[{}]", index);
+        let node = array(self.document, "nodes")
+            .get(index)
+            .ok_or_else(|| GltfError::Invalid(format!("there is no node {}", index)))?;
+        if ancestors.contains(&index) {
+            return Err(GltfError::Invalid(format!("{} contains itself", path)));
+        }
+
+        let transform = if let Some(m) = numbers::<16>(node, "matrix", &path)? {
+            // the matrix is stored column by column.
+            Matrix::with_columns(
+                Vector::new(m[0], m[1], m[2]),
+                Vector::new(m[4], m[5], m[6]),
+                Vector::new(m[8], m[9], m[10]),
+                Point::new(m[12], m[13], m[14]),
+            )
+        } else {
+            let [tx, ty, tz] = numbers(node, "translation", &path)?.unwrap_or([0.0; 3]);
+            let rotation = numbers(node, "rotation", &path)?.unwrap_or([0.0, 0.0, 0.0, 1.0]);
+            let [sx, sy, sz] = numbers(node, "scale", &path)?.unwrap_or([1.0; 3]);
+            Matrix::translation(tx, ty, tz)
+                * rotation_matrix(rotation)
+                * Matrix::scaling(sx, sy, sz)
+        };
+        if node.get("skin").is_some() {
+            self.warn("skins");
+        }
+
+        let mut children = vec![];
+        if let Some(mesh) = node.get("mesh") {
+            let mesh = mesh
+                .as_usize()
+                .ok_or_else(|| GltfError::Invalid(format!("{}.mesh is invalid", path)))?;
+            children.extend(self.mesh(mesh)?);
+        }
+        ancestors.push(index);
+        for child in array(node, "children") {
+            let child = child
+                .as_usize()
+                .ok_or_else(|| GltfError::Invalid(format!("{}.children is invalid", path)))?;
+            children.push(self.node(child, ancestors)?);
+        }
+        ancestors.pop();
+
+        Ok(Geometry::default()
+            .with_form(Form::Group(children))
+            .transformed(transform))
+    }
+
+    /// the triangles of every primitive of the mesh at `index`.
+    fn mesh(&mut self, index: usize) -> Result<Vec<Geometry>, GltfError> {
+        let mesh = array(self.document, "meshes")
+            .get(index)
+            .ok_or_else(|| GltfError::Invalid(format!("there is no mesh {}", index)))?;
+
+        let mut triangles = vec![];
+        for (p, primitive) in array(mesh, "primitives").iter().enumerate() {
+            let path = format!("meshes[{}].primitives[{}]", index, p);
+            const TRIANGLES: usize = 4;
+            if primitive
+                .get("mode")
+                .map_or(Some(TRIANGLES), Json::as_usize)
+                != Some(TRIANGLES)
+            {
+                self.warn("primitives other than triangle lists");
+                continue;
+            }
+            if primitive.get("targets").is_some() {
+                self.warn("morph targets");
+            }
+
+            let attributes = primitive
+                .get("attributes")
+                .ok_or_else(|| GltfError::Invalid(format!("{}.attributes is missing", path)))?;
+            let attribute = |name| usize_member(attributes, name, &format!("{}.attributes", path));
+            let positions = self.vectors(attribute("POSITION")?)?;
+            let normals = match attributes.get("NORMAL") {
+                Some(_) => Some(self.vectors(attribute("NORMAL")?)?),
+                None => None,
+            };
+            if normals.as_ref().is_some_and(|n| n.len() != positions.len()) {
+                return Err(GltfError::Invalid(format!(
+                    "{} has a different number of normals and positions",
+                    path
+                )));
+            }
+            let indices = match primitive.get("indices") {
+                Some(_) => {
+                    let accessor = usize_member(primitive, "indices", &path)?;
+                    let (values, components) = self.accessor(accessor)?;
+                    if components != 1 {
+                        return Err(GltfError::Invalid(format!(
+                            "accessors[{}] should hold single indices",
+                            accessor
+                        )));
+                    }
+                    values.into_iter().map(|i| i as usize).collect()
+                }
+                None => (0..positions.len()).collect::<Vec<usize>>(),
+            };
+            let material = match primitive.get("material") {
+                Some(_) => self.material(usize_member(primitive, "material", &path)?)?,
+                None => self.default_material.clone(),
+            };
+
+            for corners in indices.chunks_exact(3) {
+                if corners.iter().any(|&i| positions.len() <= i) {
+                    return Err(GltfError::Invalid(format!(
+                        "{} refers to a vertex it doesn't have",
+                        path
+                    )));
+                }
+                let (a, b, c) = (corners[0], corners[1], corners[2]);
+                let point = |i: usize| Point::from(positions[i]);
+                let form = match &normals {
+                    Some(normals) => Form::SmoothTriangle(SmoothTriangle::new(
+                        point(a),
+                        point(b),
+                        point(c),
+                        normals[a],
+                        normals[b],
+                        normals[c],
+                    )),
+                    None => Form::Triangle(Triangle::new(point(a), point(b), point(c))),
+                };
+                triangles.push(
+                    Geometry::default()
+                        .with_form(form)
+                        .with_material(material.clone()),
+                );
+            }
+        }
+        Ok(triangles)
+    }
+
+    /// the material at `index`, made from its base color.
+    fn material(&mut self, index: usize) -> Result<Arc<Material>, GltfError> {
+        if let Some(material) = self.materials.get(&index) {
+            return Ok(material.clone());
+        }
+
+        let path = format!("materials[{}]", index);
+        let json = array(self.document, "materials")
+            .get(index)
+            .ok_or_else(|| GltfError::Invalid(format!("there is no material {}", index)))?;
+        let mut material = Material::default();
+        if let Some(pbr) = json.get("pbrMetallicRoughness") {
+            let path = format!("{}.pbrMetallicRoughness", path);
+            if let Some([r, g, b, _]) = numbers(pbr, "baseColorFactor", &path)? {
+                material.texture = Texture::pattern(Pattern::solid(Color::new(r, g, b)));
+            }
+            if pbr.get("baseColorTexture").is_some() {
+                self.warn("textures");
+            }
+        }
+
+        let material = Arc::new(material);
+        self.materials.insert(index, material.clone());
+        Ok(material)
+    }
+
+    /// the three-component vectors held by the accessor at `index`.
+    fn vectors(&self, index: usize) -> Result<Vec<Vector>, GltfError> {
+        let (values, components) = self.accessor(index)?;
+        if components != 3 {
+            return Err(GltfError::Invalid(format!(
+                "accessors[{}] should hold vectors of three numbers",
+                index
+            )));
+        }
+        Ok(values
+            .chunks_exact(3)
+            .map(|v| Vector::new(v[0], v[1], v[2]))
+            .collect())
+    }
+
+    /// every number the accessor at `index` holds, along with how many of them make up
+    /// each of its elements.
+    fn accessor(&self, index: usize) -> Result<(Vec<f64>, usize), GltfError> {
+        let path = format!("accessors[{}]", index);
+        let accessor = array(self.document, "accessors")
+            .get(index)
+            .ok_or_else(|| GltfError::Invalid(format!("there is no accessor {}", index)))?;
+        if accessor.get("sparse").is_some() {
+            return Err(GltfError::Invalid(format!(
+                "{} is sparse, which isn't supported",
+                path
+            )));
+        }
+
+        let count = usize_member(accessor, "count", &path)?;
+        let components = match accessor.get("type").and_then(Json::as_str) {
+            Some("SCALAR") => 1,
+            Some("VEC2") => 2,
+            Some("VEC3") => 3,
+            Some("VEC4") => 4,
+            _ => {
+                return Err(GltfError::Invalid(format!(
+                    "{}.type is not supported",
+                    path
+                )))
+            }
+        };
+        let component_type = usize_member(accessor, "componentType", &path)?;
+        let size = match component_type {
+            5120 | 5121 => 1,
+            5122 | 5123 => 2,
+            5125 | 5126 => 4,
+            _ => {
+                return Err(GltfError::Invalid(format!(
+                    "{}.componentType is not supported",
+                    path
+                )))
+            }
+        };
+
+        // an accessor without a view holds only zeros.
+        let view_index = match accessor.get("bufferView") {
+            Some(_) => usize_member(accessor, "bufferView", &path)?,
+            None => return Ok((vec![0.0; count * components], components)),
+        };
+        let view_path = format!("bufferViews[{}]", view_index);
+        let view = array(self.document, "bufferViews")
+            .get(view_index)
+            .ok_or_else(|| GltfError::Invalid(format!("there is no buffer view {}", view_index)))?;
+        let buffer = usize_member(view, "buffer", &view_path)?;
+        let buffer = self
+            .buffers
+            .get(buffer)
+            .ok_or_else(|| GltfError::Invalid(format!("there is no buffer {}", buffer)))?;
+        let view_offset = view.get("byteOffset").and_then(Json::as_usize).unwrap_or(0);
+        let view_length = usize_member(view, "byteLength", &view_path)?;
+        let element = components * size;
+        let stride = view
+            .get("byteStride")
+            .and_then(Json::as_usize)
+            .unwrap_or(element);
+        let offset = accessor
+            .get("byteOffset")
+            .and_then(Json::as_usize)
+            .unwrap_or(0);
+
+        let fits = view_offset
+            .checked_add(view_length)
+            .is_some_and(|end| end <= buffer.len())
+            && (count == 0
+                || (count - 1)
+                    .checked_mul(stride)
+                    .and_then(|last| last.checked_add(offset + element))
+                    .is_some_and(|end| end <= view_length));
+        if !fits {
+            return Err(GltfError::Invalid(format!(
+                "{} runs past the end of its buffer",
+                path
+            )));
+        }
+
+        let data = &buffer[view_offset..view_offset + view_length];
+        let mut values = Vec::with_capacity(count * components);
+        for i in 0..count {
+            for c in 0..components {
+                let at = offset + i * stride + c * size;
+                let b = &data[at..at + size];
+                // every value is little-endian, whatever the machine reading it.
+                values.push(match component_type {
+                    5120 => b[0] as i8 as f64,
+                    5121 => b[0] as f64,
+                    5122 => i16::from_le_bytes([b[0], b[1]]) as f64,
+                    5123 => u16::from_le_bytes([b[0], b[1]]) as f64,
+                    5125 => u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                    _ => f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                });
+            }
+        }
+        Ok((values, components))
+    }
+}
+
+/// the rotation by the unit quaternion `[x, y, z, w]`.
+fn rotation_matrix([x, y, z, w]: [f64; 4]) -> Matrix {
+    #[rustfmt::skip]
+    Matrix::new(
+        1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y - z * w),       2.0 * (x * z + y * w),       0.0,
+        2.0 * (x * y + z * w),       1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z - x * w),       0.0,
+        2.0 * (x * z - y * w),       2.0 * (y * z + x * w),       1.0 - 2.0 * (x * x + y * y), 0.0,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        math::Hittable,
+        world::{Ray, Textured},
+    };
+    use std::f64::consts;
+
+    const TWO_TRIANGLES: &str = include_str!("../tests/models/two_triangles.gltf");
+
+    /// the triangles in `group`, with their corners in world space.
+    fn world_triangles(group: &Geometry, parent: Matrix) -> Vec<(Geometry, [Point; 3])> {
+        let transform = parent * group.transform;
+        match &group.form {
+            Form::Group(children) => children
+                .iter()
+                .flat_map(|child| world_triangles(child, transform))
+                .collect(),
+            Form::Triangle(t) => vec![(
+                group.clone(),
+                [transform * t.p1, transform * t.p2, transform * t.p3],
+            )],
+            Form::SmoothTriangle(t) => vec![(
+                group.clone(),
+                [
+                    transform * t.triangle.p1,
+                    transform * t.triangle.p2,
+                    transform * t.triangle.p3,
+                ],
+            )],
+            form => panic!("unexpected {:?}", form),
+        }
+    }
+
+    /// `document` laid out as a `.glb` file, with `binary` as its binary chunk.
+    fn glb(document: &str, binary: &[u8]) -> Vec<u8> {
+        let pad = |data: &[u8], with: u8| {
+            let mut data = data.to_vec();
+            while !data.len().is_multiple_of(4) {
+                data.push(with);
+            }
+            data
+        };
+        let (document, binary) = (pad(document.as_bytes(), b' '), pad(binary, 0));
+        let mut bytes = b"glTF".to_vec();
+        bytes.extend_from_slice(&2u32.to_le_bytes());
+        let length = 12 + 8 + document.len() + 8 + binary.len();
+        bytes.extend_from_slice(&(length as u32).to_le_bytes());
+        for (kind, data) in [(b"JSON", document), (b"BIN\0", binary)].iter() {
+            bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(&kind[..]);
+            bytes.extend_from_slice(data);
+        }
+        bytes
+    }
+
+    #[test]
+    fn nodes_are_placed_by_their_transforms() {
+        let model = parse_gltf(TWO_TRIANGLES.as_bytes()).unwrap();
+        assert!(model.warnings.is_empty());
+
+        let triangles = world_triangles(&model.scene, Matrix::identity());
+        assert_eq!(triangles.len(), 2);
+        // the first node is moved along x.
+        assert_eq!(
+            triangles[0].1,
+            [
+                Point::new(2.0, 0.0, 0.0),
+                Point::new(3.0, 0.0, 0.0),
+                Point::new(2.0, 1.0, 0.0)
+            ]
+        );
+        // its child is moved along z, and turned a quarter around z, inside of it.
+        assert_eq!(
+            triangles[1].1,
+            [
+                Point::new(2.0, 0.0, 3.0),
+                Point::new(2.0, 1.0, 3.0),
+                Point::new(1.0, 0.0, 3.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn imported_triangles_are_hit_where_they_are_placed() {
+        let model = parse_gltf(TWO_TRIANGLES.as_bytes()).unwrap();
+        let direction = Vector::new(0.0, 0.0, 1.0);
+        let first = model
+            .scene
+            .hit(Ray::new(Point::new(2.2, 0.2, -5.0), direction))
+            .unwrap();
+        assert_eq!(first.closest().unwrap().time, 5.0);
+
+        let second = model
+            .scene
+            .hit(Ray::new(Point::new(1.8, 0.2, -5.0), direction))
+            .unwrap()
+            .closest()
+            .unwrap();
+        assert_eq!(second.time, 8.0);
+        assert_eq!(
+            second.object.color_at(Point::new(1.8, 0.2, 3.0)),
+            Color::new(1.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn normals_make_smooth_triangles() {
+        let model = parse_gltf(TWO_TRIANGLES.as_bytes()).unwrap();
+        let triangles = world_triangles(&model.scene, Matrix::identity());
+        match &triangles[0].0.form {
+            Form::SmoothTriangle(t) => assert_eq!(t.n1, Vector::new(0.0, 0.0, -1.0)),
+            form => panic!("expected a smooth triangle, got {:?}", form),
+        }
+        assert!(matches!(triangles[1].0.form, Form::Triangle(_)));
+    }
+
+    #[test]
+    fn reading_binary_files() {
+        // the same document, with its buffer moved into the binary chunk.
+        let start = TWO_TRIANGLES.find("\"uri\"").unwrap();
+        let end = start + TWO_TRIANGLES[start..].find('\n').unwrap();
+        let data_uri = TWO_TRIANGLES[start..end].split('"').nth(3).unwrap();
+        let binary = decode_data_uri(data_uri).unwrap();
+        let document = TWO_TRIANGLES.replace(&TWO_TRIANGLES[start - 1..end], "");
+        let document = document.replace("\"byteLength\": 78,\n", "\"byteLength\": 78\n");
+
+        let from_glb = parse_gltf(&glb(&document, &binary)).unwrap();
+        let from_gltf = parse_gltf(TWO_TRIANGLES.as_bytes()).unwrap();
+        assert_eq!(from_glb.scene, from_gltf.scene);
+    }
+
+    #[test]
+    fn matrices_and_external_buffers() {
+        let document = r#"{
+            "asset": {"version": "2.0"},
+            "nodes": [{"mesh": 0, "matrix": [0,1,0,0, -1,0,0,0, 0,0,1,0, 5,0,0,1]}],
+            "meshes": [{"primitives": [{"attributes": {"POSITION": 0}}]}],
+            "accessors": [{"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3"}],
+            "bufferViews": [{"buffer": 0, "byteLength": 36, "byteStride": 12}],
+            "buffers": [{"uri": "triangle.bin", "byteLength": 36}]
+        }"#;
+        let mut opened = vec![];
+        let model = parse_gltf_with(document.as_bytes(), |uri| {
+            opened.push(uri.to_string());
+            let mut bytes = vec![];
+            for &n in [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0].iter() {
+                bytes.extend_from_slice(&n.to_le_bytes());
+            }
+            Ok(bytes)
+        })
+        .unwrap();
+        assert_eq!(opened, vec!["triangle.bin"]);
+
+        // without scenes, every node that isn't a child is shown.
+        let triangles = world_triangles(&model.scene, Matrix::identity());
+        assert_eq!(
+            triangles[0].1,
+            [
+                Point::new(5.0, 0.0, 0.0),
+                Point::new(5.0, 1.0, 0.0),
+                Point::new(4.0, 0.0, 0.0)
+            ]
+        );
+        assert!(parse_gltf(document.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn unsupported_features_are_skipped_with_warnings() {
+        let document = TWO_TRIANGLES.replacen(
+            "\"scene\": 0,",
+            "\"scene\": 0, \"animations\": [{\"channels\": [], \"samplers\": []}], \
+             \"skins\": [{\"joints\": [1]}],",
+            1,
+        );
+        let model = parse_gltf(document.as_bytes()).unwrap();
+        assert_eq!(
+            model.warnings,
+            vec![
+                GltfWarning::Unsupported {
+                    feature: "animations".to_string()
+                },
+                GltfWarning::Unsupported {
+                    feature: "skins".to_string()
+                },
+            ]
+        );
+        assert_eq!(
+            model.warnings[0].to_string(),
+            "animations are not supported, so they were skipped"
+        );
+        assert_eq!(world_triangles(&model.scene, Matrix::identity()).len(), 2);
+    }
+
+    #[test]
+    fn rotations_follow_the_right_hand_rule() {
+        let half = consts::FRAC_PI_4.sin();
+        assert_eq!(
+            rotation_matrix([half, 0.0, 0.0, half]),
+            Matrix::rotation_x(consts::FRAC_PI_2)
+        );
+        assert_eq!(
+            rotation_matrix([0.0, half, 0.0, half]),
+            Matrix::rotation_y(consts::FRAC_PI_2)
+        );
+    }
+
+    #[test]
+    fn broken_files_are_errors() {
+        let truncated = TWO_TRIANGLES.replace("\"byteLength\": 78", "\"byteLength\": 100");
+        assert!(matches!(
+            parse_gltf(truncated.as_bytes()),
+            Err(GltfError::Invalid(_))
+        ));
+        let cycle = TWO_TRIANGLES.replace("\"mesh\": 1,", "\"mesh\": 1, \"children\": [0],");
+        assert_eq!(
+            parse_gltf(cycle.as_bytes()).unwrap_err().to_string(),
+            "invalid glTF: nodes[0] contains itself"
+        );
+        assert!(matches!(
+            parse_gltf(b"{\"asset\": {\"version\": \"1.0\"}}"),
+            Err(GltfError::Invalid(_))
+        ));
+        assert!(matches!(
+            parse_gltf(b"{\"asset\": "),
+            Err(GltfError::Json(_))
+        ));
+        assert!(matches!(
+            parse_gltf(b"glTF\x02\0\0\0\xff\0\0\0"),
+            Err(GltfError::Container(_))
+        ));
+    }
+}
@@ -0,0 +1,394 @@
+//! a small JSON parser, enough to read the documents that describe glTF files.
+
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+/// a JSON value. objects keep their members in the order they were written.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// the member of an object called `key`, if this is an object that has one.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => members
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Json::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// the value as an index or count, if it is a whole number that isn't negative.
+    pub fn as_usize(&self) -> Option<usize> {
+        match self {
+            Json::Number(n) if 0.0 <= *n && n.fract() == 0.0 && *n <= usize::MAX as f64 => {
+                Some(*n as usize)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+/// why some text isn't valid JSON.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JsonError {
+    /// how many bytes into the text the problem was found.
+    pub position: usize,
+    pub message: &'static str,
+}
+
+impl Display for JsonError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid JSON at byte {}: {}",
+            self.position, self.message
+        )
+    }
+}
+
+impl Error for JsonError {}
+
+/// parses `text`, which must hold exactly one JSON value.
+pub fn parse(text: &str) -> Result<Json, JsonError> {
+    let mut parser = Parser {
+        bytes: text.as_bytes(),
+        position: 0,
+    };
+    let value = parser.value(0)?;
+    parser.skip_whitespace();
+    if parser.position < parser.bytes.len() {
+        return Err(parser.error("unexpected text after the value"));
+    }
+    Ok(value)
+}
+
+/// how deeply arrays and objects may be nested, so that hostile documents can't
+/// overflow the stack.
+const MAX_DEPTH: usize = 128;
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, message: &'static str) -> JsonError {
+        JsonError {
+            position: self.position,
+            message,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.position).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ') | Some(b'\t') | Some(b'\n') | Some(b'\r') = self.peek() {
+            self.position += 1;
+        }
+    }
+
+    /// takes `byte` if it comes next, after any whitespace.
+    fn eat(&mut self, byte: u8) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(byte) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Json, JsonError> {
+        if MAX_DEPTH < depth {
+            return Err(self.error("values are nested too deeply"));
+        }
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'{') => self.object(depth),
+            Some(b'[') => self.array(depth),
+            Some(b'"') => self.string().map(Json::String),
+            Some(b't') => self.keyword("true", Json::Bool(true)),
+            Some(b'f') => self.keyword("false", Json::Bool(false)),
+            Some(b'n') => self.keyword("null", Json::Null),
+            Some(b'-') | Some(b'0'..=b'9') => self.number(),
+            Some(_) => Err(self.error("expected a value")),
+            None => Err(self.error("expected a value, found the end")),
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str, value: Json) -> Result<Json, JsonError> {
+        if self.bytes[self.position..].starts_with(keyword.as_bytes()) {
+            self.position += keyword.len();
+            Ok(value)
+        } else {
+            Err(self.error("expected a value"))
+        }
+    }
+
+    fn object(&mut self, depth: usize) -> Result<Json, JsonError> {
+        self.position += 1;
+        let mut members = vec![];
+        if self.eat(b'}') {
+            return Ok(Json::Object(members));
+        }
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some(b'"') {
+                return Err(self.error("expected the name of a member"));
+            }
+            let name = self.string()?;
+            if !self.eat(b':') {
+                return Err(self.error("expected ':' after the name of a member"));
+            }
+            members.push((name, self.value(depth + 1)?));
+            if self.eat(b'}') {
+                return Ok(Json::Object(members));
+            }
+            if !self.eat(b',') {
+                return Err(self.error("expected ',' or '}' in an object"));
+            }
+        }
+    }
+
+    fn array(&mut self, depth: usize) -> Result<Json, JsonError> {
+        self.position += 1;
+        let mut items = vec![];
+        if self.eat(b']') {
+            return Ok(Json::Array(items));
+        }
+        loop {
+            items.push(self.value(depth + 1)?);
+            if self.eat(b']') {
+                return Ok(Json::Array(items));
+            }
+            if !self.eat(b',') {
+                return Err(self.error("expected ',' or ']' in an array"));
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Json, JsonError> {
+        let start = self.position;
+        let digits = |parser: &mut Parser| {
+            let from = parser.position;
+            while let Some(b'0'..=b'9') = parser.peek() {
+                parser.position += 1;
+            }
+            parser.position > from
+        };
+
+        if self.peek() == Some(b'-') {
+            self.position += 1;
+        }
+        if !digits(self) {
+            return Err(self.error("expected digits in a number"));
+        }
+        if self.peek() == Some(b'.') {
+            self.position += 1;
+            if !digits(self) {
+                return Err(self.error("expected digits after a decimal point"));
+            }
+        }
+        if let Some(b'e') | Some(b'E') = self.peek() {
+            self.position += 1;
+            if let Some(b'+') | Some(b'-') = self.peek() {
+                self.position += 1;
+            }
+            if !digits(self) {
+                return Err(self.error("expected digits in an exponent"));
+            }
+        }
+
+        // only ASCII has been taken, so this can't split a character.
+        let text = std::str::from_utf8(&self.bytes[start..self.position]).unwrap();
+        text.parse().map(Json::Number).map_err(|_| JsonError {
+            position: start,
+            message: "invalid number",
+        })
+    }
+
+    fn string(&mut self) -> Result<String, JsonError> {
+        self.position += 1;
+        let mut string = vec![];
+        loop {
+            match self.peek() {
+                None => return Err(self.error("a string is never closed")),
+                Some(b'"') => {
+                    self.position += 1;
+                    // the text was a str to begin with, and escapes add whole
+                    // characters, so this is valid UTF-8.
+                    return Ok(String::from_utf8(string).unwrap());
+                }
+                Some(b'\\') => {
+                    self.position += 1;
+                    let escaped = match self.peek() {
+                        Some(b'"') => '"',
+                        Some(b'\\') => '\\',
+                        Some(b'/') => '/',
+                        Some(b'b') => '\u{8}',
+                        Some(b'f') => '\u{c}',
+                        Some(b'n') => '\n',
+                        Some(b'r') => '\r',
+                        Some(b't') => '\t',
+                        Some(b'u') => {
+                            self.position += 1;
+                            self.unicode_escape()?
+                        }
+                        _ => return Err(self.error("invalid escape in a string")),
+                    };
+                    self.position += 1;
+                    let mut buffer = [0; 4];
+                    string.extend_from_slice(escaped.encode_utf8(&mut buffer).as_bytes());
+                }
+                Some(byte) if byte < 0x20 => {
+                    return Err(self.error("control characters must be escaped in strings"))
+                }
+                Some(byte) => {
+                    string.push(byte);
+                    self.position += 1;
+                }
+            }
+        }
+    }
+
+    /// reads the four hex digits after `\u`, and the second half of a surrogate pair
+    /// if they start one, leaving the position on the last digit.
+    fn unicode_escape(&mut self) -> Result<char, JsonError> {
+        let first = self.hex4()?;
+        let code = if (0xd800..0xdc00).contains(&first) {
+            if !self.bytes[self.position + 1..].starts_with(b"\\u") {
+                return Err(self.error("unpaired surrogate in a string"));
+            }
+            self.position += 3;
+            let second = self.hex4()?;
+            if !(0xdc00..0xe000).contains(&second) {
+                return Err(self.error("unpaired surrogate in a string"));
+            }
+            0x10000 + ((first - 0xd800) << 10) + (second - 0xdc00)
+        } else {
+            first
+        };
+        char::from_u32(code).ok_or_else(|| self.error("unpaired surrogate in a string"))
+    }
+
+    /// reads four hex digits, leaving the position on the last of them.
+    fn hex4(&mut self) -> Result<u32, JsonError> {
+        let digits = self
+            .bytes
+            .get(self.position..self.position + 4)
+            .and_then(|digits| std::str::from_utf8(digits).ok())
+            .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+            .ok_or_else(|| self.error("expected four hex digits after \\u"))?;
+        self.position += 3;
+        Ok(digits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parsing_values() {
+        assert_eq!(parse(" null ").unwrap(), Json::Null);
+        assert_eq!(parse("true").unwrap(), Json::Bool(true));
+        assert_eq!(parse("-1.5e2").unwrap(), Json::Number(-150.0));
+        assert_eq!(
+            parse(r#"{"a": [1, "two", {}], "b": false}"#).unwrap(),
+            Json::Object(vec![
+                (
+                    "a".to_string(),
+                    Json::Array(vec![
+                        Json::Number(1.0),
+                        Json::String("two".to_string()),
+                        Json::Object(vec![])
+                    ])
+                ),
+                ("b".to_string(), Json::Bool(false)),
+            ])
+        );
+    }
+
+    #[test]
+    fn escapes_in_strings() {
+        assert_eq!(
+            parse(r#""a\"b\\c\/d\n\u00e9\ud83d\ude00""#).unwrap(),
+            Json::String("a\"b\\c/d\né😀".to_string())
+        );
+        assert_eq!(parse("\"né\"").unwrap(), Json::String("né".to_string()));
+    }
+
+    #[test]
+    fn reading_members() {
+        let json = parse(r#"{"count": 3, "name": "x", "items": [0.5]}"#).unwrap();
+        assert_eq!(json.get("count").and_then(Json::as_usize), Some(3));
+        assert_eq!(json.get("name").and_then(Json::as_str), Some("x"));
+        assert_eq!(json.get("items").and_then(Json::as_array).unwrap().len(), 1);
+        assert_eq!(
+            json.get("items").unwrap().as_array().unwrap()[0].as_usize(),
+            None
+        );
+        assert!(json.get("missing").is_none());
+    }
+
+    #[test]
+    fn invalid_documents_are_errors() {
+        for &text in [
+            "",
+            "{",
+            "[1,]",
+            "{\"a\" 1}",
+            "\"open",
+            "01x",
+            "-",
+            "1.",
+            "tru",
+            "[1] 2",
+            "\"\\ud800\"",
+            "\"\\x\"",
+        ]
+        .iter()
+        {
+            assert!(parse(text).is_err(), "{:?} should not parse", text);
+        }
+        let deep = "[".repeat(MAX_DEPTH + 2);
+        assert_eq!(
+            parse(&deep).unwrap_err().message,
+            "values are nested too deeply"
+        );
+    }
+}
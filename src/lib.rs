@@ -1,4 +1,16 @@
 #![feature(stmt_expr_attributes)]
 
+pub mod builders;
+pub mod error;
+pub use error::RayTracerError;
+#[cfg(feature = "gltf")]
+pub mod gltf;
 pub mod math;
+pub mod obj;
+#[cfg(feature = "serde")]
+pub mod scene;
+pub mod scenes;
+pub mod stl;
+pub mod svg;
+pub mod test_support;
 pub mod world;
@@ -1,30 +1,273 @@
-#![feature(stmt_expr_attributes)]
-
-use std::f64::consts;
-
-mod math;
-mod world;
+use std::{
+    env,
+    f64::consts,
+    fmt::Display,
+    fs::File,
+    io::{self, BufReader},
+    path::{Path, PathBuf},
+    process,
+    time::Instant,
+};
 
-use crate::{
+#[cfg(feature = "gltf")]
+use ray_tracer_challenge::gltf;
+use ray_tracer_challenge::{
     math::{Form, Geometry, Matrix, Point, Transformable, Vector},
+    obj, scenes, stl,
     world::{
         light::{self, Light},
         pattern::{Gradient, Grid, Stripe},
-        Camera, Color, Pattern, Texture, View, World,
+        Background, Camera, Color, LogRecord, Pattern, RenderEvent, RenderLogger, RenderOptions,
+        Sampling, Severity, Texture, View, World,
     },
+    RayTracerError,
 };
 
-fn main() {
-    let mut floor = Geometry::default().with_form(Form::Plane);
-    floor.material.texture = Texture::pattern(Pattern::grid(Grid::new(
-        Color::new(0.5, 0.1, 0.5),
-        Color::new(0.1, 0.1, 0.1),
-    )));
+/// writes events of at least `level` to standard error, timed from when the logger was
+/// created, so that they don't get mixed into the image written to standard output.
+struct StderrLogger {
+    start: Instant,
+    level: Severity,
+}
+
+impl StderrLogger {
+    fn new(level: Severity) -> StderrLogger {
+        StderrLogger {
+            start: Instant::now(),
+            level,
+        }
+    }
+}
+
+impl RenderLogger for StderrLogger {
+    fn log(&mut self, record: LogRecord) {
+        if record.severity < self.level {
+            return;
+        }
+
+        eprintln!(
+            "[{:>9.3}s {:>7}] {}",
+            record
+                .timestamp
+                .saturating_duration_since(self.start)
+                .as_secs_f64(),
+            record.severity,
+            record.event
+        );
+    }
+}
+
+const USAGE: &str = "usage: ray_tracer_challenge [options] [<output.ppm, .pfm or .png>]
+       ray_tracer_challenge [options] --debug-pixel <x>,<y>
+       ray_tracer_challenge --list-demos
+       ray_tracer_challenge --help";
+
+const HELP: &str = "renders a few spheres on a checkered floor, or the model given with --scene
+in their place, or one of the demos. the image is saved to the output file, or
+written to standard output as a PPM when there is none.
+
+options:
+  -o, --output <file>     where to save the image, instead of naming it last
+      --scene <file>      a model to render, from an .obj or .stl file, or from a
+                          .gltf or .glb file when built with the gltf feature
+      --demo <name>       renders one of the scenes from the book instead
+      --list-demos        lists the demos, instead of rendering anything
+      --width <pixels>    how wide the image is (default 1000, or the demo's)
+      --height <pixels>   how tall the image is (default 500, or the demo's)
+      --fov <degrees>     how far the camera sees along the image's longer side
+                          (default 60, or the demo's)
+      --samples <n>       renders each pixel with n by n rays spread over it
+                          (default 1)
+      --threads <n>       how many threads to render with (default: one for each
+                          the machine can run at once)
+      --exposure <stops>  brightens the image, or darkens it when negative
+      --debug-pixel <x>,<y>
+                          describes how one pixel is traced, instead of
+                          rendering the image
+  -h, --help              shows this message";
+
+/// the options given on the command line.
+#[derive(Debug, PartialEq)]
+struct Args {
+    /// when set, the size of the image, replacing the scene's own.
+    width: Option<usize>,
+    height: Option<usize>,
+    /// when set, the camera's field of view in radians, replacing the scene's own.
+    field_of_view: Option<f64>,
+    /// how many rays are fired along each side of a pixel.
+    samples: usize,
+    /// when set, how many threads to render with.
+    threads: Option<usize>,
+    /// how many stops to brighten the image by, or darken when negative.
+    exposure: f64,
+    /// where to save the image. the image is written to standard output without it.
+    output: Option<PathBuf>,
+    /// when set, the model to render instead of the spheres.
+    scene: Option<PathBuf>,
+    /// when set, the name of the demo to render instead of the spheres.
+    demo: Option<String>,
+    /// when set, the pixel to trace and describe instead of rendering the image.
+    debug_pixel: Option<(usize, usize)>,
+}
+
+impl Default for Args {
+    fn default() -> Args {
+        Args {
+            width: None,
+            height: None,
+            field_of_view: None,
+            samples: 1,
+            threads: None,
+            exposure: 0.0,
+            output: None,
+            scene: None,
+            demo: None,
+            debug_pixel: None,
+        }
+    }
+}
+
+/// what the command line asks for.
+#[derive(Debug, PartialEq)]
+enum Command {
+    Render(Args),
+    ListDemos,
+    Help,
+}
+
+/// reads the arguments that follow the name of the program.
+fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<Command, String> {
+    let mut parsed = Args::default();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut value = |what: &str| args.next().ok_or_else(|| format!("{} needs {}", arg, what));
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "--list-demos" => return Ok(Command::ListDemos),
+            "-o" | "--output" => parsed.output = Some(value("a file")?.into()),
+            "--scene" => parsed.scene = Some(value("a file")?.into()),
+            "--demo" => {
+                let name = value("the name of a demo")?;
+                if scenes::demo(&name).is_none() {
+                    return Err(format!(
+                        "there is no demo called {}; --list-demos shows them all",
+                        name
+                    ));
+                }
+                parsed.demo = Some(name);
+            }
+            "--width" => parsed.width = Some(positive(&arg, &value("a number of pixels")?)?),
+            "--height" => parsed.height = Some(positive(&arg, &value("a number of pixels")?)?),
+            "--samples" => parsed.samples = positive(&arg, &value("a number of rays")?)?,
+            "--threads" => parsed.threads = Some(positive(&arg, &value("a number of threads")?)?),
+            "--fov" => {
+                let degrees = number(&arg, &value("an angle in degrees")?)?;
+                if !(0.0 < degrees && degrees < 180.0) {
+                    return Err(format!(
+                        "--fov must be between 0 and 180 degrees, not {}",
+                        degrees
+                    ));
+                }
+                parsed.field_of_view = Some(degrees.to_radians());
+            }
+            "--exposure" => parsed.exposure = number(&arg, &value("a number of stops")?)?,
+            "--debug-pixel" => {
+                let pixel = value("a pixel, written as x,y")?;
+                parsed.debug_pixel = Some(parse_pixel(&pixel).ok_or_else(|| {
+                    format!("--debug-pixel needs a pixel, written as x,y, not {}", pixel)
+                })?)
+            }
+            _ if parsed.output.is_none() && !arg.starts_with('-') => {
+                parsed.output = Some(arg.into())
+            }
+            _ => return Err(format!("unexpected argument {}", arg)),
+        }
+    }
+    if parsed.scene.is_some() && parsed.demo.is_some() {
+        return Err("--scene and --demo can't be used together".to_string());
+    }
+    Ok(Command::Render(parsed))
+}
+
+/// reads the value of `flag` as a whole number above zero.
+fn positive(flag: &str, value: &str) -> Result<usize, String> {
+    match value.parse() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(format!(
+            "{} needs a whole number above 0, not {}",
+            flag, value
+        )),
+    }
+}
+
+/// reads the value of `flag` as a finite number.
+fn number(flag: &str, value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(n) if n.is_finite() => Ok(n),
+        _ => Err(format!("{} needs a number, not {}", flag, value)),
+    }
+}
+
+/// reads a pixel written as "x,y".
+fn parse_pixel(pixel: &str) -> Option<(usize, usize)> {
+    let (x, y) = pixel.split_once(',')?;
+    Some((x.trim().parse().ok()?, y.trim().parse().ok()?))
+}
 
+/// reads the model in `path`, telling its format by its extension. warnings about the
+/// parts that were skipped are reported to standard error.
+fn load_scene<L: RenderLogger>(path: &Path, logger: &mut L) -> Result<Geometry, String> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase);
+    let failed = |error: &dyn Display| format!("could not load {}: {}", path.display(), error);
+    let mut warn = |warning: &dyn Display| {
+        logger.log(LogRecord::now(RenderEvent::Warning(format!(
+            "{}: {}",
+            path.display(),
+            warning
+        ))))
+    };
+    match extension.as_deref() {
+        Some("obj") => {
+            let model = obj::load_obj(path).map_err(|error| failed(&error))?;
+            for warning in &model.warnings {
+                warn(warning);
+            }
+            Ok(model.to_group())
+        }
+        Some("stl") => {
+            let file = File::open(path).map_err(|error| failed(&error))?;
+            let model = stl::parse(BufReader::new(file)).map_err(|error| failed(&error))?;
+            Ok(model.to_group())
+        }
+        #[cfg(feature = "gltf")]
+        Some("gltf") | Some("glb") => {
+            let model = gltf::load_gltf(path).map_err(|error| failed(&error))?;
+            for warning in &model.warnings {
+                warn(warning);
+            }
+            Ok(model.scene)
+        }
+        _ => Err(format!(
+            "don't know how to load {}, which isn't an .obj or .stl file{}",
+            path.display(),
+            if cfg!(feature = "gltf") {
+                ", or a .gltf or .glb file"
+            } else {
+                ""
+            }
+        )),
+    }
+}
+
+/// the spheres rendered when no scene is given.
+fn spheres() -> Vec<Geometry> {
     let mut middle = Geometry::default()
         .with_form(Form::Sphere)
         .transformed(Matrix::translation(-0.5, 1.0, 0.5));
-    middle.material.texture = Texture::pattern(
+    middle.make_material_unique().texture = Texture::pattern(
         Pattern::stripe(Stripe::new(
             Color::new(0.1, 1.0, 0.5),
             Color::new(0.5, 1.0, 1.0),
@@ -35,45 +278,310 @@ fn main() {
                 * Matrix::rotation_y(consts::PI / 4.0),
         ),
     );
-    middle.material.diffuse = 0.7;
-    middle.material.specular = 0.3;
+    middle.make_material_unique().diffuse = 0.7;
+    middle.make_material_unique().specular = 0.3;
 
     let mut right = Geometry::default().with_form(Form::Sphere).transformed(
         *Matrix::identity()
             .scale(0.5, 0.5, 0.5)
             .translate(1.5, 0.5, -0.5),
     );
-    right.material.texture = Texture::pattern(Pattern::gradient(Gradient::new(
+    right.make_material_unique().texture = Texture::pattern(Pattern::gradient(Gradient::new(
         Color::new(1.0, 0.0, 0.0),
         Color::new(0.0, 0.0, 1.0),
     )));
-    right.material.diffuse = 0.7;
-    right.material.specular = 0.3;
+    right.make_material_unique().diffuse = 0.7;
+    right.make_material_unique().specular = 0.3;
 
     let mut left = Geometry::default().with_form(Form::Sphere).transformed(
         *Matrix::identity()
             .scale(0.33, 0.33, 0.33)
             .translate(-1.5, 0.33, -0.75),
     );
-    left.material.texture = Texture::pattern(Pattern::solid(Color::new(1.0, 0.8, 0.1)));
-    left.material.diffuse = 0.7;
-    left.material.specular = 0.3;
+    left.make_material_unique().texture =
+        Texture::pattern(Pattern::solid(Color::new(1.0, 0.8, 0.1)));
+    left.make_material_unique().diffuse = 0.7;
+    left.make_material_unique().specular = 0.3;
+
+    vec![middle, right, left]
+}
+
+/// the spheres, or whatever is in their place, on a checkered floor under a blue sky.
+fn default_scene(mut objects: Vec<Geometry>) -> (World, Camera) {
+    let mut floor = Geometry::default().with_form(Form::Plane);
+    floor.make_material_unique().texture = Texture::pattern(Pattern::grid(Grid::new(
+        Color::new(0.5, 0.1, 0.5),
+        Color::new(0.1, 0.1, 0.1),
+    )));
+    objects.insert(0, floor);
 
     let sun = Light::point(light::Point::new(
         Point::new(-10.0, 10.0, -10.0),
         Color::new(1.0, 1.0, 1.0),
     ));
-
-    let world = World::new(vec![floor, middle, right, left], vec![sun]);
+    let world = World::new(objects, vec![sun]).with_background(Background::VerticalGradient {
+        zenith: Color::new(0.2, 0.4, 0.9),
+        horizon: Color::new(0.9, 0.9, 1.0),
+    });
 
     let mut camera = Camera::new(1000, 500, consts::PI / 3.0);
-    camera.view = View::transformed(
+    camera.set_view(View::transformed(
         Point::new(0.0, 1.5, -5.0),
         Point::new(0.0, 1.0, 0.0),
         Vector::new(0.0, 1.0, 0.0),
-    );
+    ));
+    (world, camera)
+}
+
+fn main() {
+    let args = match parse_args(env::args().skip(1)) {
+        Ok(Command::Render(args)) => args,
+        Ok(Command::ListDemos) => {
+            for demo in scenes::DEMOS {
+                println!("{:<18}{}", demo.name, demo.description);
+            }
+            return;
+        }
+        Ok(Command::Help) => {
+            println!("{}\n\n{}", USAGE, HELP);
+            return;
+        }
+        Err(error) => {
+            eprintln!("{}\n{}", error, USAGE);
+            process::exit(2);
+        }
+    };
+
+    let mut logger = StderrLogger::new(Severity::Info);
+    let (world, camera) = match (&args.demo, &args.scene) {
+        (Some(name), _) => (scenes::demo(name).unwrap().build)(),
+        (None, Some(path)) => default_scene(vec![load_scene(path, &mut logger).unwrap_or_else(
+            |error| {
+                eprintln!("{}", error);
+                process::exit(1);
+            },
+        )]),
+        (None, None) => default_scene(spheres()),
+    };
+    world.prepare(&mut logger);
+
+    let mut camera =
+        if args.width.is_some() || args.height.is_some() || args.field_of_view.is_some() {
+            let mut resized = Camera::new(
+                args.width.unwrap_or(camera.image_width),
+                args.height.unwrap_or(camera.image_height),
+                args.field_of_view.unwrap_or(camera.field_of_view),
+            );
+            resized.set_view(*camera.view());
+            resized
+        } else {
+            camera
+        };
+    camera.exposure = args.exposure;
+    if args.samples > 1 {
+        camera.sampling = Sampling::stratified(args.samples, 0);
+    }
+
+    if let Some((x, y)) = args.debug_pixel {
+        if x >= camera.image_width || y >= camera.image_height {
+            let error = RayTracerError::InvalidCanvasIndex {
+                x,
+                y,
+                width: camera.image_width,
+                height: camera.image_height,
+            };
+            eprintln!("{}", error);
+            process::exit(2);
+        }
+        print!("{}", world.trace_pixel(&camera, x, y));
+        return;
+    }
+
+    // report progress every 25 rows.
+    let mut options = RenderOptions {
+        progress_interval: Some(camera.image_width * 25),
+        ..RenderOptions::default()
+    };
+    if let Some(threads) = args.threads {
+        options.threads = threads;
+    }
+    let (canvas, _) = camera.render_with_options(&world, options, &mut logger);
+
+    let saved = match &args.output {
+        Some(output) => canvas
+            .save(output)
+            .map_err(|error| format!("could not save {}: {}", output.display(), error)),
+        None => canvas
+            .write_ppm(&mut io::stdout().lock())
+            .map_err(|error| format!("could not write the image: {}", error)),
+    };
+    if let Err(error) = saved {
+        eprintln!("{}", error);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ray_tracer_challenge::world::NoopLogger;
+
+    fn parse(args: &[&str]) -> Result<Command, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn no_arguments_keep_the_defaults() {
+        // the image then goes to standard output.
+        assert_eq!(parse(&[]), Ok(Command::Render(Args::default())));
+    }
+
+    #[test]
+    fn reading_every_option() {
+        let args = parse(&[
+            "--width",
+            "320",
+            "--height",
+            "200",
+            "--fov",
+            "90",
+            "--samples",
+            "4",
+            "--threads",
+            "2",
+            "--exposure",
+            "-1.5",
+            "--scene",
+            "teapot.obj",
+            "-o",
+            "teapot.png",
+        ]);
+        assert_eq!(
+            args,
+            Ok(Command::Render(Args {
+                width: Some(320),
+                height: Some(200),
+                field_of_view: Some(consts::FRAC_PI_2),
+                samples: 4,
+                threads: Some(2),
+                exposure: -1.5,
+                output: Some("teapot.png".into()),
+                scene: Some("teapot.obj".into()),
+                demo: None,
+                debug_pixel: None,
+            }))
+        );
+    }
+
+    #[test]
+    fn the_output_can_be_named_last() {
+        let args = parse(&["--exposure", "1", "image.ppm"]).unwrap();
+        assert_eq!(
+            args,
+            Command::Render(Args {
+                exposure: 1.0,
+                output: Some("image.ppm".into()),
+                ..Args::default()
+            })
+        );
+        assert_eq!(
+            parse(&["--debug-pixel", "3, 4"]).unwrap(),
+            Command::Render(Args {
+                debug_pixel: Some((3, 4)),
+                ..Args::default()
+            })
+        );
+    }
+
+    #[test]
+    fn choosing_a_demo() {
+        assert_eq!(
+            parse(&["--demo", "chapter-eight"]).unwrap(),
+            Command::Render(Args {
+                demo: Some("chapter-eight".to_string()),
+                ..Args::default()
+            })
+        );
+        assert_eq!(parse(&["--list-demos"]), Ok(Command::ListDemos));
+        assert_eq!(
+            parse(&["--demo", "chapter-ninety"]),
+            Err("there is no demo called chapter-ninety; --list-demos shows them all".to_string())
+        );
+        assert_eq!(
+            parse(&["--demo", "chapter-five", "--scene", "teapot.obj"]),
+            Err("--scene and --demo can't be used together".to_string())
+        );
+    }
+
+    #[test]
+    fn asking_for_help() {
+        assert_eq!(parse(&["--width", "10", "--help"]), Ok(Command::Help));
+        assert_eq!(parse(&["-h"]), Ok(Command::Help));
+    }
+
+    #[test]
+    fn invalid_values_are_explained() {
+        assert_eq!(
+            parse(&["--width", "0"]),
+            Err("--width needs a whole number above 0, not 0".to_string())
+        );
+        assert_eq!(
+            parse(&["--threads", "many"]),
+            Err("--threads needs a whole number above 0, not many".to_string())
+        );
+        assert_eq!(
+            parse(&["--fov", "180"]),
+            Err("--fov must be between 0 and 180 degrees, not 180".to_string())
+        );
+        assert_eq!(
+            parse(&["--exposure", "NaN"]),
+            Err("--exposure needs a number, not NaN".to_string())
+        );
+        assert_eq!(
+            parse(&["--samples"]),
+            Err("--samples needs a number of rays".to_string())
+        );
+        assert_eq!(
+            parse(&["--debug-pixel", "3"]),
+            Err("--debug-pixel needs a pixel, written as x,y, not 3".to_string())
+        );
+        assert_eq!(
+            parse(&["a.ppm", "b.ppm"]),
+            Err("unexpected argument b.ppm".to_string())
+        );
+        assert_eq!(
+            parse(&["--colour", "red"]),
+            Err("unexpected argument --colour".to_string())
+        );
+    }
+
+    #[test]
+    fn scenes_are_loaded_by_their_extension() {
+        let mut logger = NoopLogger;
+        assert!(load_scene(Path::new("tests/models/cube.stl"), &mut logger).is_ok());
+        assert!(load_scene(Path::new("tests/models/cube.obj"), &mut logger).is_ok());
+        let error = load_scene(Path::new("scene.blend"), &mut logger).unwrap_err();
+        assert!(error.starts_with("don't know how to load scene.blend"));
+        let error = load_scene(Path::new("missing.obj"), &mut logger).unwrap_err();
+        assert!(error.starts_with("could not load missing.obj: "));
+    }
 
-    let canvas = camera.render(&world);
+    #[test]
+    fn loader_warnings_are_logged() {
+        struct Collect(Vec<RenderEvent>);
+        impl RenderLogger for Collect {
+            fn log(&mut self, record: LogRecord) {
+                self.0.push(record.event);
+            }
+        }
 
-    println!("{}", canvas.to_ppm());
+        // cube.obj names a material library that isn't there.
+        let mut logger = Collect(vec![]);
+        load_scene(Path::new("tests/models/cube.obj"), &mut logger).unwrap();
+        assert!(matches!(
+            &logger.0[..],
+            [RenderEvent::Warning(message)]
+                if message.starts_with("tests/models/cube.obj: ") && message.contains("cube.mtl")
+        ));
+    }
 }
@@ -1,12 +1,32 @@
+pub mod axis;
+pub use axis::Axis;
+
+pub mod basis;
+pub use basis::{sample_cone, sample_disk, sample_hemisphere_cosine, sample_sphere, Basis};
+
+pub mod bounds;
+pub use bounds::BoundingBox;
+
 pub mod geometry;
 pub use geometry::{Form, Geometry, Hittable, Transformable};
 
 pub mod matrix;
 pub use matrix::Matrix;
 
+pub mod matrix4;
+pub use matrix4::Matrix4;
+
+pub mod noise;
+
 pub mod point;
 pub use point::Point;
 
+pub mod random;
+pub use random::XorShift;
+
+pub mod text;
+pub use text::ParseMathError;
+
 pub mod vector;
 pub use vector::Vector;
 
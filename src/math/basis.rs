@@ -0,0 +1,242 @@
+use std::f64::consts;
+
+use crate::math::Vector;
+
+/// an orthonormal frame built around a surface normal.
+///
+/// directions sampled in "local space" have the normal as their z axis, and are
+/// brought back into world space with `to_world`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Basis {
+    pub tangent: Vector,
+    pub bitangent: Vector,
+    pub normal: Vector,
+}
+
+impl Basis {
+    /// builds a basis around the normalized vector `normal`.
+    ///
+    /// uses the branchless construction from duff et al., "building an orthonormal
+    /// basis, revisited" (2017), which stays stable for normals close to +z and -z.
+    pub fn from_normal(normal: Vector) -> Basis {
+        let sign = 1.0_f64.copysign(normal[2]);
+        let a = -1.0 / (sign + normal[2]);
+        let b = normal[0] * normal[1] * a;
+        Basis {
+            tangent: Vector::new(
+                1.0 + sign * normal[0] * normal[0] * a,
+                sign * b,
+                -sign * normal[0],
+            ),
+            bitangent: Vector::new(b, sign + normal[1] * normal[1] * a, -normal[1]),
+            normal,
+        }
+    }
+
+    /// takes a direction in local space into world space.
+    pub fn to_world(&self, local: Vector) -> Vector {
+        self.tangent * local[0] + self.bitangent * local[1] + self.normal * local[2]
+    }
+
+    /// takes a direction in world space into local space.
+    pub fn to_local(&self, world: Vector) -> Vector {
+        Vector::new(
+            world.dot(&self.tangent),
+            world.dot(&self.bitangent),
+            world.dot(&self.normal),
+        )
+    }
+}
+
+/// maps two uniform numbers in `[0, 1)` to a local-space direction in the +z
+/// hemisphere, with directions close to the normal being more likely (proportional to
+/// the cosine of their angle to it).
+pub fn sample_hemisphere_cosine(u1: f64, u2: f64) -> Vector {
+    let radius = u1.sqrt();
+    let phi = 2.0 * consts::PI * u2;
+    Vector::new(
+        radius * phi.cos(),
+        radius * phi.sin(),
+        (1.0 - u1).max(0.0).sqrt(),
+    )
+}
+
+/// maps two uniform numbers in `[0, 1)` to a local-space direction, uniformly
+/// distributed inside the cone around +z whose half-angle is `half_angle` radians.
+pub fn sample_cone(u1: f64, u2: f64, half_angle: f64) -> Vector {
+    let cos_theta = 1.0 - u1 * (1.0 - half_angle.cos());
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let phi = 2.0 * consts::PI * u2;
+    Vector::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta)
+}
+
+/// maps two uniform numbers in `[0, 1)` to a direction uniformly distributed over the
+/// whole unit sphere.
+pub fn sample_sphere(u1: f64, u2: f64) -> Vector {
+    let z = 1.0 - 2.0 * u1;
+    let radius = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * consts::PI * u2;
+    Vector::new(radius * phi.cos(), radius * phi.sin(), z)
+}
+
+/// maps two uniform numbers in `[0, 1)` to a point uniformly distributed over the unit
+/// disk, as an (x, y) pair.
+///
+/// this is peter shirley and kenneth chiu's concentric mapping, which squashes
+/// concentric squares onto concentric circles, so that samples which are spread
+/// evenly over the square stay spread evenly over the disk.
+pub fn sample_disk(u1: f64, u2: f64) -> (f64, f64) {
+    let (a, b) = (2.0 * u1 - 1.0, 2.0 * u2 - 1.0);
+    if a == 0.0 && b == 0.0 {
+        return (0.0, 0.0);
+    }
+
+    let (radius, phi) = if a.abs() > b.abs() {
+        (a, consts::FRAC_PI_4 * (b / a))
+    } else {
+        (b, consts::FRAC_PI_2 - consts::FRAC_PI_4 * (a / b))
+    };
+    (radius * phi.cos(), radius * phi.sin())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::EPSILON;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    fn random_normal(rng: &mut StdRng) -> Vector {
+        loop {
+            let v = Vector::new(
+                rng.gen_range(-1.0, 1.0),
+                rng.gen_range(-1.0, 1.0),
+                rng.gen_range(-1.0, 1.0),
+            );
+            let length = v.magnitude();
+            if 0.01 < length && length <= 1.0 {
+                return v.normalized();
+            }
+        }
+    }
+
+    fn assert_orthonormal(basis: &Basis) {
+        for v in [basis.tangent, basis.bitangent, basis.normal].iter() {
+            assert!((v.magnitude() - 1.0).abs() < EPSILON);
+        }
+        assert!(basis.tangent.dot(&basis.bitangent).abs() < EPSILON);
+        assert!(basis.tangent.dot(&basis.normal).abs() < EPSILON);
+        assert!(basis.bitangent.dot(&basis.normal).abs() < EPSILON);
+        assert_eq!(basis.tangent.cross(&basis.bitangent), basis.normal);
+    }
+
+    #[test]
+    fn basis_is_orthonormal_for_random_normals() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..1000 {
+            assert_orthonormal(&Basis::from_normal(random_normal(&mut rng)));
+        }
+    }
+
+    #[test]
+    fn basis_is_orthonormal_near_poles() {
+        assert_orthonormal(&Basis::from_normal(Vector::new(0.0, 0.0, 1.0)));
+        assert_orthonormal(&Basis::from_normal(Vector::new(0.0, 0.0, -1.0)));
+        assert_orthonormal(&Basis::from_normal(
+            Vector::new(1e-9, -1e-9, -1.0).normalized(),
+        ));
+    }
+
+    #[test]
+    fn local_z_is_the_normal() {
+        let normal = Vector::new(1.0, 2.0, 3.0).normalized();
+        let basis = Basis::from_normal(normal);
+        assert_eq!(basis.to_world(Vector::new(0.0, 0.0, 1.0)), normal);
+    }
+
+    #[test]
+    fn to_world_round_trips() {
+        let mut rng = StdRng::seed_from_u64(2);
+        for _ in 0..100 {
+            let basis = Basis::from_normal(random_normal(&mut rng));
+            let local = Vector::new(
+                rng.gen_range(-5.0, 5.0),
+                rng.gen_range(-5.0, 5.0),
+                rng.gen_range(-5.0, 5.0),
+            );
+            assert_eq!(basis.to_local(basis.to_world(local)), local);
+        }
+    }
+
+    #[test]
+    fn cosine_samples_are_in_the_hemisphere() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let count = 100_000;
+        let mut total_z = 0.0;
+        for _ in 0..count {
+            let v = sample_hemisphere_cosine(rng.gen(), rng.gen());
+            assert!((v.magnitude() - 1.0).abs() < EPSILON);
+            assert!(0.0 < v[2]);
+            total_z += v[2];
+        }
+        // the mean of cos(theta) weighted by cos(theta) over the hemisphere is 2/3.
+        assert!((total_z / count as f64 - 2.0 / 3.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn sphere_samples_cover_the_sphere() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let count = 100_000;
+        let mut total = Vector::zero();
+        for _ in 0..count {
+            let v = sample_sphere(rng.gen(), rng.gen());
+            assert!((v.magnitude() - 1.0).abs() < EPSILON);
+            total += v;
+        }
+        // a uniform spread over the sphere averages out to its center.
+        assert!((total / count as f64).magnitude() < 0.01);
+    }
+
+    #[test]
+    fn disk_samples_cover_the_disk() {
+        let mut rng = StdRng::seed_from_u64(6);
+        let count = 100_000;
+        let (mut total_x, mut total_y, mut inner) = (0.0, 0.0, 0);
+        for _ in 0..count {
+            let (x, y) = sample_disk(rng.gen(), rng.gen());
+            let radius = (x * x + y * y).sqrt();
+            assert!(radius <= 1.0 + EPSILON);
+            total_x += x;
+            total_y += y;
+            if radius < 0.5 {
+                inner += 1;
+            }
+        }
+        // centered on the origin, with a quarter of the area inside half the radius.
+        assert!((total_x / count as f64).abs() < 0.01);
+        assert!((total_y / count as f64).abs() < 0.01);
+        assert!((inner as f64 / count as f64 - 0.25).abs() < 0.01);
+    }
+
+    #[test]
+    fn disk_sample_corners_and_center() {
+        assert_eq!(sample_disk(0.5, 0.5), (0.0, 0.0));
+        let (x, y) = sample_disk(1.0, 0.5);
+        assert!((x - 1.0).abs() < EPSILON && y.abs() < EPSILON);
+        let (x, y) = sample_disk(0.5, 0.0);
+        assert!(x.abs() < EPSILON && (y + 1.0).abs() < EPSILON);
+        let (x, y) = sample_disk(0.0, 0.0);
+        assert!((x * x + y * y - 1.0).abs() < EPSILON);
+        assert!((x - y).abs() < EPSILON && x < 0.0);
+    }
+
+    #[test]
+    fn cone_samples_stay_in_the_cone() {
+        let mut rng = StdRng::seed_from_u64(4);
+        let half_angle = consts::PI / 8.0;
+        for _ in 0..10_000 {
+            let v = sample_cone(rng.gen(), rng.gen(), half_angle);
+            assert!((v.magnitude() - 1.0).abs() < EPSILON);
+            assert!(v[2].acos() <= half_angle + EPSILON);
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use crate::math::{Point, Vector};
+
+/// axis-aligned box described by its minimum and maximum corners.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BoundingBox {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl BoundingBox {
+    pub fn new(min: Point, max: Point) -> BoundingBox {
+        BoundingBox { min, max }
+    }
+
+    /// the vector spanning from the minimum corner to the maximum corner.
+    pub fn size(&self) -> Vector {
+        self.max - self.min
+    }
+
+    pub fn center(&self) -> Point {
+        self.min + (self.size() / 2.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn size_of_box() {
+        let b = BoundingBox::new(Point::new(-1.0, 0.0, -3.0), Point::new(2.0, 4.0, 3.0));
+        assert_eq!(b.size(), Vector::new(3.0, 4.0, 6.0));
+    }
+
+    #[test]
+    fn center_of_box() {
+        let b = BoundingBox::new(Point::new(-1.0, 0.0, -3.0), Point::new(2.0, 4.0, 3.0));
+        assert_eq!(b.center(), Point::new(0.5, 2.0, 0.0));
+    }
+}
@@ -1,15 +1,39 @@
+pub mod cuboid;
+pub use cuboid::Cuboid;
+
+pub mod custom;
+pub use custom::Custom;
+
+pub mod cylinder;
+pub use cylinder::Cylinder;
+
+pub mod heightfield;
+pub use heightfield::{HeightSource, Heightfield};
+
 pub mod plane;
 pub use plane::Plane;
 
+pub mod quadric;
+pub use quadric::Quadric;
+
+pub mod smooth_triangle;
+pub use smooth_triangle::SmoothTriangle;
+
 pub mod sphere;
 pub use sphere::Sphere;
 
+pub mod test_shape;
+pub use test_shape::TestShape;
+
+pub mod triangle;
+pub use triangle::Triangle;
+
 use crate::{
     math::{Matrix, Point, Vector},
     world::{Color, Intersection, Intersections, Material, Ray, Textured},
 };
 
-use std::cmp::Reverse;
+use std::{cmp::Reverse, sync::Arc};
 
 pub trait Transformable {
     fn transformed(self, transform: Matrix) -> Self;
@@ -17,79 +41,226 @@ pub trait Transformable {
 }
 
 /// enum representing the possible geometry objects.
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Form {
+    /// an axis-aligned box between two corners.
+    Box {
+        min: Point,
+        max: Point,
+    },
+    /// a shape implemented outside of this crate. it can't be serialized, since it is
+    /// only known by its code.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Custom(Custom),
+    Cylinder(Cylinder),
+    /// a collection of child objects that are transformed together. a child's own
+    /// transform is applied first, followed by the transform of the group.
+    Group(Vec<Geometry>),
+    Heightfield(Heightfield),
     Plane,
+    Quadric(Quadric),
+    /// a triangle whose normal is blended between the normals at its corners, so that
+    /// a mesh of them looks smoothly curved.
+    SmoothTriangle(SmoothTriangle),
     Sphere,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Test(TestShape),
+    Triangle(Triangle),
     None,
 }
 
 /// trait outlining the functionality of a geometry object.
 pub trait Hittable {
-    fn hit(self, object_space_ray: Ray) -> Option<Intersections>;
-    fn normal_at(self, object_space_point: Point) -> Option<Vector>;
+    fn hit(&self, object_space_ray: Ray) -> Option<Intersections>;
+    fn normal_at(&self, object_space_point: Point) -> Option<Vector>;
+
+    /// like `normal_at`, but also given where on the surface a ray crossed it, as
+    /// recorded in the intersection. only shapes that blend their normals need it.
+    fn normal_at_uv(&self, object_space_point: Point, _uv: Option<(f64, f64)>) -> Option<Vector> {
+        self.normal_at(object_space_point)
+    }
 }
 
 /// encapsulates the geometry variant along with associated data.
-#[derive(Copy, Clone, Debug, PartialEq)]
+///
+/// the material is shared, so any number of objects can be given the same one without
+/// copying it, and `World::update_material` can change all of them at once.
+///
+/// serializing doesn't keep track of that sharing: each object writes out its own copy
+/// of its material, and reads back one of its own. objects that shared a material
+/// before a round trip through serde don't share it afterwards, so
+/// `World::update_material` will only change one of them.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "SerializedGeometry")
+)]
 pub struct Geometry {
     pub form: Form,
     pub transform: Matrix,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
     pub inverse: Matrix,
-    pub material: Material,
+    pub material: Arc<Material>,
+    /// for an object that moves, its transform at time 1. the object is at `transform`
+    /// at time 0, and in between, each part of the transform is blended linearly.
+    pub end_transform: Option<Matrix>,
+}
+
+/// an object as it is serialized. the inverse of its transform is worked out again
+/// rather than stored, so that it can't disagree with the transform.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct SerializedGeometry {
+    form: Form,
+    transform: Matrix,
+    material: Arc<Material>,
+    end_transform: Option<Matrix>,
+}
+
+#[cfg(feature = "serde")]
+impl From<SerializedGeometry> for Geometry {
+    fn from(serialized: SerializedGeometry) -> Geometry {
+        Geometry {
+            form: serialized.form,
+            material: serialized.material,
+            end_transform: serialized.end_transform,
+            ..Geometry::default()
+        }
+        .transformed(serialized.transform)
+    }
 }
 
 impl Geometry {
-    pub fn new(form: Form, transform: Matrix, inverse: Matrix, material: Material) -> Geometry {
+    pub fn new<M: Into<Arc<Material>>>(
+        form: Form,
+        transform: Matrix,
+        inverse: Matrix,
+        material: M,
+    ) -> Geometry {
         Geometry {
             form,
             transform,
             inverse,
-            material,
+            material: material.into(),
+            end_transform: None,
         }
     }
 
     pub fn with_form(self, form: Form) -> Geometry {
-        Geometry {
-            form,
-            transform: self.transform,
-            inverse: self.inverse,
-            material: self.material,
-        }
+        Geometry { form, ..self }
     }
 
     pub fn change_form(&mut self, form: Form) -> &mut Geometry {
-        *self = self.with_form(form);
+        *self = self.clone().with_form(form);
         self
     }
 
-    pub fn with_material(self, material: Material) -> Geometry {
+    /// gives this geometry a material, which is shared with whatever else already holds
+    /// it if it's passed as an `Arc`.
+    pub fn with_material<M: Into<Arc<Material>>>(self, material: M) -> Geometry {
         Geometry {
-            material,
-            transform: self.transform,
-            inverse: self.inverse,
-            form: self.form,
+            material: material.into(),
+            ..self
         }
     }
 
-    pub fn change_material(&mut self, material: Material) -> &mut Geometry {
-        *self = self.with_material(material);
+    pub fn change_material<M: Into<Arc<Material>>>(&mut self, material: M) -> &mut Geometry {
+        *self = self.clone().with_material(material);
         self
     }
+
+    /// gives this geometry its own copy of its material if it shares it with anything
+    /// else, so that the material can be edited without affecting other objects.
+    pub fn make_material_unique(&mut self) -> &mut Material {
+        Arc::make_mut(&mut self.material)
+    }
+
+    /// makes this geometry move, so that it is at `end_transform` by time 1. rays see
+    /// it wherever it is at their own time.
+    pub fn with_motion(self, end_transform: Matrix) -> Geometry {
+        Geometry {
+            end_transform: Some(end_transform),
+            ..self
+        }
+    }
+
+    /// stretches and turns this geometry so that the unit length of its y axis runs
+    /// from `from` to `to`: whatever was at the origin ends up at `from`, and whatever
+    /// was at `(0, 1, 0)` ends up at `to`. the geometry's own transform is applied
+    /// first, so a cylinder from 0 to 1 can be made thinner and then aimed to make a
+    /// strut between two points, which must be different.
+    pub fn aimed(self, from: Point, to: Point) -> Geometry {
+        let length = to - from;
+        let transform = Matrix::translation(from[0], from[1], from[2])
+            * Matrix::rotation_between(Vector::new(0.0, 1.0, 0.0), length)
+            * Matrix::scaling(1.0, length.magnitude(), 1.0)
+            * self.transform;
+        self.transformed(transform)
+    }
+
+    /// this geometry held still where it is at `time`.
+    pub fn at_time(&self, time: f64) -> Geometry {
+        let end = match self.end_transform {
+            Some(end) => end,
+            None => return self.clone(),
+        };
+
+        let t = time.clamp(0.0, 1.0);
+        let start = self.transform;
+        let transform = Matrix::with_columns(
+            start[0].lerp(end[0], t),
+            start[1].lerp(end[1], t),
+            start[2].lerp(end[2], t),
+            start.translation.lerp(end.translation, t),
+        );
+
+        Geometry {
+            transform,
+            inverse: inverse_of(transform),
+            end_transform: None,
+            form: self.form.clone(),
+            material: self.material.clone(),
+        }
+    }
+
+    pub fn debug_assert_consistent(&self) {
+        self.transform
+            .debug_assert_inverse(&self.inverse, "Geometry");
+    }
+
+    /// returns this geometry as seen from the space that contains `parent`, so that
+    /// it can be treated on its own after being found inside of a group.
+    fn nested_in(self, parent: &Geometry) -> Geometry {
+        Geometry {
+            transform: parent.transform * self.transform,
+            inverse: self.inverse * parent.inverse,
+            ..self
+        }
+    }
+}
+
+/// finds the inverse of a geometry's transform. a transform that squashes the geometry
+/// flat has no inverse, so it is given one full of NaNs instead, which `hit` takes to
+/// mean that there is nothing to hit.
+fn inverse_of(transform: Matrix) -> Matrix {
+    transform
+        .try_inverse()
+        .unwrap_or_else(|| Matrix::scaling(f64::NAN, f64::NAN, f64::NAN))
 }
 
 impl Transformable for Geometry {
     fn transformed(self, transform: Matrix) -> Geometry {
         Geometry {
             transform,
-            form: self.form,
-            inverse: transform.inverse(),
-            material: self.material,
+            inverse: inverse_of(transform),
+            ..self
         }
     }
 
     fn transform(&mut self, transform: Matrix) -> &mut Geometry {
-        *self = self.transformed(transform);
+        *self = self.clone().transformed(transform);
         self
     }
 }
@@ -102,20 +273,67 @@ impl Textured for Geometry {
 }
 
 impl Hittable for Geometry {
-    fn hit(self, world_space_ray: Ray) -> Option<Intersections> {
+    fn hit(&self, world_space_ray: Ray) -> Option<Intersections> {
+        // a moving object is hit wherever it is when the ray passes, and it stays there
+        // for the intersections it gives, so that they are shaded in the same place.
+        if self.end_transform.is_some() {
+            return self.at_time(world_space_ray.time).hit(world_space_ray);
+        }
+
+        self.debug_assert_consistent();
+
+        // a transform that can't be inverted squashes the object flat, leaving nothing
+        // to hit.
+        if !self.inverse.is_finite() {
+            return None;
+        }
+
         let object_space_ray = world_space_ray.transformed(self.inverse);
 
-        if let Some(intersections) = match self.form {
+        if let Form::Group(children) = &self.form {
+            // each child reports intersections with itself, so those only need to be
+            // brought out of this group's space.
+            let mut result = Intersections::default();
+            for child in children {
+                if let Some(intersections) = child.hit(object_space_ray) {
+                    for Reverse(intersection) in intersections.heap {
+                        result.insert(Intersection {
+                            ray: world_space_ray,
+                            object: intersection.object.nested_in(self),
+                            ..intersection
+                        });
+                    }
+                }
+            }
+
+            return if result.count() == 0 {
+                None
+            } else {
+                Some(result)
+            };
+        }
+
+        if let Some(intersections) = match &self.form {
+            Form::Box { min, max } => Cuboid::new(*min, *max).hit(object_space_ray),
+            Form::Custom(custom) => custom.hit(object_space_ray),
+            Form::Cylinder(cylinder) => cylinder.hit(object_space_ray),
+            Form::Heightfield(heightfield) => heightfield.hit(object_space_ray),
+            Form::Quadric(quadric) => quadric.hit(object_space_ray),
             Form::Sphere => Sphere::new().hit(object_space_ray),
             Form::Plane => Plane::new().hit(object_space_ray),
-            Form::None => None,
+            Form::SmoothTriangle(triangle) => triangle.hit(object_space_ray),
+            Form::Test(shape) => shape.hit(object_space_ray),
+            Form::Triangle(triangle) => triangle.hit(object_space_ray),
+            Form::Group(_) | Form::None => None,
         } {
             Some(Intersections::with(
                 intersections
                     .heap
                     .iter()
-                    .map(|&Reverse(intersection)| {
-                        Intersection::new(intersection.time, world_space_ray, self)
+                    .map(|Reverse(intersection)| Intersection {
+                        ray: world_space_ray,
+                        object: self.clone(),
+                        ..*intersection
                     })
                     .collect(),
             ))
@@ -124,18 +342,38 @@ impl Hittable for Geometry {
         }
     }
 
-    fn normal_at(self, world_space_point: Point) -> Option<Vector> {
+    fn normal_at(&self, world_space_point: Point) -> Option<Vector> {
+        self.normal_at_uv(world_space_point, None)
+    }
+
+    fn normal_at_uv(&self, world_space_point: Point, uv: Option<(f64, f64)>) -> Option<Vector> {
+        self.debug_assert_consistent();
         let object_space_point = self.inverse * world_space_point;
 
-        if let Some(normal) = match self.form {
+        match &self.form {
+            Form::Box { min, max } => Cuboid::new(*min, *max).normal_at(object_space_point),
+            Form::Custom(custom) => custom.normal_at(object_space_point),
+            Form::Cylinder(cylinder) => cylinder.normal_at(object_space_point),
+            Form::Heightfield(heightfield) => heightfield.normal_at(object_space_point),
+            Form::Quadric(quadric) => quadric.normal_at(object_space_point),
             Form::Sphere => Sphere::new().normal_at(object_space_point),
             Form::Plane => Plane::new().normal_at(object_space_point),
-            Form::None => None,
-        } {
-            Some((self.inverse.transposed() * normal).normalized())
-        } else {
-            None
+            Form::SmoothTriangle(triangle) => triangle.normal_at_uv(object_space_point, uv),
+            Form::Test(shape) => shape.normal_at(object_space_point),
+            Form::Triangle(triangle) => triangle.normal_at(object_space_point),
+            // a group has no surface of its own; its children's normals are found
+            // through the intersections they produce.
+            Form::Group(_) | Form::None => None,
         }
+        .map(|normal| match &self.material.normal_map {
+            Some(map) => map.perturb(object_space_point, normal),
+            None => normal,
+        })
+        .map(|normal| match &self.material.normal_perturbation {
+            Some(bumps) => bumps.perturb(object_space_point, normal),
+            None => normal,
+        })
+        .map(|normal| (self.inverse.transposed() * normal).normalized())
     }
 }
 
@@ -145,15 +383,29 @@ impl Default for Geometry {
             form: Form::None,
             transform: Matrix::identity(),
             inverse: Matrix::identity(),
-            material: Material::default(),
+            material: Arc::new(Material::default()),
+            end_transform: None,
         }
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::field_reassign_with_default)]
 mod tests {
     use super::*;
 
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "Geometry has a stale inverse")]
+    fn stale_geometry_is_caught() {
+        let mut s = Geometry::default().with_form(Form::Sphere);
+        s.transform = Matrix::scaling(2.0, 2.0, 2.0);
+        s.hit(Ray::new(
+            Point::new(0.0, 0.0, -5.0),
+            Vector::new(0.0, 0.0, 1.0),
+        ));
+    }
+
     #[test]
     fn default_transformation() {
         let s = Geometry::default();
@@ -169,17 +421,207 @@ mod tests {
         assert_eq!(s.inverse, m.inverse());
     }
 
+    #[test]
+    fn aiming_a_strut_between_two_points() {
+        let (from, to) = (Point::new(1.0, 0.0, 0.0), Point::new(1.0, 3.0, 4.0));
+        let strut = Geometry::default()
+            .with_form(Form::Cylinder(Cylinder::new(0.0, 1.0, true)))
+            .transformed(Matrix::scaling(0.1, 1.0, 0.1))
+            .aimed(from, to);
+        assert_eq!(strut.transform * Point::zero(), from);
+        assert_eq!(strut.transform * Point::new(0.0, 1.0, 0.0), to);
+        // it is still 0.1 thick across its length.
+        assert_eq!(
+            strut.transform * Point::new(1.0, 0.5, 0.0),
+            Point::new(1.1, 1.5, 2.0)
+        );
+
+        let r = Ray::new(Point::new(-5.0, 1.5, 2.0), Vector::new(1.0, 0.0, 0.0));
+        assert_eq!(strut.hit(r).unwrap().closest().unwrap().time, 5.9);
+    }
+
+    #[test]
+    fn moving_geometry_is_blended_by_time() {
+        let s = Geometry::default()
+            .with_form(Form::Sphere)
+            .with_motion(Matrix::translation(2.0, 0.0, 0.0) * Matrix::scaling(3.0, 3.0, 3.0));
+        let halfway = s.at_time(0.5);
+        let expected = Matrix::translation(1.0, 0.0, 0.0) * Matrix::scaling(2.0, 2.0, 2.0);
+        assert_eq!(halfway.transform, expected);
+        assert_eq!(halfway.inverse, expected.inverse());
+        assert_eq!(halfway.end_transform, None);
+        assert_eq!(s.at_time(-1.0).transform, Matrix::identity());
+    }
+
+    #[test]
+    fn still_geometry_ignores_time() {
+        let s = Geometry::default()
+            .with_form(Form::Sphere)
+            .transformed(Matrix::translation(0.0, 1.0, 0.0));
+        assert_eq!(s.at_time(0.7), s);
+    }
+
+    #[test]
+    fn rays_hit_moving_geometry_where_it_is_at_their_time() {
+        let s = Geometry::default()
+            .with_form(Form::Sphere)
+            .with_motion(Matrix::translation(0.0, 4.0, 0.0));
+        let r = Ray::new(Point::new(0.0, 2.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert!(s.hit(r).is_none());
+
+        let xs = s.hit(r.with_time(0.5)).unwrap();
+        let hit = xs.closest().unwrap();
+        assert_eq!(hit.time, 4.0);
+        assert_eq!(
+            hit.object.normal_at(Point::new(0.0, 2.0, -1.0)),
+            Some(Vector::new(0.0, 0.0, -1.0))
+        );
+    }
+
     #[test]
     fn default_material() {
         let s = Geometry::default();
-        assert_eq!(s.material, Material::default());
+        assert_eq!(*s.material, Material::default());
     }
 
     #[test]
     fn assigning_material() {
         let mut m = Material::default();
         m.ambient = 1.0;
-        let s = Geometry::default().with_material(m);
-        assert_eq!(s.material, m);
+        let s = Geometry::default().with_material(m.clone());
+        assert_eq!(*s.material, m);
+    }
+
+    #[test]
+    fn flattened_geometry_is_never_hit() {
+        let g = Geometry::default()
+            .with_form(Form::Sphere)
+            .transformed(Matrix::scaling(1.0, 0.0, 1.0));
+        let r = Ray::new(Point::new(0.0, 5.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+        assert!(g.hit(r).is_none());
+        let group = Geometry::default().with_form(Form::Group(vec![g]));
+        assert!(group.hit(r).is_none());
+    }
+
+    #[test]
+    fn objects_share_materials() {
+        let m = Arc::new(Material::default());
+        let a = Geometry::default().with_material(m.clone());
+        let b = Geometry::default().with_material(m.clone());
+        assert!(Arc::ptr_eq(&a.material, &b.material));
+    }
+
+    #[test]
+    fn making_material_unique_isolates_it() {
+        let m = Arc::new(Material::default());
+        let mut a = Geometry::default().with_material(m.clone());
+        let b = Geometry::default().with_material(m.clone());
+        a.make_material_unique().ambient = 1.0;
+        assert!(!Arc::ptr_eq(&a.material, &b.material));
+        assert_eq!(a.material.ambient, 1.0);
+        assert_eq!(b.material.ambient, 0.1);
+    }
+
+    #[test]
+    fn geometry_holds_material_by_reference() {
+        use std::mem::size_of;
+        assert_eq!(size_of::<Arc<Material>>(), size_of::<usize>());
+        assert!(size_of::<Arc<Material>>() < size_of::<Material>());
+        // the layout is up to the compiler, so allow a word of padding rather than
+        // checking the exact size. a material held by value would be far bigger.
+        assert!(
+            size_of::<Geometry>()
+                <= size_of::<Form>()
+                    + 2 * size_of::<Matrix>()
+                    + size_of::<Option<Matrix>>()
+                    + 2 * size_of::<usize>()
+        );
+    }
+
+    #[test]
+    fn create_group() {
+        let g = Geometry::default().with_form(Form::Group(vec![]));
+        assert_eq!(g.transform, Matrix::identity());
+        assert_eq!(g.form, Form::Group(vec![]));
+    }
+
+    #[test]
+    fn intersect_empty_group() {
+        let g = Geometry::default().with_form(Form::Group(vec![]));
+        let r = Ray::new(Point::zero(), Vector::new(0.0, 0.0, 1.0));
+        assert!(g.hit(r).is_none());
+    }
+
+    #[test]
+    fn intersect_nonempty_group() {
+        let s1 = Geometry::default().with_form(Form::Sphere);
+        let s2 = Geometry::default()
+            .with_form(Form::Sphere)
+            .transformed(Matrix::translation(0.0, 0.0, -3.0));
+        let s3 = Geometry::default()
+            .with_form(Form::Sphere)
+            .transformed(Matrix::translation(5.0, 0.0, 0.0));
+        let g = Geometry::default().with_form(Form::Group(vec![s1.clone(), s2.clone(), s3]));
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let mut xs = g.hit(r).unwrap();
+        assert_eq!(xs.count(), 4);
+        assert_eq!(xs.pop().unwrap().object, s2);
+        assert_eq!(xs.pop().unwrap().object, s2);
+        assert_eq!(xs.pop().unwrap().object, s1);
+        assert_eq!(xs.pop().unwrap().object, s1);
+    }
+
+    #[test]
+    fn intersect_transformed_group() {
+        let s = Geometry::default()
+            .with_form(Form::Sphere)
+            .transformed(Matrix::translation(5.0, 0.0, 0.0));
+        let g = Geometry::default()
+            .with_form(Form::Group(vec![s]))
+            .transformed(Matrix::scaling(2.0, 2.0, 2.0));
+        let r = Ray::new(Point::new(10.0, 0.0, -10.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = g.hit(r).unwrap();
+        assert_eq!(xs.count(), 2);
+    }
+
+    #[test]
+    fn group_children_are_brought_into_world_space() {
+        let s = Geometry::default()
+            .with_form(Form::Sphere)
+            .transformed(Matrix::translation(5.0, 0.0, 0.0));
+        let g = Geometry::default()
+            .with_form(Form::Group(vec![s]))
+            .transformed(Matrix::scaling(2.0, 2.0, 2.0));
+        let r = Ray::new(Point::new(10.0, 0.0, -10.0), Vector::new(0.0, 0.0, 1.0));
+        let hit = g.hit(r).unwrap().closest().unwrap();
+        let expected = Matrix::scaling(2.0, 2.0, 2.0) * Matrix::translation(5.0, 0.0, 0.0);
+        assert_eq!(hit.object.transform, expected);
+        assert_eq!(hit.object.inverse, expected.inverse());
+    }
+
+    #[test]
+    fn normal_on_child_object() {
+        let s = Geometry::default()
+            .with_form(Form::Sphere)
+            .transformed(Matrix::translation(5.0, 0.0, 0.0));
+        let g2 = Geometry::default()
+            .with_form(Form::Group(vec![s]))
+            .transformed(Matrix::scaling(1.0, 2.0, 3.0));
+        let g1 = Geometry::default()
+            .with_form(Form::Group(vec![g2]))
+            .transformed(Matrix::rotation_y(std::f64::consts::PI / 2.0));
+        let r = Ray::new(Point::new(-10.0, 0.0, -5.0), Vector::new(1.0, 0.0, 0.0));
+        let hit = g1.hit(r).unwrap().closest().unwrap();
+        let n = hit
+            .object
+            .normal_at(Point::new(1.7321, 1.1547, -5.5774))
+            .unwrap();
+        assert_eq!(n, Vector::new(0.2857, 0.42854, -0.85716));
+    }
+
+    #[test]
+    fn group_has_no_normal() {
+        let g = Geometry::default().with_form(Form::Group(vec![]));
+        assert!(g.normal_at(Point::zero()).is_none());
     }
 }
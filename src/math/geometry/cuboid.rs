@@ -0,0 +1,267 @@
+use crate::{
+    math::{Form, Geometry, Hittable, Point, Vector, EPSILON},
+    world::{Intersection, Intersections, Ray},
+};
+
+/// an axis-aligned box between the corners `min` and `max`. a box that is flat along
+/// an axis (where `min` and `max` agree) behaves as a thin slab.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Cuboid {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl Cuboid {
+    pub fn new(min: Point, max: Point) -> Cuboid {
+        Cuboid { min, max }
+    }
+
+    /// the times at which the ray enters and leaves the slab between `min` and `max`
+    /// along one axis.
+    fn slab(origin: f64, direction: f64, min: f64, max: f64) -> (f64, f64) {
+        if direction.abs() < EPSILON {
+            // the ray runs parallel to the slab, so it is either always or never inside.
+            if min <= origin && origin <= max {
+                (f64::NEG_INFINITY, f64::INFINITY)
+            } else {
+                (f64::INFINITY, f64::NEG_INFINITY)
+            }
+        } else {
+            let t0 = (min - origin) / direction;
+            let t1 = (max - origin) / direction;
+            if t0 <= t1 {
+                (t0, t1)
+            } else {
+                (t1, t0)
+            }
+        }
+    }
+}
+
+impl Hittable for Cuboid {
+    fn hit(&self, object_space_ray: Ray) -> Option<Intersections> {
+        let mut enter = f64::NEG_INFINITY;
+        let mut exit = f64::INFINITY;
+
+        for axis in 0..3 {
+            let (t0, t1) = Cuboid::slab(
+                object_space_ray.origin[axis],
+                object_space_ray.direction[axis],
+                self.min[axis],
+                self.max[axis],
+            );
+            enter = enter.max(t0);
+            exit = exit.min(t1);
+        }
+
+        if exit < enter {
+            return None;
+        }
+
+        let hits = Intersections::with(
+            [enter, exit]
+                .iter()
+                .map(|&t| {
+                    Intersection::new(
+                        t,
+                        object_space_ray,
+                        Geometry::default().with_form(Form::Box {
+                            min: self.min,
+                            max: self.max,
+                        }),
+                    )
+                })
+                .collect(),
+        );
+
+        if hits.count() == 0 {
+            None
+        } else {
+            Some(hits)
+        }
+    }
+
+    fn normal_at(&self, object_space_point: Point) -> Option<Vector> {
+        // the point lies on whichever face it is closest to.
+        let mut normal = Vector::new(0.0, 0.0, 0.0);
+        let mut closest = f64::INFINITY;
+
+        for axis in 0..3 {
+            let to_min = (object_space_point[axis] - self.min[axis]).abs();
+            let to_max = (object_space_point[axis] - self.max[axis]).abs();
+
+            if to_min.min(to_max) < closest {
+                closest = to_min.min(to_max);
+                normal = Vector::new(0.0, 0.0, 0.0);
+                normal[axis] = if to_max <= to_min { 1.0 } else { -1.0 };
+            }
+        }
+
+        Some(normal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::{Matrix, Transformable};
+
+    fn table_top() -> Geometry {
+        Geometry::default().with_form(Form::Box {
+            min: Point::new(-1.0, 0.0, -1.5),
+            max: Point::new(1.0, 0.1, 1.5),
+        })
+    }
+
+    fn times(xs: Option<Intersections>) -> Vec<f64> {
+        let mut xs = xs.unwrap_or_default();
+        let mut result = vec![];
+        while let Some(x) = xs.pop() {
+            result.push(x.time);
+        }
+        result
+    }
+
+    #[test]
+    fn ray_hits_each_face() {
+        let b = table_top();
+        let cases = [
+            (
+                Point::new(5.0, 0.05, 0.5),
+                Vector::new(-1.0, 0.0, 0.0),
+                4.0,
+                6.0,
+            ),
+            (
+                Point::new(-5.0, 0.05, 0.5),
+                Vector::new(1.0, 0.0, 0.0),
+                4.0,
+                6.0,
+            ),
+            (
+                Point::new(0.5, 5.0, 0.0),
+                Vector::new(0.0, -1.0, 0.0),
+                4.9,
+                5.0,
+            ),
+            (
+                Point::new(0.5, -5.0, 0.0),
+                Vector::new(0.0, 1.0, 0.0),
+                5.0,
+                5.1,
+            ),
+            (
+                Point::new(0.0, 0.05, 5.0),
+                Vector::new(0.0, 0.0, -1.0),
+                3.5,
+                6.5,
+            ),
+            (
+                Point::new(0.0, 0.05, -5.0),
+                Vector::new(0.0, 0.0, 1.0),
+                3.5,
+                6.5,
+            ),
+        ];
+        for &(origin, direction, t1, t2) in cases.iter() {
+            let xs = times(b.hit(Ray::new(origin, direction)));
+            assert_eq!(xs.len(), 2);
+            assert!((xs[0] - t1).abs() < EPSILON);
+            assert!((xs[1] - t2).abs() < EPSILON);
+        }
+    }
+
+    #[test]
+    fn ray_inside_box() {
+        let r = Ray::new(Point::new(0.0, 0.05, 0.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(times(table_top().hit(r)), vec![1.5]);
+    }
+
+    #[test]
+    fn ray_misses_box() {
+        let b = table_top();
+        let rays = [
+            Ray::new(
+                Point::new(-2.0, 0.0, 0.0),
+                Vector::new(0.2673, 0.5345, 0.8018),
+            ),
+            Ray::new(Point::new(0.0, 2.0, -2.0), Vector::new(0.0, 0.0, 1.0)),
+            Ray::new(Point::new(2.0, 0.05, 2.0), Vector::new(0.0, 0.0, -1.0)),
+            Ray::new(Point::new(0.0, 0.05, 5.0), Vector::new(0.0, 0.0, 1.0)),
+        ];
+        for &r in rays.iter() {
+            assert!(b.hit(r).is_none());
+        }
+    }
+
+    #[test]
+    fn normals_on_each_face() {
+        let b = table_top();
+        let cases = [
+            (Point::new(1.0, 0.05, 0.2), Vector::new(1.0, 0.0, 0.0)),
+            (Point::new(-1.0, 0.02, -0.7), Vector::new(-1.0, 0.0, 0.0)),
+            (Point::new(0.3, 0.1, 0.4), Vector::new(0.0, 1.0, 0.0)),
+            (Point::new(-0.6, 0.0, 0.9), Vector::new(0.0, -1.0, 0.0)),
+            (Point::new(0.4, 0.05, 1.5), Vector::new(0.0, 0.0, 1.0)),
+            (Point::new(0.0, 0.07, -1.5), Vector::new(0.0, 0.0, -1.0)),
+        ];
+        for &(point, normal) in cases.iter() {
+            assert_eq!(b.normal_at(point).unwrap(), normal);
+        }
+    }
+
+    #[test]
+    fn degenerate_box_is_thin_slab() {
+        let b = Geometry::default().with_form(Form::Box {
+            min: Point::new(-1.0, 0.0, -1.0),
+            max: Point::new(1.0, 0.0, 1.0),
+        });
+        let down = Ray::new(Point::new(0.5, 2.0, 0.5), Vector::new(0.0, -1.0, 0.0));
+        let xs = times(b.hit(down));
+        assert_eq!(xs, vec![2.0, 2.0]);
+        assert_eq!(
+            b.normal_at(Point::new(0.5, 0.0, 0.5)).unwrap(),
+            Vector::new(0.0, 1.0, 0.0)
+        );
+
+        // a ray in the plane of the slab, parallel to it, still finds it.
+        let along = Ray::new(Point::new(-5.0, 0.0, 0.0), Vector::new(1.0, 0.0, 0.0));
+        let xs = times(b.hit(along));
+        assert_eq!(xs, vec![4.0, 6.0]);
+        assert!(xs.iter().all(|t| t.is_finite()));
+
+        let beside = Ray::new(Point::new(-5.0, 0.5, 0.0), Vector::new(1.0, 0.0, 0.0));
+        assert!(b.hit(beside).is_none());
+    }
+
+    #[test]
+    fn box_matches_transformed_unit_box() {
+        let unit = Geometry::default()
+            .with_form(Form::Box {
+                min: Point::new(-1.0, -1.0, -1.0),
+                max: Point::new(1.0, 1.0, 1.0),
+            })
+            .transformed(Matrix::translation(0.0, 0.05, 0.0) * Matrix::scaling(1.0, 0.05, 1.5));
+        let direct = table_top();
+        let rays = [
+            Ray::new(Point::new(5.0, 0.05, 0.5), Vector::new(-1.0, 0.0, 0.0)),
+            Ray::new(Point::new(0.3, 4.0, -3.0), Vector::new(0.0, -0.6, 0.8)),
+            Ray::new(Point::new(-3.0, 1.0, 0.2), Vector::new(0.9, -0.3, 0.1)),
+        ];
+        for &r in rays.iter() {
+            let a = times(unit.hit(r));
+            let b = times(direct.hit(r));
+            assert_eq!(a.len(), b.len());
+            for (a, b) in a.iter().zip(b.iter()) {
+                assert!((a - b).abs() < EPSILON);
+            }
+            if let (Some(a), Some(b)) = (unit.hit(r), direct.hit(r)) {
+                let p = r.at(b.closest().unwrap().time);
+                assert_eq!(
+                    a.closest().unwrap().object.normal_at(p),
+                    direct.normal_at(p)
+                );
+            }
+        }
+    }
+}
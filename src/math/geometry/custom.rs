@@ -0,0 +1,188 @@
+use std::{
+    fmt::{self, Debug, Formatter},
+    sync::Arc,
+};
+
+use crate::{
+    math::{Hittable, Point, Vector},
+    world::{Intersections, Ray},
+};
+
+/// a shape supplied from outside of this crate. it only has to find where rays hit
+/// it and what its normals are in object space; wrapping it in a `Geometry` with
+/// `Form::Custom` takes care of transforms, materials, and shading.
+///
+/// only the times of the intersections returned by `hit` are used, so the object
+/// given to each intersection doesn't matter. clones share the same shape, and two
+/// custom shapes are only equal if they share it.
+///
+/// ```
+/// use ray_tracer_challenge::{
+///     math::{geometry::Custom, Form, Geometry, Hittable, Matrix, Point, Transformable, Vector},
+///     world::{Intersection, Intersections, Ray},
+/// };
+///
+/// /// spheres of radius 0.5 centered on every point (x, 0, z) with x and z integers
+/// /// that add up to an even number, like the black squares of a checkerboard.
+/// struct SphereCheckerboard;
+///
+/// impl SphereCheckerboard {
+///     fn hit_sphere(ray: Ray, center: Point) -> Option<f64> {
+///         let displacement = ray.origin - center;
+///         let a = ray.direction.dot(&ray.direction);
+///         let b = 2.0 * ray.direction.dot(&displacement);
+///         let c = displacement.dot(&displacement) - 0.25;
+///         let discriminant = b * b - 4.0 * a * c;
+///         if discriminant < 0.0 {
+///             return None;
+///         }
+///         [(-b - discriminant.sqrt()) / (2.0 * a), (-b + discriminant.sqrt()) / (2.0 * a)]
+///             .iter()
+///             .cloned()
+///             .find(|&t| t > 0.0)
+///     }
+/// }
+///
+/// impl Hittable for SphereCheckerboard {
+///     fn hit(&self, ray: Ray) -> Option<Intersections> {
+///         // every sphere lies between y = -0.5 and y = 0.5, so only look there, and
+///         // give up on rays that skim along it for too long.
+///         let (enter, exit) = if ray.direction[1].abs() < 1e-9 {
+///             if 0.5 < ray.origin[1].abs() {
+///                 return None;
+///             }
+///             (0.0, 100.0)
+///         } else {
+///             let t0 = (-0.5 - ray.origin[1]) / ray.direction[1];
+///             let t1 = (0.5 - ray.origin[1]) / ray.direction[1];
+///             (t0.min(t1).max(0.0), t0.max(t1).min(100.0))
+///         };
+///
+///         // walk along the ray, checking the spheres around each cell it passes.
+///         let mut closest: Option<f64> = None;
+///         let mut t = enter;
+///         while t <= exit + 0.5 && closest.is_none() {
+///             let p = ray.at(t);
+///             for i in -1..=1 {
+///                 for j in -1..=1 {
+///                     let (x, z) = (p[0].round() + i as f64, p[2].round() + j as f64);
+///                     if (x + z).rem_euclid(2.0) == 0.0 {
+///                         if let Some(hit) = Self::hit_sphere(ray, Point::new(x, 0.0, z)) {
+///                             closest = Some(closest.map_or(hit, |c| c.min(hit)));
+///                         }
+///                     }
+///                 }
+///             }
+///             t += 0.5;
+///         }
+///
+///         closest.map(|t| Intersections::with(vec![Intersection::new(t, ray, Geometry::default())]))
+///     }
+///
+///     fn normal_at(&self, point: Point) -> Option<Vector> {
+///         Some(point - Point::new(point[0].round(), 0.0, point[2].round()))
+///     }
+/// }
+///
+/// let board = Geometry::default()
+///     .with_form(Form::Custom(Custom::new(SphereCheckerboard)))
+///     .transformed(Matrix::translation(0.0, 1.0, 0.0));
+///
+/// // straight down onto the sphere centered on (2, 1, 4).
+/// let down = Vector::new(0.0, -1.0, 0.0);
+/// let xs = board.hit(Ray::new(Point::new(2.0, 5.0, 4.0), down)).unwrap();
+/// let hit = xs.closest().unwrap();
+/// assert!((hit.time - 3.5).abs() < 1e-9);
+/// assert_eq!(board.normal_at(Point::new(2.0, 1.5, 4.0)), Some(Vector::new(0.0, 1.0, 0.0)));
+///
+/// // straight down between spheres.
+/// assert!(board.hit(Ray::new(Point::new(1.0, 5.0, 0.0), down)).is_none());
+/// ```
+#[derive(Clone)]
+pub struct Custom(pub Arc<dyn Hittable + Send + Sync>);
+
+impl Custom {
+    pub fn new<H: Hittable + Send + Sync + 'static>(shape: H) -> Custom {
+        Custom(Arc::new(shape))
+    }
+}
+
+impl Debug for Custom {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Custom({:p})", Arc::as_ptr(&self.0))
+    }
+}
+
+impl PartialEq for Custom {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Hittable for Custom {
+    fn hit(&self, object_space_ray: Ray) -> Option<Intersections> {
+        self.0.hit(object_space_ray)
+    }
+
+    fn normal_at(&self, object_space_point: Point) -> Option<Vector> {
+        self.0.normal_at(object_space_point)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        math::{Form, Geometry, Matrix, Transformable},
+        world::Intersection,
+    };
+
+    /// the plane z = 0, which reports hits with whatever it's asked to.
+    struct Wall;
+
+    impl Hittable for Wall {
+        fn hit(&self, object_space_ray: Ray) -> Option<Intersections> {
+            let t = -object_space_ray.origin[2] / object_space_ray.direction[2];
+            Some(Intersections::with(vec![Intersection::new(
+                t,
+                object_space_ray,
+                Geometry::default(),
+            )]))
+        }
+
+        fn normal_at(&self, _object_space_point: Point) -> Option<Vector> {
+            Some(Vector::new(0.0, 0.0, -1.0))
+        }
+    }
+
+    #[test]
+    fn custom_shape_is_transformed() {
+        let wall = Geometry::default()
+            .with_form(Form::Custom(Custom::new(Wall)))
+            .transformed(Matrix::translation(0.0, 0.0, 3.0));
+        let r = Ray::new(Point::zero(), Vector::new(0.0, 0.0, 1.0));
+        let x = wall.hit(r).unwrap().closest().unwrap();
+        assert_eq!(x.time, 3.0);
+        // the intersection refers to the wall itself, not the object it was given.
+        assert_eq!(x.object, wall);
+    }
+
+    #[test]
+    fn custom_normals_are_transformed() {
+        let wall = Geometry::default()
+            .with_form(Form::Custom(Custom::new(Wall)))
+            .transformed(Matrix::rotation_y(std::f64::consts::PI));
+        assert_eq!(
+            wall.normal_at(Point::zero()),
+            Some(Vector::new(0.0, 0.0, 1.0))
+        );
+    }
+
+    #[test]
+    fn custom_shapes_are_equal_by_identity() {
+        let a = Custom::new(Wall);
+        let b = Custom::new(Wall);
+        assert_eq!(a, a.clone());
+        assert_ne!(a, b);
+    }
+}
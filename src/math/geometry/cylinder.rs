@@ -0,0 +1,244 @@
+use crate::{
+    math::{Form, Geometry, Hittable, Point, Vector, EPSILON},
+    world::{Intersection, Intersections, Ray},
+};
+
+/// a cylinder of radius 1 centered on the y axis, truncated between `minimum` and
+/// `maximum` (both exclusive). a `closed` cylinder is capped at both ends.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Cylinder {
+    pub minimum: f64,
+    pub maximum: f64,
+    pub closed: bool,
+}
+
+impl Cylinder {
+    pub fn new(minimum: f64, maximum: f64, closed: bool) -> Cylinder {
+        Cylinder {
+            minimum,
+            maximum,
+            closed,
+        }
+    }
+
+    fn intersection(&self, time: f64, object_space_ray: Ray) -> Intersection {
+        Intersection::new(
+            time,
+            object_space_ray,
+            Geometry::default().with_form(Form::Cylinder(*self)),
+        )
+    }
+
+    /// checks to see if the intersection at `time` is within the radius of the cylinder.
+    fn within_cap(object_space_ray: Ray, time: f64) -> bool {
+        let x = object_space_ray.origin[0] + time * object_space_ray.direction[0];
+        let z = object_space_ray.origin[2] + time * object_space_ray.direction[2];
+        (x * x + z * z) <= 1.0
+    }
+
+    fn hit_caps(&self, object_space_ray: Ray, hits: &mut Intersections) {
+        // caps only matter if the cylinder is closed, and might possibly be
+        // intersected by the ray.
+        if !self.closed || object_space_ray.direction[1].abs() < EPSILON {
+            return;
+        }
+
+        for &y in [self.minimum, self.maximum].iter() {
+            let t = (y - object_space_ray.origin[1]) / object_space_ray.direction[1];
+            if Cylinder::within_cap(object_space_ray, t) {
+                hits.insert(self.intersection(t, object_space_ray));
+            }
+        }
+    }
+}
+
+impl Default for Cylinder {
+    fn default() -> Cylinder {
+        Cylinder::new(f64::NEG_INFINITY, f64::INFINITY, false)
+    }
+}
+
+impl Hittable for Cylinder {
+    fn hit(&self, object_space_ray: Ray) -> Option<Intersections> {
+        let mut hits = Intersections::default();
+
+        let a = object_space_ray.direction[0] * object_space_ray.direction[0]
+            + object_space_ray.direction[2] * object_space_ray.direction[2];
+
+        // a ray parallel to the y axis can only hit the caps.
+        if EPSILON <= a.abs() {
+            let b = 2.0 * object_space_ray.origin[0] * object_space_ray.direction[0]
+                + 2.0 * object_space_ray.origin[2] * object_space_ray.direction[2];
+            let c = object_space_ray.origin[0] * object_space_ray.origin[0]
+                + object_space_ray.origin[2] * object_space_ray.origin[2]
+                - 1.0;
+            let discriminant = (b * b) - (4.0 * a * c);
+
+            if discriminant < 0.0 {
+                return None;
+            }
+
+            let t0 = (-b - discriminant.sqrt()) / (2.0 * a);
+            let t1 = (-b + discriminant.sqrt()) / (2.0 * a);
+
+            for &t in [t0, t1].iter() {
+                let y = object_space_ray.origin[1] + t * object_space_ray.direction[1];
+                if self.minimum < y && y < self.maximum {
+                    hits.insert(self.intersection(t, object_space_ray));
+                }
+            }
+        }
+
+        self.hit_caps(object_space_ray, &mut hits);
+
+        if hits.count() == 0 {
+            None
+        } else {
+            Some(hits)
+        }
+    }
+
+    fn normal_at(&self, object_space_point: Point) -> Option<Vector> {
+        let distance = object_space_point[0] * object_space_point[0]
+            + object_space_point[2] * object_space_point[2];
+
+        if distance < 1.0 && self.maximum - EPSILON <= object_space_point[1] {
+            Some(Vector::new(0.0, 1.0, 0.0))
+        } else if distance < 1.0 && object_space_point[1] <= self.minimum + EPSILON {
+            Some(Vector::new(0.0, -1.0, 0.0))
+        } else {
+            Some(Vector::new(
+                object_space_point[0],
+                0.0,
+                object_space_point[2],
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cylinder(minimum: f64, maximum: f64, closed: bool) -> Geometry {
+        Geometry::default().with_form(Form::Cylinder(Cylinder::new(minimum, maximum, closed)))
+    }
+
+    #[test]
+    fn ray_misses_cylinder() {
+        let c = Geometry::default().with_form(Form::Cylinder(Cylinder::default()));
+        let rays = [
+            (Point::new(1.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0)),
+            (Point::zero(), Vector::new(0.0, 1.0, 0.0)),
+            (Point::new(0.0, 0.0, -5.0), Vector::new(1.0, 1.0, 1.0)),
+        ];
+        for &(origin, direction) in rays.iter() {
+            let r = Ray::new(origin, direction.normalized());
+            assert!(c.hit(r).is_none());
+        }
+    }
+
+    #[test]
+    fn ray_strikes_cylinder() {
+        let c = Geometry::default().with_form(Form::Cylinder(Cylinder::default()));
+        let rays = [
+            (
+                Point::new(1.0, 0.0, -5.0),
+                Vector::new(0.0, 0.0, 1.0),
+                5.0,
+                5.0,
+            ),
+            (
+                Point::new(0.0, 0.0, -5.0),
+                Vector::new(0.0, 0.0, 1.0),
+                4.0,
+                6.0,
+            ),
+            (
+                Point::new(0.5, 0.0, -5.0),
+                Vector::new(0.1, 1.0, 1.0),
+                6.80798,
+                7.08872,
+            ),
+        ];
+        for &(origin, direction, t0, t1) in rays.iter() {
+            let r = Ray::new(origin, direction.normalized());
+            let mut xs = c.hit(r).unwrap();
+            assert_eq!(xs.count(), 2);
+            assert!((xs.pop().unwrap().time - t0).abs() < EPSILON);
+            assert!((xs.pop().unwrap().time - t1).abs() < EPSILON);
+        }
+    }
+
+    #[test]
+    fn normal_on_cylinder() {
+        let c = Geometry::default().with_form(Form::Cylinder(Cylinder::default()));
+        let normals = [
+            (Point::new(1.0, 0.0, 0.0), Vector::new(1.0, 0.0, 0.0)),
+            (Point::new(0.0, 5.0, -1.0), Vector::new(0.0, 0.0, -1.0)),
+            (Point::new(0.0, -2.0, 1.0), Vector::new(0.0, 0.0, 1.0)),
+            (Point::new(-1.0, 1.0, 0.0), Vector::new(-1.0, 0.0, 0.0)),
+        ];
+        for &(point, normal) in normals.iter() {
+            assert_eq!(c.normal_at(point).unwrap(), normal);
+        }
+    }
+
+    #[test]
+    fn default_cylinder_is_infinite() {
+        let c = Cylinder::default();
+        assert_eq!(c.minimum, f64::NEG_INFINITY);
+        assert_eq!(c.maximum, f64::INFINITY);
+        assert!(!c.closed);
+    }
+
+    #[test]
+    fn intersect_truncated_cylinder() {
+        let c = cylinder(1.0, 2.0, false);
+        let rays = [
+            (Point::new(0.0, 1.5, 0.0), Vector::new(0.1, 1.0, 0.0), 0),
+            (Point::new(0.0, 3.0, -5.0), Vector::new(0.0, 0.0, 1.0), 0),
+            (Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0), 0),
+            (Point::new(0.0, 2.0, -5.0), Vector::new(0.0, 0.0, 1.0), 0),
+            (Point::new(0.0, 1.0, -5.0), Vector::new(0.0, 0.0, 1.0), 0),
+            (Point::new(0.0, 1.5, -2.0), Vector::new(0.0, 0.0, 1.0), 2),
+        ];
+        for &(origin, direction, count) in rays.iter() {
+            let r = Ray::new(origin, direction.normalized());
+            assert_eq!(c.hit(r).map_or(0, |xs| xs.count()), count);
+        }
+    }
+
+    #[test]
+    fn intersect_caps_of_closed_cylinder() {
+        let c = cylinder(1.0, 2.0, true);
+        let rays = [
+            (Point::new(0.0, 3.0, 0.0), Vector::new(0.0, -1.0, 0.0), 2),
+            (Point::new(0.0, 3.0, -2.0), Vector::new(0.0, -1.0, 2.0), 2),
+            (Point::new(0.0, 4.0, -2.0), Vector::new(0.0, -1.0, 1.0), 2),
+            (Point::new(0.0, 0.0, -2.0), Vector::new(0.0, 1.0, 2.0), 2),
+            (Point::new(0.0, -1.0, -2.0), Vector::new(0.0, 1.0, 1.0), 2),
+        ];
+        for &(origin, direction, count) in rays.iter() {
+            let r = Ray::new(origin, direction.normalized());
+            assert_eq!(c.hit(r).map_or(0, |xs| xs.count()), count);
+        }
+    }
+
+    #[test]
+    fn normal_on_caps() {
+        let c = cylinder(1.0, 2.0, true);
+        let normals = [
+            (Point::new(0.0, 1.0, 0.0), Vector::new(0.0, -1.0, 0.0)),
+            (Point::new(0.5, 1.0, 0.0), Vector::new(0.0, -1.0, 0.0)),
+            (Point::new(0.0, 1.0, 0.5), Vector::new(0.0, -1.0, 0.0)),
+            (Point::new(0.0, 2.0, 0.0), Vector::new(0.0, 1.0, 0.0)),
+            (Point::new(0.5, 2.0, 0.0), Vector::new(0.0, 1.0, 0.0)),
+            (Point::new(0.0, 2.0, 0.5), Vector::new(0.0, 1.0, 0.0)),
+        ];
+        for &(point, normal) in normals.iter() {
+            assert_eq!(c.normal_at(point).unwrap(), normal);
+        }
+    }
+}
@@ -0,0 +1,451 @@
+use std::{
+    fmt::{self, Debug, Formatter},
+    sync::Arc,
+};
+
+use crate::{
+    math::{BoundingBox, Form, Geometry, Hittable, Point, Vector, EPSILON},
+    world::{Intersection, Intersections, Ray},
+    RayTracerError,
+};
+
+/// where a heightfield gets its heights from.
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "SerializedHeightSource")
+)]
+pub enum HeightSource {
+    /// heights are computed by calling a function of x and z. this can't be
+    /// serialized, since a function is only known by its code.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Function(Arc<dyn Fn(f64, f64) -> f64 + Send + Sync>),
+    /// heights are sampled from a grid of `columns` by `rows` values spread evenly over
+    /// the rectangle, with x running along each row. heights between samples are
+    /// interpolated bilinearly.
+    Grid {
+        columns: usize,
+        rows: usize,
+        samples: Arc<Vec<f64>>,
+    },
+}
+
+/// the height sources that can be serialized, which are only usable once the number
+/// of samples is checked against the size of the grid.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+enum SerializedHeightSource {
+    Grid {
+        columns: usize,
+        rows: usize,
+        samples: Arc<Vec<f64>>,
+    },
+}
+
+#[cfg(feature = "serde")]
+impl std::convert::TryFrom<SerializedHeightSource> for HeightSource {
+    type Error = RayTracerError;
+
+    fn try_from(serialized: SerializedHeightSource) -> Result<HeightSource, RayTracerError> {
+        let SerializedHeightSource::Grid {
+            columns,
+            rows,
+            samples,
+        } = serialized;
+        check_grid(columns, rows, samples.len())?;
+        Ok(HeightSource::Grid {
+            columns,
+            rows,
+            samples,
+        })
+    }
+}
+
+/// checks that `samples` heights make a grid of `columns` by `rows`, which needs at
+/// least two of each to interpolate between.
+fn check_grid(columns: usize, rows: usize, samples: usize) -> Result<(), RayTracerError> {
+    if columns < 2 || rows < 2 || Some(samples) != columns.checked_mul(rows) {
+        return Err(RayTracerError::InvalidGrid {
+            columns,
+            rows,
+            samples,
+        });
+    }
+    Ok(())
+}
+
+impl Debug for HeightSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            HeightSource::Function(_) => write!(f, "Function(..)"),
+            HeightSource::Grid { columns, rows, .. } => {
+                write!(f, "Grid {{ columns: {}, rows: {} }}", columns, rows)
+            }
+        }
+    }
+}
+
+/// two height sources are only equal if they share the same function or samples.
+impl PartialEq for HeightSource {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (HeightSource::Function(a), HeightSource::Function(b)) => Arc::ptr_eq(a, b),
+            (HeightSource::Grid { samples: a, .. }, HeightSource::Grid { samples: b, .. }) => {
+                Arc::ptr_eq(a, b)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// the surface `y = f(x, z)` over the x/z rectangle of `bounds`, whose heights are
+/// expected to stay within the y range of `bounds`. rays are marched through the box
+/// `step` units at a time, and the first crossing of the surface is then refined by
+/// bisection, so features narrower than the step may be missed.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "SerializedHeightfield")
+)]
+pub struct Heightfield {
+    pub bounds: BoundingBox,
+    step: f64,
+    pub source: HeightSource,
+}
+
+/// a heightfield as it is saved, which is only usable once its step is checked.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct SerializedHeightfield {
+    bounds: BoundingBox,
+    step: f64,
+    source: HeightSource,
+}
+
+#[cfg(feature = "serde")]
+impl std::convert::TryFrom<SerializedHeightfield> for Heightfield {
+    type Error = RayTracerError;
+
+    fn try_from(serialized: SerializedHeightfield) -> Result<Heightfield, RayTracerError> {
+        Heightfield::new(serialized.bounds, serialized.source).with_step(serialized.step)
+    }
+}
+
+impl Heightfield {
+    pub fn new(bounds: BoundingBox, source: HeightSource) -> Heightfield {
+        Heightfield {
+            bounds,
+            step: 0.01,
+            source,
+        }
+    }
+
+    pub fn from_fn<F: Fn(f64, f64) -> f64 + Send + Sync + 'static>(
+        bounds: BoundingBox,
+        f: F,
+    ) -> Heightfield {
+        Heightfield::new(bounds, HeightSource::Function(Arc::new(f)))
+    }
+
+    /// `samples` holds `rows` rows of `columns` heights each, starting at `min_z`. the
+    /// height range of the bounds is taken from the samples. there have to be at least
+    /// 2 by 2 samples, and exactly `columns * rows` of them.
+    pub fn from_grid(
+        (min_x, min_z): (f64, f64),
+        (max_x, max_z): (f64, f64),
+        columns: usize,
+        rows: usize,
+        samples: Vec<f64>,
+    ) -> Result<Heightfield, RayTracerError> {
+        check_grid(columns, rows, samples.len())?;
+        let min_y = samples.iter().cloned().fold(f64::INFINITY, f64::min);
+        let max_y = samples.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        Ok(Heightfield::new(
+            BoundingBox::new(
+                Point::new(min_x, min_y, min_z),
+                Point::new(max_x, max_y, max_z),
+            ),
+            HeightSource::Grid {
+                columns,
+                rows,
+                samples: Arc::new(samples),
+            },
+        ))
+    }
+
+    /// marches rays `step` units at a time, which has to be positive and finite.
+    pub fn with_step(self, step: f64) -> Result<Heightfield, RayTracerError> {
+        if !(step.is_finite() && 0.0 < step) {
+            return Err(RayTracerError::InvalidStep(step));
+        }
+        Ok(Heightfield { step, ..self })
+    }
+
+    /// how far rays are marched at a time.
+    pub fn step(&self) -> f64 {
+        self.step
+    }
+
+    pub fn height_at(&self, x: f64, z: f64) -> f64 {
+        match &self.source {
+            HeightSource::Function(f) => f(x, z),
+            HeightSource::Grid {
+                columns,
+                rows,
+                samples,
+            } => {
+                // find the cell containing the point, and how far across it the point is.
+                let (min, max) = (self.bounds.min, self.bounds.max);
+                let u = (x - min[0]) / (max[0] - min[0]) * (*columns - 1) as f64;
+                let v = (z - min[2]) / (max[2] - min[2]) * (*rows - 1) as f64;
+                let column = (u.floor().max(0.0) as usize).min(*columns - 2);
+                let row = (v.floor().max(0.0) as usize).min(*rows - 2);
+                let (s, t) = (u - column as f64, v - row as f64);
+
+                let at = |column: usize, row: usize| samples[row * *columns + column];
+                let near = at(column, row) * (1.0 - s) + at(column + 1, row) * s;
+                let far = at(column, row + 1) * (1.0 - s) + at(column + 1, row + 1) * s;
+                near * (1.0 - t) + far * t
+            }
+        }
+    }
+
+    /// how far above the surface the ray is at `time`.
+    fn clearance(&self, object_space_ray: Ray, time: f64) -> f64 {
+        let point = object_space_ray.at(time);
+        point[1] - self.height_at(point[0], point[2])
+    }
+
+    /// the times at which the ray enters and leaves the bounds, if it passes through
+    /// them at all in front of its origin.
+    fn span(&self, object_space_ray: Ray) -> Option<(f64, f64)> {
+        let mut enter: f64 = 0.0;
+        let mut exit = f64::INFINITY;
+
+        for axis in 0..3 {
+            let origin = object_space_ray.origin[axis];
+            let direction = object_space_ray.direction[axis];
+            let (min, max) = (self.bounds.min[axis], self.bounds.max[axis]);
+
+            if direction.abs() < EPSILON {
+                if origin < min || max < origin {
+                    return None;
+                }
+            } else {
+                let t0 = (min - origin) / direction;
+                let t1 = (max - origin) / direction;
+                enter = enter.max(t0.min(t1));
+                exit = exit.min(t0.max(t1));
+            }
+        }
+
+        if enter <= exit && exit.is_finite() {
+            Some((enter, exit))
+        } else {
+            None
+        }
+    }
+}
+
+impl Hittable for Heightfield {
+    fn hit(&self, object_space_ray: Ray) -> Option<Intersections> {
+        let (enter, exit) = self.span(object_space_ray)?;
+        let dt = self.step / object_space_ray.direction.magnitude();
+
+        let mut before = enter;
+        let mut side = self.clearance(object_space_ray, before).signum();
+
+        while before < exit {
+            let after = (before + dt).min(exit);
+            let clearance = self.clearance(object_space_ray, after);
+
+            if clearance == 0.0 || clearance.signum() != side {
+                // the ray crossed the surface during this step, so narrow it down.
+                let (mut low, mut high) = (before, after);
+                for _ in 0..64 {
+                    let middle = (low + high) / 2.0;
+                    if self.clearance(object_space_ray, middle).signum() == side {
+                        low = middle;
+                    } else {
+                        high = middle;
+                    }
+                }
+
+                return Some(Intersections::with(vec![Intersection::new(
+                    (low + high) / 2.0,
+                    object_space_ray,
+                    Geometry::default().with_form(Form::Heightfield(self.clone())),
+                )]));
+            }
+
+            before = after;
+            side = clearance.signum();
+        }
+
+        None
+    }
+
+    fn normal_at(&self, object_space_point: Point) -> Option<Vector> {
+        let (x, z) = (object_space_point[0], object_space_point[2]);
+        let slope_x =
+            (self.height_at(x + EPSILON, z) - self.height_at(x - EPSILON, z)) / (2.0 * EPSILON);
+        let slope_z =
+            (self.height_at(x, z + EPSILON) - self.height_at(x, z - EPSILON)) / (2.0 * EPSILON);
+        Some(Vector::new(-slope_x, 1.0, -slope_z))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::useless_conversion)]
+mod tests {
+    use super::*;
+    use crate::{
+        math::{Matrix, Transformable},
+        world::{light, Camera, Color, Light, View, World},
+    };
+    use std::f64::consts;
+
+    fn sine_terrain() -> Heightfield {
+        Heightfield::from_fn(
+            BoundingBox::new(Point::new(-5.0, -0.5, -5.0), Point::new(5.0, 0.5, 5.0)),
+            |x, z| 0.5 * x.sin() * z.cos(),
+        )
+    }
+
+    fn time_of(geometry: &Geometry, ray: Ray) -> Option<f64> {
+        geometry
+            .hit(ray)
+            .and_then(|xs| xs.closest())
+            .map(|x| x.time)
+    }
+
+    #[test]
+    fn vertical_rays_hit_sine_terrain() {
+        let g = Geometry::default().with_form(Form::Heightfield(sine_terrain()));
+        for &(x, z) in [(0.0, 0.0), (1.0, 0.5), (-2.0, 3.0), (4.5, -4.5)].iter() {
+            let r = Ray::new(Point::new(x, 5.0, z), Vector::new(0.0, -1.0, 0.0));
+            let expected = 5.0 - 0.5 * f64::sin(x) * f64::cos(z);
+            assert!((time_of(&g, r).unwrap() - expected).abs() < EPSILON);
+        }
+    }
+
+    #[test]
+    fn slanted_ray_hits_sine_terrain() {
+        let g = Geometry::default().with_form(Form::Heightfield(sine_terrain()));
+        let r = Ray::new(
+            Point::new(-4.0, 2.0, 0.0),
+            Vector::new(1.0, -0.5, 0.0).normalized(),
+        );
+        let t = time_of(&g, r).unwrap();
+        let p = r.at(t);
+        assert!((p[1] - 0.5 * p[0].sin()).abs() < EPSILON);
+        // the ray is above the terrain everywhere before this point.
+        let mut s = 0.0;
+        while s < t - 0.01 {
+            let q = r.at(s);
+            assert!(q[1] > 0.5 * q[0].sin());
+            s += 0.01;
+        }
+    }
+
+    #[test]
+    fn rays_outside_rectangle_miss() {
+        let g = Geometry::default().with_form(Form::Heightfield(sine_terrain()));
+        let beside = Ray::new(Point::new(6.0, 5.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+        let away = Ray::new(Point::new(0.0, 0.0, -6.0), Vector::new(0.0, 0.0, -1.0));
+        let above = Ray::new(Point::new(0.0, 5.0, -6.0), Vector::new(0.0, 0.0, 1.0));
+        assert!(g.hit(beside).is_none());
+        assert!(g.hit(away).is_none());
+        assert!(g.hit(above).is_none());
+    }
+
+    #[test]
+    fn scaled_heightfield_covers_larger_area() {
+        let small = Heightfield::from_fn(
+            BoundingBox::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0)),
+            |x, _| x,
+        );
+        let g = Geometry::default()
+            .with_form(Form::Heightfield(small))
+            .transformed(Matrix::scaling(10.0, 1.0, 10.0));
+        let r = Ray::new(Point::new(5.0, 5.0, 5.0), Vector::new(0.0, -1.0, 0.0));
+        assert!((time_of(&g, r).unwrap() - 4.5).abs() < EPSILON);
+        let outside = Ray::new(Point::new(11.0, 5.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+        assert!(g.hit(outside).is_none());
+    }
+
+    #[test]
+    fn grid_interpolates_bilinearly() {
+        let h =
+            Heightfield::from_grid((0.0, 0.0), (2.0, 2.0), 2, 2, vec![0.0, 1.0, 1.0, 2.0]).unwrap();
+        assert_eq!(h.height_at(0.0, 0.0), 0.0);
+        assert_eq!(h.height_at(2.0, 0.0), 1.0);
+        assert_eq!(h.height_at(2.0, 2.0), 2.0);
+        assert_eq!(h.height_at(1.0, 1.0), 1.0);
+        assert_eq!(h.height_at(0.5, 1.0), 0.75);
+        assert_eq!(h.bounds.min[1], 0.0);
+        assert_eq!(h.bounds.max[1], 2.0);
+    }
+
+    #[test]
+    fn grids_of_the_wrong_size_are_errors() {
+        for &(columns, rows, samples) in [(1, 2, 2), (2, 1, 2), (2, 2, 3), (3, 2, 7)].iter() {
+            let result =
+                Heightfield::from_grid((0.0, 0.0), (1.0, 1.0), columns, rows, vec![0.0; samples]);
+            assert!(matches!(
+                result,
+                Err(RayTracerError::InvalidGrid { columns: c, rows: r, samples: s })
+                    if (c, r, s) == (columns, rows, samples)
+            ));
+        }
+    }
+
+    #[test]
+    fn steps_must_be_positive_and_finite() {
+        for &step in [0.0, -0.1, f64::NAN, f64::INFINITY].iter() {
+            assert!(matches!(
+                sine_terrain().with_step(step),
+                Err(RayTracerError::InvalidStep(_))
+            ));
+        }
+        let coarse = sine_terrain().with_step(0.5).unwrap();
+        assert_eq!(coarse.step(), 0.5);
+        let g = Geometry::default().with_form(Form::Heightfield(coarse));
+        let r = Ray::new(Point::new(1.0, 5.0, 0.5), Vector::new(0.0, -1.0, 0.0));
+        let expected = 5.0 - 0.5 * f64::sin(1.0) * f64::cos(0.5);
+        assert!((time_of(&g, r).unwrap() - expected).abs() < EPSILON);
+    }
+
+    #[test]
+    fn normal_follows_slope() {
+        let g = Geometry::default().with_form(Form::Heightfield(Heightfield::from_fn(
+            BoundingBox::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0)),
+            |x, _| x,
+        )));
+        let n = g.normal_at(Point::zero()).unwrap();
+        let s = f64::from(2.0).sqrt() / 2.0;
+        assert_eq!(n, Vector::new(-s, s, 0.0));
+    }
+
+    #[test]
+    fn render_sine_terrain() {
+        let terrain = Geometry::default().with_form(Form::Heightfield(sine_terrain()));
+        let sun = Light::point(light::Point::new(
+            Point::new(-10.0, 10.0, -10.0),
+            Color::new(1.0, 1.0, 1.0),
+        ));
+        let w = World::new(vec![terrain], vec![sun]);
+        let mut c = Camera::new(9, 9, consts::PI / 3.0);
+        c.set_view(View::transformed(
+            Point::new(0.0, 6.0, -6.0),
+            Point::zero(),
+            Vector::new(0.0, 1.0, 0.0),
+        ));
+        let image = c.render(&w);
+        // the terrain fills the middle of the image, and the sky above it is empty.
+        assert_ne!(image[(4, 4)], Color::new(0.0, 0.0, 0.0));
+        assert_eq!(image[(4, 0)], Color::new(0.0, 0.0, 0.0));
+    }
+}
@@ -3,6 +3,7 @@ use crate::{
     world::{Intersection, Intersections, Ray},
 };
 
+#[derive(Default)]
 pub struct Plane {}
 
 impl Plane {
@@ -12,7 +13,7 @@ impl Plane {
 }
 
 impl Hittable for Plane {
-    fn hit(self, object_space_ray: Ray) -> Option<Intersections> {
+    fn hit(&self, object_space_ray: Ray) -> Option<Intersections> {
         if object_space_ray.direction[1].abs() < EPSILON {
             None
         } else {
@@ -29,7 +30,7 @@ impl Hittable for Plane {
         }
     }
 
-    fn normal_at(self, _object_space_point: Point) -> Option<Vector> {
+    fn normal_at(&self, _object_space_point: Point) -> Option<Vector> {
         Some(Vector::new(0.0, 1.0, 0.0))
     }
 }
@@ -0,0 +1,314 @@
+use crate::{
+    math::{Form, Geometry, Hittable, Point, Vector, EPSILON},
+    world::{Intersection, Intersections, Ray},
+};
+
+/// the surface of all points where
+/// `ax² + by² + cz² + dxy + exz + fyz + gx + hy + iz + j = 0`, optionally truncated
+/// to the points whose y lies between `minimum` and `maximum` (both exclusive).
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Quadric {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+    pub e: f64,
+    pub f: f64,
+    pub g: f64,
+    pub h: f64,
+    pub i: f64,
+    pub j: f64,
+    pub minimum: f64,
+    pub maximum: f64,
+}
+
+impl Quadric {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        a: f64,
+        b: f64,
+        c: f64,
+        d: f64,
+        e: f64,
+        f: f64,
+        g: f64,
+        h: f64,
+        i: f64,
+        j: f64,
+    ) -> Quadric {
+        Quadric {
+            a,
+            b,
+            c,
+            d,
+            e,
+            f,
+            g,
+            h,
+            i,
+            j,
+            minimum: f64::NEG_INFINITY,
+            maximum: f64::INFINITY,
+        }
+    }
+
+    /// the unit sphere, `x² + y² + z² = 1`.
+    pub fn sphere() -> Quadric {
+        Quadric::new(1.0, 1.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, -1.0)
+    }
+
+    /// the infinite cylinder of radius 1 around the y axis, `x² + z² = 1`.
+    pub fn cylinder() -> Quadric {
+        Quadric::new(1.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, -1.0)
+    }
+
+    /// the double cone around the y axis with its apex at the origin, `x² + z² = y²`.
+    pub fn cone() -> Quadric {
+        Quadric::new(1.0, -1.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0)
+    }
+
+    /// the bowl opening up the y axis from the origin, `x² + z² = y`.
+    pub fn paraboloid() -> Quadric {
+        Quadric::new(1.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, -1.0, 0.0, 0.0)
+    }
+
+    /// the single connected, waisted tube around the y axis, `x² + z² - y² = 1`.
+    pub fn hyperboloid_one_sheet() -> Quadric {
+        Quadric::new(1.0, -1.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, -1.0)
+    }
+
+    /// the pair of bowls opening away from each other along the y axis,
+    /// `x² + z² - y² = -1`.
+    pub fn hyperboloid_two_sheets() -> Quadric {
+        Quadric::new(1.0, -1.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0)
+    }
+
+    /// keeps only the part of the surface whose y lies between `minimum` and `maximum`.
+    pub fn truncated(self, minimum: f64, maximum: f64) -> Quadric {
+        Quadric {
+            minimum,
+            maximum,
+            ..self
+        }
+    }
+
+    fn intersection(&self, time: f64, object_space_ray: Ray) -> Intersection {
+        Intersection::new(
+            time,
+            object_space_ray,
+            Geometry::default().with_form(Form::Quadric(*self)),
+        )
+    }
+}
+
+impl Hittable for Quadric {
+    fn hit(&self, object_space_ray: Ray) -> Option<Intersections> {
+        let (ox, oy, oz) = (
+            object_space_ray.origin[0],
+            object_space_ray.origin[1],
+            object_space_ray.origin[2],
+        );
+        let (dx, dy, dz) = (
+            object_space_ray.direction[0],
+            object_space_ray.direction[1],
+            object_space_ray.direction[2],
+        );
+
+        // substituting the ray into the surface equation gives a quadratic in t.
+        let a = self.a * dx * dx
+            + self.b * dy * dy
+            + self.c * dz * dz
+            + self.d * dx * dy
+            + self.e * dx * dz
+            + self.f * dy * dz;
+        let b = 2.0 * self.a * ox * dx
+            + 2.0 * self.b * oy * dy
+            + 2.0 * self.c * oz * dz
+            + self.d * (ox * dy + oy * dx)
+            + self.e * (ox * dz + oz * dx)
+            + self.f * (oy * dz + oz * dy)
+            + self.g * dx
+            + self.h * dy
+            + self.i * dz;
+        let c = self.a * ox * ox
+            + self.b * oy * oy
+            + self.c * oz * oz
+            + self.d * ox * oy
+            + self.e * ox * oz
+            + self.f * oy * oz
+            + self.g * ox
+            + self.h * oy
+            + self.i * oz
+            + self.j;
+
+        // a and b grow with the length of the direction, which scaling the object
+        // shrinks or stretches, so they are only compared with zero relative to it. a is
+        // also relative to the size of the quadratic terms.
+        let length_squared = object_space_ray.direction.dot(&object_space_ray.direction);
+        let quadratic_size = [self.a, self.b, self.c, self.d, self.e, self.f]
+            .iter()
+            .fold(0.0, |size: f64, k| size.max(k.abs()));
+        let times = if a.abs() <= EPSILON * quadratic_size * length_squared {
+            // the ray runs parallel to an asymptote of the surface, so the quadratic
+            // degenerates into a line which crosses the surface at most once.
+            if b.abs() <= EPSILON * length_squared.sqrt() {
+                return None;
+            }
+            vec![-c / b]
+        } else {
+            let discriminant = (b * b) - (4.0 * a * c);
+            if discriminant < 0.0 {
+                return None;
+            }
+            vec![
+                (-b - discriminant.sqrt()) / (2.0 * a),
+                (-b + discriminant.sqrt()) / (2.0 * a),
+            ]
+        };
+
+        let mut hits = Intersections::default();
+        for t in times {
+            let y = oy + t * dy;
+            if self.minimum < y && y < self.maximum {
+                hits.insert(self.intersection(t, object_space_ray));
+            }
+        }
+
+        if hits.count() == 0 {
+            None
+        } else {
+            Some(hits)
+        }
+    }
+
+    fn normal_at(&self, object_space_point: Point) -> Option<Vector> {
+        let (x, y, z) = (
+            object_space_point[0],
+            object_space_point[1],
+            object_space_point[2],
+        );
+
+        // the gradient of the surface equation points away from the surface.
+        Some(Vector::new(
+            2.0 * self.a * x + self.d * y + self.e * z + self.g,
+            2.0 * self.b * y + self.d * x + self.f * z + self.h,
+            2.0 * self.c * z + self.e * x + self.f * y + self.i,
+        ))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::useless_conversion)]
+mod tests {
+    use super::*;
+    use crate::math::{Matrix, Transformable};
+
+    fn times(xs: Option<Intersections>) -> Vec<f64> {
+        let mut xs = xs.unwrap_or_default();
+        let mut result = vec![];
+        while let Some(x) = xs.pop() {
+            result.push(x.time);
+        }
+        result
+    }
+
+    #[test]
+    fn unit_sphere_matches_sphere() {
+        let rays = [
+            Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0)),
+            Ray::new(Point::new(0.0, 1.0, -5.0), Vector::new(0.0, 0.0, 1.0)),
+            Ray::new(Point::new(0.0, 2.0, -5.0), Vector::new(0.0, 0.0, 1.0)),
+            Ray::new(Point::zero(), Vector::new(0.0, 0.0, 1.0)),
+            Ray::new(Point::new(0.0, 0.0, 5.0), Vector::new(0.0, 0.0, 1.0)),
+            Ray::new(Point::new(0.3, -0.2, -4.0), Vector::new(0.1, 0.2, 0.9)),
+            Ray::new(Point::new(-2.0, 3.0, 1.0), Vector::new(0.5, -0.7, -0.1)),
+        ];
+        let transforms = [
+            Matrix::identity(),
+            Matrix::scaling(2.0, 0.5, 1.0),
+            Matrix::translation(0.5, 0.0, -1.0) * Matrix::rotation_z(0.3),
+        ];
+        for &transform in transforms.iter() {
+            let sphere = Geometry::default()
+                .with_form(Form::Sphere)
+                .transformed(transform);
+            let quadric = Geometry::default()
+                .with_form(Form::Quadric(Quadric::sphere()))
+                .transformed(transform);
+            for &ray in rays.iter() {
+                assert_eq!(times(quadric.hit(ray)), times(sphere.hit(ray)));
+            }
+        }
+    }
+
+    #[test]
+    fn scaled_quadric_is_not_degenerate() {
+        let r = Ray::new(Point::new(0.0, 0.0, -5000.0), Vector::new(0.0, 0.0, 1.0));
+        let scaling = Matrix::scaling(1000.0, 1000.0, 1000.0);
+        let quadric = Geometry::default()
+            .with_form(Form::Quadric(Quadric::sphere()))
+            .transformed(scaling);
+        let sphere = Geometry::default()
+            .with_form(Form::Sphere)
+            .transformed(scaling);
+        let hits = times(quadric.hit(r));
+        assert_eq!(hits.len(), 2);
+        for (t, expected) in hits.into_iter().zip(times(sphere.hit(r))) {
+            assert!((t - expected).abs() < EPSILON);
+        }
+    }
+
+    #[test]
+    fn unit_sphere_normals_match_sphere() {
+        let sphere = Geometry::default().with_form(Form::Sphere);
+        let quadric = Geometry::default().with_form(Form::Quadric(Quadric::sphere()));
+        let s = f64::from(3.0).sqrt() / 3.0;
+        for &point in [
+            Point::new(1.0, 0.0, 0.0),
+            Point::new(0.0, -1.0, 0.0),
+            Point::new(s, s, s),
+        ]
+        .iter()
+        {
+            assert_eq!(quadric.normal_at(point), sphere.normal_at(point));
+        }
+    }
+
+    #[test]
+    fn ray_parallel_to_paraboloid_axis_hits_once() {
+        let r = Ray::new(Point::new(1.0, 5.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+        let p = Geometry::default().with_form(Form::Quadric(Quadric::paraboloid()));
+        assert_eq!(times(p.hit(r)), vec![4.0]);
+    }
+
+    #[test]
+    fn ray_through_hyperboloid_waist() {
+        let r = Ray::new(Point::new(-5.0, 0.0, 0.0), Vector::new(1.0, 0.0, 0.0));
+        let h = Geometry::default().with_form(Form::Quadric(Quadric::hyperboloid_one_sheet()));
+        assert_eq!(times(h.hit(r)), vec![4.0, 6.0]);
+    }
+
+    #[test]
+    fn ray_between_hyperboloid_sheets_misses() {
+        let r = Ray::new(Point::new(-5.0, 0.0, 0.0), Vector::new(1.0, 0.0, 0.0));
+        let h = Geometry::default().with_form(Form::Quadric(Quadric::hyperboloid_two_sheets()));
+        assert!(h.hit(r).is_none());
+    }
+
+    #[test]
+    fn truncated_quadric_drops_hits_outside_range() {
+        let r = Ray::new(Point::new(0.0, 5.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let cylinder = Geometry::default().with_form(Form::Quadric(Quadric::cylinder()));
+        assert_eq!(times(cylinder.hit(r)), vec![4.0, 6.0]);
+        let truncated = cylinder.with_form(Form::Quadric(Quadric::cylinder().truncated(-1.0, 1.0)));
+        assert!(truncated.hit(r).is_none());
+    }
+
+    #[test]
+    fn paraboloid_normal_is_gradient() {
+        let p = Quadric::paraboloid();
+        let n = p.normal_at(Point::new(1.0, 1.0, 0.0)).unwrap();
+        assert_eq!(n, Vector::new(2.0, -1.0, 0.0));
+    }
+}
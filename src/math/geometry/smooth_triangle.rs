@@ -0,0 +1,131 @@
+use crate::{
+    math::{geometry::Triangle, Form, Geometry, Hittable, Point, Vector},
+    world::{Intersection, Intersections, Ray},
+};
+
+/// a triangle with a normal given at each corner. the normal anywhere else is blended
+/// between those three, so that a mesh of these triangles approximating a curved
+/// surface is shaded as if it were curved.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SmoothTriangle {
+    /// the flat triangle between the corners, which rays hit.
+    pub triangle: Triangle,
+    /// the normal at `triangle.p1`.
+    pub n1: Vector,
+    /// the normal at `triangle.p2`.
+    pub n2: Vector,
+    /// the normal at `triangle.p3`.
+    pub n3: Vector,
+}
+
+impl SmoothTriangle {
+    pub fn new(p1: Point, p2: Point, p3: Point, n1: Vector, n2: Vector, n3: Vector) -> Self {
+        SmoothTriangle {
+            triangle: Triangle::new(p1, p2, p3),
+            n1,
+            n2,
+            n3,
+        }
+    }
+
+    /// how far `point`, on the triangle, is towards its second and third corners.
+    fn barycentric(&self, point: Point) -> (f64, f64) {
+        let Triangle { p1, e1, e2, .. } = self.triangle;
+        let to_point = point - p1;
+        let (d11, d12, d22) = (e1.dot(&e1), e1.dot(&e2), e2.dot(&e2));
+        let (dp1, dp2) = (to_point.dot(&e1), to_point.dot(&e2));
+        let denominator = d11 * d22 - d12 * d12;
+        (
+            (d22 * dp1 - d12 * dp2) / denominator,
+            (d11 * dp2 - d12 * dp1) / denominator,
+        )
+    }
+}
+
+impl Hittable for SmoothTriangle {
+    fn hit(&self, object_space_ray: Ray) -> Option<Intersections> {
+        let (t, u, v) = self.triangle.intersect(object_space_ray)?;
+        Some(Intersections::with(vec![Intersection::with_uv(
+            t,
+            object_space_ray,
+            Geometry::default().with_form(Form::SmoothTriangle(*self)),
+            u,
+            v,
+        )]))
+    }
+
+    /// the normal at `object_space_point`, working out where on the triangle it is.
+    fn normal_at(&self, object_space_point: Point) -> Option<Vector> {
+        self.normal_at_uv(object_space_point, None)
+    }
+
+    fn normal_at_uv(&self, object_space_point: Point, uv: Option<(f64, f64)>) -> Option<Vector> {
+        let (u, v) = uv.unwrap_or_else(|| self.barycentric(object_space_point));
+        Some((self.n2 * u + self.n3 * v + self.n1 * (1.0 - u - v)).normalized())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::intersection::Computations;
+
+    fn setup() -> SmoothTriangle {
+        SmoothTriangle::new(
+            Point::new(0.0, 1.0, 0.0),
+            Point::new(-1.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+            Vector::new(-1.0, 0.0, 0.0),
+            Vector::new(1.0, 0.0, 0.0),
+        )
+    }
+
+    #[test]
+    fn constructing_a_smooth_triangle() {
+        let t = setup();
+        assert_eq!(t.triangle.p1, Point::new(0.0, 1.0, 0.0));
+        assert_eq!(t.triangle.p2, Point::new(-1.0, 0.0, 0.0));
+        assert_eq!(t.triangle.p3, Point::new(1.0, 0.0, 0.0));
+        assert_eq!(t.n1, Vector::new(0.0, 1.0, 0.0));
+        assert_eq!(t.n2, Vector::new(-1.0, 0.0, 0.0));
+        assert_eq!(t.n3, Vector::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn intersections_store_u_and_v() {
+        let r = Ray::new(Point::new(-0.2, 0.3, -2.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = setup().hit(r).unwrap();
+        let (u, v) = xs.closest().unwrap().uv.unwrap();
+        assert!((u - 0.45).abs() < 1e-9);
+        assert!((v - 0.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn normals_are_interpolated() {
+        let t = Geometry::default().with_form(Form::SmoothTriangle(setup()));
+        let i = Intersection::with_uv(
+            1.0,
+            Ray::new(Point::new(-0.2, 0.3, -2.0), Vector::new(0.0, 0.0, 1.0)),
+            t,
+            0.45,
+            0.25,
+        );
+        assert_eq!(i.normal(), Some(Vector::new(-0.5547, 0.83205, 0.0)));
+        assert_eq!(
+            Computations::new(&i).surface_normal,
+            Vector::new(-0.5547, 0.83205, 0.0)
+        );
+    }
+
+    #[test]
+    fn normals_without_u_and_v_are_found_from_the_point() {
+        let t = setup();
+        assert_eq!(
+            t.normal_at(Point::new(-0.2, 0.3, 0.0)),
+            t.normal_at_uv(Point::new(-0.2, 0.3, 0.0), Some((0.45, 0.25)))
+        );
+        assert_eq!(t.normal_at(t.triangle.p1), Some(t.n1));
+    }
+}
@@ -1,8 +1,9 @@
 use crate::{
-    math::{Form, Geometry, Hittable, Matrix, Point, Vector},
-    world::{Intersection, Intersections, Material, Ray},
+    math::{Form, Geometry, Hittable, Point, Vector},
+    world::{Intersection, Intersections, Ray},
 };
 
+#[derive(Default)]
 pub struct Sphere {}
 
 impl Sphere {
@@ -12,7 +13,7 @@ impl Sphere {
 }
 
 impl Hittable for Sphere {
-    fn hit(self, object_space_ray: Ray) -> Option<Intersections> {
+    fn hit(&self, object_space_ray: Ray) -> Option<Intersections> {
         let origin = Point::zero();
         let displacement = object_space_ray.origin - origin;
         let a = object_space_ray.direction.dot(&object_space_ray.direction);
@@ -25,7 +26,7 @@ impl Hittable for Sphere {
             let t2 = (-b + discriminant.sqrt()) / (2.0 * a);
 
             let hits = Intersections::with(
-                vec![t1, t2]
+                [t1, t2]
                     .iter()
                     .filter(|t| t.is_sign_positive())
                     .map(|&t| {
@@ -47,15 +48,23 @@ impl Hittable for Sphere {
         }
     }
 
-    fn normal_at(self, object_space_point: Point) -> Option<Vector> {
+    fn normal_at(&self, object_space_point: Point) -> Option<Vector> {
         Some(object_space_point - Point::zero())
     }
 }
 
 #[cfg(test)]
+#[allow(
+    clippy::approx_constant,
+    clippy::field_reassign_with_default,
+    clippy::useless_conversion
+)]
 mod tests {
     use super::*;
-    use crate::math::Transformable;
+    use crate::{
+        math::{Matrix, Transformable},
+        world::Material,
+    };
     use std::f64::consts;
 
     #[test]
@@ -220,7 +229,7 @@ mod tests {
     #[test]
     fn default_material() {
         let s = Geometry::default().with_form(Form::Sphere);
-        assert_eq!(s.material, Material::default());
+        assert_eq!(*s.material, Material::default());
     }
 
     #[test]
@@ -228,7 +237,7 @@ mod tests {
         let mut s = Geometry::default().with_form(Form::Sphere);
         let mut m = Material::default();
         m.ambient = 1.0;
-        s.material = m;
-        assert_eq!(s.material, m);
+        s.change_material(m.clone());
+        assert_eq!(*s.material, m);
     }
 }
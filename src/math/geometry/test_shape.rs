@@ -0,0 +1,143 @@
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    math::{Hittable, Point, Vector},
+    world::{Intersections, Ray},
+};
+
+/// a shape with no surface of its own, which remembers the last ray it was asked to
+/// intersect and whose normal at a point is simply that point as a vector. useful for
+/// checking how `Geometry` moves rays and points between world and object space.
+///
+/// clones share the same memory, so the saved ray can be read back through any copy
+/// (including the one held by the `Geometry` that was hit). two test shapes are only
+/// equal if they share that memory.
+#[derive(Clone, Debug, Default)]
+pub struct TestShape {
+    saved_ray: Arc<Mutex<Option<Ray>>>,
+}
+
+impl TestShape {
+    pub fn new() -> TestShape {
+        TestShape::default()
+    }
+
+    /// the object space ray from the most recent call to `hit`, if there was one.
+    pub fn saved_ray(&self) -> Option<Ray> {
+        *self.saved_ray.lock().unwrap()
+    }
+}
+
+impl PartialEq for TestShape {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.saved_ray, &other.saved_ray)
+    }
+}
+
+impl Hittable for TestShape {
+    fn hit(&self, object_space_ray: Ray) -> Option<Intersections> {
+        *self.saved_ray.lock().unwrap() = Some(object_space_ray);
+        None
+    }
+
+    fn normal_at(&self, object_space_point: Point) -> Option<Vector> {
+        Some(object_space_point - Point::zero())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::approx_constant, clippy::useless_conversion)]
+mod tests {
+    use super::*;
+    use crate::math::{Form, Geometry, Matrix, Transformable};
+    use std::f64::consts;
+
+    #[test]
+    fn nothing_saved_before_hit() {
+        let shape = TestShape::new();
+        assert!(shape.saved_ray().is_none());
+    }
+
+    #[test]
+    fn clones_share_saved_ray() {
+        let shape = TestShape::new();
+        let clone = shape.clone();
+        clone.hit(Ray::new(Point::zero(), Vector::new(0.0, 0.0, 1.0)));
+        assert_eq!(
+            shape.saved_ray().unwrap().direction,
+            Vector::new(0.0, 0.0, 1.0)
+        );
+        assert_eq!(shape, clone);
+        assert_ne!(shape, TestShape::new());
+    }
+
+    #[test]
+    fn intersect_scaled_shape() {
+        let shape = TestShape::new();
+        let s = Geometry::default()
+            .with_form(Form::Test(shape.clone()))
+            .transformed(Matrix::scaling(2.0, 2.0, 2.0));
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert!(s.hit(r).is_none());
+        let saved = shape.saved_ray().unwrap();
+        assert_eq!(saved.origin, Point::new(0.0, 0.0, -2.5));
+        assert_eq!(saved.direction, Vector::new(0.0, 0.0, 0.5));
+    }
+
+    #[test]
+    fn intersect_translated_shape() {
+        let shape = TestShape::new();
+        let s = Geometry::default()
+            .with_form(Form::Test(shape.clone()))
+            .transformed(Matrix::translation(5.0, 0.0, 0.0));
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert!(s.hit(r).is_none());
+        let saved = shape.saved_ray().unwrap();
+        assert_eq!(saved.origin, Point::new(-5.0, 0.0, -5.0));
+        assert_eq!(saved.direction, Vector::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn intersect_shape_in_group() {
+        let shape = TestShape::new();
+        let s = Geometry::default()
+            .with_form(Form::Test(shape.clone()))
+            .transformed(Matrix::translation(5.0, 0.0, 0.0));
+        let g = Geometry::default()
+            .with_form(Form::Group(vec![s]))
+            .transformed(Matrix::scaling(2.0, 2.0, 2.0));
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert!(g.hit(r).is_none());
+        let saved = shape.saved_ray().unwrap();
+        assert_eq!(saved.origin, Point::new(-5.0, 0.0, -2.5));
+        assert_eq!(saved.direction, Vector::new(0.0, 0.0, 0.5));
+    }
+
+    #[test]
+    fn normal_on_translated_shape() {
+        let s = Geometry::default()
+            .with_form(Form::Test(TestShape::new()))
+            .transformed(Matrix::translation(0.0, 1.0, 0.0));
+        let n = s.normal_at(Point::new(0.0, 1.70711, -0.70711)).unwrap();
+        assert_eq!(n, Vector::new(0.0, 0.70711, -0.70711));
+    }
+
+    #[test]
+    fn normal_on_transformed_shape() {
+        let s = Geometry::default()
+            .with_form(Form::Test(TestShape::new()))
+            .transformed(
+                *Matrix::identity()
+                    .rotate_z(consts::PI / 5.0)
+                    .scale(1.0, 0.5, 1.0),
+            );
+        let n = s
+            .normal_at(Point::new(
+                0.0,
+                f64::from(2.0).sqrt() / 2.0,
+                -f64::from(2.0).sqrt() / 2.0,
+            ))
+            .unwrap();
+        assert_eq!(n, Vector::new(0.0, 0.97014, -0.24254));
+    }
+}
@@ -0,0 +1,194 @@
+use crate::{
+    math::{Form, Geometry, Hittable, Point, Vector, EPSILON},
+    world::{Intersection, Intersections, Ray},
+};
+
+/// a flat triangle between three corners. the edges and normal are worked out once,
+/// when the triangle is made, since every ray needs them.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "SerializedTriangle")
+)]
+pub struct Triangle {
+    pub p1: Point,
+    pub p2: Point,
+    pub p3: Point,
+    /// the edge from `p1` to `p2`.
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    pub e1: Vector,
+    /// the edge from `p1` to `p3`.
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    pub e2: Vector,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    pub normal: Vector,
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct SerializedTriangle {
+    p1: Point,
+    p2: Point,
+    p3: Point,
+}
+
+#[cfg(feature = "serde")]
+impl From<SerializedTriangle> for Triangle {
+    fn from(serialized: SerializedTriangle) -> Triangle {
+        Triangle::new(serialized.p1, serialized.p2, serialized.p3)
+    }
+}
+
+impl Triangle {
+    pub fn new(p1: Point, p2: Point, p3: Point) -> Triangle {
+        let e1 = p2 - p1;
+        let e2 = p3 - p1;
+        Triangle {
+            p1,
+            p2,
+            p3,
+            e1,
+            e2,
+            normal: e2.cross(&e1).normalized(),
+        }
+    }
+
+    /// finds where a ray crosses the triangle with the möller-trumbore algorithm,
+    /// giving the time along the ray and how far the crossing is towards `p2` and `p3`.
+    pub(crate) fn intersect(&self, object_space_ray: Ray) -> Option<(f64, f64, f64)> {
+        let dir_cross_e2 = object_space_ray.direction.cross(&self.e2);
+        let det = self.e1.dot(&dir_cross_e2);
+        // the ray runs parallel to the triangle. det grows with the size of the triangle
+        // and the length of the direction, so it is compared relative to both, or small
+        // triangles from finely tessellated meshes would never be hit.
+        let scale =
+            self.e1.magnitude() * self.e2.magnitude() * object_space_ray.direction.magnitude();
+        if det.abs() <= EPSILON * scale {
+            return None;
+        }
+
+        let f = 1.0 / det;
+        let p1_to_origin = object_space_ray.origin - self.p1;
+        let u = f * p1_to_origin.dot(&dir_cross_e2);
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+
+        let origin_cross_e1 = p1_to_origin.cross(&self.e1);
+        let v = f * object_space_ray.direction.dot(&origin_cross_e1);
+        if v < 0.0 || 1.0 < u + v {
+            return None;
+        }
+
+        Some((f * self.e2.dot(&origin_cross_e1), u, v))
+    }
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, object_space_ray: Ray) -> Option<Intersections> {
+        let (t, _, _) = self.intersect(object_space_ray)?;
+        Some(Intersections::with(vec![Intersection::new(
+            t,
+            object_space_ray,
+            Geometry::default().with_form(Form::Triangle(*self)),
+        )]))
+    }
+
+    fn normal_at(&self, _object_space_point: Point) -> Option<Vector> {
+        Some(self.normal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> Triangle {
+        Triangle::new(
+            Point::new(0.0, 1.0, 0.0),
+            Point::new(-1.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, 0.0),
+        )
+    }
+
+    #[test]
+    fn constructing_a_triangle() {
+        let t = setup();
+        assert_eq!(t.e1, Vector::new(-1.0, -1.0, 0.0));
+        assert_eq!(t.e2, Vector::new(1.0, -1.0, 0.0));
+        assert_eq!(t.normal, Vector::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn normal_is_constant() {
+        let t = setup();
+        for &point in [
+            Point::new(0.0, 0.5, 0.0),
+            Point::new(-0.5, 0.75, 0.0),
+            Point::new(0.5, 0.25, 0.0),
+        ]
+        .iter()
+        {
+            assert_eq!(t.normal_at(point), Some(t.normal));
+        }
+    }
+
+    #[test]
+    fn parallel_ray_misses() {
+        let r = Ray::new(Point::new(0.0, -1.0, -2.0), Vector::new(0.0, 1.0, 0.0));
+        assert!(setup().hit(r).is_none());
+    }
+
+    #[test]
+    fn ray_misses_each_edge() {
+        for &origin in [
+            // past the edge from p1 to p3
+            Point::new(1.0, 1.0, -2.0),
+            // past the edge from p1 to p2
+            Point::new(-1.0, 1.0, -2.0),
+            // past the edge from p2 to p3
+            Point::new(0.0, -1.0, -2.0),
+        ]
+        .iter()
+        {
+            let r = Ray::new(origin, Vector::new(0.0, 0.0, 1.0));
+            assert!(setup().hit(r).is_none(), "{:?} should miss", origin);
+        }
+    }
+
+    #[test]
+    fn ray_strikes_triangle() {
+        let r = Ray::new(Point::new(0.0, 0.5, -2.0), Vector::new(0.0, 0.0, 1.0));
+        let mut xs = setup().hit(r).unwrap();
+        assert_eq!(xs.count(), 1);
+        assert_eq!(xs.pop().unwrap().time, 2.0);
+    }
+
+    #[test]
+    fn tiny_triangles_are_hit() {
+        let tiny = Triangle::new(
+            Point::new(0.0, 0.001, 0.0),
+            Point::new(-0.001, 0.0, 0.0),
+            Point::new(0.001, 0.0, 0.0),
+        );
+        let r = Ray::new(Point::new(0.0, 0.0005, -2.0), Vector::new(0.0, 0.0, 1.0));
+        let mut xs = tiny.hit(r).unwrap();
+        assert_eq!(xs.pop().unwrap().time, 2.0);
+
+        let edge_on = Ray::new(Point::new(0.0, 0.0005, -2.0), Vector::new(1.0, 0.0, 0.0));
+        assert!(tiny.hit(edge_on).is_none());
+    }
+
+    #[test]
+    fn transformed_triangles_are_hit() {
+        let t = Geometry::default().with_form(Form::Triangle(setup()));
+        let r = Ray::new(Point::new(0.0, 0.5, -2.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = t.hit(r).unwrap();
+        assert_eq!(xs.closest().unwrap().object, t);
+        assert_eq!(
+            t.normal_at(Point::new(0.0, 0.5, 0.0)),
+            Some(Vector::new(0.0, 0.0, -1.0))
+        );
+    }
+}
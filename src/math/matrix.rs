@@ -1,6 +1,11 @@
-use std::ops::{Add, AddAssign, Index, IndexMut, Mul, Sub, SubAssign};
+use std::{
+    fmt::{self, Display, Formatter},
+    ops::{Add, AddAssign, Index, IndexMut, Mul, Sub, SubAssign},
+    str::FromStr,
+};
 
-use super::{point::Point, vector::Vector, EPSILON};
+use super::{point::Point, text, vector::Vector, ParseMathError, EPSILON};
+use crate::RayTracerError;
 
 /// 4-by-4 matrix that represents both a transformation and a translation by using
 /// homogeneous coordinates (https://en.wikipedia.org/wiki/Homogeneous_coordinates).
@@ -19,6 +24,7 @@ pub struct Matrix {
 
 impl Matrix {
     #[rustfmt::skip]
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         n00: f64, n01: f64, n02: f64, n03: f64,
         n10: f64, n11: f64, n12: f64, n13: f64,
@@ -126,10 +132,106 @@ impl Matrix {
         self
     }
 
+    /// the smallest rotation that turns the direction of `from` to the direction of
+    /// `to`. neither needs to be normalized, but both must have some length. when they
+    /// point in opposite directions, every half turn around a line perpendicular to
+    /// them is as small as any other, and one of those is chosen.
+    pub fn rotation_between(from: Vector, to: Vector) -> Matrix {
+        let (from, to) = (from.normalized(), to.normalized());
+        let c = from.dot(&to);
+        // near -1 the axis below shrinks to nothing and its direction is lost to
+        // rounding, so turn half way around a perpendicular axis instead.
+        if c < -1.0 + 1e-12 {
+            let other = if from[0].abs() < 0.9 {
+                Vector::new(1.0, 0.0, 0.0)
+            } else {
+                Vector::new(0.0, 1.0, 0.0)
+            };
+            let a = from.cross(&other).normalized();
+
+            #[rustfmt::skip]
+            return Matrix::new(
+                2.0 * a[0] * a[0] - 1.0, 2.0 * a[0] * a[1],       2.0 * a[0] * a[2],       0.0,
+                2.0 * a[1] * a[0],       2.0 * a[1] * a[1] - 1.0, 2.0 * a[1] * a[2],       0.0,
+                2.0 * a[2] * a[0],       2.0 * a[2] * a[1],       2.0 * a[2] * a[2] - 1.0, 0.0,
+            );
+        }
+
+        // rodrigues' rotation formula, with the sine and cosine folded into the
+        // unnormalized axis `v` and `c`.
+        let v = from.cross(&to);
+        let k = 1.0 / (1.0 + c);
+
+        #[rustfmt::skip]
+        Matrix::new(
+            v[0] * v[0] * k + c,    v[0] * v[1] * k - v[2], v[0] * v[2] * k + v[1], 0.0,
+            v[1] * v[0] * k + v[2], v[1] * v[1] * k + c,    v[1] * v[2] * k - v[0], 0.0,
+            v[2] * v[0] * k - v[1], v[2] * v[1] * k + v[0], v[2] * v[2] * k + c,    0.0,
+        )
+    }
+
+    pub fn rotate_between(&mut self, from: Vector, to: Vector) -> &mut Matrix {
+        *self = Matrix::rotation_between(from, to) * *self;
+        self
+    }
+
+    /// mirrors space across the plane through `plane_point` that faces `plane_normal`,
+    /// which needn't be normalized. points on the plane stay where they are.
+    ///
+    /// a reflection turns space inside out, so left and right hands swap: textures on
+    /// reflected objects read backwards, and a normal worked out as the cross product
+    /// of two transformed edges points into the object rather than out of it.
+    pub fn reflection(plane_normal: Vector, plane_point: Point) -> Matrix {
+        let n = plane_normal.normalized();
+        let d = 2.0 * n.dot(&(plane_point - Point::zero()));
+
+        #[rustfmt::skip]
+        Matrix::new(
+            1.0 - 2.0 * n[0] * n[0], -2.0 * n[0] * n[1],      -2.0 * n[0] * n[2],      d * n[0],
+            -2.0 * n[1] * n[0],      1.0 - 2.0 * n[1] * n[1], -2.0 * n[1] * n[2],      d * n[1],
+            -2.0 * n[2] * n[0],      -2.0 * n[2] * n[1],      1.0 - 2.0 * n[2] * n[2], d * n[2],
+        )
+    }
+
+    pub fn reflect(&mut self, plane_normal: Vector, plane_point: Point) -> &mut Matrix {
+        *self = Matrix::reflection(plane_normal, plane_point) * *self;
+        self
+    }
+
+    /// the inverse of this matrix, which must have one.
+    ///
+    /// # panics
+    ///
+    /// in debug builds, panics if the matrix has no inverse. release builds return a
+    /// matrix of infinities and NaNs instead. see `try_inverse`.
+    pub fn inverse(&self) -> Matrix {
+        debug_assert!(
+            self.is_invertible(),
+            "tried to invert {:?}, which has no inverse",
+            self
+        );
+        self.unchecked_inverse()
+    }
+
+    /// the inverse of this matrix, or `None` if it has none.
+    pub fn try_inverse(&self) -> Option<Matrix> {
+        if self.is_invertible() {
+            Some(self.unchecked_inverse())
+        } else {
+            None
+        }
+    }
+
+    /// the inverse of this matrix, or an error if it has none.
+    pub fn checked_inverse(&self) -> Result<Matrix, RayTracerError> {
+        self.try_inverse()
+            .ok_or(RayTracerError::NonInvertibleMatrix(*self))
+    }
+
     /// a specialized way to find the inverse of matrices of this specific form.
     /// taken from "foundations of game engine development; volume 1: mathematics"
     /// by eric lengyel.
-    pub fn inverse(&self) -> Matrix {
+    fn unchecked_inverse(&self) -> Matrix {
         let a = self[0];
         let b = self[1];
         let c = self[2];
@@ -179,6 +281,64 @@ impl Matrix {
         self
     }
 
+    /// this matrix with its first three columns made perpendicular and of unit length,
+    /// so that it only rotates (or reflects) before translating. the translation is
+    /// kept as it is.
+    ///
+    /// rotations built up from many small steps, such as an object spun a little every
+    /// frame of an animation, gather rounding errors that slowly scale and shear
+    /// whatever they are applied to. calling this every so often keeps them honest. the
+    /// camera's own moves, such as `View::orbit_y`, rebuild the view from where it
+    /// stands and looks each time, so they don't need it.
+    ///
+    /// uses the gram-schmidt process, keeping the direction of the first column and
+    /// the plane of the first two. the matrix must be invertible.
+    pub fn orthonormalized(&self) -> Matrix {
+        let a = self[0].normalized();
+        let b = self[1].reject_from(&a).normalized();
+        let c = self[2].reject_from(&a).reject_from(&b).normalized();
+        Matrix::with_columns(a, b, c, self.translation)
+    }
+
+    pub fn orthonormalize(&mut self) -> &mut Matrix {
+        *self = self.orthonormalized();
+        self
+    }
+
+    /// checks that the first three columns are of unit length and perpendicular to each
+    /// other, to within `epsilon`.
+    pub fn is_orthonormal(&self, epsilon: f64) -> bool {
+        (0..3).all(|j| (self[j].magnitude() - 1.0).abs() < epsilon)
+            && self[0].dot(&self[1]).abs() < epsilon
+            && self[0].dot(&self[2]).abs() < epsilon
+            && self[1].dot(&self[2]).abs() < epsilon
+    }
+
+    /// the first three rows, in the order `Matrix::new` takes them. the fourth row is
+    /// always `{ 0, 0, 0, 1 }`, so it is left out.
+    pub fn to_rows(&self) -> [[f64; 4]; 3] {
+        let row = |i: usize| {
+            [
+                self[(i, 0)],
+                self[(i, 1)],
+                self[(i, 2)],
+                self.translation[i],
+            ]
+        };
+        [row(0), row(1), row(2)]
+    }
+
+    /// the three columns of the transformation followed by the translation, without
+    /// their implied fourth rows.
+    pub fn to_columns(&self) -> [[f64; 3]; 4] {
+        [
+            self[0].into(),
+            self[1].into(),
+            self[2].into(),
+            self.translation.into(),
+        ]
+    }
+
     /// calculates the determinant by finding the equivalent determinant of the 3-by-3
     /// transformation sub-matrix.
     pub fn determinant(&self) -> f64 {
@@ -187,9 +347,100 @@ impl Matrix {
             + self[(0, 2)] * (self[(1, 0)] * self[(2, 1)] - self[(1, 1)] * self[(2, 0)])
     }
 
-    /// uses the determinant to say if an inverse exists.
+    /// uses the determinant to say if an inverse exists. the determinant is the volume
+    /// of the box spanned by the first three columns, so it is compared to the volume
+    /// the box would have if they were perpendicular: a small but well-shaped
+    /// transform can be inverted, while one that all but squashes space flat can't.
     pub fn is_invertible(&self) -> bool {
-        EPSILON < self.determinant().abs()
+        let volume = self[0].magnitude() * self[1].magnitude() * self[2].magnitude();
+        EPSILON * volume < self.determinant().abs()
+    }
+
+    /// checks that no entry is NaN or infinite, as happens when inverting a matrix
+    /// that has no inverse.
+    pub fn is_finite(&self) -> bool {
+        (0..3).all(|i| (0..3).all(|j| self[(i, j)].is_finite()) && self.translation[i].is_finite())
+    }
+
+    /// in debug builds, panics if `inverse` has gone stale and is no longer the
+    /// inverse of this matrix, naming `owner` as the type that holds the pair. a
+    /// matrix without an inverse has nothing to be compared against, so it passes.
+    pub fn debug_assert_inverse(&self, inverse: &Matrix, owner: &str) {
+        if cfg!(debug_assertions) && self.is_invertible() && *self * *inverse != Matrix::identity()
+        {
+            panic!(
+                "{} has a stale inverse: {:?} is not the inverse of {:?}",
+                owner, inverse, self
+            );
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Matrix {
+    /// written as the 12 numbers of its first three rows, in the order `Matrix::new`
+    /// takes them. the fourth row is always `0, 0, 0, 1`, so it is left out.
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut numbers = [0.0; 12];
+        for i in 0..3 {
+            for j in 0..3 {
+                numbers[i * 4 + j] = self[(i, j)];
+            }
+            numbers[i * 4 + 3] = self.translation[i];
+        }
+        numbers.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Matrix {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Matrix, D::Error> {
+        let [n00, n01, n02, n03, n10, n11, n12, n13, n20, n21, n22, n23] =
+            <[f64; 12]>::deserialize(deserializer)?;
+        #[rustfmt::skip]
+        let matrix = Matrix::new(
+            n00, n01, n02, n03,
+            n10, n11, n12, n13,
+            n20, n21, n22, n23,
+        );
+        Ok(matrix)
+    }
+}
+
+/* conversions */
+
+impl From<[[f64; 4]; 3]> for Matrix {
+    /// builds a matrix from its first three rows, as `Matrix::new` does.
+    fn from([a, b, c]: [[f64; 4]; 3]) -> Matrix {
+        #[rustfmt::skip]
+        let matrix = Matrix::new(
+            a[0], a[1], a[2], a[3],
+            b[0], b[1], b[2], b[3],
+            c[0], c[1], c[2], c[3],
+        );
+        matrix
+    }
+}
+
+/* formatting and parsing */
+
+impl Display for Matrix {
+    /// writes the four rows on separate lines, including the implied bottom row,
+    /// with the columns aligned. `{:.3}` gives each number three decimal places.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let [a, b, c] = self.to_rows();
+        text::write_rows(f, [a, b, c, [0.0, 0.0, 0.0, 1.0]])
+    }
+}
+
+impl FromStr for Matrix {
+    type Err = ParseMathError;
+
+    /// reads four rows between `[` and `]`, as written by `Display`. the spacing and
+    /// line breaks between the numbers don't matter, but the bottom row has to be
+    /// `[ 0 0 0 1 ]`.
+    fn from_str(s: &str) -> Result<Matrix, ParseMathError> {
+        text::parse_rows(s).map(Matrix::from)
     }
 }
 
@@ -203,7 +454,7 @@ impl Index<(usize, usize)> for Matrix {
     /// be accessed via `self.translation`. also does not allow for accessing the fourth row,
     /// which is always implied to be `{ 0, 0, 0, 1 }`.
     fn index(&self, (i, j): (usize, usize)) -> &Self::Output {
-        unsafe { &std::mem::transmute::<&Matrix, &[[f64; 3]; 3]>(self)[j][i] }
+        &self[j][i]
     }
 }
 
@@ -213,7 +464,7 @@ impl IndexMut<(usize, usize)> for Matrix {
     /// be accessed via `self.translation`. also does not allow for accessing the fourth row,
     /// which is always implied to be `{ 0, 0, 0, 1 }`.
     fn index_mut(&mut self, (i, j): (usize, usize)) -> &mut f64 {
-        unsafe { &mut std::mem::transmute::<&mut Matrix, &mut [[f64; 3]; 3]>(self)[j][i] }
+        &mut self[j][i]
     }
 }
 
@@ -224,10 +475,11 @@ impl Index<usize> for Matrix {
     /// does not allow for accessing the translation column, which is a point instead of a vector and
     /// must be accessed via `self.translation`.
     fn index(&self, j: usize) -> &Self::Output {
-        unsafe {
-            std::mem::transmute::<&f64, &Vector>(
-                &std::mem::transmute::<&Matrix, &[f64; 9]>(self)[j * 3],
-            )
+        match j {
+            0 => &self.a,
+            1 => &self.b,
+            2 => &self.c,
+            _ => panic!("matrix column index out of bounds: {}", j),
         }
     }
 }
@@ -237,10 +489,11 @@ impl IndexMut<usize> for Matrix {
     /// does not allow for accessing the translation column, which is a point instead of a vector and
     /// must be accessed via `self.translation`.
     fn index_mut(&mut self, j: usize) -> &mut Vector {
-        unsafe {
-            std::mem::transmute::<&mut f64, &mut Vector>(
-                &mut std::mem::transmute::<&mut Matrix, &mut [f64; 9]>(self)[j * 3],
-            )
+        match j {
+            0 => &mut self.a,
+            1 => &mut self.b,
+            2 => &mut self.c,
+            _ => panic!("matrix column index out of bounds: {}", j),
         }
     }
 }
@@ -349,6 +602,7 @@ impl Mul<Point> for Matrix {
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison, clippy::useless_conversion)]
 mod tests {
     use super::*;
     use std::f64::consts;
@@ -369,6 +623,28 @@ mod tests {
         assert_eq!(m[(2, 2)], 11.0);
     }
 
+    #[test]
+    fn indexing_columns_and_elements() {
+        #[rustfmt::skip]
+        let mut m = Matrix::new(
+            1.0, 2.0, 3.0, 4.0,
+            5.0, 6.0, 7.0, 8.0,
+            9.0, 10.0, 11.0, 12.0,
+        );
+        assert_eq!(m[1], Vector::new(2.0, 6.0, 10.0));
+        m[2] = Vector::new(-1.0, -2.0, -3.0);
+        m[(0, 1)] = 0.5;
+        assert_eq!(m[(1, 2)], -2.0);
+        assert_eq!(m[0], Vector::new(1.0, 5.0, 9.0));
+        assert_eq!(m[1], Vector::new(0.5, 6.0, 10.0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn indexing_past_the_third_column() {
+        let _ = Matrix::identity()[3];
+    }
+
     #[test]
     fn matrix_equality() {
         #[rustfmt::skip]
@@ -481,6 +757,64 @@ mod tests {
         assert_eq!(Matrix::identity().transposed(), Matrix::identity());
     }
 
+    #[test]
+    fn converting_to_and_from_rows_and_columns() {
+        let rows = [
+            [1.0, 2.0, 3.0, 4.0],
+            [5.5, 6.5, 7.5, 8.5],
+            [9.0, 10.0, 11.0, 12.0],
+        ];
+        let m = Matrix::from(rows);
+        #[rustfmt::skip]
+        assert_eq!(m, Matrix::new(
+            1.0, 2.0,  3.0,  4.0,
+            5.5, 6.5,  7.5,  8.5,
+            9.0, 10.0, 11.0, 12.0,
+        ));
+        assert_eq!(m.to_rows(), rows);
+        assert_eq!(
+            m.to_columns(),
+            [
+                [1.0, 5.5, 9.0],
+                [2.0, 6.5, 10.0],
+                [3.0, 7.5, 11.0],
+                [4.0, 8.5, 12.0]
+            ]
+        );
+    }
+
+    #[test]
+    fn formatting_and_parsing() {
+        #[rustfmt::skip]
+        let m = Matrix::new(
+            1.0, 0.0, 0.0, 5.0,
+            0.0, 2.0, 0.0, -1.0,
+            0.0, 0.0, 1.0, 0.5,
+        );
+        let text = "\
+[ 1 0 0   5 ]
+[ 0 2 0  -1 ]
+[ 0 0 1 0.5 ]
+[ 0 0 0   1 ]";
+        assert_eq!(m.to_string(), text);
+        assert_eq!(text.parse::<Matrix>(), Ok(m));
+        assert_eq!(
+            "[1 0 0 5] [0 2 0 -1]\n\n  [ 0 0 1 0.5 ]   [0 0 0 1]\n".parse::<Matrix>(),
+            Ok(m)
+        );
+
+        let rotation = Matrix::rotation_z(consts::FRAC_PI_2);
+        assert_eq!(
+            format!("{:.2}", rotation),
+            "\
+[ 0.00 -1.00 0.00 0.00 ]
+[ 1.00  0.00 0.00 0.00 ]
+[ 0.00  0.00 1.00 0.00 ]
+[ 0.00  0.00 0.00 1.00 ]"
+        );
+        assert_eq!(rotation.to_string().parse::<Matrix>(), Ok(rotation));
+    }
+
     #[test]
     fn invertible_matrix_determinant() {
         #[rustfmt::skip]
@@ -503,6 +837,15 @@ mod tests {
         );
         assert_eq!(a.determinant(), 0.0);
         assert_eq!(a.is_invertible(), false);
+        assert!(a.is_finite());
+        assert!(!a.unchecked_inverse().is_finite());
+        assert_eq!(a.try_inverse(), None);
+        assert!(matches!(
+            a.checked_inverse(),
+            Err(RayTracerError::NonInvertibleMatrix(m)) if m == a
+        ));
+        // there's no inverse to compare a stale one to.
+        a.debug_assert_inverse(&Matrix::identity(), "Matrix");
     }
 
     #[test]
@@ -522,6 +865,41 @@ mod tests {
                 -1.0 / 4.0, 7.0 / 26.0,   1.0 / 26.0,    -9.0 / 26.0,
             ),
         );
+        assert_eq!(a.checked_inverse().unwrap(), a.inverse());
+        assert_eq!(a.try_inverse(), Some(a.inverse()));
+    }
+
+    #[test]
+    fn zero_scale_has_no_inverse() {
+        assert_eq!(Matrix::scaling(0.0, 0.0, 0.0).try_inverse(), None);
+        assert_eq!(Matrix::scaling(2.0, 0.0, 1.0).try_inverse(), None);
+        // columns that all but lie in one plane are as good as flat.
+        let sheared = Matrix::with_columns(
+            Vector::new(1.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+            Vector::new(1.0, 1.0, 1e-6),
+            Point::zero(),
+        );
+        assert_eq!(sheared.try_inverse(), None);
+    }
+
+    #[test]
+    fn small_transforms_have_inverses() {
+        // the determinants are tiny, but the columns are still perpendicular.
+        for small in [
+            Matrix::translation(1.0, 2.0, 3.0) * Matrix::scaling(0.01, 0.01, 0.01),
+            Matrix::scaling(2.0, 1e-6, 1.0),
+        ] {
+            assert!(small.is_invertible());
+            assert_eq!(small.try_inverse().unwrap() * small, Matrix::identity());
+        }
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "which has no inverse")]
+    fn inverting_a_singular_matrix_is_caught() {
+        Matrix::scaling(1.0, 0.0, 1.0).inverse();
     }
 
     #[test]
@@ -666,6 +1044,119 @@ mod tests {
         assert_eq!(full_quarter * p, Point::new(-1.0, 0.0, 0.0));
     }
 
+    #[test]
+    fn rotation_between_perpendicular_directions() {
+        let x = Vector::new(1.0, 0.0, 0.0);
+        let y = Vector::new(0.0, 1.0, 0.0);
+        let z = Vector::new(0.0, 0.0, 1.0);
+        assert_eq!(
+            Matrix::rotation_between(x, y),
+            Matrix::rotation_z(consts::PI / 2.0)
+        );
+        assert_eq!(
+            Matrix::rotation_between(y, z * 3.0),
+            Matrix::rotation_x(consts::PI / 2.0)
+        );
+        // the smallest rotation doesn't move the axis both are perpendicular to.
+        let rotation = Matrix::rotation_between(Vector::new(1.0, 1.0, 0.0), z);
+        assert_eq!(rotation * Vector::new(1.0, 1.0, 0.0).normalized(), z);
+        assert_eq!(
+            rotation * Vector::new(1.0, -1.0, 0.0),
+            Vector::new(1.0, -1.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn rotation_between_nearly_parallel_directions() {
+        let from = Vector::new(1.0, 2.0, 3.0);
+        assert_eq!(
+            Matrix::rotation_between(from, from * 2.0),
+            Matrix::identity()
+        );
+        for nudge in [1e-3, 1e-7, 1e-12] {
+            let to = from + Vector::new(nudge, -nudge, 0.0);
+            let rotation = Matrix::rotation_between(from, to);
+            assert!(rotation.is_finite());
+            assert_eq!(rotation * from.normalized(), to.normalized());
+            assert!((rotation.determinant() - 1.0).abs() < EPSILON);
+        }
+    }
+
+    #[test]
+    fn rotation_between_opposite_directions() {
+        for from in [
+            Vector::new(0.0, 1.0, 0.0),
+            Vector::new(1.0, 0.0, 0.0),
+            Vector::new(-2.0, 0.5, 1.0),
+        ] {
+            for to in [-from, -from + Vector::new(1e-9, 0.0, 0.0)] {
+                let rotation = Matrix::rotation_between(from, to);
+                assert!(rotation.is_finite());
+                assert_eq!(rotation * from.normalized(), to.normalized());
+                assert!((rotation.determinant() - 1.0).abs() < EPSILON);
+                assert_eq!(rotation * rotation, Matrix::identity());
+            }
+        }
+    }
+
+    #[test]
+    fn orthonormalizing_a_drifting_rotation() {
+        // a rotation put together from many small steps, with a little error in each.
+        let step = Matrix::rotation_x(0.01) * Matrix::rotation_y(0.02) * Matrix::rotation_z(0.03);
+        let mut drifted = Matrix::translation(1.0, 2.0, 3.0);
+        for _ in 0..1000 {
+            drifted = step * drifted;
+            drifted[(0, 1)] += 1e-6;
+            drifted[(2, 2)] *= 1.0 + 1e-6;
+        }
+        assert!(!drifted.is_orthonormal(1e-6));
+
+        let fixed = drifted.orthonormalized();
+        assert!(fixed.is_orthonormal(1e-12));
+        assert!((fixed.determinant() - 1.0).abs() < 1e-12);
+        assert_eq!(fixed.translation, drifted.translation);
+        // it is still close to the rotation it drifted from.
+        assert!((fixed[0] - drifted[0]).magnitude() < 0.01);
+    }
+
+    #[test]
+    fn orthonormal_matrices_are_left_alone() {
+        let m = Matrix::translation(4.0, -2.0, 1.0)
+            * Matrix::rotation_y(1.2)
+            * Matrix::rotation_x(-0.4)
+            * Matrix::reflection(Vector::new(1.0, 1.0, 1.0), Point::zero());
+        assert!(m.is_orthonormal(EPSILON));
+        assert_eq!(m.orthonormalized(), m);
+        assert_eq!(*Matrix::identity().orthonormalize(), Matrix::identity());
+        assert!(!Matrix::scaling(1.0, 2.0, 1.0).is_orthonormal(EPSILON));
+    }
+
+    #[test]
+    fn reflection_across_the_floor() {
+        let floor = Matrix::reflection(Vector::new(0.0, 2.0, 0.0), Point::new(3.0, 0.0, -1.0));
+        assert_eq!(floor, Matrix::scaling(1.0, -1.0, 1.0));
+    }
+
+    #[test]
+    fn reflection_across_a_plane_away_from_the_origin() {
+        let normal = Vector::new(1.0, 1.0, 0.0);
+        let on_plane = Point::new(2.0, 0.0, 5.0);
+        let mirror = Matrix::reflection(normal, on_plane);
+        // points on the plane stay put.
+        for p in [
+            on_plane,
+            Point::new(0.0, 2.0, 0.0),
+            Point::new(3.0, -1.0, -4.0),
+        ] {
+            assert_eq!(mirror * p, p);
+        }
+        // the origin is 2/sqrt(2) behind the plane, and ends up as far in front.
+        assert_eq!(mirror * Point::zero(), Point::new(2.0, 2.0, 0.0));
+        assert_eq!(mirror * normal, -normal);
+        assert_eq!(mirror * mirror, Matrix::identity());
+        assert!(mirror.determinant() < 0.0);
+    }
+
     #[test]
     fn transformations_in_sequence() {
         let p1 = Point::new(1.0, 0.0, 1.0);
@@ -702,4 +1193,25 @@ mod tests {
             .rotate_x(consts::PI / 2.0);
         assert_eq!(a * b * c, d);
     }
+
+    #[test]
+    fn consistent_inverse_passes() {
+        let a = Matrix::translation(1.0, 2.0, 3.0) * Matrix::scaling(2.0, 4.0, 8.0);
+        a.debug_assert_inverse(&a.inverse(), "Matrix");
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "Owner has a stale inverse")]
+    fn stale_inverse_panics_in_debug_builds() {
+        let a = Matrix::translation(1.0, 2.0, 3.0);
+        a.debug_assert_inverse(&Matrix::identity(), "Owner");
+    }
+
+    #[test]
+    #[cfg(not(debug_assertions))]
+    fn stale_inverse_is_not_checked_in_release_builds() {
+        let a = Matrix::translation(1.0, 2.0, 3.0);
+        a.debug_assert_inverse(&Matrix::identity(), "Owner");
+    }
 }
@@ -0,0 +1,474 @@
+use std::{
+    convert::TryFrom,
+    ops::{Index, IndexMut, Mul},
+};
+
+use super::{matrix::Matrix, point::Point, vector::Vector, EPSILON};
+use crate::RayTracerError;
+
+/// a general 4-by-4 matrix. unlike `Matrix`, its fourth row can be anything, which
+/// makes projective transforms such as perspective projections possible, at the cost
+/// of storing and multiplying all sixteen elements. every `Matrix` converts into one,
+/// and converts back if its fourth row is still `{ 0, 0, 0, 1 }`.
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Matrix4 {
+    rows: [[f64; 4]; 4],
+}
+
+impl Matrix4 {
+    pub fn new(rows: [[f64; 4]; 4]) -> Matrix4 {
+        Matrix4 { rows }
+    }
+
+    pub fn identity() -> Matrix4 {
+        Matrix4::new([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn transposed(&self) -> Matrix4 {
+        let mut transposed = *self;
+        for i in 0..4 {
+            for j in 0..4 {
+                transposed[(i, j)] = self[(j, i)];
+            }
+        }
+        transposed
+    }
+
+    pub fn transpose(&mut self) -> &mut Matrix4 {
+        *self = self.transposed();
+        self
+    }
+
+    /// the 3-by-3 matrix left when `row` and `column` are removed.
+    pub fn submatrix(&self, row: usize, column: usize) -> [[f64; 3]; 3] {
+        let mut submatrix = [[0.0; 3]; 3];
+        for (i, from) in (0..4).filter(|&i| i != row).enumerate() {
+            for (j, to) in (0..4).filter(|&j| j != column).enumerate() {
+                submatrix[i][j] = self[(from, to)];
+            }
+        }
+        submatrix
+    }
+
+    /// the determinant of the submatrix without `row` and `column`.
+    pub fn minor(&self, row: usize, column: usize) -> f64 {
+        let m = self.submatrix(row, column);
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+
+    /// the minor at `row` and `column`, negated when they add up to an odd number.
+    pub fn cofactor(&self, row: usize, column: usize) -> f64 {
+        let minor = self.minor(row, column);
+        if (row + column).is_multiple_of(2) {
+            minor
+        } else {
+            -minor
+        }
+    }
+
+    /// calculates the determinant by expanding along the first row.
+    pub fn determinant(&self) -> f64 {
+        (0..4).map(|j| self[(0, j)] * self.cofactor(0, j)).sum()
+    }
+
+    /// uses the determinant to say if an inverse exists, comparing it to the product of
+    /// the column lengths in the same way as `Matrix::is_invertible`.
+    pub fn is_invertible(&self) -> bool {
+        let volume: f64 = (0..4)
+            .map(|j| (0..4).map(|i| self[(i, j)].powi(2)).sum::<f64>().sqrt())
+            .product();
+        EPSILON * volume < self.determinant().abs()
+    }
+
+    /// the inverse of this matrix, which must have one.
+    ///
+    /// # panics
+    ///
+    /// in debug builds, panics if the matrix has no inverse, like `Matrix::inverse`.
+    pub fn inverse(&self) -> Matrix4 {
+        debug_assert!(
+            self.is_invertible(),
+            "tried to invert {:?}, which has no inverse",
+            self
+        );
+        self.unchecked_inverse()
+    }
+
+    /// the inverse of this matrix, or `None` if it has none.
+    pub fn try_inverse(&self) -> Option<Matrix4> {
+        if self.is_invertible() {
+            Some(self.unchecked_inverse())
+        } else {
+            None
+        }
+    }
+
+    /// the transpose of the matrix of cofactors, divided by the determinant.
+    fn unchecked_inverse(&self) -> Matrix4 {
+        let determinant = self.determinant();
+        let mut inverse = Matrix4::new([[0.0; 4]; 4]);
+        for i in 0..4 {
+            for j in 0..4 {
+                inverse[(j, i)] = self.cofactor(i, j) / determinant;
+            }
+        }
+        inverse
+    }
+
+    /// checks that the fourth row is `{ 0, 0, 0, 1 }`, so that the matrix can become a
+    /// `Matrix`.
+    pub fn is_affine(&self) -> bool {
+        let row = self.rows[3];
+        row[0].abs() < EPSILON
+            && row[1].abs() < EPSILON
+            && row[2].abs() < EPSILON
+            && (row[3] - 1.0).abs() < EPSILON
+    }
+}
+
+impl From<Matrix> for Matrix4 {
+    fn from(matrix: Matrix) -> Matrix4 {
+        let [a, b, c] = matrix.to_rows();
+        Matrix4::new([a, b, c, [0.0, 0.0, 0.0, 1.0]])
+    }
+}
+
+impl TryFrom<Matrix4> for Matrix {
+    type Error = RayTracerError;
+
+    /// drops the fourth row, which must be `{ 0, 0, 0, 1 }`.
+    fn try_from(matrix: Matrix4) -> Result<Matrix, RayTracerError> {
+        if !matrix.is_affine() {
+            return Err(RayTracerError::NotAffine(Box::new(matrix)));
+        }
+        let [a, b, c, _] = matrix.rows;
+        Ok(Matrix::from([a, b, c]))
+    }
+}
+
+impl PartialEq for Matrix4 {
+    /// test for equality using approximate comparison of floating point numbers.
+    fn eq(&self, other: &Self) -> bool {
+        (0..4).all(|i| (0..4).all(|j| (self[(i, j)] - other[(i, j)]).abs() < EPSILON))
+    }
+}
+
+impl Index<(usize, usize)> for Matrix4 {
+    type Output = f64;
+
+    /// access the element in row `i` and column `j`.
+    fn index(&self, (i, j): (usize, usize)) -> &Self::Output {
+        &self.rows[i][j]
+    }
+}
+
+impl IndexMut<(usize, usize)> for Matrix4 {
+    /// access the element in row `i` and column `j`.
+    fn index_mut(&mut self, (i, j): (usize, usize)) -> &mut f64 {
+        &mut self.rows[i][j]
+    }
+}
+
+impl Mul for Matrix4 {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self::Output {
+        let mut product = Matrix4::new([[0.0; 4]; 4]);
+        for i in 0..4 {
+            for j in 0..4 {
+                product[(i, j)] = (0..4).map(|k| self[(i, k)] * other[(k, j)]).sum();
+            }
+        }
+        product
+    }
+}
+
+impl Mul<Vector> for Matrix4 {
+    type Output = Vector;
+
+    /// a direction has no w to divide by, so only the first three rows affect it.
+    fn mul(self, vector: Vector) -> Self::Output {
+        let row = |i: usize| {
+            self[(i, 0)] * vector[0] + self[(i, 1)] * vector[1] + self[(i, 2)] * vector[2]
+        };
+        Vector::new(row(0), row(1), row(2))
+    }
+}
+
+impl Mul<Point> for Matrix4 {
+    type Output = Point;
+
+    /// transforms the point, then divides it by the w the fourth row gives it. a point
+    /// that ends up with a w of 0 is sent off to infinity.
+    fn mul(self, point: Point) -> Self::Output {
+        let row = |i: usize| {
+            self[(i, 0)] * point[0]
+                + self[(i, 1)] * point[1]
+                + self[(i, 2)] * point[2]
+                + self[(i, 3)]
+        };
+        let w = row(3);
+        Point::new(row(0) / w, row(1) / w, row(2) / w)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn constructing_and_inspecting_a_matrix() {
+        let m = Matrix4::new([
+            [1.0, 2.0, 3.0, 4.0],
+            [5.5, 6.5, 7.5, 8.5],
+            [9.0, 10.0, 11.0, 12.0],
+            [13.5, 14.5, 15.5, 16.5],
+        ]);
+        assert_eq!(m[(0, 0)], 1.0);
+        assert_eq!(m[(0, 3)], 4.0);
+        assert_eq!(m[(1, 0)], 5.5);
+        assert_eq!(m[(1, 2)], 7.5);
+        assert_eq!(m[(2, 2)], 11.0);
+        assert_eq!(m[(3, 0)], 13.5);
+        assert_eq!(m[(3, 2)], 15.5);
+    }
+
+    #[test]
+    fn multiplying_two_matrices() {
+        let a = Matrix4::new([
+            [1.0, 2.0, 3.0, 4.0],
+            [5.0, 6.0, 7.0, 8.0],
+            [9.0, 8.0, 7.0, 6.0],
+            [5.0, 4.0, 3.0, 2.0],
+        ]);
+        let b = Matrix4::new([
+            [-2.0, 1.0, 2.0, 3.0],
+            [3.0, 2.0, 1.0, -1.0],
+            [4.0, 3.0, 6.0, 5.0],
+            [1.0, 2.0, 7.0, 8.0],
+        ]);
+        assert_eq!(
+            a * b,
+            Matrix4::new([
+                [20.0, 22.0, 50.0, 48.0],
+                [44.0, 54.0, 114.0, 108.0],
+                [40.0, 58.0, 110.0, 102.0],
+                [16.0, 26.0, 46.0, 42.0],
+            ])
+        );
+        assert_eq!(a * Matrix4::identity(), a);
+    }
+
+    #[test]
+    fn multiplying_by_a_point() {
+        let a = Matrix4::new([
+            [1.0, 2.0, 3.0, 4.0],
+            [2.0, 4.0, 4.0, 2.0],
+            [8.0, 6.0, 4.0, 1.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        assert_eq!(a * Point::new(1.0, 2.0, 3.0), Point::new(18.0, 24.0, 33.0));
+        assert_eq!(
+            a * Vector::new(1.0, 2.0, 3.0),
+            Vector::new(14.0, 22.0, 32.0)
+        );
+    }
+
+    #[test]
+    fn points_are_divided_by_w() {
+        // a pinhole camera at the origin, looking down +z at a screen one unit away.
+        let projection = Matrix4::new([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+        ]);
+        assert_eq!(
+            projection * Point::new(2.0, -4.0, 4.0),
+            Point::new(0.5, -1.0, 1.0)
+        );
+        assert_eq!(
+            projection * Point::new(1.0, 1.0, 0.5),
+            Point::new(2.0, 2.0, 1.0)
+        );
+        assert!(!projection.is_affine());
+    }
+
+    #[test]
+    fn transposing() {
+        let a = Matrix4::new([
+            [0.0, 9.0, 3.0, 0.0],
+            [9.0, 8.0, 0.0, 8.0],
+            [1.0, 8.0, 5.0, 3.0],
+            [0.0, 0.0, 5.0, 8.0],
+        ]);
+        assert_eq!(
+            a.transposed(),
+            Matrix4::new([
+                [0.0, 9.0, 1.0, 0.0],
+                [9.0, 8.0, 8.0, 0.0],
+                [3.0, 0.0, 5.0, 5.0],
+                [0.0, 8.0, 3.0, 8.0],
+            ])
+        );
+        assert_eq!(Matrix4::identity().transposed(), Matrix4::identity());
+    }
+
+    #[test]
+    fn submatrix_of_a_4x4_matrix() {
+        let a = Matrix4::new([
+            [-6.0, 1.0, 1.0, 6.0],
+            [-8.0, 5.0, 8.0, 6.0],
+            [-1.0, 0.0, 8.0, 2.0],
+            [-7.0, 1.0, -1.0, 1.0],
+        ]);
+        assert_eq!(
+            a.submatrix(2, 1),
+            [[-6.0, 1.0, 6.0], [-8.0, 8.0, 6.0], [-7.0, -1.0, 1.0]]
+        );
+    }
+
+    #[test]
+    fn determinant_of_a_4x4_matrix() {
+        let a = Matrix4::new([
+            [-2.0, -8.0, 3.0, 5.0],
+            [-3.0, 1.0, 7.0, 3.0],
+            [1.0, 2.0, -9.0, 6.0],
+            [-6.0, 7.0, 7.0, -9.0],
+        ]);
+        assert_eq!(a.cofactor(0, 0), 690.0);
+        assert_eq!(a.cofactor(0, 1), 447.0);
+        assert_eq!(a.cofactor(0, 2), 210.0);
+        assert_eq!(a.cofactor(0, 3), 51.0);
+        assert_eq!(a.determinant(), -4071.0);
+    }
+
+    #[test]
+    fn testing_for_invertibility() {
+        let a = Matrix4::new([
+            [6.0, 4.0, 4.0, 4.0],
+            [5.0, 5.0, 7.0, 6.0],
+            [4.0, -9.0, 3.0, -7.0],
+            [9.0, 1.0, 7.0, -6.0],
+        ]);
+        assert_eq!(a.determinant(), -2120.0);
+        assert!(a.is_invertible());
+
+        let b = Matrix4::new([
+            [-4.0, 2.0, -2.0, -3.0],
+            [9.0, 6.0, 2.0, 6.0],
+            [0.0, -5.0, 1.0, -5.0],
+            [0.0, 0.0, 0.0, 0.0],
+        ]);
+        assert_eq!(b.determinant(), 0.0);
+        assert!(!b.is_invertible());
+        assert_eq!(b.try_inverse(), None);
+    }
+
+    #[test]
+    fn inverting_a_matrix() {
+        let a = Matrix4::new([
+            [-5.0, 2.0, 6.0, -8.0],
+            [1.0, -5.0, 1.0, 8.0],
+            [7.0, 7.0, -6.0, -7.0],
+            [1.0, -3.0, 7.0, 4.0],
+        ]);
+        let b = a.inverse();
+        assert_eq!(a.determinant(), 532.0);
+        assert_eq!(a.cofactor(2, 3), -160.0);
+        assert_eq!(b[(3, 2)], -160.0 / 532.0);
+        assert_eq!(a.cofactor(3, 2), 105.0);
+        assert_eq!(b[(2, 3)], 105.0 / 532.0);
+        assert_eq!(
+            b,
+            Matrix4::new([
+                [0.21805, 0.45113, 0.24060, -0.04511],
+                [-0.80827, -1.45677, -0.44361, 0.52068],
+                [-0.07895, -0.22368, -0.05263, 0.19737],
+                [-0.52256, -0.81391, -0.30075, 0.30639],
+            ])
+        );
+        assert_eq!(a.try_inverse(), Some(b));
+    }
+
+    #[test]
+    fn inverting_more_matrices() {
+        let a = Matrix4::new([
+            [8.0, -5.0, 9.0, 2.0],
+            [7.0, 5.0, 6.0, 1.0],
+            [-6.0, 0.0, 9.0, 6.0],
+            [-3.0, 0.0, -9.0, -4.0],
+        ]);
+        assert_eq!(
+            a.inverse(),
+            Matrix4::new([
+                [-0.15385, -0.15385, -0.28205, -0.53846],
+                [-0.07692, 0.12308, 0.02564, 0.03077],
+                [0.35897, 0.35897, 0.43590, 0.92308],
+                [-0.69231, -0.69231, -0.76923, -1.92308],
+            ])
+        );
+
+        let b = Matrix4::new([
+            [9.0, 3.0, 0.0, 9.0],
+            [-5.0, -2.0, -6.0, -3.0],
+            [-4.0, 9.0, 6.0, 4.0],
+            [-7.0, 6.0, 6.0, 2.0],
+        ]);
+        assert_eq!(
+            b.inverse(),
+            Matrix4::new([
+                [-0.04074, -0.07778, 0.14444, -0.22222],
+                [-0.07778, 0.03333, 0.36667, -0.33333],
+                [-0.02901, -0.14630, -0.10926, 0.12963],
+                [0.17778, 0.06667, -0.26667, 0.33333],
+            ])
+        );
+    }
+
+    #[test]
+    fn multiplying_a_product_by_its_inverse() {
+        let a = Matrix4::new([
+            [3.0, -9.0, 7.0, 3.0],
+            [3.0, -8.0, 2.0, -9.0],
+            [-4.0, 4.0, 4.0, 1.0],
+            [-6.0, 5.0, -1.0, 1.0],
+        ]);
+        let b = Matrix4::new([
+            [8.0, 2.0, 2.0, 2.0],
+            [3.0, -1.0, 7.0, 0.0],
+            [7.0, 0.0, 5.0, 4.0],
+            [6.0, -2.0, 0.0, 5.0],
+        ]);
+        let c = a * b;
+        assert_eq!(c * b.inverse(), a);
+    }
+
+    #[test]
+    fn converting_to_and_from_affine_matrices() {
+        let affine = Matrix::translation(1.0, 2.0, 3.0) * Matrix::rotation_y(0.5);
+        let full = Matrix4::from(affine);
+        assert!(full.is_affine());
+        assert_eq!(
+            full * Point::new(1.0, -1.0, 2.0),
+            affine * Point::new(1.0, -1.0, 2.0)
+        );
+        assert_eq!(Matrix4::from(affine.inverse()), full.inverse());
+        assert_eq!(Matrix::try_from(full).unwrap(), affine);
+
+        let mut projective = full;
+        projective[(3, 2)] = 1.0;
+        assert!(matches!(
+            Matrix::try_from(projective),
+            Err(RayTracerError::NotAffine(m)) if *m == projective
+        ));
+    }
+}
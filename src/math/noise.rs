@@ -0,0 +1,108 @@
+use crate::math::Point;
+
+/// smooth gradient noise at `point`, from ken perlin's "improving noise" (2002).
+///
+/// the noise is 0 at every point with integer coordinates, changes smoothly between
+/// them, and stays between -1 and 1. rather than a shuffled table, the gradient at
+/// each lattice point is picked by hashing its coordinates, so the noise doesn't repeat.
+pub fn perlin(point: Point) -> f64 {
+    let (x, y, z) = (point[0], point[1], point[2]);
+    let (xi, yi, zi) = (x.floor(), y.floor(), z.floor());
+    let (xf, yf, zf) = (x - xi, y - yi, z - zi);
+    let (xi, yi, zi) = (xi as i64, yi as i64, zi as i64);
+
+    let (u, v, w) = (fade(xf), fade(yf), fade(zf));
+    let corner = |dx: i64, dy: i64, dz: i64| {
+        gradient(
+            hash(xi + dx, yi + dy, zi + dz),
+            xf - dx as f64,
+            yf - dy as f64,
+            zf - dz as f64,
+        )
+    };
+
+    lerp(
+        w,
+        lerp(
+            v,
+            lerp(u, corner(0, 0, 0), corner(1, 0, 0)),
+            lerp(u, corner(0, 1, 0), corner(1, 1, 0)),
+        ),
+        lerp(
+            v,
+            lerp(u, corner(0, 0, 1), corner(1, 0, 1)),
+            lerp(u, corner(0, 1, 1), corner(1, 1, 1)),
+        ),
+    )
+}
+
+/// eases `t` from 0 to 1 so that the noise has no creases at lattice points.
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    a + t * (b - a)
+}
+
+fn hash(x: i64, y: i64, z: i64) -> u64 {
+    let mut h = (x as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15)
+        ^ (y as u64).wrapping_mul(0xc2b2_ae3d_27d4_eb4f)
+        ^ (z as u64).wrapping_mul(0x1656_67b1_9e37_79f9);
+    h ^= h >> 30;
+    h = h.wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    h ^= h >> 27;
+    h = h.wrapping_mul(0x94d0_49bb_1331_11eb);
+    h ^ (h >> 31)
+}
+
+/// the dot product of `(x, y, z)` with one of the twelve edges of a cube, picked by
+/// `hash`.
+fn gradient(hash: u64, x: f64, y: f64, z: f64) -> f64 {
+    match hash % 12 {
+        0 => x + y,
+        1 => -x + y,
+        2 => x - y,
+        3 => -x - y,
+        4 => x + z,
+        5 => -x + z,
+        6 => x - z,
+        7 => -x - z,
+        8 => y + z,
+        9 => -y + z,
+        10 => y - z,
+        _ => -y - z,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_on_lattice() {
+        for &(x, y, z) in [(0.0, 0.0, 0.0), (3.0, -2.0, 7.0), (-1.0, 5.0, -9.0)].iter() {
+            assert_eq!(perlin(Point::new(x, y, z)), 0.0);
+        }
+    }
+
+    #[test]
+    fn bounded_and_varied() {
+        let samples: Vec<f64> = (0..1000)
+            .map(|i| {
+                let t = i as f64 * 0.173;
+                perlin(Point::new(t, t * 0.61 - 3.0, 5.0 - t * 1.37))
+            })
+            .collect();
+        assert!(samples.iter().all(|n| -1.0 <= *n && *n <= 1.0));
+        assert!(samples.iter().any(|n| *n > 0.1));
+        assert!(samples.iter().any(|n| *n < -0.1));
+    }
+
+    #[test]
+    fn continuous() {
+        let p = Point::new(1.3, -0.7, 2.2);
+        let q = Point::new(1.3001, -0.7, 2.2);
+        assert!((perlin(p) - perlin(q)).abs() < 0.001);
+    }
+}
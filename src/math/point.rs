@@ -1,9 +1,16 @@
-use std::ops::{Add, Index, IndexMut, Sub};
+use std::{
+    convert::TryFrom,
+    fmt::{self, Display, Formatter},
+    ops::{Add, Index, IndexMut, Sub},
+    str::FromStr,
+};
 
-use super::vector::Vector;
+use super::{text, vector::Vector, ParseMathError};
+use crate::RayTracerError;
 
 /// 4-dimensional vector which always has a fourth component of 1.
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Point(Vector);
 
 impl Point {
@@ -18,6 +25,134 @@ impl Point {
     pub fn with_translation(t: Vector) -> Point {
         Point(t)
     }
+
+    /// the smaller of each pair of coordinates, which is the lowest corner of the box
+    /// the two points span.
+    pub fn min(&self, other: &Point) -> Point {
+        Point(self.0.min(&other.0))
+    }
+
+    /// the larger of each pair of coordinates, which is the highest corner of the box
+    /// the two points span.
+    pub fn max(&self, other: &Point) -> Point {
+        Point(self.0.max(&other.0))
+    }
+
+    /// the absolute value of each coordinate, which mirrors the point into the octant
+    /// where none is negative.
+    pub fn abs(&self) -> Point {
+        Point(self.0.abs())
+    }
+
+    /// the point moved into the box between `min` and `max`, along each axis where it
+    /// lies outside. where a lower bound is above its upper bound, the upper bound wins,
+    /// as in `Vector::clamp`.
+    pub fn clamp(&self, min: &Point, max: &Point) -> Point {
+        Point(self.0.clamp(&min.0, &max.0))
+    }
+
+    /// the largest of the three coordinates.
+    pub fn max_component(&self) -> f64 {
+        self.0.max_component()
+    }
+
+    /// the smallest of the three coordinates.
+    pub fn min_component(&self) -> f64 {
+        self.0.min_component()
+    }
+
+    /// the point `t` of the way from this one to `other`. `t` isn't clamped, so values
+    /// outside of `[0, 1]` carry on along the line past either point.
+    pub fn lerp(self, other: Point, t: f64) -> Point {
+        Point(self.0.lerp(other.0, t))
+    }
+}
+
+/* conversions */
+
+/// ```
+/// use std::convert::TryFrom;
+///
+/// use ray_tracer_challenge::math::{Matrix, Point};
+///
+/// // a corner of a model, as read from a line of a text file.
+/// let mut parsed = [0.0; 3];
+/// for (number, word) in parsed.iter_mut().zip("1.5 0 -2".split_whitespace()) {
+///     *number = word.parse().unwrap();
+/// }
+/// let corner = Point::from(parsed);
+/// assert_eq!(corner, Point::new(1.5, 0.0, -2.0));
+///
+/// // slices are checked for having three numbers.
+/// let numbers: Vec<f64> = "1 2".split(' ').map(|word| word.parse().unwrap()).collect();
+/// assert!(Point::try_from(&numbers[..]).is_err());
+///
+/// let raised = Matrix::translation(0.0, 1.0, 0.0) * corner;
+/// assert_eq!(<[f64; 3]>::from(raised), [1.5, 1.0, -2.0]);
+/// ```
+impl From<[f64; 3]> for Point {
+    fn from(coordinates: [f64; 3]) -> Point {
+        Point(Vector::from(coordinates))
+    }
+}
+
+impl From<(f64, f64, f64)> for Point {
+    fn from(coordinates: (f64, f64, f64)) -> Point {
+        Point(Vector::from(coordinates))
+    }
+}
+
+impl From<Point> for [f64; 3] {
+    fn from(point: Point) -> [f64; 3] {
+        point.0.into()
+    }
+}
+
+impl From<Point> for (f64, f64, f64) {
+    fn from(point: Point) -> (f64, f64, f64) {
+        point.0.into()
+    }
+}
+
+/// the point the vector reaches from the origin.
+impl From<Vector> for Point {
+    fn from(vector: Vector) -> Point {
+        Point(vector)
+    }
+}
+
+/// the vector from the origin to the point.
+impl From<Point> for Vector {
+    fn from(point: Point) -> Vector {
+        point.0
+    }
+}
+
+impl TryFrom<&[f64]> for Point {
+    type Error = RayTracerError;
+
+    /// reads the three coordinates in `numbers`, which mustn't hold any more or fewer.
+    fn try_from(numbers: &[f64]) -> Result<Point, RayTracerError> {
+        Vector::try_from(numbers).map(Point)
+    }
+}
+
+/* formatting and parsing */
+
+impl Display for Point {
+    /// writes `(x, y, z)`, so `{:.2}` gives each coordinate two decimal places.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        text::write_triple(f, (*self).into())
+    }
+}
+
+impl FromStr for Point {
+    type Err = ParseMathError;
+
+    /// reads `(x, y, z)`, as written by `Display`.
+    fn from_str(s: &str) -> Result<Point, ParseMathError> {
+        text::parse_triple(s).map(Point::from)
+    }
 }
 
 /* indexing operations */
@@ -68,6 +203,54 @@ impl Sub for Point {
 mod tests {
     use super::*;
 
+    #[test]
+    fn converting_to_and_from_arrays_tuples_and_vectors() {
+        let p = Point::new(3.0, -1.0, 0.5);
+        assert_eq!(Point::from([3.0, -1.0, 0.5]), p);
+        assert_eq!(Point::from((3.0, -1.0, 0.5)), p);
+        assert_eq!(<[f64; 3]>::from(p), [3.0, -1.0, 0.5]);
+        assert_eq!(<(f64, f64, f64)>::from(p), (3.0, -1.0, 0.5));
+        assert_eq!(Point::try_from(&[3.0, -1.0, 0.5][..]).unwrap(), p);
+        assert!(Point::try_from(&[3.0, -1.0][..]).is_err());
+
+        let v = Vector::from(p);
+        assert_eq!(v, p - Point::zero());
+        assert_eq!(Point::from(v), p);
+    }
+
+    #[test]
+    fn formatting_and_parsing() {
+        let p = Point::new(-3.0, 0.125, 10.0);
+        assert_eq!(p.to_string(), "(-3, 0.125, 10)");
+        assert_eq!(format!("{:.1}", p), "(-3.0, 0.1, 10.0)");
+        assert_eq!(p.to_string().parse::<Point>(), Ok(p));
+    }
+
+    #[test]
+    fn component_wise_operations() {
+        let a = Point::new(-1.0, 2.0, 0.0);
+        let b = Point::new(3.0, -2.0, 0.5);
+        assert_eq!(a.min(&b), Point::new(-1.0, -2.0, 0.0));
+        assert_eq!(a.max(&b), Point::new(3.0, 2.0, 0.5));
+        assert_eq!(b.abs(), Point::new(3.0, 2.0, 0.5));
+        assert_eq!(b.max_component(), 3.0);
+        assert_eq!(b.min_component(), -2.0);
+        assert_eq!(
+            Point::new(5.0, -5.0, 0.25).clamp(&a.min(&b), &a.max(&b)),
+            Point::new(3.0, -2.0, 0.25)
+        );
+    }
+
+    #[test]
+    fn interpolating_points() {
+        let a = Point::new(0.0, 1.0, 2.0);
+        let b = Point::new(4.0, 1.0, -2.0);
+        assert_eq!(a.lerp(b, 0.0), a);
+        assert_eq!(a.lerp(b, 0.5), Point::new(2.0, 1.0, 0.0));
+        assert_eq!(a.lerp(b, 1.0), b);
+        assert_eq!(a.lerp(b, 2.0), Point::new(8.0, 1.0, -6.0));
+    }
+
     #[test]
     fn subtract_two_points() {
         let p1 = Point::new(3.0, 2.0, 1.0);
@@ -0,0 +1,109 @@
+use super::Point;
+
+/// a tiny, fast random number generator from george marsaglia's "xorshift rngs" (2003),
+/// using the xorshift64* variant.
+///
+/// it is nowhere near good enough for cryptography, but it is plenty for picking
+/// sample positions, and the same seed always gives the same sequence.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct XorShift {
+    state: u64,
+}
+
+impl XorShift {
+    /// any seed is fine, including 0. the seed is scrambled first, so that seeds which
+    /// are close together still give unrelated sequences.
+    pub fn new(seed: u64) -> XorShift {
+        let state = splitmix(seed);
+        XorShift {
+            // xorshift gets stuck at 0, and nowhere else.
+            state: if state == 0 {
+                0x9e37_79b9_7f4a_7c15
+            } else {
+                state
+            },
+        }
+    }
+
+    /// a generator picked by `seed` and the point it is used at, rather than by the
+    /// order points are visited in, so that images don't depend on that order.
+    pub fn at_point(seed: u64, point: Point) -> XorShift {
+        let mut state = seed;
+        for i in 0..3 {
+            state = splitmix(state ^ point[i].to_bits());
+        }
+        XorShift::new(state)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// a number from 0 up to, but not including, 1.
+    pub fn next_f64(&mut self) -> f64 {
+        // the top 53 bits fill the mantissa exactly.
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// one step of sebastiano vigna's splitmix64, which spreads the bits of `x` around.
+fn splitmix(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_sequence() {
+        let mut a = XorShift::new(42);
+        let mut b = XorShift::new(42);
+        for _ in 0..100 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+    }
+
+    #[test]
+    fn nearby_seeds_differ() {
+        let mut a = XorShift::new(1);
+        let mut b = XorShift::new(2);
+        assert_ne!(a.next_u64(), b.next_u64());
+    }
+
+    #[test]
+    fn points_pick_their_own_sequence() {
+        let a = Point::new(1.0, 2.0, 3.0);
+        let b = Point::new(1.0, 2.0, 3.000001);
+        assert_eq!(XorShift::at_point(5, a), XorShift::at_point(5, a));
+        assert_ne!(XorShift::at_point(5, a), XorShift::at_point(5, b));
+        assert_ne!(XorShift::at_point(5, a), XorShift::at_point(6, a));
+    }
+
+    #[test]
+    fn zero_seed_is_usable() {
+        let mut rng = XorShift::new(0);
+        let first = rng.next_u64();
+        assert_ne!(first, 0);
+        assert_ne!(rng.next_u64(), first);
+    }
+
+    #[test]
+    fn floats_fall_in_unit_interval() {
+        let mut rng = XorShift::new(7);
+        let mut total = 0.0;
+        for _ in 0..10_000 {
+            let x = rng.next_f64();
+            assert!((0.0..1.0).contains(&x));
+            total += x;
+        }
+        // roughly uniform, so the average is near one half.
+        assert!((total / 10_000.0 - 0.5).abs() < 0.02);
+    }
+}
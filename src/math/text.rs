@@ -0,0 +1,256 @@
+//! the textual forms of points, vectors and matrices, shared by their `Display` and
+//! `FromStr` implementations so that anything printed can be read back.
+//!
+//! points and vectors are written as `(x, y, z)`. matrices are written as four rows,
+//! including the implied bottom row, with each column right-aligned:
+//!
+//! ```text
+//! [ 1 0 0   5 ]
+//! [ 0 2 0  -1 ]
+//! [ 0 0 1 0.5 ]
+//! [ 0 0 0   1 ]
+//! ```
+
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+use super::EPSILON;
+
+/// why a point, vector or matrix couldn't be read from text.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseMathError {
+    /// the text, or a row of it, isn't wrapped in the `open` and `close` delimiters.
+    Unenclosed {
+        open: char,
+        close: char,
+        found: String,
+    },
+    /// there are the wrong number of numbers in a triple or row, or of rows in a
+    /// matrix.
+    WrongCount {
+        what: &'static str,
+        expected: usize,
+        found: usize,
+    },
+    /// something that should be a number isn't a valid number.
+    InvalidNumber(String),
+    /// the bottom row of a matrix is something other than `[ 0 0 0 1 ]`.
+    NotAffine([f64; 4]),
+}
+
+impl Display for ParseMathError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseMathError::Unenclosed { open, close, found } => write!(
+                f,
+                "expected numbers between {:?} and {:?}, found {:?}",
+                open, close, found
+            ),
+            ParseMathError::WrongCount {
+                what,
+                expected,
+                found,
+            } => write!(f, "expected {} {}, found {}", expected, what, found),
+            ParseMathError::InvalidNumber(token) => {
+                write!(f, "{:?} isn't a valid number", token)
+            }
+            ParseMathError::NotAffine([a, b, c, d]) => write!(
+                f,
+                "the bottom row of a matrix should be [ 0 0 0 1 ], found [ {} {} {} {} ]",
+                a, b, c, d
+            ),
+        }
+    }
+}
+
+impl Error for ParseMathError {}
+
+/// writes `(x, y, z)`, giving each number the formatter's precision.
+pub(crate) fn write_triple(f: &mut Formatter<'_>, numbers: [f64; 3]) -> fmt::Result {
+    let [x, y, z] = numbers.map(|n| number(n, f.precision()));
+    write!(f, "({}, {}, {})", x, y, z)
+}
+
+/// reads `(x, y, z)`, with any whitespace around the numbers.
+pub(crate) fn parse_triple(text: &str) -> Result<[f64; 3], ParseMathError> {
+    let inside = enclosed(text.trim(), '(', ')')?;
+    let numbers = inside
+        .split(',')
+        .map(parse_number)
+        .collect::<Result<Vec<_>, _>>()?;
+    match numbers[..] {
+        [x, y, z] => Ok([x, y, z]),
+        _ => Err(ParseMathError::WrongCount {
+            what: "numbers",
+            expected: 3,
+            found: numbers.len(),
+        }),
+    }
+}
+
+/// writes each row on its own line, giving each number the formatter's precision
+/// and padding the columns to the same width.
+pub(crate) fn write_rows(f: &mut Formatter<'_>, rows: [[f64; 4]; 4]) -> fmt::Result {
+    let cells = rows.map(|row| row.map(|n| number(n, f.precision())));
+    let mut widths = [0; 4];
+    for row in cells.iter() {
+        for (width, cell) in widths.iter_mut().zip(row.iter()) {
+            *width = cell.len().max(*width);
+        }
+    }
+
+    for (i, row) in cells.iter().enumerate() {
+        if 0 < i {
+            writeln!(f)?;
+        }
+        write!(f, "[")?;
+        for (cell, width) in row.iter().zip(widths.iter()) {
+            write!(f, " {:>width$}", cell, width = width)?;
+        }
+        write!(f, " ]")?;
+    }
+    Ok(())
+}
+
+/// reads four rows of four numbers, each between `[` and `]` and separated by
+/// whitespace. the bottom row has to be `[ 0 0 0 1 ]`, so that the three rows above
+/// it can be returned as an affine matrix.
+pub(crate) fn parse_rows(text: &str) -> Result<[[f64; 4]; 3], ParseMathError> {
+    let mut rows = Vec::new();
+    let mut rest = text.trim();
+    while !rest.is_empty() {
+        let close = match (rest.starts_with('['), rest.find(']')) {
+            (true, Some(close)) => close,
+            _ => {
+                return Err(ParseMathError::Unenclosed {
+                    open: '[',
+                    close: ']',
+                    found: rest.lines().next().unwrap_or_default().to_string(),
+                })
+            }
+        };
+        let numbers = rest[1..close]
+            .split_whitespace()
+            .map(parse_number)
+            .collect::<Result<Vec<_>, _>>()?;
+        match numbers[..] {
+            [a, b, c, d] => rows.push([a, b, c, d]),
+            _ => {
+                return Err(ParseMathError::WrongCount {
+                    what: "numbers in each row",
+                    expected: 4,
+                    found: numbers.len(),
+                })
+            }
+        }
+        rest = rest[close + 1..].trim_start();
+    }
+
+    match rows[..] {
+        [a, b, c, d] if is_bottom_row(d) => Ok([a, b, c]),
+        [_, _, _, d] => Err(ParseMathError::NotAffine(d)),
+        _ => Err(ParseMathError::WrongCount {
+            what: "rows",
+            expected: 4,
+            found: rows.len(),
+        }),
+    }
+}
+
+fn is_bottom_row(row: [f64; 4]) -> bool {
+    row.iter()
+        .zip([0.0, 0.0, 0.0, 1.0].iter())
+        .all(|(a, b)| (a - b).abs() < EPSILON)
+}
+
+fn enclosed(text: &str, open: char, close: char) -> Result<&str, ParseMathError> {
+    text.strip_prefix(open)
+        .and_then(|text| text.strip_suffix(close))
+        .ok_or_else(|| ParseMathError::Unenclosed {
+            open,
+            close,
+            found: text.to_string(),
+        })
+}
+
+fn parse_number(token: &str) -> Result<f64, ParseMathError> {
+    let token = token.trim();
+    token
+        .parse()
+        .map_err(|_| ParseMathError::InvalidNumber(token.to_string()))
+}
+
+/// a number with the given precision, if any. adding zero turns `-0` into `0`, which
+/// transforms are full of and which would otherwise make them harder to read.
+fn number(n: f64, precision: Option<usize>) -> String {
+    match precision {
+        Some(precision) => format!("{:.*}", precision, n + 0.0),
+        None => (n + 0.0).to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::{Matrix, Point, Vector};
+
+    #[test]
+    fn malformed_triples_are_errors() {
+        assert_eq!(
+            "1, 2, 3".parse::<Vector>(),
+            Err(ParseMathError::Unenclosed {
+                open: '(',
+                close: ')',
+                found: "1, 2, 3".to_string()
+            })
+        );
+        assert_eq!(
+            "(1, 2)".parse::<Point>(),
+            Err(ParseMathError::WrongCount {
+                what: "numbers",
+                expected: 3,
+                found: 2
+            })
+        );
+        assert_eq!(
+            "(1, two, 3)".parse::<Vector>(),
+            Err(ParseMathError::InvalidNumber("two".to_string()))
+        );
+        assert_eq!(
+            "(1, , 3)".parse::<Point>().unwrap_err().to_string(),
+            "\"\" isn't a valid number"
+        );
+    }
+
+    #[test]
+    fn malformed_matrices_are_errors() {
+        let error = "[ 1 0 0 0 ]\n[ 0 1 0 0 ]\n[ 0 0 1 0 ]"
+            .parse::<Matrix>()
+            .unwrap_err();
+        assert_eq!(error.to_string(), "expected 4 rows, found 3");
+
+        let error = "[ 1 0 0 0 ]\n  0 1 0 0 ]\n[ 0 0 1 0 ]\n[ 0 0 0 1 ]"
+            .parse::<Matrix>()
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "expected numbers between '[' and ']', found \"0 1 0 0 ]\""
+        );
+
+        let error = "[ 1 0 0 ]\n[ 0 1 0 0 ]\n[ 0 0 1 0 ]\n[ 0 0 0 1 ]"
+            .parse::<Matrix>()
+            .unwrap_err();
+        assert_eq!(error.to_string(), "expected 4 numbers in each row, found 3");
+
+        let error = "[ 1 0 0 0 ]\n[ 0 1 0 0 ]\n[ 0 0 1 0 ]\n[ 0 0 0.5 1 ]"
+            .parse::<Matrix>()
+            .unwrap_err();
+        assert_eq!(error, ParseMathError::NotAffine([0.0, 0.0, 0.5, 1.0]));
+        assert_eq!(
+            error.to_string(),
+            "the bottom row of a matrix should be [ 0 0 0 1 ], found [ 0 0 0.5 1 ]"
+        );
+    }
+}
@@ -1,8 +1,12 @@
-use std::ops::{
-    Add, AddAssign, Div, DivAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub, SubAssign,
+use std::{
+    convert::TryFrom,
+    fmt::{self, Display, Formatter},
+    ops::{Add, AddAssign, Div, DivAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub, SubAssign},
+    str::FromStr,
 };
 
-use super::EPSILON;
+use super::{clamp_between, text, ParseMathError, EPSILON};
+use crate::RayTracerError;
 
 /// 4-dimensional vector which always has a fourth component of 0.
 #[derive(Copy, Clone, Debug)]
@@ -29,8 +33,15 @@ impl Vector {
         self.dot(self).sqrt()
     }
 
+    /// returns a unit vector pointing in the same direction. the zero vector has no
+    /// direction, so it is returned unchanged instead of dividing by zero.
     pub fn normalized(self) -> Vector {
-        self / self.magnitude()
+        let magnitude = self.magnitude();
+        if magnitude == 0.0 {
+            self
+        } else {
+            self / magnitude
+        }
     }
 
     pub fn normalize(&mut self) -> &mut Vector {
@@ -50,12 +61,99 @@ impl Vector {
         )
     }
 
+    /// the smaller of each pair of components.
+    pub fn min(&self, other: &Vector) -> Vector {
+        Vector::new(
+            self[0].min(other[0]),
+            self[1].min(other[1]),
+            self[2].min(other[2]),
+        )
+    }
+
+    /// the larger of each pair of components.
+    pub fn max(&self, other: &Vector) -> Vector {
+        Vector::new(
+            self[0].max(other[0]),
+            self[1].max(other[1]),
+            self[2].max(other[2]),
+        )
+    }
+
+    /// the absolute value of each component.
+    pub fn abs(&self) -> Vector {
+        Vector::new(self[0].abs(), self[1].abs(), self[2].abs())
+    }
+
+    /// each component kept between the matching components of `min` and `max`. where a
+    /// lower bound is above its upper bound, the upper bound wins, rather than
+    /// panicking like `f64::clamp`.
+    pub fn clamp(&self, min: &Vector, max: &Vector) -> Vector {
+        self.max(min).min(max)
+    }
+
+    /// the largest of the three components.
+    pub fn max_component(&self) -> f64 {
+        self[0].max(self[1]).max(self[2])
+    }
+
+    /// the smallest of the three components.
+    pub fn min_component(&self) -> f64 {
+        self[0].min(self[1]).min(self[2])
+    }
+
+    /// the angle between this vector and `other`, from 0 to pi, whatever their lengths.
+    /// the zero vector has no direction, and is taken to be at a right angle to
+    /// everything.
+    pub fn angle_between(&self, other: &Vector) -> f64 {
+        // rounding can take the cosine of nearly parallel vectors just past 1.
+        clamp_between(self.normalized().dot(&other.normalized()), -1.0, 1.0).acos()
+    }
+
+    /// the part of this vector that points along `other`. projecting onto the zero
+    /// vector gives the zero vector.
+    pub fn project_onto(&self, other: &Vector) -> Vector {
+        let length_squared = other.dot(other);
+        if length_squared == 0.0 {
+            Vector::zero()
+        } else {
+            *other * (self.dot(other) / length_squared)
+        }
+    }
+
+    /// the part of this vector that is perpendicular to `other`, so that it and the
+    /// projection onto `other` add back up to this vector.
+    pub fn reject_from(&self, other: &Vector) -> Vector {
+        *self - self.project_onto(other)
+    }
+
+    /// the vector `t` of the way from this one to `other`. `t` isn't clamped, so values
+    /// outside of `[0, 1]` carry on past either end.
+    pub fn lerp(self, other: Vector, t: f64) -> Vector {
+        self + (other - self) * t
+    }
+
     /// reflect this vector across another vector
     pub fn reflect_across(self, vector: Vector) -> Vector {
         self - (vector * 2.0 * self.dot(&vector))
     }
 }
 
+#[cfg(feature = "serde")]
+impl serde::Serialize for Vector {
+    /// written as its three components, `[x, y, z]`.
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        [self.x, self.y, self.z].serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Vector {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vector, D::Error> {
+        let [x, y, z] = <[f64; 3]>::deserialize(deserializer)?;
+        Ok(Vector::new(x, y, z))
+    }
+}
+
 /* equality operation */
 
 impl PartialEq for Vector {
@@ -67,19 +165,88 @@ impl PartialEq for Vector {
     }
 }
 
+/* conversions */
+
+impl From<[f64; 3]> for Vector {
+    fn from([x, y, z]: [f64; 3]) -> Vector {
+        Vector::new(x, y, z)
+    }
+}
+
+impl From<(f64, f64, f64)> for Vector {
+    fn from((x, y, z): (f64, f64, f64)) -> Vector {
+        Vector::new(x, y, z)
+    }
+}
+
+impl From<Vector> for [f64; 3] {
+    fn from(vector: Vector) -> [f64; 3] {
+        [vector.x, vector.y, vector.z]
+    }
+}
+
+impl From<Vector> for (f64, f64, f64) {
+    fn from(vector: Vector) -> (f64, f64, f64) {
+        (vector.x, vector.y, vector.z)
+    }
+}
+
+impl TryFrom<&[f64]> for Vector {
+    type Error = RayTracerError;
+
+    /// reads the three numbers in `numbers`, which mustn't hold any more or fewer.
+    fn try_from(numbers: &[f64]) -> Result<Vector, RayTracerError> {
+        match *numbers {
+            [x, y, z] => Ok(Vector::new(x, y, z)),
+            _ => Err(RayTracerError::WrongLength {
+                expected: 3,
+                found: numbers.len(),
+            }),
+        }
+    }
+}
+
+/* formatting and parsing */
+
+impl Display for Vector {
+    /// writes `(x, y, z)`, so `{:.2}` gives each component two decimal places.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        text::write_triple(f, (*self).into())
+    }
+}
+
+impl FromStr for Vector {
+    type Err = ParseMathError;
+
+    /// reads `(x, y, z)`, as written by `Display`.
+    fn from_str(s: &str) -> Result<Vector, ParseMathError> {
+        text::parse_triple(s).map(Vector::from)
+    }
+}
+
 /* indexing operations */
 
 impl Index<usize> for Vector {
     type Output = f64;
 
     fn index(&self, i: usize) -> &Self::Output {
-        unsafe { &std::mem::transmute::<&Vector, &[f64; 3]>(self)[i] }
+        match i {
+            0 => &self.x,
+            1 => &self.y,
+            2 => &self.z,
+            _ => panic!("vector index out of bounds: {}", i),
+        }
     }
 }
 
 impl IndexMut<usize> for Vector {
     fn index_mut(&mut self, i: usize) -> &mut f64 {
-        unsafe { &mut std::mem::transmute::<&mut Vector, &mut [f64; 3]>(self)[i] }
+        match i {
+            0 => &mut self.x,
+            1 => &mut self.y,
+            2 => &mut self.z,
+            _ => panic!("vector index out of bounds: {}", i),
+        }
     }
 }
 
@@ -160,8 +327,119 @@ impl SubAssign for Vector {
 }
 
 #[cfg(test)]
+#[allow(clippy::useless_conversion)]
 mod tests {
     use super::*;
+    use std::f64::consts;
+
+    #[test]
+    fn converting_to_and_from_arrays_and_tuples() {
+        let v = Vector::new(1.5, -2.0, 0.25);
+        assert_eq!(Vector::from([1.5, -2.0, 0.25]), v);
+        assert_eq!(Vector::from((1.5, -2.0, 0.25)), v);
+        assert_eq!(<[f64; 3]>::from(v), [1.5, -2.0, 0.25]);
+        let (x, y, z) = v.into();
+        assert_eq!((x, y, z), (1.5, -2.0, 0.25));
+
+        assert_eq!(Vector::try_from(&[1.5, -2.0, 0.25][..]).unwrap(), v);
+        assert!(matches!(
+            Vector::try_from(&[1.0, 2.0, 3.0, 4.0][..]),
+            Err(RayTracerError::WrongLength {
+                expected: 3,
+                found: 4
+            })
+        ));
+        assert!(Vector::try_from(&[][..]).is_err());
+    }
+
+    #[test]
+    fn formatting_and_parsing() {
+        let v = Vector::new(1.5, -0.0, 2.0 / 3.0);
+        assert_eq!(v.to_string(), "(1.5, 0, 0.6666666666666666)");
+        assert_eq!(format!("{:.2}", v), "(1.50, 0.00, 0.67)");
+        assert_eq!(v.to_string().parse::<Vector>(), Ok(v));
+        assert_eq!(
+            " (  1.5,0 ,\t-2e3 ) ".parse::<Vector>(),
+            Ok(Vector::new(1.5, 0.0, -2000.0))
+        );
+    }
+
+    #[test]
+    fn component_wise_operations() {
+        let a = Vector::new(-1.0, 2.0, -3.0);
+        let b = Vector::new(0.5, -4.0, -2.0);
+        assert_eq!(a.min(&b), Vector::new(-1.0, -4.0, -3.0));
+        assert_eq!(a.max(&b), Vector::new(0.5, 2.0, -2.0));
+        assert_eq!(a.abs(), Vector::new(1.0, 2.0, 3.0));
+        assert_eq!(a.max_component(), 2.0);
+        assert_eq!(a.min_component(), -3.0);
+        assert_eq!(
+            a.clamp(&Vector::new(-0.5, -0.5, -0.5), &Vector::new(0.5, 0.5, 0.5)),
+            Vector::new(-0.5, 0.5, -0.5)
+        );
+    }
+
+    #[test]
+    fn clamping_with_inverted_bounds() {
+        // the upper bound wins wherever it is below the lower one.
+        let (low, high) = (Vector::new(1.0, 0.0, 5.0), Vector::new(-1.0, 2.0, 4.0));
+        for v in [Vector::new(-3.0, 1.0, 0.0), Vector::new(3.0, 3.0, 9.0)] {
+            let clamped = v.clamp(&low, &high);
+            assert_eq!(clamped[0], -1.0);
+            assert_eq!(clamped[2], 4.0);
+        }
+        assert_eq!(Vector::new(-3.0, 1.0, 0.0).clamp(&low, &high)[1], 1.0);
+    }
+
+    #[test]
+    fn angles_between_vectors() {
+        let x = Vector::new(1.0, 0.0, 0.0);
+        assert_eq!(
+            x.angle_between(&Vector::new(0.0, 0.0, -3.0)),
+            consts::FRAC_PI_2
+        );
+        assert_eq!(x.angle_between(&(x * 5.0)), 0.0);
+        assert_eq!(x.angle_between(&-x), consts::PI);
+        assert!((x.angle_between(&Vector::new(1.0, 1.0, 0.0)) - consts::FRAC_PI_4).abs() < 1e-12);
+        // the cosine of these rounds to just over 1, which has no arccosine.
+        let v = Vector::new(0.02, 0.3, 0.7);
+        assert_eq!(v.angle_between(&(v * 2.0)), 0.0);
+        assert_eq!(x.angle_between(&Vector::zero()), consts::FRAC_PI_2);
+    }
+
+    #[test]
+    fn projecting_and_rejecting() {
+        let v = Vector::new(3.0, 4.0, -1.0);
+        let onto = Vector::new(2.0, 0.0, 0.0);
+        assert_eq!(v.project_onto(&onto), Vector::new(3.0, 0.0, 0.0));
+        assert_eq!(v.reject_from(&onto), Vector::new(0.0, 4.0, -1.0));
+
+        let slanted = Vector::new(1.0, -2.0, 2.0);
+        let (along, across) = (v.project_onto(&slanted), v.reject_from(&slanted));
+        assert_eq!(along + across, v);
+        assert_eq!(along.cross(&slanted), Vector::zero());
+        assert!(across.dot(&slanted).abs() < EPSILON);
+    }
+
+    #[test]
+    fn projecting_onto_the_zero_vector() {
+        let v = Vector::new(3.0, 4.0, -1.0);
+        assert_eq!(v.project_onto(&Vector::zero()), Vector::zero());
+        assert_eq!(v.reject_from(&Vector::zero()), v);
+        assert_eq!(Vector::zero().project_onto(&v), Vector::zero());
+        assert_eq!(Vector::zero().reject_from(&v), Vector::zero());
+    }
+
+    #[test]
+    fn interpolating_vectors() {
+        let a = Vector::new(1.0, -2.0, 0.0);
+        let b = Vector::new(3.0, 2.0, -4.0);
+        assert_eq!(a.lerp(b, 0.0), a);
+        assert_eq!(a.lerp(b, 0.5), Vector::new(2.0, 0.0, -2.0));
+        assert_eq!(a.lerp(b, 1.0), b);
+        assert_eq!(a.lerp(b, 2.0), Vector::new(5.0, 6.0, -8.0));
+        assert_eq!(a.lerp(b, -1.0), Vector::new(-1.0, -6.0, 4.0));
+    }
 
     #[test]
     fn add_two_vectors() {
@@ -257,6 +535,13 @@ mod tests {
         );
     }
 
+    #[test]
+    fn normalize_zero_vector() {
+        let v = Vector::zero().normalized();
+        assert_eq!(v, Vector::zero());
+        assert!(v[0].is_finite() && v[1].is_finite() && v[2].is_finite());
+    }
+
     #[test]
     fn magnitude_of_normalized_vector() {
         let v = Vector::new(1.0, 2.0, 3.0);
@@ -0,0 +1,734 @@
+//! reading models from wavefront OBJ files.
+//!
+//! only the statements that describe the shape of a model and its materials are
+//! understood: `v` for vertices, `vn` for normals, `f` for faces, `g` for groups, and
+//! `mtllib` and `usemtl` for materials. every other line is counted and skipped, so
+//! that files written by other programs can still be read.
+
+pub mod mtl;
+pub use mtl::{parse_mtl, MtlLibrary};
+
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    fs::File,
+    io::{self, BufRead, BufReader},
+    path::Path,
+    str::SplitWhitespace,
+    sync::Arc,
+};
+
+use crate::{
+    math::{
+        geometry::{SmoothTriangle, Triangle},
+        Form, Geometry, Point, Vector,
+    },
+    world::Material,
+};
+
+/// the shape read from an OBJ file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ObjModel {
+    /// the vertices, in the order they were given. the file numbers them from 1, so
+    /// vertex 1 is at index 0.
+    pub vertices: Vec<Point>,
+    /// the vertex normals, numbered like the vertices.
+    pub normals: Vec<Vector>,
+    /// the materials read from the libraries the file names, with their names.
+    pub materials: Vec<(String, Arc<Material>)>,
+    /// a group of the triangles that faces before any `g` statement are split into.
+    pub default_group: Geometry,
+    /// the groups named by `g` statements, in the order they first appear, each
+    /// holding the triangles of the faces that follow it.
+    pub groups: Vec<(String, Geometry)>,
+    /// how many lines weren't understood, and so were skipped, including those in
+    /// material libraries.
+    pub ignored: usize,
+    /// things in the file that were read, but perhaps not as its author meant.
+    pub warnings: Vec<ObjWarning>,
+}
+
+impl ObjModel {
+    /// the group the file names `name`.
+    pub fn group(&self, name: &str) -> Option<&Geometry> {
+        self.groups
+            .iter()
+            .find(|(group, _)| group == name)
+            .map(|(_, geometry)| geometry)
+    }
+
+    /// like `group`, but for changing the group.
+    pub fn group_mut(&mut self, name: &str) -> Option<&mut Geometry> {
+        self.groups
+            .iter_mut()
+            .find(|(group, _)| group == name)
+            .map(|(_, geometry)| geometry)
+    }
+
+    /// gives the group named `name` and every triangle in it `material`, which they
+    /// all share. returns whether there is such a group.
+    pub fn set_group_material<M: Into<Arc<Material>>>(&mut self, name: &str, material: M) -> bool {
+        let group = match self.group_mut(name) {
+            Some(group) => group,
+            None => return false,
+        };
+        let material = material.into();
+        if let Form::Group(triangles) = &mut group.form {
+            for triangle in triangles {
+                triangle.change_material(material.clone());
+            }
+        }
+        group.change_material(material);
+        true
+    }
+
+    /// the whole model as one group. the default group comes first, if it holds any
+    /// triangles, followed by the named groups.
+    pub fn to_group(&self) -> Geometry {
+        let has_default =
+            matches!(&self.default_group.form, Form::Group(triangles) if !triangles.is_empty());
+        let mut children: Vec<Geometry> =
+            self.groups.iter().map(|(_, group)| group.clone()).collect();
+        if has_default {
+            children.insert(0, self.default_group.clone());
+        }
+        Geometry::default().with_form(Form::Group(children))
+    }
+}
+
+/// something in an OBJ file that could be read, but not quite as it was written.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ObjWarning {
+    /// the face on the given line gives normals for some of its vertices but not
+    /// others, so its normals were ignored and it was made flat.
+    MixedNormals { line: usize },
+    /// the material library named on the given line couldn't be opened, for the
+    /// given reason, so its materials were left out.
+    MissingLibrary {
+        line: usize,
+        name: String,
+        reason: String,
+    },
+    /// the material named on the given line isn't in any library read so far, so the
+    /// faces after it have the default material.
+    UnknownMaterial { line: usize, name: String },
+}
+
+impl Display for ObjWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjWarning::MixedNormals { line } => write!(
+                f,
+                "line {}: only some of the face's vertices have normals, so it is flat",
+                line
+            ),
+            ObjWarning::MissingLibrary { line, name, reason } => write!(
+                f,
+                "line {}: the material library {:?} could not be opened: {}",
+                line, name, reason
+            ),
+            ObjWarning::UnknownMaterial { line, name } => write!(
+                f,
+                "line {}: there is no material {:?}, so the default is used",
+                line, name
+            ),
+        }
+    }
+}
+
+/// why an OBJ file couldn't be read.
+#[derive(Debug)]
+pub enum ObjError {
+    /// the reader itself failed.
+    Io(io::Error),
+    /// a statement on the given line, counting from 1, ended before its `what`.
+    MissingValue { line: usize, what: &'static str },
+    /// a value on the given line isn't a number that makes sense there.
+    InvalidValue {
+        line: usize,
+        what: &'static str,
+        token: String,
+    },
+    /// a face on the given line refers to a vertex that hasn't been given.
+    NoSuchVertex { line: usize, index: i64 },
+    /// a face on the given line refers to a normal that hasn't been given.
+    NoSuchNormal { line: usize, index: i64 },
+    /// the material library with the given name couldn't be read.
+    Library { name: String, error: Box<ObjError> },
+}
+
+impl Display for ObjError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjError::Io(error) => write!(f, "could not read the model: {}", error),
+            ObjError::MissingValue { line, what } => {
+                write!(f, "line {}: the statement ends before its {}", line, what)
+            }
+            ObjError::InvalidValue { line, what, token } => {
+                write!(f, "line {}: {:?} is not a valid {}", line, token, what)
+            }
+            ObjError::NoSuchVertex { line, index } => {
+                write!(f, "line {}: there is no vertex {}", line, index)
+            }
+            ObjError::NoSuchNormal { line, index } => {
+                write!(f, "line {}: there is no normal {}", line, index)
+            }
+            ObjError::Library { name, error } => write!(f, "in {}: {}", name, error),
+        }
+    }
+}
+
+impl Error for ObjError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ObjError::Io(error) => Some(error),
+            ObjError::Library { error, .. } => Some(error.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ObjError {
+    fn from(error: io::Error) -> ObjError {
+        ObjError::Io(error)
+    }
+}
+
+/// reads a model from an OBJ file. faces with more than three vertices are split into
+/// a fan of triangles around their first vertex, and faces that give a normal for
+/// every vertex become smooth triangles. vertices and normals are numbered from 1, or
+/// backwards from -1 for the most recent one.
+///
+/// a `g` statement puts the faces after it into the group it names, until the next
+/// one. naming a group again adds to it, and a `g` on its own goes back to the
+/// default group.
+///
+/// there is no file for the material libraries named by `mtllib` to be found next
+/// to, so they are left out with a warning. `load_obj` reads them.
+pub fn parse_obj<R: BufRead>(reader: R) -> Result<ObjModel, ObjError> {
+    parse_obj_with(reader, |_| {
+        Err::<&[u8], _>(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "libraries can't be opened while parsing on its own",
+        ))
+    })
+}
+
+/// reads a model from the OBJ file at `path`, along with the material libraries it
+/// names, which are looked for in the same directory. libraries that can't be opened
+/// are left out with a warning, and their materials replaced by the default.
+pub fn load_obj<P: AsRef<Path>>(path: P) -> Result<ObjModel, ObjError> {
+    let path = path.as_ref();
+    let directory = path.parent().unwrap_or_else(|| Path::new(""));
+    parse_obj_with(BufReader::new(File::open(path)?), |library| {
+        Ok(BufReader::new(File::open(directory.join(library))?))
+    })
+}
+
+/// like `parse_obj`, but reads each material library that `mtllib` names from what
+/// `open_library` opens for its name. faces take the material last named by `usemtl`.
+pub fn parse_obj_with<R, L, F>(reader: R, mut open_library: F) -> Result<ObjModel, ObjError>
+where
+    R: BufRead,
+    L: BufRead,
+    F: FnMut(&str) -> io::Result<L>,
+{
+    let mut vertices = vec![];
+    let mut normals = vec![];
+    let mut materials: Vec<(String, Arc<Material>)> = vec![];
+    let mut default_triangles = vec![];
+    let mut groups: Vec<(String, Vec<Geometry>)> = vec![];
+    // the index in `groups` of the group faces go into, if not the default one.
+    let mut current: Option<usize> = None;
+    // every face without a material of its own shares the same default one.
+    let default_material = Arc::new(Material::default());
+    let mut material = default_material.clone();
+    let mut ignored = 0;
+    let mut warnings = vec![];
+
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        let number = number + 1;
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some(statement @ "v") | Some(statement @ "vn") => {
+                let x = read_number(&mut tokens, number, "x coordinate")?;
+                let y = read_number(&mut tokens, number, "y coordinate")?;
+                let z = read_number(&mut tokens, number, "z coordinate")?;
+                if statement == "v" {
+                    vertices.push(Point::new(x, y, z));
+                } else {
+                    normals.push(Vector::new(x, y, z));
+                }
+            }
+            Some("f") => {
+                let corners = tokens
+                    .map(|token| face_vertex(&vertices, &normals, token, number))
+                    .collect::<Result<Vec<(Point, Option<Vector>)>, ObjError>>()?;
+                if corners.len() < 3 {
+                    return Err(ObjError::MissingValue {
+                        line: number,
+                        what: "third vertex",
+                    });
+                }
+
+                let with_normals = corners.iter().filter(|(_, normal)| normal.is_some());
+                let smooth = match with_normals.count() {
+                    0 => false,
+                    n if n == corners.len() => true,
+                    _ => {
+                        warnings.push(ObjWarning::MixedNormals { line: number });
+                        false
+                    }
+                };
+                let triangles = match current {
+                    Some(index) => &mut groups[index].1,
+                    None => &mut default_triangles,
+                };
+                for i in 1..corners.len() - 1 {
+                    let (a, b, c) = (corners[0], corners[i], corners[i + 1]);
+                    let form = match (smooth, a.1, b.1, c.1) {
+                        (true, Some(na), Some(nb), Some(nc)) => {
+                            Form::SmoothTriangle(SmoothTriangle::new(a.0, b.0, c.0, na, nb, nc))
+                        }
+                        _ => Form::Triangle(Triangle::new(a.0, b.0, c.0)),
+                    };
+                    triangles.push(
+                        Geometry::default()
+                            .with_form(form)
+                            .with_material(material.clone()),
+                    );
+                }
+            }
+            Some("g") => {
+                let name = tokens.collect::<Vec<&str>>().join(" ");
+                current = if name.is_empty() {
+                    None
+                } else {
+                    match groups.iter().position(|(group, _)| *group == name) {
+                        Some(index) => Some(index),
+                        None => {
+                            groups.push((name, vec![]));
+                            Some(groups.len() - 1)
+                        }
+                    }
+                };
+            }
+            Some("mtllib") => {
+                for name in tokens {
+                    let library = match open_library(name) {
+                        Ok(reader) => parse_mtl(reader).map_err(|error| ObjError::Library {
+                            name: name.to_string(),
+                            error: Box::new(error),
+                        })?,
+                        Err(error) => {
+                            warnings.push(ObjWarning::MissingLibrary {
+                                line: number,
+                                name: name.to_string(),
+                                reason: error.to_string(),
+                            });
+                            continue;
+                        }
+                    };
+                    ignored += library.ignored;
+                    materials.extend(library.materials);
+                }
+            }
+            Some("usemtl") => {
+                let name = tokens.collect::<Vec<&str>>().join(" ");
+                // later libraries win over earlier ones, like later definitions do.
+                material = match materials.iter().rev().find(|(known, _)| *known == name) {
+                    Some((_, known)) => known.clone(),
+                    None => {
+                        warnings.push(ObjWarning::UnknownMaterial { line: number, name });
+                        default_material.clone()
+                    }
+                };
+            }
+            // blank lines and comments say nothing, so they aren't counted.
+            None => {}
+            Some(token) if token.starts_with('#') => {}
+            Some(_) => ignored += 1,
+        }
+    }
+
+    let group = |triangles| Geometry::default().with_form(Form::Group(triangles));
+    Ok(ObjModel {
+        vertices,
+        normals,
+        materials,
+        default_group: group(default_triangles),
+        groups: groups
+            .into_iter()
+            .map(|(name, triangles)| (name, group(triangles)))
+            .collect(),
+        ignored,
+        warnings,
+    })
+}
+
+/// reads the next of `tokens`, which are on the given line, as a number.
+fn read_number(
+    tokens: &mut SplitWhitespace,
+    line: usize,
+    what: &'static str,
+) -> Result<f64, ObjError> {
+    let token = tokens.next().ok_or(ObjError::MissingValue { line, what })?;
+    token.parse().map_err(|_| ObjError::InvalidValue {
+        line,
+        what,
+        token: token.to_string(),
+    })
+}
+
+/// finds the vertex a face refers to with `token`, and its normal if it has one. the
+/// token may name a texture coordinate and normal after slashes, as in `3/1/2` or
+/// `3//2`. texture coordinates aren't used, so they aren't checked.
+fn face_vertex(
+    vertices: &[Point],
+    normals: &[Vector],
+    token: &str,
+    line: usize,
+) -> Result<(Point, Option<Vector>), ObjError> {
+    let mut parts = token.split('/');
+    let vertex = parts.next().unwrap_or(token);
+    let normal = parts.nth(1).filter(|normal| !normal.is_empty());
+
+    let index = |part: &str, what| -> Result<i64, ObjError> {
+        part.parse().map_err(|_| ObjError::InvalidValue {
+            line,
+            what,
+            token: token.to_string(),
+        })
+    };
+    let index_v = index(vertex, "vertex index")?;
+    let point = lookup(vertices, index_v).ok_or(ObjError::NoSuchVertex {
+        line,
+        index: index_v,
+    })?;
+    let normal = match normal {
+        Some(normal) => {
+            let index_n = index(normal, "normal index")?;
+            Some(lookup(normals, index_n).ok_or(ObjError::NoSuchNormal {
+                line,
+                index: index_n,
+            })?)
+        }
+        None => None,
+    };
+    Ok((point, normal))
+}
+
+/// the item an OBJ file refers to by `index`, counting from 1 at the start or from
+/// -1 at the end.
+fn lookup<T: Copy>(items: &[T], index: i64) -> Option<T> {
+    let position = if index > 0 {
+        index - 1
+    } else {
+        items.len() as i64 + index
+    };
+    if index == 0 || position < 0 || items.len() as i64 <= position {
+        return None;
+    }
+    Some(items[position as usize])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::{Color, Pattern, Texture, Textured};
+
+    fn parse(obj: &str) -> Result<ObjModel, ObjError> {
+        parse_obj(obj.as_bytes())
+    }
+
+    fn flat(p1: Point, p2: Point, p3: Point) -> Form {
+        Form::Triangle(Triangle::new(p1, p2, p3))
+    }
+
+    /// the forms of the children of `group`.
+    fn triangles(group: &Geometry) -> Vec<Form> {
+        match &group.form {
+            Form::Group(children) => children.iter().map(|child| child.form.clone()).collect(),
+            form => panic!("expected a group, got {:?}", form),
+        }
+    }
+
+    #[test]
+    fn ignoring_unrecognized_lines() {
+        let gibberish = "There was a young lady named Bright\n\
+                         who traveled much faster than light.\n\
+                         She set out one day\n\
+                         in a relative way,\n\
+                         and came back the previous night.\n";
+        let model = parse(gibberish).unwrap();
+        assert_eq!(model.ignored, 5);
+        assert!(model.vertices.is_empty() && triangles(&model.default_group).is_empty());
+    }
+
+    #[test]
+    fn vertex_records() {
+        let model = parse("v -1 1 0\nv -1.0000 0.5000 0.0000\nv 1 0 0\nv 1 1 0\n").unwrap();
+        assert_eq!(
+            model.vertices,
+            vec![
+                Point::new(-1.0, 1.0, 0.0),
+                Point::new(-1.0, 0.5, 0.0),
+                Point::new(1.0, 0.0, 0.0),
+                Point::new(1.0, 1.0, 0.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn parsing_triangle_faces() {
+        let model = parse("v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\n\nf 1 2 3\nf 1 3 4\n").unwrap();
+        let v = &model.vertices;
+        assert_eq!(
+            triangles(&model.default_group),
+            vec![flat(v[0], v[1], v[2]), flat(v[0], v[2], v[3])]
+        );
+        assert_eq!(model.ignored, 0);
+    }
+
+    #[test]
+    fn triangulating_polygons() {
+        let model =
+            parse("v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\nv 0 2 0\n\nf 1 2 3 4 5\n").unwrap();
+        let v = &model.vertices;
+        assert_eq!(
+            triangles(&model.default_group),
+            vec![
+                flat(v[0], v[1], v[2]),
+                flat(v[0], v[2], v[3]),
+                flat(v[0], v[3], v[4]),
+            ]
+        );
+    }
+
+    #[test]
+    fn negative_indices_count_back_from_the_last_vertex() {
+        let model = parse("v 0 1 0\nv -1 0 0\nv 1 0 0\nf -3 -2 -1\nf 1/4 2 3/1\n").unwrap();
+        let triangles = triangles(&model.default_group);
+        assert_eq!(triangles[0], triangles[1]);
+        assert_eq!(
+            triangles[0],
+            flat(
+                Point::new(0.0, 1.0, 0.0),
+                Point::new(-1.0, 0.0, 0.0),
+                Point::new(1.0, 0.0, 0.0)
+            )
+        );
+    }
+
+    #[test]
+    fn vertex_normal_records() {
+        let model = parse("vn 0 0 1\nvn 0.707 0 -0.707\nvn 1 2 3\n").unwrap();
+        assert_eq!(
+            model.normals,
+            vec![
+                Vector::new(0.0, 0.0, 1.0),
+                Vector::new(0.707, 0.0, -0.707),
+                Vector::new(1.0, 2.0, 3.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn faces_with_normals() {
+        let model = parse(
+            "v 0 1 0\nv -1 0 0\nv 1 0 0\n\nvn -1 0 0\nvn 1 0 0\nvn 0 1 0\n\n\
+             f 1//3 2//1 3//2\nf 1/0/3 2/102/1 3/14/2\n",
+        )
+        .unwrap();
+        let (v, n) = (&model.vertices, &model.normals);
+        let expected =
+            Form::SmoothTriangle(SmoothTriangle::new(v[0], v[1], v[2], n[2], n[0], n[1]));
+        assert_eq!(
+            triangles(&model.default_group),
+            vec![expected.clone(), expected]
+        );
+        assert!(model.warnings.is_empty());
+    }
+
+    #[test]
+    fn faces_with_some_normals_are_flat() {
+        let model = parse("v 0 1 0\nv -1 0 0\nv 1 0 0\nvn 0 0 -1\nf 1//1 2 3//1\n").unwrap();
+        let v = &model.vertices;
+        assert_eq!(
+            triangles(&model.default_group),
+            vec![flat(v[0], v[1], v[2])]
+        );
+        assert_eq!(model.warnings, vec![ObjWarning::MixedNormals { line: 5 }]);
+        assert_eq!(
+            model.warnings[0].to_string(),
+            "line 5: only some of the face's vertices have normals, so it is flat"
+        );
+    }
+
+    #[test]
+    fn comments_and_blank_lines_are_not_counted() {
+        let model = parse("# a comment\n\n   \nv 0 0 0\n").unwrap();
+        assert_eq!(model.ignored, 0);
+    }
+
+    #[test]
+    fn bad_statements_are_errors() {
+        assert!(matches!(
+            parse("v 1 2\n"),
+            Err(ObjError::MissingValue { line: 1, .. })
+        ));
+        assert!(matches!(
+            parse("v 1 2 three\n"),
+            Err(ObjError::InvalidValue { line: 1, .. })
+        ));
+        let error = parse("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 4\n").unwrap_err();
+        assert_eq!(error.to_string(), "line 4: there is no vertex 4");
+        assert!(matches!(
+            parse("v 0 0 0\nf 1 0 -1\n"),
+            Err(ObjError::NoSuchVertex { index: 0, .. })
+        ));
+        assert!(matches!(
+            parse("v 0 0 0\nv 1 0 0\nv 0 1 0\nvn 0 0 1\nf 1//1 2//2 3//-1\n"),
+            Err(ObjError::NoSuchNormal { line: 5, index: 2 })
+        ));
+        assert!(matches!(
+            parse("v 0 0 0\nv 1 0 0\nf 1 2\n"),
+            Err(ObjError::MissingValue { line: 3, .. })
+        ));
+    }
+
+    const GROUPS: &str = "v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\n\n\
+                          g FirstGroup\nf 1 2 3\ng SecondGroup\nf 1 3 4\n";
+
+    #[test]
+    fn triangles_in_groups() {
+        let model = parse(GROUPS).unwrap();
+        let v = &model.vertices;
+        assert!(triangles(&model.default_group).is_empty());
+        assert_eq!(
+            triangles(model.group("FirstGroup").unwrap()),
+            vec![flat(v[0], v[1], v[2])]
+        );
+        assert_eq!(
+            triangles(model.group("SecondGroup").unwrap()),
+            vec![flat(v[0], v[2], v[3])]
+        );
+        assert!(model.group("ThirdGroup").is_none());
+    }
+
+    #[test]
+    fn groups_can_be_named_again() {
+        let model = parse(
+            "v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\n\
+             f 1 2 3\ng wheel\nf 1 3 4\ng\nf 2 3 4\ng wheel\nf 4 3 2\n",
+        )
+        .unwrap();
+        assert_eq!(model.groups.len(), 1);
+        assert_eq!(triangles(&model.default_group).len(), 2);
+        assert_eq!(triangles(model.group("wheel").unwrap()).len(), 2);
+    }
+
+    #[test]
+    fn converting_a_model_to_a_group() {
+        let model = parse(GROUPS).unwrap();
+        assert_eq!(
+            triangles(&model.to_group()),
+            vec![
+                model.group("FirstGroup").unwrap().form.clone(),
+                model.group("SecondGroup").unwrap().form.clone(),
+            ]
+        );
+
+        // triangles outside of any named group come first, in a group of their own.
+        let model = parse("v 0 1 0\nv -1 0 0\nv 1 0 0\nf 1 2 3\ng side\nf 1 3 2\n").unwrap();
+        let group = triangles(&model.to_group());
+        assert_eq!(group[0], model.default_group.form);
+        assert_eq!(group[1], model.group("side").unwrap().form);
+    }
+
+    #[test]
+    fn groups_can_be_given_their_own_material() {
+        let mut model = parse(GROUPS).unwrap();
+        let red = Material::default()
+            .with_texture(Texture::Pattern(Pattern::solid(Color::new(1.0, 0.0, 0.0))));
+        assert!(model.set_group_material("FirstGroup", red));
+        assert!(!model.set_group_material("ThirdGroup", Material::default()));
+
+        let first_triangle = |name| match &model.group(name).unwrap().form {
+            Form::Group(children) => children[0].clone(),
+            form => panic!("expected a group, got {:?}", form),
+        };
+        let point = Point::new(0.0, 0.5, 0.0);
+        assert_eq!(
+            first_triangle("FirstGroup").color_at(point),
+            Color::new(1.0, 0.0, 0.0)
+        );
+        assert_eq!(
+            first_triangle("SecondGroup").color_at(point),
+            Color::white()
+        );
+    }
+
+    #[test]
+    fn faces_use_materials_from_libraries() {
+        let obj = "mtllib colors.mtl\nv 0 1 0\nv -1 0 0\nv 1 0 0\n\
+                   f 1 2 3\nusemtl red\nf 1 2 3\nusemtl blue\nf 1 2 3\n";
+        let mut opened = vec![];
+        let model = parse_obj_with(obj.as_bytes(), |name| {
+            opened.push(name.to_string());
+            Ok("newmtl red\nKd 1 0 0\nnewmtl blue\nKd 0 0 1\n".as_bytes())
+        })
+        .unwrap();
+        assert_eq!(opened, vec!["colors.mtl"]);
+        assert!(model.warnings.is_empty());
+
+        let point = Point::new(0.0, 0.5, 0.0);
+        let colors: Vec<Color> = match &model.default_group.form {
+            Form::Group(children) => children.iter().map(|t| t.color_at(point)).collect(),
+            form => panic!("expected a group, got {:?}", form),
+        };
+        assert_eq!(
+            colors,
+            vec![
+                Color::white(),
+                Color::new(1.0, 0.0, 0.0),
+                Color::new(0.0, 0.0, 1.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_libraries_and_materials_are_warned_about() {
+        let model =
+            parse("mtllib gone.mtl\nusemtl red\nv 0 1 0\nv -1 0 0\nv 1 0 0\nf 1 2 3\n").unwrap();
+        assert!(model.materials.is_empty());
+        assert!(matches!(
+            &model.warnings[0],
+            ObjWarning::MissingLibrary { line: 1, name, .. } if name == "gone.mtl"
+        ));
+        assert_eq!(
+            model.warnings[1],
+            ObjWarning::UnknownMaterial {
+                line: 2,
+                name: "red".to_string()
+            }
+        );
+        match &model.default_group.form {
+            Form::Group(children) => assert_eq!(*children[0].material, Material::default()),
+            form => panic!("expected a group, got {:?}", form),
+        }
+    }
+
+    #[test]
+    fn bad_libraries_are_errors() {
+        let error = parse_obj_with("mtllib bad.mtl\n".as_bytes(), |_| {
+            Ok("newmtl a\nNs shiny\n".as_bytes())
+        })
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "in bad.mtl: line 2: \"shiny\" is not a valid shininess"
+        );
+    }
+}
@@ -0,0 +1,182 @@
+//! reading materials from the MTL libraries that OBJ files name with `mtllib`.
+
+use std::{io::BufRead, str::SplitWhitespace, sync::Arc};
+
+use crate::{
+    obj::{read_number, ObjError},
+    world::{Color, Material, Pattern, Texture},
+};
+
+/// the materials read from an MTL file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MtlLibrary {
+    /// each material with its name, in the order they were given.
+    pub materials: Vec<(String, Arc<Material>)>,
+    /// how many lines weren't understood, and so were skipped.
+    pub ignored: usize,
+}
+
+impl MtlLibrary {
+    /// the material the library names `name`.
+    pub fn get(&self, name: &str) -> Option<&Arc<Material>> {
+        self.materials
+            .iter()
+            .find(|(material, _)| material == name)
+            .map(|(_, material)| material)
+    }
+}
+
+/// reads the materials of an MTL file. the colors are mapped onto the phong model as
+/// well as they fit:
+///
+/// - `Kd` is the color of the surface, which is then lit with a diffuse of 1.
+/// - `Ka` and `Ks` are single numbers for a `Material`, so their channels are averaged.
+/// - `Ns` is the shininess.
+/// - `d` is how opaque the surface is, and `Tr` how transparent, so either one sets the
+///   transparency.
+///
+/// statements before the first `newmtl` have no material to go to, so they are
+/// skipped along with the statements that aren't understood.
+pub fn parse_mtl<R: BufRead>(reader: R) -> Result<MtlLibrary, ObjError> {
+    let mut library = MtlLibrary::default();
+    let mut current: Option<(String, Material)> = None;
+
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        let number = number + 1;
+        let mut tokens = line.split_whitespace();
+        let statement = match tokens.next() {
+            // blank lines and comments say nothing, so they aren't counted.
+            None => continue,
+            Some(token) if token.starts_with('#') => continue,
+            Some(statement) => statement,
+        };
+
+        if statement == "newmtl" {
+            let name = tokens.collect::<Vec<&str>>().join(" ");
+            if name.is_empty() {
+                return Err(ObjError::MissingValue {
+                    line: number,
+                    what: "material name",
+                });
+            }
+            if let Some((name, material)) = current.take() {
+                library.materials.push((name, Arc::new(material)));
+            }
+            current = Some((name, Material::default()));
+            continue;
+        }
+
+        let material = match &mut current {
+            Some((_, material)) => material,
+            None => {
+                library.ignored += 1;
+                continue;
+            }
+        };
+        match statement {
+            "Kd" => {
+                let kd = read_color(&mut tokens, number)?;
+                material.texture = Texture::pattern(Pattern::solid(kd));
+                material.diffuse = 1.0;
+            }
+            "Ka" => material.ambient = average(read_color(&mut tokens, number)?),
+            "Ks" => material.specular = average(read_color(&mut tokens, number)?),
+            "Ns" => material.shininess = read_number(&mut tokens, number, "shininess")?,
+            "d" => material.transparency = 1.0 - read_number(&mut tokens, number, "opacity")?,
+            "Tr" => material.transparency = read_number(&mut tokens, number, "transparency")?,
+            _ => library.ignored += 1,
+        }
+    }
+
+    if let Some((name, material)) = current {
+        library.materials.push((name, Arc::new(material)));
+    }
+    Ok(library)
+}
+
+/// reads the three channels of a color from `tokens`, which are on the given line.
+fn read_color(tokens: &mut SplitWhitespace, line: usize) -> Result<Color, ObjError> {
+    Ok(Color::new(
+        read_number(tokens, line, "red value")?,
+        read_number(tokens, line, "green value")?,
+        read_number(tokens, line, "blue value")?,
+    ))
+}
+
+fn average(color: Color) -> f64 {
+    (color.red() + color.green() + color.blue()) / 3.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{math::Point, world::Textured};
+
+    const LIBRARY: &str = "# two materials\n\
+                           newmtl red plastic\n\
+                           Ka 0.2 0.1 0.0\n\
+                           Kd 1 0 0\n\
+                           Ks 0.5 0.5 0.5\n\
+                           Ns 50\n\
+                           illum 2\n\
+                           \n\
+                           newmtl glass\n\
+                           Kd 0.9 0.9 1.0\n\
+                           d 0.25\n\
+                           map_Kd glass.png\n";
+
+    #[test]
+    fn materials_are_read_in_order() {
+        let library = parse_mtl(LIBRARY.as_bytes()).unwrap();
+        let names: Vec<&str> = library.materials.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(names, vec!["red plastic", "glass"]);
+        assert_eq!(library.ignored, 2);
+    }
+
+    #[test]
+    fn statements_map_onto_the_material() {
+        let library = parse_mtl(LIBRARY.as_bytes()).unwrap();
+        let plastic = library.get("red plastic").unwrap();
+        assert_eq!(plastic.color_at(Point::zero()), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(plastic.diffuse, 1.0);
+        assert!((plastic.ambient - 0.1).abs() < 1e-9);
+        assert_eq!(plastic.specular, 0.5);
+        assert_eq!(plastic.shininess, 50.0);
+        assert_eq!(plastic.transparency, 0.0);
+
+        let glass = library.get("glass").unwrap();
+        assert_eq!(glass.transparency, 0.75);
+        assert_eq!(glass.ambient, Material::default().ambient);
+        assert!(library.get("steel").is_none());
+    }
+
+    #[test]
+    fn transparency_can_be_given_directly() {
+        let library = parse_mtl("newmtl haze\nTr 0.4\n".as_bytes()).unwrap();
+        assert_eq!(library.get("haze").unwrap().transparency, 0.4);
+    }
+
+    #[test]
+    fn statements_outside_of_a_material_are_skipped() {
+        let library = parse_mtl("Kd 1 0 0\nnewmtl plain\n".as_bytes()).unwrap();
+        assert_eq!(library.ignored, 1);
+        assert_eq!(**library.get("plain").unwrap(), Material::default());
+    }
+
+    #[test]
+    fn bad_statements_are_errors() {
+        assert!(matches!(
+            parse_mtl("newmtl a\nKd 1 0\n".as_bytes()),
+            Err(ObjError::MissingValue { line: 2, .. })
+        ));
+        assert!(matches!(
+            parse_mtl("newmtl a\nNs shiny\n".as_bytes()),
+            Err(ObjError::InvalidValue { line: 2, .. })
+        ));
+        assert!(matches!(
+            parse_mtl("newmtl\n".as_bytes()),
+            Err(ObjError::MissingValue { line: 1, .. })
+        ));
+    }
+}
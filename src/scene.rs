@@ -0,0 +1,160 @@
+//! a world saved along with the camera that views it, and what changed between two of
+//! them.
+//!
+//! objects have no names, so they are told apart by their place in `World::objects`.
+//! an object inserted in the middle of the list shows up as every object after it
+//! changing, and one more being added at the end.
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::world::{Camera, World};
+
+/// everything needed to render an image.
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct Scene {
+    pub world: World,
+    pub camera: Camera,
+}
+
+/// one way two scenes differ.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Change {
+    /// the object at this index is only in the second scene.
+    Added(usize),
+    /// the object at this index is only in the first scene.
+    Removed(usize),
+    /// the object at this index was transformed differently, or given a different
+    /// motion.
+    Moved(usize),
+    /// the object at this index has a different form.
+    Reshaped(usize),
+    /// the object at this index has a different material.
+    Restyled(usize),
+    Lights,
+    Camera,
+    /// the world's settings, ambient light, background, fog or decals.
+    Surroundings,
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::Added(index) => write!(f, "object {} was added", index),
+            Change::Removed(index) => write!(f, "object {} was removed", index),
+            Change::Moved(index) => write!(f, "object {} moved", index),
+            Change::Reshaped(index) => write!(f, "object {} changed shape", index),
+            Change::Restyled(index) => write!(f, "object {} changed material", index),
+            Change::Lights => write!(f, "the lights changed"),
+            Change::Camera => write!(f, "the camera changed"),
+            Change::Surroundings => write!(
+                f,
+                "the settings, ambient light, background, fog or decals changed"
+            ),
+        }
+    }
+}
+
+impl Scene {
+    /// how `other` differs from this scene, object by object and then everything
+    /// else. identical scenes have no changes.
+    pub fn changes(&self, other: &Scene) -> Vec<Change> {
+        let (before, after) = (&self.world, &other.world);
+        let mut changes = vec![];
+
+        for (index, (a, b)) in before.objects.iter().zip(&after.objects).enumerate() {
+            if a.transform != b.transform || a.end_transform != b.end_transform {
+                changes.push(Change::Moved(index));
+            }
+            if a.form != b.form {
+                changes.push(Change::Reshaped(index));
+            }
+            if a.material != b.material {
+                changes.push(Change::Restyled(index));
+            }
+        }
+        let shared = before.objects.len().min(after.objects.len());
+        changes.extend((shared..before.objects.len()).map(Change::Removed));
+        changes.extend((shared..after.objects.len()).map(Change::Added));
+
+        if before.lights != after.lights {
+            changes.push(Change::Lights);
+        }
+        if self.camera != other.camera {
+            changes.push(Change::Camera);
+        }
+        if before.settings != after.settings
+            || before.ambient_light != after.ambient_light
+            || before.background != after.background
+            || before.medium != after.medium
+            || before.decals != after.decals
+        {
+            changes.push(Change::Surroundings);
+        }
+
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        math::{Form, Geometry, Matrix, Transformable},
+        world::{Color, Material},
+    };
+    use std::f64::consts;
+
+    fn scene() -> Scene {
+        Scene {
+            world: World::default(),
+            camera: Camera::new(10, 10, consts::PI / 3.0),
+        }
+    }
+
+    #[test]
+    fn identical_scenes_have_no_changes() {
+        assert_eq!(scene().changes(&scene()), vec![]);
+    }
+
+    #[test]
+    fn objects_are_compared_by_index() {
+        let mut after = scene();
+        after.world.objects[0].transform(Matrix::translation(1.0, 0.0, 0.0));
+        after.world.objects[1].form = Form::Plane;
+        after.world.objects[1].material = Material::default().with_reflective(0.5).into();
+        after
+            .world
+            .objects
+            .push(Geometry::default().with_form(Form::Plane));
+        assert_eq!(
+            scene().changes(&after),
+            vec![
+                Change::Moved(0),
+                Change::Reshaped(1),
+                Change::Restyled(1),
+                Change::Added(2)
+            ]
+        );
+        assert_eq!(
+            after.changes(&scene()),
+            vec![
+                Change::Moved(0),
+                Change::Reshaped(1),
+                Change::Restyled(1),
+                Change::Removed(2)
+            ]
+        );
+    }
+
+    #[test]
+    fn everything_else_is_compared_as_a_whole() {
+        let mut after = scene();
+        after.world.lights.clear();
+        after.world.ambient_light = Color::black();
+        after.camera = Camera::new(10, 10, consts::PI / 2.0);
+        assert_eq!(
+            scene().changes(&after),
+            vec![Change::Lights, Change::Camera, Change::Surroundings]
+        );
+    }
+}
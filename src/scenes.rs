@@ -0,0 +1,559 @@
+//! ready-made cameras and scenes, including the one built at the end of each of the
+//! book's chapters.
+
+use std::{f64::consts, sync::Arc};
+
+use crate::{
+    builders,
+    math::{geometry::Cylinder, BoundingBox, Form, Geometry, Matrix, Point, Transformable, Vector},
+    world::{
+        light::{self, Light},
+        pattern::{Gradient, Grid, Ring, Stripe},
+        Camera, Color, Material, Pattern, Texture, View, World,
+    },
+};
+
+/// creates an orthographic camera that looks straight down onto `bounds`, covering its
+/// whole x/z extent. the longer of the two horizontal sides is rendered with `resolution`
+/// pixels, and +x points to the right of the image while +z points to the top.
+pub fn overhead_camera(bounds: BoundingBox, resolution: usize) -> Camera {
+    let size = bounds.size();
+    let (width, height) = if size[0] >= size[2] {
+        (resolution as f64, (resolution as f64) * size[2] / size[0])
+    } else {
+        ((resolution as f64) * size[0] / size[2], resolution as f64)
+    };
+
+    let mut camera = Camera::orthographic(
+        width.round().max(1.0) as usize,
+        height.round().max(1.0) as usize,
+        size[0].max(size[2]),
+    );
+
+    let center = bounds.center();
+    camera.set_view(View::transformed(
+        Point::new(center[0], bounds.max[1] + 1.0, center[2]),
+        Point::new(center[0], bounds.min[1], center[2]),
+        Vector::new(0.0, 0.0, 1.0),
+    ));
+
+    camera
+}
+
+/// one of the scenes in the gallery, which the binary renders with `--demo`.
+pub struct Demo {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub build: fn() -> (World, Camera),
+}
+
+/// the scene from the end of each chapter, in the order the book builds them.
+pub const DEMOS: &[Demo] = &[
+    Demo {
+        name: "chapter-five",
+        description: "the silhouette of a sphere, before there is any lighting",
+        build: chapter_five,
+    },
+    Demo {
+        name: "chapter-six",
+        description: "a single sphere, shaded by a light above and to the left",
+        build: chapter_six,
+    },
+    Demo {
+        name: "chapter-seven",
+        description: "three spheres in a room of squashed spheres, without shadows",
+        build: chapter_seven,
+    },
+    Demo {
+        name: "chapter-eight",
+        description: "the same room, now with shadows",
+        build: chapter_eight,
+    },
+    Demo {
+        name: "chapter-nine",
+        description: "the three spheres on a plane, in front of a plane for a wall",
+        build: chapter_nine,
+    },
+    Demo {
+        name: "chapter-ten",
+        description: "the planes and spheres painted with patterns",
+        build: chapter_ten,
+    },
+    Demo {
+        name: "chapter-eleven",
+        description: "a mirror, a glass sphere and a checkered floor that reflects them",
+        build: chapter_eleven,
+    },
+    Demo {
+        name: "chapter-twelve",
+        description: "a few boxes stacked on the floor",
+        build: chapter_twelve,
+    },
+    Demo {
+        name: "chapter-thirteen",
+        description: "open and closed cylinders of different heights",
+        build: chapter_thirteen,
+    },
+    Demo {
+        name: "chapter-fourteen",
+        description: "a hexagon of spheres and cylinders, built from groups",
+        build: chapter_fourteen,
+    },
+];
+
+/// the demo called `name`, if there is one.
+pub fn demo(name: &str) -> Option<&'static Demo> {
+    DEMOS.iter().find(|demo| demo.name == name)
+}
+
+/// the book's usual material for a sphere: a solid color with a softer, broader
+/// highlight than the default.
+fn matte(color: Color) -> Material {
+    Material {
+        diffuse: 0.7,
+        specular: 0.3,
+        ..Material::default().with_texture(Texture::pattern(Pattern::solid(color)))
+    }
+}
+
+/// the material of the floors and walls, which are matte all over, without any
+/// highlight.
+fn wall(color: Color) -> Material {
+    Material {
+        specular: 0.0,
+        ..matte(color)
+    }
+}
+
+/// gives `object`, and everything in it if it is a group, `material`.
+fn paint(object: &mut Geometry, material: &Arc<Material>) {
+    object.change_material(material.clone());
+    if let Form::Group(children) = &mut object.form {
+        for child in children {
+            paint(child, material);
+        }
+    }
+}
+
+/// the white light above, to the left of and behind the camera that most of the
+/// scenes are lit by.
+fn sun() -> Light {
+    Light::point(light::Point::new(
+        Point::new(-10.0, 10.0, -10.0),
+        Color::white(),
+    ))
+}
+
+/// a camera a little above the floor, looking down at the middle of the scene.
+fn room_camera() -> Camera {
+    let mut camera = Camera::new(200, 100, consts::PI / 3.0);
+    camera.set_view(View::transformed(
+        Point::new(0.0, 1.5, -5.0),
+        Point::new(0.0, 1.0, 0.0),
+        Vector::new(0.0, 1.0, 0.0),
+    ));
+    camera
+}
+
+/// a camera looking straight at a unit sphere at the origin.
+fn portrait_camera() -> Camera {
+    let mut camera = Camera::new(100, 100, consts::PI / 4.0);
+    camera.set_view(View::transformed(
+        Point::new(0.0, 0.0, -5.0),
+        Point::zero(),
+        Vector::new(0.0, 1.0, 0.0),
+    ));
+    camera
+}
+
+/// the large sphere in the middle of the room, followed by the smaller ones to its
+/// right and left.
+fn three_spheres() -> Vec<Geometry> {
+    vec![
+        Geometry::default()
+            .with_form(Form::Sphere)
+            .transformed(Matrix::translation(-0.5, 1.0, 0.5))
+            .with_material(matte(Color::new(0.1, 1.0, 0.5))),
+        Geometry::default()
+            .with_form(Form::Sphere)
+            .transformed(Matrix::translation(1.5, 0.5, -0.5) * Matrix::scaling(0.5, 0.5, 0.5))
+            .with_material(matte(Color::new(0.5, 1.0, 0.1))),
+        Geometry::default()
+            .with_form(Form::Sphere)
+            .transformed(Matrix::translation(-1.5, 0.33, -0.75) * Matrix::scaling(0.33, 0.33, 0.33))
+            .with_material(matte(Color::new(1.0, 0.8, 0.1))),
+    ]
+}
+
+/// a red disc: a sphere lit only by the ambient light, so that nothing shades it.
+pub fn chapter_five() -> (World, Camera) {
+    let sphere = Geometry::default()
+        .with_form(Form::Sphere)
+        .with_material(Material {
+            ambient: 1.0,
+            diffuse: 0.0,
+            specular: 0.0,
+            ..Material::default()
+                .with_texture(Texture::pattern(Pattern::solid(Color::new(1.0, 0.0, 0.0))))
+        });
+    (World::new(vec![sphere], vec![]), portrait_camera())
+}
+
+/// a purple sphere lit by a single light.
+pub fn chapter_six() -> (World, Camera) {
+    let sphere = Geometry::default().with_form(Form::Sphere).with_material(
+        Material::default()
+            .with_texture(Texture::pattern(Pattern::solid(Color::new(1.0, 0.2, 1.0)))),
+    );
+    (World::new(vec![sphere], vec![sun()]), portrait_camera())
+}
+
+/// three spheres on a floor, with two walls behind them, all made by flattening
+/// spheres. shadows are turned off, since the book hasn't got to them yet.
+pub fn chapter_seven() -> (World, Camera) {
+    let (mut world, camera) = chapter_eight();
+    world.settings.shadows = false;
+    (world, camera)
+}
+
+/// the room from chapter seven, with the spheres casting shadows.
+pub fn chapter_eight() -> (World, Camera) {
+    let material = wall(Color::new(1.0, 0.9, 0.9));
+    let flat = Matrix::scaling(10.0, 0.01, 10.0);
+    let side_wall = |turn: f64| {
+        Geometry::default()
+            .with_form(Form::Sphere)
+            .transformed(
+                Matrix::translation(0.0, 0.0, 5.0)
+                    * Matrix::rotation_y(turn)
+                    * Matrix::rotation_x(consts::PI / 2.0)
+                    * flat,
+            )
+            .with_material(material.clone())
+    };
+
+    let mut objects = vec![
+        Geometry::default()
+            .with_form(Form::Sphere)
+            .transformed(flat)
+            .with_material(material.clone()),
+        side_wall(-consts::PI / 4.0),
+        side_wall(consts::PI / 4.0),
+    ];
+    objects.extend(three_spheres());
+    (World::new(objects, vec![sun()]), room_camera())
+}
+
+/// the spheres on an endless floor, in front of an endless wall.
+pub fn chapter_nine() -> (World, Camera) {
+    let floor = Geometry::default()
+        .with_form(Form::Plane)
+        .with_material(wall(Color::new(1.0, 0.9, 0.9)));
+    let back_wall = Geometry::default()
+        .with_form(Form::Plane)
+        .transformed(Matrix::translation(0.0, 0.0, 5.0) * Matrix::rotation_x(consts::PI / 2.0))
+        .with_material(wall(Color::new(0.9, 0.9, 1.0)));
+
+    let mut objects = vec![floor, back_wall];
+    objects.extend(three_spheres());
+    (World::new(objects, vec![sun()]), room_camera())
+}
+
+/// the scene from chapter nine, with a checkered floor, a striped wall and a different
+/// pattern on each sphere.
+pub fn chapter_ten() -> (World, Camera) {
+    let (mut world, camera) = chapter_nine();
+    let patterns = vec![
+        Pattern::grid(Grid::new(Color::white(), Color::new(0.3, 0.3, 0.3))),
+        Pattern::stripe(Stripe::new(
+            Color::new(0.9, 0.9, 1.0),
+            Color::new(0.6, 0.6, 0.8),
+        ))
+        .transformed(Matrix::scaling(0.25, 1.0, 1.0)),
+        Pattern::stripe(Stripe::new(
+            Color::new(0.1, 1.0, 0.5),
+            Color::new(0.1, 0.5, 0.25),
+        ))
+        .transformed(Matrix::rotation_z(consts::PI / 4.0) * Matrix::scaling(0.2, 0.2, 0.2)),
+        Pattern::gradient(Gradient::new(
+            Color::new(1.0, 0.0, 0.0),
+            Color::new(0.0, 0.0, 1.0),
+        ))
+        .transformed(Matrix::translation(-1.0, 0.0, 0.0) * Matrix::scaling(2.0, 1.0, 1.0)),
+        Pattern::ring(Ring::new(
+            Color::new(1.0, 0.8, 0.1),
+            Color::new(0.5, 0.3, 0.0),
+        ))
+        .transformed(Matrix::scaling(0.1, 0.1, 0.1)),
+    ];
+    for (object, pattern) in world.objects.iter_mut().zip(patterns) {
+        object.make_material_unique().texture = Texture::pattern(pattern);
+    }
+    (world, camera)
+}
+
+/// a reflective checkered floor, with a mirrored sphere and a glass one in front of
+/// a solid one.
+pub fn chapter_eleven() -> (World, Camera) {
+    let floor = Geometry::default().with_form(Form::Plane).with_material(
+        Material::default()
+            .with_texture(Texture::pattern(Pattern::grid(Grid::new(
+                Color::white(),
+                Color::new(0.2, 0.2, 0.2),
+            ))))
+            .with_reflective(0.3),
+    );
+    let mirror = Geometry::default()
+        .with_form(Form::Sphere)
+        .transformed(Matrix::translation(-1.5, 1.0, 1.5))
+        .with_material(Material {
+            specular: 1.0,
+            ..matte(Color::new(0.1, 0.1, 0.1)).with_reflective(0.9)
+        });
+    let glass = Geometry::default()
+        .with_form(Form::Sphere)
+        .transformed(Matrix::translation(0.5, 0.75, -0.5) * Matrix::scaling(0.75, 0.75, 0.75))
+        .with_material(Material {
+            specular: 1.0,
+            ..matte(Color::black())
+                .with_reflective(0.9)
+                .with_transparency(0.9)
+                .with_refractive_index(1.5)
+        });
+    let solid = Geometry::default()
+        .with_form(Form::Sphere)
+        .transformed(Matrix::translation(1.0, 0.5, 2.5) * Matrix::scaling(0.5, 0.5, 0.5))
+        .with_material(matte(Color::new(1.0, 0.3, 0.2)));
+    (
+        World::new(vec![floor, mirror, glass, solid], vec![sun()]),
+        room_camera(),
+    )
+}
+
+/// boxes of different sizes and colors, one resting on another.
+pub fn chapter_twelve() -> (World, Camera) {
+    let floor = Geometry::default()
+        .with_form(Form::Plane)
+        .with_material(wall(Color::new(0.9, 0.9, 0.9)));
+    let cuboid = |min: Point, max: Point, color: Color| {
+        Geometry::default()
+            .with_form(Form::Box { min, max })
+            .with_material(matte(color))
+    };
+    let objects = vec![
+        floor,
+        cuboid(
+            Point::new(-2.0, 0.0, 0.0),
+            Point::new(-0.5, 1.5, 1.5),
+            Color::new(0.8, 0.3, 0.2),
+        ),
+        cuboid(
+            Point::new(-1.75, 1.5, 0.25),
+            Point::new(-0.75, 2.0, 1.25),
+            Color::new(0.2, 0.5, 0.8),
+        ),
+        cuboid(
+            Point::new(0.25, 0.0, -1.0),
+            Point::new(1.25, 1.0, 0.0),
+            Color::new(0.9, 0.8, 0.2),
+        )
+        .transformed(Matrix::rotation_y(consts::PI / 6.0)),
+    ];
+    (World::new(objects, vec![sun()]), room_camera())
+}
+
+/// a tall closed cylinder, an open one and a flat, wide one, standing on the floor.
+pub fn chapter_thirteen() -> (World, Camera) {
+    let floor = Geometry::default()
+        .with_form(Form::Plane)
+        .with_material(wall(Color::new(0.9, 0.9, 0.9)));
+    let cylinder = |cylinder: Cylinder, transform: Matrix, color: Color| {
+        Geometry::default()
+            .with_form(Form::Cylinder(cylinder))
+            .transformed(transform)
+            .with_material(matte(color))
+    };
+    let objects = vec![
+        floor,
+        cylinder(
+            Cylinder::new(0.0, 2.0, true),
+            Matrix::translation(-1.5, 0.0, 1.0) * Matrix::scaling(0.5, 1.0, 0.5),
+            Color::new(0.2, 0.6, 0.9),
+        ),
+        cylinder(
+            Cylinder::new(0.0, 1.0, false),
+            Matrix::scaling(0.4, 1.0, 0.4),
+            Color::new(0.9, 0.4, 0.2),
+        ),
+        cylinder(
+            Cylinder::new(0.0, 0.25, true),
+            Matrix::translation(1.5, 0.0, -0.5) * Matrix::scaling(0.8, 1.0, 0.8),
+            Color::new(0.4, 0.8, 0.3),
+        ),
+    ];
+    (World::new(objects, vec![sun()]), room_camera())
+}
+
+/// the book's hexagon, tilted towards the camera so that every side can be seen.
+pub fn chapter_fourteen() -> (World, Camera) {
+    let mut hexagon = builders::hexagon()
+        .transformed(Matrix::translation(0.0, 1.0, 0.0) * Matrix::rotation_x(-consts::PI / 6.0));
+    paint(&mut hexagon, &Arc::new(matte(Color::new(0.9, 0.5, 0.1))));
+    let floor = Geometry::default()
+        .with_form(Form::Plane)
+        .with_material(wall(Color::new(0.9, 0.9, 0.9)));
+    (World::new(vec![floor, hexagon], vec![sun()]), room_camera())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        math::{Form, Geometry, Matrix, Transformable},
+        world::{pattern::Grid, Color, Light, Pattern, Texture, World},
+    };
+
+    fn checkered_floor() -> Geometry {
+        let mut floor = Geometry::default().with_form(Form::Plane);
+        // nudge the pattern so the floor doesn't sit on a cell boundary in y.
+        floor.make_material_unique().texture = Texture::pattern(
+            Pattern::grid(Grid::new(Color::white(), Color::black()))
+                .transformed(Matrix::translation(0.0, 0.5, 0.0)),
+        );
+        floor
+    }
+
+    fn bounds() -> BoundingBox {
+        BoundingBox::new(Point::new(-2.0, 0.0, -2.0), Point::new(2.0, 2.0, 2.0))
+    }
+
+    #[test]
+    fn overhead_camera_covers_bounds() {
+        let c = overhead_camera(
+            BoundingBox::new(Point::new(-4.0, 0.0, -1.0), Point::new(4.0, 1.0, 1.0)),
+            16,
+        );
+        assert_eq!(c.image_width, 16);
+        assert_eq!(c.image_height, 4);
+        assert_eq!(c.ray_for_pixel(0, 0).origin, Point::new(-3.75, 2.0, 0.75));
+        assert_eq!(c.ray_for_pixel(15, 3).origin, Point::new(3.75, 2.0, -0.75));
+        assert_eq!(c.ray_for_pixel(7, 1).direction, Vector::new(0.0, -1.0, 0.0));
+    }
+
+    #[test]
+    fn albedo_of_checkered_floor_is_unshaded() {
+        let light = Light::point(crate::world::light::Point::new(
+            Point::new(-10.0, 10.0, -10.0),
+            Color::white(),
+        ));
+        let world = World::new(vec![checkered_floor()], vec![light]);
+        let camera = overhead_camera(bounds(), 4);
+        let image = camera.render_albedo(&world);
+
+        for y in 0..4 {
+            for x in 0..4 {
+                let expected = if (x + y) % 2 == 0 {
+                    Color::white()
+                } else {
+                    Color::black()
+                };
+                assert_eq!(image[(x, y)], expected);
+            }
+        }
+    }
+
+    #[test]
+    fn object_above_floor_occludes_it() {
+        let mut block = Geometry::default()
+            .with_form(Form::Sphere)
+            .transformed(Matrix::translation(0.5, 1.0, 0.5));
+        block.make_material_unique().texture =
+            Texture::pattern(Pattern::solid(Color::new(1.0, 0.0, 0.0)));
+        let world = World::new(vec![checkered_floor(), block], vec![]);
+        let camera = overhead_camera(bounds(), 4);
+        let image = camera.render_albedo(&world);
+
+        // the pixel centered over the sphere sees the sphere.
+        assert_eq!(image[(2, 1)], Color::new(1.0, 0.0, 0.0));
+        // the pixel in the far corner still sees the floor.
+        assert_eq!(image[(0, 3)], Color::black());
+    }
+
+    #[test]
+    fn height_is_normalized_over_bounds() {
+        let block = Geometry::default()
+            .with_form(Form::Sphere)
+            .transformed(Matrix::translation(0.5, 0.5, 0.5));
+        let world = World::new(vec![checkered_floor(), block], vec![]);
+        let camera = overhead_camera(bounds(), 4);
+        let image = camera.render_height(&world, bounds());
+
+        // the floor is at the bottom of the bounds.
+        assert_eq!(image[(0, 3)], Color::black());
+        // the top of the sphere is at y = 1.5, three quarters of the way up.
+        assert_eq!(image[(2, 1)], Color::new(0.75, 0.75, 0.75));
+    }
+
+    /// checks the number of objects and lights in a demo, and the camera that shows it.
+    fn assert_demo(
+        build: fn() -> (World, Camera),
+        objects: usize,
+        lights: usize,
+        (width, height): (usize, usize),
+        field_of_view: f64,
+        from: Point,
+    ) {
+        let (world, camera) = build();
+        assert_eq!(world.objects.len(), objects);
+        assert_eq!(world.lights.len(), lights);
+        assert_eq!(camera.image_width, width);
+        assert_eq!(camera.image_height, height);
+        assert_eq!(camera.field_of_view, field_of_view);
+        assert_eq!(camera.view().from(), from);
+    }
+
+    #[test]
+    fn scenes_of_single_spheres() {
+        let from = Point::new(0.0, 0.0, -5.0);
+        assert_demo(chapter_five, 1, 0, (100, 100), consts::PI / 4.0, from);
+        assert_demo(chapter_six, 1, 1, (100, 100), consts::PI / 4.0, from);
+    }
+
+    #[test]
+    fn scenes_of_rooms() {
+        let from = Point::new(0.0, 1.5, -5.0);
+        let fov = consts::PI / 3.0;
+        assert_demo(chapter_seven, 6, 1, (200, 100), fov, from);
+        assert_demo(chapter_eight, 6, 1, (200, 100), fov, from);
+        assert_demo(chapter_nine, 5, 1, (200, 100), fov, from);
+        assert_demo(chapter_ten, 5, 1, (200, 100), fov, from);
+        assert_demo(chapter_eleven, 4, 1, (200, 100), fov, from);
+        assert_demo(chapter_twelve, 4, 1, (200, 100), fov, from);
+        assert_demo(chapter_thirteen, 4, 1, (200, 100), fov, from);
+        assert_demo(chapter_fourteen, 2, 1, (200, 100), fov, from);
+    }
+
+    #[test]
+    fn shadows_are_only_cast_from_chapter_eight() {
+        assert!(!chapter_seven().0.settings.shadows);
+        assert!(chapter_eight().0.settings.shadows);
+    }
+
+    #[test]
+    fn demos_are_found_by_name() {
+        for (i, demo) in DEMOS.iter().enumerate() {
+            assert_eq!(super::demo(demo.name).unwrap().name, demo.name);
+            assert!(DEMOS[..i].iter().all(|other| other.name != demo.name));
+        }
+        assert!(super::demo("chapter-one").is_none());
+    }
+
+    #[test]
+    fn every_demo_shows_something_in_the_middle() {
+        for demo in DEMOS {
+            let (world, camera) = (demo.build)();
+            let ray = camera.ray_for_pixel(camera.image_width / 2, camera.image_height / 2);
+            assert!(world.hit(ray).is_some(), "{} shows nothing", demo.name);
+        }
+    }
+}
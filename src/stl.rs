@@ -0,0 +1,378 @@
+//! reading triangle meshes from STL files, as used for 3D printing.
+//!
+//! both kinds of STL file are understood, and told apart by their contents: the ASCII
+//! kind, which starts with `solid`, and the binary kind, which starts with an 80 byte
+//! header followed by the number of triangles.
+
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    io::{self, Read},
+    iter::Peekable,
+    vec,
+};
+
+use crate::math::{geometry::Triangle, Form, Geometry, Point, Vector, EPSILON};
+
+/// the size of the header, and then the triangle count, that start a binary file.
+const HEADER: usize = 80 + 4;
+
+/// the size of each triangle in a binary file: its normal, its three corners and two
+/// bytes of attributes.
+const RECORD: usize = 4 * 3 * 4 + 2;
+
+/// the shape read from an STL file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StlModel {
+    /// the name of the solid in an ASCII file, or the header of a binary file with any
+    /// padding removed.
+    pub name: String,
+    /// the facets of the solid.
+    pub triangles: Vec<Triangle>,
+}
+
+impl StlModel {
+    /// the model as a group of triangles, all with the default material.
+    pub fn to_group(&self) -> Geometry {
+        Geometry::default().with_form(Form::Group(
+            self.triangles
+                .iter()
+                .map(|&triangle| Geometry::default().with_form(Form::Triangle(triangle)))
+                .collect(),
+        ))
+    }
+}
+
+/// why an STL file couldn't be read.
+#[derive(Debug)]
+pub enum StlError {
+    /// the reader itself failed.
+    Io(io::Error),
+    /// a binary file ended before all of the triangles its header counts.
+    Truncated { expected: usize, found: usize },
+    /// an ASCII file has something other than the `expected` keyword on the given
+    /// line, or ends before it.
+    Expected {
+        line: usize,
+        expected: &'static str,
+        found: Option<String>,
+    },
+    /// a number on the given line of an ASCII file isn't a valid number.
+    InvalidNumber { line: usize, token: String },
+}
+
+impl Display for StlError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            StlError::Io(error) => write!(f, "could not read the model: {}", error),
+            StlError::Truncated { expected, found } => write!(
+                f,
+                "the file should be {} bytes long for its triangles, but is only {}",
+                expected, found
+            ),
+            StlError::Expected {
+                line,
+                expected,
+                found: Some(found),
+            } => write!(f, "line {}: expected {}, found {:?}", line, expected, found),
+            StlError::Expected {
+                line,
+                expected,
+                found: None,
+            } => write!(f, "line {}: expected {}, found the end", line, expected),
+            StlError::InvalidNumber { line, token } => {
+                write!(f, "line {}: {:?} is not a valid number", line, token)
+            }
+        }
+    }
+}
+
+impl Error for StlError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            StlError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for StlError {
+    fn from(error: io::Error) -> StlError {
+        StlError::Io(error)
+    }
+}
+
+/// reads a model from an STL file of either kind. each facet keeps the normal the file
+/// gives it, unless that normal has no length, in which case it is worked out from the
+/// corners, which are taken to go anticlockwise around the normal.
+pub fn parse<R: Read>(mut reader: R) -> Result<StlModel, StlError> {
+    let mut bytes = vec![];
+    reader.read_to_end(&mut bytes)?;
+
+    // binary headers may start with "solid" too, so a file is only taken to be ASCII if
+    // it isn't exactly the size its binary triangle count would make it.
+    let is_binary = match binary_size(&bytes) {
+        Some(size) if size == bytes.len() => true,
+        _ => !bytes.starts_with(b"solid"),
+    };
+    if is_binary {
+        parse_binary(&bytes)
+    } else {
+        parse_ascii(&String::from_utf8_lossy(&bytes))
+    }
+}
+
+/// how long a binary file should be for the number of triangles it counts, if it is
+/// long enough to count them.
+fn binary_size(bytes: &[u8]) -> Option<usize> {
+    let count = bytes.get(80..HEADER)?;
+    let count = u32::from_le_bytes([count[0], count[1], count[2], count[3]]) as usize;
+    count.checked_mul(RECORD)?.checked_add(HEADER)
+}
+
+fn parse_binary(bytes: &[u8]) -> Result<StlModel, StlError> {
+    let expected = binary_size(bytes).unwrap_or(HEADER);
+    if bytes.len() < expected {
+        return Err(StlError::Truncated {
+            expected,
+            found: bytes.len(),
+        });
+    }
+
+    // every number is a little-endian 32-bit float, whatever the machine reading it.
+    let float = |at: usize| {
+        f32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]]) as f64
+    };
+    let triple = |at: usize| (float(at), float(at + 4), float(at + 8));
+
+    let triangles = (HEADER..expected)
+        .step_by(RECORD)
+        .map(|at| {
+            let (nx, ny, nz) = triple(at);
+            let corner = |n: usize| {
+                let (x, y, z) = triple(at + 12 * n);
+                Point::new(x, y, z)
+            };
+            facet(Vector::new(nx, ny, nz), corner(1), corner(2), corner(3))
+        })
+        .collect();
+
+    let name = String::from_utf8_lossy(&bytes[..80]);
+    Ok(StlModel {
+        name: name.trim_end_matches(['\0', ' ']).to_string(),
+        triangles,
+    })
+}
+
+fn parse_ascii(text: &str) -> Result<StlModel, StlError> {
+    let mut tokens = Tokens {
+        tokens: text
+            .lines()
+            .enumerate()
+            .flat_map(|(number, line)| line.split_whitespace().map(move |t| (number + 1, t)))
+            .collect::<Vec<(usize, &str)>>()
+            .into_iter()
+            .peekable(),
+        line: 1,
+    };
+
+    tokens.expect("solid")?;
+    // the name runs to the end of its line, and may be left out.
+    let solid_line = tokens.line;
+    let mut name = vec![];
+    while let Some(&(line, token)) = tokens.tokens.peek() {
+        if line != solid_line || token == "facet" || token == "endsolid" {
+            break;
+        }
+        name.push(token);
+        tokens.tokens.next();
+    }
+
+    let mut triangles = vec![];
+    while tokens.next_is("facet") {
+        tokens.expect("normal")?;
+        let normal = Vector::new(tokens.number()?, tokens.number()?, tokens.number()?);
+        tokens.expect("outer")?;
+        tokens.expect("loop")?;
+        let mut corners = [Point::zero(); 3];
+        for corner in corners.iter_mut() {
+            tokens.expect("vertex")?;
+            *corner = Point::new(tokens.number()?, tokens.number()?, tokens.number()?);
+        }
+        tokens.expect("endloop")?;
+        tokens.expect("endfacet")?;
+        triangles.push(facet(normal, corners[0], corners[1], corners[2]));
+    }
+    tokens.expect("endsolid")?;
+
+    Ok(StlModel {
+        name: name.join(" "),
+        triangles,
+    })
+}
+
+/// the triangle between three corners, facing along `normal` if it has any length.
+fn facet(normal: Vector, p1: Point, p2: Point, p3: Point) -> Triangle {
+    let mut triangle = Triangle::new(p1, p2, p3);
+    if EPSILON < normal.magnitude() {
+        triangle.normal = normal.normalized();
+    }
+    triangle
+}
+
+/// the words of an ASCII file, each with the line it is on.
+struct Tokens<'a> {
+    tokens: Peekable<vec::IntoIter<(usize, &'a str)>>,
+    /// the line of the last word taken, for errors at the end of the file.
+    line: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn next(&mut self) -> Option<&'a str> {
+        let (line, token) = self.tokens.next()?;
+        self.line = line;
+        Some(token)
+    }
+
+    /// takes the next word if it is `keyword`.
+    fn next_is(&mut self, keyword: &str) -> bool {
+        let matches = matches!(self.tokens.peek(), Some(&(_, token)) if token == keyword);
+        if matches {
+            self.next();
+        }
+        matches
+    }
+
+    fn expect(&mut self, keyword: &'static str) -> Result<(), StlError> {
+        match self.next() {
+            Some(token) if token == keyword => Ok(()),
+            found => Err(StlError::Expected {
+                line: self.line,
+                expected: keyword,
+                found: found.map(str::to_string),
+            }),
+        }
+    }
+
+    fn number(&mut self) -> Result<f64, StlError> {
+        let token = self.next().ok_or(StlError::Expected {
+            line: self.line,
+            expected: "a number",
+            found: None,
+        })?;
+        token.parse().map_err(|_| StlError::InvalidNumber {
+            line: self.line,
+            token: token.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CUBE: &str = include_str!("../tests/models/cube.stl");
+
+    /// `model` written as a binary file with `header` at the start.
+    fn binary(model: &StlModel, header: &[u8]) -> Vec<u8> {
+        let mut bytes = header.to_vec();
+        bytes.resize(80, 0);
+        bytes.extend_from_slice(&(model.triangles.len() as u32).to_le_bytes());
+        for t in model.triangles.iter() {
+            let p3 = t.p1 + t.e2;
+            for &(x, y, z) in [
+                (t.normal[0], t.normal[1], t.normal[2]),
+                (t.p1[0], t.p1[1], t.p1[2]),
+                (t.p2[0], t.p2[1], t.p2[2]),
+                (p3[0], p3[1], p3[2]),
+            ]
+            .iter()
+            {
+                for &n in [x, y, z].iter() {
+                    bytes.extend_from_slice(&(n as f32).to_le_bytes());
+                }
+            }
+            bytes.extend_from_slice(&[0, 0]);
+        }
+        bytes
+    }
+
+    #[test]
+    fn reading_an_ascii_cube() {
+        let model = parse(CUBE.as_bytes()).unwrap();
+        assert_eq!(model.name, "cube");
+        assert_eq!(model.triangles.len(), 12);
+        for t in model.triangles.iter() {
+            // the corners go anticlockwise around the file's normals.
+            assert_eq!(t.normal, Triangle::new(t.p1, t.p2, t.p3).normal);
+            for &corner in [t.p1, t.p2, t.p3].iter() {
+                for axis in 0..3 {
+                    assert!(corner[axis] == 0.0 || corner[axis] == 1.0);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn reading_a_binary_cube() {
+        let ascii = parse(CUBE.as_bytes()).unwrap();
+        let model = parse(&binary(&ascii, b"binary cube")[..]).unwrap();
+        assert_eq!(model.name, "binary cube");
+        assert_eq!(model.triangles, ascii.triangles);
+    }
+
+    #[test]
+    fn binary_headers_may_start_with_solid() {
+        let ascii = parse(CUBE.as_bytes()).unwrap();
+        let model = parse(&binary(&ascii, b"solid but binary")[..]).unwrap();
+        assert_eq!(model.triangles.len(), 12);
+    }
+
+    #[test]
+    fn truncated_binary_files_are_errors() {
+        let ascii = parse(CUBE.as_bytes()).unwrap();
+        let bytes = binary(&ascii, b"");
+        for &length in [0, 50, HEADER, bytes.len() - 1].iter() {
+            let result = parse(&bytes[..length]);
+            assert!(
+                matches!(result, Err(StlError::Truncated { .. })),
+                "{} bytes gave {:?}",
+                length,
+                result
+            );
+        }
+        assert_eq!(
+            parse(&bytes[..100]).unwrap_err().to_string(),
+            "the file should be 684 bytes long for its triangles, but is only 100"
+        );
+    }
+
+    #[test]
+    fn facets_without_normals_get_one_from_their_corners() {
+        let model = parse(
+            "solid\nfacet normal 0 0 0\nouter loop\nvertex 0 1 0\nvertex -1 0 0\n\
+             vertex 1 0 0\nendloop\nendfacet\n\
+             facet normal 0 0 -2\nouter loop\nvertex 0 1 0\nvertex 1 0 0\n\
+             vertex -1 0 0\nendloop\nendfacet\nendsolid\n"
+                .as_bytes(),
+        )
+        .unwrap();
+        assert_eq!(model.name, "");
+        assert_eq!(model.triangles[0].normal, Vector::new(0.0, 0.0, -1.0));
+        // a normal from the file wins over the order of the corners.
+        assert_eq!(model.triangles[1].normal, Vector::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn malformed_ascii_files_are_errors() {
+        let error = parse("solid x\nfacet normal 0 0 1\nouter ring\n".as_bytes()).unwrap_err();
+        assert_eq!(error.to_string(), "line 3: expected loop, found \"ring\"");
+        let error = parse("solid x\nfacet normal 0 0 up\n".as_bytes()).unwrap_err();
+        assert_eq!(error.to_string(), "line 2: \"up\" is not a valid number");
+        let error = parse("solid x\n".as_bytes()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 1: expected endsolid, found the end"
+        );
+    }
+}
@@ -0,0 +1,128 @@
+use std::fmt::Write;
+
+use crate::world::Polyline;
+
+/// serializes polylines in image coordinates as an svg document of the given size, with
+/// one black stroked path per polyline.
+pub fn write(polylines: &[Polyline], width: usize, height: usize) -> String {
+    let mut svg = String::new();
+
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}">"#,
+        width, height
+    )
+    .unwrap();
+
+    for polyline in polylines
+        .iter()
+        .filter(|polyline| !polyline.points.is_empty())
+    {
+        let mut path = String::new();
+        for (index, (x, y)) in polyline.points.iter().enumerate() {
+            let command = if index == 0 { "M" } else { " L" };
+            write!(path, "{}{:.3} {:.3}", command, x, y).unwrap();
+        }
+        if polyline.closed {
+            path.push_str(" Z");
+        }
+
+        writeln!(
+            svg,
+            r#"  <path d="{}" fill="none" stroke="black" stroke-width="1"/>"#,
+            path
+        )
+        .unwrap();
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        math::{Form, Geometry, Matrix, Point, Transformable, Vector},
+        world::{Camera, View, World},
+    };
+
+    /// checks that every tag is closed in the order it was opened, and returns the
+    /// names of the elements in the order they appear.
+    fn parse_elements(xml: &str) -> Vec<String> {
+        let mut open: Vec<String> = vec![];
+        let mut elements = vec![];
+        let mut rest = xml;
+
+        while let Some(start) = rest.find('<') {
+            let end = rest[start..].find('>').expect("unterminated tag") + start;
+            let tag = &rest[start + 1..end];
+            assert_eq!(tag.matches('"').count() % 2, 0, "unbalanced quotes");
+
+            if let Some(name) = tag.strip_prefix('/') {
+                assert_eq!(open.pop().as_deref(), Some(name), "mismatched closing tag");
+            } else {
+                let name = tag.split_whitespace().next().unwrap().trim_end_matches('/');
+                elements.push(String::from(name));
+                if !tag.ends_with('/') {
+                    open.push(String::from(name));
+                }
+            }
+
+            rest = &rest[end + 1..];
+        }
+
+        assert!(open.is_empty(), "unclosed tags: {:?}", open);
+        elements
+    }
+
+    #[test]
+    fn empty_document() {
+        let svg = write(&[], 10, 20);
+        assert!(svg.contains(r#"width="10" height="20""#));
+        assert_eq!(parse_elements(&svg), vec!["svg"]);
+    }
+
+    #[test]
+    fn open_and_closed_paths() {
+        let polylines = [
+            Polyline {
+                points: vec![(0.0, 0.0), (1.0, 2.5)],
+                closed: false,
+            },
+            Polyline {
+                points: vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0)],
+                closed: true,
+            },
+        ];
+        let svg = write(&polylines, 4, 4);
+        assert!(svg.contains(r#"d="M0.000 0.000 L1.000 2.500""#));
+        assert!(svg.contains(r#"d="M0.000 0.000 L1.000 0.000 L1.000 1.000 Z""#));
+        assert_eq!(parse_elements(&svg), vec!["svg", "path", "path"]);
+    }
+
+    #[test]
+    fn silhouettes_to_svg() {
+        let w = World::new(
+            vec![
+                Geometry::default()
+                    .with_form(Form::Sphere)
+                    .transformed(Matrix::translation(-2.0, 0.0, 0.0)),
+                Geometry::default()
+                    .with_form(Form::Sphere)
+                    .transformed(Matrix::translation(2.0, 0.0, 0.0)),
+            ],
+            vec![],
+        );
+        let mut c = Camera::orthographic(40, 20, 8.0);
+        c.set_view(View::transformed(
+            Point::new(0.0, 0.0, -5.0),
+            Point::zero(),
+            Vector::new(0.0, 1.0, 0.0),
+        ));
+        let silhouettes = c.extract_silhouettes(&w, 1);
+        let svg = write(&silhouettes, c.image_width, c.image_height);
+        let elements = parse_elements(&svg);
+        assert_eq!(elements.iter().filter(|name| *name == "path").count(), 2);
+    }
+}
@@ -0,0 +1,172 @@
+//! helpers for tests that need lots of scenes, rather than carefully chosen ones.
+
+use std::f64::consts;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    math::{Form, Geometry, Matrix, Point, Transformable, Vector},
+    world::{
+        light,
+        pattern::{Gradient, Grid, Ring, Stripe},
+        Camera, Color, Light, Material, Pattern, Texture, View, World,
+    },
+};
+
+/// builds a world full of random spheres, planes, patterns, and lights, along with a
+/// 16 by 8 camera looking into it. the same seed always gives the same world.
+///
+/// the worlds are meant to be awkward rather than pretty, so now and then they include
+/// objects with no form, transforms that flatten an object entirely, and cameras whose
+/// up vector is zero or points straight along the view.
+pub fn random_world(seed: u64) -> (World, Camera) {
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let objects = (0..rng.gen_range(0, 8))
+        .map(|_| random_object(&mut rng))
+        .collect();
+    let lights = (0..rng.gen_range(0, 4))
+        .map(|_| {
+            Light::point(light::Point::new(
+                random_point(&mut rng, 10.0),
+                random_color(&mut rng),
+            ))
+        })
+        .collect();
+
+    let mut camera = Camera::new(16, 8, rng.gen_range(0.1, consts::PI - 0.1));
+    let from = random_point(&mut rng, 10.0);
+    let to = random_point(&mut rng, 2.0);
+    let up = match rng.gen_range(0, 10) {
+        0 => Vector::new(0.0, 0.0, 0.0),
+        1 => to - from,
+        _ => random_vector(&mut rng),
+    };
+    camera.set_view(View::transformed(from, to, up));
+
+    (World::new(objects, lights), camera)
+}
+
+fn random_object(rng: &mut StdRng) -> Geometry {
+    let form = match rng.gen_range(0, 20) {
+        0 => Form::None,
+        1..=4 => Form::Plane,
+        _ => Form::Sphere,
+    };
+
+    let material = Material::new(
+        Texture::pattern(random_pattern(rng)),
+        rng.gen_range(0.0, 1.0),
+        rng.gen_range(0.0, 1.0),
+        rng.gen_range(0.0, 1.0),
+        rng.gen_range(1.0, 400.0),
+    );
+
+    Geometry::default()
+        .with_form(form)
+        .with_material(material)
+        .transformed(random_transform(rng))
+}
+
+fn random_pattern(rng: &mut StdRng) -> Pattern {
+    let (a, b) = (random_color(rng), random_color(rng));
+    let pattern = match rng.gen_range(0, 5) {
+        0 => Pattern::gradient(Gradient::new(a, b)),
+        1 => Pattern::grid(Grid::new(a, b)),
+        2 => Pattern::ring(Ring::new(a, b)),
+        3 => Pattern::stripe(Stripe::new(a, b)),
+        _ => Pattern::solid(a),
+    };
+    pattern.transformed(random_transform(rng))
+}
+
+fn random_transform(rng: &mut StdRng) -> Matrix {
+    let mut scale = [0.0; 3];
+    for factor in scale.iter_mut() {
+        *factor = if rng.gen_range(0, 30) == 0 {
+            0.0
+        } else {
+            rng.gen_range(0.1, 3.0) * if rng.gen() { 1.0 } else { -1.0 }
+        };
+    }
+
+    let offset = random_point(rng, 5.0);
+    Matrix::translation(offset[0], offset[1], offset[2])
+        * Matrix::rotation_x(rng.gen_range(0.0, 2.0 * consts::PI))
+        * Matrix::rotation_y(rng.gen_range(0.0, 2.0 * consts::PI))
+        * Matrix::rotation_z(rng.gen_range(0.0, 2.0 * consts::PI))
+        * Matrix::scaling(scale[0], scale[1], scale[2])
+}
+
+fn random_point(rng: &mut StdRng, extent: f64) -> Point {
+    Point::new(
+        rng.gen_range(-extent, extent),
+        rng.gen_range(-extent, extent),
+        rng.gen_range(-extent, extent),
+    )
+}
+
+fn random_vector(rng: &mut StdRng) -> Vector {
+    Vector::new(
+        rng.gen_range(-1.0, 1.0),
+        rng.gen_range(-1.0, 1.0),
+        rng.gen_range(-1.0, 1.0),
+    )
+}
+
+fn random_color(rng: &mut StdRng) -> Color {
+    Color::new(
+        rng.gen_range(0.0, 1.0),
+        rng.gen_range(0.0, 1.0),
+        rng.gen_range(0.0, 1.0),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::NonFinite;
+
+    fn render_without_trouble(seed: u64) {
+        let (world, mut camera) = random_world(seed);
+        camera.on_non_finite = NonFinite::Panic;
+        let image = camera.render(&world);
+        for y in 0..image.height {
+            for x in 0..image.width {
+                assert!(
+                    image[(x, y)].is_finite(),
+                    "seed {} pixel ({}, {})",
+                    seed,
+                    x,
+                    y
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn same_seed_same_world() {
+        let (a, _) = random_world(7);
+        let (b, _) = random_world(7);
+        assert_eq!(a.objects.len(), b.objects.len());
+        for (a, b) in a.objects.iter().zip(b.objects.iter()) {
+            assert_eq!(a.transform, b.transform);
+            assert_eq!(a.form, b.form);
+        }
+    }
+
+    #[test]
+    fn random_worlds_render_cleanly() {
+        for seed in 0..300 {
+            render_without_trouble(seed);
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn many_random_worlds_render_cleanly() {
+        for seed in 0..20_000 {
+            render_without_trouble(seed);
+        }
+    }
+}
@@ -1,11 +1,22 @@
+pub mod background;
+pub use background::Background;
+
+pub mod bumps;
+pub use bumps::Bumps;
+
 pub mod camera;
-pub use camera::{Camera, View};
+pub use camera::{Camera, Fov, NonFinite, RenderOptions, RenderOutput, View};
 
 pub mod canvas;
-pub use canvas::Canvas;
+pub use canvas::{
+    BitDepth, BlendMode, Canvas, CanvasDiff, Dither, Filter, PpmError, PpmOptions, SizeMismatch,
+};
 
 pub mod color;
-pub use color::Color;
+pub use color::{Color, Encoding, ToneMap};
+
+pub mod decal;
+pub use decal::Decal;
 
 pub mod intersection;
 pub use intersection::{Intersection, Intersections};
@@ -13,48 +24,355 @@ pub use intersection::{Intersection, Intersections};
 pub mod light;
 pub use light::Light;
 
+pub mod logger;
+pub use logger::{LogRecord, NoopLogger, RenderEvent, RenderLogger, Severity};
+
 pub mod material;
 pub use material::Material;
 
+pub mod medium;
+pub use medium::Medium;
+
+pub mod normal_map;
+pub use normal_map::NormalMap;
+
+pub mod object_ids;
+
+pub mod path_tracer;
+
 pub mod pattern;
 pub use pattern::Pattern;
 
+pub mod pixel_trace;
+pub use pixel_trace::PixelTrace;
+
+pub mod progressive;
+pub use progressive::Rows;
+
 pub mod ray;
 pub use ray::Ray;
 
+pub mod sampler;
+pub use sampler::{Sampler, Sampling};
+
+pub mod settings;
+pub use settings::{AmbientOcclusion, RenderSettings};
+
+pub mod silhouette;
+pub use silhouette::Polyline;
+
+pub mod stats;
+pub use stats::RenderStats;
+
 pub mod texture;
-pub use texture::{Texture, Textured};
+pub use texture::{Texture, Textured, UvMapping};
 
-use std::{cmp::Reverse, collections::BinaryHeap};
+use std::{cmp::Reverse, collections::BinaryHeap, sync::Arc, time::Instant};
 
-use crate::math::{Form, Geometry, Hittable, Matrix, Point, Transformable};
+use crate::math::{
+    sample_hemisphere_cosine, Basis, Form, Geometry, Hittable, Matrix, Point, Transformable,
+    XorShift,
+};
+use intersection::Computations;
+use stats::RayCounts;
 
+/// how many times a ray is allowed to bounce between reflective surfaces, unless the
+/// world's `RenderSettings` say otherwise.
+pub const MAX_REFLECTIONS: u32 = 5;
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct World {
     pub objects: Vec<Geometry>,
     pub lights: Vec<Light>,
+    /// projected onto surfaces before they are lit, in order.
+    pub decals: Vec<Decal>,
+    pub settings: RenderSettings,
+    /// the light that reaches every surface from everywhere at once, scaled by each
+    /// material's `ambient`.
+    pub ambient_light: Color,
+    /// what rays that don't hit anything see.
+    pub background: Background,
+    /// the fog that fills the space between objects, if any.
+    pub medium: Option<Medium>,
 }
 
 impl World {
     pub fn new(objects: Vec<Geometry>, lights: Vec<Light>) -> World {
-        World { objects, lights }
+        World {
+            objects,
+            lights,
+            decals: vec![],
+            settings: RenderSettings::default(),
+            ambient_light: Color::white(),
+            background: Background::default(),
+            medium: None,
+        }
     }
 
-    pub fn cast_ray(&self, ray: Ray) -> Color {
-        let mut color = Color::new(0.0, 0.0, 0.0);
+    pub fn with_settings(self, settings: RenderSettings) -> World {
+        World { settings, ..self }
+    }
+
+    pub fn with_background(self, background: Background) -> World {
+        World { background, ..self }
+    }
+
+    pub fn with_medium(self, medium: Medium) -> World {
+        World {
+            medium: Some(medium),
+            ..self
+        }
+    }
+
+    pub fn with_ambient_light(self, ambient_light: Color) -> World {
+        World {
+            ambient_light,
+            ..self
+        }
+    }
+
+    /// checks the world over before it is rendered, reporting anything that looks like
+    /// a mistake as a warning.
+    pub fn prepare<L: RenderLogger>(&self, logger: &mut L) {
+        let start = Instant::now();
+
+        if self.lights.is_empty() {
+            logger.log(LogRecord::now(RenderEvent::Warning(String::from(
+                "the world has no lights, so everything will render black",
+            ))));
+        }
+
+        for (index, object) in self.objects.iter().enumerate() {
+            if object.form == Form::None {
+                logger.log(LogRecord::now(RenderEvent::Warning(format!(
+                    "object {} has no form and will never be hit",
+                    index
+                ))));
+            }
+            if !object.transform.is_invertible() {
+                logger.log(LogRecord::now(RenderEvent::Warning(format!(
+                    "object {} has a transform that cannot be inverted",
+                    index
+                ))));
+            }
+        }
+
+        logger.log(LogRecord::now(RenderEvent::WorldPrepared {
+            objects: self.objects.len(),
+            elapsed: start.elapsed(),
+        }));
+    }
+
+    /// edits `material`, and gives the edited copy to every object (including those
+    /// inside of groups) that shares it. returns the edited material, which replaces
+    /// `material` as the handle to use for later edits.
+    ///
+    /// objects only share a material if they were handed the same `Arc`, which a world
+    /// read back from serde never does (see `Geometry`).
+    pub fn update_material<F: FnOnce(&mut Material)>(
+        &mut self,
+        material: &Arc<Material>,
+        f: F,
+    ) -> Arc<Material> {
+        let mut updated = Material::clone(material);
+        f(&mut updated);
+        let updated = Arc::new(updated);
+
+        fn replace(objects: &mut [Geometry], old: &Arc<Material>, new: &Arc<Material>) {
+            for object in objects {
+                if Arc::ptr_eq(&object.material, old) {
+                    object.material = new.clone();
+                }
+                if let Form::Group(children) = &mut object.form {
+                    replace(children, old, new);
+                }
+            }
+        }
+        replace(&mut self.objects, material, &updated);
+
+        updated
+    }
 
-        if let Some(intersections) = self.hit(ray) {
-            if let Some(intersection) = intersections.closest() {
-                for light in &self.lights {
-                    color += light.illuminate(self, &intersection.compute());
+    /// suggests how much to scale rendered colors by so that they land around 1, given
+    /// the physical power of the lights. this is the reciprocal of the average total
+    /// irradiance that the centers of the objects receive (or the origin, if there are
+    /// no objects), ignoring shadows and which way surfaces face. worlds without any
+    /// physical lights are already in that range, so they get 1.
+    pub fn exposure_hint(&self) -> f64 {
+        let centers: Vec<Point> = if self.objects.is_empty() {
+            vec![Point::zero()]
+        } else {
+            self.objects
+                .iter()
+                .map(|object| object.transform * Point::zero())
+                .collect()
+        };
+
+        let mut physical = false;
+        let mut irradiance = 0.0;
+        for &center in centers.iter() {
+            for light in self.lights.iter() {
+                if let Some(received) = light.irradiance_at(center) {
+                    physical = true;
+                    irradiance += received;
                 }
             }
         }
+        irradiance /= centers.len() as f64;
+
+        if physical && irradiance > 0.0 {
+            1.0 / irradiance
+        } else {
+            1.0
+        }
+    }
+
+    pub fn cast_ray(&self, ray: Ray) -> Color {
+        self.cast_ray_with_depth(ray, self.settings.max_bounces)
+    }
+
+    /// like `cast_ray`, but also reports whether the ray hit anything at all, rather
+    /// than only seeing the background.
+    pub fn cast_primary_ray(&self, ray: Ray) -> (Color, bool) {
+        let (color, time) = self.trace(ray, self.settings.max_bounces);
+        (color, time.is_some())
+    }
+
+    /// like `cast_ray`, but only follows at most `remaining` more reflections.
+    pub fn cast_ray_with_depth(&self, ray: Ray, remaining: u32) -> Color {
+        self.trace(ray, remaining).0
+    }
+
+    /// finds the color seen along `ray`, and how far along it the surface with that
+    /// color is, if it hits one.
+    fn trace(&self, ray: Ray, remaining: u32) -> (Color, Option<f64>) {
+        let (color, time) = self.trace_surface(ray, remaining);
+        match &self.medium {
+            Some(medium) => (medium.apply(self, ray, color, time), time),
+            None => (color, time),
+        }
+    }
+
+    /// like `trace`, but ignores the world's medium.
+    fn trace_surface(&self, ray: Ray, remaining: u32) -> (Color, Option<f64>) {
+        let intersections = match self.hit(ray) {
+            Some(intersections) => intersections,
+            None => return (self.background.color_for(ray.direction), None),
+        };
+        let intersection = match intersections.closest() {
+            Some(intersection) => intersection,
+            None => return (self.background.color_for(ray.direction), None),
+        };
+
+        let mut computations = intersection.compute_among(&intersections);
+        for decal in &self.decals {
+            decal.apply(&mut computations);
+        }
 
+        let mut color = self.shade(&computations);
+
+        let reflected = self.reflected_color(&computations, remaining);
+        let refracted = self.refracted_color(&computations, remaining);
+        color += mix_secondary(reflected, refracted, computations.reflectance());
+
+        (color, Some(intersection.time))
+    }
+
+    /// the light the surface described by `computations` gives off towards the eye,
+    /// not counting reflection or refraction: whatever it emits and ambient light once,
+    /// plus the diffuse and specular light from every light.
+    pub fn shade(&self, computations: &Computations) -> Color {
+        let mut color = computations.material.emissive + self.ambient_at(computations);
+        for light in &self.lights {
+            color += light.illuminate(self, computations);
+        }
         color
     }
 
+    /// the ambient light given off by the surface described by `computations`.
+    pub fn ambient_at(&self, computations: &Computations) -> Color {
+        computations.material.color_at(computations.point)
+            * self.ambient_light
+            * (computations.material.ambient * self.ambient_visibility(computations))
+    }
+
+    /// how much ambient light reaches the point described by `computations`, from 0 to
+    /// 1, according to the world's ambient occlusion settings.
+    pub fn ambient_visibility(&self, computations: &Computations) -> f64 {
+        let occlusion = match self.settings.ambient_occlusion {
+            Some(occlusion) if occlusion.strength != 0.0 && occlusion.samples != 0 => occlusion,
+            _ => return 1.0,
+        };
+
+        let mut rng = XorShift::at_point(occlusion.seed, computations.point);
+
+        let basis = Basis::from_normal(computations.surface_normal);
+        let occluded = (0..occlusion.samples)
+            .filter(|_| {
+                let direction =
+                    basis.to_world(sample_hemisphere_cosine(rng.next_f64(), rng.next_f64()));
+                let ray = Ray::new(computations.over_point, direction).with_time(computations.time);
+                RayCounts::count_shadow();
+                self.hit(ray)
+                    .and_then(|intersections| intersections.closest())
+                    .is_some_and(|intersection| intersection.time < occlusion.max_distance)
+            })
+            .count();
+
+        1.0 - occlusion.strength * (occluded as f64 / occlusion.samples as f64)
+    }
+
+    /// the color mirrored by the surface described by `computations`, following at
+    /// most `remaining` more reflections.
+    pub fn reflected_color(&self, computations: &Computations, remaining: u32) -> Color {
+        let reflective = computations.material.reflective;
+        if remaining == 0 || reflective == 0.0 {
+            return Color::black();
+        }
+
+        let reflected =
+            Ray::new(computations.over_point, computations.reflect_v).with_time(computations.time);
+        RayCounts::count_secondary();
+        self.cast_ray_with_depth(reflected, remaining - 1) * reflective
+    }
+
+    /// the color seen through the surface described by `computations`, following at
+    /// most `remaining` more reflections or refractions.
+    pub fn refracted_color(&self, computations: &Computations, remaining: u32) -> Color {
+        let transparency = computations.material.transparency;
+        if remaining == 0 || transparency == 0.0 {
+            return Color::black();
+        }
+
+        // snell's law: sin(theta_i) * n1 = sin(theta_t) * n2.
+        let n_ratio = computations.n1 / computations.n2;
+        let cos_i = computations.to_eye.dot(&computations.surface_normal);
+        let sin2_t = n_ratio * n_ratio * (1.0 - cos_i * cos_i);
+        if 1.0 < sin2_t {
+            // total internal reflection.
+            return Color::black();
+        }
+
+        let cos_t = (1.0 - sin2_t).sqrt();
+        let direction =
+            computations.surface_normal * (n_ratio * cos_i - cos_t) - computations.to_eye * n_ratio;
+        let refracted = Ray::new(computations.under_point, direction).with_time(computations.time);
+        RayCounts::count_secondary();
+        let (color, distance) = self.trace(refracted, remaining - 1);
+
+        // light entering the material is absorbed by it until it gets out the other side.
+        let absorbed = match distance {
+            Some(distance) if !computations.is_inside => {
+                color * computations.material.transmittance(distance)
+            }
+            _ => color,
+        };
+        absorbed * transparency
+    }
+
     pub fn hit(&self, ray: Ray) -> Option<Intersections> {
         let mut heap: BinaryHeap<Reverse<Intersection>> = BinaryHeap::new();
+        RayCounts::count_intersection_tests(self.objects.len());
 
         for object in self.objects.iter() {
             if let Some(mut hits) = object.hit(ray) {
@@ -70,12 +388,22 @@ impl World {
     }
 }
 
+/// the reflected and refracted light leaving a surface, mixed by its `reflectance` (see
+/// `Computations::reflectance`).
+pub(crate) fn mix_secondary(reflected: Color, refracted: Color, reflectance: Option<f64>) -> Color {
+    match reflectance {
+        Some(reflectance) => reflected * reflectance + refracted * (1.0 - reflectance),
+        None => reflected + refracted,
+    }
+}
+
 impl Default for World {
     fn default() -> World {
         let mut outer = Geometry::default().with_form(Form::Sphere);
-        outer.material.texture = Texture::pattern(Pattern::solid(Color::new(0.8, 1.0, 0.6)));
-        outer.material.diffuse = 0.7;
-        outer.material.specular = 0.2;
+        outer.make_material_unique().texture =
+            Texture::pattern(Pattern::solid(Color::new(0.8, 1.0, 0.6)));
+        outer.make_material_unique().diffuse = 0.7;
+        outer.make_material_unique().specular = 0.2;
         let mut inner = Geometry::default().with_form(Form::Sphere);
         inner.transform(Matrix::scaling(0.5, 0.5, 0.5));
         let sun = Light::point(light::Point::new(
@@ -87,9 +415,15 @@ impl Default for World {
 }
 
 #[cfg(test)]
+#[allow(
+    clippy::bool_assert_comparison,
+    clippy::field_reassign_with_default,
+    clippy::useless_conversion
+)]
 mod tests {
     use super::*;
     use crate::math::Vector;
+    use std::f64::consts;
 
     #[test]
     fn empty_world() {
@@ -105,9 +439,10 @@ mod tests {
             Color::new(1.0, 1.0, 1.0),
         ));
         let mut s1 = Geometry::default().with_form(Form::Sphere);
-        s1.material.texture = Texture::pattern(Pattern::solid(Color::new(0.8, 1.0, 0.6)));
-        s1.material.diffuse = 0.7;
-        s1.material.specular = 0.2;
+        s1.make_material_unique().texture =
+            Texture::pattern(Pattern::solid(Color::new(0.8, 1.0, 0.6)));
+        s1.make_material_unique().diffuse = 0.7;
+        s1.make_material_unique().specular = 0.2;
         let mut s2 = Geometry::default().with_form(Form::Sphere);
         s2.transform(Matrix::scaling(0.5, 0.5, 0.5));
         let w = World::default();
@@ -115,6 +450,110 @@ mod tests {
         assert_eq!(w.objects, vec![s1, s2]);
     }
 
+    #[derive(Default)]
+    struct CollectingLogger {
+        events: Vec<RenderEvent>,
+    }
+
+    impl RenderLogger for CollectingLogger {
+        fn log(&mut self, record: LogRecord) {
+            self.events.push(record.event);
+        }
+    }
+
+    #[test]
+    fn prepare_default_world_has_no_warnings() {
+        let w = World::default();
+        let mut logger = CollectingLogger::default();
+        w.prepare(&mut logger);
+        assert_eq!(logger.events.len(), 1);
+        assert!(matches!(
+            logger.events[0],
+            RenderEvent::WorldPrepared { objects: 2, .. }
+        ));
+    }
+
+    #[test]
+    fn prepare_warns_about_suspicious_worlds() {
+        let w = World::new(
+            vec![
+                Geometry::default().with_form(Form::Sphere),
+                Geometry::default(),
+                Geometry::default()
+                    .with_form(Form::Sphere)
+                    .transformed(Matrix::scaling(0.0, 1.0, 1.0)),
+            ],
+            vec![],
+        );
+        let mut logger = CollectingLogger::default();
+        w.prepare(&mut logger);
+        let warnings: Vec<&RenderEvent> = logger
+            .events
+            .iter()
+            .filter(|event| event.severity() == Severity::Warning)
+            .collect();
+        assert_eq!(warnings.len(), 3);
+        assert!(matches!(
+            logger.events.last(),
+            Some(RenderEvent::WorldPrepared { objects: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn updating_shared_material_changes_every_object() {
+        let shared = Arc::new(Material::default());
+        let sphere = Geometry::default()
+            .with_form(Form::Sphere)
+            .with_material(shared.clone());
+        let grouped = Geometry::default().with_form(Form::Group(vec![sphere.clone()]));
+        let mut w = World::new(vec![sphere, grouped], vec![]);
+
+        let updated = w.update_material(&shared, |m| m.ambient = 1.0);
+        assert_eq!(updated.ambient, 1.0);
+        assert_eq!(w.objects[0].material.ambient, 1.0);
+        assert!(Arc::ptr_eq(&w.objects[0].material, &updated));
+        if let Form::Group(children) = &w.objects[1].form {
+            assert!(Arc::ptr_eq(&children[0].material, &updated));
+        } else {
+            unreachable!();
+        }
+        // the group's own material was never shared, so it was left alone.
+        assert_eq!(w.objects[1].material.ambient, 0.1);
+        // the original handle is not changed, just no longer used.
+        assert_eq!(shared.ambient, 0.1);
+    }
+
+    #[test]
+    fn unique_material_is_not_updated() {
+        let shared = Arc::new(Material::default());
+        let a = Geometry::default()
+            .with_form(Form::Sphere)
+            .with_material(shared.clone());
+        let mut b = a.clone();
+        b.make_material_unique();
+        let mut w = World::new(vec![a, b], vec![]);
+
+        w.update_material(&shared, |m| m.diffuse = 0.0);
+        assert_eq!(w.objects[0].material.diffuse, 0.0);
+        assert_eq!(w.objects[1].material.diffuse, 0.9);
+    }
+
+    #[test]
+    fn exposure_hint_for_one_watt_at_one_meter() {
+        let sphere = Geometry::default().with_form(Form::Sphere);
+        let bulb = Light::point(
+            light::Point::new(Point::new(0.0, 1.0, 0.0), Color::white()).with_power(1.0),
+        );
+        let w = World::new(vec![sphere], vec![bulb]);
+        // 1 W spread over a sphere of 1 m radius: 1 / (4 * pi) W/m^2.
+        assert!((w.exposure_hint() - 4.0 * std::f64::consts::PI).abs() < 1e-9);
+    }
+
+    #[test]
+    fn exposure_hint_without_physical_lights() {
+        assert_eq!(World::default().exposure_hint(), 1.0);
+    }
+
     #[test]
     fn intersect_with_world() {
         let w = World::default();
@@ -166,13 +605,436 @@ mod tests {
     #[test]
     fn color_with_intersection_behind_ray() {
         let mut w = World::default();
-        w.objects[0].material.ambient = 1.0;
-        w.objects[1].material.ambient = 1.0;
+        w.objects[0].make_material_unique().ambient = 1.0;
+        w.objects[1].make_material_unique().ambient = 1.0;
         let r = Ray::new(Point::new(0.0, 0.0, 0.75), Vector::new(0.0, 0.0, -1.0));
         let c = w.cast_ray(r);
         assert_eq!(c, w.objects[1].color_at(Point::zero()));
     }
 
+    fn reflective_plane() -> Geometry {
+        Geometry::default()
+            .with_form(Form::Plane)
+            .with_material(Material::default().with_reflective(0.5))
+            .transformed(Matrix::translation(0.0, -1.0, 0.0))
+    }
+
+    #[test]
+    fn reflected_color_for_nonreflective_material() {
+        let mut w = World::default();
+        w.objects[1].make_material_unique().ambient = 1.0;
+        let r = Ray::new(Point::zero(), Vector::new(0.0, 0.0, 1.0));
+        let comps = Intersection::new(1.0, r, w.objects[1].clone()).compute();
+        assert_eq!(w.reflected_color(&comps, MAX_REFLECTIONS), Color::black());
+    }
+
+    #[test]
+    fn reflected_color_for_reflective_material() {
+        let mut w = World::default();
+        let plane = reflective_plane();
+        w.objects.push(plane.clone());
+        let half = f64::from(2.0).sqrt() / 2.0;
+        let r = Ray::new(Point::new(0.0, 0.0, -3.0), Vector::new(0.0, -half, half));
+        let comps = Intersection::new(f64::from(2.0).sqrt(), r, plane).compute();
+        assert_eq!(
+            w.reflected_color(&comps, MAX_REFLECTIONS),
+            Color::new(0.19032, 0.2379, 0.14274)
+        );
+    }
+
+    #[test]
+    fn reflected_color_at_maximum_depth() {
+        let mut w = World::default();
+        let plane = reflective_plane();
+        w.objects.push(plane.clone());
+        let half = f64::from(2.0).sqrt() / 2.0;
+        let r = Ray::new(Point::new(0.0, 0.0, -3.0), Vector::new(0.0, -half, half));
+        let comps = Intersection::new(f64::from(2.0).sqrt(), r, plane).compute();
+        assert_eq!(w.reflected_color(&comps, 0), Color::black());
+    }
+
+    #[test]
+    fn mutually_reflective_surfaces_terminate() {
+        let mirror = Material::default().with_reflective(1.0);
+        let lower = Geometry::default()
+            .with_form(Form::Plane)
+            .with_material(mirror.clone())
+            .transformed(Matrix::translation(0.0, -1.0, 0.0));
+        let upper = Geometry::default()
+            .with_form(Form::Plane)
+            .with_material(mirror)
+            .transformed(Matrix::translation(0.0, 1.0, 0.0));
+        let light = Light::point(light::Point::new(Point::zero(), Color::white()));
+        let w = World::new(vec![lower, upper], vec![light]);
+        let r = Ray::new(Point::zero(), Vector::new(0.0, 1.0, 0.0));
+        assert!(w.cast_ray(r).is_finite());
+    }
+
+    #[test]
+    fn refracted_color_for_opaque_material() {
+        let w = World::default();
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = w.objects[0].hit(r).unwrap();
+        let comps = xs.closest().unwrap().compute_among(&xs);
+        assert_eq!(w.refracted_color(&comps, MAX_REFLECTIONS), Color::black());
+    }
+
+    #[test]
+    fn refracted_color_at_maximum_depth() {
+        let mut w = World::default();
+        w.objects[0] = w.objects[0].clone().with_material(
+            w.objects[0]
+                .material
+                .with_transparency(1.0)
+                .with_refractive_index(1.5),
+        );
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = w.objects[0].hit(r).unwrap();
+        let comps = xs.closest().unwrap().compute_among(&xs);
+        assert_eq!(w.refracted_color(&comps, 0), Color::black());
+    }
+
+    #[test]
+    fn refracted_color_under_total_internal_reflection() {
+        let mut w = World::default();
+        w.objects[0] = w.objects[0].clone().with_material(
+            w.objects[0]
+                .material
+                .with_transparency(1.0)
+                .with_refractive_index(1.5),
+        );
+        let half = f64::from(2.0).sqrt() / 2.0;
+        let r = Ray::new(Point::new(0.0, 0.0, half), Vector::new(0.0, 1.0, 0.0));
+        let xs = w.objects[0].hit(r).unwrap();
+        let comps = xs.closest().unwrap().compute_among(&xs);
+        assert_eq!((comps.n1, comps.n2), (1.5, 1.0));
+        assert_eq!(w.refracted_color(&comps, MAX_REFLECTIONS), Color::black());
+    }
+
+    #[test]
+    fn shading_transparent_floor() {
+        let mut w = World::default();
+        let floor = Geometry::default()
+            .with_form(Form::Plane)
+            .with_material(
+                Material::default()
+                    .with_transparency(0.5)
+                    .with_refractive_index(1.5),
+            )
+            .transformed(Matrix::translation(0.0, -1.0, 0.0));
+        let mut ball = Geometry::default()
+            .with_form(Form::Sphere)
+            .transformed(Matrix::translation(0.0, -3.5, -0.5));
+        ball.make_material_unique().texture =
+            Texture::pattern(Pattern::solid(Color::new(1.0, 0.0, 0.0)));
+        ball.make_material_unique().ambient = 0.5;
+        w.objects.push(floor);
+        w.objects.push(ball);
+        w.settings = w.settings.with_opaque_shadows(true);
+        let half = f64::from(2.0).sqrt() / 2.0;
+        let r = Ray::new(Point::new(0.0, 0.0, -3.0), Vector::new(0.0, -half, half));
+        assert_eq!(w.cast_ray(r), Color::new(0.93642, 0.68642, 0.68642));
+    }
+
+    #[test]
+    fn shading_reflective_transparent_floor() {
+        let mut w = World::default();
+        let floor = Geometry::default()
+            .with_form(Form::Plane)
+            .with_material(
+                Material::default()
+                    .with_reflective(0.5)
+                    .with_transparency(0.5)
+                    .with_refractive_index(1.5),
+            )
+            .transformed(Matrix::translation(0.0, -1.0, 0.0));
+        let mut ball = Geometry::default()
+            .with_form(Form::Sphere)
+            .transformed(Matrix::translation(0.0, -3.5, -0.5));
+        ball.make_material_unique().texture =
+            Texture::pattern(Pattern::solid(Color::new(1.0, 0.0, 0.0)));
+        ball.make_material_unique().ambient = 0.5;
+        w.objects.push(floor);
+        w.objects.push(ball);
+        w.settings = w.settings.with_opaque_shadows(true);
+        let half = f64::from(2.0).sqrt() / 2.0;
+        let r = Ray::new(Point::new(0.0, 0.0, -3.0), Vector::new(0.0, -half, half));
+        assert_eq!(w.cast_ray(r), Color::new(0.93391, 0.69643, 0.69243));
+    }
+
+    #[test]
+    fn light_passes_through_transparent_floor_onto_ball() {
+        for (reflective, expected) in [
+            (0.0, Color::new(1.12547, 0.68643, 0.68643)),
+            (0.5, Color::new(1.11500, 0.69644, 0.69243)),
+        ] {
+            let mut w = World::default();
+            let floor = Geometry::default()
+                .with_form(Form::Plane)
+                .with_material(
+                    Material::default()
+                        .with_reflective(reflective)
+                        .with_transparency(0.5)
+                        .with_refractive_index(1.5),
+                )
+                .transformed(Matrix::translation(0.0, -1.0, 0.0));
+            let mut ball = Geometry::default()
+                .with_form(Form::Sphere)
+                .transformed(Matrix::translation(0.0, -3.5, -0.5));
+            ball.make_material_unique().texture =
+                Texture::pattern(Pattern::solid(Color::new(1.0, 0.0, 0.0)));
+            ball.make_material_unique().ambient = 0.5;
+            w.objects.push(floor);
+            w.objects.push(ball);
+            let half = f64::from(2.0).sqrt() / 2.0;
+            let r = Ray::new(Point::new(0.0, 0.0, -3.0), Vector::new(0.0, -half, half));
+            // unlike the book, half of the light gets through the floor onto the red ball.
+            assert_eq!(w.cast_ray(r), expected);
+        }
+    }
+
+    fn mirror_scene() -> World {
+        let mut w = World::default();
+        let mirror = Material::default().with_reflective(0.8);
+        w.objects.push(
+            Geometry::default()
+                .with_form(Form::Plane)
+                .with_material(mirror.clone())
+                .transformed(Matrix::translation(0.0, -1.0, 0.0)),
+        );
+        w.objects.push(
+            Geometry::default()
+                .with_form(Form::Plane)
+                .with_material(mirror)
+                .transformed(Matrix::translation(0.0, 3.0, 0.0)),
+        );
+        w
+    }
+
+    #[test]
+    fn deeper_bounces_change_reflections() {
+        let r = Ray::new(Point::new(3.0, 0.0, -3.0), Vector::new(0.0, -0.6, 0.8));
+        let shallow = mirror_scene()
+            .with_settings(RenderSettings::default().with_max_bounces(1))
+            .cast_ray(r);
+        let deep = mirror_scene()
+            .with_settings(RenderSettings::default().with_max_bounces(5))
+            .cast_ray(r);
+        assert_ne!(shallow, deep);
+    }
+
+    #[test]
+    fn no_bounces_gives_direct_lighting() {
+        let r = Ray::new(Point::new(3.0, 0.0, -3.0), Vector::new(0.0, -0.6, 0.8));
+        let direct = mirror_scene()
+            .with_settings(RenderSettings::default().with_max_bounces(0))
+            .cast_ray(r);
+        let mut matte = mirror_scene();
+        for object in &mut matte.objects[2..] {
+            object.make_material_unique().reflective = 0.0;
+        }
+        assert_eq!(direct, matte.cast_ray(r));
+    }
+
+    #[test]
+    fn missed_rays_see_sky_gradient() {
+        let w = World::default().with_background(Background::VerticalGradient {
+            zenith: Color::new(0.2, 0.4, 1.0),
+            horizon: Color::white(),
+        });
+        let up = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 1.0, 0.0));
+        let level = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(1.0, 0.0, 0.0));
+        assert_eq!(w.cast_ray(up), Color::new(0.2, 0.4, 1.0));
+        assert_eq!(w.cast_ray(level), Color::white());
+    }
+
+    #[test]
+    fn missed_rays_show_background() {
+        let w = World::default().with_background(Background::Solid(Color::white()));
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 1.0, 0.0));
+        assert_eq!(w.cast_ray(r), Color::white());
+    }
+
+    #[test]
+    fn shadows_can_be_turned_off() {
+        let mut w = World::default();
+        w.lights = vec![Light::point(light::Point::new(
+            Point::new(0.0, 0.0, -10.0),
+            Color::new(1.0, 1.0, 1.0),
+        ))];
+        w.objects = vec![
+            Geometry::default().with_form(Form::Sphere),
+            Geometry::default()
+                .with_form(Form::Sphere)
+                .transformed(Matrix::translation(0.0, 0.0, 10.0)),
+        ];
+        let r = Ray::new(Point::new(0.0, 0.0, 5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(w.cast_ray(r), Color::new(0.1, 0.1, 0.1));
+        let w = w.with_settings(RenderSettings::default().with_shadows(false));
+        assert_eq!(w.cast_ray(r), Color::new(1.9, 1.9, 1.9));
+    }
+
+    fn sphere_on_floor() -> World {
+        let floor = Geometry::default().with_form(Form::Plane);
+        let ball = Geometry::default()
+            .with_form(Form::Sphere)
+            .transformed(Matrix::translation(0.0, 1.0, 0.0));
+        let sun = Light::point(light::Point::new(
+            Point::new(0.0, 10.0, -10.0),
+            Color::white(),
+        ));
+        World::new(vec![floor, ball], vec![sun])
+    }
+
+    #[test]
+    fn ambient_occlusion_darkens_contact_point() {
+        // looks at the floor right next to where the ball touches it.
+        let r = Ray::new(Point::new(0.0, 5.0, -1.2), Vector::new(0.0, -1.0, 0.0));
+        let plain = sphere_on_floor().cast_ray(r);
+        let occluded = sphere_on_floor()
+            .with_settings(
+                RenderSettings::default()
+                    .with_ambient_occlusion(AmbientOcclusion::default().with_samples(64)),
+            )
+            .cast_ray(r);
+        assert!(occluded.red() < plain.red());
+        assert!(occluded.green() < plain.green());
+        assert!(occluded.blue() < plain.blue());
+
+        // far from the ball, nothing occludes the floor.
+        let open = Ray::new(Point::new(5.0, 5.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+        let w = sphere_on_floor().with_settings(
+            RenderSettings::default().with_ambient_occlusion(AmbientOcclusion::default()),
+        );
+        assert_eq!(w.cast_ray(open), sphere_on_floor().cast_ray(open));
+    }
+
+    #[test]
+    fn ambient_occlusion_without_strength_changes_nothing() {
+        let r = Ray::new(Point::new(0.0, 5.0, -1.2), Vector::new(0.0, -1.0, 0.0));
+        let w = sphere_on_floor().with_settings(
+            RenderSettings::default()
+                .with_ambient_occlusion(AmbientOcclusion::default().with_strength(0.0)),
+        );
+        let plain = sphere_on_floor().cast_ray(r);
+        assert_eq!(w.cast_ray(r).red(), plain.red());
+        assert_eq!(w.cast_ray(r).green(), plain.green());
+        assert_eq!(w.cast_ray(r).blue(), plain.blue());
+    }
+
+    #[test]
+    fn ambient_occlusion_is_reproducible() {
+        let r = Ray::new(Point::new(0.0, 5.0, -1.2), Vector::new(0.0, -1.0, 0.0));
+        let with_seed = |seed| {
+            sphere_on_floor()
+                .with_settings(RenderSettings::default().with_ambient_occlusion(
+                    AmbientOcclusion::default().with_samples(4).with_seed(seed),
+                ))
+                .cast_ray(r)
+        };
+        assert_eq!(with_seed(7).red(), with_seed(7).red());
+    }
+
+    #[test]
+    fn thicker_glass_absorbs_more_light() {
+        let glass = Material {
+            ambient: 0.0,
+            diffuse: 0.0,
+            specular: 0.0,
+            ..Material::default()
+        }
+        .with_transparency(1.0)
+        .with_refractive_index(1.5)
+        .with_absorption(Color::new(0.5, 0.5, 0.5));
+        let mut wall = Geometry::default().with_form(Form::Plane).transformed(
+            Matrix::translation(0.0, 0.0, 10.0) * Matrix::rotation_x(consts::PI / 2.0),
+        );
+        wall.make_material_unique().ambient = 1.0;
+        let light = Light::point(light::Point::new(
+            Point::new(0.0, 0.0, -20.0),
+            Color::white(),
+        ));
+
+        let seen_through = |thickness: f64| {
+            let slab = Geometry::default()
+                .with_form(Form::Box {
+                    min: Point::new(-5.0, -5.0, 0.0),
+                    max: Point::new(5.0, 5.0, thickness),
+                })
+                .with_material(glass.clone());
+            let w = World::new(vec![slab.clone(), wall.clone()], vec![light]);
+            let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+            let xs = slab.hit(r).unwrap();
+            let comps = xs.closest().unwrap().compute_among(&xs);
+            w.refracted_color(&comps, MAX_REFLECTIONS)
+        };
+        let thin = seen_through(0.5);
+        let thick = seen_through(2.0);
+        assert!(thick.red() < thin.red());
+        assert_eq!(thick, thin * (-0.5 * 1.5_f64).exp());
+    }
+
+    #[test]
+    fn ambient_is_counted_once_for_many_lights() {
+        let w = World::default();
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = w.hit(r).unwrap();
+        let comps = xs.closest().unwrap().compute_among(&xs);
+        let ambient = w.ambient_at(&comps);
+        let one = w.shade(&comps);
+
+        let mut two_lights = World::default();
+        two_lights.lights.push(two_lights.lights[0]);
+        let two = two_lights.shade(&comps);
+        assert_eq!(two, one * 2.0 - ambient);
+        assert_eq!(ambient, Color::new(0.08, 0.1, 0.06));
+    }
+
+    #[test]
+    fn colored_ambient_light_tints_shadowed_surface() {
+        let mut w = World::default();
+        w.lights = vec![Light::point(light::Point::new(
+            Point::new(0.0, 0.0, -10.0),
+            Color::white(),
+        ))];
+        let s = Geometry::default()
+            .with_form(Form::Sphere)
+            .transformed(Matrix::translation(0.0, 0.0, 10.0));
+        w.objects.push(s.clone());
+        let r = Ray::new(Point::new(0.0, 0.0, 5.0), Vector::new(0.0, 0.0, 1.0));
+        let comps = Intersection::new(4.0, r, s).compute();
+        assert_eq!(w.shade(&comps), Color::new(0.1, 0.1, 0.1));
+
+        let w = World {
+            ambient_light: Color::new(0.2, 0.2, 0.3),
+            ..w
+        };
+        assert_eq!(w.shade(&comps), Color::new(0.02, 0.02, 0.03));
+    }
+
+    #[test]
+    fn white_ambient_light_changes_nothing() {
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let w = World::default().with_ambient_light(Color::white());
+        assert_eq!(w.cast_ray(r), Color::new(0.38066, 0.47583, 0.2855));
+    }
+
+    #[test]
+    fn shadowed_light_gives_nothing() {
+        let mut w = World::default();
+        w.lights = vec![Light::point(light::Point::new(
+            Point::new(0.0, 0.0, -10.0),
+            Color::white(),
+        ))];
+        let r = Ray::new(Point::new(0.0, 0.0, 5.0), Vector::new(0.0, 0.0, 1.0));
+        let s = Geometry::default()
+            .with_form(Form::Sphere)
+            .transformed(Matrix::translation(0.0, 0.0, 10.0));
+        w.objects.push(s.clone());
+        let comps = Intersection::new(4.0, r, s).compute();
+        assert_eq!(w.lights[0].illuminate(&w, &comps), Color::black());
+    }
+
     #[test]
     fn no_shadow_when_nothing_blocks_light() {
         let w = World::default();
@@ -213,11 +1075,11 @@ mod tests {
         let s2 = Geometry::default()
             .with_form(Form::Sphere)
             .transformed(Matrix::translation(0.0, 0.0, 10.0));
-        w.objects.push(s2);
+        w.objects.push(s2.clone());
         let r = Ray::new(Point::new(0.0, 0.0, 5.0), Vector::new(0.0, 0.0, 1.0));
         let i = Intersection::new(4.0, r, s2);
         let comps = i.compute();
-        let c = w.lights[0].illuminate(&w, &comps);
+        let c = w.shade(&comps);
         assert_eq!(c, Color::new(0.1, 0.1, 0.1));
     }
 }
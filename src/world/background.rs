@@ -0,0 +1,84 @@
+use crate::{
+    math::{clamp_between, Vector},
+    world::Color,
+};
+
+/// what rays that don't hit anything see.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Background {
+    Solid(Color),
+    /// a sky that fades from `horizon`, for rays that run level or point down, to
+    /// `zenith`, for rays that point straight up.
+    VerticalGradient {
+        zenith: Color,
+        horizon: Color,
+    },
+}
+
+impl Background {
+    /// the color seen by a ray heading in `direction`.
+    pub fn color_for(&self, direction: Vector) -> Color {
+        match self {
+            Background::Solid(color) => *color,
+            Background::VerticalGradient { zenith, horizon } => {
+                let t = clamp_between(direction.normalized()[1], 0.0, 1.0);
+                *horizon * (1.0 - t) + *zenith * t
+            }
+        }
+    }
+}
+
+impl Default for Background {
+    fn default() -> Background {
+        Background::Solid(Color::black())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sky() -> Background {
+        Background::VerticalGradient {
+            zenith: Color::new(0.2, 0.4, 1.0),
+            horizon: Color::white(),
+        }
+    }
+
+    #[test]
+    fn default_background_is_black() {
+        assert_eq!(
+            Background::default().color_for(Vector::new(0.0, 1.0, 0.0)),
+            Color::black()
+        );
+    }
+
+    #[test]
+    fn solid_background_ignores_direction() {
+        let background = Background::Solid(Color::new(0.1, 0.2, 0.3));
+        assert_eq!(
+            background.color_for(Vector::new(1.0, 0.0, 0.0)),
+            Color::new(0.1, 0.2, 0.3)
+        );
+        assert_eq!(
+            background.color_for(Vector::new(0.0, -1.0, 0.0)),
+            Color::new(0.1, 0.2, 0.3)
+        );
+    }
+
+    #[test]
+    fn gradient_by_ray_height() {
+        let sky = sky();
+        assert_eq!(
+            sky.color_for(Vector::new(0.0, 2.0, 0.0)),
+            Color::new(0.2, 0.4, 1.0)
+        );
+        assert_eq!(sky.color_for(Vector::new(0.0, 0.0, 1.0)), Color::white());
+        assert_eq!(sky.color_for(Vector::new(0.0, -1.0, 1.0)), Color::white());
+        assert_eq!(
+            sky.color_for(Vector::new(0.0, 1.0, 0.0) + Vector::new(0.0, 0.0, 3.0_f64.sqrt())),
+            Color::new(0.6, 0.7, 1.0)
+        );
+    }
+}
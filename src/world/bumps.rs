@@ -0,0 +1,130 @@
+use crate::math::{noise::perlin, Point, Vector};
+
+/// fakes a rough surface by tilting its normals by noise, without moving the surface.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Bumps {
+    /// how far the normal is pushed along the surface, as a fraction of its length.
+    pub amplitude: f64,
+    /// how many bumps there are per unit of distance in object space.
+    pub frequency: f64,
+}
+
+impl Bumps {
+    pub fn new(amplitude: f64, frequency: f64) -> Bumps {
+        Bumps {
+            amplitude,
+            frequency,
+        }
+    }
+
+    /// tilts the object space `normal` at `object_space_point`. the normal is only
+    /// pushed along the surface, by at most `amplitude`, so it never ends up facing
+    /// into it.
+    pub fn perturb(&self, object_space_point: Point, normal: Vector) -> Vector {
+        let normal = normal.normalized();
+        let p = Point::new(
+            object_space_point[0] * self.frequency,
+            object_space_point[1] * self.frequency,
+            object_space_point[2] * self.frequency,
+        );
+        // three independent noises, offset from each other so they don't line up.
+        let noise = Vector::new(
+            perlin(p),
+            perlin(p + Vector::new(31.4, 15.9, 26.5)),
+            perlin(p + Vector::new(-35.8, 97.9, -32.3)),
+        );
+        let along_surface = noise.reject_from(&normal);
+        let length = along_surface.magnitude();
+        let delta = if length > 1.0 {
+            along_surface / length
+        } else {
+            along_surface
+        } * self.amplitude;
+        (normal + delta).normalized()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        math::{Form, Geometry, Hittable, Matrix, Transformable},
+        world::{light, Camera, Canvas, Color, Light, View, World},
+    };
+    use std::f64::consts;
+
+    #[test]
+    fn perturbation_is_bounded_by_amplitude() {
+        let bumps = Bumps::new(0.3, 4.0);
+        let mut changed = false;
+        for i in 0..200 {
+            let theta = i as f64 * 0.37;
+            let phi = i as f64 * 0.11;
+            let normal = Vector::new(phi.sin() * theta.cos(), phi.cos(), phi.sin() * theta.sin());
+            let point = Point::zero() + normal;
+            let perturbed = bumps.perturb(point, normal);
+            assert!((perturbed.magnitude() - 1.0).abs() < 1e-9);
+            assert!((perturbed - normal).magnitude() <= 0.3 + 1e-9);
+            assert!(perturbed.dot(&normal) > 0.0);
+            changed |= (perturbed - normal).magnitude() > 0.01;
+        }
+        assert!(changed);
+    }
+
+    #[test]
+    fn no_amplitude_changes_nothing() {
+        let normal = Vector::new(0.0, 0.6, 0.8);
+        assert_eq!(
+            Bumps::new(0.0, 3.0).perturb(Point::new(0.3, 0.6, 0.8), normal),
+            normal
+        );
+    }
+
+    #[test]
+    fn bumped_normals_are_transformed() {
+        let mut sphere = Geometry::default()
+            .with_form(Form::Sphere)
+            .transformed(Matrix::scaling(2.0, 2.0, 2.0));
+        let smooth = sphere.normal_at(Point::new(0.0, 2.0, 0.0)).unwrap();
+        sphere.make_material_unique().normal_perturbation = Some(Bumps::new(0.5, 3.0));
+        let bumpy = sphere.normal_at(Point::new(0.0, 2.0, 0.0)).unwrap();
+        assert!((bumpy.magnitude() - 1.0).abs() < 1e-9);
+        assert!(smooth != bumpy);
+        assert!((bumpy - smooth).magnitude() <= 0.5 + 1e-9);
+    }
+
+    /// how many times the brightness along the middle row of an image turns from
+    /// rising to falling.
+    fn peaks(image: &Canvas) -> usize {
+        let row: Vec<f64> = (0..image.width)
+            .map(|x| image[(x, image.height / 2)].red())
+            .collect();
+        row.windows(3)
+            .filter(|w| w[0] < w[1] && w[2] < w[1])
+            .count()
+    }
+
+    #[test]
+    fn bumps_break_up_highlight() {
+        let render = |bumps: Option<Bumps>| {
+            let mut sphere = Geometry::default().with_form(Form::Sphere);
+            sphere.make_material_unique().normal_perturbation = bumps;
+            let lamp = Light::point(light::Point::new(
+                Point::new(0.0, 0.0, -10.0),
+                Color::white(),
+            ));
+            let world = World::new(vec![sphere], vec![lamp]);
+            let mut camera = Camera::new(41, 41, consts::PI / 8.0);
+            camera.set_view(View::transformed(
+                Point::new(0.0, 0.0, -6.0),
+                Point::zero(),
+                Vector::new(0.0, 1.0, 0.0),
+            ));
+            camera.render(&world)
+        };
+
+        assert_eq!(peaks(&render(None)), 1);
+        assert!(peaks(&render(Some(Bumps::new(0.2, 8.0)))) > 1);
+    }
+}
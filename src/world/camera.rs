@@ -1,65 +1,536 @@
 use crate::{
-    math::{matrix::Matrix, point::Point, vector::Vector},
-    world::{canvas::Canvas, ray::Ray, World},
+    math::{
+        change_interval, matrix::Matrix, point::Point, sample_disk, vector::Vector, BoundingBox,
+        EPSILON,
+    },
+    world::{
+        canvas::Canvas, color::Color, intersection::Computations, ray::Ray, sampler::pixel_rng,
+        stats::RayCounts, LogRecord, NoopLogger, RenderEvent, RenderLogger, RenderStats, Sampler,
+        Sampling, Textured, World,
+    },
+    RayTracerError,
 };
 
+use std::{
+    cmp,
+    f64::consts,
+    panic,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread,
+    time::Instant,
+};
+
+/// mixed into the sampler's seed to give the lens and the shutter streams of random
+/// numbers of their own, apart from the one that places rays within each pixel.
+const LENS_STREAM: u64 = 0x6c65_6e73;
+const SHUTTER_STREAM: u64 = 0x7368_7574;
+
+/// where the camera stands and which way it faces, along with the world-to-camera
+/// transform that follows from them.
+///
+/// the transform and its inverse can only change together, so neither can go stale:
+///
+/// ```compile_fail
+/// use ray_tracer_challenge::{math::Matrix, world::View};
+///
+/// let mut view = View::default();
+/// view.transform = Matrix::translation(0.0, -2.0, 5.0);
+/// ```
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "SerializedView")
+)]
 pub struct View {
-    pub transform: Matrix,
-    pub inverse: Matrix,
+    transform: Matrix,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    inverse: Matrix,
+    from: Point,
+    to: Point,
+    up: Vector,
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct SerializedView {
+    transform: Matrix,
+    from: Point,
+    to: Point,
+    up: Vector,
+}
+
+#[cfg(feature = "serde")]
+impl From<SerializedView> for View {
+    fn from(serialized: SerializedView) -> View {
+        View {
+            from: serialized.from,
+            to: serialized.to,
+            up: serialized.up,
+            ..View::from_matrix(serialized.transform)
+        }
+    }
 }
 
 impl View {
+    /// a view from `from`, looking at `to`, with `up` roughly upwards.
+    ///
+    /// # panics
+    ///
+    /// panics if `from` and `to` are the same point. see `try_transformed`.
     pub fn transformed(from: Point, to: Point, up: Vector) -> View {
+        View::try_transformed(from, to, up).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// a view from `from`, looking at `to`, with `up` roughly upwards, or an error if
+    /// `from` and `to` are too close together to say which way the camera faces.
+    pub fn try_transformed(from: Point, to: Point, up: Vector) -> Result<View, RayTracerError> {
+        if (to - from).magnitude() < EPSILON {
+            return Err(RayTracerError::DegenerateView { from });
+        }
         let mut view = View::default();
+        view.set_transform(
+            orientation(from, to, up) * Matrix::translation(-from[0], -from[1], -from[2]),
+        );
+        // keep the parameters exactly as given, rather than as read back.
+        view.from = from;
+        view.to = to;
+        view.up = up;
+        Ok(view)
+    }
+
+    pub fn transform(&mut self, from: Point, to: Point, up: Vector) -> &mut View {
+        *self = View::transformed(from, to, up);
+        self
+    }
 
-        let forward = (to - from).normalized();
-        let up = up.normalized();
-        let left = forward.cross(&up);
-        let up = left.cross(&forward);
+    /// a view given directly by its world-to-camera transform. the camera is taken to
+    /// look one unit ahead of where it stands.
+    ///
+    /// # panics
+    ///
+    /// panics if the transform has no inverse, as the camera's rays couldn't be
+    /// brought back into the world.
+    pub fn from_matrix(transform: Matrix) -> View {
+        *View::default().set_transform(transform)
+    }
 
-        #[rustfmt::skip]
-        let orientation = Matrix::new(
-            left[0],     left[1],     left[2],     0.0,
-            up[0],       up[1],       up[2],       0.0,
-            -forward[0], -forward[1], -forward[2], 0.0,
+    /// replaces the world-to-camera transform, along with its inverse and where the
+    /// camera stands and looks.
+    ///
+    /// # panics
+    ///
+    /// panics if the transform has no inverse, like `from_matrix`.
+    pub fn set_transform(&mut self, transform: Matrix) -> &mut View {
+        self.transform = transform;
+        self.inverse = transform
+            .checked_inverse()
+            .unwrap_or_else(|error| panic!("{}", error));
+        self.from = self.inverse * Point::zero();
+        self.to = self.from + self.inverse * Vector::new(0.0, 0.0, -1.0);
+        self.up = self.inverse * Vector::new(0.0, 1.0, 0.0);
+        self
+    }
+
+    pub fn transformation(&self) -> Matrix {
+        self.transform
+    }
+
+    pub fn inverse(&self) -> Matrix {
+        self.inverse
+    }
+
+    /// where the camera stands.
+    pub fn from(&self) -> Point {
+        self.from
+    }
+
+    /// the point the camera looks at.
+    pub fn to(&self) -> Point {
+        self.to
+    }
+
+    /// which way is roughly up for the camera.
+    pub fn up(&self) -> Vector {
+        self.up
+    }
+
+    /// swings the camera `radians` around the vertical line through the point it looks
+    /// at, keeping it at the same height and distance.
+    pub fn orbit_y(&mut self, radians: f64) -> &mut View {
+        let rotation = Matrix::rotation_y(radians);
+        let (from, to, up) = (
+            self.to + rotation * (self.from - self.to),
+            self.to,
+            rotation * self.up,
         );
+        self.transform(from, to, up)
+    }
 
-        view.transform = orientation * Matrix::translation(-from[0], -from[1], -from[2]);
-        view.inverse = view.transform.inverse();
+    /// moves the camera `distance` towards the point it looks at, or away from it when
+    /// `distance` is negative. the point it looks at moves along with it, so the camera
+    /// keeps facing the same way.
+    pub fn dolly(&mut self, distance: f64) -> &mut View {
+        let step = (self.to - self.from).normalized() * distance;
+        let (from, to, up) = (self.from + step, self.to + step, self.up);
+        self.transform(from, to, up)
+    }
 
-        view
+    /// slides the camera and the point it looks at `dx` to the right and `dy` up, as
+    /// seen through the camera.
+    pub fn pan(&mut self, dx: f64, dy: f64) -> &mut View {
+        // camera space has x pointing to the left of the image.
+        let step = self.inverse * Vector::new(-dx, dy, 0.0);
+        let (from, to, up) = (self.from + step, self.to + step, self.up);
+        self.transform(from, to, up)
     }
 
-    pub fn transform(&mut self, from: Point, to: Point, up: Vector) -> &mut View {
-        *self = View::transformed(from, to, up);
-        self
+    /// turns the camera to face `point`, without moving it.
+    pub fn look_at(&mut self, point: Point) -> &mut View {
+        let (from, up) = (self.from, self.up);
+        self.transform(from, point, up)
+    }
+
+    pub fn debug_assert_consistent(&self) {
+        self.transform.debug_assert_inverse(&self.inverse, "View");
     }
 }
 
 impl Default for View {
+    /// standing at the origin, looking down the negative z axis.
     fn default() -> View {
         View {
             transform: Matrix::identity(),
             inverse: Matrix::identity(),
+            from: Point::zero(),
+            to: Point::new(0.0, 0.0, -1.0),
+            up: Vector::new(0.0, 1.0, 0.0),
+        }
+    }
+}
+
+/// turns the world so that a camera at `from` looks at `to`, with `up` pointing up.
+fn orientation(from: Point, to: Point, up: Vector) -> Matrix {
+    let forward = (to - from).normalized();
+    let mut left = forward.cross(&up.normalized());
+    if left.magnitude() < EPSILON {
+        // the up vector is missing, or points along the view, so it says nothing
+        // about which way up is. lean on the y axis instead, or the x axis when
+        // looking straight up or down.
+        let fallback = if forward[1].abs() < 1.0 - EPSILON {
+            Vector::new(0.0, 1.0, 0.0)
+        } else {
+            Vector::new(1.0, 0.0, 0.0)
+        };
+        left = forward.cross(&fallback);
+    }
+    let up = left.cross(&forward);
+
+    #[rustfmt::skip]
+    let orientation = Matrix::new(
+        left[0],     left[1],     left[2],     0.0,
+        up[0],       up[1],       up[2],       0.0,
+        -forward[0], -forward[1], -forward[2], 0.0,
+    );
+    orientation
+}
+
+/// the angle a perspective camera sees, along one side of the image. the other side
+/// covers whatever angle the aspect ratio gives it.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Fov {
+    /// the angle from the left edge of the image to the right.
+    Horizontal(f64),
+    /// the angle from the top edge of the image to the bottom.
+    Vertical(f64),
+}
+
+/// how rays leave the camera and pass through the canvas.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Projection {
+    /// rays fan out from a single eye point, so the canvas covers `field_of_view` radians.
+    Perspective,
+    /// rays run parallel to each other, so the canvas covers a fixed area in world units.
+    Orthographic,
+    /// rays fan out from a single eye point, with their angle from the view direction
+    /// growing in step with their distance from the center of the canvas. the circle
+    /// that fits the canvas covers `field_of_view` radians, and positions outside it
+    /// show `border`, or the world's background when there is no border.
+    Fisheye { border: Option<Color> },
+    /// rays fan out from a single eye point in every direction. across the canvas, the
+    /// longitude runs from behind the camera on the right, through the view direction,
+    /// round to behind it on the left. down the canvas, the latitude runs from straight
+    /// up to straight down.
+    Equirectangular,
+}
+
+/// what to do when a pixel's color comes out NaN or infinite.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NonFinite {
+    /// stop rendering immediately, reporting the pixel and the ray that produced it.
+    Panic,
+    /// write the given color instead, and count the pixel in the render statistics.
+    Replace(Color),
+}
+
+impl Default for NonFinite {
+    /// panics in debug builds so that bugs are caught at their source, and otherwise
+    /// marks bad pixels in magenta so that a long render still finishes.
+    fn default() -> NonFinite {
+        if cfg!(debug_assertions) {
+            NonFinite::Panic
+        } else {
+            NonFinite::Replace(Color::new(1.0, 0.0, 1.0))
+        }
+    }
+}
+
+/// settings that change how an image is rendered, but not what it looks like.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RenderOptions {
+    /// the image is rendered in square tiles of this many pixels on a side, and
+    /// progress is reported once per tile.
+    pub tile_size: usize,
+    /// when set, how many pixels are finished between reports of how far the render
+    /// has got and how many rays it has fired.
+    pub progress_interval: Option<usize>,
+    /// how many threads share the work. the image comes out the same however many
+    /// there are.
+    pub threads: usize,
+    /// whether to count the shadow and secondary rays fired and the intersection tests
+    /// made. counting them slows the render down a little, so renders that throw
+    /// their statistics away leave it off.
+    pub count_rays: bool,
+}
+
+impl Default for RenderOptions {
+    /// uses as many threads as the machine can run at once.
+    fn default() -> RenderOptions {
+        RenderOptions {
+            tile_size: 16,
+            progress_interval: None,
+            threads: thread::available_parallelism().map_or(1, |threads| threads.get()),
+            count_rays: true,
         }
     }
 }
 
+/// an image along with the auxiliary passes that denoisers and debugging want, all
+/// of the same size and lined up pixel for pixel.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RenderOutput {
+    /// the image as `Camera::render` makes it.
+    pub beauty: Canvas,
+    /// the surface normal in world space where each ray first hits, facing the camera,
+    /// with each component mapped from -1 to 1 onto 0 to 1. misses face straight
+    /// along +z, which comes out as (0.5, 0.5, 1).
+    pub normal: Canvas,
+    /// the unlit surface color where each ray first hits, or the background for misses.
+    pub albedo: Canvas,
+}
+
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "SerializedCamera", into = "SerializedCamera")
+)]
 pub struct Camera {
     pub image_width: usize,
     pub image_height: usize,
     pub field_of_view: f64,
-    pub view: View,
+    pub projection: Projection,
+    view: View,
+    pub on_non_finite: NonFinite,
+    /// picks the rays whose colors are averaged to render each pixel. by default, a
+    /// single ray passes through its center.
+    pub sampling: Sampling,
+    /// when set, every pixel is first rendered with a single ray through its center,
+    /// and only those whose color differs from a neighbor's by more than this distance
+    /// are rendered again using `sampling`.
+    pub adaptive_threshold: Option<f64>,
+    /// the radius of the lens, in world units. with 0, the camera is a pinhole and
+    /// everything is in focus. otherwise, only things `focal_distance` away are.
+    pub aperture: f64,
+    /// how far in front of the camera things are in perfect focus.
+    pub focal_distance: f64,
+    /// how many rays through different points on the lens are averaged for each of the
+    /// pixel's samples, when the aperture isn't 0.
+    pub lens_samples: usize,
+    /// the times at which the shutter opens and closes. each ray is fired at a random
+    /// time in between, so that moving objects are blurred along their path. to blur
+    /// more smoothly, fire more rays per pixel with `sampling`.
+    pub shutter: (f64, f64),
+    /// brightens the image by this many stops, so that each step of 1 doubles every
+    /// pixel, before its color is clamped. negative stops darken it instead.
+    pub exposure: f64,
     half_width: f64,
     half_height: f64,
     pixel_size: f64,
 }
 
+/// a camera as it is serialized. the size of its pixels and of its canvas follow from
+/// `half_view`, which is kept instead of them since the field of view doesn't say how
+/// much an orthographic camera sees.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SerializedCamera {
+    image_width: usize,
+    image_height: usize,
+    field_of_view: f64,
+    projection: Projection,
+    /// half the extent of the canvas along its longer side.
+    half_view: f64,
+    view: View,
+    on_non_finite: NonFinite,
+    sampling: Sampling,
+    adaptive_threshold: Option<f64>,
+    aperture: f64,
+    focal_distance: f64,
+    lens_samples: usize,
+    shutter: (f64, f64),
+    exposure: f64,
+}
+
+#[cfg(feature = "serde")]
+impl From<Camera> for SerializedCamera {
+    fn from(camera: Camera) -> SerializedCamera {
+        SerializedCamera {
+            image_width: camera.image_width,
+            image_height: camera.image_height,
+            field_of_view: camera.field_of_view,
+            projection: camera.projection,
+            half_view: camera.half_width.max(camera.half_height),
+            view: camera.view,
+            on_non_finite: camera.on_non_finite,
+            sampling: camera.sampling,
+            adaptive_threshold: camera.adaptive_threshold,
+            aperture: camera.aperture,
+            focal_distance: camera.focal_distance,
+            lens_samples: camera.lens_samples,
+            shutter: camera.shutter,
+            exposure: camera.exposure,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<SerializedCamera> for Camera {
+    fn from(serialized: SerializedCamera) -> Camera {
+        Camera {
+            view: serialized.view,
+            on_non_finite: serialized.on_non_finite,
+            sampling: serialized.sampling,
+            adaptive_threshold: serialized.adaptive_threshold,
+            aperture: serialized.aperture,
+            focal_distance: serialized.focal_distance,
+            lens_samples: serialized.lens_samples,
+            shutter: serialized.shutter,
+            exposure: serialized.exposure,
+            ..Camera::with_half_view(
+                serialized.image_width,
+                serialized.image_height,
+                serialized.field_of_view,
+                serialized.projection,
+                serialized.half_view,
+            )
+        }
+    }
+}
+
 impl Camera {
+    /// creates a perspective camera that sees `field_of_view` radians along the longer
+    /// side of the image.
     pub fn new(image_width: usize, image_height: usize, field_of_view: f64) -> Camera {
         let half_view = (field_of_view / 2.0).tan();
+
+        Camera::with_half_view(
+            image_width,
+            image_height,
+            field_of_view,
+            Projection::Perspective,
+            half_view,
+        )
+    }
+
+    /// creates a perspective camera that sees `fov` along the side it names, whatever
+    /// the shape of the image. the camera's `field_of_view` is then the angle along the
+    /// longer side, as with `Camera::new`.
+    pub fn perspective(image_width: usize, image_height: usize, fov: Fov) -> Camera {
+        let aspect_ratio = (image_width as f64) / (image_height as f64);
+
+        let (half_width, half_height) = match fov {
+            Fov::Horizontal(angle) => {
+                let half_width = (angle / 2.0).tan();
+                (half_width, half_width / aspect_ratio)
+            }
+            Fov::Vertical(angle) => {
+                let half_height = (angle / 2.0).tan();
+                (half_height * aspect_ratio, half_height)
+            }
+        };
+        let half_view = half_width.max(half_height);
+
+        Camera::with_half_view(
+            image_width,
+            image_height,
+            2.0 * half_view.atan(),
+            Projection::Perspective,
+            half_view,
+        )
+    }
+
+    /// creates a camera whose parallel rays cover `view_size` world units along the
+    /// longer side of the image. the field of view is meaningless here, so it is zero.
+    pub fn orthographic(image_width: usize, image_height: usize, view_size: f64) -> Camera {
+        Camera::with_half_view(
+            image_width,
+            image_height,
+            0.0,
+            Projection::Orthographic,
+            view_size / 2.0,
+        )
+    }
+
+    /// creates a camera whose rays fan out evenly by angle, so that the circle fitting
+    /// inside the canvas covers `field_of_view` radians, which may be more than pi.
+    pub fn fisheye(image_width: usize, image_height: usize, field_of_view: f64) -> Camera {
+        let aspect_ratio = (image_width as f64) / (image_height as f64);
+
+        // the shorter side of the canvas runs from -1 to 1.
+        Camera::with_half_view(
+            image_width,
+            image_height,
+            field_of_view,
+            Projection::Fisheye { border: None },
+            aspect_ratio.max(aspect_ratio.recip()),
+        )
+    }
+
+    /// creates a camera that sees the whole world around it at once, laid out by
+    /// longitude and latitude. the image is usually twice as wide as it is tall.
+    pub fn equirectangular(image_width: usize, image_height: usize) -> Camera {
+        Camera::with_half_view(
+            image_width,
+            image_height,
+            2.0 * consts::PI,
+            Projection::Equirectangular,
+            1.0,
+        )
+    }
+
+    fn with_half_view(
+        image_width: usize,
+        image_height: usize,
+        field_of_view: f64,
+        projection: Projection,
+        half_view: f64,
+    ) -> Camera {
         let aspect_ratio = (image_width as f64) / (image_height as f64);
 
         let (half_width, half_height) = if aspect_ratio >= 1.0 {
@@ -72,170 +543,2073 @@ impl Camera {
             image_width,
             image_height,
             field_of_view,
+            projection,
             half_width,
             half_height,
             pixel_size: (half_width * 2.0) / (image_width as f64),
             view: View::default(),
+            on_non_finite: NonFinite::default(),
+            sampling: Sampling::default(),
+            adaptive_threshold: None,
+            aperture: 0.0,
+            focal_distance: 1.0,
+            lens_samples: 16,
+            shutter: (0.0, 0.0),
+            exposure: 0.0,
         }
     }
 
+    pub fn view(&self) -> &View {
+        &self.view
+    }
+
+    pub fn set_view(&mut self, view: View) -> &mut Camera {
+        self.view = view;
+        self
+    }
+
     pub fn ray_for_pixel(&self, x: usize, y: usize) -> Ray {
-        // the offset from the edge of the canvas to the pixel's center
-        let x_offset = ((x as f64) + 0.5) * self.pixel_size;
-        let y_offset = ((y as f64) + 0.5) * self.pixel_size;
-
-        // the un-transformed coordinates of the pixel in world space.
-        // (the camera looks towards -z, so +x is to the left)
-        let world_space_x = self.half_width - x_offset;
-        let world_space_y = self.half_height - y_offset;
-
-        // using the camera matrix, transform the canvas point and the origin,
-        // and then compute the ray's direction vector.
-        // (the canvas is at z = -1)
-        let pixel = self.view.inverse * Point::new(world_space_x, world_space_y, -1.0);
-        let origin = self.view.inverse * Point::new(0.0, 0.0, 0.0);
-        let direction = (pixel - origin).normalized();
+        self.ray_for_pixel_offset(x, y, 0.5, 0.5)
+    }
 
-        Ray::new(origin, direction)
+    /// finds the ray through pixel (x, y), offset from its top left corner by a
+    /// fraction `dx` of its width and `dy` of its height.
+    pub fn ray_for_pixel_offset(&self, x: usize, y: usize, dx: f64, dy: f64) -> Ray {
+        self.ray_for_position((x as f64) + dx, (y as f64) + dy)
     }
 
-    pub fn render(&self, world: &World) -> Canvas {
-        let mut image = Canvas::new(self.image_width, self.image_height);
+    /// finds the ray through any position on the canvas, measured in pixels from its
+    /// top left corner. the center of pixel (x, y) is at (x + 0.5, y + 0.5).
+    pub fn ray_for_position(&self, x: f64, y: f64) -> Ray {
+        self.view.debug_assert_consistent();
 
-        for y in 0..self.image_height {
-            for x in 0..self.image_width {
-                let ray = self.ray_for_pixel(x, y);
-                image[(x, y)] = world.cast_ray(ray);
+        let (world_space_x, world_space_y) = self.canvas_point(x, y);
+
+        match self.projection {
+            Projection::Perspective => {
+                // using the camera matrix, transform the canvas point and the origin,
+                // and then compute the ray's direction vector.
+                // (the canvas is at z = -1)
+                let pixel = self.view.inverse * Point::new(world_space_x, world_space_y, -1.0);
+                let origin = self.view.inverse * Point::new(0.0, 0.0, 0.0);
+                let direction = (pixel - origin).normalized();
+
+                Ray::new(origin, direction)
+            }
+            Projection::Orthographic => {
+                // every ray starts at its own pixel and looks straight down -z.
+                // (the canvas is at z = 0)
+                let origin = self.view.inverse * Point::new(world_space_x, world_space_y, 0.0);
+                let direction = (self.view.inverse * Vector::new(0.0, 0.0, -1.0)).normalized();
+
+                Ray::new(origin, direction)
+            }
+            Projection::Fisheye { .. } | Projection::Equirectangular => {
+                let origin = self.view.inverse * Point::new(0.0, 0.0, 0.0);
+                let direction = (self.view.inverse * self.direction_for(x, y)).normalized();
+
+                Ray::new(origin, direction)
             }
         }
+    }
 
-        image
+    /// checks whether a position on the canvas, measured as in `ray_for_position`, is
+    /// part of the image. this is only false outside a fisheye camera's circle.
+    pub fn covers(&self, x: f64, y: f64) -> bool {
+        match self.projection {
+            Projection::Fisheye { .. } => {
+                let (world_space_x, world_space_y) = self.canvas_point(x, y);
+                world_space_x.hypot(world_space_y) <= 1.0
+            }
+            _ => true,
+        }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::{math::EPSILON, world::color::Color};
-    use std::f64::consts;
+    /// the un-transformed direction of the ray through a position on the canvas, for
+    /// the projections that aren't tied to a flat canvas in front of the camera.
+    fn direction_for(&self, x: f64, y: f64) -> Vector {
+        match self.projection {
+            Projection::Equirectangular => {
+                let longitude = consts::PI * (1.0 - 2.0 * x / self.image_width as f64);
+                let latitude = consts::PI * (0.5 - y / self.image_height as f64);
 
-    #[test]
-    fn default_transformation() {
-        let from = Point::zero();
-        let to = Point::new(0.0, 0.0, -1.0);
-        let up = Vector::new(0.0, 1.0, 0.0);
-        let view = View::transformed(from, to, up);
-        assert_eq!(view.transform, Matrix::identity());
-        assert_eq!(view.inverse, Matrix::identity());
+                Vector::new(
+                    latitude.cos() * longitude.sin(),
+                    latitude.sin(),
+                    -latitude.cos() * longitude.cos(),
+                )
+            }
+            _ => {
+                // an equidistant fisheye: the angle from -z grows with the distance from
+                // the center of the canvas, reaching half the field of view at 1.
+                let (world_space_x, world_space_y) = self.canvas_point(x, y);
+                let radius = world_space_x.hypot(world_space_y);
+                if radius == 0.0 {
+                    return Vector::new(0.0, 0.0, -1.0);
+                }
+
+                let angle = radius * self.field_of_view / 2.0;
+                let sideways = angle.sin() / radius;
+                Vector::new(
+                    world_space_x * sideways,
+                    world_space_y * sideways,
+                    -angle.cos(),
+                )
+            }
+        }
     }
 
-    #[test]
-    fn looking_in_positive_z_direction() {
-        let from = Point::zero();
-        let to = Point::new(0.0, 0.0, 1.0);
-        let up = Vector::new(0.0, 1.0, 0.0);
-        let view = View::transformed(from, to, up);
-        let transform = Matrix::scaling(-1.0, 1.0, -1.0);
-        assert_eq!(view.transform, transform);
-        assert_eq!(view.inverse, transform.inverse());
+    /// like `ray_for_position`, but the ray leaves from the point (lens_x, lens_y) on
+    /// the unit disk, scaled by the aperture. it is aimed so that it crosses the ray
+    /// through the center of the lens at the focal distance.
+    pub fn ray_through_lens(&self, x: f64, y: f64, lens_x: f64, lens_y: f64) -> Ray {
+        self.view.debug_assert_consistent();
+
+        let (world_space_x, world_space_y) = self.canvas_point(x, y);
+        let (lens_x, lens_y) = (lens_x * self.aperture, lens_y * self.aperture);
+
+        // in camera space, find where the pinhole ray meets the plane of focus.
+        let (center, focus) = match self.projection {
+            Projection::Perspective => (
+                Point::new(lens_x, lens_y, 0.0),
+                Point::new(
+                    world_space_x * self.focal_distance,
+                    world_space_y * self.focal_distance,
+                    -self.focal_distance,
+                ),
+            ),
+            Projection::Orthographic => (
+                Point::new(world_space_x + lens_x, world_space_y + lens_y, 0.0),
+                Point::new(world_space_x, world_space_y, -self.focal_distance),
+            ),
+            // these can look sideways or backwards, so they focus on a sphere instead.
+            Projection::Fisheye { .. } | Projection::Equirectangular => (
+                Point::new(lens_x, lens_y, 0.0),
+                Point::zero() + self.direction_for(x, y) * self.focal_distance,
+            ),
+        };
+
+        let origin = self.view.inverse * center;
+        let direction = (self.view.inverse * focus - origin).normalized();
+        Ray::new(origin, direction)
     }
 
-    #[test]
-    fn transform_moves_world() {
-        let from = Point::new(0.0, 0.0, 8.0);
-        let to = Point::zero();
-        let up = Vector::new(0.0, 1.0, 0.0);
-        let view = View::transformed(from, to, up);
-        let transform = Matrix::translation(0.0, 0.0, -8.0);
-        assert_eq!(view.transform, transform);
-        assert_eq!(view.inverse, transform.inverse());
+    /// the un-transformed coordinates in world space of a position on the canvas.
+    /// (the camera looks towards -z, so +x is to the left)
+    fn canvas_point(&self, x: f64, y: f64) -> (f64, f64) {
+        // the offset from the edge of the canvas to the position
+        let x_offset = x * self.pixel_size;
+        let y_offset = y * self.pixel_size;
+
+        (self.half_width - x_offset, self.half_height - y_offset)
     }
 
-    #[test]
-    fn arbitrary_view_transformation() {
-        let from = Point::new(1.0, 3.0, 2.0);
-        let to = Point::new(4.0, -2.0, 8.0);
-        let up = Vector::new(1.0, 1.0, 0.0);
-        let view = View::transformed(from, to, up);
-        #[rustfmt::skip]
-        let transform = Matrix::new(
-            -0.50709, 0.50709, 0.67612, -2.36643,
-            0.76772,  0.60609, 0.12122, -2.82843,
-            -0.35857, 0.59761, -0.71714, 0.0,
-        );
-        assert_eq!(view.transform, transform);
-        assert_eq!(view.inverse, transform.inverse());
+    pub fn render(&self, world: &World) -> Canvas {
+        let options = RenderOptions {
+            count_rays: false,
+            ..RenderOptions::default()
+        };
+        self.render_with_options(world, options, &mut NoopLogger).0
     }
 
-    #[test]
-    fn construct_camera() {
-        let width = 160;
-        let height = 120;
-        let field_of_view = consts::PI / 2.0;
-        let c = Camera::new(width, height, field_of_view);
-        assert_eq!(c.image_width, width);
-        assert_eq!(c.image_height, height);
-        assert_eq!(c.field_of_view, consts::PI / 2.0);
-        assert_eq!(c.view.transform, Matrix::identity());
-        assert_eq!(c.view.inverse, Matrix::identity());
+    /// renders the world, also returning statistics gathered along the way.
+    pub fn render_with_stats(&self, world: &World) -> (Canvas, RenderStats) {
+        self.render_with_options(world, RenderOptions::default(), &mut NoopLogger)
     }
 
-    #[test]
-    fn pixel_size_horizontal_canvas() {
-        let c = Camera::new(200, 125, consts::PI / 2.0);
-        assert!((c.pixel_size - 0.01).abs() < EPSILON);
+    /// renders the world tile by tile, reporting progress to `logger`.
+    pub fn render_with_options<L: RenderLogger>(
+        &self,
+        world: &World,
+        options: RenderOptions,
+        logger: &mut L,
+    ) -> (Canvas, RenderStats) {
+        let whole = (0, 0, self.image_width, self.image_height);
+        self.render_area(world, whole, options, logger)
     }
 
-    #[test]
-    fn pixel_size_vertical_canvas() {
-        let c = Camera::new(125, 200, consts::PI / 2.0);
-        assert!((c.pixel_size - 0.01).abs() < EPSILON);
+    /// renders only the rectangle of pixels whose top left corner is (x0, y0), into a
+    /// canvas of its own. each pixel comes out just as it would in the whole image,
+    /// except that adaptive sampling can't see past the edges of the rectangle. the
+    /// rectangle is clipped to the image, so it may come out smaller than asked for.
+    pub fn render_region(
+        &self,
+        world: &World,
+        x0: usize,
+        y0: usize,
+        width: usize,
+        height: usize,
+    ) -> Canvas {
+        let left = x0.min(self.image_width);
+        let top = y0.min(self.image_height);
+        let width = width.min(self.image_width - left);
+        let height = height.min(self.image_height - top);
+
+        self.render_area(
+            world,
+            (left, top, width, height),
+            RenderOptions::default(),
+            &mut NoopLogger,
+        )
+        .0
     }
 
-    #[test]
-    fn ray_through_canvas_center() {
-        let c = Camera::new(201, 101, consts::PI / 2.0);
-        let r = c.ray_for_pixel(100, 50);
-        assert_eq!(r.origin, Point::zero());
-        assert_eq!(r.direction, Vector::new(0.0, 0.0, -1.0));
+    /// renders a stereo pair side by side into a canvas the size of the image, with the
+    /// left eye's view on the left, as VR headsets and parallel viewing expect. swap the
+    /// halves for cross-eyed viewing. the eyes stand `eye_separation` apart, either side
+    /// of the camera, and both look at the camera's target. each half sees as far up
+    /// and down as the whole image would, but only half as far across.
+    pub fn render_stereo(&self, world: &World, eye_separation: f64) -> Canvas {
+        let left_width = self.image_width / 2;
+        let right_width = self.image_width - left_width;
+
+        // camera space has x pointing to the left of the image.
+        let right = (self.view.inverse * Vector::new(-1.0, 0.0, 0.0)).normalized();
+        let step = right * (eye_separation / 2.0);
+        let (from, to, up) = (self.view.from(), self.view.to(), self.view.up());
+
+        let mut eye = self.with_image_width(left_width);
+        eye.view = View::transformed(from - step, to, up);
+        let left = eye.render(world);
+        let mut eye = self.with_image_width(right_width);
+        eye.view = View::transformed(from + step, to, up);
+        let right = eye.render(world);
+
+        let mut image = Canvas::new(self.image_width, self.image_height);
+        image.blit(&left, 0, 0);
+        image.blit(&right, left_width, 0);
+        image
     }
 
-    #[test]
-    fn ray_through_canvas_corner() {
-        let c = Camera::new(201, 101, consts::PI / 2.0);
-        let r = c.ray_for_pixel(0, 0);
-        assert_eq!(r.origin, Point::zero());
-        assert_eq!(r.direction, Vector::new(0.66519, 0.33259, -0.66851));
+    /// the same camera with a narrower or wider image, keeping the size of its pixels,
+    /// and so how far it sees up and down.
+    fn with_image_width(&self, image_width: usize) -> Camera {
+        Camera {
+            image_width,
+            half_width: self.pixel_size * image_width as f64 / 2.0,
+            ..*self
+        }
     }
 
-    #[test]
-    fn ray_through_canvas_center_transformed() {
-        let mut c = Camera::new(201, 101, consts::PI / 2.0);
-        c.view.transform = *Matrix::identity()
-            .translate(0.0, -2.0, 5.0)
-            .rotate_y(consts::PI / 4.0);
-        c.view.inverse = c.view.transform.inverse();
-        let r = c.ray_for_pixel(100, 50);
-        assert_eq!(r.origin, Point::new(0.0, 2.0, -5.0));
-        assert_eq!(
-            r.direction,
-            Vector::new(
-                f64::from(2.0).sqrt() / 2.0,
-                0.0,
-                -f64::from(2.0).sqrt() / 2.0
-            )
+    /// renders the rectangle of pixels given by its left, top, width and height, which
+    /// must lie within the image.
+    fn render_area<L: RenderLogger>(
+        &self,
+        world: &World,
+        (left, top, width, height): (usize, usize, usize, usize),
+        options: RenderOptions,
+        logger: &mut L,
+    ) -> (Canvas, RenderStats) {
+        let start = Instant::now();
+        let mut image = Canvas::new(width, height);
+        let mut stats = RenderStats::default();
+
+        let tile_size = options.tile_size.max(1);
+        let tiles_across = width.div_ceil(tile_size);
+        let tiles_down = height.div_ceil(tile_size);
+        let tiles = tiles_across * tiles_down;
+
+        logger.log(LogRecord::now(RenderEvent::RenderStarted {
+            width,
+            height,
+            tiles,
+        }));
+
+        // each tile is split into the rows that make it up, which are rendered in
+        // parallel and then gathered back up here, so that only this thread ever touches
+        // the image or the logger.
+        let mut segments = vec![];
+        let mut rows_left = vec![0; tiles];
+        for (index, rows) in rows_left.iter_mut().enumerate() {
+            let tile_left = (index % tiles_across) * tile_size;
+            let tile_top = (index / tiles_across) * tile_size;
+            for j in tile_top..(tile_top + tile_size).min(height) {
+                segments.push((index, j, tile_left..(tile_left + tile_size).min(width)));
+                *rows += 1;
+            }
+        }
+
+        let mut replaced = vec![false; width * height];
+        let progress_interval = options.progress_interval.filter(|&interval| interval > 0);
+        let (mut finished_pixels, mut finished_tiles) = (0, 0);
+
+        run_jobs(
+            options.threads,
+            segments.len(),
+            |job| {
+                let _counting = RayCounts::counting(options.count_rays);
+                let (_, j, columns) = &segments[job];
+                let mut segment_stats = RenderStats::default();
+                let pixels: Vec<((Color, f64), bool)> = columns
+                    .clone()
+                    .map(|i| {
+                        let before = segment_stats.non_finite_pixels;
+                        let pixel = self.rough_pixel(world, left + i, top + j, &mut segment_stats);
+                        (pixel, segment_stats.non_finite_pixels > before)
+                    })
+                    .collect();
+                (job, pixels, segment_stats)
+            },
+            |(job, pixels, segment_stats)| {
+                let (index, j, columns) = &segments[job];
+                for (i, ((color, alpha), was_replaced)) in columns.clone().zip(pixels) {
+                    image[(i, *j)] = color;
+                    image.set_alpha(i, *j, alpha);
+                    replaced[j * width + i] = was_replaced;
+                }
+                stats += segment_stats;
+
+                let before = finished_pixels;
+                finished_pixels += columns.len();
+                if let Some(interval) = progress_interval {
+                    if finished_pixels / interval > before / interval {
+                        logger.log(LogRecord::now(RenderEvent::Progress {
+                            pixels: finished_pixels,
+                            total: width * height,
+                            elapsed: start.elapsed(),
+                            stats,
+                        }));
+                    }
+                }
+
+                rows_left[*index] -= 1;
+                if rows_left[*index] == 0 {
+                    logger.log(LogRecord::now(RenderEvent::TileFinished {
+                        index: finished_tiles,
+                        tiles,
+                    }));
+                    finished_tiles += 1;
+                }
+            },
         );
-    }
 
-    #[test]
-    fn render_world_with_camera() {
+        if let Some(threshold) = self.adaptive_threshold {
+            let preview = image.clone();
+            run_jobs(
+                options.threads,
+                height,
+                |j| {
+                    let _counting = RayCounts::counting(options.count_rays);
+                    let mut row_stats = RenderStats::default();
+                    let refined: Vec<(usize, (Color, f64))> = (0..width)
+                        // replaced pixels are already known to be broken.
+                        .filter(|&i| {
+                            !replaced[j * width + i] && stands_out(&preview, i, j, threshold)
+                        })
+                        .map(|i| {
+                            let pixel =
+                                self.refined_pixel(world, left + i, top + j, &mut row_stats);
+                            (i, pixel)
+                        })
+                        .collect();
+                    (j, refined, row_stats)
+                },
+                |(j, refined, row_stats)| {
+                    for (i, (color, alpha)) in refined {
+                        image[(i, j)] = color;
+                        image.set_alpha(i, j, alpha);
+                    }
+                    stats += row_stats;
+                },
+            );
+        }
+
+        stats.elapsed = start.elapsed();
+        logger.log(LogRecord::now(RenderEvent::RenderFinished {
+            elapsed: stats.elapsed,
+            stats,
+        }));
+
+        (image, stats)
+    }
+
+    /// the color and alpha of pixel (x, y) after the first pass. with adaptive sampling,
+    /// this only takes a rough look through the center of the pixel.
+    pub(crate) fn rough_pixel(
+        &self,
+        world: &World,
+        x: usize,
+        y: usize,
+        stats: &mut RenderStats,
+    ) -> (Color, f64) {
+        match self.adaptive_threshold {
+            Some(_) => self.sample_pixel(world, x, y, &[(0.5, 0.5)], stats),
+            None => self.sample_pixel(world, x, y, &self.sampling.offsets(x, y), stats),
+        }
+    }
+
+    /// the color and alpha of pixel (x, y) when adaptive sampling finds that it stands
+    /// out.
+    pub(crate) fn refined_pixel(
+        &self,
+        world: &World,
+        x: usize,
+        y: usize,
+        stats: &mut RenderStats,
+    ) -> (Color, f64) {
+        stats.refined_pixels += 1;
+        self.sample_pixel(world, x, y, &self.sampling.offsets(x, y), stats)
+    }
+
+    /// averages the colors seen through pixel (x, y) at each of the given offsets. the
+    /// alpha is the fraction of those rays that hit something, so it is 0 where only the
+    /// background shows and 1 where objects cover the whole pixel.
+    fn sample_pixel(
+        &self,
+        world: &World,
+        x: usize,
+        y: usize,
+        offsets: &[(f64, f64)],
+        stats: &mut RenderStats,
+    ) -> (Color, f64) {
+        let rays = self.pixel_rays(x, y, offsets);
+
+        let mut total = Color::black();
+        let mut hits = 0;
+        for &(ray, covered) in rays.iter() {
+            let (color, hit) = match (covered, self.projection) {
+                (
+                    false,
+                    Projection::Fisheye {
+                        border: Some(border),
+                    },
+                ) => (border, false),
+                (false, _) => (world.background.color_for(ray.direction), false),
+                (true, _) => {
+                    let before = RayCounts::current();
+                    let seen = world.cast_primary_ray(ray);
+                    RayCounts::add_since(before, stats);
+                    stats.primary_rays += 1;
+                    if seen.1 {
+                        stats.hits += 1;
+                    }
+                    seen
+                }
+            };
+            if hit {
+                hits += 1;
+            }
+
+            if !color.is_finite() {
+                match self.on_non_finite {
+                    NonFinite::Panic => panic!(
+                        "non-finite color {:?} at pixel ({}, {}) from {:?}",
+                        color, x, y, ray
+                    ),
+                    NonFinite::Replace(sentinel) => {
+                        stats.non_finite_pixels += 1;
+                        return (sentinel, 1.0);
+                    }
+                }
+            }
+
+            total += color;
+        }
+
+        // the samples are averaged before exposure, while the light is still linear.
+        let count = rays.len() as f64;
+        (total * (self.exposure.exp2() / count), hits as f64 / count)
+    }
+
+    /// the rays fired through pixel (x, y) at each of the given offsets, along with
+    /// whether the image covers that spot. the points on the lens and the moments the
+    /// shutter catches are picked by the sampler's seed, each from a stream of its own.
+    fn pixel_rays(&self, x: usize, y: usize, offsets: &[(f64, f64)]) -> Vec<(Ray, bool)> {
+        let seed = self.sampling.seed();
+        let mut lens = pixel_rng(seed ^ LENS_STREAM, x, y);
+        let mut shutter = pixel_rng(seed ^ SHUTTER_STREAM, x, y);
+        let mut rays = Vec::with_capacity(offsets.len());
+        for &(dx, dy) in offsets.iter() {
+            let (position_x, position_y) = (x as f64 + dx, y as f64 + dy);
+            let covered = self.covers(position_x, position_y);
+            if self.aperture > 0.0 {
+                for _ in 0..self.lens_samples.max(1) {
+                    let (lens_x, lens_y) = sample_disk(lens.next_f64(), lens.next_f64());
+                    let ray = self.ray_through_lens(position_x, position_y, lens_x, lens_y);
+                    rays.push((ray, covered));
+                }
+            } else {
+                rays.push((self.ray_for_pixel_offset(x, y, dx, dy), covered));
+            }
+        }
+
+        // with the shutter open for a while, each ray catches moving objects at a
+        // different moment.
+        let (open, close) = self.shutter;
+        if close > open {
+            for (ray, _) in rays.iter_mut() {
+                *ray = ray.with_time(open + (close - open) * shutter.next_f64());
+            }
+        } else {
+            for (ray, _) in rays.iter_mut() {
+                *ray = ray.with_time(open);
+            }
+        }
+        rays
+    }
+
+    /// `color`, the value of pixel (x, y) found along `ray` in one of the passes, if it
+    /// is finite, and otherwise whatever `on_non_finite` asks for. the passes keep no
+    /// statistics, so replaced pixels aren't counted anywhere.
+    fn checked_pass(&self, color: Color, x: usize, y: usize, ray: Ray) -> Color {
+        if color.is_finite() {
+            return color;
+        }
+        match self.on_non_finite {
+            NonFinite::Panic => panic!(
+                "non-finite color {:?} at pixel ({}, {}) from {:?}",
+                color, x, y, ray
+            ),
+            NonFinite::Replace(sentinel) => sentinel,
+        }
+    }
+
+    /// renders the unlit surface color of the closest object along each pixel's ray.
+    /// lights are ignored entirely, and pixels whose rays miss everything are black.
+    /// non-finite colors are handled as `on_non_finite` says.
+    pub fn render_albedo(&self, world: &World) -> Canvas {
+        let mut image = Canvas::new(self.image_width, self.image_height);
+
+        for y in 0..self.image_height {
+            for x in 0..self.image_width {
+                let ray = self.ray_for_pixel(x, y);
+                if let Some(intersections) = world.hit(ray) {
+                    if let Some(intersection) = intersections.closest() {
+                        let albedo = intersection.object.color_at(ray.at(intersection.time));
+                        image[(x, y)] = self.checked_pass(albedo, x, y, ray);
+                    }
+                }
+            }
+        }
+
+        image
+    }
+
+    /// renders the height of the closest object along each pixel's ray as a shade of
+    /// grey, where the bottom of `bounds` is black and the top of `bounds` is white.
+    /// pixels whose rays miss everything are also black. non-finite heights, as from
+    /// flat bounds, are handled as `on_non_finite` says.
+    pub fn render_height(&self, world: &World, bounds: BoundingBox) -> Canvas {
+        let mut image = Canvas::new(self.image_width, self.image_height);
+
+        for y in 0..self.image_height {
+            for x in 0..self.image_width {
+                let ray = self.ray_for_pixel(x, y);
+                if let Some(intersections) = world.hit(ray) {
+                    if let Some(intersection) = intersections.closest() {
+                        let height = change_interval(
+                            ray.at(intersection.time)[1],
+                            (bounds.min[1], bounds.max[1]),
+                            (0.0, 1.0),
+                        );
+                        let height = Color::new(height, height, height);
+                        image[(x, y)] = self.checked_pass(height, x, y, ray);
+                    }
+                }
+            }
+        }
+
+        image
+    }
+
+    /// renders the image along with its normal and albedo passes. the passes average
+    /// over the same rays through each pixel as the image does, at the offsets its
+    /// sampling gives, so that anti-aliased edges line up. the rays through a lens or
+    /// during a shutter are not followed, though.
+    pub fn render_aovs(&self, world: &World) -> RenderOutput {
+        let mut normal = Canvas::new(self.image_width, self.image_height);
+        let mut albedo = Canvas::new(self.image_width, self.image_height);
+        for y in 0..self.image_height {
+            for x in 0..self.image_width {
+                let offsets = self.sampling.offsets(x, y);
+                for &(dx, dy) in offsets.iter() {
+                    let ray = self.ray_for_pixel_offset(x, y, dx, dy);
+                    let closest = world
+                        .hit(ray)
+                        .and_then(|intersections| intersections.closest());
+                    let (surface_normal, surface_color) = match closest {
+                        Some(intersection) => {
+                            let comps = Computations::new(&intersection);
+                            (
+                                comps.surface_normal,
+                                intersection.object.color_at(comps.point),
+                            )
+                        }
+                        None => (
+                            Vector::new(0.0, 0.0, 1.0),
+                            world.background.color_for(ray.direction),
+                        ),
+                    };
+                    normal[(x, y)] += Color::new(
+                        surface_normal[0] + 1.0,
+                        surface_normal[1] + 1.0,
+                        surface_normal[2] + 1.0,
+                    ) * 0.5;
+                    albedo[(x, y)] += surface_color;
+                }
+                normal[(x, y)] /= offsets.len() as f64;
+                albedo[(x, y)] /= offsets.len() as f64;
+            }
+        }
+
+        RenderOutput {
+            beauty: self.render(world),
+            normal,
+            albedo,
+        }
+    }
+
+    /// renders how far along each pixel's ray the closest object is, as a shade of grey
+    /// that runs linearly from black at `near` to white at `far`. depths outside that
+    /// range are clamped to it, and pixels whose rays miss everything are white, as if
+    /// they were at `far`. `near` must be less than `far`.
+    pub fn render_depth(
+        &self,
+        world: &World,
+        near: f64,
+        far: f64,
+        options: RenderOptions,
+    ) -> Result<Canvas, RayTracerError> {
+        // NaNs can't be compared, so they are turned away too.
+        if near.partial_cmp(&far) != Some(cmp::Ordering::Less) {
+            return Err(RayTracerError::InvalidDepthRange { near, far });
+        }
+        let mut image = self.render_raw_depth(world, options);
+        for y in 0..self.image_height {
+            for x in 0..self.image_width {
+                let depth = change_interval(image[(x, y)].red(), (near, far), (0.0, 1.0));
+                image[(x, y)] = Color::white() * depth.clamp(0.0, 1.0);
+            }
+        }
+        Ok(image)
+    }
+
+    /// like `render_depth`, but every channel of each pixel holds the distance to the
+    /// closest object as it is, for saving as a PFM image. pixels whose rays miss
+    /// everything, or that lie outside a fisheye's circle, are infinitely far away.
+    pub fn render_raw_depth(&self, world: &World, options: RenderOptions) -> Canvas {
+        let mut image = Canvas::new(self.image_width, self.image_height);
+        run_jobs(
+            options.threads,
+            self.image_height,
+            |y| {
+                let row: Vec<f64> = (0..self.image_width)
+                    .map(|x| {
+                        if !self.covers(x as f64 + 0.5, y as f64 + 0.5) {
+                            return f64::INFINITY;
+                        }
+                        world
+                            .hit(self.ray_for_pixel(x, y))
+                            .and_then(|intersections| intersections.closest().map(|i| i.time))
+                            .unwrap_or(f64::INFINITY)
+                    })
+                    .collect();
+                (y, row)
+            },
+            |(y, row)| {
+                for (x, depth) in row.into_iter().enumerate() {
+                    image[(x, y)] = Color::new(depth, depth, depth);
+                }
+            },
+        );
+        image
+    }
+}
+
+/// runs `work` on each of the jobs numbered from 0 up to `jobs`, spread over as many as
+/// `threads` threads, and hands each result to `finish` on the calling thread as soon as
+/// it is ready. results can arrive in any order.
+fn run_jobs<T, W, F>(threads: usize, jobs: usize, work: W, mut finish: F)
+where
+    T: Send,
+    W: Fn(usize) -> T + Sync,
+    F: FnMut(T),
+{
+    let threads = threads.clamp(1, jobs.max(1));
+    if threads == 1 {
+        for job in 0..jobs {
+            finish(work(job));
+        }
+        return;
+    }
+
+    let next = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+    thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|_| {
+                let (sender, next, work) = (sender.clone(), &next, &work);
+                scope.spawn(move || loop {
+                    let job = next.fetch_add(1, Ordering::Relaxed);
+                    if job >= jobs || sender.send(work(job)).is_err() {
+                        break;
+                    }
+                })
+            })
+            .collect();
+        drop(sender);
+
+        for result in receiver {
+            finish(result);
+        }
+
+        // pass on a worker's panic as it was, rather than as a generic one.
+        for worker in workers {
+            if let Err(panic) = worker.join() {
+                panic::resume_unwind(panic);
+            }
+        }
+    });
+}
+
+/// checks whether the color of pixel (x, y) differs from any of the eight around it by
+/// more than `threshold`.
+pub(crate) fn stands_out(image: &Canvas, x: usize, y: usize, threshold: f64) -> bool {
+    let color = image[(x, y)];
+    let (width, height) = (image.width, image.height);
+
+    (y.saturating_sub(1)..(y + 2).min(height)).any(|ny| {
+        (x.saturating_sub(1)..(x + 2).min(width))
+            .any(|nx| color.distance(&image[(nx, ny)]) > threshold)
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::useless_conversion)]
+mod tests {
+    use super::*;
+    use crate::{
+        math::{Form, Geometry, Transformable},
+        world::{light, Background, Light, Material, Pattern, Texture},
+    };
+    use std::{f64::consts, time::Duration};
+
+    #[test]
+    fn default_transformation() {
+        let from = Point::zero();
+        let to = Point::new(0.0, 0.0, -1.0);
+        let up = Vector::new(0.0, 1.0, 0.0);
+        let view = View::transformed(from, to, up);
+        assert_eq!(view.transform, Matrix::identity());
+        assert_eq!(view.inverse, Matrix::identity());
+    }
+
+    #[test]
+    fn looking_in_positive_z_direction() {
+        let from = Point::zero();
+        let to = Point::new(0.0, 0.0, 1.0);
+        let up = Vector::new(0.0, 1.0, 0.0);
+        let view = View::transformed(from, to, up);
+        let transform = Matrix::scaling(-1.0, 1.0, -1.0);
+        assert_eq!(view.transform, transform);
+        assert_eq!(view.inverse, transform.inverse());
+    }
+
+    #[test]
+    fn transform_moves_world() {
+        let from = Point::new(0.0, 0.0, 8.0);
+        let to = Point::zero();
+        let up = Vector::new(0.0, 1.0, 0.0);
+        let view = View::transformed(from, to, up);
+        let transform = Matrix::translation(0.0, 0.0, -8.0);
+        assert_eq!(view.transform, transform);
+        assert_eq!(view.inverse, transform.inverse());
+    }
+
+    #[test]
+    fn looking_at_where_the_camera_stands() {
+        let from = Point::new(1.0, 2.0, 3.0);
+        let up = Vector::new(0.0, 1.0, 0.0);
+        assert!(matches!(
+            View::try_transformed(from, from, up),
+            Err(RayTracerError::DegenerateView { from: f }) if f == from
+        ));
+        assert_eq!(
+            View::try_transformed(from, Point::zero(), up).unwrap(),
+            View::transformed(from, Point::zero(), up)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "looking at the point it stands on")]
+    fn transformed_panics_on_a_degenerate_view() {
+        View::transformed(Point::zero(), Point::zero(), Vector::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    #[should_panic(expected = "has no inverse")]
+    fn views_need_an_invertible_transform() {
+        View::from_matrix(Matrix::scaling(1.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn arbitrary_view_transformation() {
+        let from = Point::new(1.0, 3.0, 2.0);
+        let to = Point::new(4.0, -2.0, 8.0);
+        let up = Vector::new(1.0, 1.0, 0.0);
+        let view = View::transformed(from, to, up);
+        #[rustfmt::skip]
+        let transform = Matrix::new(
+            -0.50709, 0.50709, 0.67612, -2.36643,
+            0.76772,  0.60609, 0.12122, -2.82843,
+            -0.35857, 0.59761, -0.71714, 0.0,
+        );
+        assert_eq!(view.transform, transform);
+        assert_eq!(view.inverse, transform.inverse());
+    }
+
+    #[test]
+    fn view_without_usable_up_vector() {
+        let from = Point::new(1.0, 2.0, 3.0);
+        let to = Point::new(4.0, 2.0, 7.0);
+        for &up in [Vector::zero(), to - from, from - to].iter() {
+            let view = View::transformed(from, to, up);
+            assert!(view.transform.is_finite());
+            assert!(view.inverse.is_finite());
+            assert_eq!(
+                view.transform,
+                View::transformed(from, to, Vector::new(0.0, 1.0, 0.0)).transform
+            );
+        }
+
+        let straight_down = View::transformed(from, Point::new(1.0, -5.0, 3.0), Vector::zero());
+        assert!(straight_down.transform.is_finite());
+        assert_eq!(
+            straight_down.inverse * Vector::new(0.0, 0.0, -1.0),
+            Vector::new(0.0, -1.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn view_keeps_its_parameters() {
+        let from = Point::new(1.0, 3.0, 2.0);
+        let to = Point::new(4.0, -2.0, 8.0);
+        let up = Vector::new(1.0, 1.0, 0.0);
+        let view = View::transformed(from, to, up);
+        assert_eq!((view.from(), view.to(), view.up()), (from, to, up));
+
+        let copy = View::from_matrix(view.transform);
+        assert_eq!(copy.from(), from);
+        assert_eq!(copy.to(), from + (to - from).normalized());
+    }
+
+    #[test]
+    fn orbiting_all_the_way_round_returns_to_the_start() {
+        let start = View::transformed(
+            Point::new(1.0, 2.0, -5.0),
+            Point::new(0.0, 1.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+        );
+        let mut view = start;
+        for _ in 0..36 {
+            view.orbit_y(consts::PI / 18.0);
+        }
+        assert_eq!(view.transform, start.transform);
+        assert_eq!(view.inverse, start.inverse);
+
+        view.orbit_y(consts::PI);
+        assert_eq!(view.from(), Point::new(-1.0, 2.0, 5.0));
+        assert_eq!(view.to(), start.to());
+    }
+
+    #[test]
+    fn dollying_moves_only_along_the_view() {
+        let start = View::transformed(
+            Point::new(1.0, 2.0, -5.0),
+            Point::new(0.0, 1.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+        );
+        let mut view = start;
+        view.dolly(-2.0);
+        // moving the camera back pushes everything further along its negative z axis.
+        assert_eq!(
+            view.transform,
+            Matrix::translation(0.0, 0.0, -2.0) * start.transform
+        );
+        assert_eq!(
+            view.from() - start.from(),
+            (start.from() - start.to()).normalized() * 2.0
+        );
+    }
+
+    #[test]
+    fn panning_and_looking_around() {
+        let mut view = View::transformed(
+            Point::new(0.0, 0.0, -5.0),
+            Point::zero(),
+            Vector::new(0.0, 1.0, 0.0),
+        );
+        view.pan(1.0, 2.0);
+        // looking along positive z, the right of the image is positive x.
+        assert_eq!(view.from(), Point::new(1.0, 2.0, -5.0));
+        assert_eq!(view.to(), Point::new(1.0, 2.0, 0.0));
+
+        view.look_at(Point::new(1.0, 2.0, -10.0));
+        assert_eq!(view.from(), Point::new(1.0, 2.0, -5.0));
+        assert_eq!(
+            view.inverse * Vector::new(0.0, 0.0, -1.0),
+            Vector::new(0.0, 0.0, -1.0)
+        );
+    }
+
+    #[test]
+    fn construct_camera() {
+        let width = 160;
+        let height = 120;
+        let field_of_view = consts::PI / 2.0;
+        let c = Camera::new(width, height, field_of_view);
+        assert_eq!(c.image_width, width);
+        assert_eq!(c.image_height, height);
+        assert_eq!(c.field_of_view, consts::PI / 2.0);
+        assert_eq!(c.projection, Projection::Perspective);
+        assert_eq!(c.view.transform, Matrix::identity());
+        assert_eq!(c.view.inverse, Matrix::identity());
+    }
+
+    #[test]
+    fn vertical_fov_is_kept_as_the_image_widens() {
+        let narrow = Camera::perspective(200, 100, Fov::Vertical(consts::PI / 2.0));
+        let wide = Camera::perspective(400, 100, Fov::Vertical(consts::PI / 2.0));
+        assert!((narrow.half_height - 1.0).abs() < EPSILON);
+        assert!((wide.half_height - 1.0).abs() < EPSILON);
+        assert!((narrow.half_width - 2.0).abs() < EPSILON);
+        assert!((wide.half_width - 4.0).abs() < EPSILON);
+
+        for &y in [0.0, 25.0, 50.0, 75.0, 100.0].iter() {
+            assert_eq!(
+                narrow.ray_for_position(100.0, y).direction,
+                wide.ray_for_position(200.0, y).direction
+            );
+        }
+        assert_eq!(
+            narrow.ray_for_position(0.0, 50.0).direction,
+            Vector::new(2.0, 0.0, -1.0).normalized()
+        );
+        assert_eq!(
+            wide.ray_for_position(0.0, 50.0).direction,
+            Vector::new(4.0, 0.0, -1.0).normalized()
+        );
+    }
+
+    #[test]
+    fn horizontal_fov_matches_the_old_constructor() {
+        for &(width, height) in [(200, 100), (100, 200), (125, 125)].iter() {
+            let c = Camera::perspective(width, height, Fov::Horizontal(consts::PI / 2.0));
+            assert!((c.half_width - 1.0).abs() < EPSILON);
+            if width >= height {
+                assert_eq!(c, Camera::new(width, height, consts::PI / 2.0));
+            }
+        }
+        let tall = Camera::perspective(100, 200, Fov::Vertical(consts::PI / 2.0));
+        assert_eq!(tall, Camera::new(100, 200, consts::PI / 2.0));
+    }
+
+    #[test]
+    fn construct_orthographic_camera() {
+        let c = Camera::orthographic(200, 100, 4.0);
+        assert_eq!(c.projection, Projection::Orthographic);
+        assert!((c.half_width - 2.0).abs() < EPSILON);
+        assert!((c.half_height - 1.0).abs() < EPSILON);
+        assert!((c.pixel_size - 0.02).abs() < EPSILON);
+    }
+
+    #[test]
+    fn orthographic_rays_are_parallel() {
+        let c = Camera::orthographic(201, 101, 4.0);
+        let center = c.ray_for_pixel(100, 50);
+        let corner = c.ray_for_pixel(0, 0);
+        assert_eq!(center.origin, Point::zero());
+        assert_eq!(center.direction, Vector::new(0.0, 0.0, -1.0));
+        assert_eq!(corner.origin, Point::new(1.99005, 0.99502, 0.0));
+        assert_eq!(corner.direction, Vector::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn fisheye_directions() {
+        let c = Camera::fisheye(100, 50, consts::PI);
+        assert_eq!(c.projection, Projection::Fisheye { border: None });
+        let cases = [
+            ((50.0, 25.0), Vector::new(0.0, 0.0, -1.0)),
+            ((37.5, 25.0), Vector::new(1.0, 0.0, -1.0).normalized()),
+            ((25.0, 25.0), Vector::new(1.0, 0.0, 0.0)),
+            ((50.0, 0.0), Vector::new(0.0, 1.0, 0.0)),
+            ((75.0, 25.0), Vector::new(-1.0, 0.0, 0.0)),
+        ];
+        for &((x, y), direction) in cases.iter() {
+            let r = c.ray_for_position(x, y);
+            assert_eq!(r.origin, Point::zero());
+            assert_eq!(r.direction, direction);
+            assert!(c.covers(x, y));
+        }
+        assert!(!c.covers(10.0, 25.0));
+        assert!(!c.covers(50.0 + 25.0 * 0.8, 25.0 - 25.0 * 0.8));
+    }
+
+    #[test]
+    fn wide_fisheye_sees_behind() {
+        let c = Camera::fisheye(100, 100, 2.0 * consts::PI);
+        let r = c.ray_for_position(50.0, 100.0);
+        assert_eq!(r.direction, Vector::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn equirectangular_directions() {
+        let c = Camera::equirectangular(200, 100);
+        let cases = [
+            ((100.0, 50.0), Vector::new(0.0, 0.0, -1.0)),
+            ((0.0, 50.0), Vector::new(0.0, 0.0, 1.0)),
+            ((50.0, 50.0), Vector::new(1.0, 0.0, 0.0)),
+            ((150.0, 50.0), Vector::new(-1.0, 0.0, 0.0)),
+            ((100.0, 0.0), Vector::new(0.0, 1.0, 0.0)),
+            ((100.0, 100.0), Vector::new(0.0, -1.0, 0.0)),
+        ];
+        for &((x, y), direction) in cases.iter() {
+            let r = c.ray_for_position(x, y);
+            assert_eq!(r.origin, Point::zero());
+            assert_eq!(r.direction, direction);
+            assert!(c.covers(x, y));
+        }
+    }
+
+    #[test]
+    fn pixel_size_horizontal_canvas() {
+        let c = Camera::new(200, 125, consts::PI / 2.0);
+        assert!((c.pixel_size - 0.01).abs() < EPSILON);
+    }
+
+    #[test]
+    fn pixel_size_vertical_canvas() {
+        let c = Camera::new(125, 200, consts::PI / 2.0);
+        assert!((c.pixel_size - 0.01).abs() < EPSILON);
+    }
+
+    #[test]
+    fn ray_through_canvas_center() {
+        let c = Camera::new(201, 101, consts::PI / 2.0);
+        let r = c.ray_for_pixel(100, 50);
+        assert_eq!(r.origin, Point::zero());
+        assert_eq!(r.direction, Vector::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn ray_through_canvas_corner() {
+        let c = Camera::new(201, 101, consts::PI / 2.0);
+        let r = c.ray_for_pixel(0, 0);
+        assert_eq!(r.origin, Point::zero());
+        assert_eq!(r.direction, Vector::new(0.66519, 0.33259, -0.66851));
+    }
+
+    #[test]
+    fn setting_a_transform_keeps_the_inverse() {
+        let mut view = View::default();
+        view.set_transform(Matrix::translation(0.0, -2.0, 5.0) * Matrix::rotation_y(1.0));
+        assert_eq!(view.transformation() * view.inverse(), Matrix::identity());
+        view.dolly(3.0).orbit_y(-0.5);
+        assert_eq!(view.transformation() * view.inverse(), Matrix::identity());
+
+        let mut c = Camera::new(201, 101, consts::PI / 2.0);
+        c.set_view(view);
+        assert_eq!(c.view().inverse(), view.inverse());
+    }
+
+    #[test]
+    fn ray_through_canvas_center_transformed() {
+        let mut c = Camera::new(201, 101, consts::PI / 2.0);
+        c.set_view(View::from_matrix(
+            *Matrix::identity()
+                .translate(0.0, -2.0, 5.0)
+                .rotate_y(consts::PI / 4.0),
+        ));
+        let r = c.ray_for_pixel(100, 50);
+        assert_eq!(r.origin, Point::new(0.0, 2.0, -5.0));
+        assert_eq!(
+            r.direction,
+            Vector::new(
+                f64::from(2.0).sqrt() / 2.0,
+                0.0,
+                -f64::from(2.0).sqrt() / 2.0
+            )
+        );
+    }
+
+    #[test]
+    fn render_world_with_camera() {
+        let w = World::default();
+        let mut c = Camera::new(11, 11, consts::PI / 2.0);
+        let from = Point::new(0.0, 0.0, -5.0);
+        let to = Point::zero();
+        let up = Vector::new(0.0, 1.0, 0.0);
+        c.set_view(View::transformed(from, to, up));
+        let image = c.render(&w);
+        assert_eq!(image[(5, 5)], Color::new(0.38066, 0.47583, 0.2855));
+    }
+
+    #[test]
+    fn exposure_scales_pixels_by_stops() {
+        let w = World::default();
+        let mut c = Camera::new(11, 11, consts::PI / 2.0);
+        c.set_view(View::transformed(
+            Point::new(0.0, 0.0, -5.0),
+            Point::zero(),
+            Vector::new(0.0, 1.0, 0.0),
+        ));
+        assert_eq!(c.exposure, 0.0);
+        let image = c.render(&w);
+        assert_eq!(image[(5, 5)], Color::new(0.38066, 0.47583, 0.2855));
+
+        let (mut w, mut c) = glowing_sphere(11);
+        let material = w.objects[0].make_material_unique();
+        material.emissive = Color::new(0.25, 0.25, 0.25);
+        material.ambient = 0.0;
+        let normal = c.render(&w)[(5, 5)];
+        assert_eq!(normal, Color::new(0.25, 0.25, 0.25));
+        c.exposure = 1.0;
+        assert_eq!(c.render(&w)[(5, 5)], normal * 2.0);
+        c.exposure = -2.0;
+        assert_eq!(c.render(&w)[(5, 5)], normal * 0.25);
+    }
+
+    #[derive(Default)]
+    struct CollectingLogger {
+        events: Vec<RenderEvent>,
+    }
+
+    impl RenderLogger for CollectingLogger {
+        fn log(&mut self, record: LogRecord) {
+            self.events.push(record.event);
+        }
+    }
+
+    #[test]
+    fn render_logs_progress_per_tile() {
+        let w = World::default();
+        let mut c = Camera::new(11, 11, consts::PI / 2.0);
+        c.set_view(View::transformed(
+            Point::new(0.0, 0.0, -5.0),
+            Point::zero(),
+            Vector::new(0.0, 1.0, 0.0),
+        ));
+        let mut logger = CollectingLogger::default();
+        let (image, _) = c.render_with_options(
+            &w,
+            RenderOptions {
+                tile_size: 4,
+                ..RenderOptions::default()
+            },
+            &mut logger,
+        );
+        assert_eq!(image[(5, 5)], Color::new(0.38066, 0.47583, 0.2855));
+
+        assert_eq!(logger.events.len(), 11);
+        assert_eq!(
+            logger.events[0],
+            RenderEvent::RenderStarted {
+                width: 11,
+                height: 11,
+                tiles: 9
+            }
+        );
+        for index in 0..9 {
+            assert_eq!(
+                logger.events[index + 1],
+                RenderEvent::TileFinished { index, tiles: 9 }
+            );
+        }
+        assert!(matches!(
+            logger.events[10],
+            RenderEvent::RenderFinished { .. }
+        ));
+    }
+
+    #[test]
+    fn render_reports_progress_at_intervals() {
         let w = World::default();
         let mut c = Camera::new(11, 11, consts::PI / 2.0);
-        let from = Point::new(0.0, 0.0, -5.0);
-        let to = Point::zero();
-        let up = Vector::new(0.0, 1.0, 0.0);
-        c.view = View::transformed(from, to, up);
+        c.set_view(View::transformed(
+            Point::new(0.0, 0.0, -5.0),
+            Point::zero(),
+            Vector::new(0.0, 1.0, 0.0),
+        ));
+        let mut logger = CollectingLogger::default();
+        let (_, stats) = c.render_with_options(
+            &w,
+            RenderOptions {
+                progress_interval: Some(11),
+                ..RenderOptions::default()
+            },
+            &mut logger,
+        );
+
+        let progress: Vec<(usize, usize)> = logger
+            .events
+            .iter()
+            .filter_map(|event| match event {
+                RenderEvent::Progress { pixels, total, .. } => Some((*pixels, *total)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(progress.len(), 11);
+        assert_eq!(progress[0], (11, 121));
+        assert_eq!(progress[10], (121, 121));
+
+        let last = logger.events.iter().rev().find_map(|event| match event {
+            RenderEvent::Progress { stats, .. } => Some(*stats),
+            _ => None,
+        });
+        assert_eq!(last, Some(without_time(stats)));
+    }
+
+    /// the counts in `stats`, without the time taken, which changes from run to run.
+    fn without_time(stats: RenderStats) -> RenderStats {
+        RenderStats {
+            elapsed: Duration::ZERO,
+            ..stats
+        }
+    }
+
+    #[test]
+    fn render_counts_rays() {
+        let mut w = World::default();
+        let mut c = Camera::new(11, 11, consts::PI / 2.0);
+        c.set_view(View::transformed(
+            Point::new(0.0, 0.0, -5.0),
+            Point::zero(),
+            Vector::new(0.0, 1.0, 0.0),
+        ));
+        let (_, stats) = c.render_with_stats(&w);
+        assert_eq!(stats.primary_rays, 121);
+        // one shadow ray towards the light for each pixel that hits the spheres.
+        let hits = (0..11)
+            .flat_map(|y| (0..11).map(move |x| (x, y)))
+            .filter(|&(x, y)| w.hit(c.ray_for_pixel(x, y)).is_some())
+            .count();
+        assert_eq!(stats.hits, hits);
+        assert_eq!(stats.shadow_rays, hits * w.lights.len());
+        assert_eq!(stats.secondary_rays, 0);
+        // every ray is tested against every object.
+        assert_eq!(
+            stats.intersection_tests,
+            (stats.primary_rays + stats.shadow_rays) * w.objects.len()
+        );
+        assert!(stats.elapsed > Duration::ZERO);
+
+        w.objects[0].make_material_unique().reflective = 0.5;
+        c.sampling = Sampling::uniform(2);
+        let (_, stats) = c.render_with_stats(&w);
+        assert_eq!(stats.primary_rays, 4 * 121);
+        assert!(stats.secondary_rays > 0);
+        assert_eq!(
+            stats.rays(),
+            stats.primary_rays + stats.shadow_rays + stats.secondary_rays
+        );
+
+        // without counting, only what the camera fires itself is known.
+        let options = RenderOptions {
+            count_rays: false,
+            ..RenderOptions::default()
+        };
+        let (_, stats) = c.render_with_options(&w, options, &mut NoopLogger);
+        assert_eq!(stats.primary_rays, 4 * 121);
+        assert_eq!(stats.shadow_rays + stats.secondary_rays, 0);
+        assert_eq!(stats.intersection_tests, 0);
+    }
+
+    #[test]
+    fn threaded_render_matches_serial_render() {
+        let mut w = World::default();
+        w.objects[0].make_material_unique().reflective = 0.5;
+        let mut c = Camera::new(11, 11, consts::PI / 2.0);
+        c.set_view(View::transformed(
+            Point::new(0.0, 0.0, -5.0),
+            Point::zero(),
+            Vector::new(0.0, 1.0, 0.0),
+        ));
+        c.sampling = Sampling::jittered(2, 3);
+        c.adaptive_threshold = Some(0.05);
+
+        let render = |threads| {
+            let options = RenderOptions {
+                tile_size: 4,
+                threads,
+                ..RenderOptions::default()
+            };
+            c.render_with_options(&w, options, &mut NoopLogger)
+        };
+        let (serial, serial_stats) = render(1);
+        let (threaded, threaded_stats) = render(4);
+        assert_eq!(serial, threaded);
+        assert_eq!(without_time(serial_stats), without_time(threaded_stats));
+    }
+
+    #[test]
+    fn threaded_render_logs_every_tile() {
+        let w = World::default();
+        let c = Camera::new(11, 11, consts::PI / 2.0);
+        let mut logger = CollectingLogger::default();
+        let options = RenderOptions {
+            tile_size: 2,
+            threads: 3,
+            ..RenderOptions::default()
+        };
+        c.render_with_options(&w, options, &mut logger);
+        let finished: Vec<usize> = logger
+            .events
+            .iter()
+            .filter_map(|event| match event {
+                RenderEvent::TileFinished { index, tiles: 36 } => Some(*index),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(finished, (0..36).collect::<Vec<usize>>());
+    }
+
+    #[test]
+    fn worlds_can_be_shared_between_threads() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<World>();
+        assert_sync::<Geometry>();
+        assert_sync::<Light>();
+        assert_sync::<Camera>();
+    }
+
+    #[test]
+    fn tiled_render_matches_untiled_render() {
+        let w = World::default();
+        let mut c = Camera::new(11, 7, consts::PI / 2.0);
+        c.set_view(View::transformed(
+            Point::new(0.0, 0.0, -5.0),
+            Point::zero(),
+            Vector::new(0.0, 1.0, 0.0),
+        ));
+        let (tiled, _) = c.render_with_options(
+            &w,
+            RenderOptions {
+                tile_size: 3,
+                ..RenderOptions::default()
+            },
+            &mut NoopLogger,
+        );
+        let (whole, _) = c.render_with_options(
+            &w,
+            RenderOptions {
+                tile_size: 100,
+                ..RenderOptions::default()
+            },
+            &mut NoopLogger,
+        );
+        assert_eq!(tiled.to_ppm(), whole.to_ppm());
+    }
+
+    #[test]
+    fn ray_for_pixel_offset_at_center_matches_ray_for_pixel() {
+        let c = Camera::new(201, 101, consts::PI / 2.0);
+        let centered = c.ray_for_pixel_offset(100, 50, 0.5, 0.5);
+        let r = c.ray_for_pixel(100, 50);
+        assert_eq!(centered.origin, r.origin);
+        assert_eq!(centered.direction, r.direction);
+
+        let corner = c.ray_for_pixel_offset(0, 0, 0.0, 0.0);
+        assert_eq!(corner.direction, c.ray_for_position(0.0, 0.0).direction);
+    }
+
+    #[test]
+    fn single_sample_matches_pixel_center() {
+        let w = World::default();
+        let mut c = Camera::new(11, 11, consts::PI / 2.0);
+        c.set_view(View::transformed(
+            Point::new(0.0, 0.0, -5.0),
+            Point::zero(),
+            Vector::new(0.0, 1.0, 0.0),
+        ));
+        c.sampling = Sampling::uniform(1);
         let image = c.render(&w);
-        assert_eq!(image[(5, 5)], Color::new(0.38066, 0.47583, 0.2855));
+        for y in 0..11 {
+            for x in 0..11 {
+                assert_eq!(image[(x, y)], w.cast_ray(c.ray_for_pixel(x, y)));
+            }
+        }
+    }
+
+    fn glowing_sphere(size: usize) -> (World, Camera) {
+        let glowing = Material::default().with_emissive(Color::white());
+        let sphere = Geometry::default()
+            .with_form(Form::Sphere)
+            .with_material(glowing);
+        let w = World::new(vec![sphere], vec![]);
+        let mut c = Camera::new(size, size, consts::PI / 2.0);
+        c.set_view(View::transformed(
+            Point::new(0.0, 0.0, -5.0),
+            Point::zero(),
+            Vector::new(0.0, 1.0, 0.0),
+        ));
+        (w, c)
+    }
+
+    #[test]
+    fn supersampling_softens_silhouettes() {
+        let (w, mut c) = glowing_sphere(11);
+        let aliased = c.render(&w);
+        let (foreground, background) = (aliased[(5, 5)], aliased[(0, 0)]);
+        // the sphere's edge crosses pixel (6, 5), but its center is inside the sphere.
+        assert_eq!(aliased[(6, 5)], foreground);
+
+        c.sampling = Sampling::uniform(4);
+        let smooth = c.render(&w);
+        assert_eq!(smooth[(5, 5)], foreground);
+        assert_eq!(smooth[(0, 0)], background);
+        let edge = smooth[(6, 5)];
+        assert!(edge.red() > background.red() && edge.red() < foreground.red());
+    }
+
+    #[test]
+    fn alpha_covers_what_the_rays_hit() {
+        let w = World::default();
+        let mut c = Camera::new(11, 11, consts::PI / 2.0);
+        c.set_view(View::transformed(
+            Point::new(0.0, 0.0, -5.0),
+            Point::zero(),
+            Vector::new(0.0, 1.0, 0.0),
+        ));
+        let image = c.render(&w);
+        assert!(image.has_alpha());
+        assert_eq!(image.alpha(0, 0), 0.0);
+        assert_eq!(image.alpha(5, 5), 1.0);
+    }
+
+    #[test]
+    fn supersampled_silhouettes_are_partly_transparent() {
+        let (w, mut c) = glowing_sphere(11);
+        assert_eq!(c.render(&w).alpha(6, 5), 1.0);
+
+        c.sampling = Sampling::uniform(2);
+        let alpha = c.render(&w).alpha(6, 5);
+        assert!(alpha > 0.0 && alpha < 1.0);
+    }
+
+    #[test]
+    fn depth_is_the_distance_to_the_closest_hit() {
+        let w = World::default();
+        let mut c = Camera::new(11, 11, consts::PI / 2.0);
+        c.set_view(View::transformed(
+            Point::new(0.0, 0.0, -5.0),
+            Point::zero(),
+            Vector::new(0.0, 1.0, 0.0),
+        ));
+
+        let raw = c.render_raw_depth(&w, RenderOptions::default());
+        assert!((raw[(5, 5)].red() - 4.0).abs() < EPSILON);
+        assert_eq!(raw[(0, 0)].red(), f64::INFINITY);
+
+        let depth = c
+            .render_depth(&w, 0.0, 10.0, RenderOptions::default())
+            .unwrap();
+        assert_eq!(depth[(5, 5)], Color::new(0.4, 0.4, 0.4));
+        assert_eq!(depth[(0, 0)], Color::white());
+
+        let one_thread = RenderOptions {
+            threads: 1,
+            ..RenderOptions::default()
+        };
+        assert_eq!(c.render_depth(&w, 0.0, 10.0, one_thread).unwrap(), depth);
+    }
+
+    #[test]
+    fn fisheye_depth_stops_at_the_border() {
+        // standing inside a sphere, every ray hits something.
+        let w = World::new(
+            vec![Geometry::default()
+                .with_form(Form::Sphere)
+                .transformed(Matrix::scaling(10.0, 10.0, 10.0))],
+            vec![],
+        );
+        let c = Camera::fisheye(11, 11, consts::PI / 2.0);
+        let raw = c.render_raw_depth(&w, RenderOptions::default());
+        assert!((raw[(5, 5)].red() - 10.0).abs() < EPSILON);
+        assert_eq!(raw[(0, 0)].red(), f64::INFINITY);
+        assert_eq!(raw[(10, 10)].red(), f64::INFINITY);
+    }
+
+    #[test]
+    fn depth_ranges_must_run_forwards() {
+        let w = World::default();
+        let c = Camera::new(4, 4, consts::PI / 2.0);
+        for (near, far) in [(5.0, 5.0), (10.0, 0.0), (f64::NAN, 1.0)] {
+            assert!(matches!(
+                c.render_depth(&w, near, far, RenderOptions::default()),
+                Err(RayTracerError::InvalidDepthRange { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn aovs_of_the_default_world() {
+        let w = World::default();
+        let mut c = Camera::new(11, 11, consts::PI / 2.0);
+        c.set_view(View::transformed(
+            Point::new(0.0, 0.0, -5.0),
+            Point::zero(),
+            Vector::new(0.0, 1.0, 0.0),
+        ));
+        let output = c.render_aovs(&w);
+
+        assert_eq!(output.beauty, c.render(&w));
+        assert_eq!(output.normal[(5, 5)], Color::new(0.5, 0.5, 0.0));
+        assert_eq!(output.normal[(0, 0)], Color::new(0.5, 0.5, 1.0));
+        let outer = &w.objects[0];
+        assert_eq!(
+            output.albedo[(5, 5)],
+            outer.color_at(Point::new(0.0, 0.0, -1.0))
+        );
+        assert_eq!(output.albedo[(0, 0)], Color::black());
+    }
+
+    #[test]
+    fn aovs_line_up_with_supersampled_edges() {
+        let (w, mut c) = glowing_sphere(11);
+        c.sampling = Sampling::uniform(2);
+        let output = c.render_aovs(&w);
+        let edge = output.normal[(6, 5)];
+        assert!(edge.blue() > 0.0 && edge.blue() < 1.0);
+        assert_eq!(output.beauty.alpha(6, 5) > 0.0, edge.blue() < 1.0);
+    }
+
+    #[test]
+    fn seeded_sampling_is_reproducible() {
+        let w = World::default();
+        let mut c = Camera::new(11, 11, consts::PI / 2.0);
+        c.set_view(View::transformed(
+            Point::new(0.0, 0.0, -5.0),
+            Point::zero(),
+            Vector::new(0.0, 1.0, 0.0),
+        ));
+        for &sampling in [Sampling::jittered(2, 1), Sampling::stratified(2, 1)].iter() {
+            c.sampling = sampling;
+            let first = c.render(&w);
+            assert_eq!(first, c.render(&w));
+
+            c.sampling = match sampling {
+                Sampling::Jittered(_) => Sampling::jittered(2, 2),
+                _ => Sampling::stratified(2, 2),
+            };
+            assert_ne!(first, c.render(&w));
+        }
+    }
+
+    #[test]
+    fn adaptive_sampling_refines_only_edges() {
+        let (w, mut c) = glowing_sphere(21);
+        let rough = c.render(&w);
+
+        c.sampling = Sampling::uniform(4);
+        c.adaptive_threshold = Some(0.1);
+        let (image, stats) = c.render_with_stats(&w);
+
+        let mut edges = 0;
+        for y in 0..21 {
+            for x in 0..21 {
+                if stands_out(&rough, x, y, 0.1) {
+                    edges += 1;
+                } else {
+                    assert_eq!(image[(x, y)], rough[(x, y)]);
+                }
+            }
+        }
+        assert_eq!(stats.refined_pixels, edges);
+        // a thin band around the sphere, rather than the whole image.
+        assert!(edges > 0);
+        assert!(edges < 21 * 21 / 4);
+        assert!(!stands_out(&rough, 10, 10, 0.1));
+        assert!(!stands_out(&rough, 0, 0, 0.1));
+
+        // refined pixels along the silhouette blend the sphere into the background.
+        let (foreground, background) = (rough[(10, 10)], rough[(0, 0)]);
+        assert!((0..21).any(|x| {
+            let red = image[(x, 10)].red();
+            red > background.red() && red < foreground.red()
+        }));
+    }
+
+    #[test]
+    fn adaptive_sampling_is_off_by_default() {
+        let (w, mut c) = glowing_sphere(11);
+        c.sampling = Sampling::uniform(2);
+        let (_, stats) = c.render_with_stats(&w);
+        assert_eq!(stats.refined_pixels, 0);
+    }
+
+    #[test]
+    fn lens_center_ray_matches_pinhole() {
+        let mut c = Camera::new(201, 101, consts::PI / 2.0);
+        c.set_view(View::transformed(
+            Point::new(1.0, 2.0, -5.0),
+            Point::zero(),
+            Vector::new(0.0, 1.0, 0.0),
+        ));
+        c.aperture = 0.5;
+        c.focal_distance = 4.0;
+        let pinhole = c.ray_for_position(30.0, 70.0);
+        let lens = c.ray_through_lens(30.0, 70.0, 0.0, 0.0);
+        assert_eq!(lens.origin, pinhole.origin);
+        assert_eq!(lens.direction, pinhole.direction);
+    }
+
+    #[test]
+    fn lens_rays_meet_at_the_focal_plane() {
+        let mut c = Camera::new(201, 101, consts::PI / 2.0);
+        c.aperture = 0.5;
+        c.focal_distance = 4.0;
+        let pinhole = c.ray_for_position(30.0, 70.0);
+        // the pinhole ray reaches the plane z = -4 here.
+        let focus = pinhole.at(4.0 / -pinhole.direction[2]);
+        for &(lens_x, lens_y) in [(1.0, 0.0), (0.0, -1.0), (0.3, 0.4)].iter() {
+            let ray = c.ray_through_lens(30.0, 70.0, lens_x, lens_y);
+            assert_eq!(ray.origin, Point::new(lens_x * 0.5, lens_y * 0.5, 0.0));
+            assert_eq!(ray.at(4.0 / -ray.direction[2]), focus);
+        }
+    }
+
+    #[test]
+    fn zero_aperture_is_a_pinhole() {
+        let w = World::default();
+        let mut c = Camera::new(11, 11, consts::PI / 2.0);
+        c.set_view(View::transformed(
+            Point::new(0.0, 0.0, -5.0),
+            Point::zero(),
+            Vector::new(0.0, 1.0, 0.0),
+        ));
+        let pinhole = c.render(&w);
+        c.focal_distance = 3.0;
+        c.lens_samples = 4;
+        assert_eq!(c.render(&w), pinhole);
+    }
+
+    #[test]
+    fn fisheye_border() {
+        let w = World::default();
+        let mut c = Camera::fisheye(11, 11, consts::PI / 2.0);
+        c.set_view(View::transformed(
+            Point::new(0.0, 0.0, -5.0),
+            Point::zero(),
+            Vector::new(0.0, 1.0, 0.0),
+        ));
+        let image = c.render(&w);
+        assert_eq!(image[(0, 0)], Color::black());
+        assert_eq!(image[(5, 5)], w.cast_ray(c.ray_for_pixel(5, 5)));
+
+        let border = Color::new(1.0, 0.0, 0.0);
+        c.projection = Projection::Fisheye {
+            border: Some(border),
+        };
+        let image = c.render(&w);
+        assert_eq!(image[(0, 0)], border);
+        assert_eq!(image[(10, 10)], border);
+        assert_eq!(image[(5, 0)], w.cast_ray(c.ray_for_pixel(5, 0)));
+    }
+
+    #[test]
+    fn equirectangular_panorama_places_objects_by_longitude() {
+        let w = World::default();
+        let mut c = Camera::equirectangular(40, 20);
+        // looking along +x, so the spheres at the origin are off to the left.
+        c.set_view(View::transformed(
+            Point::new(0.0, 0.0, -5.0),
+            Point::new(5.0, 0.0, -5.0),
+            Vector::new(0.0, 1.0, 0.0),
+        ));
+        let image = c.render(&w);
+        assert_ne!(image[(10, 10)], Color::black());
+        for &x in [0, 20, 30, 39].iter() {
+            assert_eq!(image[(x, 10)], Color::black());
+        }
+        // the lit side of the spheres faces the light, up and to the left of the view.
+        let brightest = (0..40)
+            .max_by(|&a, &b| image[(a, 10)].red().total_cmp(&image[(b, 10)].red()))
+            .unwrap();
+        assert!((5..=10).contains(&brightest));
+    }
+
+    /// a far red sphere straight ahead, and a near green one below it, seen by an 80 by
+    /// 40 camera looking into the distance.
+    fn stereo_scene() -> (World, Camera) {
+        let glowing = |color: Color| Material::default().with_emissive(color);
+        let far = Geometry::default()
+            .with_form(Form::Sphere)
+            .with_material(glowing(Color::new(1.0, 0.0, 0.0)))
+            .transformed(Matrix::translation(0.0, 10.0, 100.0) * Matrix::scaling(8.0, 8.0, 8.0));
+        let near = Geometry::default()
+            .with_form(Form::Sphere)
+            .with_material(glowing(Color::new(0.0, 1.0, 0.0)))
+            .transformed(Matrix::translation(0.0, -0.5, -3.0) * Matrix::scaling(0.3, 0.3, 0.3));
+        let w = World::new(vec![far, near], vec![]);
+        let mut c = Camera::new(80, 40, consts::PI / 2.0);
+        c.set_view(View::transformed(
+            Point::new(0.0, 0.0, -5.0),
+            Point::new(0.0, 0.0, 100.0),
+            Vector::new(0.0, 1.0, 0.0),
+        ));
+        (w, c)
+    }
+
+    /// the average column, within one half of a stereo pair, of the pixels whose
+    /// `channel` is bright.
+    fn stereo_column(image: &Canvas, half: usize, channel: fn(&Color) -> f64) -> f64 {
+        let mut columns = vec![];
+        for y in 0..image.height {
+            for x in 0..image.width / 2 {
+                if channel(&image[(half * image.width / 2 + x, y)]) > 0.5 {
+                    columns.push(x as f64);
+                }
+            }
+        }
+        assert!(!columns.is_empty());
+        columns.iter().sum::<f64>() / columns.len() as f64
+    }
+
+    #[test]
+    fn stereo_halves_differ_by_depth() {
+        let (w, c) = stereo_scene();
+        let image = c.render_stereo(&w, 0.5);
+        assert_eq!((image.width, image.height), (80, 40));
+
+        let far =
+            (stereo_column(&image, 0, Color::red) - stereo_column(&image, 1, Color::red)).abs();
+        let near =
+            (stereo_column(&image, 0, Color::green) - stereo_column(&image, 1, Color::green)).abs();
+        assert!(far < 0.5, "far sphere moved {} pixels", far);
+        assert!(near > 5.0, "near sphere moved {} pixels", near);
+    }
+
+    #[test]
+    fn stereo_halves_without_separation_match() {
+        let (w, c) = stereo_scene();
+        let image = c.render_stereo(&w, 0.0);
+        for y in 0..40 {
+            for x in 0..40 {
+                assert_eq!(image[(x, y)], image[(x + 40, y)]);
+            }
+        }
+        assert_ne!(c.render_stereo(&w, 0.5), image);
+    }
+
+    #[test]
+    fn quadrants_blit_into_the_whole_render() {
+        let w = World::default();
+        let mut c = Camera::new(11, 11, consts::PI / 2.0);
+        c.set_view(View::transformed(
+            Point::new(0.0, 0.0, -5.0),
+            Point::zero(),
+            Vector::new(0.0, 1.0, 0.0),
+        ));
+
+        let mut image = Canvas::new(11, 11);
+        for &(x, y, width, height) in
+            [(0, 0, 6, 6), (6, 0, 5, 6), (0, 6, 6, 5), (6, 6, 5, 5)].iter()
+        {
+            image.blit(&c.render_region(&w, x, y, width, height), x, y);
+        }
+        assert_eq!(image, c.render(&w));
+    }
+
+    #[test]
+    fn region_matches_whole_render() {
+        let w = World::default();
+        let mut c = Camera::new(11, 11, consts::PI / 2.0);
+        c.set_view(View::transformed(
+            Point::new(0.0, 0.0, -5.0),
+            Point::zero(),
+            Vector::new(0.0, 1.0, 0.0),
+        ));
+        let whole = c.render(&w);
+        let region = c.render_region(&w, 4, 4, 3, 3);
+        assert_eq!((region.width, region.height), (3, 3));
+        for y in 0..3 {
+            for x in 0..3 {
+                assert_eq!(region[(x, y)], whole[(4 + x, 4 + y)]);
+            }
+        }
+    }
+
+    #[test]
+    fn region_is_clipped_to_the_image() {
+        let w = World::default();
+        let mut c = Camera::new(11, 11, consts::PI / 2.0);
+        c.set_view(View::transformed(
+            Point::new(0.0, 0.0, -5.0),
+            Point::zero(),
+            Vector::new(0.0, 1.0, 0.0),
+        ));
+        let whole = c.render(&w);
+
+        let corner = c.render_region(&w, 9, 8, 5, 5);
+        assert_eq!((corner.width, corner.height), (2, 3));
+        assert_eq!(corner[(1, 2)], whole[(10, 10)]);
+
+        let outside = c.render_region(&w, 20, 3, 4, 4);
+        assert_eq!((outside.width, outside.height), (0, 4));
+        let everything = c.render_region(&w, 0, 0, usize::MAX, usize::MAX);
+        assert_eq!(everything, whole);
+    }
+
+    #[test]
+    fn motion_blur_smears_moving_objects() {
+        let glowing = Material::default().with_emissive(Color::white());
+        let sphere = Geometry::default()
+            .with_form(Form::Sphere)
+            .with_material(glowing)
+            .transformed(Matrix::scaling(0.5, 0.5, 0.5))
+            .with_motion(Matrix::translation(-1.0, 0.0, 0.0) * Matrix::scaling(0.5, 0.5, 0.5));
+        let w = World::new(vec![sphere], vec![]);
+        let mut c = Camera::new(21, 11, consts::PI / 2.0);
+        c.set_view(View::transformed(
+            Point::new(0.0, 0.0, -5.0),
+            Point::zero(),
+            Vector::new(0.0, 1.0, 0.0),
+        ));
+        c.sampling = Sampling::stratified(4, 1);
+
+        // the camera looks down +z, so the sphere moves towards the left of the image.
+        let still = c.render(&w);
+        let swept = [7, 8];
+        for &x in swept.iter() {
+            assert_eq!(still[(x, 5)], Color::black());
+        }
+
+        c.shutter = (0.0, 1.0);
+        let blurred = c.render(&w);
+        for &x in swept.iter() {
+            let red = blurred[(x, 5)].red();
+            assert!(red > 0.0 && red < still[(10, 5)].red());
+        }
+    }
+
+    #[test]
+    fn lens_and_shutter_samples_follow_the_seed() {
+        let mut c = Camera::new(11, 11, consts::PI / 2.0);
+        c.aperture = 0.5;
+        c.lens_samples = 4;
+        c.shutter = (0.0, 1.0);
+        let samples = |c: &Camera| -> Vec<(Point, f64)> {
+            c.pixel_rays(3, 4, &c.sampling.offsets(3, 4))
+                .iter()
+                .map(|(ray, _)| (ray.origin, ray.time))
+                .collect()
+        };
+
+        c.sampling = Sampling::stratified(2, 1);
+        let first = samples(&c);
+        assert_eq!(samples(&c), first);
+
+        c.sampling = Sampling::stratified(2, 2);
+        let second = samples(&c);
+        for ((a_origin, a_time), (b_origin, b_time)) in first.iter().zip(second.iter()) {
+            assert_ne!(a_origin, b_origin);
+            assert_ne!(a_time, b_time);
+        }
+    }
+
+    #[test]
+    fn closed_shutter_catches_one_moment() {
+        let sphere = Geometry::default()
+            .with_form(Form::Sphere)
+            .with_motion(Matrix::translation(0.0, 5.0, 0.0));
+        let w = World::new(
+            vec![sphere],
+            vec![light::Light::point(light::Point::new(
+                Point::new(-10.0, 10.0, -10.0),
+                Color::white(),
+            ))],
+        );
+        let mut c = Camera::new(11, 11, consts::PI / 2.0);
+        c.set_view(View::transformed(
+            Point::new(0.0, 0.0, -5.0),
+            Point::zero(),
+            Vector::new(0.0, 1.0, 0.0),
+        ));
+        let at_start = c.render(&w);
+        assert_ne!(at_start[(5, 5)], Color::black());
+
+        // by time 1, the sphere has left the view entirely.
+        c.shutter = (1.0, 1.0);
+        let at_end = c.render(&w);
+        assert_eq!(at_end[(5, 5)], Color::black());
+    }
+
+    /// the largest difference in one channel between neighboring pixels along a row.
+    fn sharpness(image: &Canvas, y: usize, channel: usize) -> f64 {
+        (1..image.width)
+            .map(|x| (image[(x, y)][channel] - image[(x - 1, y)][channel]).abs())
+            .fold(0.0, f64::max)
+    }
+
+    #[test]
+    fn only_the_focal_plane_is_sharp() {
+        let glowing = |color: Color, x: f64, z: f64, radius: f64| {
+            Geometry::default()
+                .with_form(Form::Sphere)
+                .with_material(Material::default().with_emissive(color))
+                .transformed(
+                    Matrix::translation(x, 0.0, z) * Matrix::scaling(radius, radius, radius),
+                )
+        };
+        // a red sphere far away, a green one at the focal distance and a blue one close
+        // up, spread across the image so that they don't overlap.
+        let mut w = World::new(
+            vec![
+                glowing(Color::new(1.0, 0.0, 0.0), 5.0, 5.0, 1.5),
+                glowing(Color::new(0.0, 1.0, 0.0), 0.0, 0.0, 0.5),
+                glowing(Color::new(0.0, 0.0, 1.0), -1.0, -3.0, 0.4),
+            ],
+            vec![],
+        );
+        w.ambient_light = Color::black();
+
+        let mut c = Camera::new(81, 21, consts::PI / 2.0);
+        c.set_view(View::transformed(
+            Point::new(0.0, 0.0, -5.0),
+            Point::zero(),
+            Vector::new(0.0, 1.0, 0.0),
+        ));
+        let pinhole = c.render(&w);
+        for channel in 0..3 {
+            assert!(sharpness(&pinhole, 10, channel) > 0.99);
+        }
+
+        c.aperture = 0.5;
+        c.focal_distance = 5.0;
+        c.lens_samples = 64;
+        let focused = c.render(&w);
+        let (far, middle, near) = (
+            sharpness(&focused, 10, 0),
+            sharpness(&focused, 10, 1),
+            sharpness(&focused, 10, 2),
+        );
+        assert!(middle > far && middle > near);
+    }
+
+    fn world_with_broken_material() -> (World, Camera) {
+        let mut w = World::default();
+        w.objects[0].make_material_unique().diffuse = f64::NAN;
+        let mut c = Camera::new(11, 11, consts::PI / 2.0);
+        c.set_view(View::transformed(
+            Point::new(0.0, 0.0, -5.0),
+            Point::zero(),
+            Vector::new(0.0, 1.0, 0.0),
+        ));
+        (w, c)
+    }
+
+    #[test]
+    fn non_finite_pixels_are_replaced() {
+        let (w, mut c) = world_with_broken_material();
+        let sentinel = Color::new(1.0, 0.0, 1.0);
+        c.on_non_finite = NonFinite::Replace(sentinel);
+        let (image, stats) = c.render_with_stats(&w);
+        // the sphere is shaded with a NaN diffuse term, while the background is not.
+        assert_eq!(image[(5, 5)], sentinel);
+        assert_eq!(image[(0, 0)], Color::black());
+        assert!(stats.non_finite_pixels > 0);
+        assert!(stats.non_finite_pixels < 121);
+        for y in 0..11 {
+            for x in 0..11 {
+                assert!(image[(x, y)].is_finite());
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "at pixel (")]
+    fn non_finite_pixels_can_panic() {
+        let (w, mut c) = world_with_broken_material();
+        c.on_non_finite = NonFinite::Panic;
+        c.render(&w);
+    }
+
+    #[test]
+    fn non_finite_passes_are_replaced() {
+        let (mut w, mut c) = world_with_broken_material();
+        let sentinel = Color::new(1.0, 0.0, 1.0);
+        c.on_non_finite = NonFinite::Replace(sentinel);
+        let flat = BoundingBox::new(Point::zero(), Point::new(1.0, 0.0, 1.0));
+        assert_eq!(c.render_height(&w, flat)[(5, 5)], sentinel);
+
+        w.objects[0].make_material_unique().texture =
+            Texture::Pattern(Pattern::solid(Color::new(f64::NAN, 0.0, 0.0)));
+        let albedo = c.render_albedo(&w);
+        assert_eq!(albedo[(5, 5)], sentinel);
+        assert_eq!(albedo[(0, 0)], Color::black());
+    }
+
+    #[test]
+    #[should_panic(expected = "at pixel (")]
+    fn non_finite_passes_can_panic() {
+        let (w, mut c) = world_with_broken_material();
+        c.on_non_finite = NonFinite::Panic;
+        let flat = BoundingBox::new(Point::zero(), Point::new(1.0, 0.0, 1.0));
+        c.render_height(&w, flat);
+    }
+
+    #[test]
+    #[should_panic(expected = "at pixel (")]
+    fn non_finite_pixels_can_panic_on_any_thread() {
+        let (w, mut c) = world_with_broken_material();
+        c.on_non_finite = NonFinite::Panic;
+        let options = RenderOptions {
+            tile_size: 1,
+            threads: 4,
+            ..RenderOptions::default()
+        };
+        c.render_with_options(&w, options, &mut NoopLogger);
+    }
+
+    #[test]
+    fn sky_shows_above_the_floor() {
+        let sky = Color::new(0.2, 0.4, 1.0);
+        let w = World::new(
+            vec![Geometry::default().with_form(Form::Plane)],
+            vec![Light::point(light::Point::new(
+                Point::new(-10.0, 10.0, -10.0),
+                Color::white(),
+            ))],
+        )
+        .with_background(Background::VerticalGradient {
+            zenith: sky,
+            horizon: Color::white(),
+        });
+        let mut c = Camera::new(11, 11, consts::PI / 2.0);
+        c.set_view(View::transformed(
+            Point::new(0.0, 1.0, -5.0),
+            Point::new(0.0, 1.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+        ));
+        let image = c.render(&w);
+        // the top of the image looks up into the sky, which turns bluer the higher up
+        // it is, while the bottom sees the white floor.
+        assert!(image[(5, 0)].blue() > image[(5, 0)].red());
+        assert!(image[(5, 0)].red() < image[(5, 4)].red());
+        assert_eq!(image[(5, 10)], w.cast_ray(c.ray_for_pixel(5, 10)));
+        assert!(w.hit(c.ray_for_pixel(5, 10)).is_some());
+        assert!(w.hit(c.ray_for_pixel(5, 0)).is_none());
+    }
+
+    #[test]
+    fn finite_render_has_no_replacements() {
+        let (mut w, c) = world_with_broken_material();
+        w.objects[0].make_material_unique().diffuse = 0.7;
+        let (_, stats) = c.render_with_stats(&w);
+        assert_eq!(stats.non_finite_pixels, 0);
+    }
+
+    #[test]
+    fn singular_transform_renders_finite_colors() {
+        let (mut w, mut c) = world_with_broken_material();
+        w.objects[0].make_material_unique().diffuse = 0.7;
+        w.objects.push(
+            Geometry::default()
+                .with_form(Form::Plane)
+                .transformed(Matrix::scaling(0.0, 0.0, 0.0)),
+        );
+        c.on_non_finite = NonFinite::Panic;
+        let (_, stats) = c.render_with_stats(&w);
+        assert_eq!(stats.non_finite_pixels, 0);
     }
 }
@@ -1,16 +1,84 @@
 use std::{
-    fmt::{self, Display, Formatter},
+    fs::File,
+    io::{self, BufWriter, Write},
     ops::{Index, IndexMut},
+    path::Path,
     vec::Vec,
 };
 
-use super::color::{Color, MAX_COLOR};
+use super::color::{Color, ToneMap};
+use crate::RayTracerError;
 
-#[derive(Debug)]
+pub mod blend;
+pub use blend::BlendMode;
+
+pub mod bytes;
+
+pub mod compare;
+pub use compare::{CanvasDiff, SizeMismatch};
+
+pub mod draw;
+
+pub mod pfm;
+
+pub mod png;
+
+pub mod ppm;
+pub use ppm::{BitDepth, Dither, PpmError, PpmOptions};
+
+pub mod resize;
+pub use resize::Filter;
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "SerializedCanvas")
+)]
 pub struct Canvas {
     pub width: usize,
     pub height: usize,
+    #[cfg_attr(feature = "serde", serde(rename = "pixels"))]
     vals: Vec<Color>,
+    /// how opaque each pixel is, from 0 for fully transparent to 1 for fully opaque.
+    /// canvases without an alpha plane are opaque all over.
+    alpha: Option<Vec<f64>>,
+}
+
+/// a canvas as it is serialized, which is only a canvas once the number of pixels is
+/// checked against its size.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct SerializedCanvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+    alpha: Option<Vec<f64>>,
+}
+
+#[cfg(feature = "serde")]
+impl std::convert::TryFrom<SerializedCanvas> for Canvas {
+    type Error = String;
+
+    fn try_from(serialized: SerializedCanvas) -> Result<Canvas, String> {
+        let len = serialized.width.checked_mul(serialized.height);
+        let alpha_len = serialized
+            .alpha
+            .as_ref()
+            .map_or(len, |alpha| Some(alpha.len()));
+        if Some(serialized.pixels.len()) != len || alpha_len != len {
+            return Err(format!(
+                "a {}x{} canvas needs a color and alpha for each pixel",
+                serialized.width, serialized.height
+            ));
+        }
+        Ok(Canvas {
+            width: serialized.width,
+            height: serialized.height,
+            vals: serialized.pixels,
+            alpha: serialized.alpha,
+        })
+    }
 }
 
 impl Canvas {
@@ -29,42 +97,206 @@ impl Canvas {
             vals: (0..(height * width))
                 .map(|i| f(i % width, i / width))
                 .collect(),
+            alpha: None,
         }
     }
 
-    pub fn to_ppm(&self) -> String {
-        format!(
-            "P3\n{} {}\n{}\n{}",
-            self.width, self.height, MAX_COLOR as i64, self
-        )
+    /// the pixel at (x, y), or `None` if that is outside the canvas.
+    pub fn get(&self, x: usize, y: usize) -> Option<&Color> {
+        if x < self.width && y < self.height {
+            self.vals.get(x + y * self.width)
+        } else {
+            None
+        }
+    }
+
+    /// the pixel at (x, y) to change, or `None` if that is outside the canvas.
+    pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut Color> {
+        if x < self.width && y < self.height {
+            self.vals.get_mut(x + y * self.width)
+        } else {
+            None
+        }
+    }
+
+    /// the error for reaching the pixel at (x, y), which is outside the canvas.
+    fn out_of_bounds(&self, x: usize, y: usize) -> RayTracerError {
+        RayTracerError::InvalidCanvasIndex {
+            x,
+            y,
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    /// checks whether the canvas has an alpha plane, rather than being opaque all over.
+    pub fn has_alpha(&self) -> bool {
+        self.alpha.is_some()
+    }
+
+    /// how opaque the pixel at (x, y) is, from 0 to 1.
+    pub fn alpha(&self, x: usize, y: usize) -> f64 {
+        assert!(x < self.width && y < self.height);
+        self.alpha
+            .as_ref()
+            .map_or(1.0, |alpha| alpha[x + y * self.width])
+    }
+
+    /// sets how opaque the pixel at (x, y) is, giving the canvas an alpha plane if it
+    /// has none yet. every other pixel of a new alpha plane is fully opaque.
+    pub fn set_alpha(&mut self, x: usize, y: usize, alpha: f64) {
+        assert!(x < self.width && y < self.height);
+        let width = self.width;
+        let len = self.vals.len();
+        self.alpha.get_or_insert_with(|| vec![1.0; len])[x + y * width] = alpha;
+    }
+
+    /// drops the alpha plane, so that the whole canvas is opaque.
+    pub fn without_alpha(mut self) -> Canvas {
+        self.alpha = None;
+        self
+    }
+
+    /// the alpha plane as a grey canvas of its own, if there is one, so that it can be
+    /// reshaped in the same ways as the colors.
+    fn alpha_canvas(&self) -> Option<Canvas> {
+        self.alpha.as_ref().map(|alpha| {
+            Canvas::from_fn(self.width, self.height, |x, y| {
+                Color::white() * alpha[x + y * self.width]
+            })
+        })
+    }
+
+    /// gives the canvas the alpha plane stored in the red channel of `alpha`.
+    fn with_alpha_canvas(mut self, alpha: Option<Canvas>) -> Canvas {
+        self.alpha = alpha.map(|alpha| alpha.vals.iter().map(Color::red).collect());
+        self
+    }
+
+    /// the color of the pixel at `(u, v)`, where `u` runs from 0 at the left edge to 1
+    /// at the right edge and `v` runs from 0 at the bottom edge to 1 at the top edge.
+    /// `u` wraps around, so that both 0 and 1 are the left edge, while `v` is clamped.
+    pub fn at_uv(&self, u: f64, v: f64) -> Color {
+        let x = (u * self.width as f64)
+            .floor()
+            .rem_euclid(self.width as f64) as usize;
+        let y = ((1.0 - v) * self.height as f64).floor() as usize;
+        self[(x.min(self.width - 1), y.min(self.height - 1))]
+    }
+
+    /// an image of how much each pixel differs between two canvases of the same size,
+    /// as the absolute difference of each channel. identical canvases give all black.
+    pub fn difference(&self, other: &Canvas) -> Canvas {
+        assert!(
+            self.width == other.width && self.height == other.height,
+            "cannot take the difference of a {}x{} canvas and a {}x{} canvas",
+            self.width,
+            self.height,
+            other.width,
+            other.height
+        );
+
+        Canvas::from_fn(self.width, self.height, |x, y| {
+            let difference = self[(x, y)] - other[(x, y)];
+            Color::new(
+                difference.red().abs(),
+                difference.green().abs(),
+                difference.blue().abs(),
+            )
+        })
+    }
+
+    /// copies `source` onto the canvas, with its top left corner at (x, y). whatever
+    /// falls past the right or bottom edge of the canvas is left out. the alpha of the
+    /// source is copied along with its colors.
+    pub fn blit(&mut self, source: &Canvas, x: usize, y: usize) {
+        let width = source.width.min(self.width.saturating_sub(x));
+        let height = source.height.min(self.height.saturating_sub(y));
+        for j in 0..height {
+            let from = j * source.width;
+            let to = x + (y + j) * self.width;
+            self.vals[to..to + width].copy_from_slice(&source.vals[from..from + width]);
+            if source.has_alpha() || self.has_alpha() {
+                for i in 0..width {
+                    self.set_alpha(x + i, y + j, source.alpha(i, j));
+                }
+            }
+        }
+    }
+
+    /// the rectangle of the canvas whose top left corner is (x, y), as a canvas of its
+    /// own. the rectangle is clipped to the canvas, so it may come out smaller than
+    /// asked for, or even empty.
+    pub fn crop(&self, x: usize, y: usize, width: usize, height: usize) -> Canvas {
+        let x = x.min(self.width);
+        let y = y.min(self.height);
+        let width = width.min(self.width - x);
+        let height = height.min(self.height - y);
+        let alpha = self
+            .alpha_canvas()
+            .map(|alpha| alpha.crop(x, y, width, height));
+        Canvas::from_fn(width, height, |i, j| self[(x + i, y + j)]).with_alpha_canvas(alpha)
+    }
+
+    /// a copy of the canvas with every pixel tone mapped, so that colors brighter than
+    /// white survive being written out. encode the result afterwards, if at all.
+    pub fn tone_mapped(&self, tone_map: ToneMap) -> Canvas {
+        Canvas {
+            vals: self
+                .vals
+                .iter()
+                .map(|color| color.tone_mapped(tone_map))
+                .collect(),
+            alpha: self.alpha.clone(),
+            ..*self
+        }
+    }
+
+    /// writes the canvas to the file at `path`, in the format given by its extension:
+    /// `.ppm`, `.pfm` or `.png`. PPM images are streamed to the file as they are
+    /// formatted, rather than built up in memory first. only PNG keeps the alpha.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase);
+
+        match extension.as_deref() {
+            Some("ppm") => {
+                let mut writer = BufWriter::new(File::create(path)?);
+                self.write_ppm(&mut writer)?;
+                writer.flush()
+            }
+            Some("pfm") => File::create(path)?.write_all(&self.to_pfm()),
+            Some("png") => File::create(path)?.write_all(&self.to_png()),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "cannot save {}: only .ppm, .pfm and .png images are supported",
+                    path.display()
+                ),
+            )),
+        }
     }
 }
 
 impl Index<(usize, usize)> for Canvas {
     type Output = Color;
 
+    /// # panics
+    ///
+    /// panics if (x, y) is outside the canvas. see `Canvas::get`.
     fn index(&self, (x, y): (usize, usize)) -> &Self::Output {
-        self.vals.get(x + y * self.width).unwrap()
+        self.get(x, y)
+            .unwrap_or_else(|| panic!("{}", self.out_of_bounds(x, y)))
     }
 }
 
 impl IndexMut<(usize, usize)> for Canvas {
     fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut Color {
-        self.vals.get_mut(x + y * self.width).unwrap()
-    }
-}
-
-impl Display for Canvas {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        let mut result = Ok(());
-
-        for elem in &self.vals {
-            if let Err(e) = writeln!(f, "{}", elem) {
-                result = Err(e);
-            }
-        }
-
-        result
+        let error = self.out_of_bounds(x, y);
+        self.get_mut(x, y).unwrap_or_else(|| panic!("{}", error))
     }
 }
 
@@ -93,6 +325,154 @@ mod tests {
         assert_eq!(c[(2, 3)], red);
     }
 
+    #[test]
+    fn pixels_outside_the_canvas() {
+        let mut c = Canvas::new(10, 20);
+        let red = Color::new(1.0, 0.0, 0.0);
+        *c.get_mut(9, 19).unwrap() = red;
+        assert_eq!(c.get(9, 19), Some(&red));
+        // (10, 0) would be (0, 1) if only the length of the canvas were checked.
+        assert_eq!(c.get(10, 0), None);
+        assert_eq!(c.get(0, 20), None);
+        assert!(c.get_mut(10, 0).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "pixel (10, 0) is outside the 10x20 image")]
+    fn indexing_outside_the_canvas_panics() {
+        let c = Canvas::new(10, 20);
+        let _ = c[(10, 0)];
+    }
+
+    #[test]
+    fn pixel_at_uv() {
+        let c = Canvas::from_fn(4, 2, |x, y| Color::new(x as f64, y as f64, 0.0));
+        assert_eq!(c.at_uv(0.0, 1.0), Color::new(0.0, 0.0, 0.0));
+        assert_eq!(c.at_uv(0.3, 0.75), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(c.at_uv(0.99, 0.0), Color::new(3.0, 1.0, 0.0));
+        // u wraps around, v doesn't.
+        assert_eq!(c.at_uv(1.0, 0.25), Color::new(0.0, 1.0, 0.0));
+        assert_eq!(c.at_uv(1.3, 1.5), Color::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn difference_of_identical_canvases_is_black() {
+        let c = Canvas::from_fn(4, 3, |x, y| Color::new(x as f64 / 4.0, y as f64 / 3.0, 0.5));
+        let d = c.difference(&c);
+        for y in 0..3 {
+            for x in 0..4 {
+                assert_eq!(d[(x, y)], Color::black());
+            }
+        }
+    }
+
+    #[test]
+    fn difference_is_absolute() {
+        let mut a = Canvas::new(3, 3);
+        let mut b = Canvas::new(3, 3);
+        a[(1, 2)] = Color::new(1.0, 0.25, 0.0);
+        b[(1, 2)] = Color::new(0.5, 0.5, 0.0);
+        let d = a.difference(&b);
+        assert_eq!(d[(1, 2)], Color::new(0.5, 0.25, 0.0));
+        assert_eq!(d[(0, 0)], Color::black());
+    }
+
+    #[test]
+    #[should_panic]
+    fn difference_requires_same_size() {
+        Canvas::new(2, 2).difference(&Canvas::new(3, 2));
+    }
+
+    #[test]
+    fn crop_then_blit_round_trips() {
+        let c = Canvas::from_fn(6, 4, |x, y| Color::new(x as f64, y as f64, 0.5));
+        let piece = c.crop(2, 1, 3, 2);
+        assert_eq!((piece.width, piece.height), (3, 2));
+        assert_eq!(piece[(0, 0)], c[(2, 1)]);
+        assert_eq!(piece[(2, 1)], c[(4, 2)]);
+
+        let mut copy = c.clone();
+        copy.blit(&Canvas::new(3, 2), 2, 1);
+        assert_ne!(copy, c);
+        copy.blit(&piece, 2, 1);
+        assert_eq!(copy, c);
+    }
+
+    #[test]
+    fn blit_clips_to_the_canvas() {
+        let mut c = Canvas::new(4, 3);
+        let white = Canvas::from_fn(3, 3, |_, _| Color::white());
+        c.blit(&white, 2, 1);
+        for y in 0..3 {
+            for x in 0..4 {
+                let expected = if x >= 2 && y >= 1 {
+                    Color::white()
+                } else {
+                    Color::black()
+                };
+                assert_eq!(c[(x, y)], expected);
+            }
+        }
+
+        let before = c.clone();
+        c.blit(&white, 4, 0);
+        c.blit(&white, 0, 10);
+        c.blit(&Canvas::new(0, 0), 1, 1);
+        assert_eq!(c, before);
+    }
+
+    #[test]
+    fn crop_clips_to_the_canvas() {
+        let c = Canvas::from_fn(4, 3, |x, y| Color::new(x as f64, y as f64, 0.0));
+        assert_eq!(c.crop(0, 0, 4, 3), c);
+        let corner = c.crop(3, 2, 5, 5);
+        assert_eq!((corner.width, corner.height), (1, 1));
+        assert_eq!(corner[(0, 0)], c[(3, 2)]);
+        let outside = c.crop(7, 1, 2, 2);
+        assert_eq!((outside.width, outside.height), (0, 2));
+        let empty = c.crop(1, 1, 0, 0);
+        assert_eq!((empty.width, empty.height), (0, 0));
+    }
+
+    #[test]
+    fn canvases_are_opaque_until_given_alpha() {
+        let mut c = Canvas::new(3, 2);
+        assert!(!c.has_alpha());
+        assert_eq!(c.alpha(2, 1), 1.0);
+
+        c.set_alpha(1, 0, 0.25);
+        assert!(c.has_alpha());
+        assert_eq!(c.alpha(1, 0), 0.25);
+        assert_eq!(c.alpha(2, 1), 1.0);
+        assert_ne!(c, Canvas::new(3, 2));
+        assert_eq!(c.without_alpha(), Canvas::new(3, 2));
+    }
+
+    #[test]
+    fn alpha_follows_blits_and_crops() {
+        let mut source = Canvas::new(2, 2);
+        source.set_alpha(1, 1, 0.0);
+        let mut c = Canvas::new(4, 4);
+        c.blit(&source, 2, 2);
+        assert_eq!(c.alpha(3, 3), 0.0);
+        assert_eq!(c.alpha(2, 2), 1.0);
+        assert_eq!(c.crop(3, 3, 1, 1).alpha(0, 0), 0.0);
+
+        // opaque sources cover up transparent pixels.
+        c.blit(&Canvas::new(2, 2), 2, 2);
+        assert_eq!(c.alpha(3, 3), 1.0);
+    }
+
+    #[test]
+    fn identity_tone_map_keeps_the_canvas() {
+        let c = Canvas::from_fn(4, 3, |x, y| Color::new(x as f64 * 3.0, y as f64, -0.5));
+        assert_eq!(c.tone_mapped(ToneMap::Identity), c);
+
+        let mapped = c.tone_mapped(ToneMap::Reinhard { white: None });
+        assert_eq!((mapped.width, mapped.height), (4, 3));
+        assert_eq!(mapped[(3, 2)], Color::new(0.9, 2.0 / 3.0, 0.0));
+    }
+
     #[test]
     fn ppm_header() {
         let c = Canvas::new(5, 3);
@@ -115,45 +495,61 @@ mod tests {
         let ppm = c.to_ppm();
         let lines: Vec<&str> = ppm.split("\n").collect();
         assert_eq!(
-            lines[3..18],
+            lines[3..6],
             [
-                "255 0 0", "0 0 0", "0 0 0", "0 0 0", "0 0 0", "0 0 0", "0 0 0", "0 128 0",
-                "0 0 0", "0 0 0", "0 0 0", "0 0 0", "0 0 0", "0 0 0", "0 0 255",
+                "255 0 0 0 0 0 0 0 0 0 0 0 0 0 0",
+                "0 0 0 0 0 0 0 128 0 0 0 0 0 0 0",
+                "0 0 0 0 0 0 0 0 0 0 0 0 0 0 255",
             ]
         );
     }
 
     #[test]
-    fn ppm_dense_colors() {
+    fn ppm_splits_long_lines() {
         let c = Canvas::from_fn(10, 2, |_, _| Color::new(1.0, 0.8, 0.6));
         let ppm = c.to_ppm();
         let lines: Vec<&str> = ppm.split("\n").collect();
 
         assert_eq!(
-            lines[3..23],
+            lines[3..7],
             [
-                "255 204 153",
-                "255 204 153",
-                "255 204 153",
-                "255 204 153",
-                "255 204 153",
-                "255 204 153",
-                "255 204 153",
-                "255 204 153",
-                "255 204 153",
-                "255 204 153",
-                "255 204 153",
-                "255 204 153",
-                "255 204 153",
-                "255 204 153",
-                "255 204 153",
-                "255 204 153",
-                "255 204 153",
-                "255 204 153",
-                "255 204 153",
-                "255 204 153",
+                "255 204 153 255 204 153 255 204 153 255 204 153 255 204 153 255 204",
+                "153 255 204 153 255 204 153 255 204 153 255 204 153",
+                "255 204 153 255 204 153 255 204 153 255 204 153 255 204 153 255 204",
+                "153 255 204 153 255 204 153 255 204 153 255 204 153",
             ]
         );
+        assert!(lines.iter().all(|line| line.len() <= 70));
+    }
+
+    #[test]
+    fn save_ppm_file() {
+        let mut c = Canvas::new(5, 3);
+        c[(0, 0)] = Color::new(1.0, 0.0, 0.0);
+        c[(4, 2)] = Color::new(0.0, 0.5, 1.0);
+
+        let path = std::env::temp_dir().join(format!("canvas-save-{}.ppm", std::process::id()));
+        c.save(&path).unwrap();
+        let saved = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(saved, c.to_ppm());
+        let lines: Vec<&str> = saved.split("\n").collect();
+        assert_eq!(lines[..3], ["P3", "5 3", "255"]);
+        assert!(lines[3].starts_with("255 0 0 0 0 0"));
+        assert!(lines[5].ends_with("0 128 255"));
+    }
+
+    #[test]
+    fn save_reports_errors() {
+        let c = Canvas::new(2, 2);
+        let unknown = c.save(std::env::temp_dir().join("canvas.unknown"));
+        assert_eq!(unknown.unwrap_err().kind(), io::ErrorKind::InvalidInput);
+
+        let missing = std::env::temp_dir()
+            .join("no-such-directory")
+            .join("canvas.ppm");
+        assert!(c.save(missing).is_err());
     }
 
     #[test]
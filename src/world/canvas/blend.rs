@@ -0,0 +1,159 @@
+//! combining two canvases of the same size, such as a render and an overlay.
+
+use crate::world::{canvas::SizeMismatch, Canvas, Color};
+
+/// how `Canvas::composite` combines each pixel of an overlay with the canvas under it.
+/// the arithmetic works on the linear channels, before any encoding.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BlendMode {
+    /// the overlay's color and alpha replace the canvas's.
+    Replace,
+    /// the overlay's color is added to the canvas's, as when summing light passes.
+    Add,
+    /// the overlay's color is multiplied into the canvas's, as when darkening a render
+    /// by an ambient occlusion pass.
+    Multiply,
+    /// the overlay is laid over the canvas, showing through it as much as the overlay's
+    /// alpha allows, with porter and duff's "over" operator.
+    AlphaOver,
+}
+
+impl BlendMode {
+    /// the color and alpha that `over` with `over_alpha` makes when blended onto
+    /// `under` with `under_alpha`.
+    fn blend(
+        self,
+        (under, under_alpha): (Color, f64),
+        (over, over_alpha): (Color, f64),
+    ) -> (Color, f64) {
+        match self {
+            BlendMode::Replace => (over, over_alpha),
+            BlendMode::Add => (under + over, under_alpha),
+            BlendMode::Multiply => (under * over, under_alpha),
+            BlendMode::AlphaOver => {
+                let kept = under_alpha * (1.0 - over_alpha);
+                let alpha = over_alpha + kept;
+                if alpha == 0.0 {
+                    return (under, 0.0);
+                }
+                // the colors aren't premultiplied, so they are weighted here and then
+                // divided back out.
+                ((over * over_alpha + under * kept) / alpha, alpha)
+            }
+        }
+    }
+}
+
+impl Canvas {
+    /// blends `other` onto the canvas pixel by pixel. except when replacing, the
+    /// canvas keeps its own alpha for adding and multiplying. the canvases must be the
+    /// same size.
+    pub fn composite(&mut self, other: &Canvas, mode: BlendMode) -> Result<(), SizeMismatch> {
+        if (self.width, self.height) != (other.width, other.height) {
+            return Err(SizeMismatch {
+                left: (self.width, self.height),
+                right: (other.width, other.height),
+            });
+        }
+
+        let keeps_alpha = matches!(mode, BlendMode::Add | BlendMode::Multiply);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let under = (self[(x, y)], self.alpha(x, y));
+                let over = (other[(x, y)], other.alpha(x, y));
+                let (color, alpha) = mode.blend(under, over);
+                self[(x, y)] = color;
+                if !keeps_alpha && (self.has_alpha() || alpha != 1.0) {
+                    self.set_alpha(x, y, alpha);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> (Canvas, Canvas) {
+        let a = Canvas::from_fn(3, 2, |x, y| {
+            Color::new(x as f64 * 0.25, 0.5, y as f64 * 0.75)
+        });
+        let b = Canvas::from_fn(3, 2, |x, y| {
+            Color::new(0.1, x as f64 * 0.3, 1.0 - y as f64 * 0.5)
+        });
+        (a, b)
+    }
+
+    #[test]
+    fn multiplying_by_white_keeps_the_canvas() {
+        let (a, _) = setup();
+        let mut c = a.clone();
+        let white = Canvas::from_fn(3, 2, |_, _| Color::white());
+        c.composite(&white, BlendMode::Multiply).unwrap();
+        assert_eq!(c, a);
+    }
+
+    #[test]
+    fn adding_is_commutative() {
+        let (a, b) = setup();
+        let mut ab = a.clone();
+        ab.composite(&b, BlendMode::Add).unwrap();
+        let mut ba = b.clone();
+        ba.composite(&a, BlendMode::Add).unwrap();
+        assert_eq!(ab, ba);
+        assert_eq!(ab[(2, 1)], Color::new(0.6, 1.1, 1.25));
+    }
+
+    #[test]
+    fn replacing_copies_colors_and_alpha() {
+        let (mut a, mut b) = setup();
+        b.set_alpha(1, 1, 0.5);
+        a.composite(&b, BlendMode::Replace).unwrap();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn transparent_overlays_leave_the_canvas_alone() {
+        let (a, mut b) = setup();
+        for y in 0..2 {
+            for x in 0..3 {
+                b.set_alpha(x, y, 0.0);
+            }
+        }
+        let mut c = a.clone();
+        c.composite(&b, BlendMode::AlphaOver).unwrap();
+        assert_eq!(c, a);
+        assert!(!c.has_alpha());
+    }
+
+    #[test]
+    fn alpha_over_mixes_by_coverage() {
+        let (a, mut b) = setup();
+        b.set_alpha(0, 0, 0.25);
+        let mut c = a.clone();
+        c.composite(&b, BlendMode::AlphaOver).unwrap();
+        assert_eq!(c[(0, 0)], a[(0, 0)] * 0.75 + b[(0, 0)] * 0.25);
+        assert_eq!(c[(2, 1)], b[(2, 1)]);
+
+        // a half transparent overlay on a transparent canvas stays half transparent.
+        let mut clear = Canvas::new(3, 2);
+        clear.set_alpha(0, 0, 0.0);
+        clear.composite(&b, BlendMode::AlphaOver).unwrap();
+        assert_eq!(clear.alpha(0, 0), 0.25);
+        assert_eq!(clear[(0, 0)], b[(0, 0)]);
+    }
+
+    #[test]
+    fn sizes_must_match() {
+        let mut c = Canvas::new(2, 2);
+        assert_eq!(
+            c.composite(&Canvas::new(2, 3), BlendMode::Add),
+            Err(SizeMismatch {
+                left: (2, 2),
+                right: (2, 3)
+            })
+        );
+    }
+}
@@ -0,0 +1,122 @@
+//! packing a canvas into bytes, for windows, GUI toolkits and the like.
+
+use crate::world::{color::MAX_COLOR, Canvas, Color};
+
+impl Canvas {
+    /// the pixels as 8-bit red, green and blue bytes, clamped and rounded just as in a
+    /// PPM image. the pixels run left to right along each row, and the rows run from
+    /// the top of the image to the bottom with no padding, so pixel (x, y) starts at
+    /// byte `3 * (x + y * width)` and the buffer is `3 * width * height` bytes long.
+    pub fn to_rgb8(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.vals.len() * 3);
+        for color in self.vals.iter() {
+            let (r, g, b) = color.quantize(MAX_COLOR as u32);
+            bytes.extend_from_slice(&[r as u8, g as u8, b as u8]);
+        }
+        bytes
+    }
+
+    /// the pixels as 8-bit red, green, blue and alpha bytes, laid out like `to_rgb8`
+    /// but with four bytes to a pixel, so that pixel (x, y) starts at byte
+    /// `4 * (x + y * width)`. canvases without an alpha plane have an alpha of 255, for
+    /// fully opaque, everywhere. the colors are not premultiplied by their alpha.
+    pub fn to_rgba8(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.vals.len() * 4);
+        for (i, color) in self.vals.iter().enumerate() {
+            let (r, g, b) = color.quantize(MAX_COLOR as u32);
+            let alpha = match &self.alpha {
+                Some(alpha) => Color::white() * alpha[i],
+                None => Color::white(),
+            };
+            let (a, _, _) = alpha.quantize(MAX_COLOR as u32);
+            bytes.extend_from_slice(&[r as u8, g as u8, b as u8, a as u8]);
+        }
+        bytes
+    }
+
+    /// reads pixels laid out as by `to_rgba8`, alpha included.
+    ///
+    /// panics unless there are exactly `4 * width * height` bytes.
+    pub fn from_rgba8(width: usize, height: usize, bytes: &[u8]) -> Canvas {
+        assert_eq!(
+            bytes.len(),
+            width * height * 4,
+            "a {}x{} canvas needs {} bytes of RGBA",
+            width,
+            height,
+            width * height * 4
+        );
+
+        let channel = |byte: u8| byte as f64 / MAX_COLOR;
+        let mut canvas = Canvas::from_fn(width, height, |x, y| {
+            let i = (x + y * width) * 4;
+            Color::new(
+                channel(bytes[i]),
+                channel(bytes[i + 1]),
+                channel(bytes[i + 2]),
+            )
+        });
+        canvas.alpha = Some(bytes.chunks(4).map(|pixel| channel(pixel[3])).collect());
+        canvas
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> Canvas {
+        Canvas::from_fn(4, 3, |x, y| {
+            Color::new(x as f64 / 3.0, y as f64 / 2.0, ((x + y) % 2) as f64)
+        })
+    }
+
+    #[test]
+    fn buffer_lengths() {
+        let c = setup();
+        assert_eq!(c.to_rgb8().len(), 4 * 3 * 3);
+        assert_eq!(c.to_rgba8().len(), 4 * 3 * 4);
+        assert!(Canvas::new(0, 5).to_rgba8().is_empty());
+    }
+
+    #[test]
+    fn pixels_land_at_their_offsets() {
+        let mut c = Canvas::new(4, 3);
+        c[(2, 2)] = Color::new(1.5, 0.5, -1.0);
+        let rgba = c.to_rgba8();
+        let offset = 4 * (2 + 2 * 4);
+        assert_eq!(rgba[offset..offset + 4], [255, 128, 0, 255]);
+        assert_eq!(rgba[offset - 4..offset], [0, 0, 0, 255]);
+
+        let rgb = c.to_rgb8();
+        let offset = 3 * (2 + 2 * 4);
+        assert_eq!(rgb[offset..offset + 3], [255, 128, 0]);
+    }
+
+    #[test]
+    fn quantized_colors_round_trip() {
+        let bytes = setup().to_rgba8();
+        let c = Canvas::from_rgba8(4, 3, &bytes);
+        assert_eq!(c.to_rgba8(), bytes);
+        assert_eq!(c[(3, 2)], Color::new(1.0, 1.0, 1.0));
+        assert_eq!(c[(1, 0)], Color::new(85.0 / 255.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn alpha_is_packed_too() {
+        let mut c = setup();
+        c.set_alpha(1, 2, 0.5);
+        let rgba = c.to_rgba8();
+        assert_eq!(rgba[4 * (1 + 2 * 4) + 3], 128);
+        assert_eq!(rgba[3], 255);
+        let read = Canvas::from_rgba8(4, 3, &rgba);
+        assert!((read.alpha(1, 2) - 0.5).abs() < 1.0 / 255.0);
+        assert_eq!(read.alpha(0, 0), 1.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "a 2x2 canvas needs 16 bytes of RGBA")]
+    fn rgba_must_fill_the_canvas() {
+        Canvas::from_rgba8(2, 2, &[0; 12]);
+    }
+}
@@ -0,0 +1,182 @@
+//! measuring how far apart two canvases are, for tests that compare renders against
+//! images known to be right.
+
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+use crate::world::Canvas;
+
+/// two canvases couldn't be compared or combined, because they aren't the same size.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SizeMismatch {
+    /// the width and height of the canvas being compared or composited onto.
+    pub left: (usize, usize),
+    /// the width and height of the other canvas.
+    pub right: (usize, usize),
+}
+
+impl Display for SizeMismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "a {}x{} canvas does not match a {}x{} canvas in size",
+            self.left.0, self.left.1, self.right.0, self.right.1
+        )
+    }
+}
+
+impl Error for SizeMismatch {}
+
+/// how far apart the pixels of two canvases of the same size are. the error of a pixel
+/// is the largest difference in any one of its channels.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CanvasDiff {
+    /// the largest error of any pixel. pixels with NaN or infinite channels in either
+    /// canvas have an infinite error.
+    pub max_error: f64,
+    /// the average error over all the pixels.
+    pub mean_error: f64,
+    /// the first of the pixels with the largest error, or none if the canvases are
+    /// empty.
+    pub worst_pixel: Option<(usize, usize)>,
+    errors: Vec<f64>,
+}
+
+impl CanvasDiff {
+    /// how many pixels have an error greater than `tolerance`.
+    pub fn pixels_over(&self, tolerance: f64) -> usize {
+        self.errors
+            .iter()
+            .filter(|&&error| error > tolerance)
+            .count()
+    }
+}
+
+impl Canvas {
+    /// compares the canvas pixel by pixel against `other`. only the colors are
+    /// compared, not the alpha.
+    pub fn diff(&self, other: &Canvas) -> Result<CanvasDiff, SizeMismatch> {
+        if (self.width, self.height) != (other.width, other.height) {
+            return Err(SizeMismatch {
+                left: (self.width, self.height),
+                right: (other.width, other.height),
+            });
+        }
+
+        let errors: Vec<f64> = self
+            .vals
+            .iter()
+            .zip(other.vals.iter())
+            .map(|(a, b)| {
+                // NaN or infinite colors are as wrong as it gets.
+                if a.is_finite() && b.is_finite() {
+                    a.distance(b)
+                } else {
+                    f64::INFINITY
+                }
+            })
+            .collect();
+
+        let mut diff = CanvasDiff {
+            max_error: 0.0,
+            mean_error: 0.0,
+            worst_pixel: None,
+            errors,
+        };
+        for (i, &error) in diff.errors.iter().enumerate() {
+            if diff.worst_pixel.is_none() || error > diff.max_error {
+                diff.max_error = error;
+                diff.worst_pixel = Some((i % self.width, i / self.width));
+            }
+            diff.mean_error += error;
+        }
+        if !diff.errors.is_empty() {
+            diff.mean_error /= diff.errors.len() as f64;
+        }
+        Ok(diff)
+    }
+
+    /// checks that the canvases are the same size, and that no channel of any pixel
+    /// differs by more than `tolerance`.
+    pub fn approx_eq(&self, other: &Canvas, tolerance: f64) -> bool {
+        self.diff(other)
+            .is_ok_and(|diff| diff.max_error <= tolerance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::Color;
+
+    fn setup() -> (Canvas, Canvas) {
+        let a = Canvas::from_fn(3, 2, |x, y| {
+            Color::new(x as f64 * 0.25, y as f64 * 0.5, 0.0)
+        });
+        let mut b = a.clone();
+        b[(2, 1)] += Color::new(0.0, 0.0, 0.3);
+        b[(0, 1)] -= Color::new(0.1, 0.0, 0.0);
+        (a, b)
+    }
+
+    #[test]
+    fn identical_canvases() {
+        let (a, _) = setup();
+        let diff = a.diff(&a).unwrap();
+        assert_eq!(diff.max_error, 0.0);
+        assert_eq!(diff.mean_error, 0.0);
+        assert_eq!(diff.worst_pixel, Some((0, 0)));
+        assert_eq!(diff.pixels_over(0.0), 0);
+        assert!(a.approx_eq(&a, 0.0));
+    }
+
+    #[test]
+    fn diff_finds_the_worst_pixel() {
+        let (a, b) = setup();
+        let diff = a.diff(&b).unwrap();
+        assert!((diff.max_error - 0.3).abs() < 1e-12);
+        assert!((diff.mean_error - 0.4 / 6.0).abs() < 1e-12);
+        assert_eq!(diff.worst_pixel, Some((2, 1)));
+        assert_eq!(diff.pixels_over(0.05), 2);
+        assert_eq!(diff.pixels_over(0.2), 1);
+        assert_eq!(diff.pixels_over(0.3), 0);
+    }
+
+    #[test]
+    fn approx_eq_uses_the_tolerance() {
+        let (a, b) = setup();
+        assert!(!a.approx_eq(&b, 0.1));
+        assert!(a.approx_eq(&b, 0.3));
+        assert!(b.approx_eq(&a, 0.3));
+    }
+
+    #[test]
+    fn nan_pixels_are_the_worst() {
+        let (a, mut b) = setup();
+        b[(1, 0)] = Color::new(f64::NAN, 0.0, 0.0);
+        let diff = a.diff(&b).unwrap();
+        assert_eq!(diff.max_error, f64::INFINITY);
+        assert_eq!(diff.worst_pixel, Some((1, 0)));
+        assert!(!a.approx_eq(&b, 1.0));
+    }
+
+    #[test]
+    fn sizes_must_match() {
+        let a = Canvas::new(3, 2);
+        let b = Canvas::new(2, 3);
+        assert_eq!(
+            a.diff(&b),
+            Err(SizeMismatch {
+                left: (3, 2),
+                right: (2, 3)
+            })
+        );
+        assert_eq!(
+            a.diff(&b).unwrap_err().to_string(),
+            "a 3x2 canvas does not match a 2x3 canvas in size"
+        );
+        assert!(!a.approx_eq(&b, 1.0));
+    }
+}
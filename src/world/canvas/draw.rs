@@ -0,0 +1,185 @@
+//! drawing simple shapes onto a canvas, for plots and debugging overlays.
+//!
+//! positions are signed, so shapes may hang off any edge of the canvas. whatever falls
+//! outside is clipped away. on canvases with an alpha plane, drawn pixels are opaque.
+
+use crate::world::{Canvas, Color};
+
+impl Canvas {
+    /// draws a one pixel wide line from `p0` to `p1`, both included, with bresenham's
+    /// algorithm.
+    pub fn draw_line(&mut self, p0: (i64, i64), p1: (i64, i64), color: Color) {
+        let ((x0, y0), (x1, y1)) = (p0, p1);
+        // lines that pass entirely to one side of the canvas can't touch it.
+        if x0.max(x1) < 0
+            || y0.max(y1) < 0
+            || x0.min(x1) >= self.width as i64
+            || y0.min(y1) >= self.height as i64
+        {
+            return;
+        }
+
+        let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+        let (step_x, step_y) = ((x1 - x0).signum(), (y1 - y0).signum());
+        let (mut x, mut y) = (x0, y0);
+        let mut error = dx + dy;
+        loop {
+            self.plot(x, y, color);
+            if x == x1 && y == y1 {
+                break;
+            }
+            let doubled = 2 * error;
+            if doubled >= dy {
+                error += dy;
+                x += step_x;
+            }
+            if doubled <= dx {
+                error += dx;
+                y += step_y;
+            }
+        }
+    }
+
+    /// draws the outline of a circle around `center`, with the midpoint circle
+    /// algorithm. a radius of 0 draws only the center.
+    pub fn draw_circle(&mut self, center: (i64, i64), radius: usize, color: Color) {
+        let (cx, cy) = center;
+        let (mut x, mut y) = (radius as i64, 0);
+        let mut error = 1 - x;
+        while x >= y {
+            for &(dx, dy) in [(x, y), (y, x)].iter() {
+                self.plot(cx + dx, cy + dy, color);
+                self.plot(cx - dx, cy + dy, color);
+                self.plot(cx + dx, cy - dy, color);
+                self.plot(cx - dx, cy - dy, color);
+            }
+            y += 1;
+            if error < 0 {
+                error += 2 * y + 1;
+            } else {
+                x -= 1;
+                error += 2 * (y - x) + 1;
+            }
+        }
+    }
+
+    /// fills the rectangle `width` by `height` pixels whose top left corner is (x, y).
+    pub fn fill_rect(&mut self, x: i64, y: i64, width: usize, height: usize, color: Color) {
+        let clip = |start: i64, len: usize, max: usize| {
+            let end = start.saturating_add(len as i64).clamp(0, max as i64);
+            (start.clamp(0, max as i64) as usize, end as usize)
+        };
+        let (left, right) = clip(x, width, self.width);
+        let (top, bottom) = clip(y, height, self.height);
+        for j in top..bottom {
+            for i in left..right {
+                self.plot(i as i64, j as i64, color);
+            }
+        }
+    }
+
+    /// sets the pixel at (x, y), if it is on the canvas.
+    fn plot(&mut self, x: i64, y: i64, color: Color) {
+        if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 {
+            return;
+        }
+        let (x, y) = (x as usize, y as usize);
+        self[(x, y)] = color;
+        if self.has_alpha() {
+            self.set_alpha(x, y, 1.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// the pixels of `canvas` that have been drawn on, from left to right and top to
+    /// bottom.
+    fn drawn(canvas: &Canvas) -> Vec<(usize, usize)> {
+        let mut pixels = vec![];
+        for y in 0..canvas.height {
+            for x in 0..canvas.width {
+                if canvas[(x, y)] != Color::black() {
+                    pixels.push((x, y));
+                }
+            }
+        }
+        pixels
+    }
+
+    #[test]
+    fn horizontal_lines() {
+        let mut c = Canvas::new(6, 3);
+        c.draw_line((4, 1), (1, 1), Color::white());
+        assert_eq!(drawn(&c), vec![(1, 1), (2, 1), (3, 1), (4, 1)]);
+
+        // only the part on the canvas is drawn.
+        let mut c = Canvas::new(6, 3);
+        c.draw_line((-10, 2), (2, 2), Color::white());
+        assert_eq!(drawn(&c), vec![(0, 2), (1, 2), (2, 2)]);
+    }
+
+    #[test]
+    fn diagonal_and_steep_lines() {
+        let mut c = Canvas::new(4, 4);
+        c.draw_line((0, 0), (3, 3), Color::white());
+        assert_eq!(drawn(&c), vec![(0, 0), (1, 1), (2, 2), (3, 3)]);
+
+        let mut c = Canvas::new(4, 5);
+        c.draw_line((1, 0), (2, 4), Color::white());
+        let pixels = drawn(&c);
+        assert_eq!(pixels.len(), 5);
+        assert_eq!((pixels[0], pixels[4]), ((1, 0), (2, 4)));
+    }
+
+    #[test]
+    fn lines_off_the_canvas_change_nothing() {
+        let mut c = Canvas::new(5, 5);
+        c.draw_line((-3, -1), (-3, 8), Color::white());
+        c.draw_line((0, 5), (4, 9), Color::white());
+        c.draw_line((i64::MIN / 4, -2), (i64::MAX / 4, -2), Color::white());
+        assert_eq!(c, Canvas::new(5, 5));
+    }
+
+    #[test]
+    fn circles() {
+        let mut c = Canvas::new(5, 5);
+        c.draw_circle((2, 2), 0, Color::white());
+        assert_eq!(drawn(&c), vec![(2, 2)]);
+
+        let mut c = Canvas::new(5, 5);
+        c.draw_circle((2, 2), 2, Color::white());
+        let pixels = drawn(&c);
+        for &edge in [(0, 2), (4, 2), (2, 0), (2, 4)].iter() {
+            assert!(pixels.contains(&edge));
+        }
+        assert!(!pixels.contains(&(2, 2)));
+
+        // circles around points off the canvas still show their edge.
+        let mut c = Canvas::new(5, 5);
+        c.draw_circle((-2, 2), 2, Color::white());
+        assert_eq!(drawn(&c), vec![(0, 1), (0, 2), (0, 3)]);
+    }
+
+    #[test]
+    fn rectangles_are_clipped() {
+        let mut c = Canvas::new(4, 3);
+        c.fill_rect(-1, 1, 3, 5, Color::white());
+        assert_eq!(drawn(&c), vec![(0, 1), (1, 1), (0, 2), (1, 2)]);
+
+        let mut c = Canvas::new(4, 3);
+        c.fill_rect(4, 0, 2, 2, Color::white());
+        c.fill_rect(-5, -5, 3, 3, Color::white());
+        assert_eq!(c, Canvas::new(4, 3));
+    }
+
+    #[test]
+    fn drawing_makes_pixels_opaque() {
+        let mut c = Canvas::new(3, 3);
+        c.set_alpha(1, 1, 0.0);
+        c.draw_circle((1, 1), 0, Color::white());
+        assert_eq!(c.alpha(1, 1), 1.0);
+    }
+}
@@ -0,0 +1,90 @@
+//! writing portable float maps, which keep the exact linear colors of a canvas.
+
+use crate::world::Canvas;
+
+impl Canvas {
+    /// the canvas as a color portable float map. unlike PPM, the channels are written
+    /// as they are, without being clamped to 0 to 1, so that the image can still be
+    /// tone mapped or inspected afterwards. each channel is a 32-bit float, stored
+    /// least significant byte first, as the negative scale in the header says. the
+    /// rows run from the bottom of the image to the top.
+    pub fn to_pfm(&self) -> Vec<u8> {
+        let header = format!("PF\n{} {}\n-1.0\n", self.width, self.height);
+        let mut bytes = header.into_bytes();
+        bytes.reserve(self.width * self.height * 3 * 4);
+        for row in self.vals.chunks(self.width.max(1)).rev() {
+            for color in row.iter() {
+                for &channel in [color.red(), color.green(), color.blue()].iter() {
+                    bytes.extend_from_slice(&(channel as f32).to_le_bytes());
+                }
+            }
+        }
+        bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::world::{Canvas, Color};
+
+    /// reads back a color portable float map in either byte order.
+    fn from_pfm(bytes: &[u8]) -> Canvas {
+        let mut fields = Vec::new();
+        let mut at = 0;
+        while fields.len() < 4 {
+            let start = at;
+            while !bytes[at].is_ascii_whitespace() {
+                at += 1;
+            }
+            fields.push(std::str::from_utf8(&bytes[start..at]).unwrap());
+            at += 1;
+        }
+        assert_eq!(fields[0], "PF");
+        let width: usize = fields[1].parse().unwrap();
+        let height: usize = fields[2].parse().unwrap();
+        let little_endian = fields[3].parse::<f64>().unwrap() < 0.0;
+
+        let data = &bytes[at..];
+        assert_eq!(data.len(), width * height * 3 * 4);
+        let channel = |i: usize| {
+            let mut word = [0; 4];
+            word.copy_from_slice(&data[i * 4..i * 4 + 4]);
+            if little_endian {
+                f32::from_le_bytes(word) as f64
+            } else {
+                f32::from_be_bytes(word) as f64
+            }
+        };
+        Canvas::from_fn(width, height, |x, y| {
+            let i = (x + (height - 1 - y) * width) * 3;
+            Color::new(channel(i), channel(i + 1), channel(i + 2))
+        })
+    }
+
+    #[test]
+    fn pfm_header() {
+        let pfm = Canvas::new(5, 3).to_pfm();
+        assert!(pfm.starts_with(b"PF\n5 3\n-1.0\n"));
+        assert_eq!(pfm.len(), 12 + 5 * 3 * 12);
+    }
+
+    #[test]
+    fn pfm_rows_run_from_the_bottom() {
+        let mut c = Canvas::new(2, 2);
+        c[(0, 1)] = Color::new(0.25, 0.0, 0.0);
+        let pfm = c.to_pfm();
+        assert_eq!(pfm[12..16], 0.25f32.to_le_bytes());
+    }
+
+    #[test]
+    fn pfm_keeps_colors_unclamped() {
+        let c = Canvas::from_fn(3, 2, |x, y| {
+            Color::new(1.9, -0.5, x as f64 * 0.25 + y as f64 * 4.0)
+        });
+        assert_eq!(from_pfm(&c.to_pfm()), c);
+        let read = from_pfm(&c.to_pfm());
+        assert_eq!(read[(2, 1)].blue(), 4.5);
+        assert_eq!(read[(0, 0)].red(), 1.9f32 as f64);
+        assert_eq!(read[(0, 0)].green(), -0.5);
+    }
+}
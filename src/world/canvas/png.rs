@@ -0,0 +1,193 @@
+//! writing PNG images, which unlike PPM can keep the alpha plane of a canvas.
+
+use crate::world::Canvas;
+
+/// the most bytes a single uncompressed deflate block can hold.
+const MAX_STORED_BLOCK: usize = 0xffff;
+
+impl Canvas {
+    /// the canvas as an 8-bit PNG image. canvases with an alpha plane are written as
+    /// RGBA, and the rest as RGB. the image data is stored without compression, which
+    /// keeps the writer small at the cost of larger files.
+    pub fn to_png(&self) -> Vec<u8> {
+        let (channels, color_type) = if self.has_alpha() { (4, 6) } else { (3, 2) };
+        let pixels = if self.has_alpha() {
+            self.to_rgba8()
+        } else {
+            self.to_rgb8()
+        };
+
+        // each row starts with the filter it uses, which is always none here.
+        let mut scanlines = Vec::with_capacity(pixels.len() + self.height);
+        for row in pixels.chunks(self.width.max(1) * channels) {
+            scanlines.push(0);
+            scanlines.extend_from_slice(row);
+        }
+
+        let mut header = Vec::with_capacity(13);
+        header.extend_from_slice(&(self.width as u32).to_be_bytes());
+        header.extend_from_slice(&(self.height as u32).to_be_bytes());
+        // 8 bits a channel, then the color type, and the default compression, filter
+        // and interlace methods.
+        header.extend_from_slice(&[8, color_type, 0, 0, 0]);
+
+        let mut bytes = b"\x89PNG\r\n\x1a\n".to_vec();
+        write_chunk(&mut bytes, b"IHDR", &header);
+        write_chunk(&mut bytes, b"IDAT", &zlib_stored(&scanlines));
+        write_chunk(&mut bytes, b"IEND", &[]);
+        bytes
+    }
+}
+
+/// appends a chunk with its length, type, data and checksum.
+fn write_chunk(bytes: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    bytes.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = bytes.len();
+    bytes.extend_from_slice(kind);
+    bytes.extend_from_slice(data);
+    let crc = crc32(&bytes[start..]);
+    bytes.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// wraps `data` in a zlib stream made of uncompressed deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let blocks = data.len().div_ceil(MAX_STORED_BLOCK).max(1);
+    let mut bytes = Vec::with_capacity(data.len() + blocks * 5 + 6);
+    // deflate with a 32K window, and check bits that make the header a multiple of 31.
+    bytes.extend_from_slice(&[0x78, 0x01]);
+
+    let mut chunks = data.chunks(MAX_STORED_BLOCK).peekable();
+    if chunks.peek().is_none() {
+        bytes.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(chunk) = chunks.next() {
+        let last = chunks.peek().is_none();
+        let len = chunk.len() as u16;
+        bytes.push(last as u8);
+        bytes.extend_from_slice(&len.to_le_bytes());
+        bytes.extend_from_slice(&(!len).to_le_bytes());
+        bytes.extend_from_slice(chunk);
+    }
+
+    bytes.extend_from_slice(&adler32(data).to_be_bytes());
+    bytes
+}
+
+/// the CRC-32 that PNG uses to check each chunk.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data.iter() {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// the adler-32 checksum that zlib puts at the end of a stream.
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data.iter() {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::Color;
+
+    /// reads back a PNG written by `to_png`, as its width, height, channels and pixel
+    /// bytes. only uncompressed blocks and unfiltered rows are understood.
+    fn from_png(bytes: &[u8]) -> (usize, usize, usize, Vec<u8>) {
+        assert_eq!(&bytes[..8], b"\x89PNG\r\n\x1a\n");
+        let mut at = 8;
+        let (mut width, mut height, mut channels) = (0, 0, 0);
+        let mut stream = vec![];
+        while at < bytes.len() {
+            let mut word = [0; 4];
+            word.copy_from_slice(&bytes[at..at + 4]);
+            let len = u32::from_be_bytes(word) as usize;
+            let body = &bytes[at + 4..at + 8 + len];
+            word.copy_from_slice(&bytes[at + 8 + len..at + 12 + len]);
+            assert_eq!(u32::from_be_bytes(word), crc32(body));
+
+            let data = &body[4..];
+            match &body[..4] {
+                b"IHDR" => {
+                    word.copy_from_slice(&data[..4]);
+                    width = u32::from_be_bytes(word) as usize;
+                    word.copy_from_slice(&data[4..8]);
+                    height = u32::from_be_bytes(word) as usize;
+                    channels = if data[9] == 6 { 4 } else { 3 };
+                }
+                b"IDAT" => stream.extend_from_slice(data),
+                _ => {}
+            }
+            at += 12 + len;
+        }
+
+        assert_eq!((stream[0] as u32 * 256 + stream[1] as u32) % 31, 0);
+        let mut scanlines = vec![];
+        let mut at = 2;
+        loop {
+            let last = stream[at] & 1 == 1;
+            assert_eq!(stream[at] >> 1, 0);
+            let len = stream[at + 1] as usize | (stream[at + 2] as usize) << 8;
+            scanlines.extend_from_slice(&stream[at + 5..at + 5 + len]);
+            at += 5 + len;
+            if last {
+                break;
+            }
+        }
+        let mut word = [0; 4];
+        word.copy_from_slice(&stream[at..at + 4]);
+        assert_eq!(u32::from_be_bytes(word), adler32(&scanlines));
+
+        let mut pixels = vec![];
+        for row in scanlines.chunks(width * channels + 1) {
+            assert_eq!(row[0], 0);
+            pixels.extend_from_slice(&row[1..]);
+        }
+        (width, height, channels, pixels)
+    }
+
+    #[test]
+    fn checksums() {
+        assert_eq!(crc32(b"IEND"), 0xae42_6082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+
+    #[test]
+    fn opaque_canvases_are_rgb() {
+        let c = Canvas::from_fn(3, 2, |x, y| Color::new(x as f64 / 2.0, y as f64, 0.0));
+        let png = c.to_png();
+        assert!(png.ends_with(&[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82]));
+        assert_eq!(from_png(&png), (3, 2, 3, c.to_rgb8()));
+    }
+
+    #[test]
+    fn alpha_is_written_as_rgba() {
+        let mut c = Canvas::new(2, 2);
+        c.set_alpha(1, 1, 0.0);
+        let (width, height, channels, pixels) = from_png(&c.to_png());
+        assert_eq!((width, height, channels), (2, 2, 4));
+        assert_eq!(pixels, c.to_rgba8());
+        assert_eq!(pixels[15], 0);
+    }
+
+    #[test]
+    fn large_images_span_several_blocks() {
+        let c = Canvas::from_fn(200, 120, |x, y| {
+            Color::new(x as f64 / 200.0, 0.5, y as f64 / 120.0)
+        });
+        assert_eq!(from_png(&c.to_png()), (200, 120, 3, c.to_rgb8()));
+    }
+}
@@ -0,0 +1,819 @@
+//! reading and writing PPM images, in either the plain (P3) or the raw (P6) format.
+
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    io::{self, Read, Write},
+};
+
+use crate::world::{color::Encoding, Canvas, Color};
+
+/// how many bits each sample of a written image takes.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum BitDepth {
+    /// samples from 0 to 255, which is what most programs expect.
+    #[default]
+    Eight,
+    /// samples from 0 to 65535, so that smooth gradients don't show bands.
+    Sixteen,
+}
+
+impl BitDepth {
+    /// the largest sample, which stands for a channel of 1.
+    pub fn max(&self) -> u32 {
+        match self {
+            BitDepth::Eight => u8::MAX as u32,
+            BitDepth::Sixteen => u16::MAX as u32,
+        }
+    }
+}
+
+/// how the rounding error of quantizing is spread around, so that smooth gradients
+/// don't break into visible bands.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Dither {
+    /// each sample is rounded to the nearest value on its own.
+    #[default]
+    None,
+    /// each sample is nudged by a threshold from a repeating 4 by 4 bayer matrix.
+    Ordered,
+    /// the error of each sample is passed on to its neighbors to the right and below,
+    /// as in floyd and steinberg's error diffusion.
+    FloydSteinberg,
+}
+
+/// the 4 by 4 bayer matrix, whose thresholds are spread as evenly as possible.
+const BAYER: [[f64; 4]; 4] = [
+    [0.0, 8.0, 2.0, 10.0],
+    [12.0, 4.0, 14.0, 6.0],
+    [3.0, 11.0, 1.0, 9.0],
+    [15.0, 7.0, 13.0, 5.0],
+];
+
+/// how a canvas is written out as a PPM image.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct PpmOptions {
+    pub depth: BitDepth,
+    /// how the canvas's linear colors are turned into the samples in the file. by
+    /// default they are written as they are.
+    pub encoding: Encoding,
+    pub dither: Dither,
+}
+
+impl PpmOptions {
+    /// the samples of the canvas, one row at a time.
+    fn rows<'a>(&self, canvas: &'a Canvas) -> QuantizedRows<'a> {
+        QuantizedRows {
+            canvas,
+            options: *self,
+            y: 0,
+            errors: vec![[0.0; 3]; canvas.width + 2],
+        }
+    }
+}
+
+/// the encoded and quantized samples of each row of a canvas, dithered as the options
+/// say. the canvas itself is left alone.
+struct QuantizedRows<'a> {
+    canvas: &'a Canvas,
+    options: PpmOptions,
+    y: usize,
+    /// with error diffusion, the error carried into each pixel of the row, offset by one
+    /// so that the pixels either side of the row have somewhere to go.
+    errors: Vec<[f64; 3]>,
+}
+
+impl<'a> Iterator for QuantizedRows<'a> {
+    type Item = Vec<[u32; 3]>;
+
+    fn next(&mut self) -> Option<Vec<[u32; 3]>> {
+        if self.y >= self.canvas.height {
+            return None;
+        }
+        let (width, y) = (self.canvas.width, self.y);
+        self.y += 1;
+
+        let max = self.options.depth.max();
+        let row = &self.canvas.vals[y * width..(y + 1) * width];
+        let encoding = self.options.encoding;
+        let encoded = row.iter().map(|color| color.encoded(encoding));
+        let samples = match self.options.dither {
+            Dither::None => encoded
+                .map(|color| {
+                    let (r, g, b) = color.quantize(max);
+                    [r, g, b]
+                })
+                .collect(),
+            Dither::Ordered => encoded
+                .enumerate()
+                .map(|(x, color)| {
+                    let nudge = (BAYER[y % 4][x % 4] + 0.5) / 16.0 - 0.5;
+                    let channels = [color.red(), color.green(), color.blue()];
+                    let mut samples = [0; 3];
+                    for (sample, channel) in samples.iter_mut().zip(channels.iter()) {
+                        *sample = round_sample(channel * max as f64 + nudge, max);
+                    }
+                    samples
+                })
+                .collect(),
+            Dither::FloydSteinberg => {
+                let carried = std::mem::replace(&mut self.errors, vec![[0.0; 3]; width + 2]);
+                let mut across = [0.0; 3];
+                let mut samples = Vec::with_capacity(width);
+                for (x, color) in encoded.enumerate() {
+                    let channels = [color.red(), color.green(), color.blue()];
+                    let mut quantized = [0; 3];
+                    for c in 0..3 {
+                        let wanted = channels[c] * max as f64 + carried[x + 1][c] + across[c];
+                        quantized[c] = round_sample(wanted, max);
+                        let error = wanted - quantized[c] as f64;
+                        across[c] = error * 7.0 / 16.0;
+                        self.errors[x][c] += error * 3.0 / 16.0;
+                        self.errors[x + 1][c] += error * 5.0 / 16.0;
+                        self.errors[x + 2][c] += error / 16.0;
+                    }
+                    samples.push(quantized);
+                }
+                samples
+            }
+        };
+        Some(samples)
+    }
+}
+
+/// rounds a sample that may have been nudged out of range back into it.
+fn round_sample(sample: f64, max: u32) -> u32 {
+    sample.round().max(0.0).min(max as f64) as u32
+}
+
+/// the longest line a PPM file should have, not counting the newline.
+const PPM_LINE_LENGTH: usize = 70;
+
+/// hands the samples of a plain PPM image to `write`, one line at a time, each with its
+/// newline. rows of the image are wrapped between samples so that no line is longer
+/// than the PPM format allows. the first error from `write` stops the writing.
+fn write_plain_samples<E, F>(canvas: &Canvas, options: PpmOptions, mut write: F) -> Result<(), E>
+where
+    F: FnMut(&str) -> Result<(), E>,
+{
+    let mut line = String::with_capacity(PPM_LINE_LENGTH + 1);
+    for row in options.rows(canvas) {
+        for samples in row.iter() {
+            for &sample in samples.iter() {
+                let sample = sample.to_string();
+                if !line.is_empty() && line.len() + 1 + sample.len() > PPM_LINE_LENGTH {
+                    line.push('\n');
+                    write(&line)?;
+                    line.clear();
+                }
+                if !line.is_empty() {
+                    line.push(' ');
+                }
+                line.push_str(&sample);
+            }
+        }
+        line.push('\n');
+        write(&line)?;
+        line.clear();
+    }
+
+    Ok(())
+}
+
+impl Display for Canvas {
+    /// writes the pixels as 8-bit plain PPM samples, without the header.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write_plain_samples(self, PpmOptions::default(), |line| f.write_str(line))
+    }
+}
+
+/// why a PPM image couldn't be read.
+#[derive(Debug)]
+pub enum PpmError {
+    /// the reader itself failed.
+    Io(io::Error),
+    /// the file doesn't start with P3 or P6, so it isn't a PPM image this can read.
+    UnknownFormat(String),
+    /// the file ended before the given part of the image.
+    MissingValue(&'static str),
+    /// the given part of the image isn't a number that makes sense there.
+    InvalidValue { what: &'static str, token: String },
+    /// the file ended after only `found` of the `expected` samples.
+    TooFewSamples { expected: usize, found: usize },
+}
+
+impl Display for PpmError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            PpmError::Io(error) => write!(f, "could not read the image: {}", error),
+            PpmError::UnknownFormat(magic) => {
+                write!(f, "expected a P3 or P6 image, found {:?}", magic)
+            }
+            PpmError::MissingValue(what) => write!(f, "the image ends before its {}", what),
+            PpmError::InvalidValue { what, token } => {
+                write!(f, "{:?} is not a valid {}", token, what)
+            }
+            PpmError::TooFewSamples { expected, found } => write!(
+                f,
+                "the image should have {} samples, but ends after {}",
+                expected, found
+            ),
+        }
+    }
+}
+
+impl Error for PpmError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PpmError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for PpmError {
+    fn from(error: io::Error) -> PpmError {
+        PpmError::Io(error)
+    }
+}
+
+impl Canvas {
+    /// the canvas as a plain PPM image with 8-bit samples, written as they are.
+    pub fn to_ppm(&self) -> String {
+        self.to_ppm_with(PpmOptions::default())
+    }
+
+    /// the canvas as a plain PPM image, written as `options` say.
+    pub fn to_ppm_with(&self, options: PpmOptions) -> String {
+        let mut ppm = Vec::new();
+        self.write_ppm_with(&mut ppm, options)
+            .expect("writing to memory cannot fail");
+        String::from_utf8(ppm).expect("PPM headers and samples are ascii")
+    }
+
+    /// the canvas as a raw PPM image, whose samples are stored as bytes rather than
+    /// text. 16-bit samples take two bytes each, most significant first.
+    pub fn to_raw_ppm(&self, options: PpmOptions) -> Vec<u8> {
+        let header = format!(
+            "P6\n{} {}\n{}\n",
+            self.width,
+            self.height,
+            options.depth.max()
+        );
+        let mut bytes = header.into_bytes();
+        for samples in options.rows(self).flatten() {
+            for &sample in samples.iter() {
+                match options.depth {
+                    BitDepth::Eight => bytes.push(sample as u8),
+                    BitDepth::Sixteen => bytes.extend_from_slice(&(sample as u16).to_be_bytes()),
+                }
+            }
+        }
+        bytes
+    }
+
+    /// streams the canvas as a plain PPM image with 8-bit samples to `writer`, a line
+    /// at a time, so that the whole image never has to be held as text. the first
+    /// error from the writer is returned as it was.
+    pub fn write_ppm<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.write_ppm_with(writer, PpmOptions::default())
+    }
+
+    /// like `write_ppm`, but written as `options` say.
+    pub fn write_ppm_with<W: Write>(&self, writer: &mut W, options: PpmOptions) -> io::Result<()> {
+        write!(
+            writer,
+            "P3\n{} {}\n{}\n",
+            self.width,
+            self.height,
+            options.depth.max()
+        )?;
+        write_plain_samples(self, options, |line| writer.write_all(line.as_bytes()))
+    }
+
+    /// reads a PPM image. comments and any amount of whitespace are allowed between
+    /// values, and samples are scaled by the image's maximum value, so that they run
+    /// from 0 to 1 whatever it is.
+    pub fn from_ppm<R: Read>(mut reader: R) -> Result<Canvas, PpmError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        let mut tokens = Tokens {
+            bytes: &bytes,
+            at: 0,
+        };
+
+        let magic = tokens.next().ok_or(PpmError::MissingValue("format"))?;
+        let raw = match magic {
+            "P3" => false,
+            "P6" => true,
+            _ => return Err(PpmError::UnknownFormat(magic.to_string())),
+        };
+        let width = tokens.number("width")?;
+        let height = tokens.number("height")?;
+        let max = tokens.number("maximum value")?;
+        if max == 0 || max > u16::MAX as usize {
+            return Err(PpmError::InvalidValue {
+                what: "maximum value",
+                token: max.to_string(),
+            });
+        }
+
+        let expected = width
+            .checked_mul(height)
+            .and_then(|pixels| pixels.checked_mul(3))
+            .ok_or_else(|| PpmError::InvalidValue {
+                what: "image size",
+                token: format!("{}x{}", width, height),
+            })?;
+        let samples = if raw {
+            tokens.raw_samples(expected, max)?
+        } else {
+            // every plain sample takes at least two bytes, a digit and a separator, so
+            // the header can't make this allocate more than the file could fill.
+            let mut samples = Vec::with_capacity(expected.min(bytes.len() / 2));
+            while samples.len() < expected {
+                match tokens.next() {
+                    Some(token) => samples.push(sample(token, max)?),
+                    None => {
+                        return Err(PpmError::TooFewSamples {
+                            expected,
+                            found: samples.len(),
+                        })
+                    }
+                }
+            }
+            samples
+        };
+
+        let scale = 1.0 / max as f64;
+        Ok(Canvas::from_fn(width, height, |x, y| {
+            let i = (x + y * width) * 3;
+            Color::new(
+                samples[i] as f64 * scale,
+                samples[i + 1] as f64 * scale,
+                samples[i + 2] as f64 * scale,
+            )
+        }))
+    }
+}
+
+/// parses a single plain sample, which can be no more than the maximum value.
+fn sample(token: &str, max: usize) -> Result<usize, PpmError> {
+    match token.parse() {
+        Ok(value) if value <= max => Ok(value),
+        _ => Err(PpmError::InvalidValue {
+            what: "sample",
+            token: token.to_string(),
+        }),
+    }
+}
+
+/// the whitespace-separated values of a PPM file, skipping comments.
+struct Tokens<'a> {
+    bytes: &'a [u8],
+    at: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn next(&mut self) -> Option<&'a str> {
+        // comments run from a # to the end of the line.
+        while let Some(&byte) = self.bytes.get(self.at) {
+            if byte == b'#' {
+                while self.bytes.get(self.at).is_some_and(|&byte| byte != b'\n') {
+                    self.at += 1;
+                }
+            } else if byte.is_ascii_whitespace() {
+                self.at += 1;
+            } else {
+                break;
+            }
+        }
+
+        let start = self.at;
+        while self
+            .bytes
+            .get(self.at)
+            .is_some_and(|byte| !byte.is_ascii_whitespace() && *byte != b'#')
+        {
+            self.at += 1;
+        }
+
+        if start == self.at {
+            None
+        } else {
+            // anything that isn't ascii can't be a number anyway.
+            Some(std::str::from_utf8(&self.bytes[start..self.at]).unwrap_or("\u{fffd}"))
+        }
+    }
+
+    fn number(&mut self, what: &'static str) -> Result<usize, PpmError> {
+        let token = self.next().ok_or(PpmError::MissingValue(what))?;
+        token.parse().map_err(|_| PpmError::InvalidValue {
+            what,
+            token: token.to_string(),
+        })
+    }
+
+    /// reads the binary samples of a P6 image, which follow a single whitespace byte
+    /// after the maximum value. they take two bytes each, most significant first, when
+    /// the maximum value doesn't fit in one.
+    fn raw_samples(&mut self, expected: usize, max: usize) -> Result<Vec<usize>, PpmError> {
+        let width = if max > u8::MAX as usize { 2 } else { 1 };
+        let data = self.bytes.get(self.at + 1..).unwrap_or(&[]);
+        if data.len() / width < expected {
+            return Err(PpmError::TooFewSamples {
+                expected,
+                found: data.len() / width,
+            });
+        }
+
+        data.chunks(width)
+            .take(expected)
+            .map(|chunk| {
+                let value = chunk
+                    .iter()
+                    .fold(0, |value, &byte| value << 8 | byte as usize);
+                if value <= max {
+                    Ok(value)
+                } else {
+                    Err(PpmError::InvalidValue {
+                        what: "sample",
+                        token: value.to_string(),
+                    })
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(ppm: &str) -> Result<Canvas, PpmError> {
+        Canvas::from_ppm(ppm.as_bytes())
+    }
+
+    fn sixteen_bits() -> PpmOptions {
+        PpmOptions {
+            depth: BitDepth::Sixteen,
+            ..PpmOptions::default()
+        }
+    }
+
+    #[test]
+    fn wrong_magic_number() {
+        let ppm = "P32\n1 1\n255\n0 0 0\n";
+        match parse(ppm) {
+            Err(PpmError::UnknownFormat(magic)) => assert_eq!(magic, "P32"),
+            other => panic!("expected an unknown format, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn canvas_size_from_header() {
+        let ppm = "P3\n10 2\n255\n".to_string() + &"0 0 0\n".repeat(20);
+        let canvas = parse(&ppm).unwrap();
+        assert_eq!(canvas.width, 10);
+        assert_eq!(canvas.height, 2);
+    }
+
+    #[test]
+    fn pixel_data() {
+        let ppm = "P3
+4 3
+255
+255 127 0  0 127 255  127 255 0  255 255 255
+0 0 0  255 0 0  0 255 0  0 0 255
+255 255 0  0 255 255  255 0 255  127 127 127
+";
+        let canvas = parse(ppm).unwrap();
+        let cases = [
+            ((0, 0), Color::new(1.0, 0.49804, 0.0)),
+            ((1, 0), Color::new(0.0, 0.49804, 1.0)),
+            ((2, 0), Color::new(0.49804, 1.0, 0.0)),
+            ((3, 0), Color::new(1.0, 1.0, 1.0)),
+            ((0, 1), Color::new(0.0, 0.0, 0.0)),
+            ((1, 1), Color::new(1.0, 0.0, 0.0)),
+            ((2, 1), Color::new(0.0, 1.0, 0.0)),
+            ((3, 1), Color::new(0.0, 0.0, 1.0)),
+            ((0, 2), Color::new(1.0, 1.0, 0.0)),
+            ((1, 2), Color::new(0.0, 1.0, 1.0)),
+            ((2, 2), Color::new(1.0, 0.0, 1.0)),
+            ((3, 2), Color::new(0.49804, 0.49804, 0.49804)),
+        ];
+        for &(pixel, color) in cases.iter() {
+            assert_eq!(canvas[pixel], color);
+        }
+    }
+
+    #[test]
+    fn comments_are_ignored() {
+        let ppm = "P3
+# this is a comment
+2 1
+# this, too
+255
+# another comment
+255 255 255
+# oh, no, comments in the pixel data!
+255 0 255
+";
+        let canvas = parse(ppm).unwrap();
+        assert_eq!(canvas[(0, 0)], Color::new(1.0, 1.0, 1.0));
+        assert_eq!(canvas[(1, 0)], Color::new(1.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn samples_can_span_lines() {
+        let ppm = "P3
+1 1
+255
+51
+153
+
+204
+";
+        let canvas = parse(ppm).unwrap();
+        assert_eq!(canvas[(0, 0)], Color::new(0.2, 0.6, 0.8));
+    }
+
+    #[test]
+    fn samples_are_scaled_by_the_maximum_value() {
+        let ppm = "P3
+2 2
+100
+100 100 100  50 50 50
+75 50 25  0 0 0
+";
+        let canvas = parse(ppm).unwrap();
+        assert_eq!(canvas[(0, 1)], Color::new(0.75, 0.5, 0.25));
+    }
+
+    #[test]
+    fn raw_pixel_data() {
+        let mut ppm = b"P6\n# raw\n2 1\n255\n".to_vec();
+        ppm.extend_from_slice(&[255, 0, 51, 0, 255, 204]);
+        let canvas = Canvas::from_ppm(&ppm[..]).unwrap();
+        assert_eq!(canvas[(0, 0)], Color::new(1.0, 0.0, 0.2));
+        assert_eq!(canvas[(1, 0)], Color::new(0.0, 1.0, 0.8));
+
+        let mut wide = b"P6 1 1 1000\n".to_vec();
+        wide.extend_from_slice(&[3, 232, 1, 244, 0, 0]);
+        let canvas = Canvas::from_ppm(&wide[..]).unwrap();
+        assert_eq!(canvas[(0, 0)], Color::new(1.0, 0.5, 0.0));
+    }
+
+    #[test]
+    fn malformed_images_are_errors() {
+        assert!(matches!(parse(""), Err(PpmError::MissingValue("format"))));
+        assert!(matches!(
+            parse("P3\n2 1\n"),
+            Err(PpmError::MissingValue("maximum value"))
+        ));
+        assert!(matches!(
+            parse("P3\n2 1\n255\n0 0 0 255\n"),
+            Err(PpmError::TooFewSamples {
+                expected: 6,
+                found: 4
+            })
+        ));
+        assert!(matches!(
+            parse("P3\n1 1\n255\n0 zero 0\n"),
+            Err(PpmError::InvalidValue { what: "sample", .. })
+        ));
+        assert!(matches!(
+            parse("P3\n1 1\n255\n0 256 0\n"),
+            Err(PpmError::InvalidValue { what: "sample", .. })
+        ));
+        assert!(matches!(
+            parse("P3\nwide 1\n255\n"),
+            Err(PpmError::InvalidValue { what: "width", .. })
+        ));
+        assert!(matches!(
+            parse("P3\n1 1\n0\n0 0 0\n"),
+            Err(PpmError::InvalidValue {
+                what: "maximum value",
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse("P3\n4294967296 4294967296\n255\n0 0 0"),
+            Err(PpmError::InvalidValue {
+                what: "image size",
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse("P3\n100000 100000\n255\n0 0 0"),
+            Err(PpmError::TooFewSamples {
+                expected: 30_000_000_000,
+                found: 3
+            })
+        ));
+        assert!(matches!(
+            Canvas::from_ppm(&b"P6 100000 100000 65535\n\x01\x02\x03"[..]),
+            Err(PpmError::TooFewSamples {
+                expected: 30_000_000_000,
+                found: 1
+            })
+        ));
+        assert!(matches!(
+            Canvas::from_ppm(&b"P6 2 1 255\n\x01\x02\x03"[..]),
+            Err(PpmError::TooFewSamples {
+                expected: 6,
+                found: 3
+            })
+        ));
+    }
+
+    #[test]
+    fn errors_describe_the_problem() {
+        let error = parse("P3\n1 1\n255\n0 zero 0\n").unwrap_err();
+        assert_eq!(error.to_string(), "\"zero\" is not a valid sample");
+    }
+
+    #[test]
+    fn eight_bits_by_default() {
+        let canvas = Canvas::from_fn(3, 2, |x, y| Color::new(x as f64 / 2.0, y as f64, 0.3));
+        assert_eq!(BitDepth::default(), BitDepth::Eight);
+        assert_eq!(canvas.to_ppm_with(PpmOptions::default()), canvas.to_ppm());
+        assert!(canvas
+            .to_ppm()
+            .starts_with("P3\n3 2\n255\n0 0 77 128 0 77 255 0 77\n"));
+    }
+
+    #[test]
+    fn sixteen_bit_plain_ppm() {
+        let canvas = Canvas::from_fn(2, 1, |x, _| Color::new(1.0, 0.5, x as f64 * 2.0));
+        let ppm = canvas.to_ppm_with(sixteen_bits());
+        assert_eq!(ppm, "P3\n2 1\n65535\n65535 32768 0 65535 32768 65535\n");
+
+        let read = parse(&ppm).unwrap();
+        assert!((read[(0, 0)].green() - 32768.0 / 65535.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn raw_ppm_samples() {
+        let canvas = Canvas::from_fn(1, 1, |_, _| Color::new(1.0, 0.5, 0.2));
+        assert_eq!(
+            canvas.to_raw_ppm(PpmOptions::default()),
+            b"P6\n1 1\n255\n\xff\x80\x33".to_vec()
+        );
+        // 0.5 and 0.2 of 65535 are 0x8000 and 0x3333, most significant byte first.
+        assert_eq!(
+            canvas.to_raw_ppm(sixteen_bits()),
+            b"P6\n1 1\n65535\n\xff\xff\x80\x00\x33\x33".to_vec()
+        );
+        let read = Canvas::from_ppm(&canvas.to_raw_ppm(sixteen_bits())[..]).unwrap();
+        assert_eq!(read[(0, 0)], canvas[(0, 0)]);
+    }
+
+    #[test]
+    fn encoded_samples() {
+        let canvas = Canvas::from_fn(3, 1, |x, _| Color::white() * (x as f64 / 2.0));
+        let with = |encoding| {
+            canvas.to_ppm_with(PpmOptions {
+                encoding,
+                ..PpmOptions::default()
+            })
+        };
+        assert_eq!(with(Encoding::Linear), canvas.to_ppm());
+        assert!(with(Encoding::Srgb).ends_with("0 0 0 188 188 188 255 255 255\n"));
+        assert!(with(Encoding::Gamma(2.0)).ends_with("0 0 0 180 180 180 255 255 255\n"));
+        assert_eq!(
+            canvas.to_raw_ppm(PpmOptions {
+                encoding: Encoding::Srgb,
+                ..PpmOptions::default()
+            })[11..],
+            [0, 0, 0, 188, 188, 188, 255, 255, 255]
+        );
+    }
+
+    /// how many different shades each column of a 1024 pixel wide gradient from black to
+    /// white comes out as, on average over its rows.
+    fn gradient_shades(dither: Dither) -> usize {
+        let gradient = Canvas::from_fn(1024, 8, |x, _| Color::white() * (x as f64 / 1023.0));
+        let options = PpmOptions {
+            dither,
+            ..PpmOptions::default()
+        };
+        let rows: Vec<Vec<[u32; 3]>> = options.rows(&gradient).collect();
+        let mut averages: Vec<u32> = (0..1024)
+            .map(|x| rows.iter().map(|row| row[x][0]).sum())
+            .collect();
+        averages.sort_unstable();
+        averages.dedup();
+        averages.len()
+    }
+
+    #[test]
+    fn dithering_breaks_up_bands() {
+        assert_eq!(gradient_shades(Dither::None), 256);
+        assert!(gradient_shades(Dither::Ordered) > 256);
+        assert!(gradient_shades(Dither::FloydSteinberg) > 256);
+    }
+
+    #[test]
+    fn dithering_keeps_flat_colors_on_average() {
+        let grey = Canvas::from_fn(16, 16, |_, _| Color::new(0.5, 0.25, 0.0));
+        for &dither in [Dither::Ordered, Dither::FloydSteinberg].iter() {
+            let options = PpmOptions {
+                dither,
+                ..PpmOptions::default()
+            };
+            let samples: Vec<[u32; 3]> = options.rows(&grey).flatten().collect();
+            let average =
+                |c: usize| samples.iter().map(|s| s[c] as f64).sum::<f64>() / samples.len() as f64;
+            assert!((average(0) - 127.5).abs() < 0.1, "{:?}", dither);
+            assert!((average(1) - 63.75).abs() < 0.1, "{:?}", dither);
+            assert_eq!(average(2), 0.0);
+            // neighbors differ, rather than the whole canvas rounding the same way.
+            assert_ne!(samples[0][0], samples[1][0]);
+        }
+    }
+
+    #[test]
+    fn dithering_does_not_change_the_canvas() {
+        let canvas = Canvas::from_fn(5, 5, |x, y| Color::new(x as f64 / 4.0, y as f64 / 4.0, 0.3));
+        let copy = canvas.clone();
+        canvas.to_ppm_with(PpmOptions {
+            dither: Dither::FloydSteinberg,
+            ..PpmOptions::default()
+        });
+        assert_eq!(canvas, copy);
+        assert_eq!(
+            canvas.to_ppm_with(PpmOptions {
+                dither: Dither::None,
+                ..PpmOptions::default()
+            }),
+            canvas.to_ppm()
+        );
+    }
+
+    #[test]
+    fn round_trip_through_ppm() {
+        let canvas = Canvas::from_fn(7, 5, |x, y| {
+            Color::new(x as f64 / 6.0, y as f64 / 4.0, ((x + y) % 2) as f64)
+        });
+        let read = parse(&canvas.to_ppm()).unwrap();
+        assert_eq!(read.width, 7);
+        assert_eq!(read.height, 5);
+        for y in 0..5 {
+            for x in 0..7 {
+                assert!(read[(x, y)].distance(&canvas[(x, y)]) < 1.0 / 255.0);
+            }
+        }
+        assert_eq!(read.to_ppm(), canvas.to_ppm());
+    }
+
+    /// a writer that accepts `room` bytes and then fails.
+    struct FullWriter {
+        room: usize,
+        written: Vec<u8>,
+    }
+
+    impl Write for FullWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.room == 0 {
+                return Err(io::Error::new(io::ErrorKind::WriteZero, "no room left"));
+            }
+            let len = buf.len().min(self.room);
+            self.room -= len;
+            self.written.extend_from_slice(&buf[..len]);
+            Ok(len)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn streamed_ppm_matches_to_ppm() {
+        let c = Canvas::from_fn(23, 4, |x, y| {
+            Color::new(x as f64 / 22.0, y as f64 / 3.0, 0.5)
+        });
+        let mut streamed = Vec::new();
+        c.write_ppm(&mut streamed).unwrap();
+        assert_eq!(streamed, c.to_ppm().into_bytes());
+
+        let mut streamed = Vec::new();
+        c.write_ppm_with(&mut streamed, sixteen_bits()).unwrap();
+        assert_eq!(streamed, c.to_ppm_with(sixteen_bits()).into_bytes());
+    }
+
+    #[test]
+    fn streaming_stops_at_the_first_error() {
+        let c = Canvas::new(30, 30);
+        for &room in [0, 5, 100].iter() {
+            let mut writer = FullWriter {
+                room,
+                written: vec![],
+            };
+            let error = c.write_ppm(&mut writer).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::WriteZero);
+            assert_eq!(writer.written, c.to_ppm().as_bytes()[..room]);
+        }
+    }
+}
@@ -0,0 +1,171 @@
+//! changing the size of a canvas.
+
+use crate::world::{Canvas, Color};
+
+/// how a resized canvas picks the color of each of its pixels from the original.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Filter {
+    /// the color of the closest pixel, so that enlarging by a whole number repeats
+    /// each pixel exactly.
+    Nearest,
+    /// a blend of the four closest pixels, weighted by how close each one is.
+    Bilinear,
+}
+
+impl Canvas {
+    /// a copy of the canvas stretched or squeezed to `width` by `height` pixels. past
+    /// the edges of the canvas, its edge pixels are repeated rather than wrapping round.
+    ///
+    /// panics if either side of either canvas is zero.
+    pub fn resized(&self, width: usize, height: usize, filter: Filter) -> Canvas {
+        assert!(
+            width > 0 && height > 0 && self.width > 0 && self.height > 0,
+            "cannot resize a {}x{} canvas to {}x{}",
+            self.width,
+            self.height,
+            width,
+            height
+        );
+
+        let alpha = self
+            .alpha_canvas()
+            .map(|alpha| alpha.resized(width, height, filter));
+        let scale_x = self.width as f64 / width as f64;
+        let scale_y = self.height as f64 / height as f64;
+        Canvas::from_fn(width, height, |x, y| {
+            // where the center of the new pixel falls on the old canvas.
+            let u = (x as f64 + 0.5) * scale_x;
+            let v = (y as f64 + 0.5) * scale_y;
+            match filter {
+                Filter::Nearest => self[(self.clamp_x(u.floor()), self.clamp_y(v.floor()))],
+                Filter::Bilinear => self.bilinear(u - 0.5, v - 0.5),
+            }
+        })
+        .with_alpha_canvas(alpha)
+    }
+
+    /// a canvas `factor` times smaller on each side, each of whose pixels is the average
+    /// of a `factor` by `factor` block of this one. this turns a render made at a
+    /// multiple of the wanted size into a smoothly anti-aliased image. when the sides
+    /// don't divide evenly, the blocks along the right and bottom edges are cut short.
+    ///
+    /// panics if `factor` is zero.
+    pub fn downsample(&self, factor: usize) -> Canvas {
+        assert!(factor > 0, "cannot downsample by a factor of 0");
+
+        let alpha = self.alpha_canvas().map(|alpha| alpha.downsample(factor));
+        let width = self.width.div_ceil(factor);
+        let height = self.height.div_ceil(factor);
+        Canvas::from_fn(width, height, |x, y| {
+            let mut total = Color::black();
+            let mut count = 0;
+            for j in y * factor..((y + 1) * factor).min(self.height) {
+                for i in x * factor..((x + 1) * factor).min(self.width) {
+                    total += self[(i, j)];
+                    count += 1;
+                }
+            }
+            total * (1.0 / count as f64)
+        })
+        .with_alpha_canvas(alpha)
+    }
+
+    /// blends the four pixels around the position (x, y), measured in pixels from the
+    /// center of the top left one.
+    fn bilinear(&self, x: f64, y: f64) -> Color {
+        let (left, top) = (x.floor(), y.floor());
+        let (tx, ty) = (x - left, y - top);
+        let (x0, x1) = (self.clamp_x(left), self.clamp_x(left + 1.0));
+        let (y0, y1) = (self.clamp_y(top), self.clamp_y(top + 1.0));
+
+        let upper = self[(x0, y0)] * (1.0 - tx) + self[(x1, y0)] * tx;
+        let lower = self[(x0, y1)] * (1.0 - tx) + self[(x1, y1)] * tx;
+        upper * (1.0 - ty) + lower * ty
+    }
+
+    fn clamp_x(&self, x: f64) -> usize {
+        x.max(0.0).min((self.width - 1) as f64) as usize
+    }
+
+    fn clamp_y(&self, y: f64) -> usize {
+        y.max(0.0).min((self.height - 1) as f64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> Canvas {
+        let colors = [
+            Color::new(1.0, 0.0, 0.0),
+            Color::new(0.0, 1.0, 0.0),
+            Color::new(0.0, 0.0, 1.0),
+            Color::new(1.0, 1.0, 1.0),
+        ];
+        Canvas::from_fn(2, 2, |x, y| colors[x + 2 * y])
+    }
+
+    #[test]
+    fn downsampling_averages_blocks() {
+        let small = setup().downsample(2);
+        assert_eq!((small.width, small.height), (1, 1));
+        assert_eq!(small[(0, 0)], Color::new(0.5, 0.5, 0.5));
+    }
+
+    #[test]
+    fn downsampling_cuts_edge_blocks_short() {
+        let c = Canvas::from_fn(3, 1, |x, _| Color::white() * x as f64);
+        let small = c.downsample(2);
+        assert_eq!((small.width, small.height), (2, 1));
+        assert_eq!(small[(0, 0)], Color::white() * 0.5);
+        assert_eq!(small[(1, 0)], Color::white() * 2.0);
+        assert_eq!(c.downsample(1), c);
+    }
+
+    #[test]
+    fn nearest_upscaling_repeats_pixels() {
+        let c = setup();
+        let big = c.resized(4, 4, Filter::Nearest);
+        for y in 0..4 {
+            for x in 0..4 {
+                assert_eq!(big[(x, y)], c[(x / 2, y / 2)]);
+            }
+        }
+        assert_eq!(big.resized(2, 2, Filter::Nearest), c);
+    }
+
+    #[test]
+    fn bilinear_midpoints_interpolate() {
+        let c = setup();
+        let big = c.resized(3, 3, Filter::Bilinear);
+        assert_eq!(big[(1, 1)], Color::new(0.5, 0.5, 0.5));
+        assert_eq!(big[(1, 0)], Color::new(0.5, 0.5, 0.0));
+        // the corners are past the centers of the corner pixels, so they clamp.
+        assert_eq!(big[(0, 0)], c[(0, 0)]);
+        assert_eq!(big[(2, 2)], c[(1, 1)]);
+        assert_eq!(c.resized(2, 2, Filter::Bilinear), c);
+    }
+
+    #[test]
+    fn resizing_keeps_alpha() {
+        let mut c = setup();
+        c.set_alpha(0, 0, 0.0);
+        assert_eq!(c.downsample(2).alpha(0, 0), 0.75);
+        assert_eq!(c.resized(4, 4, Filter::Nearest).alpha(1, 1), 0.0);
+        assert_eq!(c.resized(3, 3, Filter::Bilinear).alpha(1, 1), 0.75);
+        assert!(!setup().downsample(2).has_alpha());
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot resize a 2x2 canvas to 0x3")]
+    fn resizing_to_nothing_panics() {
+        setup().resized(0, 3, Filter::Nearest);
+    }
+
+    #[test]
+    #[should_panic]
+    fn downsampling_by_zero_panics() {
+        setup().downsample(0);
+    }
+}
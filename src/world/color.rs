@@ -1,17 +1,82 @@
 use std::{
+    convert::TryFrom,
     f64,
     fmt::{self, Display, Formatter},
     ops::{Add, AddAssign, Div, DivAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub, SubAssign},
 };
 
-use crate::math::{change_interval, clamp_between, Vector};
+use crate::{
+    math::{change_interval, clamp_between, Vector},
+    RayTracerError,
+};
 
 pub const MIN_COLOR: f64 = 0.0;
 pub const MAX_COLOR: f64 = 255.0;
 
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Color(Vector);
 
+/// how linear color channels are turned into the values stored in an image file.
+/// lighting and blending always work on linear colors. encoding only happens as an
+/// image is written, since most viewers expect the extra precision that nonlinear
+/// values give to dark colors.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Encoding {
+    /// the channels are stored as they are.
+    #[default]
+    Linear,
+    /// each channel is raised to the power of one over the given gamma.
+    Gamma(f64),
+    /// the piecewise curve from the sRGB standard, which is close to a gamma of 2.2
+    /// but straight near black.
+    Srgb,
+}
+
+/// how colors brighter than white are squeezed back into the range an image can hold.
+/// each channel is mapped on its own, so very bright colors drift towards white.
+/// tone mapping comes before encoding.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum ToneMap {
+    /// the colors are left alone, and anything over 1 is clipped when written.
+    #[default]
+    Identity,
+    /// brightens by the given number of stops, then clamps each channel to 0 to 1.
+    Exposure(f64),
+    /// erik reinhard's operator, `c / (1 + c)`, which leaves dark channels nearly as they
+    /// are and brings even the brightest ones below 1. with a white point, channels
+    /// that bright come out as exactly 1 instead.
+    Reinhard { white: Option<f64> },
+}
+
+impl ToneMap {
+    pub fn map(&self, channel: f64) -> f64 {
+        match self {
+            ToneMap::Identity => channel,
+            ToneMap::Exposure(stops) => clamp_between(channel * stops.exp2(), 0.0, 1.0),
+            ToneMap::Reinhard { white: None } => {
+                let channel = channel.max(0.0);
+                channel / (1.0 + channel)
+            }
+            ToneMap::Reinhard { white: Some(white) } => {
+                let channel = channel.max(0.0);
+                channel * (1.0 + channel / (white * white)) / (1.0 + channel)
+            }
+        }
+    }
+}
+
+impl Encoding {
+    pub fn encode(&self, channel: f64) -> f64 {
+        match self {
+            Encoding::Linear => channel,
+            Encoding::Gamma(gamma) => channel.max(0.0).powf(gamma.recip()),
+            Encoding::Srgb if channel <= 0.003_130_8 => 12.92 * channel.max(0.0),
+            Encoding::Srgb => 1.055 * channel.powf(1.0 / 2.4) - 0.055,
+        }
+    }
+}
+
 impl Color {
     pub fn new(r: f64, g: f64, b: f64) -> Color {
         Color(Vector::new(r, g, b))
@@ -40,33 +105,146 @@ impl Color {
     pub fn blue(&self) -> f64 {
         self.0[2]
     }
-}
 
-impl Display for Color {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{} {} {}",
-            change_interval(
-                clamp_between(self.red(), 0.0, 1.0),
-                (0.0, 1.0),
-                (MIN_COLOR, MAX_COLOR)
-            )
-            .round() as i64,
-            change_interval(
-                clamp_between(self.green(), 0.0, 1.0),
-                (0.0, 1.0),
-                (MIN_COLOR, MAX_COLOR)
-            )
-            .round() as i64,
+    /// checks that no channel is NaN or infinite.
+    pub fn is_finite(&self) -> bool {
+        self.red().is_finite() && self.green().is_finite() && self.blue().is_finite()
+    }
+
+    /// the channels as whole numbers from 0 to `max`, as written to an image file.
+    /// channels outside 0 to 1 are clamped.
+    pub fn quantize(&self, max: u32) -> (u32, u32, u32) {
+        let quantize = |channel: f64| {
             change_interval(
-                clamp_between(self.blue(), 0.0, 1.0),
+                clamp_between(channel, 0.0, 1.0),
                 (0.0, 1.0),
-                (MIN_COLOR, MAX_COLOR)
+                (MIN_COLOR, max as f64),
             )
-            .round() as i64,
+            .round() as u32
+        };
+        (
+            quantize(self.red()),
+            quantize(self.green()),
+            quantize(self.blue()),
+        )
+    }
+
+    /// the color with each channel tone mapped.
+    pub fn tone_mapped(&self, tone_map: ToneMap) -> Color {
+        Color::new(
+            tone_map.map(self.red()),
+            tone_map.map(self.green()),
+            tone_map.map(self.blue()),
         )
     }
+
+    /// the color with each channel encoded, ready to be quantized.
+    pub fn encoded(&self, encoding: Encoding) -> Color {
+        Color::new(
+            encoding.encode(self.red()),
+            encoding.encode(self.green()),
+            encoding.encode(self.blue()),
+        )
+    }
+
+    /// the color `t` of the way from this one to `other`, channel by channel. `t` isn't
+    /// clamped, so values outside of `[0, 1]` carry on past either color, and may leave
+    /// channels below 0 or above 1.
+    pub fn lerp(self, other: Color, t: f64) -> Color {
+        Color(self.0.lerp(other.0, t))
+    }
+
+    /// the average of `colors`, such as the samples taken within one pixel. there is
+    /// nothing to average in an empty slice, so it mixes to black.
+    pub fn mix(colors: &[Color]) -> Color {
+        if colors.is_empty() {
+            return Color::black();
+        }
+        colors
+            .iter()
+            .fold(Color::black(), |sum, &color| sum + color)
+            / colors.len() as f64
+    }
+
+    /// how far apart two colors are, as the largest difference in any one channel.
+    pub fn distance(&self, other: &Color) -> f64 {
+        (*self - *other).abs().max_component()
+    }
+
+    /// the smaller of each pair of channels.
+    pub fn min(&self, other: &Color) -> Color {
+        Color(self.0.min(&other.0))
+    }
+
+    /// the larger of each pair of channels.
+    pub fn max(&self, other: &Color) -> Color {
+        Color(self.0.max(&other.0))
+    }
+
+    /// the absolute value of each channel.
+    pub fn abs(&self) -> Color {
+        Color(self.0.abs())
+    }
+
+    /// each channel kept between the matching channels of `min` and `max`. where a
+    /// lower bound is above its upper bound, the upper bound wins, as in
+    /// `Vector::clamp`.
+    pub fn clamp(&self, min: &Color, max: &Color) -> Color {
+        Color(self.0.clamp(&min.0, &max.0))
+    }
+
+    /// the brightest of the three channels.
+    pub fn max_component(&self) -> f64 {
+        self.0.max_component()
+    }
+
+    /// the dimmest of the three channels.
+    pub fn min_component(&self) -> f64 {
+        self.0.min_component()
+    }
+}
+
+impl Display for Color {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let (r, g, b) = self.quantize(MAX_COLOR as u32);
+        write!(f, "{} {} {}", r, g, b)
+    }
+}
+
+/* conversions */
+
+impl From<[f64; 3]> for Color {
+    fn from(channels: [f64; 3]) -> Color {
+        Color(Vector::from(channels))
+    }
+}
+
+impl From<(f64, f64, f64)> for Color {
+    fn from(channels: (f64, f64, f64)) -> Color {
+        Color(Vector::from(channels))
+    }
+}
+
+impl From<Color> for [f64; 3] {
+    fn from(color: Color) -> [f64; 3] {
+        color.0.into()
+    }
+}
+
+impl From<Color> for (f64, f64, f64) {
+    fn from(color: Color) -> (f64, f64, f64) {
+        color.0.into()
+    }
+}
+
+impl TryFrom<&[f64]> for Color {
+    type Error = RayTracerError;
+
+    /// reads the red, green and blue channels in `numbers`, which mustn't hold any more
+    /// or fewer.
+    fn try_from(numbers: &[f64]) -> Result<Color, RayTracerError> {
+        Vector::try_from(numbers).map(Color)
+    }
 }
 
 /* indexing operations */
@@ -168,6 +346,58 @@ impl Mul for Color {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::math::EPSILON;
+
+    #[test]
+    fn converting_to_and_from_arrays_and_tuples() {
+        let c = Color::new(0.9, 0.6, 0.75);
+        assert_eq!(Color::from([0.9, 0.6, 0.75]), c);
+        assert_eq!(Color::from((0.9, 0.6, 0.75)), c);
+        assert_eq!(<[f64; 3]>::from(c), [0.9, 0.6, 0.75]);
+        assert_eq!(<(f64, f64, f64)>::from(c), (0.9, 0.6, 0.75));
+        assert_eq!(Color::try_from(&[0.9, 0.6, 0.75][..]).unwrap(), c);
+        assert!(Color::try_from(&[0.9, 0.6, 0.75, 1.0][..]).is_err());
+    }
+
+    #[test]
+    fn channel_wise_operations() {
+        let a = Color::new(-0.5, 1.5, 0.25);
+        let b = Color::new(0.0, 0.75, 2.0);
+        assert_eq!(a.min(&b), Color::new(-0.5, 0.75, 0.25));
+        assert_eq!(a.max(&b), Color::new(0.0, 1.5, 2.0));
+        assert_eq!(a.abs(), Color::new(0.5, 1.5, 0.25));
+        assert_eq!(
+            a.clamp(&Color::black(), &Color::white()),
+            Color::new(0.0, 1.0, 0.25)
+        );
+        assert_eq!(a.max_component(), 1.5);
+        assert_eq!(a.min_component(), -0.5);
+        assert_eq!(a.distance(&b), 1.75);
+    }
+
+    #[test]
+    fn interpolating_colors() {
+        let a = Color::new(0.2, 0.4, 1.0);
+        let b = Color::new(0.6, 0.4, 0.0);
+        assert_eq!(a.lerp(b, 0.0), a);
+        assert_eq!(a.lerp(b, 0.5), Color::new(0.4, 0.4, 0.5));
+        assert_eq!(a.lerp(b, 1.0), b);
+        // nothing is clamped, so going past the end can leave the usual range.
+        assert_eq!(a.lerp(b, 2.0), Color::new(1.0, 0.4, -1.0));
+    }
+
+    #[test]
+    fn mixing_colors() {
+        let samples = [
+            Color::new(1.0, 0.0, 0.0),
+            Color::new(0.0, 1.0, 0.0),
+            Color::new(0.5, 0.5, 0.0),
+            Color::new(0.5, 0.5, 2.0),
+        ];
+        assert_eq!(Color::mix(&samples), Color::new(0.5, 0.5, 0.5));
+        assert_eq!(Color::mix(&samples[..1]), samples[0]);
+        assert_eq!(Color::mix(&[]), Color::black());
+    }
 
     #[test]
     fn colors_are_vectors() {
@@ -197,6 +427,74 @@ mod tests {
         assert_eq!(c * 2.0, Color::new(0.4, 0.6, 0.8));
     }
 
+    #[test]
+    fn finite_colors() {
+        assert!(Color::new(-0.5, 0.4, 1.7).is_finite());
+        assert!(!Color::new(f64::NAN, 0.4, 1.7).is_finite());
+        assert!(!Color::new(0.0, f64::INFINITY, 0.0).is_finite());
+        assert!(!Color::new(0.0, 0.0, f64::NEG_INFINITY).is_finite());
+    }
+
+    #[test]
+    fn encodings_keep_black_and_white() {
+        for &encoding in [Encoding::Linear, Encoding::Gamma(2.2), Encoding::Srgb].iter() {
+            assert_eq!(encoding.encode(0.0), 0.0);
+            assert!((encoding.encode(1.0) - 1.0).abs() < EPSILON);
+            assert_eq!(Color::black().encoded(encoding).quantize(255), (0, 0, 0));
+            assert_eq!(
+                Color::white().encoded(encoding).quantize(255),
+                (255, 255, 255)
+            );
+        }
+    }
+
+    #[test]
+    fn srgb_encoding() {
+        let grey = Color::new(0.5, 0.5, 0.5).encoded(Encoding::Srgb);
+        assert_eq!(grey, Color::new(0.73536, 0.73536, 0.73536));
+        assert_eq!(grey.quantize(255), (188, 188, 188));
+        // near black the curve is a straight line.
+        assert!((Encoding::Srgb.encode(0.001) - 0.01292).abs() < EPSILON);
+        assert_eq!(Encoding::Gamma(2.0).encode(0.25), 0.5);
+    }
+
+    #[test]
+    fn reinhard_tone_mapping() {
+        let reinhard = ToneMap::Reinhard { white: None };
+        for &channel in [0.0, 0.01, 0.05, 0.1].iter() {
+            assert!((reinhard.map(channel) - channel).abs() <= 0.01);
+        }
+        let mut previous = 0.0;
+        for &channel in [1.0, 10.0, 1000.0, 1e9].iter() {
+            let mapped = reinhard.map(channel);
+            assert!(previous < mapped && mapped < 1.0);
+            previous = mapped;
+        }
+        assert_eq!(reinhard.map(1.0), 0.5);
+
+        let white = ToneMap::Reinhard { white: Some(4.0) };
+        assert!((white.map(4.0) - 1.0).abs() < EPSILON);
+        assert!((white.map(0.05) - 0.05).abs() <= 0.01);
+    }
+
+    #[test]
+    fn exposure_tone_mapping() {
+        let brighter = ToneMap::Exposure(1.0);
+        assert_eq!(brighter.map(0.25), 0.5);
+        assert_eq!(brighter.map(0.75), 1.0);
+        assert_eq!(brighter.map(-0.5), 0.0);
+        assert_eq!(ToneMap::Identity.map(7.5), 7.5);
+    }
+
+    #[test]
+    fn distance_between_colors() {
+        let c1 = Color::new(0.9, 0.6, 0.75);
+        let c2 = Color::new(0.7, 0.1, 0.85);
+        assert_eq!(c1.distance(&c1), 0.0);
+        assert!((c1.distance(&c2) - 0.5).abs() < 1e-12);
+        assert_eq!(c1.distance(&c2), c2.distance(&c1));
+    }
+
     #[test]
     fn multiply_two_colors() {
         let c1 = Color::new(1.0, 0.2, 0.4);
@@ -0,0 +1,313 @@
+use std::{f64::consts, sync::Arc};
+
+use crate::{
+    math::{clamp_between, Matrix, Point, Transformable, Vector},
+    world::{intersection::Computations, Pattern, Texture, Textured},
+};
+
+/// a pattern projected onto whatever surfaces it faces, like a slide projector.
+///
+/// in projector space, the decal shines along -z through the window where both x and
+/// y are between -1 and 1; `projector_transform` places, orients, and sizes that
+/// window in the world. a surface is fully covered while the angle between its normal
+/// and the direction back toward the projector is at most a right angle minus
+/// `fade_angle`, after which coverage falls off linearly until the surface is edge-on.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "SerializedDecal")
+)]
+pub struct Decal {
+    projector_transform: Matrix,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    projector_inverse: Matrix,
+    pub pattern: Pattern,
+    pub fade_angle: f64,
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct SerializedDecal {
+    projector_transform: Matrix,
+    pattern: Pattern,
+    fade_angle: f64,
+}
+
+#[cfg(feature = "serde")]
+impl From<SerializedDecal> for Decal {
+    fn from(serialized: SerializedDecal) -> Decal {
+        Decal::new(
+            serialized.projector_transform,
+            serialized.pattern,
+            serialized.fade_angle,
+        )
+    }
+}
+
+/// finds the inverse of a projector's transform. a transform that squashes the window
+/// flat has no inverse, so it is given one full of NaNs instead, which no point falls
+/// inside the window of, so the decal covers nothing.
+fn inverse_of(transform: Matrix) -> Matrix {
+    transform
+        .try_inverse()
+        .unwrap_or_else(|| Matrix::scaling(f64::NAN, f64::NAN, f64::NAN))
+}
+
+impl Decal {
+    pub fn new(projector_transform: Matrix, pattern: Pattern, fade_angle: f64) -> Decal {
+        Decal {
+            projector_transform,
+            projector_inverse: inverse_of(projector_transform),
+            pattern,
+            fade_angle,
+        }
+    }
+
+    pub fn projector_transform(&self) -> Matrix {
+        self.projector_transform
+    }
+
+    pub fn projector_inverse(&self) -> Matrix {
+        self.projector_inverse
+    }
+
+    pub fn debug_assert_consistent(&self) {
+        self.projector_transform
+            .debug_assert_inverse(&self.projector_inverse, "Decal");
+    }
+
+    /// how much of the decal covers the surface at `world_space_point`, from 0 (none)
+    /// to 1 (fully opaque).
+    pub fn coverage(&self, world_space_point: Point, surface_normal: Vector) -> f64 {
+        self.debug_assert_consistent();
+        let projector_space_point = self.projector_inverse * world_space_point;
+        let inside_window = projector_space_point[0].abs() <= 1.0
+            && projector_space_point[1].abs() <= 1.0
+            && projector_space_point[2] <= 0.0;
+
+        if !inside_window {
+            return 0.0;
+        }
+
+        let to_projector = self.projector_transform * Vector::new(0.0, 0.0, 1.0);
+        let angle = surface_normal.angle_between(&to_projector);
+        let remaining = consts::FRAC_PI_2 - angle;
+
+        if self.fade_angle <= 0.0 {
+            if remaining > 0.0 {
+                1.0
+            } else {
+                0.0
+            }
+        } else {
+            clamp_between(remaining / self.fade_angle, 0.0, 1.0)
+        }
+    }
+
+    /// blends the decal's color over the surface color of the shaded point. the
+    /// material is left untouched wherever the decal doesn't reach.
+    pub fn apply(&self, computations: &mut Computations) {
+        let coverage = self.coverage(computations.point, computations.surface_normal);
+
+        if coverage > 0.0 {
+            let surface = computations.material.color_at(computations.point);
+            let decal = self
+                .pattern
+                .color_at(self.projector_inverse * computations.point);
+            let blended = surface * (1.0 - coverage) + decal * coverage;
+            computations.material = Arc::new(
+                computations
+                    .material
+                    .with_texture(Texture::pattern(Pattern::solid(blended))),
+            );
+        }
+    }
+}
+
+/// transforming a decal moves its projector, replacing the old placement.
+impl Transformable for Decal {
+    fn transformed(self, transform: Matrix) -> Decal {
+        Decal {
+            projector_transform: transform,
+            projector_inverse: inverse_of(transform),
+            ..self
+        }
+    }
+
+    fn transform(&mut self, transform: Matrix) -> &mut Decal {
+        self.projector_transform = transform;
+        self.projector_inverse = inverse_of(transform);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::Color;
+    use crate::{
+        math::{Form, Geometry},
+        world::{light, Light, Material, Ray, World},
+    };
+
+    fn red() -> Color {
+        Color::new(1.0, 0.0, 0.0)
+    }
+
+    /// a projector hovering over the origin, pointing straight down, whose window
+    /// covers -2 <= x <= 2 and -1 <= z <= 1 on the floor.
+    fn downward_decal(fade_angle: f64) -> Decal {
+        Decal::new(
+            Matrix::translation(0.0, 5.0, 0.0)
+                * Matrix::rotation_x(-consts::FRAC_PI_2)
+                * Matrix::scaling(2.0, 1.0, 1.0),
+            Pattern::solid(red()),
+            fade_angle,
+        )
+    }
+
+    /// a white floor that is shaded using nothing but its own color.
+    fn unlit_floor_world() -> World {
+        let floor = Geometry::default()
+            .with_form(Form::Plane)
+            .with_material(Material::new(
+                Texture::pattern(Pattern::solid(Color::white())),
+                1.0,
+                0.0,
+                0.0,
+                200.0,
+            ));
+        let sun = Light::point(light::Point::new(
+            Point::new(0.0, 10.0, 0.0),
+            Color::white(),
+        ));
+        World::new(vec![floor], vec![sun])
+    }
+
+    fn floor_color(world: &World, x: f64, z: f64) -> Color {
+        world.cast_ray(Ray::new(Point::new(x, 1.0, z), Vector::new(0.0, -1.0, 0.0)))
+    }
+
+    #[test]
+    fn decal_colors_expected_rectangle() {
+        let mut w = unlit_floor_world();
+        w.decals.push(downward_decal(0.0));
+        assert_eq!(floor_color(&w, 0.0, 0.0), red());
+        assert_eq!(floor_color(&w, 1.9, 0.9), red());
+        assert_eq!(floor_color(&w, -1.9, -0.9), red());
+        assert_eq!(floor_color(&w, 2.1, 0.0), Color::white());
+        assert_eq!(floor_color(&w, 0.0, 1.1), Color::white());
+        assert_eq!(floor_color(&w, -1.0, -1.1), Color::white());
+    }
+
+    #[test]
+    fn moving_the_projector_keeps_its_inverse() {
+        let mut w = unlit_floor_world();
+        let mut decal = downward_decal(0.0);
+        let moved = Matrix::translation(10.0, 5.0, 0.0) * Matrix::rotation_x(-consts::FRAC_PI_2);
+        decal.transform(moved);
+        assert_eq!(decal.projector_transform(), moved);
+        assert_eq!(decal.projector_inverse(), moved.inverse());
+        w.decals.push(decal);
+        assert_eq!(floor_color(&w, 0.0, 0.0), Color::white());
+        assert_eq!(floor_color(&w, 10.0, 0.0), red());
+
+        let back = w.decals[0]
+            .clone()
+            .transformed(downward_decal(0.0).projector_transform());
+        assert_eq!(back, downward_decal(0.0));
+    }
+
+    #[test]
+    fn flattened_projectors_cover_nothing() {
+        let flat = Matrix::translation(0.0, 5.0, 0.0)
+            * Matrix::rotation_x(-consts::FRAC_PI_2)
+            * Matrix::scaling(2.0, 1.0, 0.0);
+        let mut w = unlit_floor_world();
+        w.decals.push(Decal::new(flat, Pattern::solid(red()), 0.0));
+        w.decals.push(downward_decal(0.0).transformed(flat));
+        let mut moved = downward_decal(0.0);
+        moved.transform(flat);
+        w.decals.push(moved);
+        assert_eq!(floor_color(&w, 0.0, 0.0), Color::white());
+    }
+
+    #[test]
+    fn surfaces_facing_away_are_unaffected() {
+        let mut w = unlit_floor_world();
+        // a projector underneath the floor, pointing up at its underside.
+        w.decals.push(Decal::new(
+            Matrix::translation(0.0, -5.0, 0.0) * Matrix::rotation_x(consts::FRAC_PI_2),
+            Pattern::solid(red()),
+            0.0,
+        ));
+        assert_eq!(floor_color(&w, 0.0, 0.0), Color::white());
+    }
+
+    #[test]
+    fn objects_behind_projector_are_unaffected() {
+        let mut w = unlit_floor_world();
+        // a projector under the floor, pointing down and away from it.
+        w.decals.push(Decal::new(
+            Matrix::translation(0.0, -5.0, 0.0) * Matrix::rotation_x(-consts::FRAC_PI_2),
+            Pattern::solid(red()),
+            0.0,
+        ));
+        assert_eq!(floor_color(&w, 0.0, 0.0), Color::white());
+    }
+
+    #[test]
+    fn fade_angle_feathers_coverage() {
+        let decal = downward_decal(consts::FRAC_PI_4);
+        let point = Point::zero();
+        let facing = Vector::new(0.0, 1.0, 0.0);
+        let tilted_30 = Vector::new(0.0, (consts::PI / 6.0).cos(), (consts::PI / 6.0).sin());
+        let tilted_60 = Vector::new(0.0, (consts::PI / 3.0).cos(), (consts::PI / 3.0).sin());
+        let edge_on = Vector::new(0.0, 0.0, 1.0);
+        assert_eq!(decal.coverage(point, facing), 1.0);
+        assert_eq!(decal.coverage(point, tilted_30), 1.0);
+        assert!((decal.coverage(point, tilted_60) - 2.0 / 3.0).abs() < 0.0001);
+        assert_eq!(decal.coverage(point, edge_on), 0.0);
+    }
+
+    #[test]
+    fn partial_coverage_blends_colors() {
+        let mut w = unlit_floor_world();
+        // tilt the projector 60 degrees away from straight down, and widen its window
+        // so that it still reaches the origin, leaving the floor only partly covered.
+        let decal = Decal::new(
+            Matrix::translation(0.0, 5.0, 0.0)
+                * Matrix::rotation_x(-consts::FRAC_PI_2)
+                * Matrix::rotation_x(consts::PI / 3.0)
+                * Matrix::scaling(100.0, 100.0, 1.0),
+            Pattern::solid(red()),
+            consts::FRAC_PI_4,
+        );
+        w.decals.push(decal);
+        let c = floor_color(&w, 0.0, 0.0);
+        let expected = Color::white() * (1.0 / 3.0) + red() * (2.0 / 3.0);
+        assert_eq!(c, expected);
+    }
+
+    #[test]
+    fn later_decals_layer_over_earlier_ones() {
+        let mut w = unlit_floor_world();
+        w.decals.push(downward_decal(0.0));
+        w.decals.push(Decal::new(
+            Matrix::translation(1.0, 5.0, 0.0) * Matrix::rotation_x(-consts::FRAC_PI_2),
+            Pattern::solid(Color::new(0.0, 0.0, 1.0)),
+            0.0,
+        ));
+        assert_eq!(floor_color(&w, -1.5, 0.0), red());
+        assert_eq!(floor_color(&w, 1.5, 0.0), Color::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn no_decals_leave_output_unchanged() {
+        let w = World::default();
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert!(w.decals.is_empty());
+        assert_eq!(w.cast_ray(r), Color::new(0.38066, 0.47583, 0.2855));
+    }
+}
@@ -1,6 +1,7 @@
 use std::{
     cmp::{Ordering, Reverse},
     collections::BinaryHeap,
+    sync::Arc,
 };
 
 use crate::{
@@ -8,52 +9,196 @@ use crate::{
     world::{Material, Ray},
 };
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Clone, Debug)]
 pub struct Computations {
+    /// exactly where the ray meets the surface.
     pub point: Point,
+    /// just above the surface, where shadow and reflection rays start from, so that
+    /// they don't hit the surface they leave.
+    pub over_point: Point,
+    /// just below the surface, where rays that pass through it start from.
+    pub under_point: Point,
     pub to_eye: Vector,
     pub surface_normal: Vector,
+    /// the direction the incoming ray bounces off the surface in.
+    pub reflect_v: Vector,
     pub is_inside: bool,
-    pub material: Material,
+    pub material: Arc<Material>,
+    /// the refractive index of the material the ray is leaving.
+    pub n1: f64,
+    /// the refractive index of the material the ray is entering.
+    pub n2: f64,
+    /// when the ray met the surface. rays that leave the surface are fired at the same
+    /// time, so that they see moving objects where this ray did.
+    pub time: f64,
 }
 
 impl Computations {
     pub fn new(intersection: &Intersection) -> Computations {
+        Computations::among(
+            intersection,
+            &Intersections::with(vec![intersection.clone()]),
+        )
+    }
+
+    /// like `new`, but works out which materials the ray passes between at the
+    /// intersection by walking `intersections`, all of which are along the same ray.
+    pub fn among(intersection: &Intersection, intersections: &Intersections) -> Computations {
         let point = intersection.ray.at(intersection.time);
         let to_eye = -intersection.ray.direction;
 
-        let mut surface_normal = intersection.object.normal_at(point).unwrap();
+        // a shape without a normal here has to be facing some way, so face the eye.
+        let mut surface_normal = intersection.normal().unwrap_or_else(|| to_eye.normalized());
         let mut is_inside = false;
         if surface_normal.dot(&to_eye) < 0.0 {
             is_inside = true;
             surface_normal = -surface_normal;
         }
 
+        let (n1, n2) = refractive_indices(intersection, intersections);
+
         Computations {
-            point: point + (surface_normal * EPSILON),
+            point,
+            over_point: point + (surface_normal * EPSILON),
+            under_point: point - (surface_normal * EPSILON),
             to_eye,
             surface_normal,
+            reflect_v: intersection.ray.direction.reflect_across(surface_normal),
             is_inside,
-            material: intersection.object.material,
+            material: intersection.object.material.clone(),
+            n1,
+            n2,
+            time: intersection.ray.time,
+        }
+    }
+
+    /// the fraction of the reflected light to mix in, with the rest of the mix going to
+    /// the refracted light, for surfaces that both reflect and let light through. other
+    /// surfaces give `None`, and have both added as they are.
+    pub fn reflectance(&self) -> Option<f64> {
+        if 0.0 < self.material.reflective && 0.0 < self.material.transparency {
+            Some(self.schlick())
+        } else {
+            None
+        }
+    }
+
+    /// the fraction of light the surface reflects rather than lets through, using
+    /// schlick's approximation of the fresnel equations.
+    pub fn schlick(&self) -> f64 {
+        let mut cos = self.to_eye.dot(&self.surface_normal);
+
+        if self.n1 > self.n2 {
+            let n_ratio = self.n1 / self.n2;
+            let sin2_t = n_ratio * n_ratio * (1.0 - cos * cos);
+            if 1.0 < sin2_t {
+                // total internal reflection.
+                return 1.0;
+            }
+            cos = (1.0 - sin2_t).sqrt();
+        }
+
+        let r0 = ((self.n1 - self.n2) / (self.n1 + self.n2)).powi(2);
+        r0 + (1.0 - r0) * (1.0 - cos).powi(5)
+    }
+}
+
+/// finds the refractive indices on either side of `hit`, innermost object first.
+///
+/// intersections behind the ray's origin are never kept, so an object whose first
+/// intersection is where the ray leaves it must already contain the origin.
+fn refractive_indices(hit: &Intersection, intersections: &Intersections) -> (f64, f64) {
+    let sorted = intersections.sorted();
+    let leaves = |intersection: &Intersection| {
+        intersection
+            .normal()
+            .map(|normal| 0.0 < normal.dot(&intersection.ray.direction))
+    };
+
+    let mut seen: Vec<&Geometry> = vec![];
+    let mut containers: Vec<&Geometry> = vec![];
+    for intersection in &sorted {
+        if !seen.contains(&&intersection.object) {
+            seen.push(&intersection.object);
+            if leaves(intersection) == Some(true) {
+                containers.insert(0, &intersection.object);
+            }
+        }
+    }
+
+    let index_of = |containers: &[&Geometry]| {
+        containers
+            .last()
+            .map_or(1.0, |object| object.material.refractive_index)
+    };
+
+    let mut n1 = 1.0;
+    for intersection in &sorted {
+        let is_hit = intersection.time == hit.time && intersection.object == hit.object;
+        if is_hit {
+            n1 = index_of(&containers);
+        }
+
+        match containers
+            .iter()
+            .position(|object| **object == intersection.object)
+        {
+            Some(position) => {
+                containers.remove(position);
+            }
+            None => containers.push(&intersection.object),
+        }
+
+        if is_hit {
+            return (n1, index_of(&containers));
         }
     }
+
+    (n1, n1)
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Clone, Debug)]
 pub struct Intersection {
     pub time: f64,
     pub ray: Ray,
     pub object: Geometry,
+    /// where on the surface the ray crossed it, for shapes that blend their normals
+    /// across the surface. for a triangle, these are how far the crossing is towards
+    /// its second and third corners.
+    pub uv: Option<(f64, f64)>,
 }
 
 impl Intersection {
     pub fn new(time: f64, ray: Ray, object: Geometry) -> Intersection {
-        Intersection { time, ray, object }
+        Intersection {
+            time,
+            ray,
+            object,
+            uv: None,
+        }
+    }
+
+    /// an intersection that remembers where on the surface it is.
+    pub fn with_uv(time: f64, ray: Ray, object: Geometry, u: f64, v: f64) -> Intersection {
+        Intersection {
+            uv: Some((u, v)),
+            ..Intersection::new(time, ray, object)
+        }
+    }
+
+    /// the normal of the surface where the ray crosses it.
+    pub fn normal(&self) -> Option<Vector> {
+        self.object.normal_at_uv(self.ray.at(self.time), self.uv)
     }
 
     pub fn compute(&self) -> Computations {
         Computations::new(self)
     }
+
+    /// like `compute`, but knows about every other intersection along the ray.
+    pub fn compute_among(&self, intersections: &Intersections) -> Computations {
+        Computations::among(self, intersections)
+    }
 }
 
 /// HACK: this would imply that two different intersections are equal
@@ -71,13 +216,15 @@ impl Eq for Intersection {}
 
 impl PartialOrd for Intersection {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.time.partial_cmp(&other.time)
+        Some(self.cmp(other))
     }
 }
 
+/// intersections are ordered by time. NaN times, which only a broken transform can
+/// produce, are given a fixed place in that order rather than panicking.
 impl Ord for Intersection {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.time.partial_cmp(&other.time).unwrap()
+        self.time.total_cmp(&other.time)
     }
 }
 
@@ -116,11 +263,20 @@ impl Intersections {
     }
 
     pub fn closest(&self) -> Option<Intersection> {
-        if let Some(&Reverse(intersection)) = self.heap.peek() {
-            Some(intersection)
-        } else {
-            None
-        }
+        self.heap
+            .peek()
+            .map(|Reverse(intersection)| intersection.clone())
+    }
+
+    /// every intersection, from the closest to the furthest.
+    pub fn sorted(&self) -> Vec<Intersection> {
+        let mut sorted: Vec<Intersection> = self
+            .heap
+            .iter()
+            .map(|Reverse(intersection)| intersection.clone())
+            .collect();
+        sorted.sort();
+        sorted
     }
 
     pub fn count(&self) -> usize {
@@ -137,26 +293,46 @@ impl Intersections {
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison, clippy::useless_conversion)]
 mod tests {
     use super::*;
     use crate::math::{Form, Geometry, Matrix, Point, Transformable, Vector};
+    use crate::world::Material;
+
+    #[test]
+    fn shape_without_normal_faces_eye() {
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 2.0));
+        let i = Intersection::new(1.0, r, Geometry::default());
+        let comps = i.compute();
+        assert_eq!(comps.surface_normal, Vector::new(0.0, 0.0, -1.0));
+        assert!(!comps.is_inside);
+    }
 
     #[test]
     fn intersection_encapsulates_object() {
         let s = Geometry::default().with_form(Form::Sphere);
         let r = Ray::new(Point::zero(), Vector::zero());
-        let i = Intersection::new(3.5, r, s);
+        let i = Intersection::new(3.5, r, s.clone());
         assert_eq!(i.time, 3.5);
         assert_eq!(i.object, s);
     }
 
+    #[test]
+    fn ordering_intersections_with_nan_times() {
+        let r = Ray::new(Point::zero(), Vector::new(0.0, 0.0, 1.0));
+        let at = |time| Intersection::new(time, r, Geometry::default());
+        assert_eq!(at(f64::NAN).cmp(&at(1.0)), Ordering::Greater);
+        assert_eq!(at(1.0).cmp(&at(f64::NAN)), Ordering::Less);
+        assert_eq!(at(1.0).cmp(&at(2.0)), Ordering::Less);
+    }
+
     #[test]
     fn aggregating_intersections() {
         let s = Geometry::default().with_form(Form::Sphere);
         let r = Ray::new(Point::zero(), Vector::zero());
-        let i1 = Intersection::new(1.0, r, s);
-        let i2 = Intersection::new(2.0, r, s);
-        let mut xs = Intersections::with(vec![i2, i1]);
+        let i1 = Intersection::new(1.0, r, s.clone());
+        let i2 = Intersection::new(2.0, r, s.clone());
+        let mut xs = Intersections::with(vec![i2.clone(), i1.clone()]);
         assert_eq!(xs.count(), 2);
         assert_eq!(xs.pop().unwrap().time, i1.time);
         assert_eq!(xs.pop().unwrap().time, i2.time);
@@ -176,9 +352,9 @@ mod tests {
     fn closest_hit_multiple_options() {
         let s = Geometry::default().with_form(Form::Sphere);
         let r = Ray::new(Point::zero(), Vector::zero());
-        let i1 = Intersection::new(1.0, r, s);
-        let i2 = Intersection::new(2.0, r, s);
-        let xs = Intersections::with(vec![i1, i2]);
+        let i1 = Intersection::new(1.0, r, s.clone());
+        let i2 = Intersection::new(2.0, r, s.clone());
+        let xs = Intersections::with(vec![i1.clone(), i2.clone()]);
         assert_eq!(xs.closest().unwrap(), i1);
     }
 
@@ -186,9 +362,9 @@ mod tests {
     fn closest_hit_one_option() {
         let s = Geometry::default().with_form(Form::Sphere);
         let r = Ray::new(Point::zero(), Vector::zero());
-        let i1 = Intersection::new(-1.0, r, s);
-        let i2 = Intersection::new(1.0, r, s);
-        let xs = Intersections::with(vec![i1, i2]);
+        let i1 = Intersection::new(-1.0, r, s.clone());
+        let i2 = Intersection::new(1.0, r, s.clone());
+        let xs = Intersections::with(vec![i1.clone(), i2.clone()]);
         assert_eq!(xs.closest().unwrap(), i2);
     }
 
@@ -196,21 +372,32 @@ mod tests {
     fn closest_hit_no_options() {
         let s = Geometry::default().with_form(Form::Sphere);
         let r = Ray::new(Point::zero(), Vector::zero());
-        let i1 = Intersection::new(-1.0, r, s);
-        let i2 = Intersection::new(-2.0, r, s);
-        let xs = Intersections::with(vec![i1, i2]);
+        let i1 = Intersection::new(-1.0, r, s.clone());
+        let i2 = Intersection::new(-2.0, r, s.clone());
+        let xs = Intersections::with(vec![i1.clone(), i2.clone()]);
         assert!(xs.closest().is_none());
     }
 
+    #[test]
+    fn nan_intersections_are_dropped() {
+        let s = Geometry::default().with_form(Form::Sphere);
+        let r = Ray::new(Point::zero(), Vector::zero());
+        let i1 = Intersection::new(f64::NAN, r, s.clone());
+        let i2 = Intersection::new(2.0, r, s.clone());
+        let xs = Intersections::with(vec![i1.clone(), i2.clone()]);
+        assert_eq!(xs.count(), 1);
+        assert_eq!(xs.closest().unwrap(), i2);
+    }
+
     #[test]
     fn closest_hit_has_lowest_nonnegative_time() {
         let s = Geometry::default().with_form(Form::Sphere);
         let r = Ray::new(Point::zero(), Vector::zero());
-        let i1 = Intersection::new(5.0, r, s);
-        let i2 = Intersection::new(7.0, r, s);
-        let i3 = Intersection::new(-3.0, r, s);
-        let i4 = Intersection::new(2.0, r, s);
-        let xs = Intersections::with(vec![i1, i2, i3, i4]);
+        let i1 = Intersection::new(5.0, r, s.clone());
+        let i2 = Intersection::new(7.0, r, s.clone());
+        let i3 = Intersection::new(-3.0, r, s.clone());
+        let i4 = Intersection::new(2.0, r, s.clone());
+        let xs = Intersections::with(vec![i1.clone(), i2.clone(), i3.clone(), i4.clone()]);
         assert_eq!(xs.closest().unwrap(), i4);
     }
 
@@ -246,6 +433,117 @@ mod tests {
         assert_eq!(comps.surface_normal, Vector::new(0.0, 0.0, -1.0));
     }
 
+    #[test]
+    fn reflection_vector() {
+        let r = Ray::new(
+            Point::new(0.0, 1.0, -1.0),
+            Vector::new(
+                0.0,
+                -f64::from(2.0).sqrt() / 2.0,
+                f64::from(2.0).sqrt() / 2.0,
+            ),
+        );
+        let i = Intersection::new(
+            f64::from(2.0).sqrt(),
+            r,
+            Geometry::default().with_form(Form::Plane),
+        );
+        let comps = i.compute();
+        assert_eq!(
+            comps.reflect_v,
+            Vector::new(
+                0.0,
+                f64::from(2.0).sqrt() / 2.0,
+                f64::from(2.0).sqrt() / 2.0
+            )
+        );
+    }
+
+    fn glass_sphere() -> Geometry {
+        Geometry::default().with_form(Form::Sphere).with_material(
+            Material::default()
+                .with_transparency(1.0)
+                .with_refractive_index(1.5),
+        )
+    }
+
+    #[test]
+    fn refractive_indices_at_various_intersections() {
+        let a = glass_sphere().transformed(Matrix::scaling(2.0, 2.0, 2.0));
+        let b = glass_sphere()
+            .with_material(Material::default().with_refractive_index(2.0))
+            .transformed(Matrix::translation(0.0, 0.0, -0.25));
+        let c = glass_sphere()
+            .with_material(Material::default().with_refractive_index(2.5))
+            .transformed(Matrix::translation(0.0, 0.0, 0.25));
+        let r = Ray::new(Point::new(0.0, 0.0, -4.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = Intersections::with(vec![
+            Intersection::new(2.0, r, a.clone()),
+            Intersection::new(2.75, r, b.clone()),
+            Intersection::new(3.25, r, c.clone()),
+            Intersection::new(4.75, r, b),
+            Intersection::new(5.25, r, c),
+            Intersection::new(6.0, r, a),
+        ]);
+        let expected = [
+            (1.0, 1.5),
+            (1.5, 2.0),
+            (2.0, 2.5),
+            (2.5, 2.5),
+            (2.5, 1.5),
+            (1.5, 1.0),
+        ];
+        for (i, (n1, n2)) in xs.sorted().iter().zip(expected.iter()) {
+            let comps = i.compute_among(&xs);
+            assert_eq!((comps.n1, comps.n2), (*n1, *n2));
+        }
+    }
+
+    #[test]
+    fn ray_starting_inside_glass_leaves_it() {
+        let r = Ray::new(Point::zero(), Vector::new(0.0, 0.0, 1.0));
+        let xs = glass_sphere().hit(r).unwrap();
+        let comps = xs.closest().unwrap().compute_among(&xs);
+        assert_eq!((comps.n1, comps.n2), (1.5, 1.0));
+    }
+
+    #[test]
+    fn schlick_under_total_internal_reflection() {
+        let half = f64::from(2.0).sqrt() / 2.0;
+        let r = Ray::new(Point::new(0.0, 0.0, half), Vector::new(0.0, 1.0, 0.0));
+        let xs = glass_sphere().hit(r).unwrap();
+        let comps = xs.closest().unwrap().compute_among(&xs);
+        assert_eq!(comps.schlick(), 1.0);
+    }
+
+    #[test]
+    fn schlick_with_perpendicular_ray() {
+        let r = Ray::new(Point::zero(), Vector::new(0.0, 1.0, 0.0));
+        let xs = glass_sphere().hit(r).unwrap();
+        let comps = xs.closest().unwrap().compute_among(&xs);
+        assert!((comps.schlick() - 0.04).abs() < EPSILON);
+    }
+
+    #[test]
+    fn schlick_with_small_angle_and_larger_n2() {
+        let r = Ray::new(Point::new(0.0, 0.99, -2.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = Intersections::with(vec![Intersection::new(1.8589, r, glass_sphere())]);
+        let comps = xs.closest().unwrap().compute_among(&xs);
+        assert!((comps.schlick() - 0.48873).abs() < EPSILON);
+    }
+
+    #[test]
+    fn under_point_is_below_surface() {
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let shape = glass_sphere().transformed(Matrix::translation(0.0, 0.0, 1.0));
+        let i = Intersection::new(5.0, r, shape);
+        let xs = Intersections::with(vec![i.clone()]);
+        let comps = i.compute_among(&xs);
+        assert!(comps.under_point[2] > EPSILON / 2.0);
+        assert!(comps.point[2] < comps.under_point[2]);
+        assert!(comps.over_point[2] < comps.point[2]);
+    }
+
     #[test]
     fn intersection_offsets_point() {
         let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
@@ -254,6 +552,7 @@ mod tests {
             .transformed(Matrix::translation(0.0, 0.0, 1.0));
         let i = Intersection::new(5.0, r, shape);
         let comps = i.compute();
-        assert!(comps.point[2] < (-EPSILON / 2.0));
+        assert!(comps.over_point[2] < (-EPSILON / 2.0));
+        assert!(comps.over_point[2] < comps.point[2]);
     }
 }
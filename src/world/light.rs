@@ -1,71 +1,252 @@
 use crate::{
-    math,
-    world::{intersection::Computations, Color, Textured, World},
+    math::{self, Geometry},
+    world::{intersection::Computations, stats::RayCounts, Color, Ray, Textured, World},
 };
 
+pub mod area;
+pub use area::{Area, Jitter};
+
 pub mod point;
 pub use point::Point;
 
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Light {
+    Area(Area),
     Point(Point),
 }
 
 impl Light {
+    pub fn area(area: Area) -> Light {
+        Self::Area(area)
+    }
+
     pub fn point(point: Point) -> Light {
         Self::Point(point)
     }
 
+    /// where the light shines from, or its middle if it isn't a single point.
+    pub fn center(&self) -> math::Point {
+        match self {
+            Self::Area(area) => area.center(),
+            Self::Point(p) => p.position,
+        }
+    }
+
+    /// the color of the light when it arrives at `point`, ignoring anything in the way.
+    pub fn color_at(&self, point: math::Point) -> Color {
+        match self {
+            Self::Area(area) => area.color,
+            Self::Point(p) => p.intensity_at(point),
+        }
+    }
+
+    /// the irradiance, in watts per square meter, that a physical light gives a
+    /// surface at `point` that faces it.
+    pub fn irradiance_at(&self, point: math::Point) -> Option<f64> {
+        match self {
+            Self::Area(_) => None,
+            Self::Point(p) => p.irradiance_at(point),
+        }
+    }
+
+    /// the diffuse and specular light this light gives the surface described by
+    /// `computations`. ambient light doesn't come from any one light, so it is left to
+    /// `World::shade`.
     pub fn illuminate(&self, world: &World, computations: &Computations) -> Color {
-        let variant = match self {
-            Self::Point(point) => point,
-        };
-
-        // combine the surface color with the light's color with respect to its intensity
-        let effective_color = computations.material.color_at(computations.point) * variant.color;
-        // find the direction to the light source
-        let to_light = (variant.position - computations.point).normalized();
-        // compute the ambient contribution
-        let ambient = effective_color * computations.material.ambient;
-        // light_dot_normal represents the cosine of the angle between the
-        // light vector and the normal vector. a negative number means the
-        // light is on the other side of the surface.
-        let light_dot_normal = to_light.dot(&computations.surface_normal);
-
-        let (diffuse, specular) = if light_dot_normal >= 0.0 {
-            // compute the diffuse contribution
-            let diffuse = effective_color * computations.material.diffuse * light_dot_normal;
-            // reflect_dot_eye represents the cosine of the angle between the
-            // reflection vector and the eye vector. a negative number means the
-            // light reflects away from the eye.
-            let reflected_light = (-to_light).reflect_across(computations.surface_normal);
-            let reflect_dot_eye = reflected_light.dot(&computations.to_eye);
-            if reflect_dot_eye <= 0.0 {
-                (diffuse, Color::new(0.0, 0.0, 0.0))
-            } else {
-                // compute the specular contribution
-                let factor = reflect_dot_eye.powf(computations.material.shininess);
-                (
-                    diffuse,
-                    variant.color * computations.material.specular * factor,
+        let illumination = self.illumination(world, computations);
+        illumination.diffuse + illumination.specular
+    }
+
+    /// like `illuminate`, but keeps the diffuse and specular light apart, along with
+    /// how much of the light got past whatever is in the way.
+    pub fn illumination(&self, world: &World, computations: &Computations) -> Illumination {
+        let shadows = world.settings.shadows;
+        match self {
+            Self::Area(area) => {
+                // light the surface from each sample on the light, and average them.
+                let samples = area.samples(computations.point);
+                let mut total = Illumination::dark();
+                for position in &samples {
+                    let transmission = if shadows {
+                        transmission_between(
+                            world,
+                            computations.over_point,
+                            *position,
+                            computations.time,
+                        )
+                    } else {
+                        Color::white()
+                    };
+                    let sample = illuminate_from(computations, *position, area.color, transmission);
+                    total.diffuse += sample.diffuse;
+                    total.specular += sample.specular;
+                    total.transmission += sample.transmission;
+                }
+                let count = samples.len() as f64;
+                Illumination {
+                    diffuse: total.diffuse / count,
+                    specular: total.specular / count,
+                    transmission: total.transmission / count,
+                }
+            }
+            Self::Point(point) => {
+                let transmission = if shadows {
+                    point.transmission_to(world, computations.over_point, computations.time)
+                } else {
+                    Color::white()
+                };
+                illuminate_from(
+                    computations,
+                    point.position,
+                    point.intensity_at(computations.point),
+                    transmission,
                 )
             }
-        } else {
-            (Color::new(0.0, 0.0, 0.0), Color::new(0.0, 0.0, 0.0))
-        };
-
-        if variant.casts_shade(world, computations.point) {
-            // the point is in the shadow cast by this light
-            ambient
-        } else {
-            // add the three contributions together to get the final shading
-            ambient + diffuse + specular
         }
     }
 
     pub fn casts_shade(&self, world: &World, point: math::Point) -> bool {
         match self {
+            Self::Area(area) => area.intensity_at(world, point) == 0.0,
             Self::Point(p) => p.casts_shade(world, point),
         }
     }
+
+    /// the fraction of the light that isn't blocked from reaching `point`, from 0 (in
+    /// full shadow) to 1 (not shadowed at all).
+    pub fn intensity_at(&self, world: &World, point: math::Point) -> f64 {
+        match self {
+            Self::Area(area) => area.intensity_at(world, point),
+            Self::Point(p) => p.visibility_at(world, point),
+        }
+    }
+
+    /// how much of the light makes it to `point` at `time`. see
+    /// `light::transmission_between`.
+    pub fn transmission_to(&self, world: &World, point: math::Point, time: f64) -> Color {
+        match self {
+            Self::Area(area) => area.transmission_to(world, point, time),
+            Self::Point(p) => p.transmission_to(world, point, time),
+        }
+    }
+}
+
+/// the light one light gives a surface, split into its parts.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Illumination {
+    /// the light scattered evenly in every direction, already dimmed by `transmission`.
+    pub diffuse: Color,
+    /// the highlight reflected towards the eye, already dimmed by `transmission`.
+    pub specular: Color,
+    /// how much of the light made it past whatever is in the way, from black in full
+    /// shadow to white when nothing is in the way.
+    pub transmission: Color,
+}
+
+impl Illumination {
+    /// no light at all, as in full shadow.
+    fn dark() -> Illumination {
+        Illumination {
+            diffuse: Color::black(),
+            specular: Color::black(),
+            transmission: Color::black(),
+        }
+    }
+}
+
+/// the diffuse and specular light given to the surface described by `computations`
+/// by light of color `intensity` coming from `position`, of which `transmission` makes
+/// it past whatever is in the way.
+fn illuminate_from(
+    computations: &Computations,
+    position: math::Point,
+    intensity: Color,
+    transmission: Color,
+) -> Illumination {
+    // combine the surface color with the light's color with respect to its intensity
+    let effective_color = computations.material.color_at(computations.point) * intensity;
+    // find the direction to the light source
+    let to_light = (position - computations.point).normalized();
+    // light_dot_normal represents the cosine of the angle between the
+    // light vector and the normal vector. a negative number means the
+    // light is on the other side of the surface.
+    let light_dot_normal = to_light.dot(&computations.surface_normal);
+
+    let (diffuse, specular) = if light_dot_normal >= 0.0 {
+        // compute the diffuse contribution
+        let diffuse = effective_color * computations.material.diffuse * light_dot_normal;
+        // reflect_dot_eye represents the cosine of the angle between the
+        // reflection vector and the eye vector. a negative number means the
+        // light reflects away from the eye.
+        let reflected_light = (-to_light).reflect_across(computations.surface_normal);
+        let reflect_dot_eye = reflected_light.dot(&computations.to_eye);
+        // written so that a NaN cosine falls through to the black case rather
+        // than being raised to a power.
+        if reflect_dot_eye > 0.0 {
+            // compute the specular contribution
+            let factor = reflect_dot_eye.powf(computations.material.shininess);
+            (diffuse, intensity * computations.material.specular * factor)
+        } else {
+            (diffuse, Color::new(0.0, 0.0, 0.0))
+        }
+    } else {
+        (Color::new(0.0, 0.0, 0.0), Color::new(0.0, 0.0, 0.0))
+    };
+
+    if transmission == Color::black() {
+        // the point is in the shadow cast by this light
+        Illumination::dark()
+    } else {
+        // dim the light by whatever it passed through to get here
+        Illumination {
+            diffuse: diffuse * transmission,
+            specular: specular * transmission,
+            transmission,
+        }
+    }
+}
+
+/// how much of the light leaving `position` makes it to `point`, after passing through
+/// everything in the way. each object it passes through lets through its transparency,
+/// tinted by its color, so white means nothing is in the way and black means fully
+/// shadowed. moving objects are caught where they are at `time`. with the world's
+/// `opaque_shadows` setting, anything in the way shadows the point completely.
+fn transmission_between(
+    world: &World,
+    point: math::Point,
+    position: math::Point,
+    time: f64,
+) -> Color {
+    let to_light = position - point;
+    let distance = to_light.magnitude();
+    let direction = to_light.normalized();
+    let ray_to_light = Ray::new(point, direction).with_time(time);
+    RayCounts::count_shadow();
+
+    let mut transmission = Color::white();
+    if let Some(intersections) = world.hit(ray_to_light) {
+        let mut blockers: Vec<Geometry> = vec![];
+        for intersection in intersections.sorted() {
+            if distance <= intersection.time {
+                break;
+            }
+            // light passing through an object crosses its surface twice, but is
+            // only dimmed by it once.
+            if blockers.contains(&intersection.object) {
+                continue;
+            }
+
+            let object = intersection.object;
+            let transparency = object.material.transparency;
+            if transparency == 0.0 || world.settings.opaque_shadows {
+                return Color::black();
+            }
+            transmission =
+                transmission * object.color_at(ray_to_light.at(intersection.time)) * transparency;
+            blockers.push(object);
+        }
+    }
+
+    transmission
 }
@@ -0,0 +1,251 @@
+use crate::{
+    math::{self, Vector, XorShift},
+    world::{Color, World},
+};
+
+/// where on each cell of an area light its sample is taken from.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Jitter {
+    /// always the middle of the cell, which gives banded shadows but is the same for
+    /// every point.
+    Centered,
+    /// somewhere random in the cell, picked by the seed and the point being lit, which
+    /// trades the bands for noise.
+    Seeded(u64),
+}
+
+/// a flat, rectangular light, which casts soft shadows.
+///
+/// the rectangle is split into a grid of cells, and each point is lit by one sample
+/// from each cell, so that a point that can see only part of the light is only partly
+/// lit.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Area {
+    pub corner: math::Point,
+    /// one cell's edge along the light's first side.
+    pub u_step: Vector,
+    pub u_steps: usize,
+    /// one cell's edge along the light's second side.
+    pub v_step: Vector,
+    pub v_steps: usize,
+    pub color: Color,
+    pub jitter: Jitter,
+}
+
+impl Area {
+    /// a light from `corner` to `corner + u + v`, split into `u_steps` cells along `u`
+    /// and `v_steps` cells along `v`.
+    pub fn new(
+        corner: math::Point,
+        u: Vector,
+        u_steps: usize,
+        v: Vector,
+        v_steps: usize,
+        color: Color,
+    ) -> Area {
+        let u_steps = u_steps.max(1);
+        let v_steps = v_steps.max(1);
+        Area {
+            corner,
+            u_step: u / u_steps as f64,
+            u_steps,
+            v_step: v / v_steps as f64,
+            v_steps,
+            color,
+            jitter: Jitter::Centered,
+        }
+    }
+
+    pub fn with_jitter(self, jitter: Jitter) -> Area {
+        Area { jitter, ..self }
+    }
+
+    pub fn samples_count(&self) -> usize {
+        self.u_steps * self.v_steps
+    }
+
+    /// the middle of the light.
+    pub fn center(&self) -> math::Point {
+        self.corner
+            + self.u_step * (self.u_steps as f64 / 2.0)
+            + self.v_step * (self.v_steps as f64 / 2.0)
+    }
+
+    /// the point at `(u_offset, v_offset)` within the cell at `(u, v)`, with offsets
+    /// running from 0 to 1 across the cell.
+    pub fn point_on_light(
+        &self,
+        u: usize,
+        v: usize,
+        (u_offset, v_offset): (f64, f64),
+    ) -> math::Point {
+        self.corner + self.u_step * (u as f64 + u_offset) + self.v_step * (v as f64 + v_offset)
+    }
+
+    /// one sample from each cell, for lighting `point`.
+    pub fn samples(&self, point: math::Point) -> Vec<math::Point> {
+        let mut rng = match self.jitter {
+            Jitter::Centered => None,
+            Jitter::Seeded(seed) => Some(XorShift::at_point(seed, point)),
+        };
+
+        let mut samples = Vec::with_capacity(self.samples_count());
+        for v in 0..self.v_steps {
+            for u in 0..self.u_steps {
+                let offset = match &mut rng {
+                    Some(rng) => (rng.next_f64(), rng.next_f64()),
+                    None => (0.5, 0.5),
+                };
+                samples.push(self.point_on_light(u, v, offset));
+            }
+        }
+        samples
+    }
+
+    /// the fraction of the light's samples that aren't blocked from reaching `point`.
+    pub fn intensity_at(&self, world: &World, point: math::Point) -> f64 {
+        let samples = self.samples(point);
+        let lit = samples
+            .iter()
+            .filter(|position| {
+                super::transmission_between(world, point, **position, 0.0) != Color::black()
+            })
+            .count();
+        lit as f64 / samples.len() as f64
+    }
+
+    /// how much of the light makes it to `point` at `time`, averaged over its samples.
+    pub fn transmission_to(&self, world: &World, point: math::Point, time: f64) -> Color {
+        let samples = self.samples(point);
+        let mut transmission = Color::black();
+        for position in &samples {
+            transmission += super::transmission_between(world, point, *position, time);
+        }
+        transmission / samples.len() as f64
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::field_reassign_with_default)]
+mod tests {
+    use super::*;
+    use crate::{
+        math::{Form, Geometry, Matrix, Transformable},
+        world::{Light, Ray, RenderSettings},
+    };
+    use std::f64::consts;
+
+    fn book_light() -> Area {
+        Area::new(
+            math::Point::new(-0.5, -0.5, -5.0),
+            Vector::new(1.0, 0.0, 0.0),
+            2,
+            Vector::new(0.0, 1.0, 0.0),
+            2,
+            Color::white(),
+        )
+    }
+
+    #[test]
+    fn creating_area_light() {
+        let light = Area::new(
+            math::Point::zero(),
+            Vector::new(2.0, 0.0, 0.0),
+            4,
+            Vector::new(0.0, 0.0, 1.0),
+            2,
+            Color::white(),
+        );
+        assert_eq!(light.corner, math::Point::zero());
+        assert_eq!(light.u_step, Vector::new(0.5, 0.0, 0.0));
+        assert_eq!(light.u_steps, 4);
+        assert_eq!(light.v_step, Vector::new(0.0, 0.0, 0.5));
+        assert_eq!(light.v_steps, 2);
+        assert_eq!(light.samples_count(), 8);
+        assert_eq!(light.center(), math::Point::new(1.0, 0.0, 0.5));
+    }
+
+    #[test]
+    fn finding_single_point_on_area_light() {
+        let light = Area::new(
+            math::Point::zero(),
+            Vector::new(2.0, 0.0, 0.0),
+            4,
+            Vector::new(0.0, 0.0, 1.0),
+            2,
+            Color::white(),
+        );
+        let cases = [
+            (0, 0, math::Point::new(0.25, 0.0, 0.25)),
+            (1, 0, math::Point::new(0.75, 0.0, 0.25)),
+            (0, 1, math::Point::new(0.25, 0.0, 0.75)),
+            (2, 0, math::Point::new(1.25, 0.0, 0.25)),
+            (3, 1, math::Point::new(1.75, 0.0, 0.75)),
+        ];
+        for (u, v, expected) in cases.iter() {
+            assert_eq!(light.point_on_light(*u, *v, (0.5, 0.5)), *expected);
+        }
+    }
+
+    #[test]
+    fn area_light_intensity() {
+        let w = World::default();
+        let light = book_light();
+        let cases = [
+            (math::Point::new(0.0, 0.0, 2.0), 0.0),
+            (math::Point::new(1.0, -1.0, 2.0), 0.25),
+            (math::Point::new(1.5, 0.0, 2.0), 0.5),
+            (math::Point::new(1.25, 1.25, 3.0), 0.75),
+            (math::Point::new(0.0, 0.0, -2.0), 1.0),
+        ];
+        for (point, expected) in cases.iter() {
+            assert_eq!(light.intensity_at(&w, *point), *expected);
+        }
+    }
+
+    #[test]
+    fn jittered_samples_stay_in_their_cells() {
+        let light = book_light().with_jitter(Jitter::Seeded(3));
+        let point = math::Point::new(1.0, 2.0, 3.0);
+        let samples = light.samples(point);
+        assert_eq!(samples, light.samples(point));
+        assert_ne!(samples, book_light().samples(point));
+        for (i, sample) in samples.iter().enumerate() {
+            // each cell is half a unit on a side, starting from the corner.
+            let u_min = -0.5 + 0.5 * (i % 2) as f64;
+            let v_min = -0.5 + 0.5 * (i / 2) as f64;
+            assert!(u_min <= sample[0] && sample[0] <= u_min + 0.5);
+            assert!(v_min <= sample[1] && sample[1] <= v_min + 0.5);
+            assert_eq!(sample[2], -5.0);
+        }
+    }
+
+    #[test]
+    fn penumbra_is_partly_lit() {
+        let mut w = World::default();
+        w.lights = vec![Light::area(book_light().with_jitter(Jitter::Seeded(1)))];
+        let wall = Geometry::default()
+            .with_form(Form::Plane)
+            .transformed(Matrix::translation(0.0, 0.0, 2.0) * Matrix::rotation_x(consts::PI / 2.0));
+        w.objects.push(wall.clone());
+
+        let r = Ray::new(
+            math::Point::new(1.0, -1.0, -10.0),
+            Vector::new(0.0, 0.0, 1.0),
+        );
+        let xs = w.hit(r).unwrap();
+        let comps = xs.closest().unwrap().compute_among(&xs);
+        assert_eq!(xs.closest().unwrap().object, wall);
+
+        let color = w.cast_ray(r);
+        let dark = w.ambient_at(&comps);
+        let lit = World::new(w.objects.clone(), w.lights.clone())
+            .with_settings(RenderSettings::default().with_shadows(false))
+            .shade(&comps);
+        for i in 0..3 {
+            assert!(dark[i] < color[i] && color[i] < lit[i]);
+        }
+    }
+}
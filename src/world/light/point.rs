@@ -1,50 +1,141 @@
+use std::f64::consts;
+
 use crate::{
-    math,
-    world::{intersection::Computations, Color, Material, Ray, World},
+    math::{self, sample_sphere, XorShift},
+    world::{Color, World},
 };
 
+/// how many lumens of visible light a watt of radiant power gives, assuming all of it
+/// is emitted at the wavelength the eye is most sensitive to (555 nm).
+pub const LUMENS_PER_WATT: f64 = 683.0;
+
+/// a light that shines equally in every direction from a single position.
+///
+/// without a `power`, the light's color is used as is, no matter how far away it is.
+/// with a `power` in watts, the light is treated as a physical point source: it spreads
+/// its power evenly over a sphere, so that a surface `d` meters away facing the light
+/// receives `power / (4 * pi * d^2)` watts per square meter, and the light's color is
+/// scaled by that irradiance.
+///
+/// without a `radius`, the light casts hard shadows. with one, it is treated as a ball
+/// of that radius when finding shadows, so that a point which can see only part of it
+/// is only partly shadowed.
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Point {
     pub position: math::Point,
     pub color: Color,
+    pub power: Option<f64>,
+    pub radius: f64,
 }
 
 impl Point {
     pub fn new(position: math::Point, color: Color) -> Point {
-        Point { position, color }
+        Point {
+            position,
+            color,
+            power: None,
+            radius: 0.0,
+        }
+    }
+
+    pub fn with_radius(self, radius: f64) -> Point {
+        Point { radius, ..self }
+    }
+
+    /// gives the light a physical power, in watts. only point lights have one; area
+    /// lights always shine with their plain color.
+    pub fn with_power(self, watts: f64) -> Point {
+        Point {
+            power: Some(watts),
+            ..self
+        }
+    }
+
+    /// gives the light a physical power, in lumens.
+    pub fn with_lumens(self, lumens: f64) -> Point {
+        self.with_power(lumens / LUMENS_PER_WATT)
+    }
+
+    /// the irradiance, in watts per square meter, that a physical light gives a
+    /// surface at `point` that faces it.
+    pub fn irradiance_at(&self, point: math::Point) -> Option<f64> {
+        self.power.map(|watts| {
+            let distance = (self.position - point).magnitude();
+            watts / (4.0 * consts::PI * distance * distance)
+        })
+    }
+
+    /// the color of the light when it arrives at `point`.
+    pub fn intensity_at(&self, point: math::Point) -> Color {
+        match self.irradiance_at(point) {
+            Some(irradiance) => self.color * irradiance,
+            None => self.color,
+        }
     }
 
     pub fn casts_shade(&self, world: &World, point: math::Point) -> bool {
-        let to_light = self.position - point;
-        let distance = to_light.magnitude();
-        let direction = to_light.normalized();
-        let ray_to_light = Ray::new(point, direction);
-
-        if let Some(intersections) = world.hit(ray_to_light) {
-            if let Some(intersection) = intersections.closest() {
-                if intersection.time < distance {
-                    return true;
-                }
-            }
+        self.transmission_to(world, point, 0.0) == Color::black()
+    }
+
+    /// the points on the light that shadows are found from when lighting `point`: just
+    /// its position without a radius, or as many points on its surface as `world`'s
+    /// render settings ask for.
+    pub fn samples(&self, world: &World, point: math::Point) -> Vec<math::Point> {
+        if self.radius == 0.0 || world.settings.light_samples == 0 {
+            return vec![self.position];
         }
 
-        false
+        let mut rng = XorShift::at_point(world.settings.light_seed, point);
+        (0..world.settings.light_samples)
+            .map(|_| self.position + sample_sphere(rng.next_f64(), rng.next_f64()) * self.radius)
+            .collect()
+    }
+
+    /// the fraction of the light that isn't blocked from reaching `point`.
+    pub fn visibility_at(&self, world: &World, point: math::Point) -> f64 {
+        let samples = self.samples(world, point);
+        let lit = samples
+            .iter()
+            .filter(|position| {
+                super::transmission_between(world, point, **position, 0.0) != Color::black()
+            })
+            .count();
+        lit as f64 / samples.len() as f64
+    }
+
+    /// how much of the light makes it to `point`, after passing through everything in
+    /// the way at `time`, averaged over its samples. see `light::transmission_between`.
+    pub fn transmission_to(&self, world: &World, point: math::Point, time: f64) -> Color {
+        let samples = self.samples(world, point);
+        if let [position] = samples[..] {
+            return super::transmission_between(world, point, position, time);
+        }
+
+        let mut transmission = Color::black();
+        for position in &samples {
+            transmission += super::transmission_between(world, point, *position, time);
+        }
+        transmission / samples.len() as f64
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::useless_conversion)]
 mod tests {
     use super::*;
     use crate::{
-        math::{Form, Geometry, Vector},
+        math::{Form, Geometry, Matrix, Transformable, Vector},
         world::{
+            intersection::Computations,
             pattern::{Pattern, Stripe},
-            Light, Texture,
+            Light, Material, RenderSettings, Texture,
         },
     };
+    use std::sync::Arc;
 
-    fn setup() -> (Material, math::Point) {
-        (Material::default(), math::Point::zero())
+    fn setup() -> (Arc<Material>, math::Point) {
+        (Arc::new(Material::default()), math::Point::zero())
     }
 
     #[test]
@@ -56,6 +147,76 @@ mod tests {
         assert_eq!(light.color, color);
     }
 
+    #[test]
+    fn legacy_light_is_unchanged() {
+        let light = Point::new(math::Point::new(3.0, 4.0, 0.0), Color::new(0.5, 1.0, 1.0));
+        assert_eq!(light.power, None);
+        assert_eq!(light.intensity_at(math::Point::zero()), light.color);
+        assert_eq!(
+            light.intensity_at(math::Point::new(100.0, 0.0, 0.0)),
+            light.color
+        );
+    }
+
+    #[test]
+    fn lumens_convert_to_watts() {
+        let light = Point::new(math::Point::zero(), Color::white()).with_lumens(1366.0);
+        assert_eq!(light.power, Some(2.0));
+    }
+
+    #[test]
+    fn physical_light_falls_off_with_distance() {
+        let light = Point::new(math::Point::zero(), Color::white()).with_power(4.0 * consts::PI);
+        assert_eq!(
+            light.irradiance_at(math::Point::new(1.0, 0.0, 0.0)),
+            Some(1.0)
+        );
+        assert_eq!(
+            light.irradiance_at(math::Point::new(0.0, 2.0, 0.0)),
+            Some(0.25)
+        );
+        assert_eq!(
+            light.intensity_at(math::Point::new(0.0, 0.0, 2.0)),
+            Color::new(0.25, 0.25, 0.25)
+        );
+    }
+
+    #[test]
+    fn doubling_power_doubles_diffuse() {
+        let material = Arc::new(Material::new(
+            Texture::pattern(Pattern::solid(Color::white())),
+            0.0,
+            1.0,
+            0.0,
+            200.0,
+        ));
+        let shade = |watts: f64| {
+            let light = Light::point(
+                Point::new(math::Point::new(0.0, 0.0, -1.0), Color::white()).with_power(watts),
+            );
+            let world = World::new(vec![], vec![light]);
+            light.illuminate(
+                &world,
+                &Computations {
+                    point: math::Point::zero(),
+                    over_point: math::Point::zero(),
+                    under_point: math::Point::zero(),
+                    to_eye: Vector::new(0.0, 0.0, -1.0),
+                    surface_normal: Vector::new(0.0, 0.0, -1.0),
+                    reflect_v: Vector::zero(),
+                    material: material.clone(),
+                    is_inside: false,
+                    n1: 1.0,
+                    n2: 1.0,
+                    time: 0.0,
+                },
+            )
+        };
+        let single = shade(1.0);
+        assert_eq!(single, Color::white() * (1.0 / (4.0 * consts::PI)));
+        assert_eq!(shade(2.0), single * 2.0);
+    }
+
     #[test]
     fn eye_between_light_and_surface() {
         let (material, point) = setup();
@@ -66,16 +227,19 @@ mod tests {
             Color::new(1.0, 1.0, 1.0),
         ));
         let world = World::new(vec![], vec![light]);
-        let result = light.illuminate(
-            &world,
-            &Computations {
-                point,
-                to_eye,
-                surface_normal,
-                material,
-                is_inside: true,
-            },
-        );
+        let result = world.shade(&Computations {
+            point,
+            over_point: math::Point::zero(),
+            under_point: math::Point::zero(),
+            to_eye,
+            surface_normal,
+            reflect_v: Vector::zero(),
+            material,
+            is_inside: true,
+            n1: 1.0,
+            n2: 1.0,
+            time: 0.0,
+        });
         assert_eq!(result, Color::new(1.9, 1.9, 1.9));
     }
 
@@ -93,16 +257,19 @@ mod tests {
             Color::new(1.0, 1.0, 1.0),
         ));
         let world = World::new(vec![], vec![light]);
-        let result = light.illuminate(
-            &world,
-            &Computations {
-                point,
-                to_eye,
-                surface_normal,
-                material,
-                is_inside: true,
-            },
-        );
+        let result = world.shade(&Computations {
+            point,
+            over_point: math::Point::zero(),
+            under_point: math::Point::zero(),
+            to_eye,
+            surface_normal,
+            reflect_v: Vector::zero(),
+            material,
+            is_inside: true,
+            n1: 1.0,
+            n2: 1.0,
+            time: 0.0,
+        });
         assert_eq!(result, Color::new(1.0, 1.0, 1.0));
     }
 
@@ -116,16 +283,19 @@ mod tests {
             Color::new(1.0, 1.0, 1.0),
         ));
         let world = World::new(vec![], vec![light]);
-        let result = light.illuminate(
-            &world,
-            &Computations {
-                point,
-                to_eye,
-                surface_normal,
-                material,
-                is_inside: true,
-            },
-        );
+        let result = world.shade(&Computations {
+            point,
+            over_point: math::Point::zero(),
+            under_point: math::Point::zero(),
+            to_eye,
+            surface_normal,
+            reflect_v: Vector::zero(),
+            material,
+            is_inside: true,
+            n1: 1.0,
+            n2: 1.0,
+            time: 0.0,
+        });
         assert_eq!(result, Color::new(0.7364, 0.7364, 0.7364));
     }
 
@@ -143,19 +313,49 @@ mod tests {
             Color::new(1.0, 1.0, 1.0),
         ));
         let world = World::new(vec![], vec![light]);
-        let result = light.illuminate(
-            &world,
-            &Computations {
-                point,
-                to_eye,
-                surface_normal,
-                material,
-                is_inside: true,
-            },
-        );
+        let result = world.shade(&Computations {
+            point,
+            over_point: math::Point::zero(),
+            under_point: math::Point::zero(),
+            to_eye,
+            surface_normal,
+            reflect_v: Vector::zero(),
+            material,
+            is_inside: true,
+            n1: 1.0,
+            n2: 1.0,
+            time: 0.0,
+        });
         assert_eq!(result, Color::new(1.6364, 1.6364, 1.6364));
     }
 
+    #[test]
+    fn nan_eye_vector_skips_specular() {
+        let (material, point) = setup();
+        let to_eye = Vector::new(f64::NAN, f64::NAN, f64::NAN);
+        let surface_normal = Vector::new(0.0, 0.0, -1.0);
+        let light = Light::point(Point::new(
+            math::Point::new(0.0, 0.0, -10.0),
+            Color::new(1.0, 1.0, 1.0),
+        ));
+        let world = World::new(vec![], vec![light]);
+        let result = world.shade(&Computations {
+            point,
+            over_point: math::Point::zero(),
+            under_point: math::Point::zero(),
+            to_eye,
+            surface_normal,
+            reflect_v: Vector::zero(),
+            material,
+            is_inside: false,
+            n1: 1.0,
+            n2: 1.0,
+            time: 0.0,
+        });
+        assert!(result.is_finite());
+        assert_eq!(result, Color::new(1.0, 1.0, 1.0));
+    }
+
     #[test]
     fn light_behind_surface() {
         let (material, point) = setup();
@@ -166,16 +366,19 @@ mod tests {
             Color::new(1.0, 1.0, 1.0),
         ));
         let world = World::new(vec![], vec![light]);
-        let result = light.illuminate(
-            &world,
-            &Computations {
-                point,
-                to_eye,
-                surface_normal,
-                material,
-                is_inside: false,
-            },
-        );
+        let result = world.shade(&Computations {
+            point,
+            over_point: math::Point::zero(),
+            under_point: math::Point::zero(),
+            to_eye,
+            surface_normal,
+            reflect_v: Vector::zero(),
+            material,
+            is_inside: false,
+            n1: 1.0,
+            n2: 1.0,
+            time: 0.0,
+        });
         assert_eq!(result, Color::new(0.1, 0.1, 0.1));
     }
 
@@ -192,27 +395,127 @@ mod tests {
             vec![Geometry::default().with_form(Form::Sphere)],
             vec![light],
         );
-        let result = light.illuminate(
-            &world,
-            &Computations {
-                point,
-                to_eye,
-                surface_normal,
-                material,
-                is_inside: false,
-            },
-        );
+        let result = world.shade(&Computations {
+            point,
+            over_point: math::Point::zero(),
+            under_point: math::Point::zero(),
+            to_eye,
+            surface_normal,
+            reflect_v: Vector::zero(),
+            material,
+            is_inside: false,
+            n1: 1.0,
+            n2: 1.0,
+            time: 0.0,
+        });
         assert_eq!(result, Color::new(0.1, 0.1, 0.1));
     }
 
+    #[test]
+    fn light_without_radius_has_one_sample() {
+        let light = Point::new(math::Point::new(1.0, 2.0, 3.0), Color::white());
+        let w = World::new(vec![], vec![]);
+        assert_eq!(light.radius, 0.0);
+        assert_eq!(light.samples(&w, math::Point::zero()), vec![light.position]);
+    }
+
+    #[test]
+    fn round_light_partly_shadowed_by_sphere_edge() {
+        let world = World::new(vec![Geometry::default().with_form(Form::Sphere)], vec![])
+            .with_settings(RenderSettings::default().with_light_samples(256));
+        // the line to the light's center just grazes the sphere.
+        let point = math::Point::new(0.0, 2.0, 10.0);
+        let light = Point::new(math::Point::new(0.0, 0.0, -10.0), Color::white());
+        assert_eq!(light.visibility_at(&world, point), 0.0);
+
+        let mut previous = 0.0;
+        for radius in [0.25, 0.5, 1.0, 2.0].iter() {
+            let visibility = light.with_radius(*radius).visibility_at(&world, point);
+            assert!(0.0 < visibility && visibility < 1.0);
+            assert!(previous <= visibility);
+            previous = visibility;
+        }
+    }
+
+    #[test]
+    fn opaque_blocker_transmits_nothing() {
+        let light = Point::new(math::Point::new(0.0, 0.0, -10.0), Color::white());
+        let world = World::new(vec![Geometry::default().with_form(Form::Sphere)], vec![]);
+        let point = math::Point::new(0.0, 0.0, 10.0);
+        assert_eq!(light.transmission_to(&world, point, 0.0), Color::black());
+        assert!(light.casts_shade(&world, point));
+    }
+
+    #[test]
+    fn glass_blockers_tint_and_dim_the_light() {
+        let light = Point::new(math::Point::new(0.0, 0.0, -10.0), Color::white());
+        let glass = |color: Color, z: f64| {
+            Geometry::default()
+                .with_form(Form::Sphere)
+                .with_material(
+                    Material::default()
+                        .with_texture(Texture::pattern(Pattern::solid(color)))
+                        .with_transparency(0.5),
+                )
+                .transformed(Matrix::translation(0.0, 0.0, z))
+        };
+        let world = World::new(
+            vec![
+                glass(Color::new(1.0, 0.5, 1.0), 0.0),
+                glass(Color::white(), -4.0),
+            ],
+            vec![],
+        );
+        let point = math::Point::new(0.0, 0.0, 10.0);
+        assert_eq!(
+            light.transmission_to(&world, point, 0.0),
+            Color::new(0.25, 0.125, 0.25)
+        );
+        assert!(!light.casts_shade(&world, point));
+        // nothing is in the way of a point in front of both spheres.
+        assert_eq!(
+            light.transmission_to(&world, math::Point::new(0.0, 0.0, -6.0), 0.0),
+            Color::white()
+        );
+    }
+
+    #[test]
+    fn light_through_glass_dims_diffuse_and_specular() {
+        let (material, point) = setup();
+        let blocker = Geometry::default()
+            .with_form(Form::Sphere)
+            .with_material(Material::default().with_transparency(0.5))
+            .transformed(Matrix::translation(0.0, 0.0, -5.0));
+        let light = Light::point(Point::new(
+            math::Point::new(0.0, 0.0, -10.0),
+            Color::new(1.0, 1.0, 1.0),
+        ));
+        let world = World::new(vec![blocker], vec![light]);
+        let result = world.shade(&Computations {
+            point,
+            over_point: math::Point::zero(),
+            under_point: math::Point::zero(),
+            to_eye: Vector::new(0.0, 0.0, -1.0),
+            surface_normal: Vector::new(0.0, 0.0, -1.0),
+            reflect_v: Vector::zero(),
+            material,
+            is_inside: false,
+            n1: 1.0,
+            n2: 1.0,
+            time: 0.0,
+        });
+        // 0.1 ambient, plus half of the 0.9 diffuse and 0.9 specular.
+        assert_eq!(result, Color::new(1.0, 1.0, 1.0));
+    }
+
     #[test]
     fn lighting_patterned_surface() {
         let (mut material, _) = setup();
-        material.texture =
-            Texture::pattern(Pattern::stripe(Stripe::new(Color::white(), Color::black())));
-        material.ambient = 1.0;
-        material.diffuse = 0.0;
-        material.specular = 0.0;
+        let m = Arc::make_mut(&mut material);
+        m.texture = Texture::pattern(Pattern::stripe(Stripe::new(Color::white(), Color::black())));
+        m.ambient = 1.0;
+        m.diffuse = 0.0;
+        m.specular = 0.0;
         let to_eye = Vector::new(0.0, 0.0, -1.0);
         let surface_normal = Vector::new(0.0, 0.0, -1.0);
         let light = Light::point(Point::new(
@@ -220,26 +523,32 @@ mod tests {
             Color::white(),
         ));
         let world = World::new(vec![], vec![light]);
-        let c1 = light.illuminate(
-            &world,
-            &Computations {
-                point: math::Point::new(0.9, 0.0, 0.0),
-                to_eye,
-                surface_normal,
-                material,
-                is_inside: false,
-            },
-        );
-        let c2 = light.illuminate(
-            &world,
-            &Computations {
-                point: math::Point::new(1.1, 0.0, 0.0),
-                to_eye,
-                surface_normal,
-                material,
-                is_inside: false,
-            },
-        );
+        let c1 = world.shade(&Computations {
+            point: math::Point::new(0.9, 0.0, 0.0),
+            over_point: math::Point::new(0.9, 0.0, 0.0),
+            under_point: math::Point::zero(),
+            to_eye,
+            surface_normal,
+            reflect_v: Vector::zero(),
+            material: material.clone(),
+            is_inside: false,
+            n1: 1.0,
+            n2: 1.0,
+            time: 0.0,
+        });
+        let c2 = world.shade(&Computations {
+            point: math::Point::new(1.1, 0.0, 0.0),
+            over_point: math::Point::new(1.1, 0.0, 0.0),
+            under_point: math::Point::zero(),
+            to_eye,
+            surface_normal,
+            reflect_v: Vector::zero(),
+            material,
+            is_inside: false,
+            n1: 1.0,
+            n2: 1.0,
+            time: 0.0,
+        });
         assert_eq!(c1, Color::white());
         assert_eq!(c2, Color::black());
     }
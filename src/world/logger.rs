@@ -0,0 +1,221 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    time::{Duration, Instant},
+};
+
+use crate::world::RenderStats;
+
+/// how much attention an event deserves.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Debug,
+    Info,
+    Warning,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Debug => write!(f, "debug"),
+            Severity::Info => write!(f, "info"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// something that happened while preparing or rendering a world.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RenderEvent {
+    /// the world was checked over and made ready to render.
+    WorldPrepared { objects: usize, elapsed: Duration },
+    RenderStarted {
+        width: usize,
+        height: usize,
+        tiles: usize,
+    },
+    /// `index` counts up from 0 in the order that tiles are finished.
+    TileFinished { index: usize, tiles: usize },
+    /// `pixels` out of `total` are finished, with `stats` gathered over those pixels.
+    Progress {
+        pixels: usize,
+        total: usize,
+        elapsed: Duration,
+        stats: RenderStats,
+    },
+    RenderFinished {
+        elapsed: Duration,
+        stats: RenderStats,
+    },
+    /// something suspicious that doesn't stop the render from going ahead.
+    Warning(String),
+}
+
+impl RenderEvent {
+    pub fn severity(&self) -> Severity {
+        match self {
+            RenderEvent::TileFinished { .. } => Severity::Debug,
+            RenderEvent::Warning(_) => Severity::Warning,
+            _ => Severity::Info,
+        }
+    }
+}
+
+impl Display for RenderEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderEvent::WorldPrepared { objects, elapsed } => write!(
+                f,
+                "prepared {} objects in {} ms",
+                objects,
+                elapsed.as_millis()
+            ),
+            RenderEvent::RenderStarted {
+                width,
+                height,
+                tiles,
+            } => write!(
+                f,
+                "rendering {}x{} pixels in {} tiles",
+                width, height, tiles
+            ),
+            RenderEvent::TileFinished { index, tiles } => {
+                write!(f, "finished tile {} of {}", index + 1, tiles)
+            }
+            RenderEvent::Progress {
+                pixels,
+                total,
+                elapsed,
+                stats,
+            } => {
+                let done = if *total == 0 {
+                    100.0
+                } else {
+                    100.0 * *pixels as f64 / *total as f64
+                };
+                write!(f, "{:.1}% of pixels done", done)?;
+                // assume the remaining pixels take as long as the finished ones did.
+                // nothing can be guessed before any are finished.
+                if *pixels > 0 {
+                    let remaining = elapsed.as_secs_f64() * total.saturating_sub(*pixels) as f64
+                        / *pixels as f64;
+                    write!(f, ", about {:.1} s left", remaining)?;
+                }
+                write!(f, " ({} rays)", stats.rays())
+            }
+            RenderEvent::RenderFinished { elapsed, stats } => write!(
+                f,
+                "rendered in {} ms ({} non-finite pixels)",
+                elapsed.as_millis(),
+                stats.non_finite_pixels
+            ),
+            RenderEvent::Warning(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+/// an event, along with when it happened.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogRecord {
+    pub timestamp: Instant,
+    pub severity: Severity,
+    pub event: RenderEvent,
+}
+
+impl LogRecord {
+    pub fn now(event: RenderEvent) -> LogRecord {
+        LogRecord {
+            timestamp: Instant::now(),
+            severity: event.severity(),
+            event,
+        }
+    }
+}
+
+/// receives events as a world is prepared and rendered.
+///
+/// loggers are only ever called through an exclusive reference, so a renderer that
+/// spreads its work across threads has to funnel events through one place, and a
+/// logger never sees two calls at once.
+pub trait RenderLogger {
+    fn log(&mut self, record: LogRecord);
+}
+
+/// throws every event away. it has no size, and its `log` does nothing, though the
+/// records handed to it (and the `Instant::now()` stamped on each) are still built.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct NoopLogger;
+
+const _: () = assert!(std::mem::size_of::<NoopLogger>() == 0);
+
+impl RenderLogger for NoopLogger {
+    #[inline(always)]
+    fn log(&mut self, _record: LogRecord) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn severity_follows_event() {
+        assert_eq!(
+            LogRecord::now(RenderEvent::Warning(String::from("careful"))).severity,
+            Severity::Warning
+        );
+        assert_eq!(
+            LogRecord::now(RenderEvent::TileFinished { index: 0, tiles: 1 }).severity,
+            Severity::Debug
+        );
+        assert_eq!(
+            LogRecord::now(RenderEvent::RenderStarted {
+                width: 1,
+                height: 1,
+                tiles: 1
+            })
+            .severity,
+            Severity::Info
+        );
+    }
+
+    #[test]
+    fn noop_logger_is_zero_sized() {
+        assert_eq!(std::mem::size_of::<NoopLogger>(), 0);
+    }
+
+    #[test]
+    fn events_display_readably() {
+        let event = RenderEvent::TileFinished { index: 2, tiles: 8 };
+        assert_eq!(event.to_string(), "finished tile 3 of 8");
+
+        let event = RenderEvent::Progress {
+            pixels: 25,
+            total: 100,
+            elapsed: Duration::from_secs(2),
+            stats: RenderStats {
+                primary_rays: 25,
+                shadow_rays: 30,
+                ..RenderStats::default()
+            },
+        };
+        assert_eq!(
+            event.to_string(),
+            "25.0% of pixels done, about 6.0 s left (55 rays)"
+        );
+    }
+
+    #[test]
+    fn progress_without_an_estimate() {
+        let progress = |pixels, total| RenderEvent::Progress {
+            pixels,
+            total,
+            elapsed: Duration::from_secs(2),
+            stats: RenderStats::default(),
+        };
+        assert_eq!(progress(0, 100).to_string(), "0.0% of pixels done (0 rays)");
+        assert_eq!(progress(0, 0).to_string(), "100.0% of pixels done (0 rays)");
+        assert_eq!(
+            progress(120, 100).to_string(),
+            "120.0% of pixels done, about 0.0 s left (0 rays)"
+        );
+    }
+}
@@ -1,17 +1,34 @@
 use crate::{
     math::{Point, EPSILON},
-    world::{Color, Pattern, Texture, Textured},
+    world::{Bumps, Color, NormalMap, Pattern, Texture, Textured},
 };
 
 /// contains required data for the phong reflection model.
 /// (https://en.wikipedia.org/wiki/Phong_reflection_model)
-#[derive(Copy, Clone, Debug)]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Material {
     pub texture: Texture,
     pub ambient: f64,
     pub diffuse: f64,
     pub specular: f64,
     pub shininess: f64,
+    /// how much of the surrounding scene the surface mirrors, from 0 (none) to 1 (a
+    /// perfect mirror).
+    pub reflective: f64,
+    /// how much light passes through the surface, from 0 (opaque) to 1.
+    pub transparency: f64,
+    /// how much light bends when passing into the material; 1 for a vacuum.
+    pub refractive_index: f64,
+    /// how densely the material absorbs each channel of the light passing through
+    /// it, per unit of distance travelled. black absorbs nothing.
+    pub absorption: Color,
+    /// light the surface gives off by itself, whether or not anything lights it.
+    pub emissive: Color,
+    /// tilts the surface's normals to make it look rough, if given.
+    pub normal_perturbation: Option<Bumps>,
+    /// tilts the surface's normals by a picture, if given.
+    pub normal_map: Option<NormalMap>,
 }
 
 impl Material {
@@ -28,16 +45,79 @@ impl Material {
             diffuse,
             specular,
             shininess,
+            reflective: 0.0,
+            transparency: 0.0,
+            refractive_index: 1.0,
+            absorption: Color::black(),
+            emissive: Color::black(),
+            normal_perturbation: None,
+            normal_map: None,
         }
     }
 
     pub fn with_texture(&self, texture: Texture) -> Material {
-        Material::new(
+        Material {
             texture,
-            self.ambient,
-            self.diffuse,
-            self.specular,
-            self.shininess,
+            ..self.clone()
+        }
+    }
+
+    pub fn with_reflective(&self, reflective: f64) -> Material {
+        Material {
+            reflective,
+            ..self.clone()
+        }
+    }
+
+    pub fn with_transparency(&self, transparency: f64) -> Material {
+        Material {
+            transparency,
+            ..self.clone()
+        }
+    }
+
+    pub fn with_refractive_index(&self, refractive_index: f64) -> Material {
+        Material {
+            refractive_index,
+            ..self.clone()
+        }
+    }
+
+    pub fn with_absorption(&self, absorption: Color) -> Material {
+        Material {
+            absorption,
+            ..self.clone()
+        }
+    }
+
+    pub fn with_emissive(&self, emissive: Color) -> Material {
+        Material {
+            emissive,
+            ..self.clone()
+        }
+    }
+
+    pub fn with_normal_perturbation(&self, bumps: Bumps) -> Material {
+        Material {
+            normal_perturbation: Some(bumps),
+            ..self.clone()
+        }
+    }
+
+    pub fn with_normal_map(&self, normal_map: NormalMap) -> Material {
+        Material {
+            normal_map: Some(normal_map),
+            ..self.clone()
+        }
+    }
+
+    /// how much of each channel of the light is left after travelling `distance`
+    /// through the material, following the beer-lambert law.
+    pub fn transmittance(&self, distance: f64) -> Color {
+        Color::new(
+            (-self.absorption.red() * distance).exp(),
+            (-self.absorption.green() * distance).exp(),
+            (-self.absorption.blue() * distance).exp(),
         )
     }
 }
@@ -67,6 +147,13 @@ impl PartialEq for Material {
             && (self.diffuse - other.diffuse).abs() < EPSILON
             && (self.specular - other.specular).abs() < EPSILON
             && (self.shininess - other.shininess).abs() < EPSILON
+            && (self.reflective - other.reflective).abs() < EPSILON
+            && (self.transparency - other.transparency).abs() < EPSILON
+            && (self.refractive_index - other.refractive_index).abs() < EPSILON
+            && self.absorption == other.absorption
+            && self.emissive == other.emissive
+            && self.normal_perturbation == other.normal_perturbation
+            && self.normal_map == other.normal_map
     }
 }
 
@@ -85,5 +172,30 @@ mod tests {
         assert_eq!(m.diffuse, 0.9);
         assert_eq!(m.specular, 0.9);
         assert_eq!(m.shininess, 200.0);
+        assert_eq!(m.reflective, 0.0);
+        assert_eq!(m.transparency, 0.0);
+        assert_eq!(m.refractive_index, 1.0);
+        assert_eq!(m.absorption, Color::black());
+        assert_eq!(m.emissive, Color::black());
+        assert_eq!(m.normal_perturbation, None);
+        assert_eq!(m.normal_map, None);
+    }
+
+    #[test]
+    fn transmittance_falls_off_exponentially() {
+        let m = Material::default().with_absorption(Color::new(0.0, 1.0, 2.0));
+        assert_eq!(m.transmittance(0.0), Color::white());
+        assert_eq!(
+            m.transmittance(1.5),
+            Color::new(1.0, (-1.5_f64).exp(), (-3.0_f64).exp())
+        );
+    }
+
+    #[test]
+    fn with_texture_keeps_reflectivity() {
+        let m = Material::default()
+            .with_reflective(0.5)
+            .with_texture(Texture::pattern(Pattern::solid(Color::black())));
+        assert_eq!(m.reflective, 0.5);
     }
 }
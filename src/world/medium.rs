@@ -0,0 +1,152 @@
+use crate::world::{Color, Ray, World};
+
+/// a fog that fills the whole world evenly, dimming whatever is seen through it and
+/// glowing where light passes through it.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Medium {
+    /// how much of the light is absorbed or scattered per unit of distance travelled
+    /// through the fog. with 0, the fog isn't there at all.
+    pub density: f64,
+    /// tints the light that the fog scatters towards the eye.
+    pub color: Color,
+    /// how many points along each ray the lights are sampled at to find how much light
+    /// the fog scatters towards the eye. with 0, the fog only dims.
+    pub scattering_samples: u32,
+    /// how far rays that don't hit anything are followed through the fog.
+    pub max_distance: f64,
+}
+
+impl Medium {
+    pub fn new(density: f64, color: Color, scattering_samples: u32) -> Medium {
+        Medium {
+            density,
+            color,
+            scattering_samples,
+            max_distance: 100.0,
+        }
+    }
+
+    pub fn with_max_distance(self, max_distance: f64) -> Medium {
+        Medium {
+            max_distance,
+            ..self
+        }
+    }
+
+    /// the fraction of the light that makes it `distance` through the fog without
+    /// being absorbed or scattered, following the beer-lambert law.
+    pub fn transmittance(&self, distance: f64) -> f64 {
+        (-self.density * distance).exp()
+    }
+
+    /// the color seen along `ray` once it has passed through the fog, given that the
+    /// color `seen` is `time` along it, or that it hits nothing if there is no time.
+    pub fn apply(&self, world: &World, ray: Ray, seen: Color, time: Option<f64>) -> Color {
+        if self.density == 0.0 {
+            return seen;
+        }
+
+        let speed = ray.direction.magnitude();
+        let time = time.unwrap_or(self.max_distance / speed);
+        let color = seen * self.transmittance(time * speed);
+        if self.scattering_samples == 0 {
+            return color;
+        }
+
+        // march along the ray, adding the light that each step of the fog scatters
+        // towards the eye, dimmed by the fog between that step and the eye.
+        let step = time / self.scattering_samples as f64;
+        let mut scattered = Color::black();
+        for i in 0..self.scattering_samples {
+            let t = (i as f64 + 0.5) * step;
+            let point = ray.at(t);
+            for light in &world.lights {
+                let distance = (light.center() - point).magnitude();
+                scattered += light.color_at(point)
+                    * light.transmission_to(world, point, ray.time)
+                    * self.transmittance(distance)
+                    * self.transmittance(t * speed);
+            }
+        }
+
+        color + scattered * self.color * (self.density * step * speed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        math::{Form, Geometry, Point, Vector},
+        world::{light, Camera, Light, View},
+    };
+    use std::f64::consts;
+
+    #[test]
+    fn transmittance_follows_beer_lambert() {
+        let fog = Medium::new(0.5, Color::white(), 0);
+        assert_eq!(fog.transmittance(0.0), 1.0);
+        assert!((fog.transmittance(2.0) - consts::E.recip()).abs() < 1e-12);
+        assert!((fog.transmittance(4.0) - consts::E.powi(-2)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn no_density_changes_nothing() {
+        let world = World::default().with_medium(Medium::new(0.0, Color::white(), 8));
+        let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(world.cast_ray(ray), World::default().cast_ray(ray));
+    }
+
+    #[test]
+    fn fog_dims_surfaces_by_distance() {
+        let fog = Medium::new(0.1, Color::white(), 0);
+        let world = World::default().with_medium(fog);
+        let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(
+            world.cast_ray(ray),
+            World::default().cast_ray(ray) * (-0.4_f64).exp()
+        );
+    }
+
+    #[test]
+    fn unlit_fog_is_dark() {
+        let world = World::new(vec![], vec![]).with_medium(Medium::new(1.0, Color::white(), 8));
+        let ray = Ray::new(Point::zero(), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(world.cast_ray(ray), Color::black());
+    }
+
+    #[test]
+    fn fog_glows_under_light() {
+        let floor = Geometry::default().with_form(Form::Plane);
+        let lamp = Light::point(light::Point::new(Point::new(0.0, 3.0, 0.0), Color::white()));
+        let world = World::new(vec![floor], vec![lamp])
+            .with_medium(Medium::new(0.2, Color::white(), 16).with_max_distance(20.0));
+
+        let mut camera = Camera::new(21, 11, consts::PI / 2.0);
+        camera.set_view(View::transformed(
+            Point::new(0.0, 1.5, -6.0),
+            Point::new(0.0, 1.5, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+        ));
+        let image = camera.render(&world);
+
+        // above the horizon, so that only the fog is seen.
+        let (middle, edge) = (image[(10, 2)], image[(0, 2)]);
+        assert!(edge.red() < middle.red());
+    }
+
+    #[test]
+    fn point_lights_light_the_fog_from_their_position() {
+        let lamp = Light::point(light::Point::new(Point::new(0.0, 1.0, 0.0), Color::white()));
+        let world = World::new(vec![], vec![lamp])
+            .with_medium(Medium::new(0.5, Color::white(), 1).with_max_distance(2.0));
+        let ray = Ray::new(Point::new(0.0, 0.0, -1.0), Vector::new(0.0, 0.0, 1.0));
+        // one sample, halfway along the ray, one unit below the light.
+        let expected = (-0.5_f64).exp() * (-0.5_f64).exp() * 0.5 * 2.0;
+        assert_eq!(
+            world.cast_ray(ray),
+            Color::new(expected, expected, expected)
+        );
+    }
+}
@@ -0,0 +1,145 @@
+use crate::{
+    math::{Basis, Point, Vector},
+    world::{texture::Image, Canvas, Textured, UvMapping},
+};
+
+/// tilts a surface's normals by the colors of a picture wrapped onto it, to add detail
+/// that isn't in its shape.
+///
+/// each pixel holds a normal in tangent space, where x points the way `u` grows, y the
+/// way `v` grows, and z straight out of the surface, with each channel running from 0
+/// for -1 to 1 for +1. so the usual light blue `(0.5, 0.5, 1)` leaves the normal alone.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NormalMap {
+    pub image: Image,
+}
+
+impl NormalMap {
+    pub fn new(canvas: Canvas, mapping: UvMapping) -> NormalMap {
+        NormalMap {
+            image: Image::new(canvas, mapping),
+        }
+    }
+
+    /// tilts the object space `normal` at `object_space_point` by the map.
+    pub fn perturb(&self, object_space_point: Point, normal: Vector) -> Vector {
+        let normal = normal.normalized();
+        let color = self.image.color_at(object_space_point);
+        let tilt = Vector::new(
+            color.red() * 2.0 - 1.0,
+            color.green() * 2.0 - 1.0,
+            color.blue() * 2.0 - 1.0,
+        );
+
+        let texture_space_point = self.image.inverse() * object_space_point;
+        let (du, dv) = self.image.mapping.tangents(texture_space_point);
+        let transform = self.image.transformation();
+        let basis = tangent_basis(normal, transform * du, transform * dv);
+        basis.to_world(tilt).normalized()
+    }
+}
+
+/// an orthonormal basis around `normal` whose tangent follows `du` and whose bitangent
+/// points the same way as `dv`, falling back to any basis where those pinch to zero.
+fn tangent_basis(normal: Vector, du: Vector, dv: Vector) -> Basis {
+    let tangent = du.reject_from(&normal);
+    if tangent.magnitude() < 1e-9 {
+        return Basis::from_normal(normal);
+    }
+    let tangent = tangent.normalized();
+    let mut bitangent = normal.cross(&tangent);
+    if bitangent.dot(&dv) < 0.0 {
+        bitangent = -bitangent;
+    }
+    Basis {
+        tangent,
+        bitangent,
+        normal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        math::{Form, Geometry, Hittable},
+        world::{light, Color, Light, Material, Ray, World},
+    };
+
+    fn flat() -> Canvas {
+        Canvas::from_fn(4, 2, |_, _| Color::new(0.5, 0.5, 1.0))
+    }
+
+    /// every pixel tilted 30 degrees towards +u.
+    fn tilted() -> Canvas {
+        Canvas::from_fn(4, 2, |_, _| {
+            Color::new(0.75, 0.5, (1.0 + 3.0_f64.sqrt() / 2.0) / 2.0)
+        })
+    }
+
+    fn sphere(map: Canvas) -> Geometry {
+        Geometry::default().with_form(Form::Sphere).with_material(
+            Material::default().with_normal_map(NormalMap::new(map, UvMapping::Spherical)),
+        )
+    }
+
+    #[test]
+    fn flat_map_keeps_normals() {
+        let mapped = sphere(flat());
+        let plain = Geometry::default().with_form(Form::Sphere);
+        for point in [
+            Point::new(0.0, 0.0, -1.0),
+            Point::new(0.6, 0.0, 0.8),
+            Point::new(0.0, 0.6, -0.8),
+            Point::new(0.0, 1.0, 0.0),
+        ]
+        .iter()
+        {
+            assert_eq!(mapped.normal_at(*point), plain.normal_at(*point));
+        }
+    }
+
+    #[test]
+    fn flat_map_keeps_plane_normal() {
+        let plane = Geometry::default().with_form(Form::Plane).with_material(
+            Material::default().with_normal_map(NormalMap::new(flat(), UvMapping::Planar)),
+        );
+        assert_eq!(
+            plane.normal_at(Point::new(0.3, 0.0, 7.1)),
+            Some(Vector::new(0.0, 1.0, 0.0))
+        );
+    }
+
+    #[test]
+    fn tilted_map_turns_normals_towards_u() {
+        let mapped = sphere(tilted());
+        // on the front of the sphere, u grows towards +x.
+        assert_eq!(
+            mapped.normal_at(Point::new(0.0, 0.0, -1.0)),
+            Some(Vector::new(0.5, 0.0, -(3.0_f64.sqrt()) / 2.0))
+        );
+        // on the right of it, u grows towards +z.
+        assert_eq!(
+            mapped.normal_at(Point::new(1.0, 0.0, 0.0)),
+            Some(Vector::new(3.0_f64.sqrt() / 2.0, 0.0, 0.5))
+        );
+    }
+
+    #[test]
+    fn tilted_map_moves_lit_side() {
+        let shade = |map: Canvas| {
+            let lamp = Light::point(light::Point::new(
+                Point::new(10.0, 0.0, -10.0),
+                Color::white(),
+            ));
+            let world = World::new(vec![sphere(map)], vec![lamp]);
+            world.cast_ray(Ray::new(
+                Point::new(0.0, 0.0, -5.0),
+                Vector::new(0.0, 0.0, 1.0),
+            ))
+        };
+        // the front of the sphere now faces the light more squarely.
+        assert!(shade(flat()).red() < shade(tilted()).red());
+    }
+}
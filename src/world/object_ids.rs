@@ -0,0 +1,98 @@
+//! rendering which object each pixel sees, so that each object can be masked in post.
+
+use crate::{
+    math::XorShift,
+    world::{Camera, Canvas, Color, World},
+};
+
+impl Camera {
+    /// renders the index in `world.objects` of the closest object seen through the
+    /// center of each pixel, or none where the ray misses everything. the pixels run
+    /// from left to right and then from top to bottom. there is no anti-aliasing,
+    /// since indices can't be averaged.
+    pub fn render_object_ids(&self, world: &World) -> Vec<Option<usize>> {
+        let mut ids = Vec::with_capacity(self.image_width * self.image_height);
+        for y in 0..self.image_height {
+            for x in 0..self.image_width {
+                let sample = self.closest_object(world, x as f64 + 0.5, y as f64 + 0.5);
+                ids.push(sample.map(|(index, _)| index));
+            }
+        }
+        ids
+    }
+
+    /// like `render_object_ids`, but each object is given a color of its own, which
+    /// is the same for the same index every time. misses are black.
+    pub fn render_object_id_colors(&self, world: &World) -> Canvas {
+        let ids = self.render_object_ids(world);
+        Canvas::from_fn(self.image_width, self.image_height, |x, y| {
+            ids[x + y * self.image_width].map_or(Color::black(), id_color)
+        })
+    }
+}
+
+/// a bright, arbitrary color for an object index, so that neighboring indices are easy
+/// to tell apart and none of them can be mistaken for a miss.
+fn id_color(index: usize) -> Color {
+    let mut rng = XorShift::new(index as u64);
+    let mut channel = || 0.25 + 0.75 * rng.next_f64();
+    Color::new(channel(), channel(), channel())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        math::{Matrix, Point, Transformable, Vector},
+        world::View,
+    };
+    use std::f64::consts;
+
+    fn setup() -> (World, Camera) {
+        // the inner sphere is hidden inside the outer one, so it is brought out in
+        // front of it.
+        let mut w = World::default();
+        w.objects[1]
+            .transform(Matrix::translation(0.0, 0.0, -2.0) * Matrix::scaling(0.5, 0.5, 0.5));
+        let mut c = Camera::new(21, 21, consts::PI / 4.0);
+        c.set_view(View::transformed(
+            Point::new(0.0, 0.0, -5.0),
+            Point::zero(),
+            Vector::new(0.0, 1.0, 0.0),
+        ));
+        (w, c)
+    }
+
+    #[test]
+    fn each_object_gets_its_own_id() {
+        let (w, c) = setup();
+        let ids = c.render_object_ids(&w);
+        assert_eq!(ids.len(), 441);
+        let mut distinct = ids.clone();
+        distinct.sort_unstable();
+        distinct.dedup();
+        assert_eq!(distinct, vec![None, Some(0), Some(1)]);
+
+        // the inner sphere makes a disc in the middle of the outer one.
+        let inner: Vec<(usize, usize)> = (0..441)
+            .filter(|&i| ids[i] == Some(1))
+            .map(|i| (i % 21, i / 21))
+            .collect();
+        assert!(inner.contains(&(10, 10)));
+        for &(x, y) in inner.iter() {
+            assert!(inner.contains(&(20 - x, y)) && inner.contains(&(y, x)));
+        }
+        assert_eq!(ids[15 + 10 * 21], Some(0));
+        assert_eq!(ids[0], None);
+    }
+
+    #[test]
+    fn id_colors_are_stable() {
+        let (w, c) = setup();
+        let colors = c.render_object_id_colors(&w);
+        assert_eq!(colors[(0, 0)], Color::black());
+        assert_eq!(colors[(10, 10)], id_color(1));
+        assert_ne!(id_color(0), id_color(1));
+        assert_eq!(colors, c.render_object_id_colors(&w));
+    }
+}
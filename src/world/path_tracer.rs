@@ -0,0 +1,187 @@
+//! an alternative to the phong renderer that follows light as it bounces between
+//! diffuse surfaces, so that surfaces are also lit by each other.
+//!
+//! only surfaces with an `emissive` material (and the world's background) give off
+//! light here; the world's `lights` are ignored.
+
+use crate::{
+    math::{sample_hemisphere_cosine, Basis, Point, XorShift},
+    world::{Camera, Canvas, Color, Ray, Textured, World},
+};
+
+impl Camera {
+    /// renders `world` by averaging `samples_per_pixel` random light paths through each
+    /// pixel, each bouncing off at most `max_depth` surfaces.
+    ///
+    /// the paths are picked by the world's `path_seed`, so that the same seed gives the
+    /// same image.
+    pub fn render_path_traced(
+        &self,
+        world: &World,
+        samples_per_pixel: usize,
+        max_depth: u32,
+    ) -> Canvas {
+        let samples_per_pixel = samples_per_pixel.max(1);
+        Canvas::from_fn(self.image_width, self.image_height, |x, y| {
+            let pixel = Point::new(x as f64, y as f64, 0.0);
+            let mut rng = XorShift::at_point(world.settings.path_seed, pixel);
+
+            let mut color = Color::black();
+            for _ in 0..samples_per_pixel {
+                // spread the samples over the pixel, rather than through its center.
+                let ray =
+                    self.ray_for_position(x as f64 + rng.next_f64(), y as f64 + rng.next_f64());
+                color += radiance(world, ray, max_depth, &mut rng);
+            }
+            color / samples_per_pixel as f64
+        })
+    }
+}
+
+/// the light arriving along `ray`, following it through at most `depth` surfaces.
+fn radiance(world: &World, ray: Ray, depth: u32, rng: &mut XorShift) -> Color {
+    if depth == 0 {
+        return Color::black();
+    }
+
+    let intersections = match world.hit(ray) {
+        Some(intersections) => intersections,
+        None => return world.background.color_for(ray.direction),
+    };
+    let intersection = match intersections.closest() {
+        Some(intersection) => intersection,
+        None => return world.background.color_for(ray.direction),
+    };
+    let computations = intersection.compute_among(&intersections);
+    let material = &computations.material;
+
+    // a lambertian surface scatters light in proportion to the cosine of its angle to
+    // the normal, which is exactly how the bounce is sampled, so the two cancel out and
+    // the light brought back only needs to be tinted by the surface.
+    let albedo = intersection.object.color_at(computations.point) * material.diffuse;
+    let direction = Basis::from_normal(computations.surface_normal)
+        .to_world(sample_hemisphere_cosine(rng.next_f64(), rng.next_f64()));
+    let bounced = Ray::new(computations.over_point, direction);
+
+    material.emissive + albedo * radiance(world, bounced, depth - 1, rng)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        math::{Form, Geometry, Matrix, Transformable, Vector},
+        world::{Material, Pattern, Texture, View},
+    };
+    use std::f64::consts;
+
+    fn wall(color: Color, transform: Matrix) -> Geometry {
+        Geometry::default()
+            .with_form(Form::Plane)
+            .with_material(
+                Material::default().with_texture(Texture::pattern(Pattern::solid(color))),
+            )
+            .transformed(transform)
+    }
+
+    /// a white box lit from its ceiling, with a red wall on the left, a green wall on
+    /// the right, and a white ball in the middle.
+    fn cornell_box() -> (World, Camera) {
+        let white = Color::white();
+        let ceiling = Geometry::default()
+            .with_form(Form::Plane)
+            .with_material(Material::default().with_emissive(Color::new(1.5, 1.5, 1.5)))
+            .transformed(Matrix::translation(0.0, 4.0, 0.0));
+        let objects = vec![
+            wall(white, Matrix::identity()),
+            ceiling,
+            wall(
+                white,
+                Matrix::translation(0.0, 0.0, 2.0) * Matrix::rotation_x(consts::PI / 2.0),
+            ),
+            wall(
+                Color::new(1.0, 0.0, 0.0),
+                Matrix::translation(-2.0, 0.0, 0.0) * Matrix::rotation_z(consts::PI / 2.0),
+            ),
+            wall(
+                Color::new(0.0, 1.0, 0.0),
+                Matrix::translation(2.0, 0.0, 0.0) * Matrix::rotation_z(consts::PI / 2.0),
+            ),
+            Geometry::default()
+                .with_form(Form::Sphere)
+                .transformed(Matrix::translation(0.0, 1.0, 0.0)),
+        ];
+        let world = World::new(objects, vec![]);
+
+        let mut camera = Camera::new(15, 15, consts::PI / 3.0);
+        camera.set_view(View::transformed(
+            Point::new(0.0, 1.0, -4.0),
+            Point::new(0.0, 1.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+        ));
+        (world, camera)
+    }
+
+    fn assert_black(image: &Canvas) {
+        for y in 0..image.height {
+            for x in 0..image.width {
+                assert_eq!(image[(x, y)], Color::black());
+            }
+        }
+    }
+
+    #[test]
+    fn walls_bleed_color_onto_white_ball() {
+        let (world, camera) = cornell_box();
+        let image = camera.render_path_traced(&world, 64, 4);
+        // the left and right edges of the ball face the red and green walls.
+        let (left, right) = (image[(5, 7)], image[(9, 7)]);
+        assert_eq!(
+            world
+                .hit(camera.ray_for_pixel(5, 7))
+                .unwrap()
+                .closest()
+                .unwrap()
+                .object
+                .form,
+            Form::Sphere
+        );
+        assert_eq!(
+            world
+                .hit(camera.ray_for_pixel(9, 7))
+                .unwrap()
+                .closest()
+                .unwrap()
+                .object
+                .form,
+            Form::Sphere
+        );
+        assert!(left.green() < left.red());
+        assert!(right.red() < right.green());
+    }
+
+    #[test]
+    fn same_seed_gives_same_image() {
+        let (world, camera) = cornell_box();
+        let a = camera.render_path_traced(&world, 2, 3);
+        let b = camera.render_path_traced(&world, 2, 3);
+        assert!(a.approx_eq(&b, 0.0));
+    }
+
+    #[test]
+    fn nothing_emissive_is_dark() {
+        let (mut world, camera) = cornell_box();
+        world.objects[1].make_material_unique().emissive = Color::black();
+        let image = camera.render_path_traced(&world, 4, 1);
+        assert_black(&image);
+    }
+
+    #[test]
+    fn point_lights_are_ignored() {
+        let (mut world, camera) = cornell_box();
+        world.objects[1].make_material_unique().emissive = Color::black();
+        world.lights = World::default().lights;
+        let image = camera.render_path_traced(&world, 4, 3);
+        assert_black(&image);
+    }
+}
@@ -3,12 +3,18 @@ use crate::{
     world::{Color, Textured},
 };
 
+pub mod blend;
+pub use blend::{Blend, BlendMode};
+
 pub mod gradient;
-pub use gradient::Gradient;
+pub use gradient::{Gradient, GradientMode};
 
 pub mod grid;
 pub use grid::Grid;
 
+pub mod paint;
+pub use paint::Paint;
+
 pub mod ring;
 pub use ring::Ring;
 
@@ -18,16 +24,35 @@ pub use solid::Solid;
 pub mod stripe;
 pub use stripe::Stripe;
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+pub mod uv_checkers;
+pub use uv_checkers::UvCheckers;
+
+/// finds the inverse of a pattern's transform. a transform that squashes the pattern
+/// flat has no inverse, so every point is sent to the pattern's origin instead, which
+/// gives it a single color everywhere rather than NaN.
+pub(crate) fn inverse_of(transform: Matrix) -> Matrix {
+    transform
+        .try_inverse()
+        .unwrap_or_else(|| Matrix::scaling(0.0, 0.0, 0.0))
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Pattern {
+    Blend(Blend),
     Gradient(Gradient),
     Grid(Grid),
     Ring(Ring),
     Solid(Solid),
     Stripe(Stripe),
+    UvCheckers(UvCheckers),
 }
 
 impl Pattern {
+    pub fn blend(blend: Blend) -> Pattern {
+        Pattern::Blend(blend)
+    }
+
     pub fn gradient(gradient: Gradient) -> Pattern {
         Pattern::Gradient(gradient)
     }
@@ -47,27 +72,49 @@ impl Pattern {
     pub fn stripe(stripe: Stripe) -> Pattern {
         Pattern::Stripe(stripe)
     }
+
+    pub fn uv_checkers(uv_checkers: UvCheckers) -> Pattern {
+        Pattern::UvCheckers(uv_checkers)
+    }
 }
 
 impl Transformable for Pattern {
     fn transformed(self, transform: Matrix) -> Pattern {
         match self {
+            Pattern::Blend(blend) => Pattern::blend(blend.transformed(transform)),
             Pattern::Gradient(gradient) => Pattern::gradient(gradient.transformed(transform)),
             Pattern::Grid(grid) => Pattern::grid(grid.transformed(transform)),
             Pattern::Ring(ring) => Pattern::ring(ring.transformed(transform)),
             Pattern::Solid(_) => self,
             Pattern::Stripe(stripe) => Pattern::stripe(stripe.transformed(transform)),
+            Pattern::UvCheckers(uv_checkers) => {
+                Pattern::uv_checkers(uv_checkers.transformed(transform))
+            }
         }
     }
 
     fn transform(&mut self, transform: Matrix) -> &mut Pattern {
-        *self = match self {
-            Pattern::Gradient(gradient) => Pattern::gradient(gradient.transformed(transform)),
-            Pattern::Grid(grid) => Pattern::grid(grid.transformed(transform)),
-            Pattern::Ring(ring) => Pattern::ring(ring.transformed(transform)),
-            Pattern::Solid(_) => *self,
-            Pattern::Stripe(stripe) => Pattern::stripe(stripe.transformed(transform)),
-        };
+        match self {
+            Pattern::Blend(blend) => {
+                blend.transform(transform);
+            }
+            Pattern::Gradient(gradient) => {
+                gradient.transform(transform);
+            }
+            Pattern::Grid(grid) => {
+                grid.transform(transform);
+            }
+            Pattern::Ring(ring) => {
+                ring.transform(transform);
+            }
+            Pattern::Solid(_) => {}
+            Pattern::Stripe(stripe) => {
+                stripe.transform(transform);
+            }
+            Pattern::UvCheckers(uv_checkers) => {
+                uv_checkers.transform(transform);
+            }
+        }
         self
     }
 }
@@ -75,11 +122,13 @@ impl Transformable for Pattern {
 impl Textured for Pattern {
     fn color_at(&self, object_space_point: Point) -> Color {
         match self {
+            Pattern::Blend(blend) => blend.color_at(object_space_point),
             Pattern::Gradient(gradient) => gradient.color_at(object_space_point),
             Pattern::Grid(grid) => grid.color_at(object_space_point),
             Pattern::Ring(ring) => ring.color_at(object_space_point),
             Pattern::Solid(solid) => solid.color_at(object_space_point),
             Pattern::Stripe(stripe) => stripe.color_at(object_space_point),
+            Pattern::UvCheckers(uv_checkers) => uv_checkers.color_at(object_space_point),
         }
     }
 }
@@ -96,6 +145,61 @@ mod tests {
         (Color::black(), Color::white())
     }
 
+    #[test]
+    fn transforming_keeps_inverse_consistent() {
+        let (black, white) = setup();
+        let transform = Matrix::translation(1.0, 2.0, 3.0) * Matrix::scaling(2.0, 0.5, 4.0);
+        let mut gradient = Gradient::new(black, white).transformed(transform);
+        let mut grid = Grid::new(black, white).transformed(transform);
+        let mut ring = Ring::new(black, white).transformed(transform);
+        let mut stripe = Stripe::new(black, white).transformed(transform);
+        for (transformation, inverse) in [
+            (gradient.transformation(), gradient.inverse()),
+            (grid.transformation(), grid.inverse()),
+            (ring.transformation(), ring.inverse()),
+            (stripe.transformation(), stripe.inverse()),
+        ]
+        .iter()
+        {
+            assert_eq!(*transformation, transform);
+            assert_eq!(*inverse, transform.inverse());
+        }
+
+        let transform = Matrix::rotation_x(1.0);
+        gradient.transform(transform);
+        grid.transform(transform);
+        ring.transform(transform);
+        stripe.transform(transform);
+        for (transformation, inverse) in [
+            (gradient.transformation(), gradient.inverse()),
+            (grid.transformation(), grid.inverse()),
+            (ring.transformation(), ring.inverse()),
+            (stripe.transformation(), stripe.inverse()),
+        ]
+        .iter()
+        {
+            assert_eq!(*transformation * *inverse, Matrix::identity());
+        }
+    }
+
+    #[test]
+    fn flattened_pattern_has_one_color() {
+        let (black, white) = setup();
+        let squashed = Matrix::translation(0.5, 0.0, 0.0) * Matrix::scaling(0.0, 1.0, 1.0);
+        let patterns = [
+            Pattern::gradient(Gradient::new(black, white)),
+            Pattern::grid(Grid::new(black, white)),
+            Pattern::ring(Ring::new(black, white)),
+            Pattern::stripe(Stripe::new(black, white)),
+        ];
+        for pattern in patterns.iter() {
+            let pattern = pattern.clone().transformed(squashed);
+            let origin = pattern.color_at(Point::zero());
+            assert!(origin.is_finite());
+            assert_eq!(pattern.color_at(Point::new(3.7, -1.2, 8.1)), origin);
+        }
+    }
+
     #[test]
     fn create_stripes() {
         let (black, white) = setup();
@@ -0,0 +1,187 @@
+use crate::{
+    math::{Matrix, Point, Transformable},
+    world::{pattern::inverse_of, Color, Pattern, Textured},
+};
+
+/// how the colors of the two patterns in a `Blend` are combined.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BlendMode {
+    /// halfway between the two colors.
+    Average,
+    /// the product of the two colors, which can only darken.
+    Multiply,
+    /// the inverse of the product of the inverted colors, which can only brighten.
+    Screen,
+    /// the first color weighted by `1 - w` plus the second color weighted by `w`.
+    Weighted(f64),
+}
+
+impl BlendMode {
+    pub fn combine(&self, a: Color, b: Color) -> Color {
+        match self {
+            BlendMode::Average => (a + b) * 0.5,
+            BlendMode::Multiply => a * b,
+            BlendMode::Screen => Color::white() - (Color::white() - a) * (Color::white() - b),
+            BlendMode::Weighted(w) => a * (1.0 - w) + b * *w,
+        }
+    }
+}
+
+/// two patterns laid over each other.
+///
+/// the blend's own transform moves both patterns together, and each pattern's
+/// transform then moves it relative to the blend.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "SerializedBlend")
+)]
+pub struct Blend {
+    a: Box<Pattern>,
+    b: Box<Pattern>,
+    pub mode: BlendMode,
+    transform: Matrix,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    inverse: Matrix,
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct SerializedBlend {
+    a: Box<Pattern>,
+    b: Box<Pattern>,
+    mode: BlendMode,
+    transform: Matrix,
+}
+
+#[cfg(feature = "serde")]
+impl From<SerializedBlend> for Blend {
+    fn from(serialized: SerializedBlend) -> Blend {
+        Blend::new(*serialized.a, *serialized.b, serialized.mode).transformed(serialized.transform)
+    }
+}
+
+impl Blend {
+    pub fn new(a: Pattern, b: Pattern, mode: BlendMode) -> Blend {
+        Blend {
+            a: Box::new(a),
+            b: Box::new(b),
+            mode,
+            transform: Matrix::identity(),
+            inverse: Matrix::identity(),
+        }
+    }
+
+    pub fn transformation(&self) -> Matrix {
+        self.transform
+    }
+
+    pub fn inverse(&self) -> Matrix {
+        self.inverse
+    }
+
+    pub fn debug_assert_consistent(&self) {
+        self.transform.debug_assert_inverse(&self.inverse, "Blend");
+    }
+}
+
+impl Transformable for Blend {
+    fn transformed(self, transform: Matrix) -> Blend {
+        Blend {
+            transform,
+            inverse: inverse_of(transform),
+            ..self
+        }
+    }
+
+    fn transform(&mut self, transform: Matrix) -> &mut Blend {
+        self.transform = transform;
+        self.inverse = inverse_of(transform);
+        self
+    }
+}
+
+impl Textured for Blend {
+    fn color_at(&self, object_space_point: Point) -> Color {
+        self.debug_assert_consistent();
+        let pattern_space_point = self.inverse * object_space_point;
+        self.mode.combine(
+            self.a.color_at(pattern_space_point),
+            self.b.color_at(pattern_space_point),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::pattern::Stripe;
+    use std::f64::consts;
+
+    fn setup() -> (Color, Color) {
+        (Color::black(), Color::white())
+    }
+
+    fn crossed_stripes() -> Blend {
+        let (black, white) = setup();
+        Blend::new(
+            Pattern::stripe(Stripe::new(white, black)),
+            Pattern::stripe(Stripe::new(white, black))
+                .transformed(Matrix::rotation_y(consts::PI / 2.0)),
+            BlendMode::Average,
+        )
+    }
+
+    #[test]
+    fn average_of_crossed_stripes() {
+        let (black, white) = setup();
+        let blend = crossed_stripes();
+        assert_eq!(blend.color_at(Point::new(0.5, 0.0, -0.5)), white);
+        assert_eq!(
+            blend.color_at(Point::new(1.5, 0.0, -0.5)),
+            Color::new(0.5, 0.5, 0.5)
+        );
+        assert_eq!(
+            blend.color_at(Point::new(0.5, 0.0, 0.5)),
+            Color::new(0.5, 0.5, 0.5)
+        );
+        assert_eq!(blend.color_at(Point::new(1.5, 0.0, 0.5)), black);
+    }
+
+    #[test]
+    fn blend_transform_moves_both_children() {
+        let (black, white) = setup();
+        let blend = crossed_stripes().transformed(Matrix::translation(1.0, 0.0, 1.0));
+        assert_eq!(blend.color_at(Point::new(1.5, 0.0, 0.5)), white);
+        assert_eq!(blend.color_at(Point::new(0.5, 0.0, 1.5)), black);
+        // the children keep their own transforms.
+        assert_eq!(
+            blend.color_at(Point::new(2.5, 0.0, 0.5)),
+            Color::new(0.5, 0.5, 0.5)
+        );
+    }
+
+    #[test]
+    fn blend_modes() {
+        let a = Color::new(0.2, 0.5, 1.0);
+        let b = Color::new(0.5, 0.5, 0.0);
+        assert_eq!(BlendMode::Average.combine(a, b), Color::new(0.35, 0.5, 0.5));
+        assert_eq!(
+            BlendMode::Multiply.combine(a, b),
+            Color::new(0.1, 0.25, 0.0)
+        );
+        assert_eq!(BlendMode::Screen.combine(a, b), Color::new(0.6, 0.75, 1.0));
+        assert_eq!(
+            BlendMode::Weighted(0.25).combine(a, b),
+            Color::new(0.275, 0.5, 0.75)
+        );
+    }
+
+    #[test]
+    fn default_transformation() {
+        let blend = crossed_stripes();
+        assert_eq!(blend.transformation(), Matrix::identity());
+    }
+}
@@ -1,65 +1,152 @@
 use crate::{
-    math::{Matrix, Point, Transformable},
-    world::{Color, Textured},
+    math::{clamp_between, Matrix, Point, Transformable},
+    world::{
+        pattern::{inverse_of, Paint},
+        Color, Textured,
+    },
 };
 
 use std::ops::{Index, IndexMut};
 
+/// what a `Gradient` does outside of the unit it blends across.
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GradientMode {
+    /// starts again from the first color at every whole unit.
+    Repeat,
+    /// runs back from the second color to the first over the next unit, and so on.
+    Mirror,
+    /// stays the first color before the unit, and the second color after it.
+    Clamp,
+}
+
+impl GradientMode {
+    /// how far from the first color to the second `x` is, from 0 to 1.
+    pub fn fraction(&self, x: f64) -> f64 {
+        match self {
+            GradientMode::Repeat => x - x.floor(),
+            GradientMode::Mirror => {
+                let fraction = x.rem_euclid(2.0);
+                if fraction > 1.0 {
+                    2.0 - fraction
+                } else {
+                    fraction
+                }
+            }
+            GradientMode::Clamp => clamp_between(x, 0.0, 1.0),
+        }
+    }
+}
+
+/// blends from one color to another along x, between x = 0 and x = 1.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "SerializedGradient")
+)]
 pub struct Gradient {
-    a: Color,
-    b: Color,
-    pub transform: Matrix,
-    pub inverse: Matrix,
+    a: Paint,
+    b: Paint,
+    pub mode: GradientMode,
+    transform: Matrix,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    inverse: Matrix,
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct SerializedGradient {
+    a: Paint,
+    b: Paint,
+    mode: GradientMode,
+    transform: Matrix,
+}
+
+#[cfg(feature = "serde")]
+impl From<SerializedGradient> for Gradient {
+    fn from(serialized: SerializedGradient) -> Gradient {
+        Gradient::new(serialized.a, serialized.b)
+            .with_mode(serialized.mode)
+            .transformed(serialized.transform)
+    }
 }
 
 impl Gradient {
-    pub fn new(a: Color, b: Color) -> Gradient {
+    pub fn new<A: Into<Paint>, B: Into<Paint>>(a: A, b: B) -> Gradient {
         Gradient {
-            a,
-            b,
+            a: a.into(),
+            b: b.into(),
+            mode: GradientMode::Repeat,
             transform: Matrix::identity(),
             inverse: Matrix::identity(),
         }
     }
+
+    pub fn with_mode(self, mode: GradientMode) -> Gradient {
+        Gradient { mode, ..self }
+    }
+
+    pub fn transformation(&self) -> Matrix {
+        self.transform
+    }
+
+    pub fn inverse(&self) -> Matrix {
+        self.inverse
+    }
+
+    pub fn debug_assert_consistent(&self) {
+        self.transform
+            .debug_assert_inverse(&self.inverse, "Gradient");
+    }
 }
 
 impl Transformable for Gradient {
     fn transformed(self, transform: Matrix) -> Gradient {
         Gradient {
-            a: self.a,
-            b: self.b,
             transform,
-            inverse: transform.inverse(),
+            inverse: inverse_of(transform),
+            ..self
         }
     }
 
     fn transform(&mut self, transform: Matrix) -> &mut Gradient {
-        *self = self.transformed(transform);
+        self.transform = transform;
+        self.inverse = inverse_of(transform);
         self
     }
 }
 
 impl Textured for Gradient {
     fn color_at(&self, object_space_point: Point) -> Color {
+        self.debug_assert_consistent();
         let pattern_space_point = self.inverse * object_space_point;
-        let distance = self.b - self.a;
-        let fraction = pattern_space_point[0] - pattern_space_point[0].floor();
-        self.a + distance * fraction
+        let a = self.a.color_at(pattern_space_point);
+        let b = self.b.color_at(pattern_space_point);
+        let fraction = self.mode.fraction(pattern_space_point[0]);
+        a.lerp(b, fraction)
     }
 }
 
 impl Index<usize> for Gradient {
-    type Output = Color;
+    type Output = Paint;
 
     fn index(&self, i: usize) -> &Self::Output {
-        unsafe { &std::mem::transmute::<&Gradient, &[Color; 2]>(self)[i] }
+        match i {
+            0 => &self.a,
+            1 => &self.b,
+            _ => panic!("pattern index out of bounds: {}", i),
+        }
     }
 }
 
 impl IndexMut<usize> for Gradient {
-    fn index_mut(&mut self, i: usize) -> &mut Color {
-        unsafe { &mut std::mem::transmute::<&mut Gradient, &mut [Color; 2]>(self)[i] }
+    fn index_mut(&mut self, i: usize) -> &mut Paint {
+        match i {
+            0 => &mut self.a,
+            1 => &mut self.b,
+            _ => panic!("pattern index out of bounds: {}", i),
+        }
     }
 }
 
@@ -84,4 +171,49 @@ mod tests {
             Color::new(0.25, 0.25, 0.25)
         );
     }
+
+    #[test]
+    fn repeated_gradient_jumps_back() {
+        let pattern = Gradient::new(Color::white(), Color::black());
+        assert_eq!(pattern.color_at(Point::new(1.0, 0.0, 0.0)), Color::white());
+        assert_eq!(
+            pattern.color_at(Point::new(1.5, 0.0, 0.0)),
+            Color::new(0.5, 0.5, 0.5)
+        );
+    }
+
+    #[test]
+    fn mirrored_gradient() {
+        let pattern = Gradient::new(Color::white(), Color::black()).with_mode(GradientMode::Mirror);
+        let cases = [
+            (0.5, 0.5),
+            (1.0, 0.0),
+            (1.5, 0.5),
+            (2.0, 1.0),
+            (2.5, 0.5),
+            (-0.25, 0.75),
+        ];
+        for (x, grey) in cases.iter() {
+            assert_eq!(
+                pattern.color_at(Point::new(*x, 0.0, 0.0)),
+                Color::new(*grey, *grey, *grey),
+                "at x = {}",
+                x
+            );
+        }
+    }
+
+    #[test]
+    fn clamped_gradient() {
+        let pattern = Gradient::new(Color::white(), Color::black()).with_mode(GradientMode::Clamp);
+        let cases = [(-1.0, 1.0), (0.5, 0.5), (1.0, 0.0), (1.5, 0.0), (2.5, 0.0)];
+        for (x, grey) in cases.iter() {
+            assert_eq!(
+                pattern.color_at(Point::new(*x, 0.0, 0.0)),
+                Color::new(*grey, *grey, *grey),
+                "at x = {}",
+                x
+            );
+        }
+    }
 }
@@ -1,27 +1,64 @@
 use crate::{
     math::{Matrix, Point, Transformable},
-    world::{Color, Textured},
+    world::{
+        pattern::{inverse_of, Paint},
+        Color, Textured,
+    },
 };
 
 use std::ops::{Index, IndexMut};
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "SerializedGrid")
+)]
 pub struct Grid {
-    a: Color,
-    b: Color,
-    pub transform: Matrix,
-    pub inverse: Matrix,
+    a: Paint,
+    b: Paint,
+    transform: Matrix,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    inverse: Matrix,
+}
+
+/// a grid as it is serialized. the inverse is worked out again from the transform.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct SerializedGrid {
+    a: Paint,
+    b: Paint,
+    transform: Matrix,
+}
+
+#[cfg(feature = "serde")]
+impl From<SerializedGrid> for Grid {
+    fn from(serialized: SerializedGrid) -> Grid {
+        Grid::new(serialized.a, serialized.b).transformed(serialized.transform)
+    }
 }
 
 impl Grid {
-    pub fn new(a: Color, b: Color) -> Grid {
+    pub fn new<A: Into<Paint>, B: Into<Paint>>(a: A, b: B) -> Grid {
         Grid {
-            a,
-            b,
+            a: a.into(),
+            b: b.into(),
             transform: Matrix::identity(),
             inverse: Matrix::identity(),
         }
     }
+
+    pub fn transformation(&self) -> Matrix {
+        self.transform
+    }
+
+    pub fn inverse(&self) -> Matrix {
+        self.inverse
+    }
+
+    pub fn debug_assert_consistent(&self) {
+        self.transform.debug_assert_inverse(&self.inverse, "Grid");
+    }
 }
 
 impl Transformable for Grid {
@@ -30,44 +67,56 @@ impl Transformable for Grid {
             a: self.a,
             b: self.b,
             transform,
-            inverse: transform.inverse(),
+            inverse: inverse_of(transform),
         }
     }
 
     fn transform(&mut self, transform: Matrix) -> &mut Grid {
-        *self = self.transformed(transform);
+        self.transform = transform;
+        self.inverse = inverse_of(transform);
         self
     }
 }
 
 impl Textured for Grid {
     fn color_at(&self, object_space_point: Point) -> Color {
+        self.debug_assert_consistent();
         let pattern_space_point = self.inverse * object_space_point;
-        self[((pattern_space_point[0].floor()
+        let i = (pattern_space_point[0].floor()
             + pattern_space_point[1].floor()
             + pattern_space_point[2].floor())
         .rem_euclid(2.0)
-        .floor()) as usize]
+        .floor() as usize;
+        self[i].color_at(pattern_space_point)
     }
 }
 
 impl Index<usize> for Grid {
-    type Output = Color;
+    type Output = Paint;
 
     fn index(&self, i: usize) -> &Self::Output {
-        unsafe { &std::mem::transmute::<&Grid, &[Color; 2]>(self)[i] }
+        match i {
+            0 => &self.a,
+            1 => &self.b,
+            _ => panic!("pattern index out of bounds: {}", i),
+        }
     }
 }
 
 impl IndexMut<usize> for Grid {
-    fn index_mut(&mut self, i: usize) -> &mut Color {
-        unsafe { &mut std::mem::transmute::<&mut Grid, &mut [Color; 2]>(self)[i] }
+    fn index_mut(&mut self, i: usize) -> &mut Paint {
+        match i {
+            0 => &mut self.a,
+            1 => &mut self.b,
+            _ => panic!("pattern index out of bounds: {}", i),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::world::{pattern::Stripe, Pattern};
 
     #[test]
     fn repeats_in_x() {
@@ -98,4 +147,28 @@ mod tests {
         assert_eq!(pattern.color_at(Point::new(0.0, 0.0, 0.99)), Color::white());
         assert_eq!(pattern.color_at(Point::new(0.0, 0.0, 1.01)), Color::black());
     }
+
+    #[test]
+    fn checker_of_stripes() {
+        let stripes = Pattern::stripe(
+            Stripe::new(Color::white(), Color::black())
+                .transformed(Matrix::scaling(0.25, 1.0, 1.0)),
+        );
+        let pattern = Grid::new(stripes, Color::new(0.5, 0.5, 0.5));
+        // the stripes show in the first cell...
+        assert_eq!(pattern.color_at(Point::new(0.1, 0.0, 0.0)), Color::white());
+        assert_eq!(pattern.color_at(Point::new(0.3, 0.0, 0.0)), Color::black());
+        // ...but not in the next one along...
+        assert_eq!(
+            pattern.color_at(Point::new(1.1, 0.0, 0.0)),
+            Color::new(0.5, 0.5, 0.5)
+        );
+        assert_eq!(
+            pattern.color_at(Point::new(1.3, 0.0, 0.0)),
+            Color::new(0.5, 0.5, 0.5)
+        );
+        // ...and again in the one after that.
+        assert_eq!(pattern.color_at(Point::new(2.1, 0.0, 0.0)), Color::white());
+        assert_eq!(pattern.color_at(Point::new(2.3, 0.0, 0.0)), Color::black());
+    }
 }
@@ -0,0 +1,43 @@
+use crate::{
+    math::Point,
+    world::{Color, Pattern, Textured},
+};
+
+/// what fills one of the slots of a two-color pattern: either a plain color, or
+/// another pattern, which is found at the same pattern-space point as its parent.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Paint {
+    Color(Color),
+    Pattern(Box<Pattern>),
+}
+
+impl Textured for Paint {
+    fn color_at(&self, pattern_space_point: Point) -> Color {
+        match self {
+            Paint::Color(color) => *color,
+            Paint::Pattern(pattern) => pattern.color_at(pattern_space_point),
+        }
+    }
+}
+
+impl From<Color> for Paint {
+    fn from(color: Color) -> Paint {
+        Paint::Color(color)
+    }
+}
+
+impl From<Pattern> for Paint {
+    fn from(pattern: Pattern) -> Paint {
+        Paint::Pattern(Box::new(pattern))
+    }
+}
+
+impl PartialEq<Color> for Paint {
+    fn eq(&self, color: &Color) -> bool {
+        match self {
+            Paint::Color(own) => own == color,
+            Paint::Pattern(_) => false,
+        }
+    }
+}
@@ -1,27 +1,63 @@
 use crate::{
     math::{Matrix, Point, Transformable},
-    world::{Color, Textured},
+    world::{
+        pattern::{inverse_of, Paint},
+        Color, Textured,
+    },
 };
 
 use std::ops::{Index, IndexMut};
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "SerializedRing")
+)]
 pub struct Ring {
-    a: Color,
-    b: Color,
-    pub transform: Matrix,
-    pub inverse: Matrix,
+    a: Paint,
+    b: Paint,
+    transform: Matrix,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    inverse: Matrix,
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct SerializedRing {
+    a: Paint,
+    b: Paint,
+    transform: Matrix,
+}
+
+#[cfg(feature = "serde")]
+impl From<SerializedRing> for Ring {
+    fn from(serialized: SerializedRing) -> Ring {
+        Ring::new(serialized.a, serialized.b).transformed(serialized.transform)
+    }
 }
 
 impl Ring {
-    pub fn new(a: Color, b: Color) -> Ring {
+    pub fn new<A: Into<Paint>, B: Into<Paint>>(a: A, b: B) -> Ring {
         Ring {
-            a,
-            b,
+            a: a.into(),
+            b: b.into(),
             transform: Matrix::identity(),
             inverse: Matrix::identity(),
         }
     }
+
+    pub fn transformation(&self) -> Matrix {
+        self.transform
+    }
+
+    pub fn inverse(&self) -> Matrix {
+        self.inverse
+    }
+
+    pub fn debug_assert_consistent(&self) {
+        self.transform.debug_assert_inverse(&self.inverse, "Ring");
+    }
 }
 
 impl Transformable for Ring {
@@ -30,38 +66,49 @@ impl Transformable for Ring {
             a: self.a,
             b: self.b,
             transform,
-            inverse: transform.inverse(),
+            inverse: inverse_of(transform),
         }
     }
 
     fn transform(&mut self, transform: Matrix) -> &mut Ring {
-        *self = self.transformed(transform);
+        self.transform = transform;
+        self.inverse = inverse_of(transform);
         self
     }
 }
 
 impl Textured for Ring {
     fn color_at(&self, object_space_point: Point) -> Color {
+        self.debug_assert_consistent();
         let pattern_space_point = self.inverse * object_space_point;
-        self[((pattern_space_point[0] * pattern_space_point[0]
+        let i = (pattern_space_point[0] * pattern_space_point[0]
             + pattern_space_point[2] * pattern_space_point[2])
             .sqrt()
             .rem_euclid(2.0)
-            .floor()) as usize]
+            .floor() as usize;
+        self[i].color_at(pattern_space_point)
     }
 }
 
 impl Index<usize> for Ring {
-    type Output = Color;
+    type Output = Paint;
 
     fn index(&self, i: usize) -> &Self::Output {
-        unsafe { &std::mem::transmute::<&Ring, &[Color; 2]>(self)[i] }
+        match i {
+            0 => &self.a,
+            1 => &self.b,
+            _ => panic!("pattern index out of bounds: {}", i),
+        }
     }
 }
 
 impl IndexMut<usize> for Ring {
-    fn index_mut(&mut self, i: usize) -> &mut Color {
-        unsafe { &mut std::mem::transmute::<&mut Ring, &mut [Color; 2]>(self)[i] }
+    fn index_mut(&mut self, i: usize) -> &mut Paint {
+        match i {
+            0 => &mut self.a,
+            1 => &mut self.b,
+            _ => panic!("pattern index out of bounds: {}", i),
+        }
     }
 }
 
@@ -4,6 +4,7 @@ use crate::{
 };
 
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Solid {
     pub color: Color,
 }
@@ -15,7 +16,7 @@ impl Solid {
 }
 
 impl Textured for Solid {
-    fn color_at(&self, point: Point) -> Color {
+    fn color_at(&self, _point: Point) -> Color {
         self.color
     }
 }
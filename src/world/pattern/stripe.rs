@@ -1,63 +1,144 @@
 use crate::{
-    math::{Matrix, Point, Transformable},
-    world::{Color, Textured},
+    math::{Axis, Matrix, Point, Transformable},
+    world::{
+        pattern::{inverse_of, Paint},
+        Color, Textured,
+    },
 };
 
 use std::ops::{Index, IndexMut};
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+/// bands of two colors which alternate along `axis`.
+///
+/// the pattern repeats every `width` units, and the first color takes up `duty` of
+/// each repeat, starting from where it begins. by default each band is one unit wide.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "SerializedStripe")
+)]
 pub struct Stripe {
-    a: Color,
-    b: Color,
-    pub transform: Matrix,
-    pub inverse: Matrix,
+    a: Paint,
+    b: Paint,
+    pub axis: Axis,
+    pub width: f64,
+    pub duty: f64,
+    transform: Matrix,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    inverse: Matrix,
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct SerializedStripe {
+    a: Paint,
+    b: Paint,
+    axis: Axis,
+    width: f64,
+    duty: f64,
+    transform: Matrix,
+}
+
+#[cfg(feature = "serde")]
+impl From<SerializedStripe> for Stripe {
+    fn from(serialized: SerializedStripe) -> Stripe {
+        Stripe {
+            axis: serialized.axis,
+            width: serialized.width,
+            duty: serialized.duty,
+            ..Stripe::new(serialized.a, serialized.b)
+        }
+        .transformed(serialized.transform)
+    }
 }
 
 impl Stripe {
-    pub fn new(a: Color, b: Color) -> Stripe {
+    pub fn new<A: Into<Paint>, B: Into<Paint>>(a: A, b: B) -> Stripe {
         Stripe {
-            a,
-            b,
+            a: a.into(),
+            b: b.into(),
+            axis: Axis::X,
+            width: 2.0,
+            duty: 0.5,
             transform: Matrix::identity(),
             inverse: Matrix::identity(),
         }
     }
+
+    pub fn with_axis(self, axis: Axis) -> Stripe {
+        Stripe { axis, ..self }
+    }
+
+    pub fn with_width(self, width: f64) -> Stripe {
+        Stripe { width, ..self }
+    }
+
+    pub fn with_duty(self, duty: f64) -> Stripe {
+        Stripe { duty, ..self }
+    }
+
+    pub fn transformation(&self) -> Matrix {
+        self.transform
+    }
+
+    pub fn inverse(&self) -> Matrix {
+        self.inverse
+    }
+
+    pub fn debug_assert_consistent(&self) {
+        self.transform.debug_assert_inverse(&self.inverse, "Stripe");
+    }
 }
 
 impl Transformable for Stripe {
     fn transformed(self, transform: Matrix) -> Stripe {
         Stripe {
-            a: self.a,
-            b: self.b,
             transform,
-            inverse: transform.inverse(),
+            inverse: inverse_of(transform),
+            ..self
         }
     }
 
     fn transform(&mut self, transform: Matrix) -> &mut Stripe {
-        *self = self.transformed(transform);
+        self.transform = transform;
+        self.inverse = inverse_of(transform);
         self
     }
 }
 
 impl Textured for Stripe {
     fn color_at(&self, object_space_point: Point) -> Color {
+        self.debug_assert_consistent();
         let pattern_space_point = self.inverse * object_space_point;
-        self[(pattern_space_point[0].rem_euclid(2.0).floor()) as usize]
+        let position = (pattern_space_point[self.axis.index()] / self.width).rem_euclid(1.0);
+        if position < self.duty {
+            self.a.color_at(pattern_space_point)
+        } else {
+            self.b.color_at(pattern_space_point)
+        }
     }
 }
 
 impl Index<usize> for Stripe {
-    type Output = Color;
+    type Output = Paint;
 
     fn index(&self, i: usize) -> &Self::Output {
-        unsafe { &std::mem::transmute::<&Stripe, &[Color; 2]>(self)[i] }
+        match i {
+            0 => &self.a,
+            1 => &self.b,
+            _ => panic!("pattern index out of bounds: {}", i),
+        }
     }
 }
 
 impl IndexMut<usize> for Stripe {
-    fn index_mut(&mut self, i: usize) -> &mut Color {
-        unsafe { &mut std::mem::transmute::<&mut Stripe, &mut [Color; 2]>(self)[i] }
+    fn index_mut(&mut self, i: usize) -> &mut Paint {
+        match i {
+            0 => &mut self.a,
+            1 => &mut self.b,
+            _ => panic!("pattern index out of bounds: {}", i),
+        }
     }
 }
 
@@ -99,17 +180,90 @@ mod tests {
         assert_eq!(stripe.color_at(Point::new(-1.1, 0.0, 0.0)), white);
     }
 
+    #[test]
+    fn y_stripe_is_constant_in_x() {
+        let (black, white) = setup();
+        let stripe = Stripe::new(white, black).with_axis(Axis::Y);
+        assert_eq!(stripe.color_at(Point::new(0.0, 0.0, 0.0)), white);
+        assert_eq!(stripe.color_at(Point::new(1.0, 0.0, 0.0)), white);
+        assert_eq!(stripe.color_at(Point::new(2.0, 0.0, 0.0)), white);
+    }
+
+    #[test]
+    fn y_stripe_is_constant_in_z() {
+        let (black, white) = setup();
+        let stripe = Stripe::new(white, black).with_axis(Axis::Y);
+        assert_eq!(stripe.color_at(Point::new(0.0, 0.0, 0.0)), white);
+        assert_eq!(stripe.color_at(Point::new(0.0, 0.0, 1.0)), white);
+        assert_eq!(stripe.color_at(Point::new(0.0, 0.0, 2.0)), white);
+    }
+
+    #[test]
+    fn y_stripe_alternates_in_y() {
+        let (black, white) = setup();
+        let stripe = Stripe::new(white, black).with_axis(Axis::Y);
+        assert_eq!(stripe.color_at(Point::new(0.0, 0.0, 0.0)), white);
+        assert_eq!(stripe.color_at(Point::new(0.0, 0.9, 0.0)), white);
+        assert_eq!(stripe.color_at(Point::new(0.0, 1.0, 0.0)), black);
+        assert_eq!(stripe.color_at(Point::new(0.0, -0.1, 0.0)), black);
+        assert_eq!(stripe.color_at(Point::new(0.0, -1.0, 0.0)), black);
+        assert_eq!(stripe.color_at(Point::new(0.0, -1.1, 0.0)), white);
+    }
+
+    #[test]
+    fn z_stripe_alternates_in_z() {
+        let (black, white) = setup();
+        let stripe = Stripe::new(white, black).with_axis(Axis::Z);
+        assert_eq!(stripe.color_at(Point::new(5.0, 5.0, 0.5)), white);
+        assert_eq!(stripe.color_at(Point::new(5.0, 5.0, 1.5)), black);
+    }
+
+    #[test]
+    fn quarter_duty_cycle() {
+        let (black, white) = setup();
+        let stripe = Stripe::new(white, black).with_duty(0.25);
+        assert_eq!(stripe.color_at(Point::new(0.0, 0.0, 0.0)), white);
+        assert_eq!(stripe.color_at(Point::new(0.4, 0.0, 0.0)), white);
+        assert_eq!(stripe.color_at(Point::new(0.5, 0.0, 0.0)), black);
+        assert_eq!(stripe.color_at(Point::new(1.0, 0.0, 0.0)), black);
+        assert_eq!(stripe.color_at(Point::new(1.9, 0.0, 0.0)), black);
+        assert_eq!(stripe.color_at(Point::new(2.1, 0.0, 0.0)), white);
+        assert_eq!(stripe.color_at(Point::new(-0.1, 0.0, 0.0)), black);
+        assert_eq!(stripe.color_at(Point::new(-1.6, 0.0, 0.0)), white);
+    }
+
+    #[test]
+    fn non_integer_width() {
+        let (black, white) = setup();
+        let stripe = Stripe::new(white, black).with_width(0.3);
+        assert_eq!(stripe.color_at(Point::new(0.1, 0.0, 0.0)), white);
+        assert_eq!(stripe.color_at(Point::new(0.2, 0.0, 0.0)), black);
+        assert_eq!(stripe.color_at(Point::new(0.35, 0.0, 0.0)), white);
+        assert_eq!(stripe.color_at(Point::new(0.5, 0.0, 0.0)), black);
+        assert_eq!(stripe.color_at(Point::new(-0.1, 0.0, 0.0)), black);
+    }
+
     #[test]
     fn default_transformation() {
         let (black, white) = setup();
         let stripe = Stripe::new(white, black);
-        assert_eq!(stripe.transform, Matrix::identity());
+        assert_eq!(stripe.transformation(), Matrix::identity());
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "Stripe has a stale inverse")]
+    fn stale_stripe_is_caught() {
+        let (black, white) = setup();
+        let mut stripe = Stripe::new(white, black);
+        stripe.transform = Matrix::scaling(2.0, 1.0, 1.0);
+        stripe.color_at(Point::zero());
     }
 
     #[test]
     fn transformable() {
         let (black, white) = setup();
         let stripe = Stripe::new(white, black).transformed(Matrix::translation(1.0, 2.0, 3.0));
-        assert_eq!(stripe.transform, Matrix::translation(1.0, 2.0, 3.0));
+        assert_eq!(stripe.transformation(), Matrix::translation(1.0, 2.0, 3.0));
     }
 }
@@ -0,0 +1,188 @@
+use crate::{
+    math::{Matrix, Point, Transformable},
+    world::{
+        pattern::{inverse_of, Paint},
+        texture::UvMapping,
+        Color, Textured,
+    },
+};
+
+/// a checkerboard drawn on a surface's `(u, v)` coordinates rather than through space,
+/// so that it follows the surface instead of flickering wherever the surface grazes the
+/// boundary between two cells of a `Grid`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "SerializedUvCheckers")
+)]
+pub struct UvCheckers {
+    /// how many squares there are across `u`.
+    pub width: f64,
+    /// how many squares there are across `v`.
+    pub height: f64,
+    a: Paint,
+    b: Paint,
+    pub mapping: UvMapping,
+    transform: Matrix,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    inverse: Matrix,
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct SerializedUvCheckers {
+    width: f64,
+    height: f64,
+    a: Paint,
+    b: Paint,
+    mapping: UvMapping,
+    transform: Matrix,
+}
+
+#[cfg(feature = "serde")]
+impl From<SerializedUvCheckers> for UvCheckers {
+    fn from(serialized: SerializedUvCheckers) -> UvCheckers {
+        UvCheckers::new(
+            serialized.width,
+            serialized.height,
+            serialized.a,
+            serialized.b,
+        )
+        .with_mapping(serialized.mapping)
+        .transformed(serialized.transform)
+    }
+}
+
+impl UvCheckers {
+    /// a checkerboard of `width` by `height` squares, wrapped onto spheres.
+    pub fn new<A: Into<Paint>, B: Into<Paint>>(width: f64, height: f64, a: A, b: B) -> UvCheckers {
+        UvCheckers {
+            width,
+            height,
+            a: a.into(),
+            b: b.into(),
+            mapping: UvMapping::Spherical,
+            transform: Matrix::identity(),
+            inverse: Matrix::identity(),
+        }
+    }
+
+    /// the same checkerboard, flattened onto surfaces by `mapping` instead.
+    pub fn with_mapping(self, mapping: UvMapping) -> UvCheckers {
+        UvCheckers { mapping, ..self }
+    }
+
+    /// which of the two slots the square at `(u, v)` is filled with.
+    fn slot_at_uv(&self, u: f64, v: f64) -> &Paint {
+        if ((u * self.width).floor() + (v * self.height).floor()).rem_euclid(2.0) == 0.0 {
+            &self.a
+        } else {
+            &self.b
+        }
+    }
+
+    pub fn transformation(&self) -> Matrix {
+        self.transform
+    }
+
+    pub fn inverse(&self) -> Matrix {
+        self.inverse
+    }
+
+    pub fn debug_assert_consistent(&self) {
+        self.transform
+            .debug_assert_inverse(&self.inverse, "UvCheckers");
+    }
+}
+
+impl Transformable for UvCheckers {
+    fn transformed(self, transform: Matrix) -> UvCheckers {
+        UvCheckers {
+            transform,
+            inverse: inverse_of(transform),
+            ..self
+        }
+    }
+
+    fn transform(&mut self, transform: Matrix) -> &mut UvCheckers {
+        self.transform = transform;
+        self.inverse = inverse_of(transform);
+        self
+    }
+}
+
+impl Textured for UvCheckers {
+    fn color_at(&self, object_space_point: Point) -> Color {
+        self.debug_assert_consistent();
+        let pattern_space_point = self.inverse * object_space_point;
+        let (u, v) = self.mapping.map(pattern_space_point);
+        self.slot_at_uv(u, v).color_at(pattern_space_point)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        math::{Form, Geometry, Vector},
+        world::{Camera, Material, Pattern, Texture, View, World},
+    };
+    use std::f64::consts;
+
+    #[test]
+    fn checkers_by_uv() {
+        let (black, white) = (Color::black(), Color::white());
+        let pattern = UvCheckers::new(2.0, 2.0, black, white);
+        let cases = [
+            ((0.0, 0.0), black),
+            ((0.5, 0.0), white),
+            ((0.0, 0.5), white),
+            ((0.5, 0.5), black),
+            ((1.0, 1.0), black),
+        ];
+        for ((u, v), color) in cases.iter() {
+            assert_eq!(pattern.slot_at_uv(*u, *v), color);
+        }
+    }
+
+    #[test]
+    fn checkers_follow_mapping() {
+        let (black, white) = (Color::black(), Color::white());
+        let pattern = UvCheckers::new(2.0, 2.0, black, white).with_mapping(UvMapping::Planar);
+        assert_eq!(pattern.color_at(Point::new(0.25, 0.0, 0.25)), black);
+        assert_eq!(pattern.color_at(Point::new(0.75, 0.0, 0.25)), white);
+        assert_eq!(pattern.color_at(Point::new(1.25, 7.0, 1.75)), white);
+    }
+
+    #[test]
+    fn no_speckles_where_sphere_grazes_cell_boundaries() {
+        let checkers = UvCheckers::new(6.0, 3.0, Color::white(), Color::black());
+        let mut material =
+            Material::default().with_texture(Texture::pattern(Pattern::uv_checkers(checkers)));
+        material.ambient = 1.0;
+        material.diffuse = 0.0;
+        material.specular = 0.0;
+        let sphere = Geometry::default()
+            .with_form(Form::Sphere)
+            .with_material(material);
+        let world = World::new(vec![sphere], vec![]);
+
+        // looking straight at the side of the sphere that touches the plane x = 1,
+        // which a 3d grid would split into specks.
+        let mut camera = Camera::new(11, 11, consts::PI / 3.0);
+        camera.set_view(View::transformed(
+            Point::new(5.0, 0.0, 0.0),
+            Point::zero(),
+            Vector::new(0.0, 1.0, 0.0),
+        ));
+        let image = camera.render(&world);
+
+        let center = image[(5, 5)];
+        for y in 4..=6 {
+            for x in 4..=6 {
+                assert_eq!(image[(x, y)], center);
+            }
+        }
+    }
+}
@@ -0,0 +1,291 @@
+//! following the ray through a single pixel step by step, to find out why it came out
+//! the color it did.
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::{
+    math::{Hittable, Point, Vector},
+    world::{
+        intersection::Computations, light::Illumination, mix_secondary, Camera, Color, Ray, World,
+    },
+};
+
+/// everything that went into the color of one pixel, as found by `World::trace_pixel`.
+#[derive(Clone, Debug)]
+pub struct PixelTrace {
+    /// the pixel that was traced.
+    pub pixel: (usize, usize),
+    /// the ray through the center of the pixel.
+    pub ray: Ray,
+    /// every place the ray crosses an object, nearest first, including those behind
+    /// the camera.
+    pub intersections: Vec<TracedIntersection>,
+    /// the surface the pixel shows, if the ray hits one.
+    pub hit: Option<TracedHit>,
+    /// the color the pixel comes out, before the camera's exposure.
+    pub color: Color,
+}
+
+/// one place a ray crosses an object.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TracedIntersection {
+    /// the index in `world.objects` of the object crossed. for groups, this is the
+    /// group, not the child inside it.
+    pub object: usize,
+    /// how far along the ray the crossing is.
+    pub time: f64,
+    /// where the crossing is.
+    pub point: Point,
+}
+
+/// the surface a traced ray hits, and the light it gives off towards the eye.
+#[derive(Clone, Debug)]
+pub struct TracedHit {
+    /// which of the ray's intersections is the one seen.
+    pub intersection: TracedIntersection,
+    /// the surface at the hit, after any decals.
+    pub computations: Computations,
+    /// the light the surface gives off by itself.
+    pub emissive: Color,
+    /// the ambient light the surface gives off.
+    pub ambient: Color,
+    /// the light each of the world's lights gives the surface, in the same order.
+    pub lights: Vec<Illumination>,
+    /// the color mirrored by the surface, before it is mixed in.
+    pub reflected: Color,
+    /// the color seen through the surface, before it is mixed in.
+    pub refracted: Color,
+    /// the fraction of the reflected color that is mixed in, with the rest of the
+    /// mix going to the refracted color. see `Computations::reflectance`.
+    pub reflectance: Option<f64>,
+}
+
+impl World {
+    /// follows the ray through the center of pixel (x, y) of `camera`, recording each
+    /// step on the way to its color. the color comes out just as `cast_ray` gives it.
+    pub fn trace_pixel(&self, camera: &Camera, x: usize, y: usize) -> PixelTrace {
+        let ray = camera.ray_for_pixel(x, y);
+
+        let mut crossings = vec![];
+        for (index, object) in self.objects.iter().enumerate() {
+            if let Some(intersections) = object.hit(ray) {
+                for intersection in intersections.sorted() {
+                    crossings.push((index, intersection));
+                }
+            }
+        }
+        crossings.sort_by(|a, b| a.1.time.partial_cmp(&b.1.time).unwrap());
+        let intersections: Vec<TracedIntersection> = crossings
+            .iter()
+            .map(|(index, intersection)| TracedIntersection {
+                object: *index,
+                time: intersection.time,
+                point: ray.at(intersection.time),
+            })
+            .collect();
+
+        let hit = self.hit(ray).and_then(|all| {
+            let closest = all.closest()?;
+            let seen = crossings
+                .iter()
+                .position(|(_, intersection)| *intersection == closest)?;
+
+            let mut computations = closest.compute_among(&all);
+            for decal in &self.decals {
+                decal.apply(&mut computations);
+            }
+            let remaining = self.settings.max_bounces;
+            let reflectance = computations.reflectance();
+            Some(TracedHit {
+                intersection: intersections[seen],
+                emissive: computations.material.emissive,
+                ambient: self.ambient_at(&computations),
+                lights: self
+                    .lights
+                    .iter()
+                    .map(|light| light.illumination(self, &computations))
+                    .collect(),
+                reflected: self.reflected_color(&computations, remaining),
+                refracted: self.refracted_color(&computations, remaining),
+                reflectance,
+                computations,
+            })
+        });
+
+        let (surface, time) = match &hit {
+            Some(hit) => (hit.color(), Some(hit.intersection.time)),
+            None => (self.background.color_for(ray.direction), None),
+        };
+        let color = match &self.medium {
+            Some(medium) => medium.apply(self, ray, surface, time),
+            None => surface,
+        };
+
+        PixelTrace {
+            pixel: (x, y),
+            ray,
+            intersections,
+            hit,
+            color,
+        }
+    }
+}
+
+impl TracedHit {
+    /// the color of the surface seen, adding up all of its parts.
+    pub fn color(&self) -> Color {
+        let mut color = self.emissive + self.ambient;
+        for illumination in self.lights.iter() {
+            color += illumination.diffuse + illumination.specular;
+        }
+        color + mix_secondary(self.reflected, self.refracted, self.reflectance)
+    }
+}
+
+impl Display for PixelTrace {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let (x, y) = self.pixel;
+        writeln!(f, "pixel ({}, {})", x, y)?;
+        writeln!(
+            f,
+            "  ray from {} towards {}",
+            point(self.ray.origin),
+            vector(self.ray.direction)
+        )?;
+        writeln!(f, "  {} intersections:", self.intersections.len())?;
+        for intersection in self.intersections.iter() {
+            writeln!(
+                f,
+                "    object {} at t = {:.5}, {}",
+                intersection.object,
+                intersection.time,
+                point(intersection.point)
+            )?;
+        }
+
+        match &self.hit {
+            None => writeln!(f, "  misses everything, so sees the background")?,
+            Some(hit) => {
+                let computations = &hit.computations;
+                writeln!(
+                    f,
+                    "  hits object {} at t = {:.5}",
+                    hit.intersection.object, hit.intersection.time
+                )?;
+                writeln!(f, "    normal {}", vector(computations.surface_normal))?;
+                writeln!(
+                    f,
+                    "    inside: {}, n1 = {}, n2 = {}",
+                    computations.is_inside, computations.n1, computations.n2
+                )?;
+                writeln!(f, "    emissive  {}", color(hit.emissive))?;
+                writeln!(f, "    ambient   {}", color(hit.ambient))?;
+                for (i, illumination) in hit.lights.iter().enumerate() {
+                    writeln!(
+                        f,
+                        "    light {}: transmission {}",
+                        i,
+                        color(illumination.transmission)
+                    )?;
+                    writeln!(f, "      diffuse  {}", color(illumination.diffuse))?;
+                    writeln!(f, "      specular {}", color(illumination.specular))?;
+                }
+                writeln!(f, "    reflected {}", color(hit.reflected))?;
+                writeln!(f, "    refracted {}", color(hit.refracted))?;
+                if let Some(reflectance) = hit.reflectance {
+                    writeln!(f, "    reflectance {:.5}", reflectance)?;
+                }
+            }
+        }
+
+        writeln!(f, "  color {}", color(self.color))
+    }
+}
+
+/// three numbers written compactly, for points, vectors and colors in a trace.
+struct Triple([f64; 3]);
+
+impl Display for Triple {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let [a, b, c] = self.0;
+        write!(f, "({:.5}, {:.5}, {:.5})", a, b, c)
+    }
+}
+
+fn point(p: Point) -> Triple {
+    Triple([p[0], p[1], p[2]])
+}
+
+fn vector(v: Vector) -> Triple {
+    Triple([v[0], v[1], v[2]])
+}
+
+fn color(c: Color) -> Triple {
+    Triple([c.red(), c.green(), c.blue()])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::{light, Light, View};
+    use std::f64::consts;
+
+    fn setup() -> (World, Camera) {
+        let w = World::default();
+        let mut c = Camera::new(11, 11, consts::PI / 2.0);
+        c.set_view(View::transformed(
+            Point::new(0.0, 0.0, -5.0),
+            Point::zero(),
+            Vector::new(0.0, 1.0, 0.0),
+        ));
+        (w, c)
+    }
+
+    #[test]
+    fn tracing_the_center_of_the_default_world() {
+        let (w, c) = setup();
+        let trace = w.trace_pixel(&c, 5, 5);
+        assert_eq!(trace.ray.direction, Vector::new(0.0, 0.0, 1.0));
+
+        let objects: Vec<usize> = trace.intersections.iter().map(|i| i.object).collect();
+        assert_eq!(objects, vec![0, 1, 1, 0]);
+        assert_eq!(trace.intersections[0].point, Point::new(0.0, 0.0, -1.0));
+
+        let hit = trace.hit.as_ref().unwrap();
+        assert_eq!(hit.intersection, trace.intersections[0]);
+        assert_eq!(hit.lights.len(), 1);
+        assert_eq!(hit.lights[0].transmission, Color::white());
+        assert_eq!(trace.color, w.cast_ray(trace.ray));
+    }
+
+    #[test]
+    fn tracing_a_miss() {
+        let (w, c) = setup();
+        let trace = w.trace_pixel(&c, 0, 0);
+        assert!(trace.intersections.is_empty());
+        assert!(trace.hit.is_none());
+        assert_eq!(trace.color, w.cast_ray(trace.ray));
+        assert!(trace.to_string().contains("misses everything"));
+    }
+
+    #[test]
+    fn tracing_shadows_and_reflections() {
+        let (mut w, c) = setup();
+        w.objects[0].make_material_unique().reflective = 0.5;
+        w.lights.push(Light::point(light::Point::new(
+            Point::new(0.0, 0.0, 10.0),
+            Color::white(),
+        )));
+        let trace = w.trace_pixel(&c, 5, 4);
+        let hit = trace.hit.as_ref().unwrap();
+        // the second light is behind the spheres.
+        assert_eq!(hit.lights[1].transmission, Color::black());
+        assert_eq!(hit.lights[1].diffuse, Color::black());
+        assert_eq!(trace.color, w.cast_ray(trace.ray));
+
+        let printed = trace.to_string();
+        assert!(printed.starts_with("pixel (5, 4)\n"));
+        assert!(printed.contains(&format!("{} intersections", trace.intersections.len())));
+        assert!(printed.contains("light 1: transmission"));
+    }
+}
@@ -0,0 +1,203 @@
+//! rendering an image one row at a time, so that callers can show or save each row as
+//! soon as it is finished.
+
+use std::{collections::VecDeque, time::Instant};
+
+use crate::world::{
+    camera::stands_out, stats::RayCounts, Camera, Canvas, Color, RenderStats, World,
+};
+
+impl Camera {
+    /// renders `world` lazily, one row at a time from the top, yielding the index of each
+    /// row along with its colors. the rows come out exactly as they would from `render`,
+    /// leaving out the alpha, and no row is rendered until it is asked for.
+    pub fn render_progressive<'a>(&'a self, world: &'a World) -> Rows<'a> {
+        Rows {
+            camera: self,
+            world,
+            next_row: 0,
+            previews: VecDeque::with_capacity(3),
+            stats: RenderStats::default(),
+        }
+    }
+}
+
+/// the rows of an image, rendered as they are needed. see `Camera::render_progressive`.
+pub struct Rows<'a> {
+    camera: &'a Camera,
+    world: &'a World,
+    next_row: usize,
+    /// with adaptive sampling, the first pass over the rows around the next one, along
+    /// with which of their pixels were replaced for being non-finite.
+    previews: VecDeque<(usize, Vec<Color>, Vec<bool>)>,
+    stats: RenderStats,
+}
+
+impl<'a> Rows<'a> {
+    /// the statistics gathered over the rows rendered so far. the time taken only counts
+    /// time spent rendering rows, not time between them.
+    pub fn stats(&self) -> RenderStats {
+        self.stats
+    }
+
+    fn rough_row(&mut self, y: usize) -> (usize, Vec<Color>, Vec<bool>) {
+        let mut colors = Vec::with_capacity(self.camera.image_width);
+        let mut replaced = Vec::with_capacity(self.camera.image_width);
+        for x in 0..self.camera.image_width {
+            let before = self.stats.non_finite_pixels;
+            colors.push(self.camera.rough_pixel(self.world, x, y, &mut self.stats).0);
+            replaced.push(self.stats.non_finite_pixels > before);
+        }
+        (y, colors, replaced)
+    }
+
+    /// the colors of row `y`, which must be the next row.
+    fn render_row(&mut self, y: usize) -> Vec<Color> {
+        let threshold = match self.camera.adaptive_threshold {
+            Some(threshold) => threshold,
+            None => return self.rough_row(y).1,
+        };
+
+        // keep the first pass over the row above, this row, and the row below.
+        while self
+            .previews
+            .front()
+            .is_some_and(|&(row, _, _)| row + 1 < y)
+        {
+            self.previews.pop_front();
+        }
+        let last = (y + 1).min(self.camera.image_height - 1);
+        let mut row = self
+            .previews
+            .back()
+            .map_or(y.saturating_sub(1), |p| p.0 + 1);
+        while row <= last {
+            let preview = self.rough_row(row);
+            self.previews.push_back(preview);
+            row += 1;
+        }
+
+        let first = self.previews[0].0;
+        let window = Canvas::from_fn(self.camera.image_width, self.previews.len(), |x, j| {
+            self.previews[j].1[x]
+        });
+
+        let (_, mut colors, replaced) = self.previews[y - first].clone();
+        for (x, color) in colors.iter_mut().enumerate() {
+            // replaced pixels are already known to be broken.
+            if !replaced[x] && stands_out(&window, x, y - first, threshold) {
+                *color = self
+                    .camera
+                    .refined_pixel(self.world, x, y, &mut self.stats)
+                    .0;
+            }
+        }
+
+        colors
+    }
+}
+
+impl<'a> Iterator for Rows<'a> {
+    type Item = (usize, Vec<Color>);
+
+    fn next(&mut self) -> Option<(usize, Vec<Color>)> {
+        let y = self.next_row;
+        if y >= self.camera.image_height {
+            return None;
+        }
+        self.next_row += 1;
+
+        let start = Instant::now();
+        let _counting = RayCounts::counting(true);
+        let row = self.render_row(y);
+        self.stats.elapsed += start.elapsed();
+        Some((y, row))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        math::{Form, Geometry, Point, Vector},
+        world::{Material, NonFinite, Sampling, View},
+    };
+    use std::{f64::consts, time::Duration};
+
+    fn setup() -> (World, Camera) {
+        let w = World::default();
+        let mut c = Camera::new(11, 11, consts::PI / 2.0);
+        c.set_view(View::transformed(
+            Point::new(0.0, 0.0, -5.0),
+            Point::zero(),
+            Vector::new(0.0, 1.0, 0.0),
+        ));
+        (w, c)
+    }
+
+    fn assemble<I: Iterator<Item = (usize, Vec<Color>)>>(c: &Camera, rows: I) -> Canvas {
+        let mut image = Canvas::new(c.image_width, c.image_height);
+        for (y, row) in rows {
+            for (x, color) in row.into_iter().enumerate() {
+                image[(x, y)] = color;
+            }
+        }
+        image
+    }
+
+    #[test]
+    fn rows_assemble_into_the_render() {
+        let (w, c) = setup();
+        let rows: Vec<usize> = c.render_progressive(&w).map(|(y, _)| y).collect();
+        assert_eq!(rows, (0..11).collect::<Vec<usize>>());
+        assert_eq!(
+            assemble(&c, c.render_progressive(&w)),
+            c.render(&w).without_alpha()
+        );
+    }
+
+    #[test]
+    fn adaptive_rows_assemble_into_the_render() {
+        let glowing = Material::default().with_emissive(Color::white());
+        let w = World::new(
+            vec![Geometry::default()
+                .with_form(Form::Sphere)
+                .with_material(glowing)],
+            vec![],
+        );
+        let (_, mut c) = setup();
+        c.sampling = Sampling::uniform(3);
+        c.adaptive_threshold = Some(0.1);
+
+        let mut rows = c.render_progressive(&w);
+        let image = assemble(&c, rows.by_ref());
+        let (whole, stats) = c.render_with_stats(&w);
+        assert_eq!(image, whole.without_alpha());
+        assert!(rows.stats().elapsed > Duration::ZERO);
+        assert_eq!(
+            RenderStats {
+                elapsed: Duration::ZERO,
+                ..rows.stats()
+            },
+            RenderStats {
+                elapsed: Duration::ZERO,
+                ..stats
+            }
+        );
+    }
+
+    #[test]
+    fn rows_are_rendered_lazily() {
+        let (mut w, mut c) = setup();
+        w.objects[0].make_material_unique().diffuse = f64::NAN;
+        c.on_non_finite = NonFinite::Replace(Color::black());
+
+        // the broken sphere only shows up in the middle rows, so the first few rows are
+        // fine as long as nothing past them has been rendered yet.
+        let mut rows = c.render_progressive(&w);
+        assert_eq!(rows.by_ref().take(3).count(), 3);
+        assert_eq!(rows.stats().non_finite_pixels, 0);
+        assert_eq!(rows.by_ref().count(), 8);
+        assert!(rows.stats().non_finite_pixels > 0);
+    }
+}
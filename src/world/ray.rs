@@ -4,11 +4,22 @@ use crate::math::{matrix::Matrix, point::Point, vector::Vector};
 pub struct Ray {
     pub origin: Point,
     pub direction: Vector,
+    /// when the ray was fired, for objects that move. rays fired while the camera's
+    /// shutter is open carry this on to the rays they spawn.
+    pub time: f64,
 }
 
 impl Ray {
     pub fn new(origin: Point, direction: Vector) -> Ray {
-        Ray { origin, direction }
+        Ray {
+            origin,
+            direction,
+            time: 0.0,
+        }
+    }
+
+    pub fn with_time(self, time: f64) -> Ray {
+        Ray { time, ..self }
     }
 
     pub fn at(&self, time: f64) -> Point {
@@ -16,7 +27,7 @@ impl Ray {
     }
 
     pub fn transformed(&self, matrix: Matrix) -> Ray {
-        Ray::new(matrix * self.origin, matrix * self.direction)
+        Ray::new(matrix * self.origin, matrix * self.direction).with_time(self.time)
     }
 
     pub fn transform(mut self, matrix: Matrix) -> Ray {
@@ -56,6 +67,16 @@ mod tests {
         assert_eq!(r2.direction, Vector::new(0.0, 1.0, 0.0));
     }
 
+    #[test]
+    fn transformed_rays_keep_their_time() {
+        let r1 = Ray::new(Point::new(1.0, 2.0, 3.0), Vector::new(0.0, 1.0, 0.0));
+        assert_eq!(r1.time, 0.0);
+        let r2 = r1
+            .with_time(0.25)
+            .transformed(Matrix::scaling(2.0, 2.0, 2.0));
+        assert_eq!(r2.time, 0.25);
+    }
+
     #[test]
     fn scale_ray() {
         let r1 = Ray::new(Point::new(1.0, 2.0, 3.0), Vector::new(0.0, 1.0, 0.0));
@@ -0,0 +1,217 @@
+use crate::math::XorShift;
+
+/// picks where within a pixel its rays pass through.
+pub trait Sampler {
+    /// the offsets of the rays through pixel (x, y), as fractions of the pixel's width
+    /// and height measured from its top left corner. every offset lies from 0 up to,
+    /// but not including, 1.
+    fn offsets(&self, x: usize, y: usize) -> Vec<(f64, f64)>;
+}
+
+/// a grid of `samples` by `samples` rays, each through the center of its cell.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Uniform {
+    pub samples: usize,
+}
+
+impl Uniform {
+    pub fn new(samples: usize) -> Uniform {
+        Uniform { samples }
+    }
+}
+
+impl Sampler for Uniform {
+    fn offsets(&self, _x: usize, _y: usize) -> Vec<(f64, f64)> {
+        grid(self.samples, || (0.5, 0.5))
+    }
+}
+
+/// `samples` times `samples` rays, each anywhere at all within the pixel.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Jittered {
+    pub samples: usize,
+    pub seed: u64,
+}
+
+impl Jittered {
+    pub fn new(samples: usize, seed: u64) -> Jittered {
+        Jittered { samples, seed }
+    }
+}
+
+impl Sampler for Jittered {
+    fn offsets(&self, x: usize, y: usize) -> Vec<(f64, f64)> {
+        let mut rng = pixel_rng(self.seed, x, y);
+        let count = self.samples.max(1) * self.samples.max(1);
+        (0..count)
+            .map(|_| (rng.next_f64(), rng.next_f64()))
+            .collect()
+    }
+}
+
+/// a grid of `samples` by `samples` rays, each somewhere within its own cell, so that
+/// the rays are random but still spread evenly across the pixel.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Stratified {
+    pub samples: usize,
+    pub seed: u64,
+}
+
+impl Stratified {
+    pub fn new(samples: usize, seed: u64) -> Stratified {
+        Stratified { samples, seed }
+    }
+}
+
+impl Sampler for Stratified {
+    fn offsets(&self, x: usize, y: usize) -> Vec<(f64, f64)> {
+        let mut rng = pixel_rng(self.seed, x, y);
+        grid(self.samples, || (rng.next_f64(), rng.next_f64()))
+    }
+}
+
+/// any of the samplers, so that a camera can hold whichever one it was given.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Sampling {
+    Uniform(Uniform),
+    Jittered(Jittered),
+    Stratified(Stratified),
+}
+
+impl Sampling {
+    pub fn uniform(samples: usize) -> Sampling {
+        Sampling::Uniform(Uniform::new(samples))
+    }
+
+    pub fn jittered(samples: usize, seed: u64) -> Sampling {
+        Sampling::Jittered(Jittered::new(samples, seed))
+    }
+
+    pub fn stratified(samples: usize, seed: u64) -> Sampling {
+        Sampling::Stratified(Stratified::new(samples, seed))
+    }
+
+    /// the seed the sampler picks its offsets with, which the camera also picks its
+    /// lens and shutter samples with. uniform sampling has no seed of its own, so it
+    /// gives 0.
+    pub fn seed(&self) -> u64 {
+        match self {
+            Sampling::Uniform(_) => 0,
+            Sampling::Jittered(jittered) => jittered.seed,
+            Sampling::Stratified(stratified) => stratified.seed,
+        }
+    }
+}
+
+impl Default for Sampling {
+    /// a single ray through the center of each pixel.
+    fn default() -> Sampling {
+        Sampling::uniform(1)
+    }
+}
+
+impl Sampler for Sampling {
+    fn offsets(&self, x: usize, y: usize) -> Vec<(f64, f64)> {
+        match self {
+            Sampling::Uniform(uniform) => uniform.offsets(x, y),
+            Sampling::Jittered(jittered) => jittered.offsets(x, y),
+            Sampling::Stratified(stratified) => stratified.offsets(x, y),
+        }
+    }
+}
+
+/// the largest number that is less than 1.
+const BELOW_ONE: f64 = 1.0 - f64::EPSILON / 2.0;
+
+/// splits the pixel into `samples` by `samples` cells, and places one offset in each,
+/// at the position within the cell given by `within`.
+fn grid<F: FnMut() -> (f64, f64)>(samples: usize, mut within: F) -> Vec<(f64, f64)> {
+    let samples = samples.max(1);
+    let step = 1.0 / samples as f64;
+
+    let mut offsets = Vec::with_capacity(samples * samples);
+    for j in 0..samples {
+        for i in 0..samples {
+            let (dx, dy) = within();
+            // rounding could otherwise push a sample onto the next pixel.
+            offsets.push((
+                ((i as f64 + dx) * step).min(BELOW_ONE),
+                ((j as f64 + dy) * step).min(BELOW_ONE),
+            ));
+        }
+    }
+    offsets
+}
+
+/// a generator that depends only on the seed and the pixel, so that each pixel gets the
+/// same samples no matter the order the image is rendered in.
+pub(crate) fn pixel_rng(seed: u64, x: usize, y: usize) -> XorShift {
+    XorShift::new(seed ^ ((x as u64) << 32 | y as u64).rotate_left(17))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> [Sampling; 3] {
+        [
+            Sampling::uniform(4),
+            Sampling::jittered(4, 7),
+            Sampling::stratified(4, 7),
+        ]
+    }
+
+    #[test]
+    fn offsets_stay_within_the_pixel() {
+        for sampler in setup().iter() {
+            for &(x, y) in [(0, 0), (3, 9), (640, 480)].iter() {
+                let offsets = sampler.offsets(x, y);
+                assert_eq!(offsets.len(), 16);
+                for &(dx, dy) in offsets.iter() {
+                    assert!((0.0..1.0).contains(&dx), "{:?} gave {}", sampler, dx);
+                    assert!((0.0..1.0).contains(&dy), "{:?} gave {}", sampler, dy);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn single_uniform_sample_is_the_pixel_center() {
+        assert_eq!(Sampling::default().offsets(3, 4), vec![(0.5, 0.5)]);
+        assert_eq!(
+            Sampling::uniform(2).offsets(0, 0),
+            vec![(0.25, 0.25), (0.75, 0.25), (0.25, 0.75), (0.75, 0.75)]
+        );
+    }
+
+    #[test]
+    fn stratified_samples_fill_every_cell() {
+        let offsets = Sampling::stratified(3, 11).offsets(5, 5);
+        let mut cells: Vec<(usize, usize)> = offsets
+            .iter()
+            .map(|&(dx, dy)| ((dx * 3.0) as usize, (dy * 3.0) as usize))
+            .collect();
+        cells.sort_unstable();
+        cells.dedup();
+        assert_eq!(cells.len(), 9);
+    }
+
+    #[test]
+    fn seeds_are_reproducible() {
+        for sampler in [Sampling::jittered(2, 3), Sampling::stratified(2, 3)].iter() {
+            assert_eq!(sampler.offsets(1, 2), sampler.offsets(1, 2));
+        }
+        assert_ne!(
+            Sampling::jittered(2, 3).offsets(1, 2),
+            Sampling::jittered(2, 4).offsets(1, 2)
+        );
+        assert_ne!(
+            Sampling::stratified(2, 3).offsets(1, 2),
+            Sampling::stratified(2, 3).offsets(2, 1)
+        );
+    }
+}
@@ -0,0 +1,171 @@
+use crate::world::MAX_REFLECTIONS;
+
+/// settings that trade the quality of a rendered image for speed, or otherwise
+/// change what it looks like without changing the world in it.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RenderSettings {
+    /// how many times a ray may bounce off or pass through surfaces. with 0, only
+    /// direct lighting is shaded.
+    pub max_bounces: u32,
+    /// whether objects block light from reaching whatever is behind them.
+    pub shadows: bool,
+    /// whether every object blocks light completely, as in the book, rather than
+    /// transparent objects letting some of it through.
+    pub opaque_shadows: bool,
+    /// how many points are sampled on a point light with a radius to find how much of
+    /// it is shadowed.
+    pub light_samples: u32,
+    /// picks the samples on point lights with a radius, so that the same seed gives
+    /// the same image.
+    pub light_seed: u64,
+    /// picks the paths followed by `Camera::render_path_traced`, so that the same seed
+    /// gives the same image.
+    pub path_seed: u64,
+    /// darkens ambient light in creases and where objects meet, if given.
+    pub ambient_occlusion: Option<AmbientOcclusion>,
+}
+
+impl RenderSettings {
+    pub fn with_max_bounces(self, max_bounces: u32) -> RenderSettings {
+        RenderSettings {
+            max_bounces,
+            ..self
+        }
+    }
+
+    pub fn with_shadows(self, shadows: bool) -> RenderSettings {
+        RenderSettings { shadows, ..self }
+    }
+
+    pub fn with_opaque_shadows(self, opaque_shadows: bool) -> RenderSettings {
+        RenderSettings {
+            opaque_shadows,
+            ..self
+        }
+    }
+
+    pub fn with_light_samples(self, light_samples: u32) -> RenderSettings {
+        RenderSettings {
+            light_samples,
+            ..self
+        }
+    }
+
+    pub fn with_light_seed(self, light_seed: u64) -> RenderSettings {
+        RenderSettings { light_seed, ..self }
+    }
+
+    pub fn with_path_seed(self, path_seed: u64) -> RenderSettings {
+        RenderSettings { path_seed, ..self }
+    }
+
+    pub fn with_ambient_occlusion(self, ambient_occlusion: AmbientOcclusion) -> RenderSettings {
+        RenderSettings {
+            ambient_occlusion: Some(ambient_occlusion),
+            ..self
+        }
+    }
+}
+
+impl Default for RenderSettings {
+    fn default() -> RenderSettings {
+        RenderSettings {
+            max_bounces: MAX_REFLECTIONS,
+            shadows: true,
+            opaque_shadows: false,
+            light_samples: 16,
+            light_seed: 0,
+            path_seed: 0,
+            ambient_occlusion: None,
+        }
+    }
+}
+
+/// how ambient occlusion is estimated: rays are sent out from each shaded point over
+/// the hemisphere around its normal, and ambient light is dimmed by the fraction of
+/// them that hit something nearby.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AmbientOcclusion {
+    /// how many rays are sent out from each point.
+    pub samples: u32,
+    /// anything further away than this doesn't occlude the point.
+    pub max_distance: f64,
+    /// how much a fully occluded point is darkened, from 0 (not at all) to 1 (black).
+    pub strength: f64,
+    /// picks the directions of the rays, so that the same seed gives the same image.
+    pub seed: u64,
+}
+
+impl AmbientOcclusion {
+    pub fn with_samples(self, samples: u32) -> AmbientOcclusion {
+        AmbientOcclusion { samples, ..self }
+    }
+
+    pub fn with_max_distance(self, max_distance: f64) -> AmbientOcclusion {
+        AmbientOcclusion {
+            max_distance,
+            ..self
+        }
+    }
+
+    pub fn with_strength(self, strength: f64) -> AmbientOcclusion {
+        AmbientOcclusion { strength, ..self }
+    }
+
+    pub fn with_seed(self, seed: u64) -> AmbientOcclusion {
+        AmbientOcclusion { seed, ..self }
+    }
+}
+
+impl Default for AmbientOcclusion {
+    fn default() -> AmbientOcclusion {
+        AmbientOcclusion {
+            samples: 16,
+            max_distance: 1.0,
+            strength: 1.0,
+            seed: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_settings() {
+        let settings = RenderSettings::default();
+        assert_eq!(settings.max_bounces, 5);
+        assert!(settings.shadows);
+        assert!(!settings.opaque_shadows);
+        assert_eq!(settings.light_samples, 16);
+        assert_eq!(settings.light_seed, 0);
+        assert_eq!(settings.path_seed, 0);
+        assert_eq!(settings.ambient_occlusion, None);
+    }
+
+    #[test]
+    fn builder_changes_one_setting_at_a_time() {
+        let settings = RenderSettings::default()
+            .with_max_bounces(1)
+            .with_shadows(false)
+            .with_opaque_shadows(true)
+            .with_light_samples(4)
+            .with_light_seed(9)
+            .with_path_seed(2);
+        assert_eq!(
+            settings,
+            RenderSettings {
+                max_bounces: 1,
+                shadows: false,
+                opaque_shadows: true,
+                light_samples: 4,
+                light_seed: 9,
+                path_seed: 2,
+                ambient_occlusion: None,
+            }
+        );
+    }
+}
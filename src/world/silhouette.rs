@@ -0,0 +1,418 @@
+use std::collections::HashMap;
+
+use crate::{
+    math::{clamp_between, Hittable},
+    world::{Camera, World},
+};
+
+/// how far apart two samples' depths must be, relative to the nearer one, for one of
+/// them to be considered in front of the other.
+const DEPTH_DISCONTINUITY: f64 = 0.05;
+
+/// how many times the boundary between two samples is halved to place it precisely.
+const REFINEMENT_STEPS: usize = 12;
+
+/// how far, in pixels, a simplified polyline may stray from the traced outline.
+const SIMPLIFY_TOLERANCE: f64 = 0.1;
+
+/// a connected line through a sequence of points in image coordinates, measured in
+/// pixels from the top left corner of the image. a closed polyline also runs from its
+/// last point back to its first, which is not repeated.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Polyline {
+    pub points: Vec<(f64, f64)>,
+    pub closed: bool,
+}
+
+/// the index of the closest object seen by a ray, and how far away it is.
+type Sample = Option<(usize, f64)>;
+
+/// one of the lines between neighboring samples, named by the sample at its top left
+/// end and whether it runs across (to the right) or down.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+enum Edge {
+    Across(usize, usize),
+    Down(usize, usize),
+}
+
+impl Camera {
+    /// traces the outlines of everything the camera sees, at `resolution` samples per
+    /// pixel along each axis.
+    ///
+    /// an outline is drawn wherever an object stands out against the background, or
+    /// passes in front of another object further away. where two objects touch,
+    /// neither is in front of the other, so outlines are left open there.
+    pub fn extract_silhouettes(&self, world: &World, resolution: usize) -> Vec<Polyline> {
+        let resolution = resolution.max(1);
+        let columns = self.image_width * resolution;
+        let rows = self.image_height * resolution;
+        let position = |i: usize| ((i as f64) + 0.5) / (resolution as f64);
+
+        let samples: Vec<Sample> = (0..(columns * rows))
+            .map(|index| {
+                self.closest_object(world, position(index % columns), position(index / columns))
+            })
+            .collect();
+        let at = |i: usize, j: usize| samples[j * columns + i];
+
+        // find every line between neighboring samples that an outline crosses, and where.
+        let mut crossings: HashMap<Edge, (f64, f64)> = HashMap::new();
+        for j in 0..rows {
+            for i in 0..columns {
+                if i + 1 < columns && separated(at(i, j), at(i + 1, j)) {
+                    let y = position(j);
+                    let x = self.refine(world, at(i, j), |t| {
+                        (position(i) + t * (position(i + 1) - position(i)), y)
+                    });
+                    crossings.insert(Edge::Across(i, j), x);
+                }
+                if j + 1 < rows && separated(at(i, j), at(i, j + 1)) {
+                    let x = position(i);
+                    let y = self.refine(world, at(i, j), |t| {
+                        (x, position(j) + t * (position(j + 1) - position(j)))
+                    });
+                    crossings.insert(Edge::Down(i, j), y);
+                }
+            }
+        }
+
+        // marching squares: within each cell, join up the crossings on its sides.
+        let mut links: HashMap<Edge, Vec<Edge>> = HashMap::new();
+        for j in 0..rows.saturating_sub(1) {
+            for i in 0..columns.saturating_sub(1) {
+                let sides = [
+                    Edge::Across(i, j),
+                    Edge::Down(i + 1, j),
+                    Edge::Across(i, j + 1),
+                    Edge::Down(i, j),
+                ];
+                let crossed: Vec<Edge> = sides
+                    .iter()
+                    .filter(|edge| crossings.contains_key(edge))
+                    .cloned()
+                    .collect();
+
+                // with only one crossing, an outline ends in this cell. with three, the
+                // odd one out ends here. with four, the outlines pass each other.
+                let pairs: Vec<(Edge, Edge)> = match crossed.len() {
+                    2 | 3 => vec![(crossed[0], crossed[1])],
+                    4 => vec![(crossed[0], crossed[1]), (crossed[2], crossed[3])],
+                    _ => vec![],
+                };
+                for (a, b) in pairs {
+                    links.entry(a).or_default().push(b);
+                    links.entry(b).or_default().push(a);
+                }
+            }
+        }
+
+        chain(&links, &crossings)
+            .into_iter()
+            .map(|polyline| simplify(polyline, SIMPLIFY_TOLERANCE))
+            .collect()
+    }
+
+    /// the index in `world.objects` of the closest object seen through the position
+    /// (x, y) on the canvas, and how far away it is.
+    pub(crate) fn closest_object(&self, world: &World, x: f64, y: f64) -> Sample {
+        let ray = self.ray_for_position(x, y);
+
+        world
+            .objects
+            .iter()
+            .enumerate()
+            .filter_map(|(index, object)| {
+                object
+                    .hit(ray)
+                    .and_then(|intersections| intersections.closest())
+                    .map(|intersection| (index, intersection.time))
+            })
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+    }
+
+    /// finds where along a line of positions, from `position(0.0)` (which sees `start`)
+    /// to `position(1.0)`, the outline lies.
+    fn refine<F: Fn(f64) -> (f64, f64)>(
+        &self,
+        world: &World,
+        start: Sample,
+        position: F,
+    ) -> (f64, f64) {
+        let (mut low, mut high) = (0.0, 1.0);
+        for _ in 0..REFINEMENT_STEPS {
+            let middle = (low + high) / 2.0;
+            let (x, y) = position(middle);
+            if separated(start, self.closest_object(world, x, y)) {
+                high = middle;
+            } else {
+                low = middle;
+            }
+        }
+        position((low + high) / 2.0)
+    }
+}
+
+/// whether an outline runs between two samples. depth changes quickly across a
+/// surface seen edge-on, so it's only compared between different objects.
+fn separated(a: Sample, b: Sample) -> bool {
+    match (a, b) {
+        (None, None) => false,
+        (Some((a, near)), Some((b, far))) => {
+            a != b && (near - far).abs() > DEPTH_DISCONTINUITY * near.min(far)
+        }
+        _ => true,
+    }
+}
+
+/// follows the links between crossings to build polylines, starting with those that
+/// have loose ends and then closing up the loops that remain.
+fn chain(links: &HashMap<Edge, Vec<Edge>>, crossings: &HashMap<Edge, (f64, f64)>) -> Vec<Polyline> {
+    // visit crossings in a fixed order, so that the output doesn't depend on hashing.
+    let mut starts: Vec<&Edge> = links.keys().collect();
+    starts.sort_by_key(|edge| match edge {
+        Edge::Across(i, j) => (*j, *i, 0),
+        Edge::Down(i, j) => (*j, *i, 1),
+    });
+
+    let mut visited: HashMap<Edge, bool> = HashMap::new();
+    let mut polylines = vec![];
+
+    for &closed in [false, true].iter() {
+        for &&start in starts.iter() {
+            let loose = links[&start].len() < 2;
+            if visited.contains_key(&start) || loose == closed {
+                continue;
+            }
+
+            let mut points = vec![];
+            let mut previous = None;
+            let mut current = start;
+            loop {
+                visited.insert(current, true);
+                points.push(crossings[&current]);
+
+                let next = links[&current]
+                    .iter()
+                    .find(|&&edge| Some(edge) != previous && !visited.contains_key(&edge));
+                match next {
+                    Some(&edge) => {
+                        previous = Some(current);
+                        current = edge;
+                    }
+                    None => break,
+                }
+            }
+
+            polylines.push(Polyline { points, closed });
+        }
+    }
+
+    polylines
+}
+
+/// douglas-peucker simplification: keeps the ends of the line, and then recursively
+/// keeps whichever point strays furthest from the line between them, if it strays
+/// further than `tolerance`.
+fn simplify(polyline: Polyline, tolerance: f64) -> Polyline {
+    let points = &polyline.points;
+    if points.len() < 3 {
+        return polyline;
+    }
+
+    let points = if polyline.closed {
+        // split the loop in two at the point furthest from the first, and simplify each
+        // half as an open line.
+        let first = points[0];
+        let (furthest, _) = points
+            .iter()
+            .enumerate()
+            .map(|(index, &point)| (index, distance(first, point)))
+            .fold(
+                (0, 0.0),
+                |best, next| if next.1 > best.1 { next } else { best },
+            );
+
+        let mut there = simplify_open(&points[..=furthest], tolerance);
+        let mut back: Vec<(f64, f64)> = points[furthest..].to_vec();
+        back.push(first);
+        let back = simplify_open(&back, tolerance);
+
+        there.pop();
+        there.extend_from_slice(&back[..back.len() - 1]);
+        there
+    } else {
+        simplify_open(points, tolerance)
+    };
+
+    Polyline {
+        points,
+        closed: polyline.closed,
+    }
+}
+
+fn simplify_open(points: &[(f64, f64)], tolerance: f64) -> Vec<(f64, f64)> {
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+
+    let (start, end) = (points[0], points[points.len() - 1]);
+    let (furthest, straying) = points[1..points.len() - 1]
+        .iter()
+        .enumerate()
+        .map(|(index, &point)| (index + 1, distance_to_segment(point, start, end)))
+        .fold(
+            (0, 0.0),
+            |best, next| if next.1 > best.1 { next } else { best },
+        );
+
+    if straying <= tolerance {
+        vec![start, end]
+    } else {
+        let mut result = simplify_open(&points[..=furthest], tolerance);
+        result.pop();
+        result.extend(simplify_open(&points[furthest..], tolerance));
+        result
+    }
+}
+
+fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
+
+fn distance_to_segment(point: (f64, f64), start: (f64, f64), end: (f64, f64)) -> f64 {
+    let length = distance(start, end);
+    if length == 0.0 {
+        return distance(point, start);
+    }
+
+    let along = ((point.0 - start.0) * (end.0 - start.0) + (point.1 - start.1) * (end.1 - start.1))
+        / (length * length);
+    let along = clamp_between(along, 0.0, 1.0);
+    let closest = (
+        start.0 + along * (end.0 - start.0),
+        start.1 + along * (end.1 - start.1),
+    );
+    distance(point, closest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        math::{Form, Geometry, Matrix, Point, Transformable, Vector},
+        world::View,
+    };
+    use std::f64::consts;
+
+    fn looking_down_z(camera: Camera) -> Camera {
+        let mut camera = camera;
+        camera.set_view(View::transformed(
+            Point::new(0.0, 0.0, -5.0),
+            Point::zero(),
+            Vector::new(0.0, 1.0, 0.0),
+        ));
+        camera
+    }
+
+    #[test]
+    fn single_sphere_is_one_circle() {
+        let w = World::new(vec![Geometry::default().with_form(Form::Sphere)], vec![]);
+        // 40 pixels cover 4 units, so the sphere has a radius of 10 pixels.
+        let c = looking_down_z(Camera::orthographic(40, 40, 4.0));
+        let silhouettes = c.extract_silhouettes(&w, 1);
+
+        assert_eq!(silhouettes.len(), 1);
+        assert!(silhouettes[0].closed);
+        assert!(silhouettes[0].points.len() >= 8);
+        for &point in silhouettes[0].points.iter() {
+            assert!((distance(point, (20.0, 20.0)) - 10.0).abs() < 1.0);
+        }
+    }
+
+    #[test]
+    fn two_spheres_are_two_loops() {
+        let w = World::new(
+            vec![
+                Geometry::default()
+                    .with_form(Form::Sphere)
+                    .transformed(Matrix::translation(-2.0, 0.0, 0.0)),
+                Geometry::default()
+                    .with_form(Form::Sphere)
+                    .transformed(Matrix::translation(2.0, 0.0, 0.0)),
+            ],
+            vec![],
+        );
+        let c = looking_down_z(Camera::new(60, 30, consts::PI / 2.0));
+        let silhouettes = c.extract_silhouettes(&w, 1);
+
+        assert_eq!(silhouettes.len(), 2);
+        assert!(silhouettes.iter().all(|polyline| polyline.closed));
+        // one loop is on each side of the image.
+        let left = silhouettes
+            .iter()
+            .filter(|polyline| polyline.points.iter().all(|p| p.0 < 30.0))
+            .count();
+        assert_eq!(left, 1);
+    }
+
+    #[test]
+    fn sphere_resting_on_floor_is_open() {
+        let floor = Geometry::default().with_form(Form::Plane);
+        let wall = Geometry::default().with_form(Form::Plane).transformed(
+            Matrix::translation(0.0, 0.0, 3.0) * Matrix::rotation_x(consts::FRAC_PI_2),
+        );
+        let sphere = Geometry::default()
+            .with_form(Form::Sphere)
+            .transformed(Matrix::translation(0.0, 1.0, 0.0));
+        let w = World::new(vec![floor, wall, sphere], vec![]);
+        // the floor and the wall behind the sphere fill the whole image, and meet
+        // without an outline between them.
+        let mut c = Camera::new(40, 40, consts::PI / 3.0);
+        c.set_view(View::transformed(
+            Point::new(0.0, 1.0, -5.0),
+            Point::new(0.0, 1.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+        ));
+        let silhouettes = c.extract_silhouettes(&w, 1);
+
+        assert_eq!(silhouettes.len(), 1);
+        let outline = &silhouettes[0];
+        assert!(!outline.closed);
+        // the outline is broken at the bottom of the sphere, where it touches the floor.
+        let (first, last) = (outline.points[0], outline.points[outline.points.len() - 1]);
+        assert!(first.1 > 20.0 && last.1 > 20.0);
+        assert!(outline.points.iter().all(|p| p.1 <= first.1.max(last.1)));
+    }
+
+    #[test]
+    fn simplify_straight_line() {
+        let line = Polyline {
+            points: vec![(0.0, 0.0), (1.0, 0.01), (2.0, -0.01), (3.0, 0.0)],
+            closed: false,
+        };
+        assert_eq!(simplify(line, 0.1).points, vec![(0.0, 0.0), (3.0, 0.0)]);
+    }
+
+    #[test]
+    fn simplify_keeps_corners() {
+        let square = Polyline {
+            points: vec![
+                (0.0, 0.0),
+                (1.0, 0.0),
+                (2.0, 0.0),
+                (2.0, 1.0),
+                (2.0, 2.0),
+                (1.0, 2.0),
+                (0.0, 2.0),
+                (0.0, 1.0),
+            ],
+            closed: true,
+        };
+        let simplified = simplify(square, 0.1);
+        assert!(simplified.closed);
+        assert_eq!(
+            simplified.points,
+            vec![(0.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 2.0)]
+        );
+    }
+}
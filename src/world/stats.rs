@@ -0,0 +1,141 @@
+use std::{cell::Cell, ops::AddAssign, time::Duration};
+
+/// counts collected over the course of rendering an image.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct RenderStats {
+    /// pixels whose color came out NaN or infinite and had to be replaced.
+    pub non_finite_pixels: usize,
+    /// pixels that stood out from their neighbors, and so were rendered again with the
+    /// camera's full sampling.
+    pub refined_pixels: usize,
+    /// rays fired from the camera.
+    pub primary_rays: usize,
+    /// rays fired only to check whether anything is in the way, towards lights or to
+    /// find ambient occlusion.
+    pub shadow_rays: usize,
+    /// rays fired to find what a surface reflects or lets through.
+    pub secondary_rays: usize,
+    /// rays fired from the camera that hit something, rather than seeing only the
+    /// background.
+    pub hits: usize,
+    /// times a ray of any kind was tested against one of the world's objects.
+    pub intersection_tests: usize,
+    /// how long the render took from start to finish.
+    pub elapsed: Duration,
+}
+
+impl RenderStats {
+    /// every ray fired, of any kind.
+    pub fn rays(&self) -> usize {
+        self.primary_rays + self.shadow_rays + self.secondary_rays
+    }
+}
+
+impl AddAssign for RenderStats {
+    fn add_assign(&mut self, other: RenderStats) {
+        self.non_finite_pixels += other.non_finite_pixels;
+        self.refined_pixels += other.refined_pixels;
+        self.primary_rays += other.primary_rays;
+        self.shadow_rays += other.shadow_rays;
+        self.secondary_rays += other.secondary_rays;
+        self.hits += other.hits;
+        self.intersection_tests += other.intersection_tests;
+        self.elapsed += other.elapsed;
+    }
+}
+
+/// the shadow and secondary rays fired, and the intersection tests made, so far on one
+/// thread.
+///
+/// these are counted deep inside the world, where there is no `RenderStats` to hand,
+/// so they go into a plain per-thread counter instead, and a renderer finds how many
+/// rays went into a pixel by comparing the counts from before and after it. nothing is
+/// counted unless the thread has asked for it with `RayCounts::counting`, so renders
+/// that throw their statistics away only pay for checking that flag.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub(crate) struct RayCounts {
+    pub shadow: usize,
+    pub secondary: usize,
+    pub intersection_tests: usize,
+}
+
+thread_local! {
+    static COUNTING: Cell<bool> = const { Cell::new(false) };
+    static RAY_COUNTS: Cell<RayCounts> = const {
+        Cell::new(RayCounts {
+            shadow: 0,
+            secondary: 0,
+            intersection_tests: 0,
+        })
+    };
+}
+
+impl RayCounts {
+    /// turns counting on this thread on or off, until the returned guard is dropped.
+    pub fn counting(enabled: bool) -> Counting {
+        Counting {
+            was: COUNTING.with(|counting| counting.replace(enabled)),
+        }
+    }
+
+    fn enabled() -> bool {
+        COUNTING.with(Cell::get)
+    }
+
+    pub fn current() -> RayCounts {
+        RAY_COUNTS.with(Cell::get)
+    }
+
+    pub fn count_shadow() {
+        if !RayCounts::enabled() {
+            return;
+        }
+        RAY_COUNTS.with(|counts| {
+            let mut c = counts.get();
+            c.shadow += 1;
+            counts.set(c);
+        });
+    }
+
+    pub fn count_secondary() {
+        if !RayCounts::enabled() {
+            return;
+        }
+        RAY_COUNTS.with(|counts| {
+            let mut c = counts.get();
+            c.secondary += 1;
+            counts.set(c);
+        });
+    }
+
+    pub fn count_intersection_tests(tests: usize) {
+        if !RayCounts::enabled() {
+            return;
+        }
+        RAY_COUNTS.with(|counts| {
+            let mut c = counts.get();
+            c.intersection_tests += tests;
+            counts.set(c);
+        });
+    }
+
+    /// adds the rays fired and intersection tests made on this thread since `before`
+    /// to `stats`.
+    pub fn add_since(before: RayCounts, stats: &mut RenderStats) {
+        let now = RayCounts::current();
+        stats.shadow_rays += now.shadow - before.shadow;
+        stats.secondary_rays += now.secondary - before.secondary;
+        stats.intersection_tests += now.intersection_tests - before.intersection_tests;
+    }
+}
+
+/// puts counting back the way it was when dropped. see `RayCounts::counting`.
+pub(crate) struct Counting {
+    was: bool,
+}
+
+impl Drop for Counting {
+    fn drop(&mut self) {
+        COUNTING.with(|counting| counting.set(self.was));
+    }
+}
@@ -3,32 +3,83 @@ use crate::{
     world::{Color, Pattern},
 };
 
+pub mod cube;
+pub use cube::{Cube, Face, FaceTexture};
+
+pub mod image;
+pub use image::Image;
+
+pub mod mapping;
+pub use mapping::UvMapping;
+
+pub mod procedural;
+pub use procedural::Procedural;
+
 pub trait Textured {
     fn color_at(&self, point: Point) -> Color;
 }
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Texture {
+    Cube(Cube),
+    Image(Image),
     Pattern(Pattern),
+    /// can't be serialized, since a procedural texture is only known by its code.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Procedural(Procedural),
 }
 
 impl Texture {
+    pub fn cube(cube: Cube) -> Texture {
+        Texture::Cube(cube)
+    }
+
+    pub fn image(image: Image) -> Texture {
+        Texture::Image(image)
+    }
+
     pub fn pattern(pattern: Pattern) -> Texture {
         Texture::Pattern(pattern)
     }
+
+    pub fn procedural(procedural: Procedural) -> Texture {
+        Texture::Procedural(procedural)
+    }
+
+    /// a texture colored by calling `f` with each point, in the texture's own space.
+    pub fn from_fn<F: Fn(Point) -> Color + Send + Sync + 'static>(f: F) -> Texture {
+        Texture::procedural(Procedural::new(f))
+    }
 }
 
 impl Transformable for Texture {
     fn transformed(self, transform: Matrix) -> Texture {
         match self {
+            Texture::Cube(cube) => Texture::cube(cube.transformed(transform)),
+            Texture::Image(image) => Texture::image(image.transformed(transform)),
             Texture::Pattern(pattern) => Texture::pattern(pattern.transformed(transform)),
+            Texture::Procedural(procedural) => {
+                Texture::procedural(procedural.transformed(transform))
+            }
         }
     }
 
     fn transform(&mut self, transform: Matrix) -> &mut Texture {
-        *self = match self {
-            Texture::Pattern(pattern) => Texture::pattern(pattern.transformed(transform)),
-        };
+        match self {
+            Texture::Cube(cube) => {
+                cube.transform(transform);
+            }
+            Texture::Image(image) => {
+                image.transform(transform);
+            }
+            Texture::Pattern(pattern) => {
+                pattern.transform(transform);
+            }
+            Texture::Procedural(procedural) => {
+                procedural.transform(transform);
+            }
+        }
         self
     }
 }
@@ -36,7 +87,57 @@ impl Transformable for Texture {
 impl Textured for Texture {
     fn color_at(&self, object_space_point: Point) -> Color {
         match self {
+            Texture::Cube(cube) => cube.color_at(object_space_point),
+            Texture::Image(image) => image.color_at(object_space_point),
             Texture::Pattern(pattern) => pattern.color_at(object_space_point),
+            Texture::Procedural(procedural) => procedural.color_at(object_space_point),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        math::{clamp_between, Form, Geometry},
+        world::Material,
+    };
+
+    /// fades from black to white over the first ten units along z.
+    fn ramp() -> Texture {
+        Texture::from_fn(|point| {
+            let t = clamp_between(point[2] / 10.0, 0.0, 1.0);
+            Color::new(t, t, t)
+        })
+    }
+
+    #[test]
+    fn closure_colors_floor() {
+        let floor = Geometry::default()
+            .with_form(Form::Plane)
+            .with_material(Material::default().with_texture(ramp()));
+        assert_eq!(floor.color_at(Point::new(3.0, 0.0, -2.0)), Color::black());
+        assert_eq!(
+            floor.color_at(Point::new(-7.0, 0.0, 2.5)),
+            Color::new(0.25, 0.25, 0.25)
+        );
+        assert_eq!(floor.color_at(Point::new(0.0, 0.0, 50.0)), Color::white());
+    }
+
+    #[test]
+    fn transformed_closure() {
+        let texture = ramp().transformed(Matrix::scaling(1.0, 1.0, 2.0));
+        assert_eq!(
+            texture.color_at(Point::new(0.0, 0.0, 10.0)),
+            Color::new(0.5, 0.5, 0.5)
+        );
+    }
+
+    #[test]
+    fn closures_are_equal_by_identity() {
+        let a = ramp();
+        assert_eq!(a, a.clone());
+        assert_ne!(a, ramp());
+        assert_ne!(a, a.clone().transformed(Matrix::scaling(2.0, 2.0, 2.0)));
+    }
+}
@@ -0,0 +1,379 @@
+use std::sync::Arc;
+
+use crate::{
+    math::{Matrix, Point, Transformable},
+    world::{pattern::inverse_of, Canvas, Color, Pattern, Textured},
+};
+
+/// one of the six faces of the cube from -1 to 1 on every axis.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Face {
+    /// -x
+    Left,
+    /// +x
+    Right,
+    /// +z
+    Front,
+    /// -z
+    Back,
+    /// +y
+    Up,
+    /// -y
+    Down,
+}
+
+impl Face {
+    /// the face that `point` is closest to, by whichever of its coordinates is
+    /// furthest from 0.
+    pub fn of(point: Point) -> Face {
+        let (x, y, z) = (point[0], point[1], point[2]);
+        let largest = x.abs().max(y.abs()).max(z.abs());
+        if largest == x {
+            Face::Right
+        } else if largest == -x {
+            Face::Left
+        } else if largest == y {
+            Face::Up
+        } else if largest == -y {
+            Face::Down
+        } else if largest == z {
+            Face::Front
+        } else {
+            Face::Back
+        }
+    }
+
+    /// where `point` lands on this face, with `u` running left to right and `v` bottom
+    /// to top when the cube is unfolded into a cross: left, front, right and back in a
+    /// row, with up above front and down below it.
+    pub fn uv(&self, point: Point) -> (f64, f64) {
+        let (x, y, z) = (point[0], point[1], point[2]);
+        let unit = |a: f64| (a + 1.0).rem_euclid(2.0) / 2.0;
+        match self {
+            Face::Left => (unit(z), unit(y)),
+            Face::Right => (unit(-z), unit(y)),
+            Face::Front => (unit(x), unit(y)),
+            Face::Back => (unit(-x), unit(y)),
+            Face::Up => (unit(x), unit(-z)),
+            Face::Down => (unit(x), unit(z)),
+        }
+    }
+}
+
+/// what is drawn on one face of a `Cube`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FaceTexture {
+    /// a picture stretched over the whole face.
+    Image(Arc<Canvas>),
+    /// a pattern laid flat over the face, with `u` along its x axis and `v` along its z
+    /// axis, the same way a planar mapping lays out an image.
+    Pattern(Box<Pattern>),
+}
+
+impl FaceTexture {
+    pub fn color_at_uv(&self, u: f64, v: f64) -> Color {
+        match self {
+            FaceTexture::Image(canvas) => canvas.at_uv(u, v),
+            FaceTexture::Pattern(pattern) => pattern.color_at(Point::new(u, 0.0, v)),
+        }
+    }
+}
+
+impl From<Canvas> for FaceTexture {
+    fn from(canvas: Canvas) -> FaceTexture {
+        FaceTexture::Image(Arc::new(canvas))
+    }
+}
+
+impl From<Pattern> for FaceTexture {
+    fn from(pattern: Pattern) -> FaceTexture {
+        FaceTexture::Pattern(Box::new(pattern))
+    }
+}
+
+/// a different texture on each face of the cube from -1 to 1 on every axis. points off
+/// the cube take the texture of the face they are closest to, so it can also be
+/// wrapped around other shapes, or seen from inside as a skybox.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "SerializedCube")
+)]
+pub struct Cube {
+    pub left: FaceTexture,
+    pub right: FaceTexture,
+    pub front: FaceTexture,
+    pub back: FaceTexture,
+    pub up: FaceTexture,
+    pub down: FaceTexture,
+    transform: Matrix,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    inverse: Matrix,
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct SerializedCube {
+    left: FaceTexture,
+    right: FaceTexture,
+    front: FaceTexture,
+    back: FaceTexture,
+    up: FaceTexture,
+    down: FaceTexture,
+    transform: Matrix,
+}
+
+#[cfg(feature = "serde")]
+impl From<SerializedCube> for Cube {
+    fn from(serialized: SerializedCube) -> Cube {
+        Cube::new(
+            serialized.left,
+            serialized.right,
+            serialized.front,
+            serialized.back,
+            serialized.up,
+            serialized.down,
+        )
+        .transformed(serialized.transform)
+    }
+}
+
+impl Cube {
+    pub fn new<F: Into<FaceTexture>>(left: F, right: F, front: F, back: F, up: F, down: F) -> Cube {
+        Cube {
+            left: left.into(),
+            right: right.into(),
+            front: front.into(),
+            back: back.into(),
+            up: up.into(),
+            down: down.into(),
+            transform: Matrix::identity(),
+            inverse: Matrix::identity(),
+        }
+    }
+
+    pub fn face(&self, face: Face) -> &FaceTexture {
+        match face {
+            Face::Left => &self.left,
+            Face::Right => &self.right,
+            Face::Front => &self.front,
+            Face::Back => &self.back,
+            Face::Up => &self.up,
+            Face::Down => &self.down,
+        }
+    }
+
+    pub fn transformation(&self) -> Matrix {
+        self.transform
+    }
+
+    pub fn inverse(&self) -> Matrix {
+        self.inverse
+    }
+
+    pub fn debug_assert_consistent(&self) {
+        self.transform.debug_assert_inverse(&self.inverse, "Cube");
+    }
+}
+
+impl Transformable for Cube {
+    fn transformed(self, transform: Matrix) -> Cube {
+        Cube {
+            transform,
+            inverse: inverse_of(transform),
+            ..self
+        }
+    }
+
+    fn transform(&mut self, transform: Matrix) -> &mut Cube {
+        self.transform = transform;
+        self.inverse = inverse_of(transform);
+        self
+    }
+}
+
+impl Textured for Cube {
+    fn color_at(&self, object_space_point: Point) -> Color {
+        self.debug_assert_consistent();
+        let texture_space_point = self.inverse * object_space_point;
+        let face = Face::of(texture_space_point);
+        let (u, v) = face.uv(texture_space_point);
+        self.face(face).color_at_uv(u, v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::pattern::Stripe;
+
+    fn red() -> Color {
+        Color::new(1.0, 0.0, 0.0)
+    }
+    fn yellow() -> Color {
+        Color::new(1.0, 1.0, 0.0)
+    }
+    fn brown() -> Color {
+        Color::new(1.0, 0.5, 0.0)
+    }
+    fn green() -> Color {
+        Color::new(0.0, 1.0, 0.0)
+    }
+    fn cyan() -> Color {
+        Color::new(0.0, 1.0, 1.0)
+    }
+    fn blue() -> Color {
+        Color::new(0.0, 0.0, 1.0)
+    }
+    fn purple() -> Color {
+        Color::new(1.0, 0.0, 1.0)
+    }
+    fn white() -> Color {
+        Color::white()
+    }
+
+    /// a face colored `main`, apart from its four corners, which are each colored
+    /// differently so that it's clear which way up it is.
+    fn align_check(
+        main: Color,
+        upper_left: Color,
+        upper_right: Color,
+        bottom_left: Color,
+        bottom_right: Color,
+    ) -> Canvas {
+        Canvas::from_fn(5, 5, |x, y| match (x, y) {
+            (0, 0) => upper_left,
+            (4, 0) => upper_right,
+            (0, 4) => bottom_left,
+            (4, 4) => bottom_right,
+            _ => main,
+        })
+    }
+
+    fn cube() -> Cube {
+        Cube::new(
+            align_check(yellow(), cyan(), red(), blue(), brown()),
+            align_check(red(), yellow(), purple(), green(), white()),
+            align_check(cyan(), red(), yellow(), brown(), green()),
+            align_check(green(), purple(), cyan(), white(), blue()),
+            align_check(brown(), cyan(), purple(), red(), yellow()),
+            align_check(purple(), brown(), green(), blue(), white()),
+        )
+    }
+
+    #[test]
+    fn face_of_point() {
+        let cases = [
+            (Point::new(-1.0, 0.5, -0.25), Face::Left),
+            (Point::new(1.1, -0.75, 0.8), Face::Right),
+            (Point::new(0.1, 0.6, 0.9), Face::Front),
+            (Point::new(-0.7, 0.0, -2.0), Face::Back),
+            (Point::new(0.5, 1.0, 0.9), Face::Up),
+            (Point::new(-0.2, -1.3, 1.1), Face::Down),
+        ];
+        for (point, face) in cases.iter() {
+            assert_eq!(Face::of(*point), *face);
+        }
+    }
+
+    #[test]
+    fn uv_on_each_face() {
+        let cases = [
+            (Face::Front, Point::new(-0.5, 0.5, 1.0), (0.25, 0.75)),
+            (Face::Front, Point::new(0.5, -0.5, 1.0), (0.75, 0.25)),
+            (Face::Back, Point::new(0.5, 0.5, -1.0), (0.25, 0.75)),
+            (Face::Back, Point::new(-0.5, -0.5, -1.0), (0.75, 0.25)),
+            (Face::Left, Point::new(-1.0, 0.5, -0.5), (0.25, 0.75)),
+            (Face::Left, Point::new(-1.0, -0.5, 0.5), (0.75, 0.25)),
+            (Face::Right, Point::new(1.0, 0.5, 0.5), (0.25, 0.75)),
+            (Face::Right, Point::new(1.0, -0.5, -0.5), (0.75, 0.25)),
+            (Face::Up, Point::new(-0.5, 1.0, -0.5), (0.25, 0.75)),
+            (Face::Up, Point::new(0.5, 1.0, 0.5), (0.75, 0.25)),
+            (Face::Down, Point::new(-0.5, -1.0, 0.5), (0.25, 0.75)),
+            (Face::Down, Point::new(0.5, -1.0, -0.5), (0.75, 0.25)),
+        ];
+        for (face, point, (u, v)) in cases.iter() {
+            let (actual_u, actual_v) = face.uv(*point);
+            assert!(
+                (actual_u - u).abs() < 1e-9,
+                "u of {:?} on {:?}",
+                point,
+                face
+            );
+            assert!(
+                (actual_v - v).abs() < 1e-9,
+                "v of {:?} on {:?}",
+                point,
+                face
+            );
+        }
+    }
+
+    #[test]
+    fn colors_near_every_corner() {
+        let cube = cube();
+        let cases = [
+            // left
+            (Point::new(-1.0, 0.0, 0.0), yellow()),
+            (Point::new(-1.0, 0.9, -0.9), cyan()),
+            (Point::new(-1.0, 0.9, 0.9), red()),
+            (Point::new(-1.0, -0.9, -0.9), blue()),
+            (Point::new(-1.0, -0.9, 0.9), brown()),
+            // front
+            (Point::new(0.0, 0.0, 1.0), cyan()),
+            (Point::new(-0.9, 0.9, 1.0), red()),
+            (Point::new(0.9, 0.9, 1.0), yellow()),
+            (Point::new(-0.9, -0.9, 1.0), brown()),
+            (Point::new(0.9, -0.9, 1.0), green()),
+            // right
+            (Point::new(1.0, 0.0, 0.0), red()),
+            (Point::new(1.0, 0.9, 0.9), yellow()),
+            (Point::new(1.0, 0.9, -0.9), purple()),
+            (Point::new(1.0, -0.9, 0.9), green()),
+            (Point::new(1.0, -0.9, -0.9), white()),
+            // back
+            (Point::new(0.0, 0.0, -1.0), green()),
+            (Point::new(0.9, 0.9, -1.0), purple()),
+            (Point::new(-0.9, 0.9, -1.0), cyan()),
+            (Point::new(0.9, -0.9, -1.0), white()),
+            (Point::new(-0.9, -0.9, -1.0), blue()),
+            // up
+            (Point::new(0.0, 1.0, 0.0), brown()),
+            (Point::new(-0.9, 1.0, -0.9), cyan()),
+            (Point::new(0.9, 1.0, -0.9), purple()),
+            (Point::new(-0.9, 1.0, 0.9), red()),
+            (Point::new(0.9, 1.0, 0.9), yellow()),
+            // down
+            (Point::new(0.0, -1.0, 0.0), purple()),
+            (Point::new(-0.9, -1.0, 0.9), brown()),
+            (Point::new(0.9, -1.0, 0.9), green()),
+            (Point::new(-0.9, -1.0, -0.9), blue()),
+            (Point::new(0.9, -1.0, -0.9), white()),
+        ];
+        for (point, color) in cases.iter() {
+            assert_eq!(cube.color_at(*point), *color, "at {:?}", point);
+        }
+    }
+
+    #[test]
+    fn patterns_on_faces() {
+        let stripes = || Pattern::stripe(Stripe::new(white(), red()));
+        let cube = Cube::new(
+            stripes(),
+            stripes(),
+            stripes().transformed(Matrix::scaling(0.25, 1.0, 1.0)),
+            stripes(),
+            stripes(),
+            stripes(),
+        );
+        // u runs from 0 to 1 across the front, so the narrow stripes change along it.
+        assert_eq!(cube.color_at(Point::new(-0.9, 0.0, 1.0)), white());
+        assert_eq!(cube.color_at(Point::new(-0.3, 0.0, 1.0)), red());
+        assert_eq!(cube.color_at(Point::new(0.1, 0.0, 1.0)), white());
+        // whereas the wide stripes on the other faces never do.
+        assert_eq!(cube.color_at(Point::new(1.0, 0.0, 0.9)), white());
+        assert_eq!(cube.color_at(Point::new(1.0, 0.0, -0.9)), white());
+    }
+}
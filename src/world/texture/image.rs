@@ -0,0 +1,160 @@
+use std::sync::Arc;
+
+use crate::{
+    math::{Matrix, Point, Transformable},
+    world::{pattern::inverse_of, texture::UvMapping, Canvas, Color, Textured},
+};
+
+/// a picture wrapped onto a surface.
+///
+/// points are moved into the texture's own space by its transform, flattened onto the
+/// picture by its mapping, and then given the color of the pixel they land on.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "SerializedImage")
+)]
+pub struct Image {
+    pub canvas: Arc<Canvas>,
+    pub mapping: UvMapping,
+    transform: Matrix,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    inverse: Matrix,
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct SerializedImage {
+    canvas: Arc<Canvas>,
+    mapping: UvMapping,
+    transform: Matrix,
+}
+
+#[cfg(feature = "serde")]
+impl From<SerializedImage> for Image {
+    fn from(serialized: SerializedImage) -> Image {
+        Image {
+            canvas: serialized.canvas,
+            mapping: serialized.mapping,
+            transform: Matrix::identity(),
+            inverse: Matrix::identity(),
+        }
+        .transformed(serialized.transform)
+    }
+}
+
+impl Image {
+    pub fn new(canvas: Canvas, mapping: UvMapping) -> Image {
+        Image {
+            canvas: Arc::new(canvas),
+            mapping,
+            transform: Matrix::identity(),
+            inverse: Matrix::identity(),
+        }
+    }
+
+    /// the same picture, flattened onto surfaces by `mapping` instead.
+    pub fn with_mapping(self, mapping: UvMapping) -> Image {
+        Image { mapping, ..self }
+    }
+
+    pub fn transformation(&self) -> Matrix {
+        self.transform
+    }
+
+    pub fn inverse(&self) -> Matrix {
+        self.inverse
+    }
+
+    pub fn debug_assert_consistent(&self) {
+        self.transform.debug_assert_inverse(&self.inverse, "Image");
+    }
+}
+
+impl Transformable for Image {
+    fn transformed(self, transform: Matrix) -> Image {
+        Image {
+            transform,
+            inverse: inverse_of(transform),
+            ..self
+        }
+    }
+
+    fn transform(&mut self, transform: Matrix) -> &mut Image {
+        self.transform = transform;
+        self.inverse = inverse_of(transform);
+        self
+    }
+}
+
+impl Textured for Image {
+    fn color_at(&self, object_space_point: Point) -> Color {
+        self.debug_assert_consistent();
+        let texture_space_point = self.inverse * object_space_point;
+        let (u, v) = self.mapping.map(texture_space_point);
+        self.canvas.at_uv(u, v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a 4 by 2 image whose pixels all differ, with red growing to the right and green
+    /// growing downwards.
+    fn image() -> Image {
+        Image::new(
+            Canvas::from_fn(4, 2, |x, y| Color::new(x as f64 / 4.0, y as f64, 0.0)),
+            UvMapping::Spherical,
+        )
+    }
+
+    #[test]
+    fn spherical_image_on_axes() {
+        let image = image();
+        let pixel = |x: usize, y: usize| Color::new(x as f64 / 4.0, y as f64, 0.0);
+        // around the equator, which lies on the boundary between the rows, so the
+        // lower row is used.
+        assert_eq!(image.color_at(Point::new(0.0, 0.0, 1.0)), pixel(2, 1));
+        assert_eq!(image.color_at(Point::new(1.0, 0.0, 0.0)), pixel(1, 1));
+        assert_eq!(image.color_at(Point::new(-1.0, 0.0, 0.0)), pixel(3, 1));
+        // the poles are at the top and bottom of the image.
+        assert_eq!(image.color_at(Point::new(0.0, 1.0, 0.0)), pixel(2, 0));
+        assert_eq!(image.color_at(Point::new(0.0, -1.0, 0.0)), pixel(2, 1));
+        // just above the equator.
+        assert_eq!(image.color_at(Point::new(1.0, 0.1, 0.0)), pixel(1, 0));
+    }
+
+    #[test]
+    fn spherical_image_seam() {
+        let image = image();
+        let pixel = |x: usize| Color::new(x as f64 / 4.0, 1.0, 0.0);
+        // either side of the seam along -z lands on opposite edges of the image.
+        assert_eq!(image.color_at(Point::new(0.01, -0.1, -1.0)), pixel(0));
+        assert_eq!(image.color_at(Point::new(-0.01, -0.1, -1.0)), pixel(3));
+        assert_eq!(image.color_at(Point::new(0.0, -0.1, -1.0)), pixel(0));
+    }
+
+    #[test]
+    fn same_image_tiles_a_plane() {
+        let image = image().with_mapping(UvMapping::Planar);
+        assert_eq!(
+            image.color_at(Point::new(0.3, 0.0, 0.9)),
+            Color::new(0.25, 0.0, 0.0)
+        );
+        assert_eq!(
+            image.color_at(Point::new(-0.3, 5.0, -0.9)),
+            Color::new(0.5, 1.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn transformed_image() {
+        let image = image().transformed(Matrix::scaling(2.0, 2.0, 2.0));
+        assert_eq!(
+            image.color_at(Point::new(2.0, 0.0, 0.0)),
+            Color::new(0.25, 1.0, 0.0)
+        );
+    }
+}
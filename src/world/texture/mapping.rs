@@ -0,0 +1,152 @@
+use std::f64::consts;
+
+use crate::math::{Point, Vector};
+
+/// how points on a surface are flattened onto an image, as `(u, v)` coordinates that
+/// each run from 0 to 1, with `v` running from the bottom of the image to its top.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum UvMapping {
+    /// wraps the image around the unit sphere like a globe: `u` goes around the y axis
+    /// and `v` from the south pole to the north pole. the image's left and right edges
+    /// meet along -z.
+    Spherical,
+    /// tiles the image across the xz plane, once per unit square, with `u` along x and
+    /// `v` along z.
+    Planar,
+    /// wraps the image around the y axis once, like the label on a can, and repeats it
+    /// once per unit of height. the image's left and right edges meet along -z.
+    Cylindrical,
+}
+
+impl UvMapping {
+    /// where `point` lands on the image.
+    pub fn map(&self, point: Point) -> (f64, f64) {
+        match self {
+            UvMapping::Spherical => spherical(point),
+            UvMapping::Planar => (point[0].rem_euclid(1.0), point[2].rem_euclid(1.0)),
+            UvMapping::Cylindrical => (around_y(point), point[1].rem_euclid(1.0)),
+        }
+    }
+
+    /// the directions that `u` and `v` grow in at `point`, not necessarily normalized.
+    /// either can be zero where the mapping pinches to a point, like at the poles of a
+    /// sphere.
+    pub fn tangents(&self, point: Point) -> (Vector, Vector) {
+        let (x, y, z) = (point[0], point[1], point[2]);
+        // u grows anticlockwise around the y axis when looking down from above.
+        let around = Vector::new(-z, 0.0, x);
+        match self {
+            UvMapping::Spherical => (around, Vector::new(-x * y, x * x + z * z, -z * y)),
+            UvMapping::Planar => (Vector::new(1.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0)),
+            UvMapping::Cylindrical => (around, Vector::new(0.0, 1.0, 0.0)),
+        }
+    }
+}
+
+/// how far around the y axis `point` is, from 0 to 1, starting from -z and growing
+/// anticlockwise when looking down from above.
+fn around_y(point: Point) -> f64 {
+    // the angle around the y axis, from -pi to pi, which is 0 along +z.
+    let theta = point[0].atan2(point[2]);
+    1.0 - (theta / (2.0 * consts::PI) + 0.5)
+}
+
+fn spherical(point: Point) -> (f64, f64) {
+    let radius = Vector::new(point[0], point[1], point[2]).magnitude();
+    if radius == 0.0 {
+        return (0.5, 0.5);
+    }
+    // the angle down from the north pole, from 0 to pi.
+    let phi = (point[1] / radius).acos();
+
+    (around_y(point), 1.0 - phi / consts::PI)
+}
+
+#[cfg(test)]
+#[allow(clippy::approx_constant)]
+mod tests {
+    use super::*;
+
+    fn assert_maps(mapping: UvMapping, cases: &[(Point, (f64, f64))]) {
+        for (point, (u, v)) in cases.iter() {
+            let (actual_u, actual_v) = mapping.map(*point);
+            assert!((actual_u - u).abs() < 1e-4, "u of {:?}", point);
+            assert!((actual_v - v).abs() < 1e-4, "v of {:?}", point);
+        }
+    }
+
+    #[test]
+    fn spherical_mapping() {
+        let cases = [
+            (Point::new(0.0, 0.0, -1.0), (0.0, 0.5)),
+            (Point::new(1.0, 0.0, 0.0), (0.25, 0.5)),
+            (Point::new(0.0, 0.0, 1.0), (0.5, 0.5)),
+            (Point::new(-1.0, 0.0, 0.0), (0.75, 0.5)),
+            (Point::new(0.0, 1.0, 0.0), (0.5, 1.0)),
+            (Point::new(0.0, -1.0, 0.0), (0.5, 0.0)),
+            (
+                Point::new(2.0_f64.sqrt() / 2.0, 2.0_f64.sqrt() / 2.0, 0.0),
+                (0.25, 0.75),
+            ),
+        ];
+        assert_maps(UvMapping::Spherical, &cases);
+    }
+
+    #[test]
+    fn tangents_follow_uv() {
+        let step = 1e-6;
+        let points = [
+            Point::new(0.6, 0.0, -0.8),
+            Point::new(-0.36, 0.6, 0.48),
+            Point::new(0.3, -0.9, 0.3),
+        ];
+        for mapping in [
+            UvMapping::Spherical,
+            UvMapping::Planar,
+            UvMapping::Cylindrical,
+        ]
+        .iter()
+        {
+            for point in points.iter() {
+                let (u, v) = mapping.map(*point);
+                let (du, dv) = mapping.tangents(*point);
+                let (u_after, _) = mapping.map(*point + du.normalized() * step);
+                let (_, v_after) = mapping.map(*point + dv.normalized() * step);
+                assert!(u < u_after, "{:?} u at {:?}", mapping, point);
+                assert!(v < v_after, "{:?} v at {:?}", mapping, point);
+            }
+        }
+    }
+
+    #[test]
+    fn planar_mapping() {
+        let cases = [
+            (Point::new(0.25, 0.0, 0.5), (0.25, 0.5)),
+            (Point::new(0.25, 0.0, -0.25), (0.25, 0.75)),
+            (Point::new(0.25, 0.5, -0.25), (0.25, 0.75)),
+            (Point::new(1.25, 0.0, 0.5), (0.25, 0.5)),
+            (Point::new(0.25, 0.0, -1.75), (0.25, 0.25)),
+            (Point::new(1.0, 0.0, -1.0), (0.0, 0.0)),
+            (Point::new(0.0, 0.0, 0.0), (0.0, 0.0)),
+        ];
+        assert_maps(UvMapping::Planar, &cases);
+    }
+
+    #[test]
+    fn cylindrical_mapping() {
+        let cases = [
+            (Point::new(0.0, 0.0, -1.0), (0.0, 0.0)),
+            (Point::new(0.0, 0.5, -1.0), (0.0, 0.5)),
+            (Point::new(0.0, 1.0, -1.0), (0.0, 0.0)),
+            (Point::new(0.70711, 0.5, -0.70711), (0.125, 0.5)),
+            (Point::new(1.0, 0.5, 0.0), (0.25, 0.5)),
+            (Point::new(0.70711, 0.5, 0.70711), (0.375, 0.5)),
+            (Point::new(0.0, -0.25, 1.0), (0.5, 0.75)),
+            (Point::new(-0.70711, 0.5, 0.70711), (0.625, 0.5)),
+            (Point::new(-1.0, 1.25, 0.0), (0.75, 0.25)),
+            (Point::new(-0.70711, 0.5, -0.70711), (0.875, 0.5)),
+        ];
+        assert_maps(UvMapping::Cylindrical, &cases);
+    }
+}
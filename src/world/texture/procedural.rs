@@ -0,0 +1,84 @@
+use std::{
+    fmt::{self, Debug, Formatter},
+    sync::Arc,
+};
+
+use crate::{
+    math::{Matrix, Point, Transformable},
+    world::{pattern::inverse_of, Color, Textured},
+};
+
+/// a texture whose color at each point is worked out by a closure, for trying out
+/// ideas without writing a new pattern.
+///
+/// clones share the same closure, and two procedural textures are only equal if they
+/// share it and have the same transform.
+#[derive(Clone)]
+pub struct Procedural {
+    f: Arc<dyn Fn(Point) -> Color + Send + Sync>,
+    transform: Matrix,
+    inverse: Matrix,
+}
+
+impl Procedural {
+    pub fn new<F: Fn(Point) -> Color + Send + Sync + 'static>(f: F) -> Procedural {
+        Procedural {
+            f: Arc::new(f),
+            transform: Matrix::identity(),
+            inverse: Matrix::identity(),
+        }
+    }
+
+    pub fn transformation(&self) -> Matrix {
+        self.transform
+    }
+
+    pub fn inverse(&self) -> Matrix {
+        self.inverse
+    }
+
+    pub fn debug_assert_consistent(&self) {
+        self.transform
+            .debug_assert_inverse(&self.inverse, "Procedural");
+    }
+}
+
+impl Debug for Procedural {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Procedural({:p}, {:?})",
+            Arc::as_ptr(&self.f),
+            self.transform
+        )
+    }
+}
+
+impl PartialEq for Procedural {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.f, &other.f) && self.transform == other.transform
+    }
+}
+
+impl Transformable for Procedural {
+    fn transformed(self, transform: Matrix) -> Procedural {
+        Procedural {
+            transform,
+            inverse: inverse_of(transform),
+            ..self
+        }
+    }
+
+    fn transform(&mut self, transform: Matrix) -> &mut Procedural {
+        self.transform = transform;
+        self.inverse = inverse_of(transform);
+        self
+    }
+}
+
+impl Textured for Procedural {
+    fn color_at(&self, object_space_point: Point) -> Color {
+        self.debug_assert_consistent();
+        (self.f)(self.inverse * object_space_point)
+    }
+}
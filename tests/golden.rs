@@ -0,0 +1,39 @@
+//! renders scenes and compares them against images that are known to be right, so
+//! that refactoring can't quietly change what gets drawn.
+
+use std::f64::consts;
+
+use ray_tracer_challenge::{
+    math::{Point, Vector},
+    world::{Camera, Canvas, View, World},
+};
+
+/// half of the step between two 16-bit samples, which is as close as the stored image
+/// can get to the render it was made from.
+const TOLERANCE: f64 = 0.5 / 65535.0 + 1e-9;
+
+fn golden(ppm: &str) -> Canvas {
+    Canvas::from_ppm(ppm.as_bytes()).expect("golden images are valid PPM")
+}
+
+#[test]
+fn default_world() {
+    let world = World::default();
+    let mut camera = Camera::new(11, 11, consts::PI / 2.0);
+    camera.set_view(View::transformed(
+        Point::new(0.0, 0.0, -5.0),
+        Point::zero(),
+        Vector::new(0.0, 1.0, 0.0),
+    ));
+    let image = camera.render(&world);
+
+    let expected = golden(include_str!("golden/default_world.ppm"));
+    let diff = image.diff(&expected).unwrap();
+    assert!(
+        image.approx_eq(&expected, TOLERANCE),
+        "{} pixels differ, worst by {} at {:?}",
+        diff.pixels_over(TOLERANCE),
+        diff.max_error,
+        diff.worst_pixel
+    );
+}
@@ -0,0 +1,158 @@
+//! reads models from OBJ files and renders them, to check that the faces come out as
+//! solid shapes, shaded the way they should be.
+
+use std::f64::consts;
+
+use ray_tracer_challenge::{
+    math::{Form, Geometry, Matrix, Point, Transformable, Vector},
+    obj::{load_obj, parse_obj, ObjWarning},
+    world::{Camera, Canvas, Color, Textured, View, World},
+};
+
+/// the objects in `group`.
+fn children(group: &Geometry) -> &[Geometry] {
+    match &group.form {
+        Form::Group(children) => children,
+        form => panic!("expected a group, got {:?}", form),
+    }
+}
+
+#[test]
+fn rendering_a_cube() {
+    let model = load_obj("tests/models/cube.obj").unwrap();
+    assert_eq!(model.vertices.len(), 8);
+    assert_eq!(children(&model.default_group).len(), 12);
+    // the object name and smoothing group.
+    assert_eq!(model.ignored, 2);
+    assert_eq!(model.normals.len(), 1);
+    // there is no cube.mtl, so the cube keeps the default material.
+    assert!(matches!(
+        &model.warnings[..],
+        [ObjWarning::MissingLibrary { name, .. }] if name == "cube.mtl"
+    ));
+
+    let mut world = World::default();
+    let mut cube = model.to_group();
+    cube.transform(Matrix::rotation_y(consts::PI / 6.0));
+    world.objects = vec![cube];
+    let mut camera = Camera::new(11, 11, consts::PI / 3.0);
+    camera.set_view(View::transformed(
+        Point::new(0.0, 1.5, -5.0),
+        Point::zero(),
+        Vector::new(0.0, 1.0, 0.0),
+    ));
+    let image = camera.render(&world);
+
+    // the middle of the image shows the cube, lit from the light above and to the left,
+    // and the corners show the background.
+    assert_eq!(image.alpha(5, 5), 1.0);
+    assert!(image[(5, 5)] != image[(0, 0)]);
+    assert_eq!(image.alpha(0, 0), 0.0);
+    assert_eq!(image.alpha(10, 10), 0.0);
+}
+
+/// a unit sphere made of `rings` bands of `segments` faces each, as an OBJ file. with
+/// `normals`, each vertex is given the sphere's true normal there.
+fn low_poly_sphere(rings: usize, segments: usize, normals: bool) -> String {
+    let mut obj = String::new();
+    for ring in 0..=rings {
+        let polar = consts::PI * ring as f64 / rings as f64;
+        for segment in 0..segments {
+            let azimuth = 2.0 * consts::PI * segment as f64 / segments as f64;
+            let (x, y, z) = (
+                polar.sin() * azimuth.cos(),
+                polar.cos(),
+                polar.sin() * azimuth.sin(),
+            );
+            obj += &format!("v {} {} {}\nvn {} {} {}\n", x, y, z, x, y, z);
+        }
+    }
+
+    let corner = |ring: usize, segment: usize| {
+        let index = ring * segments + segment % segments + 1;
+        if normals {
+            format!("{}//{}", index, index)
+        } else {
+            index.to_string()
+        }
+    };
+    for ring in 0..rings {
+        for segment in 0..segments {
+            obj += &format!(
+                "f {} {} {} {}\n",
+                corner(ring, segment),
+                corner(ring, segment + 1),
+                corner(ring + 1, segment + 1),
+                corner(ring + 1, segment)
+            );
+        }
+    }
+    obj
+}
+
+#[test]
+fn faces_take_their_materials_from_the_library() {
+    let model = load_obj("tests/models/two_tone.obj").unwrap();
+    assert!(model.warnings.is_empty());
+    // the illumination model isn't understood.
+    assert_eq!(model.ignored, 1);
+
+    let triangles = children(&model.default_group);
+    let point = Point::new(0.0, 0.0, 0.0);
+    assert_eq!(triangles[0].color_at(point), Color::new(0.8, 0.1, 0.1));
+    assert_eq!(triangles[1].color_at(point), Color::new(0.1, 0.1, 0.8));
+    assert_eq!(triangles[0].material.shininess, 10.0);
+    assert_eq!(triangles[1].material.specular, 0.5);
+}
+
+#[test]
+fn smooth_spheres_are_shaded_like_spheres() {
+    let render = |sphere: Geometry| {
+        let world = World {
+            objects: vec![sphere],
+            ..World::default()
+        };
+        let mut camera = Camera::new(31, 31, consts::PI / 4.0);
+        camera.set_view(View::transformed(
+            Point::new(0.0, 0.0, -5.0),
+            Point::zero(),
+            Vector::new(0.0, 1.0, 0.0),
+        ));
+        camera.render(&world)
+    };
+    let mesh = |normals| {
+        let obj = low_poly_sphere(8, 16, normals);
+        let model = parse_obj(obj.as_bytes()).unwrap();
+        assert!(model.warnings.is_empty());
+        render(model.to_group())
+    };
+
+    let exact = render(Geometry::default().with_form(Form::Sphere));
+    let worst_error = |image: &Canvas| {
+        let mut worst: f64 = 0.0;
+        for y in 0..exact.height {
+            for x in 0..exact.width {
+                // the edges of the mesh are straight, so only compare the pixels both
+                // images show the surface in.
+                if image.alpha(x, y) == 1.0 && exact.alpha(x, y) == 1.0 {
+                    let difference = image[(x, y)] - exact[(x, y)];
+                    worst = worst
+                        .max(difference.red().abs())
+                        .max(difference.green().abs())
+                        .max(difference.blue().abs());
+                }
+            }
+        }
+        worst
+    };
+
+    let smooth = worst_error(&mesh(true));
+    let flat = worst_error(&mesh(false));
+    assert!(smooth < 0.2, "the smooth mesh is off by up to {}", smooth);
+    assert!(
+        smooth * 3.0 < flat,
+        "smooth {} against flat {}",
+        smooth,
+        flat
+    );
+}
@@ -0,0 +1,137 @@
+//! runs the raydiff tool on scene files, to check what it reports, the images it saves
+//! and how it exits.
+#![cfg(feature = "raydiff")]
+
+use std::{
+    f64::consts,
+    fs::{self, File},
+    io::BufReader,
+    path::PathBuf,
+    process::{Command, Output},
+};
+
+use ray_tracer_challenge::{
+    math::{Form, Geometry, Matrix, Point, Transformable, Vector},
+    scene::Scene,
+    world::{
+        light::{self, Light},
+        Camera, Canvas, Color, View, World,
+    },
+};
+
+/// two spheres side by side, with the second one `offset` above where it starts.
+fn spheres(offset: f64) -> Scene {
+    let left = Geometry::default()
+        .with_form(Form::Sphere)
+        .transformed(Matrix::translation(-1.5, 0.0, 0.0));
+    let right = Geometry::default()
+        .with_form(Form::Sphere)
+        .transformed(Matrix::translation(1.5, offset, 0.0));
+    let world = World::new(
+        vec![left, right],
+        vec![Light::point(light::Point::new(
+            Point::new(-10.0, 10.0, -10.0),
+            Color::white(),
+        ))],
+    );
+    let mut camera = Camera::new(40, 20, consts::PI / 3.0);
+    camera.set_view(View::transformed(
+        Point::new(0.0, 0.0, -6.0),
+        Point::zero(),
+        Vector::new(0.0, 1.0, 0.0),
+    ));
+    Scene { world, camera }
+}
+
+/// a directory of its own for `test` to write files into.
+fn scratch(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("raydiff-{}-{}", test, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn raydiff(args: &[&PathBuf]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_raydiff"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn load_ppm(path: &PathBuf) -> Canvas {
+    Canvas::from_ppm(BufReader::new(File::open(path).unwrap())).unwrap()
+}
+
+#[test]
+fn identical_scenes_match() {
+    let dir = scratch("identical");
+    let (a, b, out) = (dir.join("a.yaml"), dir.join("b.json"), dir.join("diff.ppm"));
+    fs::write(&a, serde_yaml::to_string(&spheres(0.0)).unwrap()).unwrap();
+    fs::write(&b, serde_json::to_string(&spheres(0.0)).unwrap()).unwrap();
+
+    let output = raydiff(&[&a, &b, &"--out".into(), &out]);
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(
+        stdout,
+        "0 of 800 pixels differ by more than 0, by at most 0.00000\n"
+    );
+
+    let diff = load_ppm(&out);
+    for y in 0..diff.height {
+        for x in 0..diff.width {
+            assert_eq!(diff[(x, y)], Color::black());
+        }
+    }
+    assert_eq!(
+        load_ppm(&dir.join("diff-a.ppm")),
+        load_ppm(&dir.join("diff-b.ppm"))
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn moving_a_sphere_is_reported_where_it_moved() {
+    let dir = scratch("moved");
+    let (a, b, out) = (dir.join("a.yaml"), dir.join("b.yaml"), dir.join("diff.ppm"));
+    let (before, after) = (spheres(0.0), spheres(0.5));
+    fs::write(&a, serde_yaml::to_string(&before).unwrap()).unwrap();
+    fs::write(&b, serde_yaml::to_string(&after).unwrap()).unwrap();
+
+    let output = raydiff(&[&a, &b, &"--out".into(), &out]);
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.lines().next(), Some("object 1 moved"));
+
+    // only the pixels that see the moved sphere, before or after, can change.
+    let diff = load_ppm(&out);
+    let seen_before = before.camera.render_object_ids(&before.world);
+    let seen_after = after.camera.render_object_ids(&after.world);
+    let mut changed = 0;
+    for y in 0..diff.height {
+        for x in 0..diff.width {
+            if diff[(x, y)] != Color::black() {
+                let i = x + y * diff.width;
+                assert!(seen_before[i] == Some(1) || seen_after[i] == Some(1));
+                changed += 1;
+            }
+        }
+    }
+    assert!(changed > 0);
+
+    // with a threshold above every difference, the scenes count as the same.
+    let output = raydiff(&[&a, &b, &"--threshold".into(), &"2".into()]);
+    assert_eq!(output.status.code(), Some(0));
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn unreadable_scenes_are_errors() {
+    let dir = scratch("unreadable");
+    let (a, b) = (dir.join("a.yaml"), dir.join("b.txt"));
+    fs::write(&a, "not: [a scene").unwrap();
+    fs::write(&b, "").unwrap();
+    assert_eq!(raydiff(&[&a, &a]).status.code(), Some(2));
+    assert_eq!(raydiff(&[&b, &b]).status.code(), Some(2));
+    assert_eq!(raydiff(&[&a]).status.code(), Some(2));
+    fs::remove_dir_all(dir).unwrap();
+}
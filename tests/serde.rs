@@ -0,0 +1,167 @@
+//! saves worlds and cameras as JSON and reads them back, to check that nothing is lost
+//! on the way and that nothing which can be worked out again is stored.
+#![cfg(feature = "serde")]
+
+use std::{f64::consts, sync::Arc};
+
+use ray_tracer_challenge::{
+    math::{
+        geometry::{Custom, Heightfield, Sphere, Triangle},
+        Form, Geometry, Matrix, Point, Transformable, Vector,
+    },
+    world::{
+        pattern::{Grid, Stripe},
+        Camera, Canvas, Color, Material, Pattern, Texture, View, World,
+    },
+};
+
+fn round_trip<T: serde::Serialize + serde::de::DeserializeOwned>(value: &T) -> T {
+    let json = serde_json::to_string(value).unwrap();
+    serde_json::from_str(&json).unwrap()
+}
+
+fn assert_same_world(a: &World, b: &World) {
+    assert_eq!(a.objects, b.objects);
+    assert_eq!(a.lights, b.lights);
+    assert_eq!(a.decals, b.decals);
+    assert_eq!(a.settings, b.settings);
+    assert_eq!(a.ambient_light, b.ambient_light);
+    assert_eq!(a.background, b.background);
+    assert_eq!(a.medium, b.medium);
+}
+
+#[test]
+fn the_default_world_survives_a_round_trip() {
+    let world = World::default();
+    assert_same_world(&round_trip(&world), &world);
+}
+
+#[test]
+fn patterns_and_meshes_survive_a_round_trip() {
+    let checkers = Pattern::grid(Grid::new(
+        Color::white(),
+        Pattern::stripe(Stripe::new(Color::black(), Color::new(1.0, 0.0, 0.0))),
+    ))
+    .transformed(Matrix::scaling(0.5, 0.5, 0.5));
+    let floor = Geometry::default()
+        .with_form(Form::Plane)
+        .with_material(Material::default().with_texture(Texture::pattern(checkers)));
+    let triangle = Geometry::default()
+        .with_form(Form::Group(vec![Geometry::default().with_form(
+            Form::Triangle(Triangle::new(
+                Point::new(0.0, 1.0, 0.0),
+                Point::new(-1.0, 0.0, 0.0),
+                Point::new(1.0, 0.0, 0.0),
+            )),
+        )]))
+        .transformed(Matrix::rotation_y(consts::PI / 5.0))
+        .with_motion(Matrix::translation(0.0, 1.0, 0.0));
+    let world = World {
+        objects: vec![floor, triangle],
+        ..World::default()
+    };
+    assert_same_world(&round_trip(&world), &world);
+}
+
+#[test]
+fn only_what_cant_be_worked_out_is_stored() {
+    let sphere = Geometry::default()
+        .with_form(Form::Sphere)
+        .transformed(Matrix::translation(1.0, 2.0, 3.0));
+    let json = serde_json::to_value(&sphere).unwrap();
+    assert!(json.get("inverse").is_none());
+    assert_eq!(
+        json["transform"],
+        serde_json::json!([1.0, 0.0, 0.0, 1.0, 0.0, 1.0, 0.0, 2.0, 0.0, 0.0, 1.0, 3.0])
+    );
+
+    let mut camera = Camera::new(20, 10, consts::PI / 2.0);
+    camera.set_view(View::transformed(
+        Point::new(0.0, 1.0, -5.0),
+        Point::zero(),
+        Vector::new(0.0, 1.0, 0.0),
+    ));
+    let json = serde_json::to_value(camera).unwrap();
+    assert!(json.get("pixel_size").is_none());
+    assert!(json["view"].get("inverse").is_none());
+    assert_eq!(json["view"]["from"], serde_json::json!([0.0, 1.0, -5.0]));
+}
+
+#[test]
+fn cameras_render_the_same_after_a_round_trip() {
+    let world = World::default();
+    let view = View::transformed(
+        Point::new(1.0, 2.0, -5.0),
+        Point::zero(),
+        Vector::new(0.0, 1.0, 0.0),
+    );
+    for mut camera in [
+        Camera::new(24, 16, consts::PI / 3.0),
+        Camera::orthographic(16, 24, 3.0),
+        Camera::fisheye(20, 20, consts::PI),
+    ] {
+        camera.set_view(view);
+        let copy = round_trip(&camera);
+        assert_eq!(copy, camera);
+        assert_eq!(copy.render(&world), camera.render(&world));
+    }
+}
+
+#[test]
+fn shapes_only_known_by_their_code_cant_be_serialized() {
+    let custom = Geometry::default().with_form(Form::Custom(Custom::new(Sphere {})));
+    assert!(serde_json::to_string(&custom).is_err());
+}
+
+#[test]
+fn canvases_must_have_a_pixel_for_every_position() {
+    let canvas = Canvas::from_fn(2, 2, |x, y| Color::new(x as f64, y as f64, 0.0));
+    assert_eq!(round_trip(&canvas), canvas);
+
+    let mut json = serde_json::to_value(&canvas).unwrap();
+    json["height"] = serde_json::json!(3);
+    assert!(serde_json::from_value::<Canvas>(json).is_err());
+}
+
+#[test]
+fn heightfields_must_have_a_usable_step_and_grid() {
+    let terrain =
+        Heightfield::from_grid((0.0, 0.0), (1.0, 1.0), 2, 2, vec![0.0, 0.5, 0.5, 1.0]).unwrap();
+    let json = serde_json::to_value(&terrain).unwrap();
+    assert_eq!(
+        serde_json::from_value::<Heightfield>(json.clone())
+            .unwrap()
+            .step(),
+        terrain.step()
+    );
+
+    let mut flat = json.clone();
+    flat["step"] = serde_json::json!(0.0);
+    assert!(serde_json::from_value::<Heightfield>(flat).is_err());
+
+    let mut short = json;
+    short["source"]["Grid"]["rows"] = serde_json::json!(3);
+    assert!(serde_json::from_value::<Heightfield>(short).is_err());
+}
+
+#[test]
+fn shared_materials_come_back_as_copies() {
+    let shared = Arc::new(Material::default());
+    let object = Geometry::default()
+        .with_form(Form::Sphere)
+        .with_material(shared.clone());
+    let mut world = World {
+        objects: vec![object.clone(), object],
+        ..World::default()
+    };
+    world = round_trip(&world);
+    assert!(!Arc::ptr_eq(
+        &world.objects[0].material,
+        &world.objects[1].material
+    ));
+
+    let first = world.objects[0].material.clone();
+    world.update_material(&first, |material| material.ambient = 1.0);
+    assert_eq!(world.objects[0].material.ambient, 1.0);
+    assert_eq!(world.objects[1].material.ambient, shared.ambient);
+}